@@ -1,5 +1,6 @@
 use cairo_vm::{
     felt::Felt252,
+    hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
     types::program::Program,
     vm::{runners::cairo_runner::CairoRunner, vm_core::VirtualMachine},
 };
@@ -60,6 +61,68 @@ fn load_program_data(c: &mut Criterion) {
     });
 }
 
+fn run_step_loop(c: &mut Criterion) {
+    // Mostly assert_eq, call, ret and jnz instructions, without hints nor builtins
+    let program = include_bytes!("../cairo_programs/benchmarks/big_fibonacci.json");
+    let program = Program::from_bytes(program.as_slice(), Some("main")).unwrap();
+    let mut group = c.benchmark_group("step_loop");
+    // A single run takes millions of steps
+    group.sample_size(10);
+    group.bench_function("big_fibonacci", |b| {
+        b.iter_batched(
+            || {
+                let mut runner = CairoRunner::new(&program, "all_cairo", false).unwrap();
+                let mut vm = VirtualMachine::new(false);
+                let end = runner.initialize(&mut vm).unwrap();
+                (runner, vm, end)
+            },
+            |(mut runner, mut vm, end)| {
+                runner
+                    .run_until_pc(end, &mut vm, &mut BuiltinHintProcessor::new_empty())
+                    .unwrap();
+                black_box(vm)
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
+fn relocate(c: &mut Criterion) {
+    // Millions of trace entries and memory cells, enable the `parallel` feature to compare
+    let program = include_bytes!("../cairo_programs/benchmarks/big_fibonacci.json");
+    let program = Program::from_bytes(program.as_slice(), Some("main")).unwrap();
+    let mut group = c.benchmark_group("relocate");
+    group.sample_size(10);
+    group.bench_function("big_fibonacci", |b| {
+        b.iter_batched(
+            || {
+                let mut runner = CairoRunner::new(&program, "all_cairo", false).unwrap();
+                let mut vm = VirtualMachine::new(true);
+                let end = runner.initialize(&mut vm).unwrap();
+                runner
+                    .run_until_pc(end, &mut vm, &mut BuiltinHintProcessor::new_empty())
+                    .unwrap();
+                runner
+                    .end_run(
+                        false,
+                        false,
+                        &mut vm,
+                        &mut BuiltinHintProcessor::new_empty(),
+                    )
+                    .unwrap();
+                (runner, vm)
+            },
+            |(mut runner, mut vm)| {
+                runner.relocate(&mut vm, true).unwrap();
+                black_box((runner, vm))
+            },
+            BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+}
+
 fn add_u64_with_felt252(c: &mut Criterion) {
     // There are 9 possible cases:
     // - The felt is `0`
@@ -94,5 +157,12 @@ fn add_u64_with_felt252(c: &mut Criterion) {
 }
 
 criterion_group!(felt, add_u64_with_felt252);
-criterion_group!(runner, build_many_runners, load_program_data, parse_program);
+criterion_group!(
+    runner,
+    build_many_runners,
+    load_program_data,
+    parse_program,
+    run_step_loop,
+    relocate
+);
 criterion_main!(felt, runner);
@@ -0,0 +1,160 @@
+//! Benchmarks of the VM on the fixtures of `cairo_vm::bench_utils`, each run on every
+//! [FixtureSize]. Run them with `make benchmark-vm`, or
+//! `cargo bench --bench vm_benchmark --features bench_utils`.
+
+use cairo_vm::{
+    bench_utils::{
+        countdown_program, empty_segments, filled_segments, finished_run, initialized_run,
+        memory_addresses, parse_program_json, program_json, FixtureSize, HintDispatch,
+    },
+    felt::Felt252,
+    hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+    types::exec_scope::ExecutionScopes,
+    vm::vm_core::VirtualMachine,
+};
+use criterion::{
+    black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput,
+};
+
+#[cfg(feature = "with_mimalloc")]
+use mimalloc::MiMalloc;
+
+#[cfg(feature = "with_mimalloc")]
+#[global_allocator]
+static ALLOC: MiMalloc = MiMalloc;
+
+// The memory fixtures spread their cells over this many segments
+const SEGMENTS: usize = 4;
+
+fn step_throughput(c: &mut Criterion) {
+    let mut group = c.benchmark_group("step_throughput");
+    group.sample_size(10);
+    for size in FixtureSize::ALL {
+        let program = countdown_program(size.count());
+        group.throughput(Throughput::Elements(2 * size.count() as u64 + 2));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size.name()),
+            &program,
+            |b, program| {
+                b.iter_batched(
+                    || initialized_run(program, false).unwrap(),
+                    |(mut runner, mut vm, end)| {
+                        runner
+                            .run_until_pc(end, &mut vm, &mut BuiltinHintProcessor::new_empty())
+                            .unwrap();
+                        black_box(vm)
+                    },
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+fn memory_insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("memory_insert");
+    for size in FixtureSize::ALL {
+        let addresses = memory_addresses(SEGMENTS, size.count() / SEGMENTS);
+        group.throughput(Throughput::Elements(addresses.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size.name()),
+            &addresses,
+            |b, addresses| {
+                b.iter_batched(
+                    || empty_segments(SEGMENTS),
+                    |mut vm| {
+                        for address in addresses {
+                            vm.insert_value(*address, Felt252::new(address.offset))
+                                .unwrap();
+                        }
+                        black_box(vm)
+                    },
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+fn memory_get(c: &mut Criterion) {
+    let mut group = c.benchmark_group("memory_get");
+    for size in FixtureSize::ALL {
+        let cells_per_segment = size.count() / SEGMENTS;
+        let vm = filled_segments(SEGMENTS, cells_per_segment);
+        let addresses = memory_addresses(SEGMENTS, cells_per_segment);
+        group.throughput(Throughput::Elements(addresses.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size.name()),
+            &addresses,
+            |b, addresses| {
+                b.iter(|| {
+                    for address in addresses {
+                        black_box(vm.get_integer(black_box(*address)).unwrap());
+                    }
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+fn hint_dispatch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hint_dispatch");
+    for size in FixtureSize::ALL {
+        let mut hint_dispatch = HintDispatch::new(size.count());
+        let mut vm = VirtualMachine::new(false);
+        let mut exec_scopes = ExecutionScopes::new();
+        group.throughput(Throughput::Elements(hint_dispatch.hint_data.len() as u64));
+        group.bench_function(BenchmarkId::from_parameter(size.name()), |b| {
+            b.iter(|| hint_dispatch.run(&mut vm, &mut exec_scopes).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn program_load(c: &mut Criterion) {
+    let mut group = c.benchmark_group("program_load");
+    for size in FixtureSize::ALL {
+        let program_json = program_json(size.count());
+        group.throughput(Throughput::Bytes(program_json.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size.name()),
+            &program_json,
+            |b, program_json| {
+                b.iter_with_large_drop(|| parse_program_json(black_box(program_json)).unwrap())
+            },
+        );
+    }
+    group.finish();
+}
+
+fn relocation(c: &mut Criterion) {
+    let mut group = c.benchmark_group("relocation");
+    group.sample_size(10);
+    for size in FixtureSize::ALL {
+        let program = countdown_program(size.count());
+        group.throughput(Throughput::Elements(2 * size.count() as u64 + 2));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(size.name()),
+            &program,
+            |b, program| {
+                b.iter_batched(
+                    || finished_run(program, true).unwrap(),
+                    |(mut runner, mut vm)| {
+                        runner.relocate(&mut vm, true).unwrap();
+                        black_box((runner, vm))
+                    },
+                    BatchSize::LargeInput,
+                )
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(execution, step_throughput, hint_dispatch, relocation);
+criterion_group!(memory, memory_insert, memory_get);
+criterion_group!(loading, program_load);
+criterion_main!(execution, memory, loading);
@@ -3,12 +3,30 @@
 use bincode::enc::write::Writer;
 use cairo_vm::cairo_run::{self, EncodeTraceError};
 use cairo_vm::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor;
+use cairo_vm::hint_processor::hint_coverage::HintCoverage;
+use cairo_vm::types::errors::program_errors::ProgramError;
+use cairo_vm::types::felt_format::FeltFormat;
+use cairo_vm::types::program::Program;
 use cairo_vm::vm::errors::cairo_run_errors::CairoRunError;
 use cairo_vm::vm::errors::trace_errors::TraceError;
 use cairo_vm::vm::errors::vm_errors::VirtualMachineError;
+use cairo_vm::vm::run_events::{RunEvent, RunEvents};
+use cairo_vm::vm::runners::cairo_1_output::CairoRunOutput;
+use cairo_vm::vm::runners::cairo_runner::RunResources;
+use cairo_vm::vm::runners::layout_selector::LayoutSelector;
+use cairo_vm::vm::trace::compressed_trace::CompressedTrace;
+use cairo_vm::vm::unknown_hints::UnknownHintPolicy;
 use clap::{Parser, ValueHint};
+use sha2::{Digest, Sha256};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Write as _;
 use std::io::{self, Write};
 use std::path::PathBuf;
+use std::process::ExitCode;
+use std::sync::mpsc::{self, Receiver, SyncSender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 use thiserror::Error;
 
 #[cfg(feature = "with_mimalloc")]
@@ -25,18 +43,67 @@ struct Args {
     filename: PathBuf,
     #[clap(long = "trace_file", value_parser)]
     trace_file: Option<PathBuf>,
+    /// Writes the trace delta-encoded with varints, see `CompressedTrace`
+    #[clap(long = "compressed_trace_file", value_parser)]
+    compressed_trace_file: Option<PathBuf>,
     #[structopt(long = "print_output")]
     print_output: bool,
     #[structopt(long = "entrypoint", default_value = "main")]
     entrypoint: String,
     #[structopt(long = "memory_file")]
     memory_file: Option<PathBuf>,
+    /// The layout to run the program with, or auto to select the cheapest one fitting the program
     #[clap(long = "layout", default_value = "plain", value_parser=validate_layout)]
     layout: String,
     #[structopt(long = "proof_mode")]
     proof_mode: bool,
     #[structopt(long = "secure_run")]
     secure_run: Option<bool>,
+    /// Runs programs whose builtins aren't declared in the order of the layout
+    #[structopt(long = "allow_unordered_builtins")]
+    allow_unordered_builtins: bool,
+    /// Checks that ap moves as the ap tracking data of the program says, to debug wrong values in hints
+    #[structopt(long = "check_ap_tracking")]
+    check_ap_tracking: bool,
+    /// Validates the builtin segments when the run ends instead of on each write. Ignored when a trace is written
+    #[structopt(long = "lazy_validation")]
+    lazy_validation: bool,
+    /// Fails the run as soon as a value is written beyond this offset of its segment, to catch runaway pointer arithmetic
+    #[structopt(long = "max_segment_offset")]
+    max_segment_offset: Option<usize>,
+    /// Fills the unused cells of the builtin segments as the prover expects, and prints how many were filled for each builtin. Requires proof mode
+    #[structopt(long = "fill_builtin_holes")]
+    fill_builtin_holes: bool,
+    /// What to do with the hints that are not supported: fail the run (error), skip them (skip), or skip them printing a warning (warn)
+    #[clap(long = "unknown_hints", default_value = "error", value_parser=parse_unknown_hints)]
+    unknown_hints: UnknownHints,
+    /// Prints the felts of the output in decimal (dec), hex (hex) or as short strings (str), falling back to hex for the felts which aren't printable text
+    #[clap(long = "felt_format", default_value = "dec", value_parser=parse_felt_format)]
+    felt_format: FeltFormat,
+    /// Fails the run at the instruction reading a memory cell which has no value, outside the builtin segments, instead of deducing its value
+    #[structopt(long = "strict_memory_reads")]
+    strict_memory_reads: bool,
+    /// Fails the run when the instruction at a pc runs more than this number of times, to stop untrusted programs stuck in a loop
+    #[structopt(long = "pc_execution_limit")]
+    pc_execution_limit: Option<usize>,
+    /// Fails the run if it hasn't ended after this number of steps
+    #[structopt(long = "max_steps")]
+    max_steps: Option<usize>,
+    /// Prints a warning for each builtin whose stop pointer doesn't match the cells it used instead of failing the run. Ignored in proof mode
+    #[structopt(long = "tolerate_stop_pointers")]
+    tolerate_stop_pointers: bool,
+    /// Reads the return value of main as the PanicResult of a Cairo 1 program, printing the decoded panic data and exiting with code 3 when it panicked
+    #[structopt(long = "panic_result")]
+    panic_result: bool,
+    /// Lists the hints of the program that are not supported, without running it
+    #[structopt(long = "list_hints", alias = "list-hints")]
+    list_hints: bool,
+    /// Shows the progress of the run on stderr, with an estimated time left when --max_steps is given
+    #[structopt(long = "progress")]
+    progress: bool,
+    /// Writes the SHA-256 checksums of the trace and memory files written, in the format of sha256sum
+    #[structopt(long = "run_report")]
+    run_report: Option<PathBuf>,
 }
 
 fn validate_layout(value: &str) -> Result<String, String> {
@@ -49,11 +116,45 @@ fn validate_layout(value: &str) -> Result<String, String> {
         | "recursive_large_output"
         | "all_cairo"
         | "all_solidity"
-        | "dynamic" => Ok(value.to_string()),
+        | "dynamic"
+        | "auto" => Ok(value.to_string()),
         _ => Err(format!("{value} is not a valid layout")),
     }
 }
 
+/// What the CLI does with the hints that are not supported, see `--unknown_hints`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum UnknownHints {
+    Error,
+    Skip,
+    /// Skips them, printing a warning for each one at the end of the run
+    Warn,
+}
+
+impl UnknownHints {
+    fn policy(self) -> UnknownHintPolicy {
+        match self {
+            UnknownHints::Error => UnknownHintPolicy::Error,
+            UnknownHints::Skip | UnknownHints::Warn => UnknownHintPolicy::Skip,
+        }
+    }
+}
+
+fn parse_unknown_hints(value: &str) -> Result<UnknownHints, String> {
+    match value {
+        "error" => Ok(UnknownHints::Error),
+        "skip" => Ok(UnknownHints::Skip),
+        "warn" => Ok(UnknownHints::Warn),
+        _ => Err(format!(
+            "{value} is not a valid unknown hints policy, expected error, skip or warn"
+        )),
+    }
+}
+
+fn parse_felt_format(value: &str) -> Result<FeltFormat, String> {
+    value.parse()
+}
+
 #[derive(Debug, Error)]
 enum Error {
     #[error("Invalid arguments")]
@@ -62,117 +163,384 @@ enum Error {
     IO(#[from] std::io::Error),
     #[error("The cairo program execution failed")]
     Runner(#[from] CairoRunError),
+    #[error("The cairo program could not be loaded")]
+    Program(#[from] ProgramError),
     #[error(transparent)]
     EncodeTrace(#[from] EncodeTraceError),
     #[error(transparent)]
     VirtualMachine(#[from] VirtualMachineError),
     #[error(transparent)]
     Trace(#[from] TraceError),
+    #[error("No layout has all the builtins of the program")]
+    NoLayout,
+    #[error("The cairo program panicked with {0}")]
+    Panic(String),
 }
 
-struct FileWriter {
-    buf_writer: io::BufWriter<std::fs::File>,
+/// The program failed to run, or its run couldn't be read back
+const EXIT_RUN_FAILURE: u8 = 1;
+/// Invalid arguments, or a program which couldn't be loaded
+const EXIT_USAGE: u8 = 2;
+/// The run succeeded, but the program panicked, see `--panic_result`
+const EXIT_PANIC: u8 = 3;
+/// A file couldn't be read or written
+const EXIT_IO: u8 = 4;
+
+impl Error {
+    /// The exit code of the CLI, telling the classes of failures apart for the scripts running it
+    fn exit_code(&self) -> u8 {
+        match self {
+            // 0 when only printing the help or the version
+            Error::Cli(error) => error.exit_code() as u8,
+            Error::Program(_) | Error::Runner(CairoRunError::Program(_)) | Error::NoLayout => {
+                EXIT_USAGE
+            }
+            Error::IO(_) => EXIT_IO,
+            Error::Panic(_) => EXIT_PANIC,
+            Error::Runner(_)
+            | Error::EncodeTrace(_)
+            | Error::VirtualMachine(_)
+            | Error::Trace(_) => EXIT_RUN_FAILURE,
+        }
+    }
+}
+
+/// Size of the chunks of an artifact sent to its writer thread
+const ARTIFACT_CHUNK_SIZE: usize = 1024 * 1024;
+/// Chunks serialized ahead of the writer thread, bounding the memory used while it catches up
+const ARTIFACT_CHUNKS_IN_FLIGHT: usize = 4;
+
+/// The SHA-256 checksum of a file written by the run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ArtifactDigest {
+    path: PathBuf,
+    sha256: [u8; 32],
+}
+
+impl ArtifactDigest {
+    /// Returns the line of the artifact in the run report, as printed by sha256sum.
+    fn report_line(&self) -> String {
+        let mut line = String::with_capacity(66 + self.path.as_os_str().len());
+        for byte in self.sha256 {
+            let _ = write!(line, "{byte:02x}");
+        }
+        let _ = write!(line, "  {}", self.path.display());
+        line
+    }
+}
+
+/// Writes a file from a background thread, which also computes its checksum, so the file is
+/// written while the next chunks are serialized.
+struct ArtifactWriter {
+    path: PathBuf,
+    chunk: Vec<u8>,
     bytes_written: usize,
+    sender: SyncSender<Vec<u8>>,
+    writer: JoinHandle<io::Result<[u8; 32]>>,
+}
+
+impl ArtifactWriter {
+    fn create(path: PathBuf) -> io::Result<Self> {
+        let mut file = std::fs::File::create(&path)?;
+        let (sender, receiver) = mpsc::sync_channel::<Vec<u8>>(ARTIFACT_CHUNKS_IN_FLIGHT);
+        // An error drops the receiver, making the next chunk sent fail
+        let writer = thread::spawn(move || {
+            let mut hasher = Sha256::new();
+            for chunk in receiver {
+                file.write_all(&chunk)?;
+                hasher.update(&chunk);
+            }
+            file.flush()?;
+            let mut sha256 = [0; 32];
+            sha256.copy_from_slice(&hasher.finalize());
+            Ok(sha256)
+        });
+        Ok(Self {
+            path,
+            chunk: Vec::with_capacity(ARTIFACT_CHUNK_SIZE),
+            bytes_written: 0,
+            sender,
+            writer,
+        })
+    }
+
+    /// Waits for the writer thread to write the whole file, and returns its digest. Errors
+    /// writing the file are returned here, so this should be called even if serializing failed.
+    fn finish(self) -> Result<ArtifactDigest, Error> {
+        let ArtifactWriter {
+            path,
+            chunk,
+            sender,
+            writer,
+            ..
+        } = self;
+        if !chunk.is_empty() {
+            // Fails only if the thread stopped, which the join reports
+            let _ = sender.send(chunk);
+        }
+        drop(sender);
+        let sha256 = writer
+            .join()
+            .map_err(|_| io::Error::other("The writer thread panicked"))??;
+        Ok(ArtifactDigest { path, sha256 })
+    }
+
+    /// Buffers `bytes`, sending the chunk to the writer thread once it is full. Fails if the
+    /// thread stopped, on an error that [finish](Self::finish) returns.
+    fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.chunk.extend_from_slice(bytes);
+        self.bytes_written += bytes.len();
+        if self.chunk.len() >= ARTIFACT_CHUNK_SIZE {
+            let chunk = std::mem::replace(&mut self.chunk, Vec::with_capacity(ARTIFACT_CHUNK_SIZE));
+            self.sender
+                .send(chunk)
+                .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        }
+        Ok(())
+    }
 }
 
-impl Writer for FileWriter {
+impl Writer for ArtifactWriter {
     fn write(&mut self, bytes: &[u8]) -> Result<(), bincode::error::EncodeError> {
-        self.buf_writer
-            .write_all(bytes)
+        self.write_bytes(bytes)
             .map_err(|e| bincode::error::EncodeError::Io {
                 inner: e,
                 index: self.bytes_written,
-            })?;
-
-        self.bytes_written += bytes.len();
-
-        Ok(())
+            })
     }
 }
 
-impl FileWriter {
-    fn new(buf_writer: io::BufWriter<std::fs::File>) -> Self {
-        Self {
-            buf_writer,
-            bytes_written: 0,
+/// Steps between the progress events sent by the VM
+const PROGRESS_STEP_INTERVAL: usize = 10_000;
+/// Events waiting to be reported before the VM drops the new ones, such as executed hints
+const PROGRESS_EVENTS_CAPACITY: usize = 1024;
+/// Minimum time between two updates of the progress line
+const PROGRESS_REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Reports the progress of the run on stderr until the VM stops sending events.
+///
+/// The time left is only estimated when the run has a step limit, `max_steps`, as the number of
+/// steps of a run isn't known before it ends.
+fn report_progress(receiver: Receiver<RunEvent>, max_steps: Option<usize>) -> JoinHandle<()> {
+    thread::spawn(move || {
+        let start = Instant::now();
+        let mut last_refresh: Option<Instant> = None;
+        let mut last_step = 0;
+        for event in receiver {
+            let RunEvent::StepMilestone { step, .. } = event else {
+                continue;
+            };
+            last_step = step;
+            if last_refresh.is_none_or(|time| time.elapsed() >= PROGRESS_REFRESH_INTERVAL) {
+                eprint!("\r{}", progress_line(step, start.elapsed(), max_steps));
+                last_refresh = Some(Instant::now());
+            }
         }
-    }
+        if last_refresh.is_some() {
+            eprintln!("\r{}", progress_line(last_step, start.elapsed(), None));
+        }
+    })
+}
 
-    fn flush(&mut self) -> io::Result<()> {
-        self.buf_writer.flush()
+fn progress_line(step: usize, elapsed: Duration, target_step: Option<usize>) -> String {
+    let steps_per_sec = step as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+    let mut line = format!("step {step} ({steps_per_sec:.0} steps/s)");
+    if let Some(target_step) = target_step {
+        let secs_left = target_step.saturating_sub(step) as f64 / steps_per_sec.max(f64::EPSILON);
+        let _ = write!(line, ", ~{secs_left:.0}s left to reach {target_step} steps");
     }
+    // Clears what remains of a longer previous line
+    format!("{line:<64}")
 }
 
 fn run(args: impl Iterator<Item = String>) -> Result<(), Error> {
-    let args = Args::try_parse_from(args);
-    let args = match args {
-        Ok(args) => args,
-        Err(error) => {
-            eprintln!("{error}");
-            return Err(Error::Cli(error));
-        }
+    let args = Args::try_parse_from(args)?;
+    let trace_enabled = args.trace_file.is_some() || args.compressed_trace_file.is_some();
+    let mut hint_executor = match args.max_steps {
+        Some(max_steps) => BuiltinHintProcessor::new(HashMap::new(), RunResources::new(max_steps)),
+        None => BuiltinHintProcessor::new_empty(),
+    };
+    let (run_events, progress) = if args.progress {
+        let (run_events, receiver) =
+            RunEvents::channel(PROGRESS_STEP_INTERVAL, PROGRESS_EVENTS_CAPACITY);
+        (
+            Some(run_events),
+            Some(report_progress(receiver, args.max_steps)),
+        )
+    } else {
+        (None, None)
+    };
+    let program_content = std::fs::read(&args.filename).map_err(Error::IO)?;
+    let layout = if args.layout == "auto" && !args.list_hints {
+        let layout = select_layout(&program_content, &args.entrypoint, args.proof_mode)?;
+        eprintln!("Selected the {layout} layout");
+        layout
+    } else {
+        args.layout.as_str()
     };
-    let trace_enabled = args.trace_file.is_some();
-    let mut hint_executor = BuiltinHintProcessor::new_empty();
     let cairo_run_config = cairo_run::CairoRunConfig {
         entrypoint: &args.entrypoint,
         trace_enabled,
+        // Keeps only the compressed trace in memory when it is the only one written
+        compress_trace: args.trace_file.is_none(),
         relocate_mem: args.memory_file.is_some(),
-        layout: &args.layout,
+        layout,
         proof_mode: args.proof_mode,
         secure_run: args.secure_run,
+        allow_unordered_builtins: args.allow_unordered_builtins,
+        check_ap_tracking: args.check_ap_tracking,
+        lazy_validation: args.lazy_validation,
+        size_hints: None,
+        max_segment_offset: args.max_segment_offset,
+        fill_builtin_holes: args.fill_builtin_holes,
+        step_sampling_interval: None,
+        pc_execution_limit: args.pc_execution_limit,
+        tolerate_stop_pointer_mismatches: args.tolerate_stop_pointers,
+        memory_journal: false,
+        unknown_hint_policy: args.unknown_hints.policy(),
+        strict_memory_reads: args.strict_memory_reads,
+        run_events,
     };
 
-    let program_content = std::fs::read(args.filename).map_err(Error::IO)?;
+    if args.list_hints {
+        let program = Program::from_bytes(&program_content, None)?;
+        print!("{}", HintCoverage::from_program(&program, &hint_executor));
+        return Ok(());
+    }
+
+    let result = cairo_run::cairo_run(&program_content, &cairo_run_config, &mut hint_executor);
+    // The progress report ends once every sender of events is dropped
+    drop(cairo_run_config);
+    let result = result.map(|(cairo_runner, mut vm)| {
+        vm.set_run_events(None);
+        (cairo_runner, vm)
+    });
+    if let Some(progress) = progress {
+        let _ = progress.join();
+    }
+    let (cairo_runner, mut vm) = result?;
+
+    for warning in cairo_runner.get_stop_pointer_warnings() {
+        eprintln!("Warning: {warning}");
+    }
 
-    let (cairo_runner, mut vm) =
-        match cairo_run::cairo_run(&program_content, &cairo_run_config, &mut hint_executor) {
-            Ok(runner) => runner,
-            Err(error) => {
-                eprintln!("{error}");
-                return Err(Error::Runner(error));
+    let skipped_hints = vm.get_skipped_hints();
+    if !skipped_hints.is_empty() {
+        if args.unknown_hints == UnknownHints::Warn {
+            for skipped_hint in skipped_hints {
+                eprintln!(
+                    "Warning: skipped unknown hint {} at pc {} {} times: {}",
+                    skipped_hint.hint_index, skipped_hint.pc, skipped_hint.count, skipped_hint.code
+                );
             }
-        };
+        }
+        let executions: usize = skipped_hints.iter().map(|hint| hint.count).sum();
+        eprintln!("Skipped {executions} unknown hint executions");
+    }
+
+    if let Some(filled_holes) = cairo_runner.get_filled_builtin_holes() {
+        let mut filled_holes: Vec<_> = filled_holes.iter().collect();
+        filled_holes.sort();
+        println!("Builtin cells filled:");
+        for (builtin, filled) in filled_holes {
+            println!("{builtin}: {filled}");
+        }
+    }
 
     if args.print_output {
         let mut output_buffer = "Program Output:\n".to_string();
-        vm.write_output(&mut output_buffer)?;
+        vm.write_output_in_format(&mut output_buffer, args.felt_format)?;
         print!("{output_buffer}");
     }
 
+    let mut artifacts = Vec::new();
+
     if let Some(trace_path) = args.trace_file {
         let relocated_trace = vm.get_relocated_trace()?;
 
-        let trace_file = std::fs::File::create(trace_path)?;
-        let mut trace_writer =
-            FileWriter::new(io::BufWriter::with_capacity(3 * 1024 * 1024, trace_file));
+        let mut trace_writer = ArtifactWriter::create(trace_path)?;
+        let encoded = cairo_run::write_encoded_trace(relocated_trace, &mut trace_writer);
+        artifacts.push(trace_writer.finish()?);
+        encoded?;
+    }
 
-        cairo_run::write_encoded_trace(relocated_trace, &mut trace_writer)?;
-        trace_writer.flush()?;
+    if let Some(trace_path) = args.compressed_trace_file {
+        let compressed_trace = match vm.get_relocated_compressed_trace() {
+            Ok(compressed_trace) => Cow::Borrowed(compressed_trace),
+            Err(_) => Cow::Owned(CompressedTrace::from(vm.get_relocated_trace()?.as_slice())),
+        };
+        let mut trace_writer = ArtifactWriter::create(trace_path)?;
+        let written = trace_writer.write_bytes(compressed_trace.as_bytes());
+        artifacts.push(trace_writer.finish()?);
+        written?;
     }
 
     if let Some(memory_path) = args.memory_file {
-        let memory_file = std::fs::File::create(memory_path)?;
-        let mut memory_writer =
-            FileWriter::new(io::BufWriter::with_capacity(5 * 1024 * 1024, memory_file));
+        let mut memory_writer = ArtifactWriter::create(memory_path)?;
+        let encoded =
+            cairo_run::write_encoded_memory(&cairo_runner.relocated_memory, &mut memory_writer);
+        artifacts.push(memory_writer.finish()?);
+        encoded?;
+    }
 
-        cairo_run::write_encoded_memory(&cairo_runner.relocated_memory, &mut memory_writer)?;
-        memory_writer.flush()?;
+    if let Some(report_path) = args.run_report {
+        let report: String = artifacts
+            .iter()
+            .map(|artifact| artifact.report_line() + "\n")
+            .collect();
+        std::fs::write(report_path, report)?;
+    }
+
+    if args.panic_result {
+        let output = CairoRunOutput::from_return_values(&vm).map_err(CairoRunError::from)?;
+        if let CairoRunOutput::Panic(_, message) = output {
+            return Err(Error::Panic(message));
+        }
     }
 
     Ok(())
 }
 
-fn main() -> Result<(), Error> {
-    match run(std::env::args()) {
-        Ok(()) => Ok(()),
-        Err(Error::Cli(_)) => {
-            Ok(()) // Exit with code 0 to avoid printing CLI error message
+/// Selects the cheapest layout having the builtins of the program. In proof mode the layout must
+/// also allot enough instances of each builtin for the steps run, so the program is run a first
+/// time out of proof mode to count them.
+fn select_layout(
+    program_content: &[u8],
+    entrypoint: &str,
+    proof_mode: bool,
+) -> Result<&'static str, Error> {
+    let program = Program::from_bytes(program_content, Some(entrypoint))?;
+    let layout = if proof_mode {
+        let stats = LayoutSelector::dry_run(&program, &mut BuiltinHintProcessor::new_empty())
+            .map_err(|error| *error)?;
+        LayoutSelector::minimal_for(&stats)
+    } else {
+        LayoutSelector::minimal_for(&program)
+    };
+    layout.ok_or(Error::NoLayout)
+}
+
+/// Runs the CLI, printing the error it fails with, and returns its exit code
+fn run_with_exit_code(args: impl Iterator<Item = String>) -> u8 {
+    match run(args) {
+        Ok(()) => 0,
+        Err(error) => {
+            match &error {
+                // Along with the usage, or the help or the version when they were asked for
+                Error::Cli(error) => eprintln!("{error}"),
+                Error::Runner(error) => eprintln!("{error}"),
+                Error::Panic(message) => eprintln!("Program panicked with {message}"),
+                _ => eprintln!("Error: {error:?}"),
+            }
+            error.exit_code()
         }
-        Err(error) => Err(error),
     }
 }
 
+fn main() -> ExitCode {
+    ExitCode::from(run_with_exit_code(std::env::args()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -205,6 +573,7 @@ mod tests {
                  Some("recursive_large_output"),
                  Some("all_cairo"),
                  Some("all_solidity"),
+                 Some("auto"),
                  //FIXME: dynamic layout leads to _very_ slow execution
                  //Some("dynamic"),
         )]
@@ -243,6 +612,231 @@ mod tests {
         assert_matches!(run(args.into_iter()), Ok(_));
     }
 
+    #[rstest]
+    #[case("../cairo_programs/fibonacci.json")]
+    #[case("../cairo_programs/dict.json")]
+    fn test_list_hints(#[case] program: &str) {
+        let args = ["cairo-vm-cli", "--list_hints", program]
+            .into_iter()
+            .map(String::from);
+        assert_matches!(run(args), Ok(()));
+    }
+
+    #[test]
+    fn test_list_hints_bad_file() {
+        let args = [
+            "cairo-vm-cli",
+            "--list-hints",
+            "../cairo_programs/manually_compiled/invalid_odd_length_hex.json",
+        ]
+        .into_iter()
+        .map(String::from);
+        assert_matches!(run(args), Err(Error::Program(_)));
+    }
+
+    #[test]
+    fn test_compressed_trace_file() {
+        let trace_path = std::env::temp_dir().join("cairo_vm_cli_compressed_trace");
+        let args = [
+            "cairo-vm-cli",
+            "--compressed_trace_file",
+            trace_path.to_str().unwrap(),
+            "../cairo_programs/fibonacci.json",
+        ]
+        .into_iter()
+        .map(String::from);
+        assert_matches!(run(args), Ok(()));
+
+        let compressed_trace = std::fs::read(&trace_path).unwrap();
+        assert_matches!(CompressedTrace::from_bytes(compressed_trace.clone()), Ok(trace) if !trace.is_empty());
+
+        // With --trace_file the compressed trace is built from the uncompressed one instead
+        let uncompressed_trace_path = std::env::temp_dir().join("cairo_vm_cli_uncompressed_trace");
+        let args = [
+            "cairo-vm-cli",
+            "--trace_file",
+            uncompressed_trace_path.to_str().unwrap(),
+            "--compressed_trace_file",
+            trace_path.to_str().unwrap(),
+            "../cairo_programs/fibonacci.json",
+        ]
+        .into_iter()
+        .map(String::from);
+        assert_matches!(run(args), Ok(()));
+        assert_eq!(std::fs::read(&trace_path).unwrap(), compressed_trace);
+        std::fs::remove_file(trace_path).unwrap();
+        std::fs::remove_file(uncompressed_trace_path).unwrap();
+    }
+
+    #[test]
+    fn test_artifact_writer_checksum() {
+        let path = std::env::temp_dir().join("cairo_vm_cli_artifact_writer");
+        let bytes: Vec<u8> = (0..3 * ARTIFACT_CHUNK_SIZE + 7)
+            .map(|i| (i % 251) as u8)
+            .collect();
+        let mut writer = ArtifactWriter::create(path.clone()).unwrap();
+        for piece in bytes.chunks(1000) {
+            writer.write_bytes(piece).unwrap();
+        }
+        let digest = writer.finish().unwrap();
+
+        assert_eq!(std::fs::read(&path).unwrap(), bytes);
+        assert_eq!(digest.sha256.as_slice(), Sha256::digest(&bytes).as_slice());
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_artifact_digest_report_line() {
+        let mut sha256 = [0; 32];
+        sha256.copy_from_slice(&Sha256::digest(b""));
+        let digest = ArtifactDigest {
+            path: PathBuf::from("trace.bin"),
+            sha256,
+        };
+        assert_eq!(
+            digest.report_line(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855  trace.bin"
+        );
+    }
+
+    #[test]
+    fn test_run_report() {
+        let dir = std::env::temp_dir().join("cairo_vm_cli_run_report");
+        std::fs::create_dir_all(&dir).unwrap();
+        let trace_path = dir.join("trace.bin");
+        let memory_path = dir.join("memory.bin");
+        let report_path = dir.join("report.sha256");
+        let args = [
+            "cairo-vm-cli",
+            "--trace_file",
+            trace_path.to_str().unwrap(),
+            "--memory_file",
+            memory_path.to_str().unwrap(),
+            "--run_report",
+            report_path.to_str().unwrap(),
+            "../cairo_programs/fibonacci.json",
+        ]
+        .into_iter()
+        .map(String::from);
+        assert_matches!(run(args), Ok(()));
+
+        let report = std::fs::read_to_string(&report_path).unwrap();
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(), 2);
+        for (line, path) in lines.into_iter().zip([&trace_path, &memory_path]) {
+            let (sha256, report_path) = line.split_once("  ").unwrap();
+            assert_eq!(report_path, path.to_str().unwrap());
+            let expected: String = Sha256::digest(std::fs::read(path).unwrap())
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect();
+            assert_eq!(sha256, expected);
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_run_trace_file_in_missing_dir() {
+        let args = [
+            "cairo-vm-cli",
+            "--trace_file",
+            "../missing/trace.bin",
+            "../cairo_programs/fibonacci.json",
+        ]
+        .into_iter()
+        .map(String::from);
+        assert_matches!(run(args), Err(Error::IO(_)));
+    }
+
+    #[rstest]
+    #[case(["cairo-vm-cli", "--progress", "../cairo_programs/fibonacci.json"].as_slice())]
+    #[case(["cairo-vm-cli", "--progress", "--proof_mode", "../cairo_programs/proof_programs/fibonacci.json"].as_slice())]
+    #[case(["cairo-vm-cli", "--progress", "../cairo_programs/manually_compiled/invalid_memory.json"].as_slice())]
+    #[case(["cairo-vm-cli", "--progress", "--max_steps", "100000", "../cairo_programs/fibonacci.json"].as_slice())]
+    fn test_run_with_progress(#[case] args: &[&str]) {
+        let args = args.iter().cloned().map(String::from);
+        // The progress report has to end for the run to return
+        assert_matches!(run(args), Ok(()) | Err(Error::Runner(_)));
+    }
+
+    #[test]
+    fn test_run_max_steps() {
+        let args = |max_steps: &str| -> Vec<String> {
+            [
+                "cairo-vm-cli",
+                "../cairo_programs/fibonacci.json",
+                "--max_steps",
+                max_steps,
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect()
+        };
+        assert_matches!(run(args("100000").into_iter()), Ok(()));
+        assert_matches!(
+            run(args("10").into_iter()),
+            Err(Error::Runner(CairoRunError::VmException(exception)))
+                if matches!(exception.inner_exc, VirtualMachineError::UnfinishedExecution)
+        );
+    }
+
+    #[test]
+    fn test_progress_line() {
+        assert_eq!(
+            progress_line(1000, Duration::from_secs(2), None).trim_end(),
+            "step 1000 (500 steps/s)"
+        );
+        assert_eq!(
+            progress_line(1000, Duration::from_secs(2), Some(1024)).trim_end(),
+            "step 1000 (500 steps/s), ~0s left to reach 1024 steps"
+        );
+        assert_eq!(
+            progress_line(10_000, Duration::from_secs(1), Some(16_384)).trim_end(),
+            "step 10000 (10000 steps/s), ~1s left to reach 16384 steps"
+        );
+    }
+
+    #[rstest]
+    #[case(["cairo-vm-cli", "../cairo_programs/bad_programs/unknown_hint.json"].as_slice(), false)]
+    #[case(["cairo-vm-cli", "--unknown_hints", "error", "../cairo_programs/bad_programs/unknown_hint.json"].as_slice(), false)]
+    #[case(["cairo-vm-cli", "--unknown_hints", "skip", "../cairo_programs/bad_programs/unknown_hint.json"].as_slice(), true)]
+    #[case(["cairo-vm-cli", "--unknown_hints", "warn", "../cairo_programs/bad_programs/unknown_hint.json"].as_slice(), true)]
+    fn test_run_unknown_hints(#[case] args: &[&str], #[case] succeeds: bool) {
+        let args = args.iter().cloned().map(String::from);
+        if succeeds {
+            assert_matches!(run(args), Ok(()));
+        } else {
+            assert_matches!(run(args), Err(Error::Runner(_)));
+        }
+    }
+
+    #[test]
+    fn test_run_with_felt_format() {
+        let args = [
+            "cairo-vm-cli",
+            "--print_output",
+            "--felt_format",
+            "hex",
+            "../cairo_programs/fibonacci.json",
+        ]
+        .into_iter()
+        .map(String::from);
+        assert_matches!(run(args), Ok(()));
+    }
+
+    #[test]
+    fn test_parse_felt_format() {
+        assert_eq!(parse_felt_format("str"), Ok(FeltFormat::ShortString));
+        assert!(parse_felt_format("oct").is_err());
+    }
+
+    #[test]
+    fn test_parse_unknown_hints() {
+        assert_eq!(parse_unknown_hints("warn"), Ok(UnknownHints::Warn));
+        assert_eq!(UnknownHints::Warn.policy(), UnknownHintPolicy::Skip);
+        assert!(parse_unknown_hints("ignore").is_err());
+    }
+
     #[test]
     fn test_run_missing_program() {
         let args = ["cairo-vm-cli", "../missing/program.json"]
@@ -262,11 +856,68 @@ mod tests {
         assert_matches!(run(args), Err(Error::Runner(_)));
     }
 
-    //Since the functionality here is trivial, I just call the function
-    //to fool Codecov.
     #[test]
     fn test_main() {
-        assert!(main().is_ok());
+        let args = ["cairo-vm-cli", "../cairo_programs/fibonacci.json"]
+            .into_iter()
+            .map(String::from);
+        assert_eq!(run_with_exit_code(args), 0);
+        let args = ["cairo-vm-cli", "--layout", "tiny", "program.json"]
+            .into_iter()
+            .map(String::from);
+        assert_eq!(run_with_exit_code(args), EXIT_USAGE);
+        let args = [
+            "cairo-vm-cli",
+            "../cairo_programs/bad_programs/unknown_hint.json",
+        ]
+        .into_iter()
+        .map(String::from);
+        assert_eq!(run_with_exit_code(args), EXIT_RUN_FAILURE);
+    }
+
+    #[test]
+    fn test_exit_codes() {
+        let args = ["cairo-vm-cli", "--layout", "tiny", "program.json"]
+            .into_iter()
+            .map(String::from);
+        assert_eq!(run(args).unwrap_err().exit_code(), EXIT_USAGE);
+        let args = ["cairo-vm-cli", "--help"].into_iter().map(String::from);
+        assert_eq!(run(args).unwrap_err().exit_code(), 0);
+        let args = ["cairo-vm-cli", "../missing/program.json"]
+            .into_iter()
+            .map(String::from);
+        assert_eq!(run(args).unwrap_err().exit_code(), EXIT_IO);
+        let args = [
+            "cairo-vm-cli",
+            "../cairo_programs/manually_compiled/invalid_odd_length_hex.json",
+        ]
+        .into_iter()
+        .map(String::from);
+        assert_eq!(run(args).unwrap_err().exit_code(), EXIT_USAGE);
+        let args = [
+            "cairo-vm-cli",
+            "../cairo_programs/bad_programs/unknown_hint.json",
+        ]
+        .into_iter()
+        .map(String::from);
+        assert_eq!(run(args).unwrap_err().exit_code(), EXIT_RUN_FAILURE);
+        assert_eq!(
+            Error::Panic(String::from("'Out of gas'")).exit_code(),
+            EXIT_PANIC
+        );
+    }
+
+    #[test]
+    fn test_run_panic_result_without_panic_result() {
+        // The stack of a Cairo 0 main doesn't end with a PanicResult
+        let args = [
+            "cairo-vm-cli",
+            "--panic_result",
+            "../cairo_programs/fibonacci.json",
+        ]
+        .into_iter()
+        .map(String::from);
+        assert_matches!(run(args), Err(Error::Runner(CairoRunError::Runner(_))));
     }
 
     #[test]
@@ -280,6 +931,7 @@ mod tests {
             "recursive_large_output",
             "all_cairo",
             "all_solidity",
+            "auto",
         ];
 
         for layout in valid_layouts {
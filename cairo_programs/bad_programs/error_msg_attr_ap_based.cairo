@@ -0,0 +1,9 @@
+func main() {
+    alloc_locals;
+    local x = 3;
+    tempvar y = 4;
+    with_attr error_message("x is {ids.x} and y is {y}") {
+        assert x = y;
+    }
+    return ();
+}
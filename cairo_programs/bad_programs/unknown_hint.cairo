@@ -0,0 +1,7 @@
+func main() {
+    // Not implemented by the VM, and irrelevant to the result
+    %{ print("Computing the result") %}
+    tempvar x = 2;
+    assert x = 2;
+    return ();
+}
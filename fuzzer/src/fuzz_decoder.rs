@@ -0,0 +1,21 @@
+use cairo_vm::vm::decoding::decoder::{decode_instruction, decode_strict};
+use honggfuzz::fuzz;
+
+#[cfg(feature = "with_mimalloc")]
+use mimalloc::MiMalloc;
+
+#[cfg(feature = "with_mimalloc")]
+#[global_allocator]
+static ALLOC: MiMalloc = MiMalloc;
+
+fn main() {
+    loop {
+        fuzz!(|encoded_instr: u64| {
+            // The instructions accepted by decode_strict are a subset of the ones accepted by
+            // decode_instruction, decoded the same way
+            if let Ok(instruction) = decode_strict(encoded_instr) {
+                assert_eq!(decode_instruction(encoded_instr).unwrap(), instruction);
+            }
+        });
+    }
+}
@@ -112,10 +112,24 @@ fn run(args: Args) -> Result<(), Error> {
     let cairo_run_config = cairo_run::CairoRunConfig {
         entrypoint: &args.entrypoint,
         trace_enabled,
+        compress_trace: false,
         relocate_mem: args.memory_file.is_some(),
         layout: &args.layout.to_string(),
         proof_mode: args.proof_mode,
         secure_run: args.secure_run,
+        allow_unordered_builtins: false,
+        check_ap_tracking: false,
+        lazy_validation: false,
+        size_hints: None,
+        max_segment_offset: None,
+        fill_builtin_holes: false,
+        step_sampling_interval: None,
+        pc_execution_limit: None,
+        tolerate_stop_pointer_mismatches: false,
+        memory_journal: false,
+        unknown_hint_policy: Default::default(),
+        strict_memory_reads: false,
+        run_events: None,
     };
 
     let (cairo_runner, mut vm) =
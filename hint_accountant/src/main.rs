@@ -6,9 +6,9 @@ use cairo_vm::{
         hint_processor_definition::HintProcessorLogic,
     },
     serde::deserialize_program::ApTracking,
-    types::exec_scope::ExecutionScopes,
+    types::{constants::Constants, exec_scope::ExecutionScopes},
     vm::{errors::hint_errors::HintError, vm_core::VirtualMachine},
-    with_std::collections::{HashMap, HashSet},
+    with_std::collections::{BTreeSet, HashMap},
 };
 use serde::Deserialize;
 use serde_json::Value;
@@ -52,9 +52,9 @@ fn run() {
         HashMap::new(),
         Vec::new(),
         ExecutionScopes::new(),
-        HashMap::new(),
+        Constants::new(),
     );
-    let missing_hints: HashSet<_> = WHITELISTS
+    let missing_hints: BTreeSet<_> = WHITELISTS
         .iter()
         .flat_map(|wl| {
             serde_json::from_str::<Whitelist>(wl)
@@ -0,0 +1,311 @@
+//! Fixtures for the benchmarks of the VM, enabled by the `bench_utils` feature.
+//!
+//! The fixtures are built in memory, without compiled Cairo programs, so the benchmarks of
+//! `bench/vm_benchmark.rs` run on a fresh checkout and measure the same work on every version.
+//! Each kind of fixture comes in the sizes of [FixtureSize], for patches changing how the VM
+//! scales to be compared on every size. Downstream benchmarks should use these fixtures too, so
+//! their numbers can be compared with the ones of the suite.
+
+use crate::stdlib::{any::Any, collections::HashMap, prelude::*, rc::Rc};
+
+use felt::Felt252;
+use serde_json::json;
+
+use crate::{
+    hint_processor::{
+        builtin_hint_processor::builtin_hint_processor_definition::{
+            BuiltinHintProcessor, HintFunc, HintProcessorData,
+        },
+        hint_processor_definition::HintProcessorLogic,
+    },
+    serde::deserialize_program::ReferenceManager,
+    types::{
+        constants::Constants,
+        errors::program_errors::ProgramError,
+        exec_scope::ExecutionScopes,
+        program::Program,
+        relocatable::{MaybeRelocatable, Relocatable},
+    },
+    vm::{
+        errors::{cairo_run_errors::CairoRunError, hint_errors::HintError},
+        runners::cairo_runner::CairoRunner,
+        vm_core::VirtualMachine,
+    },
+};
+
+/// The sizes of the fixtures, each ten times the previous one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FixtureSize {
+    Small,
+    Medium,
+    Large,
+}
+
+impl FixtureSize {
+    pub const ALL: [FixtureSize; 3] = [FixtureSize::Small, FixtureSize::Medium, FixtureSize::Large];
+
+    /// Returns the name of the size, to use as the parameter of a benchmark.
+    pub fn name(self) -> &'static str {
+        match self {
+            FixtureSize::Small => "small",
+            FixtureSize::Medium => "medium",
+            FixtureSize::Large => "large",
+        }
+    }
+
+    /// Returns the number of elements of a fixture of this size: iterations of a loop, memory
+    /// cells, hints or program cells.
+    pub fn count(self) -> usize {
+        match self {
+            FixtureSize::Small => 1_000,
+            FixtureSize::Medium => 10_000,
+            FixtureSize::Large => 100_000,
+        }
+    }
+}
+
+// [ap] = iterations, ap++
+// loop:
+// [ap] = [ap + -1] + -1, ap++
+// jmp rel loop if [ap + -1] != 0
+// ret
+const COUNTDOWN_ASSIGN: i64 = 0x480680017fff8000;
+const COUNTDOWN_DECREMENT: i64 = 0x482480017fff8000;
+const COUNTDOWN_JNZ: i64 = 0x20680017fff7fff;
+const COUNTDOWN_RET: i64 = 0x208b7fff7fff7ffe;
+
+fn countdown_data(iterations: usize) -> Vec<Felt252> {
+    vec![
+        Felt252::new(COUNTDOWN_ASSIGN),
+        Felt252::new(iterations.max(1)),
+        Felt252::new(COUNTDOWN_DECREMENT),
+        Felt252::new(-1),
+        Felt252::new(COUNTDOWN_JNZ),
+        Felt252::new(-2),
+        Felt252::new(COUNTDOWN_RET),
+    ]
+}
+
+/// Returns a program counting down from `iterations` in a loop, without hints nor builtins.
+/// Its run takes `2 * iterations + 2` steps, and writes `iterations + 1` cells of the execution
+/// segment.
+pub fn countdown_program(iterations: usize) -> Program {
+    Program::new(
+        Vec::new(),
+        countdown_data(iterations)
+            .into_iter()
+            .map(MaybeRelocatable::from)
+            .collect(),
+        Some(0),
+        HashMap::new(),
+        ReferenceManager {
+            references: Vec::new(),
+        },
+        HashMap::new(),
+        Vec::new(),
+        None,
+    )
+    .expect("The countdown program is valid")
+}
+
+/// Returns the compiled JSON of a program of `cells` cells: the countdown program of
+/// [countdown_program] for a single iteration, followed by cells that are never run.
+pub fn program_json(cells: usize) -> Vec<u8> {
+    let mut data = countdown_data(1);
+    let padding = cells.saturating_sub(data.len());
+    data.extend((0..padding).map(|index| {
+        if index % 2 == 0 {
+            Felt252::new(COUNTDOWN_ASSIGN)
+        } else {
+            Felt252::new(index)
+        }
+    }));
+    let data: Vec<String> = data
+        .iter()
+        .map(|value| format!("0x{}", value.to_str_radix(16)))
+        .collect();
+    let program = json!({
+        "prime": "0x800000000000011000000000000000000000000000000000000000000000001",
+        "builtins": [],
+        "data": data,
+        "identifiers": {
+            "__main__.main": { "pc": 0, "type": "function" }
+        },
+        "hints": {},
+        "reference_manager": { "references": [] },
+        "attributes": [],
+        "debug_info": null
+    });
+    serde_json::to_vec(&program).expect("The program serializes")
+}
+
+/// Returns a runner of `program` on the `plain` layout and its VM, initialized, along with the pc
+/// ending the run.
+pub fn initialized_run(
+    program: &Program,
+    trace_enabled: bool,
+) -> Result<(CairoRunner, VirtualMachine, Relocatable), Box<CairoRunError>> {
+    let mut runner = CairoRunner::new(program, "plain", false).map_err(CairoRunError::from)?;
+    let mut vm = VirtualMachine::new(trace_enabled);
+    let end = runner.initialize(&mut vm).map_err(CairoRunError::from)?;
+    Ok((runner, vm, end))
+}
+
+/// Runs `program` to its end, without relocating it, to benchmark the relocation.
+pub fn finished_run(
+    program: &Program,
+    trace_enabled: bool,
+) -> Result<(CairoRunner, VirtualMachine), Box<CairoRunError>> {
+    let (mut runner, mut vm, end) = initialized_run(program, trace_enabled)?;
+    let mut hint_processor = BuiltinHintProcessor::new_empty();
+    runner
+        .run_until_pc(end, &mut vm, &mut hint_processor)
+        .map_err(CairoRunError::from)?;
+    runner
+        .end_run(false, false, &mut vm, &mut hint_processor)
+        .map_err(CairoRunError::from)?;
+    Ok((runner, vm))
+}
+
+/// Returns a VM with `segments` empty segments.
+pub fn empty_segments(segments: usize) -> VirtualMachine {
+    let mut vm = VirtualMachine::new(false);
+    for _ in 0..segments {
+        vm.add_memory_segment();
+    }
+    vm
+}
+
+/// Returns the addresses of the first `cells_per_segment` cells of each of the first `segments`
+/// segments, segment by segment.
+pub fn memory_addresses(segments: usize, cells_per_segment: usize) -> Vec<Relocatable> {
+    (0..segments as isize)
+        .flat_map(|segment_index| {
+            (0..cells_per_segment).map(move |offset| Relocatable::from((segment_index, offset)))
+        })
+        .collect()
+}
+
+/// Returns a VM whose `segments` segments hold their offset in each of their first
+/// `cells_per_segment` cells.
+pub fn filled_segments(segments: usize, cells_per_segment: usize) -> VirtualMachine {
+    let mut vm = empty_segments(segments);
+    for address in memory_addresses(segments, cells_per_segment) {
+        vm.insert_value(address, Felt252::new(address.offset))
+            .expect("The cells are written once");
+    }
+    vm
+}
+
+/// Hints run one after the other, to benchmark how the [BuiltinHintProcessor] finds the
+/// implementation of a hint from its code.
+pub struct HintDispatch {
+    pub hint_processor: BuiltinHintProcessor,
+    pub hint_data: Vec<Box<dyn Any>>,
+    pub constants: Constants,
+}
+
+impl HintDispatch {
+    /// Creates `extra_hints` hints doing nothing, added to the hint processor, followed by a
+    /// builtin hint entering a scope and another exiting it.
+    pub fn new(extra_hints: usize) -> Self {
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let noop = Rc::new(HintFunc(Box::new(|_, _, _, _, _| Ok(()))));
+        let mut codes: Vec<String> = (0..extra_hints)
+            .map(|index| format!("noop_hint_{index}"))
+            .collect();
+        for code in codes.iter() {
+            hint_processor.add_hint(code.clone(), Rc::clone(&noop));
+        }
+        codes.push(String::from("vm_enter_scope()"));
+        codes.push(String::from("vm_exit_scope()"));
+        let hint_data = codes
+            .into_iter()
+            .map(|code| {
+                Box::new(HintProcessorData::new_default(code, HashMap::new())) as Box<dyn Any>
+            })
+            .collect();
+        HintDispatch {
+            hint_processor,
+            hint_data,
+            constants: Constants::new(),
+        }
+    }
+
+    /// Runs every hint once, leaving the scopes as they were.
+    pub fn run(
+        &mut self,
+        vm: &mut VirtualMachine,
+        exec_scopes: &mut ExecutionScopes,
+    ) -> Result<(), HintError> {
+        for hint_data in self.hint_data.iter() {
+            self.hint_processor
+                .execute_hint(vm, exec_scopes, hint_data, &self.constants)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the program returned by [program_json].
+pub fn parse_program_json(program_json: &[u8]) -> Result<Program, ProgramError> {
+    Program::from_bytes(program_json, Some("main"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn countdown_run_takes_the_expected_steps() {
+        let (_, vm) = finished_run(&countdown_program(10), true).unwrap();
+        assert_eq!(vm.current_step, 22);
+        assert_eq!(
+            vm.segments
+                .memory
+                .get_integer((1, 2).into())
+                .unwrap()
+                .as_ref(),
+            &Felt252::new(10)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn program_json_parses_and_runs() {
+        let program = parse_program_json(&program_json(FixtureSize::Small.count())).unwrap();
+        assert_eq!(program.data_len(), FixtureSize::Small.count());
+        let (mut runner, mut vm, end) = initialized_run(&program, false).unwrap();
+        runner
+            .run_until_pc(end, &mut vm, &mut BuiltinHintProcessor::new_empty())
+            .unwrap();
+        assert_eq!(vm.current_step, 4);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn filled_segments_hold_their_offsets() {
+        let vm = filled_segments(2, 5);
+        assert_eq!(vm.segments.num_segments(), 2);
+        assert_eq!(
+            vm.get_integer((1, 4).into()).unwrap().as_ref(),
+            &Felt252::new(4)
+        );
+        assert_eq!(memory_addresses(2, 5).len(), 10);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn hint_dispatch_runs_every_hint() {
+        let mut hint_dispatch = HintDispatch::new(3);
+        assert_eq!(hint_dispatch.hint_data.len(), 5);
+        let mut exec_scopes = ExecutionScopes::new();
+        let mut vm = VirtualMachine::new(false);
+        hint_dispatch.run(&mut vm, &mut exec_scopes).unwrap();
+        hint_dispatch.run(&mut vm, &mut exec_scopes).unwrap();
+        assert_eq!(exec_scopes.data.len(), 1);
+    }
+}
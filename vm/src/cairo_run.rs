@@ -1,10 +1,13 @@
+use crate::stdlib::prelude::*;
+
 use crate::{
     hint_processor::hint_processor_definition::HintProcessor,
     types::program::Program,
     vm::{
         errors::{cairo_run_errors::CairoRunError, vm_exception::VmException},
-        runners::cairo_runner::CairoRunner,
+        runners::{cairo_runner::CairoRunner, run_outcome::RunSizeHints},
         security::verify_secure_runner,
+        unknown_hints::UnknownHintPolicy,
         vm_core::VirtualMachine,
     },
 };
@@ -14,13 +17,80 @@ use felt::Felt252;
 
 use thiserror_no_std::Error;
 
+/// The options of a run, accepted by [cairo_run] and [CairoRunner::from_config].
+///
+/// [CairoRunConfig::execute], [CairoRunConfig::prove] and [CairoRunConfig::debug] preset the
+/// options for the usual kinds of runs, which the chained setters or the struct update syntax
+/// then adjust:
+///
+/// ```
+/// # use cairo_vm::cairo_run::CairoRunConfig;
+/// let cairo_run_config = CairoRunConfig {
+///     max_segment_offset: Some(1 << 20),
+///     ..CairoRunConfig::prove().layout("all_cairo")
+/// };
+/// assert!(cairo_run_config.proof_mode && cairo_run_config.trace_enabled);
+/// ```
 pub struct CairoRunConfig<'a> {
+    /// The function the run starts from, only used when the program is parsed by [cairo_run]
     pub entrypoint: &'a str,
+    /// Records the registers of each step, to relocate them into the trace of the run
     pub trace_enabled: bool,
+    /// Keeps the trace compressed in memory when `trace_enabled` is set, to be read with
+    /// [VirtualMachine::get_relocated_compressed_trace] instead of
+    /// [VirtualMachine::get_relocated_trace]
+    pub compress_trace: bool,
+    /// Relocates the memory into a single address space at the end of the run, as the prover
+    /// expects
     pub relocate_mem: bool,
+    /// The name of the layout, giving the builtins available and the cells allotted to them
     pub layout: &'a str,
+    /// Runs the program from its `__start__` label and pads the run to a power of two steps, so
+    /// it can be proven
     pub proof_mode: bool,
+    /// Verifies the memory of the builtins and the bounds of the pointers at the end of the run.
+    /// Defaults to running the verification out of proof mode only
     pub secure_run: Option<bool>,
+    /// Allows programs whose builtins aren't declared in the order of the layout,
+    /// see [CairoRunner::set_allow_unordered_builtins]
+    pub allow_unordered_builtins: bool,
+    /// Checks the ap tracking data of the program against the run,
+    /// see [CairoRunner::set_check_ap_tracking]
+    pub check_ap_tracking: bool,
+    /// Validates the builtin segments when the run ends instead of on each write,
+    /// see [crate::vm::vm_memory::memory::Memory::set_lazy_validation]. Ignored when `trace_enabled` is set, so runs which are
+    /// traced keep failing at the step writing an invalid value
+    pub lazy_validation: bool,
+    /// Sizes of a previous run of the same workload, to allocate the trace and segments upfront,
+    /// see [RunSizeHints::apply]
+    pub size_hints: Option<&'a RunSizeHints>,
+    /// Fails the writes beyond this offset of a segment during the run,
+    /// see [crate::vm::vm_memory::memory::Memory::set_max_segment_offset]
+    pub max_segment_offset: Option<usize>,
+    /// Fills the holes of the builtin segments before finalizing them, which requires `proof_mode`,
+    /// see [CairoRunner::fill_builtin_memory_holes]
+    pub fill_builtin_holes: bool,
+    /// Samples the pc and segment sizes every given number of steps,
+    /// see [VirtualMachine::enable_step_sampling]
+    pub step_sampling_interval: Option<usize>,
+    /// Fails the run when the instruction at a pc runs more than this number of times,
+    /// see [VirtualMachine::set_pc_execution_limit]
+    pub pc_execution_limit: Option<usize>,
+    /// Reports the stop pointers not matching the cells used by their builtins instead of failing
+    /// runs out of proof mode, see [CairoRunner::set_tolerate_stop_pointer_mismatches]
+    pub tolerate_stop_pointer_mismatches: bool,
+    /// Records the memory accesses of the instructions run, see
+    /// [VirtualMachine::enable_memory_journal]
+    pub memory_journal: bool,
+    /// What the VM does with the hints the hint processor doesn't know,
+    /// see [VirtualMachine::set_unknown_hint_policy]
+    pub unknown_hint_policy: UnknownHintPolicy,
+    /// Fails the instructions reading memory cells which have no value and no builtin deduces,
+    /// see [VirtualMachine::set_strict_memory_reads]
+    pub strict_memory_reads: bool,
+    /// Sends the events of the run through this channel, see [VirtualMachine::set_run_events]
+    #[cfg(feature = "std")]
+    pub run_events: Option<crate::vm::run_events::RunEvents>,
 }
 
 impl<'a> Default for CairoRunConfig<'a> {
@@ -28,11 +98,198 @@ impl<'a> Default for CairoRunConfig<'a> {
         CairoRunConfig {
             entrypoint: "main",
             trace_enabled: false,
+            compress_trace: false,
             relocate_mem: false,
             layout: "plain",
             proof_mode: false,
             secure_run: None,
+            allow_unordered_builtins: false,
+            check_ap_tracking: false,
+            lazy_validation: false,
+            size_hints: None,
+            max_segment_offset: None,
+            fill_builtin_holes: false,
+            step_sampling_interval: None,
+            pc_execution_limit: None,
+            tolerate_stop_pointer_mismatches: false,
+            memory_journal: false,
+            unknown_hint_policy: UnknownHintPolicy::Error,
+            strict_memory_reads: false,
+            #[cfg(feature = "std")]
+            run_events: None,
+        }
+    }
+}
+
+impl<'a> CairoRunConfig<'a> {
+    /// Runs a program to get its results, verifying the run as it is done out of proof mode,
+    /// without keeping the trace. This is the default configuration.
+    pub fn execute() -> Self {
+        Self::default()
+    }
+
+    /// Runs a program in proof mode, keeping the trace and relocating the memory to build the
+    /// inputs of a prover.
+    pub fn prove() -> Self {
+        CairoRunConfig {
+            trace_enabled: true,
+            relocate_mem: true,
+            proof_mode: true,
+            ..Self::default()
+        }
+    }
+
+    /// Runs a program out of proof mode keeping everything needed to debug it: the trace, the
+    /// relocated memory and the memory accesses of each step, checking ap against the ap tracking
+    /// data of the program.
+    pub fn debug() -> Self {
+        CairoRunConfig {
+            trace_enabled: true,
+            relocate_mem: true,
+            check_ap_tracking: true,
+            memory_journal: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn entrypoint(mut self, entrypoint: &'a str) -> Self {
+        self.entrypoint = entrypoint;
+        self
+    }
+
+    pub fn layout(mut self, layout: &'a str) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    pub fn proof_mode(mut self, proof_mode: bool) -> Self {
+        self.proof_mode = proof_mode;
+        self
+    }
+
+    pub fn secure_run(mut self, secure_run: bool) -> Self {
+        self.secure_run = Some(secure_run);
+        self
+    }
+
+    pub fn trace_enabled(mut self, trace_enabled: bool) -> Self {
+        self.trace_enabled = trace_enabled;
+        self
+    }
+
+    pub fn compress_trace(mut self, compress_trace: bool) -> Self {
+        self.compress_trace = compress_trace;
+        self
+    }
+
+    pub fn relocate_mem(mut self, relocate_mem: bool) -> Self {
+        self.relocate_mem = relocate_mem;
+        self
+    }
+
+    pub fn allow_unordered_builtins(mut self, allow_unordered_builtins: bool) -> Self {
+        self.allow_unordered_builtins = allow_unordered_builtins;
+        self
+    }
+
+    pub fn check_ap_tracking(mut self, check_ap_tracking: bool) -> Self {
+        self.check_ap_tracking = check_ap_tracking;
+        self
+    }
+
+    pub fn lazy_validation(mut self, lazy_validation: bool) -> Self {
+        self.lazy_validation = lazy_validation;
+        self
+    }
+
+    pub fn size_hints(mut self, size_hints: &'a RunSizeHints) -> Self {
+        self.size_hints = Some(size_hints);
+        self
+    }
+
+    pub fn max_segment_offset(mut self, max_segment_offset: usize) -> Self {
+        self.max_segment_offset = Some(max_segment_offset);
+        self
+    }
+
+    pub fn fill_builtin_holes(mut self, fill_builtin_holes: bool) -> Self {
+        self.fill_builtin_holes = fill_builtin_holes;
+        self
+    }
+
+    pub fn step_sampling_interval(mut self, step_sampling_interval: usize) -> Self {
+        self.step_sampling_interval = Some(step_sampling_interval);
+        self
+    }
+
+    pub fn pc_execution_limit(mut self, pc_execution_limit: usize) -> Self {
+        self.pc_execution_limit = Some(pc_execution_limit);
+        self
+    }
+
+    pub fn tolerate_stop_pointer_mismatches(mut self, tolerate: bool) -> Self {
+        self.tolerate_stop_pointer_mismatches = tolerate;
+        self
+    }
+
+    pub fn memory_journal(mut self, memory_journal: bool) -> Self {
+        self.memory_journal = memory_journal;
+        self
+    }
+
+    pub fn unknown_hint_policy(mut self, unknown_hint_policy: UnknownHintPolicy) -> Self {
+        self.unknown_hint_policy = unknown_hint_policy;
+        self
+    }
+
+    pub fn strict_memory_reads(mut self, strict_memory_reads: bool) -> Self {
+        self.strict_memory_reads = strict_memory_reads;
+        self
+    }
+
+    #[cfg(feature = "std")]
+    pub fn run_events(mut self, run_events: crate::vm::run_events::RunEvents) -> Self {
+        self.run_events = Some(run_events);
+        self
+    }
+
+    /// Returns whether the run is verified when it ends, which `secure_run` defaults to out of
+    /// proof mode.
+    pub fn is_secure_run(&self) -> bool {
+        self.secure_run.unwrap_or(!self.proof_mode)
+    }
+
+    /// Creates the virtual machine of the run, with the options of the config applied.
+    ///
+    /// Along with [CairoRunner::from_config], this applies every option but `entrypoint`,
+    /// `relocate_mem`, `secure_run` and `fill_builtin_holes`, which are about the steps around
+    /// the run rather than the runner or the VM: [cairo_run_program] takes care of them.
+    pub fn new_vm(&self) -> VirtualMachine {
+        let mut vm = VirtualMachine::new(self.trace_enabled);
+        if self.trace_enabled && self.compress_trace {
+            vm.compress_trace();
         }
+        #[cfg(feature = "std")]
+        vm.set_run_events(self.run_events.clone());
+        vm.segments
+            .memory
+            .set_lazy_validation(self.lazy_validation && !self.trace_enabled);
+        vm.segments
+            .memory
+            .set_max_segment_offset(self.max_segment_offset);
+        if let Some(size_hints) = self.size_hints {
+            size_hints.apply(&mut vm);
+        }
+        if let Some(interval) = self.step_sampling_interval {
+            vm.enable_step_sampling(interval);
+        }
+        vm.set_pc_execution_limit(self.pc_execution_limit);
+        if self.memory_journal {
+            vm.enable_memory_journal();
+        }
+        vm.set_unknown_hint_policy(self.unknown_hint_policy);
+        vm.set_strict_memory_reads(self.strict_memory_reads);
+        vm
     }
 }
 
@@ -43,34 +300,52 @@ pub fn cairo_run(
 ) -> Result<(CairoRunner, VirtualMachine), CairoRunError> {
     let program = Program::from_bytes(program_content, Some(cairo_run_config.entrypoint))?;
 
-    let secure_run = cairo_run_config
-        .secure_run
-        .unwrap_or(!cairo_run_config.proof_mode);
-
-    let mut cairo_runner = CairoRunner::new(
-        &program,
-        cairo_run_config.layout,
-        cairo_run_config.proof_mode,
-    )?;
+    cairo_run_program(&program, cairo_run_config, hint_executor).map_err(|error| *error)
+}
 
-    let mut vm = VirtualMachine::new(cairo_run_config.trace_enabled);
-    let end = cairo_runner.initialize(&mut vm)?;
+/// Runs an already parsed [`Program`] from its `main` entrypoint.
+///
+/// Note that `cairo_run_config.entrypoint` is ignored here, as it is resolved when parsing the program.
+pub fn cairo_run_program(
+    program: &Program,
+    cairo_run_config: &CairoRunConfig,
+    hint_executor: &mut dyn HintProcessor,
+) -> Result<(CairoRunner, VirtualMachine), Box<CairoRunError>> {
+    let mut cairo_runner =
+        CairoRunner::from_config(program, cairo_run_config).map_err(CairoRunError::from)?;
+    let mut vm = cairo_run_config.new_vm();
+    let end = cairo_runner
+        .initialize(&mut vm)
+        .map_err(CairoRunError::from)?;
     // check step calculation
 
     cairo_runner
         .run_until_pc(end, &mut vm, hint_executor)
-        .map_err(|err| VmException::from_vm_error(&cairo_runner, &vm, err))?;
-    cairo_runner.end_run(false, false, &mut vm, hint_executor)?;
+        .map_err(|err| CairoRunError::from(VmException::from_vm_error(&cairo_runner, &vm, err)))?;
+    cairo_runner
+        .end_run(false, false, &mut vm, hint_executor)
+        .map_err(CairoRunError::from)?;
 
-    vm.verify_auto_deductions()?;
-    cairo_runner.read_return_values(&mut vm)?;
+    vm.verify_auto_deductions().map_err(CairoRunError::from)?;
+    cairo_runner
+        .read_return_values(&mut vm)
+        .map_err(CairoRunError::from)?;
+    if cairo_run_config.fill_builtin_holes {
+        cairo_runner
+            .fill_builtin_memory_holes(&mut vm)
+            .map_err(CairoRunError::from)?;
+    }
     if cairo_run_config.proof_mode {
-        cairo_runner.finalize_segments(&mut vm)?;
+        cairo_runner
+            .finalize_segments(&mut vm)
+            .map_err(CairoRunError::from)?;
     }
-    if secure_run {
-        verify_secure_runner(&cairo_runner, true, None, &mut vm)?;
+    if cairo_run_config.is_secure_run() {
+        verify_secure_runner(&cairo_runner, true, None, &mut vm).map_err(CairoRunError::from)?;
     }
-    cairo_runner.relocate(&mut vm, cairo_run_config.relocate_mem)?;
+    cairo_runner
+        .relocate(&mut vm, cairo_run_config.relocate_mem)
+        .map_err(CairoRunError::from)?;
 
     Ok((cairo_runner, vm))
 }
@@ -126,14 +401,19 @@ pub fn write_encoded_memory(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::stdlib::prelude::*;
     use crate::{
         hint_processor::{
             builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
             hint_processor_definition::HintProcessor,
         },
+        types::relocatable::Relocatable,
         utils::test_utils::*,
+        vm::{
+            errors::runner_errors::RunnerError,
+            runners::builtin_runner::{BITWISE_BUILTIN_NAME, OUTPUT_BUILTIN_NAME},
+        },
     };
+    use assert_matches::assert_matches;
     use bincode::enc::write::SliceWriter;
     use felt::Felt252;
 
@@ -229,6 +509,32 @@ mod tests {
         assert_eq!(&output_buffer, "0\n");
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_with_compressed_trace() {
+        let program_content = include_bytes!("../../cairo_programs/fibonacci.json");
+        let cairo_run_config = CairoRunConfig::default().trace_enabled(true);
+        let (_, vm) = cairo_run(
+            program_content,
+            &cairo_run_config,
+            &mut BuiltinHintProcessor::new_empty(),
+        )
+        .unwrap();
+        let (_, compressed_vm) = cairo_run(
+            program_content,
+            &cairo_run_config.compress_trace(true),
+            &mut BuiltinHintProcessor::new_empty(),
+        )
+        .unwrap();
+
+        assert!(compressed_vm.get_relocated_trace().is_err());
+        let compressed_trace = compressed_vm.get_relocated_compressed_trace().unwrap();
+        assert_eq!(
+            compressed_trace.iter().collect::<Vec<_>>(),
+            *vm.get_relocated_trace().unwrap()
+        );
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn write_binary_trace_file() {
@@ -297,4 +603,142 @@ mod tests {
         assert!(cairo_runner.relocate(&mut vm, false).is_ok());
         assert!(vm.get_relocated_trace().is_err());
     }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn cairo_run_with_max_segment_offset() {
+        let program_data = include_bytes!("../../cairo_programs/fibonacci.json");
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let cairo_run_config = CairoRunConfig {
+            max_segment_offset: Some(1 << 20),
+            ..CairoRunConfig::default()
+        };
+        assert!(cairo_run(program_data, &cairo_run_config, &mut hint_processor).is_ok());
+
+        let cairo_run_config = CairoRunConfig {
+            max_segment_offset: Some(10),
+            ..CairoRunConfig::default()
+        };
+        let Err(error) = cairo_run(program_data, &cairo_run_config, &mut hint_processor) else {
+            panic!("the run should exceed the maximum segment offset");
+        };
+        assert!(error
+            .to_string()
+            .contains("beyond the maximum segment offset 10"));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn cairo_run_fill_builtin_holes() {
+        let program_data =
+            include_bytes!("../../cairo_programs/proof_programs/bitwise_output.json");
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let cairo_run_config = CairoRunConfig {
+            layout: "all_cairo",
+            proof_mode: true,
+            fill_builtin_holes: true,
+            ..CairoRunConfig::default()
+        };
+        let (cairo_runner, vm) =
+            cairo_run(program_data, &cairo_run_config, &mut hint_processor).unwrap();
+
+        let bitwise = vm
+            .get_builtin_runners()
+            .iter()
+            .find(|builtin| builtin.name() == BITWISE_BUILTIN_NAME)
+            .unwrap();
+        let size = vm.segments.get_segment_size(bitwise.base()).unwrap();
+        assert!((0..size).all(|offset| vm
+            .segments
+            .memory
+            .get(&Relocatable::from((bitwise.base() as isize, offset)))
+            .is_some()));
+        // The program only wrote the inputs and read the `and` of its single bitwise instance
+        let filled_holes = cairo_runner.get_filled_builtin_holes().unwrap();
+        assert_eq!(filled_holes[BITWISE_BUILTIN_NAME], size - 3);
+        assert_eq!(filled_holes[OUTPUT_BUILTIN_NAME], 0);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn cairo_run_fill_builtin_holes_no_proof_mode() {
+        let program_data = include_bytes!("../../cairo_programs/bitwise_output.json");
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let cairo_run_config = CairoRunConfig {
+            layout: "all_cairo",
+            fill_builtin_holes: true,
+            ..CairoRunConfig::default()
+        };
+        let Err(error) = cairo_run(program_data, &cairo_run_config, &mut hint_processor) else {
+            panic!("filling the builtin holes should require proof mode");
+        };
+        assert_matches!(
+            error,
+            CairoRunError::Runner(RunnerError::FillHolesNoProofMode)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn cairo_run_config_presets() {
+        let execute = CairoRunConfig::execute();
+        assert!(!execute.proof_mode && !execute.trace_enabled && execute.is_secure_run());
+
+        let prove = CairoRunConfig::prove().layout("all_cairo");
+        assert!(prove.proof_mode && prove.trace_enabled && prove.relocate_mem);
+        assert!(!prove.is_secure_run());
+        assert!(prove.secure_run(true).is_secure_run());
+
+        let debug = CairoRunConfig::debug().entrypoint("fib");
+        assert!(debug.check_ap_tracking && debug.memory_journal && debug.trace_enabled);
+        assert_eq!(debug.entrypoint, "fib");
+        assert!(debug.new_vm().get_memory_journal().is_some());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn cairo_run_config_setters() {
+        let cairo_run_config = CairoRunConfig::execute()
+            .max_segment_offset(10)
+            .memory_journal(true)
+            .fill_builtin_holes(true);
+        assert!(cairo_run_config.fill_builtin_holes);
+        let vm = cairo_run_config.new_vm();
+        assert_eq!(vm.segments.memory.max_segment_offset(), Some(10));
+        assert!(vm.get_memory_journal().is_some());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn cairo_run_debug_preset() {
+        let program_data = include_bytes!("../../cairo_programs/fibonacci.json");
+        let (cairo_runner, vm) = cairo_run(
+            program_data,
+            &CairoRunConfig::debug().layout("all_cairo"),
+            &mut BuiltinHintProcessor::new_empty(),
+        )
+        .unwrap();
+        assert!(vm.get_relocated_trace().is_ok());
+        assert!(!cairo_runner.relocated_memory.is_empty());
+        // Four accesses for each of the 80 steps
+        assert_eq!(vm.get_memory_journal().unwrap().len(), 320);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn cairo_runner_from_config() {
+        let program = Program::from_bytes(
+            include_bytes!("../../cairo_programs/fibonacci.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let cairo_runner =
+            CairoRunner::from_config(&program, &CairoRunConfig::prove().layout("small")).unwrap();
+        assert!(cairo_runner.proof_mode);
+        assert_eq!(cairo_runner.layout._name, "small");
+        assert_matches!(
+            CairoRunner::from_config(&program, &CairoRunConfig::execute().layout("tiny")),
+            Err(RunnerError::InvalidLayoutName(_))
+        );
+    }
 }
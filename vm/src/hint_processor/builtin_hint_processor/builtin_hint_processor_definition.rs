@@ -26,8 +26,12 @@ use crate::{
             ec_double_assign_new_x, ec_double_assign_new_x_v2,
         },
         hint_processor_definition::HintProcessorLogic,
+        hint_processor_utils::compute_addr_from_reference,
+    },
+    vm::{
+        parallel_hints::{execute_parallel_hint, ParallelHint, ParallelHintFunc},
+        runners::cairo_runner::{ResourceTracker, RunResources},
     },
-    vm::runners::cairo_runner::{ResourceTracker, RunResources},
 };
 use crate::{
     hint_processor::{
@@ -56,6 +60,7 @@ use crate::{
             math_utils::*,
             memcpy_hint_utils::{add_segment, enter_scope, exit_scope, memcpy_enter_scope},
             memset_utils::{memset_enter_scope, memset_step_loop},
+            oracle::{execute_oracle_hint, parse_oracle_hint},
             poseidon_utils::{n_greater_than_10, n_greater_than_2},
             pow_utils::pow,
             secp::{
@@ -107,21 +112,228 @@ use crate::{
         hint_processor_definition::HintReference,
     },
     serde::deserialize_program::ApTracking,
-    stdlib::{any::Any, collections::HashMap, prelude::*, rc::Rc},
-    types::exec_scope::ExecutionScopes,
+    stdlib::{
+        any::Any,
+        collections::{HashMap, HashSet},
+        prelude::*,
+        rc::Rc,
+        sync::Arc,
+    },
+    types::{constants::Constants, exec_scope::ExecutionScopes},
     vm::{errors::hint_errors::HintError, vm_core::VirtualMachine},
 };
-use felt::Felt252;
+
+use lazy_static::lazy_static;
 
 #[cfg(feature = "skip_next_instruction_hint")]
 use crate::hint_processor::builtin_hint_processor::skip_next_instruction::skip_next_instruction;
 
 use super::blake2s_utils::example_blake2s_compress;
 
+lazy_static! {
+    // The codes of the hints implemented natively by [BuiltinHintProcessor::execute_hint], to tell
+    // whether a hint is supported without running it. Must be kept in sync with its dispatch,
+    // which the tests check both ways.
+    static ref BUILTIN_HINT_CODES: HashSet<&'static str> = {
+        #[allow(unused_mut)]
+        let mut codes: HashSet<&'static str> = [
+        hint_code::ADD_SEGMENT,
+        hint_code::IS_NN,
+        hint_code::IS_NN_OUT_OF_RANGE,
+        hint_code::ASSERT_LE_FELT,
+        hint_code::ASSERT_LE_FELT_EXCLUDED_2,
+        hint_code::ASSERT_LE_FELT_EXCLUDED_1,
+        hint_code::ASSERT_LE_FELT_EXCLUDED_0,
+        hint_code::IS_LE_FELT,
+        hint_code::ASSERT_250_BITS,
+        hint_code::IS_250_BITS,
+        hint_code::IS_ADDR_BOUNDED,
+        hint_code::IS_POSITIVE,
+        hint_code::SPLIT_INT_ASSERT_RANGE,
+        hint_code::SPLIT_INT,
+        hint_code::ASSERT_NOT_EQUAL,
+        hint_code::ASSERT_NN,
+        hint_code::SQRT,
+        hint_code::ASSERT_NOT_ZERO,
+        hint_code::IS_QUAD_RESIDUE,
+        hint_code::VM_EXIT_SCOPE,
+        hint_code::MEMCPY_ENTER_SCOPE,
+        hint_code::MEMSET_ENTER_SCOPE,
+        hint_code::MEMCPY_CONTINUE_COPYING,
+        hint_code::MEMSET_CONTINUE_LOOP,
+        hint_code::SPLIT_FELT,
+        hint_code::UNSIGNED_DIV_REM,
+        hint_code::SIGNED_DIV_REM,
+        hint_code::ASSERT_LT_FELT,
+        hint_code::FIND_ELEMENT,
+        hint_code::SEARCH_SORTED_LOWER,
+        hint_code::POW,
+        hint_code::SET_ADD,
+        hint_code::DICT_NEW,
+        hint_code::DICT_READ,
+        hint_code::DICT_WRITE,
+        hint_code::DEFAULT_DICT_NEW,
+        hint_code::SQUASH_DICT_INNER_FIRST_ITERATION,
+        hint_code::USORT_ENTER_SCOPE,
+        hint_code::USORT_BODY,
+        hint_code::USORT_VERIFY,
+        hint_code::USORT_VERIFY_MULTIPLICITY_ASSERT,
+        hint_code::USORT_VERIFY_MULTIPLICITY_BODY,
+        hint_code::BLAKE2S_COMPUTE,
+        hint_code::VERIFY_ZERO_V1,
+        hint_code::VERIFY_ZERO_V2,
+        hint_code::VERIFY_ZERO_V3,
+        hint_code::VERIFY_ZERO_EXTERNAL_SECP,
+        hint_code::NONDET_BIGINT3_V1,
+        hint_code::NONDET_BIGINT3_V2,
+        hint_code::REDUCE,
+        hint_code::REDUCE_ED25519,
+        hint_code::BLAKE2S_FINALIZE,
+        hint_code::BLAKE2S_FINALIZE_V2,
+        hint_code::BLAKE2S_FINALIZE_V3,
+        hint_code::BLAKE2S_ADD_UINT256,
+        hint_code::BLAKE2S_ADD_UINT256_BIGEND,
+        hint_code::UNSAFE_KECCAK,
+        hint_code::UNSAFE_KECCAK_FINALIZE,
+        hint_code::SQUASH_DICT_INNER_SKIP_LOOP,
+        hint_code::SQUASH_DICT_INNER_CHECK_ACCESS_INDEX,
+        hint_code::SQUASH_DICT_INNER_CONTINUE_LOOP,
+        hint_code::SQUASH_DICT_INNER_ASSERT_LEN_KEYS,
+        hint_code::SQUASH_DICT_INNER_LEN_ASSERT,
+        hint_code::SQUASH_DICT_INNER_USED_ACCESSES_ASSERT,
+        hint_code::SQUASH_DICT_INNER_NEXT_KEY,
+        hint_code::SQUASH_DICT,
+        hint_code::VM_ENTER_SCOPE,
+        hint_code::DICT_UPDATE,
+        hint_code::DICT_SQUASH_COPY_DICT,
+        hint_code::DICT_SQUASH_UPDATE_PTR,
+        hint_code::UINT256_ADD,
+        hint_code::UINT256_ADD_LOW,
+        hint_code::UINT128_ADD,
+        hint_code::UINT256_SUB,
+        hint_code::SPLIT_64,
+        hint_code::UINT256_SQRT,
+        hint_code::UINT256_SQRT_FELT,
+        hint_code::UINT256_SIGNED_NN,
+        hint_code::UINT256_UNSIGNED_DIV_REM,
+        hint_code::UINT256_EXPANDED_UNSIGNED_DIV_REM,
+        hint_code::BIGINT_TO_UINT256,
+        hint_code::IS_ZERO_PACK_V1,
+        hint_code::IS_ZERO_PACK_V2,
+        hint_code::IS_ZERO_NONDET,
+        hint_code::IS_ZERO_INT,
+        hint_code::IS_ZERO_PACK_EXTERNAL_SECP_V1,
+        hint_code::IS_ZERO_PACK_EXTERNAL_SECP_V2,
+        hint_code::IS_ZERO_PACK_ED25519,
+        hint_code::IS_ZERO_ASSIGN_SCOPE_VARS,
+        hint_code::IS_ZERO_ASSIGN_SCOPE_VARS_EXTERNAL_SECP,
+        hint_code::IS_ZERO_ASSIGN_SCOPE_VARS_ED25519,
+        hint_code::DIV_MOD_N_PACKED_DIVMOD_V1,
+        hint_code::GET_FELT_BIT_LENGTH,
+        hint_code::BIGINT_PACK_DIV_MOD,
+        hint_code::BIGINT_SAFE_DIV,
+        hint_code::DIV_MOD_N_PACKED_DIVMOD_EXTERNAL_N,
+        hint_code::DIV_MOD_N_SAFE_DIV,
+        hint_code::DIV_MOD_N_SAFE_DIV_PLUS_ONE,
+        hint_code::GET_POINT_FROM_X,
+        hint_code::EC_NEGATE,
+        hint_code::EC_NEGATE_EMBEDDED_SECP,
+        hint_code::EC_DOUBLE_SLOPE_V1,
+        hint_code::EC_DOUBLE_SLOPE_V2,
+        hint_code::EC_DOUBLE_SLOPE_V3,
+        hint_code::EC_DOUBLE_SLOPE_EXTERNAL_CONSTS,
+        hint_code::COMPUTE_SLOPE_V1,
+        hint_code::SQUARE_SLOPE_X_MOD_P,
+        hint_code::COMPUTE_SLOPE_V2,
+        hint_code::COMPUTE_SLOPE_SECP256R1,
+        hint_code::IMPORT_SECP256R1_P,
+        hint_code::COMPUTE_SLOPE_WHITELIST,
+        hint_code::EC_DOUBLE_ASSIGN_NEW_X_V1,
+        hint_code::EC_DOUBLE_ASSIGN_NEW_X_V2,
+        hint_code::EC_DOUBLE_ASSIGN_NEW_X_V3,
+        hint_code::EC_DOUBLE_ASSIGN_NEW_X_V4,
+        hint_code::EC_DOUBLE_ASSIGN_NEW_Y,
+        hint_code::KECCAK_WRITE_ARGS,
+        hint_code::COMPARE_BYTES_IN_WORD_NONDET,
+        hint_code::SHA256_MAIN_CONSTANT_INPUT_LENGTH,
+        hint_code::SHA256_MAIN_ARBITRARY_INPUT_LENGTH,
+        hint_code::SHA256_INPUT,
+        hint_code::SHA256_FINALIZE,
+        hint_code::CAIRO_KECCAK_INPUT_IS_FULL_WORD,
+        hint_code::COMPARE_KECCAK_FULL_RATE_IN_BYTES_NONDET,
+        hint_code::BLOCK_PERMUTATION,
+        hint_code::BLOCK_PERMUTATION_WHITELIST_V1,
+        hint_code::BLOCK_PERMUTATION_WHITELIST_V2,
+        hint_code::CAIRO_KECCAK_FINALIZE_V1,
+        hint_code::CAIRO_KECCAK_FINALIZE_V2,
+        hint_code::FAST_EC_ADD_ASSIGN_NEW_X,
+        hint_code::FAST_EC_ADD_ASSIGN_NEW_X_V2,
+        hint_code::FAST_EC_ADD_ASSIGN_NEW_X_V3,
+        hint_code::FAST_EC_ADD_ASSIGN_NEW_Y,
+        hint_code::EC_MUL_INNER,
+        hint_code::RELOCATE_SEGMENT,
+        hint_code::TEMPORARY_ARRAY,
+        hint_code::VERIFY_ECDSA_SIGNATURE,
+        hint_code::SPLIT_OUTPUT_0,
+        hint_code::SPLIT_OUTPUT_1,
+        hint_code::SPLIT_INPUT_3,
+        hint_code::SPLIT_INPUT_6,
+        hint_code::SPLIT_INPUT_9,
+        hint_code::SPLIT_INPUT_12,
+        hint_code::SPLIT_INPUT_15,
+        hint_code::SPLIT_N_BYTES,
+        hint_code::SPLIT_OUTPUT_MID_LOW_HIGH,
+        hint_code::NONDET_N_GREATER_THAN_10,
+        hint_code::NONDET_N_GREATER_THAN_2,
+        hint_code::RANDOM_EC_POINT,
+        hint_code::CHAINED_EC_OP_RANDOM_EC_POINT,
+        hint_code::RECOVER_Y,
+        hint_code::PACK_MODN_DIV_MODN,
+        hint_code::XS_SAFE_DIV,
+        hint_code::UINT384_UNSIGNED_DIV_REM,
+        hint_code::UINT384_SPLIT_128,
+        hint_code::ADD_NO_UINT384_CHECK,
+        hint_code::UINT384_SQRT,
+        hint_code::UNSIGNED_DIV_REM_UINT768_BY_UINT384,
+        hint_code::UNSIGNED_DIV_REM_UINT768_BY_UINT384_STRIPPED,
+        hint_code::SUB_REDUCED_A_AND_REDUCED_B,
+        hint_code::UINT384_GET_SQUARE_ROOT,
+        hint_code::UINT256_GET_SQUARE_ROOT,
+        hint_code::UINT384_SIGNED_NN,
+        hint_code::UINT384_DIV,
+        hint_code::UINT256_MUL_DIV_MOD,
+        hint_code::IMPORT_SECP256R1_ALPHA,
+        hint_code::IMPORT_SECP256R1_N,
+        hint_code::UINT512_UNSIGNED_DIV_REM,
+        hint_code::HI_MAX_BITLEN,
+        hint_code::QUAD_BIT,
+        hint_code::INV_MOD_P_UINT256,
+        hint_code::INV_MOD_P_UINT512,
+        hint_code::DI_BIT,
+        hint_code::EXAMPLE_BLAKE2S_COMPRESS,
+        hint_code::EC_RECOVER_DIV_MOD_N_PACKED,
+        hint_code::EC_RECOVER_SUB_A_B,
+        hint_code::A_B_BITAND_1,
+        hint_code::ASSERT_LE_FELT_V_0_6,
+        hint_code::ASSERT_LE_FELT_V_0_8,
+        hint_code::EC_RECOVER_PRODUCT_MOD,
+        hint_code::EC_RECOVER_PRODUCT_DIV_M,
+        hint_code::SPLIT_XX,
+        ]
+        .into_iter()
+        .collect();
+        #[cfg(feature = "skip_next_instruction_hint")]
+        codes.insert(hint_code::SKIP_NEXT_INSTRUCTION);
+        codes
+    };
+}
+
 pub struct HintProcessorData {
     pub code: String,
     pub ap_tracking: ApTracking,
     pub ids_data: HashMap<String, HintReference>,
+    /// The scope paths of the module and functions the hint runs in, from the outermost one
+    pub accessible_scopes: Vec<String>,
 }
 
 impl HintProcessorData {
@@ -130,6 +342,7 @@ impl HintProcessorData {
             code,
             ap_tracking: ApTracking::default(),
             ids_data,
+            accessible_scopes: Vec::new(),
         }
     }
 }
@@ -142,19 +355,25 @@ pub struct HintFunc(
                 &mut ExecutionScopes,
                 &HashMap<String, HintReference>,
                 &ApTracking,
-                &HashMap<String, Felt252>,
+                &Constants,
             ) -> Result<(), HintError>
             + Sync,
     >,
 );
 pub struct BuiltinHintProcessor {
     pub extra_hints: HashMap<String, Rc<HintFunc>>,
+    // Hints only implemented within a scope, by code, along with their scope
+    scoped_hints: HashMap<String, Vec<(String, Rc<HintFunc>)>>,
+    // Hints which may run in parallel with the other hints at their pc, by code
+    parallel_hints: HashMap<String, Arc<ParallelHintFunc>>,
     run_resources: RunResources,
 }
 impl BuiltinHintProcessor {
     pub fn new_empty() -> Self {
         BuiltinHintProcessor {
             extra_hints: HashMap::new(),
+            scoped_hints: HashMap::new(),
+            parallel_hints: HashMap::new(),
             run_resources: RunResources::default(),
         }
     }
@@ -162,6 +381,8 @@ impl BuiltinHintProcessor {
     pub fn new(extra_hints: HashMap<String, Rc<HintFunc>>, run_resources: RunResources) -> Self {
         BuiltinHintProcessor {
             extra_hints,
+            scoped_hints: HashMap::new(),
+            parallel_hints: HashMap::new(),
             run_resources,
         }
     }
@@ -169,6 +390,57 @@ impl BuiltinHintProcessor {
     pub fn add_hint(&mut self, hint_code: String, hint_func: Rc<HintFunc>) {
         self.extra_hints.insert(hint_code, hint_func);
     }
+
+    /// Registers `hint_func` as the implementation of `hint_code` for the hints which run inside
+    /// `scope`, a module or function path such as `starkware.cairo.common.math`, that is the hints
+    /// listing `scope` among their `accessible_scopes`.
+    ///
+    /// Scoped implementations take precedence over the ones added with [Self::add_hint] and the
+    /// builtin ones, the implementation of the innermost scope being used when several scopes
+    /// match. Hints with the same code outside of the scope keep their usual implementation.
+    /// Registering a scope twice for the same code replaces its implementation.
+    pub fn add_scoped_hint(&mut self, scope: String, hint_code: String, hint_func: Rc<HintFunc>) {
+        let implementations = self.scoped_hints.entry(hint_code).or_default();
+        match implementations.iter_mut().find(|(s, _)| *s == scope) {
+            Some(implementation) => implementation.1 = hint_func,
+            None => implementations.push((scope, hint_func)),
+        }
+    }
+
+    /// Registers `hint_func` as the implementation of `hint_code` which may run in parallel with
+    /// the other hints at its pc, see [VirtualMachine::set_parallel_hints]. Otherwise it runs as
+    /// the hints added with [Self::add_hint], which take precedence over it, as do the scoped ones.
+    pub fn add_parallel_hint(&mut self, hint_code: String, hint_func: Arc<ParallelHintFunc>) {
+        self.parallel_hints.insert(hint_code, hint_func);
+    }
+
+    // Returns the implementation registered for the innermost of the accessible scopes of the
+    // hint, if any
+    fn scoped_hint(&self, hint_data: &HintProcessorData) -> Option<&Rc<HintFunc>> {
+        let implementations = self.scoped_hints.get(&hint_data.code)?;
+        hint_data
+            .accessible_scopes
+            .iter()
+            .rev()
+            .find_map(|accessible_scope| {
+                implementations
+                    .iter()
+                    .find(|(scope, _)| scope == accessible_scope)
+                    .map(|(_, hint_func)| hint_func)
+            })
+    }
+
+    /// Returns true if `hint_code` is either an extra hint or implemented by the processor.
+    ///
+    /// The code is looked up among the registered hints and the natively implemented ones,
+    /// without running it.
+    pub fn supports_hint(&self, hint_code: &str) -> bool {
+        self.extra_hints.contains_key(hint_code)
+            || self.scoped_hints.contains_key(hint_code)
+            || self.parallel_hints.contains_key(hint_code)
+            || BUILTIN_HINT_CODES.contains(hint_code)
+            || parse_oracle_hint(hint_code).is_some()
+    }
 }
 
 impl HintProcessorLogic for BuiltinHintProcessor {
@@ -177,13 +449,16 @@ impl HintProcessorLogic for BuiltinHintProcessor {
         vm: &mut VirtualMachine,
         exec_scopes: &mut ExecutionScopes,
         hint_data: &Box<dyn Any>,
-        constants: &HashMap<String, Felt252>,
+        constants: &Constants,
     ) -> Result<(), HintError> {
         let hint_data = hint_data
             .downcast_ref::<HintProcessorData>()
             .ok_or(HintError::WrongHintData)?;
 
-        if let Some(hint_func) = self.extra_hints.get(&hint_data.code) {
+        if let Some(hint_func) = self
+            .scoped_hint(hint_data)
+            .or_else(|| self.extra_hints.get(&hint_data.code))
+        {
             return hint_func.0(
                 vm,
                 exec_scopes,
@@ -192,6 +467,18 @@ impl HintProcessorLogic for BuiltinHintProcessor {
                 constants,
             );
         }
+        if let Some(hint_func) = self.parallel_hints.get(&hint_data.code) {
+            let ids = hint_data
+                .ids_data
+                .iter()
+                .map(|(name, reference)| {
+                    compute_addr_from_reference(reference, vm, &hint_data.ap_tracking)
+                        .map(|addr| (name.clone(), addr))
+                        .ok_or_else(|| HintError::UnknownIdentifier(name.clone().into_boxed_str()))
+                })
+                .collect::<Result<_, HintError>>()?;
+            return execute_parallel_hint(hint_func, vm, &ids);
+        }
         match &*hint_data.code {
             hint_code::ADD_SEGMENT => add_segment(vm),
             hint_code::IS_NN => is_nn(vm, &hint_data.ids_data, &hint_data.ap_tracking),
@@ -726,9 +1013,12 @@ impl HintProcessorLogic for BuiltinHintProcessor {
             hint_code::RANDOM_EC_POINT => {
                 random_ec_point_hint(vm, &hint_data.ids_data, &hint_data.ap_tracking)
             }
-            hint_code::CHAINED_EC_OP_RANDOM_EC_POINT => {
-                chained_ec_op_random_ec_point_hint(vm, &hint_data.ids_data, &hint_data.ap_tracking)
-            }
+            hint_code::CHAINED_EC_OP_RANDOM_EC_POINT => chained_ec_op_random_ec_point_hint(
+                vm,
+                exec_scopes,
+                &hint_data.ids_data,
+                &hint_data.ap_tracking,
+            ),
             hint_code::RECOVER_Y => recover_y_hint(vm, &hint_data.ids_data, &hint_data.ap_tracking),
             hint_code::PACK_MODN_DIV_MODN => {
                 pack_modn_div_modn(vm, exec_scopes, &hint_data.ids_data, &hint_data.ap_tracking)
@@ -810,8 +1100,38 @@ impl HintProcessorLogic for BuiltinHintProcessor {
             hint_code::SPLIT_XX => split_xx(vm, &hint_data.ids_data, &hint_data.ap_tracking),
             #[cfg(feature = "skip_next_instruction_hint")]
             hint_code::SKIP_NEXT_INSTRUCTION => skip_next_instruction(vm),
-            code => Err(HintError::UnknownHint(code.to_string().into_boxed_str())),
+            code => match parse_oracle_hint(code) {
+                Some(requests) => execute_oracle_hint(
+                    vm,
+                    exec_scopes,
+                    &requests,
+                    &hint_data.ids_data,
+                    &hint_data.ap_tracking,
+                ),
+                None => Err(HintError::UnknownHint(code.to_string().into_boxed_str())),
+            },
+        }
+    }
+
+    fn parallel_hint<'a>(
+        &'a self,
+        vm: &VirtualMachine,
+        hint_data: &Box<dyn Any>,
+    ) -> Option<ParallelHint<'a>> {
+        let hint_data = hint_data.downcast_ref::<HintProcessorData>()?;
+        if self.scoped_hint(hint_data).is_some() || self.extra_hints.contains_key(&hint_data.code) {
+            return None;
         }
+        let func = self.parallel_hints.get(&hint_data.code)?;
+        let ids = hint_data
+            .ids_data
+            .iter()
+            .map(|(name, reference)| {
+                let addr = compute_addr_from_reference(reference, vm, &hint_data.ap_tracking)?;
+                Some((name.clone(), addr))
+            })
+            .collect::<Option<_>>()?;
+        Some(ParallelHint { func, ids })
     }
 }
 
@@ -849,6 +1169,7 @@ mod tests {
         },
     };
     use assert_matches::assert_matches;
+    use felt::Felt252;
     use num_traits::{One, Zero};
 
     #[cfg(target_arch = "wasm32")]
@@ -916,6 +1237,83 @@ mod tests {
         );
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn supports_hint() {
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        assert!(hint_processor.supports_hint(hint_code::ADD_SEGMENT));
+        assert!(hint_processor.supports_hint(hint_code::IS_NN));
+        assert!(!hint_processor.supports_hint("random_invalid_code"));
+
+        let hint_func = HintFunc(Box::new(|_, _, _, _, _| Ok(())));
+        hint_processor.add_hint(String::from("random_invalid_code"), Rc::new(hint_func));
+        assert!(hint_processor.supports_hint("random_invalid_code"));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn builtin_hint_codes_are_dispatched() {
+        for code in BUILTIN_HINT_CODES.iter() {
+            let hint_data = any_box!(HintProcessorData::new_default(
+                code.to_string(),
+                HashMap::new()
+            ));
+            // Running the hint on an empty VM fails, or even panics, once it reaches its
+            // implementation: only an unknown hint error means the code isn't dispatched
+            let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                BuiltinHintProcessor::new_empty().execute_hint(
+                    &mut vm!(),
+                    &mut ExecutionScopes::new(),
+                    &hint_data,
+                    &Constants::new(),
+                )
+            }));
+            assert!(
+                !matches!(result, Ok(Err(HintError::UnknownHint(_)))),
+                "{code} isn't dispatched"
+            );
+        }
+    }
+
+    // Returns the names of the hint_code constants used in `source`
+    fn hint_code_constants(source: &str) -> HashSet<&str> {
+        source
+            .split("hint_code::")
+            .skip(1)
+            .filter_map(|rest| {
+                rest.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .next()
+            })
+            .collect()
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn dispatched_hints_are_builtin_hint_codes() {
+        // The dispatch is a match on the codes, so its codes are read from the source, by the
+        // name of their constant
+        let source = include_str!("builtin_hint_processor_definition.rs");
+        let section = |start: &str, end: &str| {
+            let start = source.find(start).unwrap();
+            &source[start..start + source[start..].find(end).unwrap()]
+        };
+        let listed = hint_code_constants(section(
+            "static ref BUILTIN_HINT_CODES",
+            "pub struct HintProcessorData",
+        ));
+        let dispatched = hint_code_constants(section(
+            "match &*hint_data.code {",
+            "code => match parse_oracle_hint(code)",
+        ));
+        assert!(!dispatched.is_empty());
+        for constant in dispatched {
+            assert!(
+                listed.contains(constant),
+                "hint_code::{constant} is dispatched but missing from BUILTIN_HINT_CODES"
+            );
+        }
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn memcpy_enter_scope_valid() {
@@ -1230,7 +1628,7 @@ mod tests {
         exec_scopes: &mut ExecutionScopes,
         _ids_data: &HashMap<String, HintReference>,
         _ap_tracking: &ApTracking,
-        _constants: &HashMap<String, Felt252>,
+        _constants: &Constants,
     ) -> Result<(), HintError> {
         exec_scopes.enter_scope(HashMap::new());
         Ok(())
@@ -1253,7 +1651,7 @@ mod tests {
                 &mut vm,
                 exec_scopes,
                 &any_box!(hint_data),
-                &HashMap::new(),
+                &Constants::new(),
             ),
             Ok(())
         );
@@ -1265,10 +1663,71 @@ mod tests {
                 &mut vm,
                 exec_scopes,
                 &any_box!(hint_data),
-                &HashMap::new(),
+                &Constants::new(),
             ),
             Ok(())
         );
         assert_eq!(exec_scopes.data.len(), 3);
     }
+
+    fn insert_assert_nn(
+        _vm: &mut VirtualMachine,
+        exec_scopes: &mut ExecutionScopes,
+        _ids_data: &HashMap<String, HintReference>,
+        _ap_tracking: &ApTracking,
+        _constants: &Constants,
+    ) -> Result<(), HintError> {
+        exec_scopes.insert_value("assert_nn", 1);
+        Ok(())
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn scoped_hint_dispatch() {
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        hint_processor.add_scoped_hint(
+            String::from("lib.math"),
+            String::from(hint_code::ADD_SEGMENT),
+            Rc::new(HintFunc(Box::new(enter_scope))),
+        );
+        hint_processor.add_scoped_hint(
+            String::from("lib.math.assert_nn"),
+            String::from(hint_code::ADD_SEGMENT),
+            Rc::new(HintFunc(Box::new(insert_assert_nn))),
+        );
+        let mut vm = vm!();
+        add_segments!(vm, 1);
+        let mut exec_scopes = ExecutionScopes::new();
+        let mut run_in = |scopes: &[&str], exec_scopes: &mut ExecutionScopes| {
+            let scopes: Vec<String> = scopes.iter().map(|scope| scope.to_string()).collect();
+            let hint_data = hint_processor
+                .compile_hint_with_scopes(
+                    hint_code::ADD_SEGMENT,
+                    &scopes,
+                    &ApTracking::default(),
+                    &HashMap::new(),
+                    &[],
+                )
+                .unwrap();
+            hint_processor.execute_hint(&mut vm, exec_scopes, &hint_data, &Constants::new())
+        };
+
+        // The same code in an unrelated library runs the builtin implementation
+        assert_matches!(run_in(&["other", "other.math"], &mut exec_scopes), Ok(()));
+        assert_eq!(exec_scopes.data.len(), 1);
+        // Within the scope, the scoped implementation runs instead
+        assert_matches!(
+            run_in(&["lib", "lib.math", "lib.math.assert_le"], &mut exec_scopes),
+            Ok(())
+        );
+        assert_eq!(exec_scopes.data.len(), 2);
+        // The innermost scope wins
+        assert_matches!(
+            run_in(&["lib", "lib.math", "lib.math.assert_nn"], &mut exec_scopes),
+            Ok(())
+        );
+        assert_eq!(exec_scopes.data.len(), 2);
+        assert_matches!(exec_scopes.get::<i32>("assert_nn"), Ok(1));
+        assert_eq!(vm.segments.num_segments(), 2);
+    }
 }
@@ -14,7 +14,7 @@ use crate::{
         hint_processor_definition::HintReference,
     },
     serde::deserialize_program::ApTracking,
-    types::{errors::math_errors::MathError, relocatable::MaybeRelocatable},
+    types::{constants::Constants, errors::math_errors::MathError, relocatable::MaybeRelocatable},
     vm::{
         errors::{hint_errors::HintError, vm_errors::VirtualMachineError},
         vm_core::VirtualMachine,
@@ -82,7 +82,7 @@ pub fn compare_bytes_in_word_nondet(
     vm: &mut VirtualMachine,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
-    constants: &HashMap<String, Felt252>,
+    constants: &Constants,
 ) -> Result<(), HintError> {
     let n_bytes = get_integer_from_var_name("n_bytes", vm, ids_data, ap_tracking)?;
     let n_bytes = n_bytes.as_ref();
@@ -111,7 +111,7 @@ pub fn compare_keccak_full_rate_in_bytes_nondet(
     vm: &mut VirtualMachine,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
-    constants: &HashMap<String, Felt252>,
+    constants: &Constants,
 ) -> Result<(), HintError> {
     let n_bytes = get_integer_from_var_name("n_bytes", vm, ids_data, ap_tracking)?;
     let n_bytes = n_bytes.as_ref();
@@ -149,7 +149,7 @@ pub(crate) fn block_permutation_v1(
     vm: &mut VirtualMachine,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
-    constants: &HashMap<String, Felt252>,
+    constants: &Constants,
 ) -> Result<(), HintError> {
     let keccak_state_size_felts = constants
         .get(KECCAK_STATE_SIZE_FELTS)
@@ -216,7 +216,7 @@ pub(crate) fn block_permutation_v2(
     vm: &mut VirtualMachine,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
-    constants: &HashMap<String, Felt252>,
+    constants: &Constants,
 ) -> Result<(), HintError> {
     let keccak_state_size_felts = constants
         .get(KECCAK_STATE_SIZE_FELTS)
@@ -252,7 +252,7 @@ fn cairo_keccak_finalize(
     vm: &mut VirtualMachine,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
-    constants: &HashMap<String, Felt252>,
+    constants: &Constants,
     block_size_limit: usize,
 ) -> Result<(), HintError> {
     let keccak_state_size_felts = constants
@@ -313,7 +313,7 @@ pub(crate) fn cairo_keccak_finalize_v1(
     vm: &mut VirtualMachine,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
-    constants: &HashMap<String, Felt252>,
+    constants: &Constants,
 ) -> Result<(), HintError> {
     cairo_keccak_finalize(vm, ids_data, ap_tracking, constants, 10)
 }
@@ -334,7 +334,7 @@ pub(crate) fn cairo_keccak_finalize_v2(
     vm: &mut VirtualMachine,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
-    constants: &HashMap<String, Felt252>,
+    constants: &Constants,
 ) -> Result<(), HintError> {
     cairo_keccak_finalize(vm, ids_data, ap_tracking, constants, 1000)
 }
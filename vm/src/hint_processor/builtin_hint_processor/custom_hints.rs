@@ -0,0 +1,240 @@
+//! Custom hints declared with [define_hint](crate::define_hint), without the boilerplate of
+//! resolving their ids.
+//!
+//! A hint declared with the macro is a unit struct implementing [CustomHint]: its code, and a
+//! body receiving the values of the ids it declares, extracted according to their Cairo type
+//! before it runs. The errors of the extraction are the ones of the [hint_utils](super::hint_utils)
+//! helpers, naming the variable that couldn't be resolved.
+
+use crate::stdlib::{borrow::Cow, collections::HashMap, prelude::*, rc::Rc};
+
+use felt::Felt252;
+
+use crate::{
+    hint_processor::{
+        builtin_hint_processor::{
+            builtin_hint_processor_definition::{BuiltinHintProcessor, HintFunc},
+            hint_utils::{
+                get_integer_from_var_name, get_maybe_relocatable_from_var_name,
+                get_ptr_from_var_name, get_relocatable_from_var_name,
+            },
+        },
+        hint_processor_definition::HintReference,
+    },
+    serde::deserialize_program::ApTracking,
+    types::{
+        constants::Constants,
+        exec_scope::ExecutionScopes,
+        relocatable::{MaybeRelocatable, Relocatable},
+    },
+    vm::{errors::hint_errors::HintError, vm_core::VirtualMachine},
+};
+
+/// Declares a custom hint: its code, its ids with their Cairo types, and its body.
+///
+/// Each id is declared as `name: kind`, and bound to a variable of the same name before the body
+/// runs. The kinds are:
+/// * `felt`: the value of a felt, as a [Felt252](crate::felt::Felt252)
+/// * `ptr`: the value of a pointer, as a [Relocatable](crate::types::relocatable::Relocatable)
+/// * `addr`: the address of the variable, to read the members of a struct or write the variable
+/// * `value`: the value of the variable, as a
+///   [MaybeRelocatable](crate::types::relocatable::MaybeRelocatable)
+///
+/// The body gets the VM, the execution scopes and the constants under the names given after
+/// `fn run`, and returns a `Result<(), HintError>`, so memory and scope errors can be propagated
+/// with `?`.
+///
+/// ```
+/// use cairo_vm::define_hint;
+/// use cairo_vm::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor;
+///
+/// define_hint! {
+///     /// Writes twice `ids.a` to `ids.b`
+///     pub struct DoubleA = "ids.b = 2 * ids.a";
+///     ids { a: felt, b: addr }
+///     fn run(vm, _exec_scopes, _constants) {
+///         vm.insert_value(b, a.clone() + a)?;
+///         Ok(())
+///     }
+/// }
+///
+/// let mut hint_processor = BuiltinHintProcessor::new_empty();
+/// hint_processor.add_custom_hint::<DoubleA>();
+/// ```
+#[macro_export]
+macro_rules! define_hint {
+    (
+        $(#[$attr:meta])*
+        $vis:vis struct $name:ident = $code:expr;
+        ids { $($id:ident : $kind:ident),* $(,)? }
+        fn run($vm:ident, $exec_scopes:ident, $constants:ident) $body:block
+    ) => {
+        $(#[$attr])*
+        $vis struct $name;
+
+        impl $crate::hint_processor::builtin_hint_processor::custom_hints::CustomHint for $name {
+            const CODE: &'static str = $code;
+
+            #[allow(unused_variables)]
+            fn run(
+                $vm: &mut $crate::vm::vm_core::VirtualMachine,
+                $exec_scopes: &mut $crate::types::exec_scope::ExecutionScopes,
+                ids: &$crate::hint_processor::builtin_hint_processor::custom_hints::HintIds,
+                $constants: &$crate::types::constants::Constants,
+            ) -> ::core::result::Result<(), $crate::vm::errors::hint_errors::HintError> {
+                $(let $id = ids.$kind(stringify!($id), $vm)?;)*
+                $body
+            }
+        }
+    };
+}
+
+/// A hint declared with [define_hint](crate::define_hint).
+pub trait CustomHint: 'static {
+    /// The code of the hint, as written in the Cairo program
+    const CODE: &'static str;
+
+    fn run(
+        vm: &mut VirtualMachine,
+        exec_scopes: &mut ExecutionScopes,
+        ids: &HintIds,
+        constants: &Constants,
+    ) -> Result<(), HintError>;
+
+    /// Returns the hint as a [HintFunc], to add it to a [BuiltinHintProcessor].
+    fn hint_func() -> HintFunc {
+        HintFunc(Box::new(
+            |vm, exec_scopes, ids_data, ap_tracking, constants| {
+                Self::run(
+                    vm,
+                    exec_scopes,
+                    &HintIds::new(ids_data, ap_tracking),
+                    constants,
+                )
+            },
+        ))
+    }
+}
+
+/// The ids of a hint, resolved by name.
+pub struct HintIds<'a> {
+    ids_data: &'a HashMap<String, HintReference>,
+    ap_tracking: &'a ApTracking,
+}
+
+impl<'a> HintIds<'a> {
+    pub fn new(ids_data: &'a HashMap<String, HintReference>, ap_tracking: &'a ApTracking) -> Self {
+        HintIds {
+            ids_data,
+            ap_tracking,
+        }
+    }
+
+    pub fn felt(&self, name: &str, vm: &VirtualMachine) -> Result<Felt252, HintError> {
+        get_integer_from_var_name(name, vm, self.ids_data, self.ap_tracking).map(Cow::into_owned)
+    }
+
+    pub fn ptr(&self, name: &str, vm: &VirtualMachine) -> Result<Relocatable, HintError> {
+        get_ptr_from_var_name(name, vm, self.ids_data, self.ap_tracking)
+    }
+
+    pub fn addr(&self, name: &str, vm: &VirtualMachine) -> Result<Relocatable, HintError> {
+        get_relocatable_from_var_name(name, vm, self.ids_data, self.ap_tracking)
+    }
+
+    pub fn value(&self, name: &str, vm: &VirtualMachine) -> Result<MaybeRelocatable, HintError> {
+        get_maybe_relocatable_from_var_name(name, vm, self.ids_data, self.ap_tracking)
+    }
+}
+
+impl BuiltinHintProcessor {
+    /// Adds the hint `H`, run for the hints whose code is `H::CODE`.
+    pub fn add_custom_hint<H: CustomHint>(&mut self) {
+        self.add_hint(H::CODE.to_string(), Rc::new(H::hint_func()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        any_box,
+        hint_processor::{
+            builtin_hint_processor::builtin_hint_processor_definition::HintProcessorData,
+            hint_processor_definition::HintProcessorLogic,
+        },
+        utils::test_utils::*,
+    };
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    define_hint! {
+        /// Writes `ids.a` times `ids.p[0]` to `ids.res`, and counts its runs in the scope
+        struct MulByFirst = "ids.res = ids.a * ids.p[0]";
+        ids { a: felt, p: ptr, res: addr }
+        fn run(vm, exec_scopes, _constants) {
+            let first = vm.get_integer(p)?.into_owned();
+            vm.insert_value(res, a * first)?;
+            let runs = exec_scopes.get::<u64>("runs").unwrap_or_default();
+            exec_scopes.insert_value("runs", runs + 1);
+            Ok(())
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_defined_hint() {
+        let mut vm = vm!();
+        vm.segments = segments![((1, 0), 6), ((1, 1), (2, 0)), ((2, 0), 7)];
+        vm.run_context.fp = 3;
+        let ids_data = non_continuous_ids_data![("a", -3), ("p", -2), ("res", -1)];
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        hint_processor.add_custom_hint::<MulByFirst>();
+        let mut exec_scopes = ExecutionScopes::new();
+        let hint_data = HintProcessorData::new_default(MulByFirst::CODE.to_string(), ids_data);
+        assert_matches!(
+            hint_processor.execute_hint(
+                &mut vm,
+                &mut exec_scopes,
+                &any_box!(hint_data),
+                &Constants::new()
+            ),
+            Ok(())
+        );
+        check_memory![vm.segments.memory, ((1, 2), 42)];
+        assert_matches!(exec_scopes.get::<u64>("runs"), Ok(1));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn defined_hint_with_bad_ids() {
+        let mut vm = vm!();
+        // ids.a holds a pointer
+        vm.segments = segments![((1, 0), (2, 0)), ((1, 1), (2, 0))];
+        vm.run_context.fp = 3;
+        let ids_data = non_continuous_ids_data![("a", -3), ("p", -2), ("res", -1)];
+        assert_matches!(
+            MulByFirst::hint_func().0(
+                &mut vm,
+                &mut ExecutionScopes::new(),
+                &ids_data,
+                &ApTracking::default(),
+                &Constants::new()
+            ),
+            Err(HintError::IdentifierNotInteger(bx)) if bx.0 == "a"
+        );
+        let ids_data = non_continuous_ids_data![("p", -2), ("res", -1)];
+        assert_matches!(
+            MulByFirst::hint_func().0(
+                &mut vm,
+                &mut ExecutionScopes::new(),
+                &ids_data,
+                &ApTracking::default(),
+                &Constants::new()
+            ),
+            Err(HintError::UnknownIdentifier(name)) if name.as_ref() == "a"
+        );
+    }
+}
@@ -8,6 +8,7 @@ use crate::{
         hint_processor_definition::HintReference,
     },
     serde::deserialize_program::ApTracking,
+    types::exec_scope::ExecutionScopes,
     vm::{errors::hint_errors::HintError, vm_core::VirtualMachine},
 };
 use felt::Felt252;
@@ -104,14 +105,22 @@ pub fn random_ec_point_hint(
 //     ids.s.x, ids.s.y = random_ec_point(FIELD_PRIME, ALPHA, BETA, seed)"
 pub fn chained_ec_op_random_ec_point_hint(
     vm: &mut VirtualMachine,
+    exec_scopes: &ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
 ) -> Result<(), HintError> {
     let n_elms = get_integer_from_var_name("len", vm, ids_data, ap_tracking)?;
-    if n_elms.is_zero() || n_elms.to_usize().is_none() {
-        return Err(HintError::InvalidLenValue(Box::new(n_elms.into_owned())));
+    if let Ok(max_len) = exec_scopes.get::<Felt252>("__chained_ec_op_max_len") {
+        if n_elms.as_ref() > &max_len {
+            return Err(HintError::ChainedEcOpMaxLenExceeded(Box::new((
+                max_len,
+                n_elms.into_owned(),
+            ))));
+        }
     }
-    let n_elms = n_elms.to_usize().unwrap();
+    let n_elms = n_elms
+        .to_usize()
+        .ok_or_else(|| HintError::InvalidLenValue(Box::new(n_elms.into_owned())))?;
     let p = EcPoint::from_var_name("p", vm, ids_data, ap_tracking)?;
     let m = get_ptr_from_var_name("m", vm, ids_data, ap_tracking)?;
     let q = get_ptr_from_var_name("q", vm, ids_data, ap_tracking)?;
@@ -554,4 +563,55 @@ mod tests {
             .unwrap()
         );
     }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_chained_ec_op_random_ec_point_hint_empty() {
+        let hint_code = hint_code::CHAINED_EC_OP_RANDOM_EC_POINT;
+        let mut vm = vm!();
+        vm.run_context.fp = 6;
+        let ids_data =
+            non_continuous_ids_data![("p", -6), ("m", -4), ("q", -3), ("len", -2), ("s", -1)];
+        // p = (1, 2), m = [], q = [], len = 0
+        vm.segments = segments![
+            ((1, 0), 1),
+            ((1, 1), 2),
+            ((1, 2), (2, 0)),
+            ((1, 3), (3, 0)),
+            ((1, 4), 0)
+        ];
+        add_segments!(vm, 2);
+        assert_matches!(run_hint!(vm, ids_data, hint_code), Ok(()));
+        // The point only depends on p, as the seed of the ec_op hint without m and q
+        let seed: Vec<u8> = [Felt252::one(), Felt252::new(2)]
+            .iter()
+            .flat_map(|x| x.to_be_bytes())
+            .collect();
+        let (x, y) = random_ec_point_seeded(seed).unwrap();
+        assert_eq!(vm.get_integer((1, 5).into()).unwrap().as_ref(), &x);
+        assert_eq!(vm.get_integer((1, 6).into()).unwrap().as_ref(), &y);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_chained_ec_op_random_ec_point_hint_max_len_exceeded() {
+        let hint_code = hint_code::CHAINED_EC_OP_RANDOM_EC_POINT;
+        let mut vm = vm!();
+        vm.run_context.fp = 6;
+        let ids_data =
+            non_continuous_ids_data![("p", -6), ("m", -4), ("q", -3), ("len", -2), ("s", -1)];
+        vm.segments = segments![
+            ((1, 0), 1),
+            ((1, 1), 2),
+            ((1, 2), (2, 0)),
+            ((1, 3), (3, 0)),
+            ((1, 4), 3)
+        ];
+        let mut exec_scopes = scope![("__chained_ec_op_max_len", Felt252::new(2))];
+        assert_matches!(
+            run_hint!(vm, ids_data, hint_code, &mut exec_scopes),
+            Err(HintError::ChainedEcOpMaxLenExceeded(bx))
+                if *bx == (Felt252::new(2), Felt252::new(3))
+        );
+    }
 }
@@ -10,6 +10,8 @@ use crate::hint_processor::hint_processor_utils::{
     get_integer_from_reference, get_maybe_relocatable_from_reference,
 };
 use crate::serde::deserialize_program::ApTracking;
+use crate::types::constants::Constants;
+use crate::types::exec_scope::ExecutionScopes;
 use crate::types::relocatable::MaybeRelocatable;
 use crate::types::relocatable::Relocatable;
 use crate::vm::errors::hint_errors::HintError;
@@ -37,6 +39,18 @@ pub fn insert_value_into_ap(
         .map_err(HintError::Memory)
 }
 
+//Chooses the branch of the nondeterministic jump following the hint from the bool variable
+//var_name of the current scope: the jump is taken if it is true
+//See VirtualMachine::insert_nondet_jump
+pub fn insert_nondet_jump_from_scope(
+    vm: &mut VirtualMachine,
+    exec_scopes: &ExecutionScopes,
+    var_name: &str,
+) -> Result<(), HintError> {
+    let taken: bool = exec_scopes.get(var_name)?;
+    vm.insert_nondet_jump(taken).map_err(HintError::Internal)
+}
+
 //Returns the Relocatable value stored in the given ids variable
 pub fn get_ptr_from_var_name(
     var_name: &str,
@@ -125,12 +139,10 @@ pub fn get_reference_from_var_name<'a>(
 
 pub fn get_constant_from_var_name<'a>(
     var_name: &'static str,
-    constants: &'a HashMap<String, Felt252>,
+    constants: &'a Constants,
 ) -> Result<&'a Felt252, HintError> {
     constants
-        .iter()
-        .find(|(k, _)| k.rsplit('.').next() == Some(var_name))
-        .map(|(_, n)| n)
+        .get_by_name(var_name)
         .ok_or_else(|| HintError::MissingConstant(Box::new(var_name)))
 }
 
@@ -140,6 +152,7 @@ mod tests {
     use crate::stdlib::string::ToString;
 
     use crate::{
+        any_box,
         hint_processor::hint_processor_definition::HintReference,
         relocatable,
         serde::deserialize_program::OffsetValue,
@@ -151,6 +164,38 @@ mod tests {
     #[cfg(target_arch = "wasm32")]
     use wasm_bindgen_test::*;
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn insert_nondet_jump_from_scope_valid() {
+        let mut vm = vm!();
+        // jmp rel 3 if [ap] != 0
+        vm.segments = segments![((0, 0), 0x20680017fff8000_i64), ((0, 1), 3)];
+        vm.segments.add();
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.assign_or_update_variable("take_branch", any_box!(true));
+
+        assert_matches!(
+            insert_nondet_jump_from_scope(&mut vm, &exec_scopes, "take_branch"),
+            Ok(())
+        );
+        assert_eq!(
+            vm.get_maybe(&relocatable!(1, 0)),
+            Some(mayberelocatable!(1))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn insert_nondet_jump_from_scope_missing_variable() {
+        let mut vm = vm!();
+        let exec_scopes = ExecutionScopes::new();
+
+        assert_matches!(
+            insert_nondet_jump_from_scope(&mut vm, &exec_scopes, "take_branch"),
+            Err(HintError::VariableNotInScopeError(bx)) if bx.as_ref() == "take_branch"
+        );
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn get_ptr_from_var_name_immediate_value() {
@@ -9,7 +9,7 @@ use crate::{
         hint_processor_definition::HintReference,
     },
     serde::deserialize_program::ApTracking,
-    types::{exec_scope::ExecutionScopes, relocatable::Relocatable},
+    types::{constants::Constants, exec_scope::ExecutionScopes, relocatable::Relocatable},
     vm::{errors::hint_errors::HintError, vm_core::VirtualMachine},
 };
 use felt::Felt252;
@@ -228,7 +228,7 @@ pub fn split_n_bytes(
     vm: &mut VirtualMachine,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
-    constants: &HashMap<String, Felt252>,
+    constants: &Constants,
 ) -> Result<(), HintError> {
     let n_bytes =
         get_integer_from_var_name("n_bytes", vm, ids_data, ap_tracking).and_then(|x| {
@@ -358,7 +358,7 @@ mod tests {
                 ids_data,
                 hint_code::SPLIT_N_BYTES,
                 exec_scopes_ref!(),
-                &HashMap::from([(String::from(BYTES_IN_WORD), Felt252::from(8))])
+                &Constants::from([(String::from(BYTES_IN_WORD), Felt252::from(8))])
             ),
             Ok(())
         );
@@ -377,7 +377,7 @@ mod tests {
                 ids_data,
                 hint_code::SPLIT_OUTPUT_MID_LOW_HIGH,
                 exec_scopes_ref!(),
-                &HashMap::from([(String::from(BYTES_IN_WORD), Felt252::from(8))])
+                &Constants::from([(String::from(BYTES_IN_WORD), Felt252::from(8))])
             ),
             Ok(())
         );
@@ -23,7 +23,7 @@ use crate::{
     },
     math_utils::isqrt,
     serde::deserialize_program::ApTracking,
-    types::{exec_scope::ExecutionScopes, relocatable::MaybeRelocatable},
+    types::{constants::Constants, exec_scope::ExecutionScopes, relocatable::MaybeRelocatable},
     vm::{
         errors::{hint_errors::HintError, vm_errors::VirtualMachineError},
         vm_core::VirtualMachine,
@@ -104,7 +104,7 @@ pub fn assert_le_felt(
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
-    constants: &HashMap<String, Felt252>,
+    constants: &Constants,
 ) -> Result<(), HintError> {
     const PRIME_OVER_3_HIGH: &str = "starkware.cairo.common.math.assert_le_felt.PRIME_OVER_3_HIGH";
     const PRIME_OVER_2_HIGH: &str = "starkware.cairo.common.math.assert_le_felt.PRIME_OVER_2_HIGH";
@@ -563,7 +563,7 @@ pub fn assert_250_bit(
     vm: &mut VirtualMachine,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
-    constants: &HashMap<String, Felt252>,
+    constants: &Constants,
 ) -> Result<(), HintError> {
     const UPPER_BOUND: &str = "starkware.cairo.common.math.assert_250_bit.UPPER_BOUND";
     const SHIFT: &str = "starkware.cairo.common.math.assert_250_bit.SHIFT";
@@ -616,7 +616,7 @@ pub fn is_addr_bounded(
     vm: &mut VirtualMachine,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
-    constants: &HashMap<String, Felt252>,
+    constants: &Constants,
 ) -> Result<(), HintError> {
     let addr = get_integer_from_var_name("addr", vm, ids_data, ap_tracking)?;
     let prime = Felt252::prime();
@@ -954,7 +954,7 @@ mod tests {
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn run_assert_le_felt_valid() {
-        let mut constants = HashMap::new();
+        let mut constants = Constants::new();
         constants.insert(
             "starkware.cairo.common.math.assert_le_felt.PRIME_OVER_3_HIGH".to_string(),
             felt_str!("4000000000000088000000000000001", 16),
@@ -1146,7 +1146,7 @@ mod tests {
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn run_is_assert_le_felt_invalid() {
         let mut vm = vm_with_range_check!();
-        let mut constants = HashMap::new();
+        let mut constants = Constants::new();
         constants.insert(
             "starkware.cairo.common.math.assert_le_felt.PRIME_OVER_3_HIGH".to_string(),
             felt_str!("4000000000000088000000000000001", 16),
@@ -1173,7 +1173,7 @@ mod tests {
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn run_is_assert_le_felt_a_is_not_integer() {
         let mut vm = vm_with_range_check!();
-        let mut constants = HashMap::new();
+        let mut constants = Constants::new();
         constants.insert(
             "starkware.cairo.common.math.assert_le_felt.PRIME_OVER_3_HIGH".to_string(),
             felt_str!("4000000000000088000000000000001", 16),
@@ -1199,7 +1199,7 @@ mod tests {
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn run_is_assert_le_felt_b_is_not_integer() {
         let mut vm = vm_with_range_check!();
-        let mut constants = HashMap::new();
+        let mut constants = Constants::new();
         constants.insert(
             "starkware.cairo.common.math.assert_le_felt.PRIME_OVER_3_HIGH".to_string(),
             felt_str!("4000000000000088000000000000001", 16),
@@ -1897,7 +1897,7 @@ mod tests {
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn run_assert_250_bit_valid() {
         let hint_code = hint_code::ASSERT_250_BITS;
-        let constants = HashMap::from([
+        let constants = Constants::from([
             ("UPPER_BOUND".to_string(), Felt252::from(15)),
             ("SHIFT".to_string(), Felt252::from(5)),
         ]);
@@ -1922,7 +1922,7 @@ mod tests {
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn run_assert_250_bit_invalid() {
         let hint_code = hint_code::ASSERT_250_BITS;
-        let constants = HashMap::from([
+        let constants = Constants::from([
             ("UPPER_BOUND".to_string(), Felt252::from(15)),
             ("SHIFT".to_string(), Felt252::from(5)),
         ]);
@@ -2051,7 +2051,7 @@ mod tests {
                 ids_data,
                 hint_code,
                 exec_scopes_ref!(),
-                &HashMap::from([(ADDR_BOUND.to_string(), addr_bound)])
+                &Constants::from([(ADDR_BOUND.to_string(), addr_bound)])
             ),
             Err(HintError::AssertionFailed(bx))
                 if bx.as_ref() == "normalize_address() cannot be used with the current constants."
@@ -3,6 +3,7 @@ pub mod blake2s_hash;
 pub mod blake2s_utils;
 pub mod builtin_hint_processor_definition;
 pub mod cairo_keccak;
+pub mod custom_hints;
 pub mod dict_hint_utils;
 pub mod dict_manager;
 pub mod ec_recover;
@@ -16,6 +17,7 @@ pub mod keccak_utils;
 pub mod math_utils;
 pub mod memcpy_hint_utils;
 pub mod memset_utils;
+pub mod oracle;
 pub mod poseidon_utils;
 pub mod pow_utils;
 pub mod secp;
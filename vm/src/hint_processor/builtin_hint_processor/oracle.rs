@@ -0,0 +1,437 @@
+//! Hints answering the nondeterministic choices of a program from outside the VM.
+//!
+//! Programs choosing witness values in hints can be driven by an [Oracle] instead of a custom
+//! hint for each choice: the hint asks the oracle for the values of a label, and the
+//! [BuiltinHintProcessor](super::builtin_hint_processor_definition::BuiltinHintProcessor)
+//! writes its answer. The oracle of a run is set with [ExecutionScopes::set_oracle]. Oracle hints
+//! are made of lines of the following forms:
+//! ```text
+//! ids.x = oracle("label")
+//! segments.write_arg(ids.ptr, oracle("label"))
+//! ```
+//! The first assigns an answer of a single felt to `ids.x`, the second writes the felts of the
+//! answer from the address `ids.ptr` points to.
+
+use crate::stdlib::{
+    collections::{HashMap, VecDeque},
+    prelude::*,
+};
+
+use felt::Felt252;
+use num_traits::Num;
+
+use crate::{
+    hint_processor::{
+        builtin_hint_processor::hint_utils::{get_ptr_from_var_name, insert_value_from_var_name},
+        hint_processor_definition::HintReference,
+    },
+    serde::deserialize_program::ApTracking,
+    types::{exec_scope::ExecutionScopes, relocatable::MaybeRelocatable},
+    vm::{errors::hint_errors::HintError, vm_core::VirtualMachine},
+};
+
+/// Name of the main scope variable holding the [Oracle] of a run
+pub const ORACLE: &str = "__oracle";
+
+/// A source of the values chosen by oracle hints.
+pub trait Oracle {
+    /// Returns the next answer for `label`.
+    fn query(&mut self, label: &str) -> Result<Vec<Felt252>, HintError>;
+}
+
+impl<F: FnMut(&str) -> Result<Vec<Felt252>, HintError>> Oracle for F {
+    fn query(&mut self, label: &str) -> Result<Vec<Felt252>, HintError> {
+        self(label)
+    }
+}
+
+/// An [Oracle] replaying answers known in advance, in order for each label.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AnswersOracle {
+    answers: HashMap<String, VecDeque<Vec<Felt252>>>,
+}
+
+impl AnswersOracle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds `answer` after the answers for `label` added before.
+    pub fn push(&mut self, label: &str, answer: Vec<Felt252>) {
+        self.answers
+            .entry(label.to_string())
+            .or_default()
+            .push_back(answer);
+    }
+
+    /// Parses a JSON object mapping each label to the list of its answers, each either a felt or
+    /// an array of felts. Felts are numbers, or strings in decimal or `0x` prefixed hex:
+    /// ```json
+    /// {"witness": [3, ["0x10", 2]]}
+    /// ```
+    pub fn from_json(json: &str) -> Result<Self, HintError> {
+        let invalid = |error: &dyn core::fmt::Display| {
+            HintError::InvalidOracleAnswer(Box::new((String::from("<json>"), error.to_string())))
+        };
+        let labels: HashMap<String, Vec<serde_json::Value>> =
+            serde_json::from_str(json).map_err(|error| invalid(&error))?;
+        let mut oracle = AnswersOracle::new();
+        for (label, answers) in labels {
+            for answer in answers {
+                let felts = match answer {
+                    serde_json::Value::Array(values) => values
+                        .iter()
+                        .map(|value| json_felt(&label, value))
+                        .collect::<Result<Vec<_>, _>>()?,
+                    value => vec![json_felt(&label, &value)?],
+                };
+                oracle.push(&label, felts);
+            }
+        }
+        Ok(oracle)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn from_file(path: &std::path::Path) -> Result<Self, HintError> {
+        let json = std::fs::read_to_string(path).map_err(|error| {
+            HintError::InvalidOracleAnswer(Box::new((
+                path.display().to_string(),
+                error.to_string(),
+            )))
+        })?;
+        Self::from_json(&json)
+    }
+}
+
+impl Oracle for AnswersOracle {
+    fn query(&mut self, label: &str) -> Result<Vec<Felt252>, HintError> {
+        self.answers
+            .get_mut(label)
+            .and_then(VecDeque::pop_front)
+            .ok_or_else(|| HintError::OracleNoAnswer(label.to_string().into_boxed_str()))
+    }
+}
+
+fn json_felt(label: &str, value: &serde_json::Value) -> Result<Felt252, HintError> {
+    let felt = match value {
+        serde_json::Value::Number(number) => parse_felt(&number.to_string()),
+        serde_json::Value::String(string) => parse_felt(string),
+        _ => None,
+    };
+    felt.ok_or_else(|| {
+        HintError::InvalidOracleAnswer(Box::new((label.to_string(), value.to_string())))
+    })
+}
+
+// Parses a felt in decimal or `0x` prefixed hex
+fn parse_felt(string: &str) -> Option<Felt252> {
+    match string.strip_prefix("0x") {
+        Some(hex) => Felt252::from_str_radix(hex, 16).ok(),
+        None => Felt252::from_str_radix(string, 10).ok(),
+    }
+}
+
+/// An [Oracle] asking for each answer interactively: it writes the label to `prompt` and reads a
+/// line of whitespace separated felts (in decimal or `0x` prefixed hex) from `input`.
+#[cfg(feature = "std")]
+pub struct ReaderOracle<R, W> {
+    input: R,
+    prompt: W,
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::BufRead, W: std::io::Write> ReaderOracle<R, W> {
+    pub fn new(input: R, prompt: W) -> Self {
+        ReaderOracle { input, prompt }
+    }
+}
+
+#[cfg(feature = "std")]
+impl ReaderOracle<std::io::StdinLock<'static>, std::io::Stderr> {
+    /// Reads the answers from stdin, prompting for them on stderr so they don't mix with the
+    /// output of the run.
+    pub fn stdin() -> Self {
+        ReaderOracle::new(std::io::stdin().lock(), std::io::stderr())
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R: std::io::BufRead, W: std::io::Write> Oracle for ReaderOracle<R, W> {
+    fn query(&mut self, label: &str) -> Result<Vec<Felt252>, HintError> {
+        let io_error = |error: std::io::Error| {
+            HintError::InvalidOracleAnswer(Box::new((label.to_string(), error.to_string())))
+        };
+        write!(self.prompt, "{label}: ").map_err(io_error)?;
+        self.prompt.flush().map_err(io_error)?;
+        let mut line = String::new();
+        if self.input.read_line(&mut line).map_err(io_error)? == 0 {
+            return Err(HintError::OracleNoAnswer(
+                label.to_string().into_boxed_str(),
+            ));
+        }
+        line.split_whitespace()
+            .map(|word| {
+                parse_felt(word).ok_or_else(|| {
+                    HintError::InvalidOracleAnswer(Box::new((label.to_string(), word.to_string())))
+                })
+            })
+            .collect()
+    }
+}
+
+impl ExecutionScopes {
+    /// Makes `oracle` answer the oracle hints of the run, through the main scope.
+    pub fn set_oracle(&mut self, oracle: impl Oracle + 'static) {
+        if let Some(main_scope) = self.data.first_mut() {
+            let oracle: Box<dyn Oracle> = Box::new(oracle);
+            main_scope.insert(ORACLE.to_string(), Box::new(oracle));
+        }
+    }
+
+    pub fn oracle_mut(&mut self) -> Option<&mut dyn Oracle> {
+        let oracle: &mut Box<dyn Oracle> =
+            self.data.first_mut()?.get_mut(ORACLE)?.downcast_mut()?;
+        Some(oracle.as_mut())
+    }
+}
+
+/// A line of an oracle hint.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) enum OracleRequest<'a> {
+    /// `ids.<var_name> = oracle("<label>")`
+    Assign { var_name: &'a str, label: &'a str },
+    /// `segments.write_arg(ids.<var_name>, oracle("<label>"))`
+    WriteArg { var_name: &'a str, label: &'a str },
+}
+
+/// Parses the lines of an oracle hint, None if `code` isn't one.
+pub(crate) fn parse_oracle_hint(code: &str) -> Option<Vec<OracleRequest<'_>>> {
+    let requests = code
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            if let Some(args) = line
+                .strip_prefix("segments.write_arg(ids.")
+                .and_then(|args| args.strip_suffix(')'))
+            {
+                let (var_name, call) = args.split_once(',')?;
+                return Some(OracleRequest::WriteArg {
+                    var_name: parse_var_name(var_name)?,
+                    label: parse_oracle_call(call)?,
+                });
+            }
+            let (var_name, call) = line.strip_prefix("ids.")?.split_once('=')?;
+            Some(OracleRequest::Assign {
+                var_name: parse_var_name(var_name)?,
+                label: parse_oracle_call(call)?,
+            })
+        })
+        .collect::<Option<Vec<_>>>()?;
+    (!requests.is_empty()).then_some(requests)
+}
+
+fn parse_var_name(var_name: &str) -> Option<&str> {
+    let var_name = var_name.trim();
+    (!var_name.is_empty()
+        && var_name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_'))
+    .then_some(var_name)
+}
+
+// Parses `oracle("<label>")` or `oracle('<label>')` into the label
+fn parse_oracle_call(call: &str) -> Option<&str> {
+    let label = call
+        .trim()
+        .strip_prefix("oracle(")?
+        .strip_suffix(')')?
+        .trim();
+    label
+        .strip_prefix('"')
+        .and_then(|label| label.strip_suffix('"'))
+        .or_else(|| {
+            label
+                .strip_prefix('\'')
+                .and_then(|label| label.strip_suffix('\''))
+        })
+}
+
+/// Asks the oracle of the run for the answer of each request, and writes it.
+pub(crate) fn execute_oracle_hint(
+    vm: &mut VirtualMachine,
+    exec_scopes: &mut ExecutionScopes,
+    requests: &[OracleRequest],
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError> {
+    for request in requests {
+        let (OracleRequest::Assign { label, .. } | OracleRequest::WriteArg { label, .. }) = request;
+        let answer = exec_scopes
+            .oracle_mut()
+            .ok_or_else(|| HintError::MissingOracle(label.to_string().into_boxed_str()))?
+            .query(label)?;
+        match request {
+            OracleRequest::Assign { var_name, .. } => {
+                let [value] = <[Felt252; 1]>::try_from(answer).map_err(|answer| {
+                    HintError::InvalidOracleAnswer(Box::new((
+                        label.to_string(),
+                        format!("expected a single felt, got {} felts", answer.len()),
+                    )))
+                })?;
+                insert_value_from_var_name(var_name, value, vm, ids_data, ap_tracking)?;
+            }
+            OracleRequest::WriteArg { var_name, .. } => {
+                let ptr = get_ptr_from_var_name(var_name, vm, ids_data, ap_tracking)?;
+                let values: Vec<MaybeRelocatable> =
+                    answer.into_iter().map(MaybeRelocatable::from).collect();
+                vm.load_data(ptr, &values).map_err(HintError::Memory)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        any_box,
+        hint_processor::{
+            builtin_hint_processor::builtin_hint_processor_definition::{
+                BuiltinHintProcessor, HintProcessorData,
+            },
+            hint_processor_definition::HintProcessorLogic,
+        },
+        types::relocatable::Relocatable,
+        utils::test_utils::*,
+    };
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn parse_oracle_hints() {
+        assert_eq!(
+            parse_oracle_hint(
+                "ids.x = oracle(\"witness\")\nsegments.write_arg(ids.arr, oracle('values'))"
+            ),
+            Some(vec![
+                OracleRequest::Assign {
+                    var_name: "x",
+                    label: "witness"
+                },
+                OracleRequest::WriteArg {
+                    var_name: "arr",
+                    label: "values"
+                },
+            ])
+        );
+        assert_eq!(parse_oracle_hint("ids.x = oracle(witness)"), None);
+        assert_eq!(parse_oracle_hint("ids.x = 5"), None);
+        assert_eq!(parse_oracle_hint(""), None);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn answers_oracle_from_json() {
+        let mut oracle =
+            AnswersOracle::from_json(r#"{"witness": [3, ["0x10", "2"]], "empty": []}"#).unwrap();
+
+        assert_matches!(oracle.query("witness"), Ok(answer) if answer == vec![Felt252::new(3)]);
+        assert_matches!(
+            oracle.query("witness"),
+            Ok(answer) if answer == vec![Felt252::new(16), Felt252::new(2)]
+        );
+        assert_matches!(
+            oracle.query("witness"),
+            Err(HintError::OracleNoAnswer(label)) if label.as_ref() == "witness"
+        );
+        assert_matches!(oracle.query("empty"), Err(HintError::OracleNoAnswer(_)));
+        assert_matches!(
+            AnswersOracle::from_json(r#"{"witness": [true]}"#),
+            Err(HintError::InvalidOracleAnswer(bx)) if bx.0 == "witness"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn reader_oracle_prompts_for_answers() {
+        let mut prompt = Vec::new();
+        let mut oracle = ReaderOracle::new("1 0x2\n".as_bytes(), &mut prompt);
+
+        assert_matches!(
+            oracle.query("witness"),
+            Ok(answer) if answer == vec![Felt252::new(1), Felt252::new(2)]
+        );
+        assert_matches!(oracle.query("witness"), Err(HintError::OracleNoAnswer(_)));
+        assert_eq!(prompt, b"witness: witness: ");
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_oracle_hint() {
+        let mut vm = vm!();
+        vm.run_context.fp = 2;
+        vm.segments = segments![((1, 1), (2, 0))];
+        vm.segments.add();
+        let ids_data = ids_data!["x", "arr"];
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.set_oracle(|label: &str| -> Result<Vec<Felt252>, HintError> {
+            match label {
+                "witness" => Ok(vec![Felt252::new(7)]),
+                _ => Ok(vec![Felt252::new(1), Felt252::new(2)]),
+            }
+        });
+
+        assert_matches!(
+            run_hint!(
+                vm,
+                ids_data,
+                "ids.x = oracle(\"witness\")\nsegments.write_arg(ids.arr, oracle(\"values\"))",
+                &mut exec_scopes
+            ),
+            Ok(())
+        );
+        check_memory![
+            vm.segments.memory,
+            ((1, 0), 7),
+            ((1, 1), (2, 0)),
+            ((2, 0), 1),
+            ((2, 1), 2)
+        ];
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_oracle_hint_without_oracle() {
+        let mut vm = vm!();
+        vm.run_context.fp = 1;
+        vm.segments.add();
+        vm.segments.add();
+
+        assert_matches!(
+            run_hint!(vm, ids_data!["x"], "ids.x = oracle(\"witness\")"),
+            Err(HintError::MissingOracle(label)) if label.as_ref() == "witness"
+        );
+        assert_eq!(vm.segments.memory.get(&Relocatable::from((1, 0))), None);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_oracle_hint_wrong_answer_length() {
+        let mut vm = vm!();
+        vm.run_context.fp = 1;
+        vm.segments.add();
+        vm.segments.add();
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.set_oracle(|_: &str| -> Result<Vec<Felt252>, HintError> { Ok(Vec::new()) });
+
+        assert_matches!(
+            run_hint!(vm, ids_data!["x"], "ids.x = oracle(\"witness\")", &mut exec_scopes),
+            Err(HintError::InvalidOracleAnswer(bx)) if bx.0 == "witness"
+        );
+    }
+}
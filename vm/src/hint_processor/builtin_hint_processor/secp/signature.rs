@@ -10,11 +10,10 @@ use crate::{
     math_utils::{div_mod, safe_div_bigint},
     serde::deserialize_program::ApTracking,
     stdlib::{collections::HashMap, ops::Shr, prelude::*},
-    types::exec_scope::ExecutionScopes,
+    types::{constants::Constants, exec_scope::ExecutionScopes},
     vm::{errors::hint_errors::HintError, vm_core::VirtualMachine},
 };
 use core::ops::Add;
-use felt::Felt252;
 use num_bigint::BigInt;
 use num_integer::Integer;
 
@@ -109,7 +108,7 @@ pub fn get_point_from_x(
     exec_scopes: &mut ExecutionScopes,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
-    constants: &HashMap<String, Felt252>,
+    constants: &Constants,
 ) -> Result<(), HintError> {
     exec_scopes.insert_value("SECP_P", SECP_P.clone());
     #[allow(deprecated)]
@@ -178,6 +177,7 @@ mod tests {
         utils::test_utils::*,
     };
     use assert_matches::assert_matches;
+    use felt::Felt252;
     use num_traits::{One, Zero};
 
     #[cfg(target_arch = "wasm32")]
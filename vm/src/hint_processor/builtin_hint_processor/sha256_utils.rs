@@ -8,7 +8,7 @@ use crate::{
         hint_processor_utils::felt_to_u32,
     },
     serde::deserialize_program::ApTracking,
-    types::relocatable::MaybeRelocatable,
+    types::{constants::Constants, relocatable::MaybeRelocatable},
     vm::errors::{hint_errors::HintError, vm_errors::VirtualMachineError},
     vm::vm_core::VirtualMachine,
 };
@@ -53,7 +53,7 @@ fn sha256_main(
     vm: &mut VirtualMachine,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
-    constants: &HashMap<String, Felt252>,
+    constants: &Constants,
     iv: &mut [u32; 8],
 ) -> Result<(), HintError> {
     let input_ptr = get_ptr_from_var_name("sha256_start", vm, ids_data, ap_tracking)?;
@@ -113,7 +113,7 @@ pub fn sha256_main_constant_input_length(
     vm: &mut VirtualMachine,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
-    constants: &HashMap<String, Felt252>,
+    constants: &Constants,
 ) -> Result<(), HintError> {
     let mut iv = IV;
     sha256_main(vm, ids_data, ap_tracking, constants, &mut iv)
@@ -136,7 +136,7 @@ pub fn sha256_main_arbitrary_input_length(
     vm: &mut VirtualMachine,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
-    constants: &HashMap<String, Felt252>,
+    constants: &Constants,
 ) -> Result<(), HintError> {
     let iv_ptr = get_ptr_from_var_name("state", vm, ids_data, ap_tracking)?;
 
@@ -288,7 +288,7 @@ mod tests {
         ];
         vm.run_context.fp = 2;
         let ids_data = ids_data!["sha256_start", "output"];
-        let constants = HashMap::from([(
+        let constants = Constants::from([(
             "SHA256_INPUT_CHUNK_SIZE_FELTS".to_string(),
             Felt252::from(SHA256_INPUT_CHUNK_SIZE_FELTS),
         )]);
@@ -348,7 +348,7 @@ mod tests {
         ];
         vm.run_context.fp = 3;
         let ids_data = ids_data!["sha256_start", "output", "state"];
-        let constants = HashMap::from([
+        let constants = Constants::from([
             (
                 "SHA256_INPUT_CHUNK_SIZE_FELTS".to_string(),
                 Felt252::from(SHA256_INPUT_CHUNK_SIZE_FELTS),
@@ -397,7 +397,7 @@ mod tests {
         ];
         vm.run_context.fp = 3;
         let ids_data = ids_data!["sha256_start", "output", "state"];
-        let constants = HashMap::from([
+        let constants = Constants::from([
             (
                 "SHA256_INPUT_CHUNK_SIZE_FELTS".to_string(),
                 Felt252::from(100),
@@ -434,7 +434,7 @@ mod tests {
         ];
         vm.run_context.fp = 3;
         let ids_data = ids_data!["sha256_start", "output", "state"];
-        let constants = HashMap::from([
+        let constants = Constants::from([
             (
                 "SHA256_INPUT_CHUNK_SIZE_FELTS".to_string(),
                 Felt252::from(SHA256_INPUT_CHUNK_SIZE_FELTS),
@@ -469,7 +469,7 @@ mod tests {
         ];
         vm.run_context.fp = 3;
         let ids_data = ids_data!["sha256_start", "output", "state"];
-        let constants = HashMap::from([
+        let constants = Constants::from([
             (
                 "SHA256_INPUT_CHUNK_SIZE_FELTS".to_string(),
                 Felt252::from(SHA256_INPUT_CHUNK_SIZE_FELTS),
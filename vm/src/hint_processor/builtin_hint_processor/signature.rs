@@ -1,5 +1,6 @@
 use crate::stdlib::{boxed::Box, collections::HashMap, prelude::*};
 
+#[allow(unused_imports)]
 use num_integer::Integer;
 
 use crate::{
@@ -31,7 +32,7 @@ pub fn verify_ecdsa_signature(
     }
     if !ecdsa_ptr
         .offset
-        .is_multiple_of(&(CELLS_PER_SIGNATURE as usize))
+        .is_multiple_of(CELLS_PER_SIGNATURE as usize)
     {
         return Err(HintError::AddSignatureNotAPublicKey(Box::new(ecdsa_ptr)));
     }
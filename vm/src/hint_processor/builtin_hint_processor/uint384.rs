@@ -4,6 +4,7 @@ use num_traits::Zero;
 
 use crate::math_utils::isqrt;
 use crate::stdlib::{boxed::Box, collections::HashMap, prelude::*};
+use crate::types::constants::Constants;
 use crate::types::errors::math_errors::MathError;
 use crate::{
     hint_processor::hint_processor_definition::HintReference,
@@ -104,7 +105,7 @@ pub fn add_no_uint384_check(
     vm: &mut VirtualMachine,
     ids_data: &HashMap<String, HintReference>,
     ap_tracking: &ApTracking,
-    constants: &HashMap<String, Felt252>,
+    constants: &Constants,
 ) -> Result<(), HintError> {
     let a = Uint384::from_var_name("a", vm, ids_data, ap_tracking)?;
     let b = Uint384::from_var_name("b", vm, ids_data, ap_tracking)?;
@@ -5,6 +5,7 @@ use crate::felt::{felt_str, Felt252};
 use crate::hint_processor::cairo_1_hint_processor::dict_manager::DictSquashExecScope;
 use crate::hint_processor::hint_processor_definition::HintReference;
 use crate::stdlib::{boxed::Box, collections::HashMap, prelude::*};
+use crate::types::constants::Constants;
 use crate::types::relocatable::Relocatable;
 use crate::vm::runners::cairo_runner::ResourceTracker;
 use crate::vm::runners::cairo_runner::RunResources;
@@ -60,6 +61,48 @@ impl Cairo1HintProcessor {
             run_resources,
         }
     }
+    /// Returns true if `hint` is implemented by [execute](Self::execute), which must be kept in
+    /// sync with it, as the tests check. Hints are classified by their variant, without running
+    /// them.
+    pub fn supports_hint(hint: &Hint) -> bool {
+        matches!(
+            hint,
+            Hint::Core(CoreHintBase::Core(
+                CoreHint::AllocSegment { .. }
+                    | CoreHint::TestLessThan { .. }
+                    | CoreHint::TestLessThanOrEqual { .. }
+                    | CoreHint::SquareRoot { .. }
+                    | CoreHint::GetSegmentArenaIndex { .. }
+                    | CoreHint::DivMod { .. }
+                    | CoreHint::DebugPrint { .. }
+                    | CoreHint::Uint256SquareRoot { .. }
+                    | CoreHint::GetNextDictKey { .. }
+                    | CoreHint::Uint256DivMod { .. }
+                    | CoreHint::AssertLeIsFirstArcExcluded { .. }
+                    | CoreHint::AssertLeIsSecondArcExcluded { .. }
+                    | CoreHint::LinearSplit { .. }
+                    | CoreHint::AllocFelt252Dict { .. }
+                    | CoreHint::AssertLeFindSmallArcs { .. }
+                    | CoreHint::RandomEcPoint { .. }
+                    | CoreHint::ShouldSkipSquashLoop { .. }
+                    | CoreHint::Felt252DictEntryInit { .. }
+                    | CoreHint::Felt252DictEntryUpdate { .. }
+                    | CoreHint::GetCurrentAccessDelta { .. }
+                    | CoreHint::InitSquashData { .. }
+                    | CoreHint::AllocConstantSize { .. }
+                    | CoreHint::GetCurrentAccessIndex { .. }
+                    | CoreHint::ShouldContinueSquashLoop { .. }
+                    | CoreHint::FieldSqrt { .. }
+                    | CoreHint::WideMul128 { .. }
+                    | CoreHint::Uint512DivModByUint256 { .. }
+            )) | Hint::Core(CoreHintBase::Deprecated(
+                DeprecatedHint::Felt252DictRead { .. }
+                    | DeprecatedHint::Felt252DictWrite { .. }
+                    | DeprecatedHint::AssertAllAccessesUsed { .. }
+            ))
+        )
+    }
+
     // Runs a single Hint
     pub fn execute(
         &self,
@@ -1133,7 +1176,7 @@ impl HintProcessorLogic for Cairo1HintProcessor {
         //Data structure that can be downcasted to the structure generated by compile_hint
         hint_data: &Box<dyn Any>,
         //Constant values extracted from the program specification.
-        _constants: &HashMap<String, Felt252>,
+        _constants: &Constants,
     ) -> Result<(), HintError> {
         let hints: &Vec<Hint> = hint_data.downcast_ref().ok_or(HintError::WrongHintData)?;
         for hint in hints {
@@ -1160,3 +1203,37 @@ impl ResourceTracker for Cairo1HintProcessor {
         &self.run_resources
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::stdlib::collections::HashSet;
+
+    // Returns the names of the hint variants used in `source`
+    fn hint_variants(source: &str) -> HashSet<&str> {
+        ["CoreHint::", "DeprecatedHint::"]
+            .iter()
+            .flat_map(|prefix| source.split(prefix).skip(1))
+            .filter_map(|rest| {
+                rest.split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                    .next()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn supported_hints_are_the_executed_ones() {
+        // Both are matches on the variants of the hints, so they are compared from the source
+        let source = include_str!("hint_processor.rs");
+        let section = |start: &str, end: &str| {
+            let start = source.find(start).unwrap();
+            &source[start..start + source[start..].find(end).unwrap()]
+        };
+        let supported = hint_variants(section("pub fn supports_hint", "// Runs a single Hint"));
+        let executed = hint_variants(section(
+            "pub fn execute(",
+            "hint => Err(HintError::UnknownHint(",
+        ));
+        assert!(!executed.is_empty());
+        assert_eq!(supported, executed);
+    }
+}
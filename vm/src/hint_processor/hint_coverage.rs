@@ -0,0 +1,199 @@
+//! Static reports of the hints a program needs and whether they are supported.
+//!
+//! Running a program stops at the first hint the hint processor doesn't know. These reports scan
+//! every hint of a program beforehand instead, so all the unsupported ones can be listed at once.
+
+use crate::stdlib::prelude::*;
+
+use core::fmt;
+
+use crate::{
+    hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+    types::program::Program,
+};
+
+#[cfg(feature = "cairo-1-hints")]
+use crate::hint_processor::cairo_1_hint_processor::hint_processor::Cairo1HintProcessor;
+#[cfg(feature = "cairo-1-hints")]
+use cairo_lang_starknet::casm_contract_class::CasmContractClass;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HintSupport {
+    /// Implemented by the [BuiltinHintProcessor], either natively or as an extra hint
+    Builtin,
+    /// Implemented by the `Cairo1HintProcessor`
+    Cairo1,
+    Unknown,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HintCoverageEntry {
+    pub pc: usize,
+    pub code: String,
+    pub support: HintSupport,
+}
+
+/// Classification of every hint of a program, sorted by pc.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HintCoverage {
+    pub entries: Vec<HintCoverageEntry>,
+}
+
+impl HintCoverage {
+    pub fn unknown_hints(&self) -> impl Iterator<Item = &HintCoverageEntry> {
+        self.entries
+            .iter()
+            .filter(|entry| entry.support == HintSupport::Unknown)
+    }
+
+    pub fn is_fully_supported(&self) -> bool {
+        self.unknown_hints().next().is_none()
+    }
+
+    /// Scans the hints of a cairo 0 program.
+    ///
+    /// Programs built from a `CasmContractClass` only store the pcs of their hints, use
+    /// [HintCoverage::from_casm_contract_class] for those instead.
+    pub fn from_program(program: &Program, hint_processor: &BuiltinHintProcessor) -> Self {
+        let mut entries: Vec<_> = program
+            .shared_program_data
+            .hints
+            .iter()
+            .flat_map(|(pc, hints)| {
+                hints.iter().map(|hint| HintCoverageEntry {
+                    pc: *pc,
+                    code: hint.code.clone(),
+                    support: if hint_processor.supports_hint(&hint.code) {
+                        HintSupport::Builtin
+                    } else {
+                        HintSupport::Unknown
+                    },
+                })
+            })
+            .collect();
+        // Hints at the same pc keep their order, as they are executed in sequence
+        entries.sort_by_key(|entry| entry.pc);
+        HintCoverage { entries }
+    }
+
+    /// Scans the hints of a cairo 1 contract class.
+    #[cfg(feature = "cairo-1-hints")]
+    pub fn from_casm_contract_class(contract_class: &CasmContractClass) -> Self {
+        let mut entries: Vec<_> = contract_class
+            .hints
+            .iter()
+            .flat_map(|(pc, hints)| {
+                hints.iter().map(move |hint| HintCoverageEntry {
+                    pc: *pc,
+                    code: format!("{:?}", hint),
+                    support: if Cairo1HintProcessor::supports_hint(hint) {
+                        HintSupport::Cairo1
+                    } else {
+                        HintSupport::Unknown
+                    },
+                })
+            })
+            .collect();
+        entries.sort_by_key(|entry| entry.pc);
+        HintCoverage { entries }
+    }
+}
+
+impl fmt::Display for HintCoverage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let unknown = self.unknown_hints().count();
+        writeln!(
+            f,
+            "{} hints, {} supported, {} unknown",
+            self.entries.len(),
+            self.entries.len() - unknown,
+            unknown
+        )?;
+        for entry in self.unknown_hints() {
+            writeln!(f, "\npc {}:\n{}", entry.pc, entry.code)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stdlib::collections::HashMap;
+    use crate::{
+        hint_processor::builtin_hint_processor::{
+            builtin_hint_processor_definition::HintFunc, hint_code,
+        },
+        serde::deserialize_program::{ApTracking, FlowTrackingData, HintParams},
+        stdlib::rc::Rc,
+        utils::test_utils::*,
+    };
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    fn hint_params(code: &str) -> HintParams {
+        HintParams {
+            code: code.to_string(),
+            accessible_scopes: Vec::new(),
+            flow_tracking_data: FlowTrackingData {
+                ap_tracking: ApTracking::default(),
+                reference_ids: HashMap::new(),
+            },
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn program_hint_coverage() {
+        let program = program!(
+            hints = HashMap::from([
+                (4, vec![hint_params("custom_hint")]),
+                (
+                    0,
+                    vec![hint_params(hint_code::ADD_SEGMENT), hint_params("unknown")]
+                ),
+            ]),
+        );
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let coverage = HintCoverage::from_program(&program, &hint_processor);
+
+        assert_eq!(
+            coverage
+                .entries
+                .iter()
+                .map(|entry| (entry.pc, entry.support))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, HintSupport::Builtin),
+                (0, HintSupport::Unknown),
+                (4, HintSupport::Unknown)
+            ]
+        );
+        assert_eq!(
+            coverage.to_string(),
+            "3 hints, 1 supported, 2 unknown\n\npc 0:\nunknown\n\npc 4:\ncustom_hint\n"
+        );
+
+        let hint_func = HintFunc(Box::new(|_, _, _, _, _| Ok(())));
+        hint_processor.add_hint(String::from("custom_hint"), Rc::new(hint_func));
+        let coverage = HintCoverage::from_program(&program, &hint_processor);
+        assert_eq!(
+            coverage
+                .unknown_hints()
+                .map(|entry| entry.code.as_str())
+                .collect::<Vec<_>>(),
+            vec!["unknown"]
+        );
+        assert!(!coverage.is_fully_supported());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn program_without_hints_is_fully_supported() {
+        let program = program!();
+        let coverage = HintCoverage::from_program(&program, &BuiltinHintProcessor::new_empty());
+        assert!(coverage.is_fully_supported());
+        assert_eq!(coverage.to_string(), "0 hints, 0 supported, 0 unknown\n");
+    }
+}
@@ -1,18 +1,25 @@
-use crate::stdlib::{any::Any, boxed::Box, collections::HashMap, prelude::*};
+use crate::stdlib::{
+    any::Any,
+    boxed::Box,
+    collections::{HashMap, HashSet},
+    prelude::*,
+};
 
 use crate::any_box;
 use crate::serde::deserialize_program::ApTracking;
 use crate::serde::deserialize_program::OffsetValue;
 use crate::serde::deserialize_program::Reference;
+use crate::types::constants::Constants;
 use crate::types::exec_scope::ExecutionScopes;
 use crate::types::instruction::Register;
 use crate::vm::errors::hint_errors::HintError;
 use crate::vm::errors::vm_errors::VirtualMachineError;
+use crate::vm::parallel_hints::ParallelHint;
 use crate::vm::runners::cairo_runner::ResourceTracker;
 use crate::vm::vm_core::VirtualMachine;
 
 use super::builtin_hint_processor::builtin_hint_processor_definition::HintProcessorData;
-use felt::Felt252;
+use super::hint_processor_utils::compute_addr_from_reference;
 
 pub trait HintProcessorLogic {
     //Executes the hint which's data is provided by a dynamic structure previously created by compile_hint
@@ -27,7 +34,7 @@ pub trait HintProcessorLogic {
         //Data structure that can be downcasted to the structure generated by compile_hint
         hint_data: &Box<dyn Any>,
         //Constant values extracted from the program specification.
-        constants: &HashMap<String, Felt252>,
+        constants: &Constants,
     ) -> Result<(), HintError>;
 
     //Transforms hint data outputed by the VM into whichever format will be later used by execute_hint
@@ -47,8 +54,104 @@ pub trait HintProcessorLogic {
             code: hint_code.to_string(),
             ap_tracking: ap_tracking_data.clone(),
             ids_data: get_ids_data(reference_ids, references)?,
+            accessible_scopes: Vec::new(),
         }))
     }
+
+    /// Transforms the hint data like [compile_hint](Self::compile_hint), also given the
+    /// `accessible_scopes` of the hint, the scope paths of the module and functions it runs in.
+    ///
+    /// The default implementation records the scopes in the data built by the default
+    /// [compile_hint](Self::compile_hint), to dispatch hints registered for a scope, see
+    /// [BuiltinHintProcessor::add_scoped_hint](super::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor::add_scoped_hint).
+    fn compile_hint_with_scopes(
+        &self,
+        hint_code: &str,
+        accessible_scopes: &[String],
+        ap_tracking_data: &ApTracking,
+        reference_ids: &HashMap<String, usize>,
+        references: &[HintReference],
+    ) -> Result<Box<dyn Any>, VirtualMachineError> {
+        let mut hint_data =
+            self.compile_hint(hint_code, ap_tracking_data, reference_ids, references)?;
+        if let Some(hint_data) = hint_data.downcast_mut::<HintProcessorData>() {
+            hint_data.accessible_scopes = accessible_scopes.to_vec();
+        }
+        Ok(hint_data)
+    }
+
+    /// Arranges the hints to run at `pc`, given as compiled in the order the program declares
+    /// them. The VM runs the hints at a pc one after the other in the order returned, each one
+    /// seeing the memory and scopes left by the previous ones.
+    ///
+    /// The default implementation keeps the declared order. Processors may reorder or drop the
+    /// hints, or interleave hint data of their own, which [execute_hint](Self::execute_hint) must
+    /// then understand.
+    fn arrange_hints(&self, _pc: usize, hints: Vec<Box<dyn Any>>) -> Vec<Box<dyn Any>> {
+        hints
+    }
+
+    /// Returns the implementation of the hint described by `hint_data` which may run in parallel
+    /// with the other hints at its pc, along with the addresses of its ids, if it has one. See
+    /// [VirtualMachine::set_parallel_hints].
+    ///
+    /// The default implementation returns None, running every hint with
+    /// [execute_hint](Self::execute_hint).
+    fn parallel_hint<'a>(
+        &'a self,
+        _vm: &VirtualMachine,
+        _hint_data: &Box<dyn Any>,
+    ) -> Option<ParallelHint<'a>> {
+        None
+    }
+
+    /// Returns the code of the hint described by `hint_data` and the segments it may write to
+    /// besides the segments it adds, checked when the hint sandbox is enabled, see
+    /// [VirtualMachine::set_hint_sandbox].
+    ///
+    /// The default implementation understands the data built by the default
+    /// [compile_hint](Self::compile_hint): the hint may write to the segments holding its ids, and
+    /// to the segments its ids point to. Hints described by other data may only write to the
+    /// segments they add.
+    fn hint_write_scope(&self, vm: &VirtualMachine, hint_data: &Box<dyn Any>) -> HintWriteScope {
+        match hint_data.downcast_ref::<HintProcessorData>() {
+            Some(hint_data) => HintWriteScope::from_ids(vm, hint_data),
+            None => HintWriteScope::default(),
+        }
+    }
+}
+
+/// The segments a hint may write to when the hint sandbox is enabled, besides the segments it
+/// adds, see [HintProcessorLogic::hint_write_scope].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct HintWriteScope {
+    /// Code of the hint, to report the writes outside of the scope
+    pub code: String,
+    pub segments: HashSet<isize>,
+}
+
+impl HintWriteScope {
+    /// Scope of the segments holding the ids of the hint and of those its ids point to.
+    pub fn from_ids(vm: &VirtualMachine, hint_data: &HintProcessorData) -> Self {
+        let mut segments = HashSet::new();
+        for reference in hint_data.ids_data.values() {
+            let addr = match compute_addr_from_reference(reference, vm, &hint_data.ap_tracking) {
+                Some(addr) => addr,
+                None => continue,
+            };
+            segments.insert(addr.segment_index);
+            if let Some(ptr) = vm
+                .get_maybe(&addr)
+                .and_then(|value| value.get_relocatable())
+            {
+                segments.insert(ptr.segment_index);
+            }
+        }
+        HintWriteScope {
+            code: hint_data.code.clone(),
+            segments,
+        }
+    }
 }
 
 pub trait HintProcessor: HintProcessorLogic + ResourceTracker {}
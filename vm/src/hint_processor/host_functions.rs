@@ -0,0 +1,314 @@
+//! Functions of the embedder that hints can call by name.
+//!
+//! Custom hints often need data owned by the embedder (storage, oracles, ...). Instead of
+//! capturing it in each hint, the embedder registers host functions once and hints call them
+//! through [ExecutionScopes::call_host]. Every call goes through the registry, which records it,
+//! so the embedder controls and can audit what hints reach outside the VM.
+
+use crate::stdlib::{collections::HashMap, prelude::*, rc::Rc};
+
+use felt::Felt252;
+use num_traits::{One, ToPrimitive, Zero};
+
+use crate::{
+    types::{errors::math_errors::MathError, exec_scope::ExecutionScopes},
+    vm::errors::hint_errors::HintError,
+};
+
+/// Name of the main scope variable holding the [HostFunctions] of a run
+pub const HOST_FUNCTIONS: &str = "__host_functions";
+
+pub type HostFunction = dyn Fn(&[Felt252]) -> Result<Vec<Felt252>, HintError>;
+
+/// A value that can be passed to, or returned from, a host function.
+pub trait HostValue: Sized {
+    fn to_felts(&self) -> Vec<Felt252>;
+    fn from_felts(felts: &[Felt252]) -> Result<Self, HintError>;
+}
+
+fn expect_len(felts: &[Felt252], expected: usize) -> Result<(), HintError> {
+    if felts.len() != expected {
+        return Err(HintError::WrongHostValueLength(Box::new((
+            expected,
+            felts.len(),
+        ))));
+    }
+    Ok(())
+}
+
+impl HostValue for () {
+    fn to_felts(&self) -> Vec<Felt252> {
+        Vec::new()
+    }
+
+    fn from_felts(felts: &[Felt252]) -> Result<Self, HintError> {
+        expect_len(felts, 0)
+    }
+}
+
+impl HostValue for Felt252 {
+    fn to_felts(&self) -> Vec<Felt252> {
+        vec![self.clone()]
+    }
+
+    fn from_felts(felts: &[Felt252]) -> Result<Self, HintError> {
+        expect_len(felts, 1)?;
+        Ok(felts[0].clone())
+    }
+}
+
+impl HostValue for Vec<Felt252> {
+    fn to_felts(&self) -> Vec<Felt252> {
+        self.clone()
+    }
+
+    fn from_felts(felts: &[Felt252]) -> Result<Self, HintError> {
+        Ok(felts.to_vec())
+    }
+}
+
+impl HostValue for bool {
+    fn to_felts(&self) -> Vec<Felt252> {
+        vec![if *self {
+            Felt252::one()
+        } else {
+            Felt252::zero()
+        }]
+    }
+
+    fn from_felts(felts: &[Felt252]) -> Result<Self, HintError> {
+        Ok(!Felt252::from_felts(felts)?.is_zero())
+    }
+}
+
+impl HostValue for u64 {
+    fn to_felts(&self) -> Vec<Felt252> {
+        vec![Felt252::from(*self)]
+    }
+
+    fn from_felts(felts: &[Felt252]) -> Result<Self, HintError> {
+        let felt = Felt252::from_felts(felts)?;
+        felt.to_u64()
+            .ok_or_else(|| MathError::Felt252ToU64Conversion(Box::new(felt)).into())
+    }
+}
+
+/// A call made through [HostFunctions::call], in the order they happened.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HostCall {
+    pub name: String,
+    pub args: Vec<Felt252>,
+    pub result: Result<Vec<Felt252>, String>,
+}
+
+/// Registry of the host functions available to the hints of a run.
+#[derive(Default)]
+pub struct HostFunctions {
+    functions: HashMap<String, Rc<HostFunction>>,
+    calls: Vec<HostCall>,
+}
+
+impl HostFunctions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers `function` under `name`, replacing any previous function with that name.
+    pub fn register(
+        &mut self,
+        name: &str,
+        function: impl Fn(&[Felt252]) -> Result<Vec<Felt252>, HintError> + 'static,
+    ) {
+        self.functions.insert(name.to_string(), Rc::new(function));
+    }
+
+    pub fn is_registered(&self, name: &str) -> bool {
+        self.functions.contains_key(name)
+    }
+
+    /// Calls the function registered under `name` and records the call.
+    pub fn call(&mut self, name: &str, args: &[Felt252]) -> Result<Vec<Felt252>, HintError> {
+        let function = self
+            .functions
+            .get(name)
+            .cloned()
+            .ok_or_else(|| HintError::UnknownHostFunction(name.to_string().into_boxed_str()))?;
+        let result = function(args);
+        self.calls.push(HostCall {
+            name: name.to_string(),
+            args: args.to_vec(),
+            result: result.as_ref().cloned().map_err(|error| error.to_string()),
+        });
+        result
+    }
+
+    /// Returns the calls made so far, oldest first.
+    pub fn calls(&self) -> &[HostCall] {
+        &self.calls
+    }
+}
+
+impl core::fmt::Debug for HostFunctions {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let mut names: Vec<_> = self.functions.keys().collect();
+        names.sort();
+        f.debug_struct("HostFunctions")
+            .field("functions", &names)
+            .field("calls", &self.calls)
+            .finish()
+    }
+}
+
+impl ExecutionScopes {
+    /// Makes `host_functions` available to the hints of the run, through the main scope.
+    pub fn set_host_functions(&mut self, host_functions: HostFunctions) {
+        if let Some(main_scope) = self.data.first_mut() {
+            main_scope.insert(HOST_FUNCTIONS.to_string(), Box::new(host_functions));
+        }
+    }
+
+    pub fn host_functions(&self) -> Option<&HostFunctions> {
+        self.data.first()?.get(HOST_FUNCTIONS)?.downcast_ref()
+    }
+
+    pub fn host_functions_mut(&mut self) -> Option<&mut HostFunctions> {
+        self.data
+            .first_mut()?
+            .get_mut(HOST_FUNCTIONS)?
+            .downcast_mut()
+    }
+
+    /// Calls the host function registered under `name`, encoding `args` and decoding its result.
+    pub fn call_host<A: HostValue, R: HostValue>(
+        &mut self,
+        name: &str,
+        args: A,
+    ) -> Result<R, HintError> {
+        let host_functions = self
+            .host_functions_mut()
+            .ok_or_else(|| HintError::UnknownHostFunction(name.to_string().into_boxed_str()))?;
+        R::from_felts(&host_functions.call(name, &args.to_felts())?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        any_box,
+        hint_processor::{
+            builtin_hint_processor::builtin_hint_processor_definition::{
+                BuiltinHintProcessor, HintFunc, HintProcessorData,
+            },
+            hint_processor_definition::HintProcessorLogic,
+        },
+        types::constants::Constants,
+        utils::test_utils::*,
+        vm::vm_core::VirtualMachine,
+    };
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    fn storage_host_functions() -> HostFunctions {
+        let mut host_functions = HostFunctions::new();
+        host_functions.register("storage_read", |args| {
+            Ok(vec![args[0].clone() * Felt252::from(2)])
+        });
+        host_functions.register("fail", |_| {
+            Err(HintError::CustomHint(
+                "host failure".to_string().into_boxed_str(),
+            ))
+        });
+        host_functions
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn call_records_calls() {
+        let mut host_functions = storage_host_functions();
+        assert!(host_functions.is_registered("storage_read"));
+        assert_matches!(
+            host_functions.call("storage_read", &[Felt252::from(21)]),
+            Ok(result) if result == vec![Felt252::from(42)]
+        );
+        assert_matches!(
+            host_functions.call("fail", &[]),
+            Err(HintError::CustomHint(_))
+        );
+        assert_eq!(
+            host_functions.calls(),
+            &[
+                HostCall {
+                    name: "storage_read".to_string(),
+                    args: vec![Felt252::from(21)],
+                    result: Ok(vec![Felt252::from(42)]),
+                },
+                HostCall {
+                    name: "fail".to_string(),
+                    args: Vec::new(),
+                    result: Err("Custom Hint Error: host failure".to_string()),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn call_unknown_host_function() {
+        let mut host_functions = storage_host_functions();
+        assert_matches!(
+            host_functions.call("storage_write", &[]),
+            Err(HintError::UnknownHostFunction(name)) if name.as_ref() == "storage_write"
+        );
+        assert!(host_functions.calls().is_empty());
+
+        let mut exec_scopes = ExecutionScopes::new();
+        assert_matches!(
+            exec_scopes.call_host::<_, Felt252>("storage_read", Felt252::one()),
+            Err(HintError::UnknownHostFunction(_))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn call_host_decodes_result() {
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.set_host_functions(storage_host_functions());
+        exec_scopes.enter_scope(HashMap::new());
+
+        assert_matches!(exec_scopes.call_host("storage_read", 21_u64), Ok(42_u64));
+        assert_matches!(exec_scopes.call_host("storage_read", true), Ok(true));
+        assert_matches!(
+            exec_scopes.call_host::<_, ()>("storage_read", Felt252::one()),
+            Err(HintError::WrongHostValueLength(bx)) if *bx == (0, 1)
+        );
+        assert_eq!(exec_scopes.host_functions().unwrap().calls().len(), 3);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn custom_hint_calls_host() {
+        let hint_func = HintFunc(Box::new(|_, exec_scopes, _, _, _| {
+            let value: Felt252 = exec_scopes.call_host("storage_read", Felt252::from(5))?;
+            exec_scopes.insert_value("value", value);
+            Ok(())
+        }));
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        hint_processor.add_hint(String::from("read_storage"), Rc::new(hint_func));
+
+        let mut vm = vm!();
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.set_host_functions(storage_host_functions());
+        let hint_data = any_box!(HintProcessorData::new_default(
+            String::from("read_storage"),
+            HashMap::new()
+        ));
+        assert_matches!(
+            hint_processor.execute_hint(&mut vm, &mut exec_scopes, &hint_data, &Constants::new()),
+            Ok(())
+        );
+        assert_matches!(exec_scopes.get::<Felt252>("value"), Ok(value) if value == Felt252::from(10));
+    }
+}
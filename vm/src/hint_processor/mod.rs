@@ -1,5 +1,11 @@
 pub mod builtin_hint_processor;
 #[cfg(feature = "cairo-1-hints")]
 pub mod cairo_1_hint_processor;
+pub mod hint_coverage;
 pub mod hint_processor_definition;
 pub mod hint_processor_utils;
+pub mod host_functions;
+#[cfg(feature = "unsafe-plugins")]
+#[cfg_attr(docsrs, doc(cfg(feature = "unsafe-plugins")))]
+#[allow(unsafe_code)]
+pub mod plugins;
@@ -0,0 +1,446 @@
+//! A C-compatible plugin interface for hint handlers.
+//!
+//! A plugin is a dynamic library exporting the following functions:
+//! ```c
+//! uint32_t cairo_vm_plugin_abi_version(void);
+//! void cairo_vm_plugin_register(const CairoVmPluginRegistrar *registrar);
+//! ```
+//! The first one returns the version of the ABI the plugin was built for, and loading the plugin
+//! fails unless it is [PLUGIN_ABI_VERSION]. The second one calls
+//! `registrar->register_hint(registrar->ctx, code, callback)` once for every hint code it
+//! implements. Hint codes are NUL-terminated UTF-8 strings that are copied by the host.
+//!
+//! When a registered hint is executed, its callback receives a [VmFacade], a limited view of the
+//! VM that allows reading and writing `ids` variables as 32-byte big-endian felts, and returns
+//! [PLUGIN_OK] on success. Callbacks must not unwind across the FFI boundary, and neither do the
+//! functions of the host: a panic inside them is reported as a failure ([PLUGIN_ERROR]) instead.
+//!
+//! Loaded hints are registered as extra hints of a [BuiltinHintProcessor], so they take
+//! precedence over the builtin implementation of the same hint code.
+
+use crate::stdlib::{collections::HashMap, prelude::*, rc::Rc, sync::Arc};
+
+use core::ffi::{c_char, c_void, CStr};
+use std::{
+    ffi::OsStr,
+    panic::{catch_unwind, AssertUnwindSafe},
+};
+
+use felt::Felt252;
+use libloading::Library;
+use num_bigint::BigUint;
+use thiserror_no_std::Error;
+
+use crate::{
+    hint_processor::{
+        builtin_hint_processor::{
+            builtin_hint_processor_definition::{BuiltinHintProcessor, HintFunc},
+            hint_utils::{get_integer_from_var_name, insert_value_from_var_name},
+        },
+        hint_processor_definition::HintReference,
+    },
+    serde::deserialize_program::ApTracking,
+    vm::{errors::hint_errors::HintError, vm_core::VirtualMachine},
+};
+
+/// Version of the plugin ABI, which plugins must have been built for. Also exposed to plugins
+/// through [PluginRegistrar::abi_version].
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+/// Status returned by plugin callbacks and facade functions on success.
+pub const PLUGIN_OK: i32 = 0;
+/// Status returned by facade functions when the operation failed.
+pub const PLUGIN_ERROR: i32 = -1;
+
+const PLUGIN_ABI_VERSION_SYMBOL: &[u8] = b"cairo_vm_plugin_abi_version\0";
+const PLUGIN_REGISTER_SYMBOL: &[u8] = b"cairo_vm_plugin_register\0";
+
+pub type PluginHintCallback = extern "C" fn(vm: *const VmFacade) -> i32;
+type PluginAbiVersionFn = unsafe extern "C" fn() -> u32;
+type PluginRegisterFn = unsafe extern "C" fn(registrar: *const PluginRegistrar);
+
+#[repr(C)]
+pub struct PluginRegistrar {
+    pub abi_version: u32,
+    pub ctx: *mut c_void,
+    pub register_hint:
+        extern "C" fn(ctx: *mut c_void, code: *const c_char, callback: PluginHintCallback),
+}
+
+/// Limited view of the VM handed to plugin callbacks. Only valid during the callback.
+#[repr(C)]
+pub struct VmFacade {
+    pub ctx: *mut c_void,
+    /// Writes the value of `ids.<name>` into `out`, which must point to 32 bytes.
+    pub get_ids_felt: extern "C" fn(ctx: *mut c_void, name: *const c_char, out: *mut u8) -> i32,
+    /// Assigns the 32 bytes pointed by `value`, a big-endian integer below the field prime, to
+    /// `ids.<name>`. Fails for values not below the prime instead of reducing them.
+    pub set_ids_felt: extern "C" fn(ctx: *mut c_void, name: *const c_char, value: *const u8) -> i32,
+    /// Returns the offset of the ap register, or `usize::MAX` if it failed.
+    pub get_ap: extern "C" fn(ctx: *mut c_void) -> usize,
+    /// Returns the offset of the fp register, or `usize::MAX` if it failed.
+    pub get_fp: extern "C" fn(ctx: *mut c_void) -> usize,
+}
+
+#[derive(Debug, Error)]
+pub enum PluginError {
+    #[error("Failed to load plugin library: {0}")]
+    Load(#[from] libloading::Error),
+    #[error("Plugin was built for ABI version {0}, but the host implements version {PLUGIN_ABI_VERSION}")]
+    AbiVersionMismatch(u32),
+    #[error("Plugin registered a hint code that is not valid UTF-8")]
+    InvalidHintCode,
+    #[error("Registering a hint of the plugin panicked")]
+    RegistrationPanicked,
+}
+
+/// A loaded plugin library and the hints it registered.
+pub struct HintPlugin {
+    library: Arc<Library>,
+    hints: Vec<(String, PluginHintCallback)>,
+}
+
+struct Registration {
+    hints: Vec<(String, PluginHintCallback)>,
+    invalid_code: bool,
+    panicked: bool,
+}
+
+extern "C" fn register_hint(ctx: *mut c_void, code: *const c_char, callback: PluginHintCallback) {
+    // SAFETY: `ctx` is the `Registration` created in `HintPlugin::load`, which outlives the call
+    // to the plugin's register function
+    let registration = unsafe { &mut *(ctx as *mut Registration) };
+    let registered = catch_unwind(AssertUnwindSafe(|| {
+        if code.is_null() {
+            registration.invalid_code = true;
+            return;
+        }
+        // SAFETY: the plugin guarantees `code` is a valid NUL-terminated string
+        match unsafe { CStr::from_ptr(code) }.to_str() {
+            Ok(code) => registration.hints.push((code.to_string(), callback)),
+            Err(_) => registration.invalid_code = true,
+        }
+    }));
+    if registered.is_err() {
+        registration.panicked = true;
+    }
+}
+
+fn check_abi_version(abi_version: u32) -> Result<(), PluginError> {
+    if abi_version != PLUGIN_ABI_VERSION {
+        return Err(PluginError::AbiVersionMismatch(abi_version));
+    }
+    Ok(())
+}
+
+impl HintPlugin {
+    /// Loads the plugin at `path` and collects the hints it registers, failing if it was built for
+    /// another version of the ABI.
+    ///
+    /// # Safety
+    /// Loading a library runs its initialization code, and the registered callbacks will be called
+    /// during hint execution: the library must be trusted and implement the ABI described in the
+    /// [module documentation](self).
+    pub unsafe fn load(path: impl AsRef<OsStr>) -> Result<Self, PluginError> {
+        let library = Library::new(path)?;
+        let abi_version = library.get::<PluginAbiVersionFn>(PLUGIN_ABI_VERSION_SYMBOL)?();
+        check_abi_version(abi_version)?;
+        let mut registration = Registration {
+            hints: Vec::new(),
+            invalid_code: false,
+            panicked: false,
+        };
+        {
+            let register_fn = library.get::<PluginRegisterFn>(PLUGIN_REGISTER_SYMBOL)?;
+            let registrar = PluginRegistrar {
+                abi_version: PLUGIN_ABI_VERSION,
+                ctx: &mut registration as *mut Registration as *mut c_void,
+                register_hint,
+            };
+            register_fn(&registrar);
+        }
+
+        if registration.panicked {
+            return Err(PluginError::RegistrationPanicked);
+        }
+        if registration.invalid_code {
+            return Err(PluginError::InvalidHintCode);
+        }
+        Ok(HintPlugin {
+            library: Arc::new(library),
+            hints: registration.hints,
+        })
+    }
+
+    pub fn hint_codes(&self) -> impl Iterator<Item = &str> {
+        self.hints.iter().map(|(code, _)| code.as_str())
+    }
+
+    /// Adds the plugin's hints to `hint_processor`.
+    pub fn register(&self, hint_processor: &mut BuiltinHintProcessor) {
+        for (code, callback) in self.hints.iter() {
+            let (library, callback) = (self.library.clone(), *callback);
+            let hint_func = HintFunc(Box::new(
+                move |vm, _exec_scopes, ids_data, ap_tracking, _constants| {
+                    // Keep the library loaded for as long as the hint is alive
+                    let _library = &library;
+                    run_callback(callback, vm, ids_data, ap_tracking)
+                },
+            ));
+            hint_processor.add_hint(code.clone(), Rc::new(hint_func));
+        }
+    }
+}
+
+struct FacadeContext<'a> {
+    vm: &'a mut VirtualMachine,
+    ids_data: &'a HashMap<String, HintReference>,
+    ap_tracking: &'a ApTracking,
+    // First error produced by a facade function, reported instead of the plugin's status
+    error: Option<HintError>,
+}
+
+fn run_callback(
+    callback: PluginHintCallback,
+    vm: &mut VirtualMachine,
+    ids_data: &HashMap<String, HintReference>,
+    ap_tracking: &ApTracking,
+) -> Result<(), HintError> {
+    let mut context = FacadeContext {
+        vm,
+        ids_data,
+        ap_tracking,
+        error: None,
+    };
+    let facade = VmFacade {
+        ctx: &mut context as *mut FacadeContext as *mut c_void,
+        get_ids_felt,
+        set_ids_felt,
+        get_ap,
+        get_fp,
+    };
+    let status = callback(&facade);
+
+    match context.error {
+        Some(error) => Err(error),
+        None if status == PLUGIN_OK => Ok(()),
+        None => Err(HintError::CustomHint(
+            format!("Plugin hint failed with status {status}").into_boxed_str(),
+        )),
+    }
+}
+
+/// Returns the context of the facade handed to a plugin callback.
+///
+/// # Safety
+/// `ctx` must be the `ctx` of a [VmFacade] built by [run_callback], and the callback it was
+/// handed to must still be running, so the [FacadeContext] it points to is alive and not
+/// borrowed elsewhere.
+unsafe fn facade_context<'a>(ctx: *mut c_void) -> &'a mut FacadeContext<'a> {
+    &mut *(ctx as *mut FacadeContext)
+}
+
+// Runs the body of a facade function on its context, reporting a panic as a failure of the hint
+// instead of unwinding into the plugin
+fn run_facade_function(
+    ctx: *mut c_void,
+    body: impl FnOnce(&mut FacadeContext) -> Result<(), HintError>,
+) -> i32 {
+    // SAFETY: the plugin only calls facade functions with the `ctx` of the facade handed to its
+    // callback, while the callback runs, as required by the ABI `HintPlugin::load` relies on
+    let context = unsafe { facade_context(ctx) };
+    let result = catch_unwind(AssertUnwindSafe(|| body(&mut *context))).unwrap_or_else(|_| {
+        Err(HintError::CustomHint(
+            "Plugin facade function panicked".into(),
+        ))
+    });
+    report(context, result)
+}
+
+fn var_name<'a>(name: *const c_char) -> Result<&'a str, HintError> {
+    if name.is_null() {
+        return Err(HintError::CustomHint(
+            "Plugin passed a null ids name".into(),
+        ));
+    }
+    // SAFETY: the plugin guarantees `name` is a valid NUL-terminated string
+    unsafe { CStr::from_ptr(name) }
+        .to_str()
+        .map_err(|_| HintError::CustomHint("Plugin passed a non UTF-8 ids name".into()))
+}
+
+fn report(context: &mut FacadeContext, result: Result<(), HintError>) -> i32 {
+    match result {
+        Ok(()) => PLUGIN_OK,
+        Err(error) => {
+            context.error.get_or_insert(error);
+            PLUGIN_ERROR
+        }
+    }
+}
+
+extern "C" fn get_ids_felt(ctx: *mut c_void, name: *const c_char, out: *mut u8) -> i32 {
+    run_facade_function(ctx, |context| {
+        let name = var_name(name)?;
+        let value =
+            get_integer_from_var_name(name, context.vm, context.ids_data, context.ap_tracking)?;
+        let bytes = value.to_be_bytes();
+        // SAFETY: the plugin guarantees `out` points to 32 writable bytes
+        unsafe { core::ptr::copy_nonoverlapping(bytes.as_ptr(), out, bytes.len()) };
+        Ok(())
+    })
+}
+
+extern "C" fn set_ids_felt(ctx: *mut c_void, name: *const c_char, value: *const u8) -> i32 {
+    run_facade_function(ctx, |context| {
+        let name = var_name(name)?;
+        // SAFETY: the plugin guarantees `value` points to 32 readable bytes
+        let bytes = unsafe { core::slice::from_raw_parts(value, 32) };
+        if BigUint::from_bytes_be(bytes) >= Felt252::prime() {
+            return Err(HintError::CustomHint(
+                format!("Plugin passed a value of ids.{name} not below the field prime")
+                    .into_boxed_str(),
+            ));
+        }
+        insert_value_from_var_name(
+            name,
+            Felt252::from_bytes_be(bytes),
+            context.vm,
+            context.ids_data,
+            context.ap_tracking,
+        )
+    })
+}
+
+extern "C" fn get_ap(ctx: *mut c_void) -> usize {
+    // SAFETY: the plugin only calls facade functions with the `ctx` of the facade handed to its
+    // callback, while the callback runs
+    let context = unsafe { facade_context(ctx) };
+    catch_unwind(AssertUnwindSafe(|| context.vm.get_ap().offset)).unwrap_or(usize::MAX)
+}
+
+extern "C" fn get_fp(ctx: *mut c_void) -> usize {
+    // SAFETY: the plugin only calls facade functions with the `ctx` of the facade handed to its
+    // callback, while the callback runs
+    let context = unsafe { facade_context(ctx) };
+    catch_unwind(AssertUnwindSafe(|| context.vm.get_fp().offset)).unwrap_or(usize::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{relocatable, types::relocatable::Relocatable, utils::test_utils::*};
+    use assert_matches::assert_matches;
+
+    extern "C" fn double_x(vm: *const VmFacade) -> i32 {
+        let vm = unsafe { &*vm };
+        let mut bytes = [0u8; 32];
+        let status = (vm.get_ids_felt)(vm.ctx, c"x".as_ptr(), bytes.as_mut_ptr());
+        if status != PLUGIN_OK {
+            return status;
+        }
+        // Small values only: double the last byte
+        bytes[31] *= 2;
+        (vm.set_ids_felt)(vm.ctx, c"y".as_ptr(), bytes.as_ptr())
+    }
+
+    extern "C" fn set_y_to_max(vm: *const VmFacade) -> i32 {
+        let vm = unsafe { &*vm };
+        let bytes = [0xff_u8; 32];
+        (vm.set_ids_felt)(vm.ctx, c"y".as_ptr(), bytes.as_ptr())
+    }
+
+    extern "C" fn read_missing(vm: *const VmFacade) -> i32 {
+        let vm = unsafe { &*vm };
+        let mut bytes = [0u8; 32];
+        (vm.get_ids_felt)(vm.ctx, c"missing".as_ptr(), bytes.as_mut_ptr())
+    }
+
+    #[test]
+    fn run_plugin_callback() {
+        let mut vm = vm!();
+        vm.segments = segments![((1, 0), 21)];
+        vm.run_context.fp = 2;
+        let ids_data = ids_data!["x", "y"];
+
+        assert_matches!(
+            run_callback(double_x, &mut vm, &ids_data, &ApTracking::default()),
+            Ok(())
+        );
+        check_memory![vm.segments.memory, ((1, 1), 42)];
+    }
+
+    #[test]
+    fn run_plugin_callback_reports_facade_errors() {
+        let mut vm = vm!();
+        let ids_data = ids_data!["x"];
+
+        assert_matches!(
+            run_callback(read_missing, &mut vm, &ids_data, &ApTracking::default()),
+            Err(HintError::UnknownIdentifier(name)) if name.as_ref() == "missing"
+        );
+    }
+
+    #[test]
+    fn run_plugin_callback_rejects_values_not_below_the_prime() {
+        let mut vm = vm!();
+        vm.segments = segments![((1, 0), 21)];
+        vm.run_context.fp = 2;
+        let ids_data = ids_data!["x", "y"];
+
+        assert_matches!(
+            run_callback(set_y_to_max, &mut vm, &ids_data, &ApTracking::default()),
+            Err(HintError::CustomHint(message))
+                if message.as_ref() == "Plugin passed a value of ids.y not below the field prime"
+        );
+        assert_eq!(vm.get_maybe(&relocatable!(1, 1)), None);
+    }
+
+    #[test]
+    fn check_plugin_abi_version() {
+        assert_matches!(check_abi_version(PLUGIN_ABI_VERSION), Ok(()));
+        assert_matches!(
+            check_abi_version(PLUGIN_ABI_VERSION + 1),
+            Err(PluginError::AbiVersionMismatch(version)) if version == PLUGIN_ABI_VERSION + 1
+        );
+        assert_eq!(
+            PluginError::AbiVersionMismatch(2).to_string(),
+            "Plugin was built for ABI version 2, but the host implements version 1"
+        );
+    }
+
+    #[test]
+    fn register_hint_collects_codes() {
+        let mut registration = Registration {
+            hints: Vec::new(),
+            invalid_code: false,
+            panicked: false,
+        };
+        let ctx = &mut registration as *mut Registration as *mut c_void;
+        register_hint(ctx, c"ids.y = ids.x * 2".as_ptr(), double_x);
+        register_hint(ctx, c"\xff".as_ptr(), double_x);
+
+        assert_eq!(registration.hints.len(), 1);
+        assert_eq!(registration.hints[0].0, "ids.y = ids.x * 2");
+        assert!(registration.invalid_code);
+    }
+
+    #[test]
+    fn facade_function_reports_panics() {
+        let mut vm = vm!();
+        let ids_data = ids_data!["x"];
+        let mut context = FacadeContext {
+            vm: &mut vm,
+            ids_data: &ids_data,
+            ap_tracking: &ApTracking::default(),
+            error: None,
+        };
+        let ctx = &mut context as *mut FacadeContext as *mut c_void;
+
+        let status = run_facade_function(ctx, |_| panic!("facade bug"));
+        assert_eq!(status, PLUGIN_ERROR);
+        assert_matches!(
+            context.error,
+            Some(HintError::CustomHint(message)) if message.as_ref() == "Plugin facade function panicked"
+        );
+    }
+}
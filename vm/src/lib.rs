@@ -4,10 +4,30 @@
 //! - `skip_next_instruction_hint`: Enable the `skip_next_instruction()` hint. Not enabled by default.
 //! - `hooks`: Enable [Hooks](vm::hooks) support for the [VirtualMachine](vm::vm_core::VirtualMachine). Not enabled by default.
 //! - `with_mimalloc`: Use [MiMalloc](https://crates.io/crates/mimalloc) as the program global allocator.
+//! - `python-bindings`: Enable minimal [PyO3](https://pyo3.rs) [bindings](python) for the core API. Requires `std`. Not enabled by default.
+//! - `unsafe-plugins`: Enable loading hint implementations from dynamic libraries through a C ABI [plugin interface](hint_processor::plugins). Requires `std`. Not enabled by default.
+//! - `server`: Enable the JSON-RPC execution [Server](server::Server). Requires `std`. Not enabled by default.
+//!
+//! # Stability
+//! - [prelude]: stable. Re-exports the types needed to load, run and inspect a program, such as
+//!   [Program](prelude::Program), [CairoRunner](prelude::CairoRunner) and
+//!   [VirtualMachine](prelude::VirtualMachine), under paths kept across releases.
+//! - The other modules: public, but items may move between modules as the crate is reorganized.
+//!   Prefer the prelude for the items it exports.
+//! - The modules behind the `bench_utils`, `python-bindings`, `unsafe-plugins` and `server`
+//!   features: experimental, they may change in any release.
 
 #![cfg_attr(docsrs, feature(doc_cfg))]
 #![deny(warnings)]
-#![forbid(unsafe_code)]
+// `unsafe` code is only allowed in the modules behind the `python-bindings` and `unsafe-plugins` features
+#![cfg_attr(
+    not(any(feature = "python-bindings", feature = "unsafe-plugins")),
+    forbid(unsafe_code)
+)]
+#![cfg_attr(
+    any(feature = "python-bindings", feature = "unsafe-plugins"),
+    deny(unsafe_code)
+)]
 #![cfg_attr(any(target_arch = "wasm32", not(feature = "std")), no_std)]
 
 #[cfg(feature = "std")]
@@ -49,10 +69,21 @@ mod stdlib {
 }
 
 pub extern crate felt;
+#[cfg(feature = "bench_utils")]
+#[cfg_attr(docsrs, doc(cfg(feature = "bench_utils")))]
+pub mod bench_utils;
 pub mod cairo_run;
 pub mod hint_processor;
 pub mod math_utils;
+pub mod prelude;
+#[cfg(feature = "python-bindings")]
+#[cfg_attr(docsrs, doc(cfg(feature = "python-bindings")))]
+#[allow(unsafe_code)]
+pub mod python;
 pub mod serde;
+#[cfg(feature = "server")]
+#[cfg_attr(docsrs, doc(cfg(feature = "server")))]
+pub mod server;
 pub mod types;
 pub mod utils;
 pub mod vm;
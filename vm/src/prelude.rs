@@ -0,0 +1,51 @@
+//! The types needed to load, run and inspect a program, in one import:
+//!
+//! ```
+//! use cairo_vm::prelude::*;
+//! ```
+//!
+//! The paths of this module are stable: the items are re-exported here from wherever they are
+//! defined, so imports of the prelude don't break when the modules below are reorganized.
+
+pub use crate::{
+    cairo_run::{cairo_run, CairoRunConfig},
+    felt::Felt252,
+    hint_processor::{
+        builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+        hint_processor_definition::{HintProcessor, HintProcessorLogic, HintReference},
+    },
+    types::{
+        exec_scope::ExecutionScopes,
+        program::Program,
+        relocatable::{MaybeRelocatable, Relocatable},
+    },
+    vm::{
+        errors::{
+            cairo_run_errors::CairoRunError, hint_errors::HintError, vm_errors::VirtualMachineError,
+        },
+        runners::cairo_runner::{CairoRunner, ResourceTracker, RunResources},
+        vm_core::VirtualMachine,
+    },
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn prelude_initializes_a_run() {
+        let program = Program::default();
+        let mut runner = CairoRunner::new(&program, "plain", false).unwrap();
+        let mut vm = VirtualMachine::new(false);
+        runner.initialize_segments(&mut vm, None);
+        assert_eq!(runner.program_base, Some(Relocatable::from((0, 0))));
+        assert_eq!(
+            MaybeRelocatable::from(Felt252::new(7)).get_int_ref(),
+            Some(&Felt252::new(7))
+        );
+    }
+}
@@ -0,0 +1,251 @@
+//! Minimal [PyO3](https://pyo3.rs) bindings for the core API.
+//!
+//! These bindings don't define a Python module by themselves: extension crates (such as
+//! `cairo-vm-py`) are expected to call [register] from their own `#[pymodule]`.
+//! Relocatable values are exposed to Python as `(segment_index, offset)` tuples.
+
+// The impls generated by the pyo3 macros trigger this lint on recent toolchains, which older ones
+// don't know
+#![allow(unknown_lints, non_local_definitions)]
+
+use pyo3::{
+    exceptions::{PyRuntimeError, PyValueError},
+    prelude::*,
+};
+
+use crate::{
+    hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+    types::{program::Program, relocatable::Relocatable},
+    vm::{runners::cairo_runner::CairoRunner, vm_core::VirtualMachine},
+};
+
+fn runtime_error(error: impl ToString) -> PyErr {
+    PyRuntimeError::new_err(error.to_string())
+}
+
+#[pyclass(name = "Program")]
+#[derive(Clone)]
+pub struct PyProgram {
+    pub(crate) inner: Program,
+}
+
+#[pymethods]
+impl PyProgram {
+    #[new]
+    #[pyo3(signature = (content, entrypoint = None))]
+    fn new(content: &[u8], entrypoint: Option<&str>) -> PyResult<Self> {
+        let inner = Program::from_bytes(content, entrypoint)
+            .map_err(|error| PyValueError::new_err(error.to_string()))?;
+        Ok(PyProgram { inner })
+    }
+
+    fn data_len(&self) -> usize {
+        self.inner.data_len()
+    }
+
+    fn builtins(&self) -> Vec<String> {
+        self.inner
+            .iter_builtins()
+            .map(|builtin| builtin.name().to_string())
+            .collect()
+    }
+
+    fn get_identifier_pc(&self, name: &str) -> Option<usize> {
+        self.inner.get_identifier(name)?.pc
+    }
+}
+
+#[pyclass(name = "VirtualMachine", unsendable)]
+pub struct PyVirtualMachine {
+    pub(crate) inner: VirtualMachine,
+}
+
+#[pymethods]
+impl PyVirtualMachine {
+    #[new]
+    #[pyo3(signature = (trace_enabled = false))]
+    fn new(trace_enabled: bool) -> Self {
+        PyVirtualMachine {
+            inner: VirtualMachine::new(trace_enabled),
+        }
+    }
+
+    fn current_step(&self) -> usize {
+        self.inner.current_step
+    }
+
+    fn get_output(&mut self) -> PyResult<String> {
+        let mut output = String::new();
+        self.inner
+            .write_output(&mut output)
+            .map_err(runtime_error)?;
+        Ok(output)
+    }
+
+    /// Returns the relocated trace as a list of `(pc, ap, fp)` tuples
+    fn get_relocated_trace(&self) -> PyResult<Vec<(usize, usize, usize)>> {
+        let trace = self.inner.get_relocated_trace().map_err(runtime_error)?;
+        Ok(trace
+            .iter()
+            .map(|entry| (entry.pc, entry.ap, entry.fp))
+            .collect())
+    }
+}
+
+#[pyclass(name = "CairoRunner", unsendable)]
+pub struct PyCairoRunner {
+    pub(crate) inner: CairoRunner,
+    hint_processor: BuiltinHintProcessor,
+}
+
+#[pymethods]
+impl PyCairoRunner {
+    #[new]
+    #[pyo3(signature = (program, layout = "plain", proof_mode = false))]
+    fn new(program: &PyProgram, layout: &str, proof_mode: bool) -> PyResult<Self> {
+        let inner = CairoRunner::new(&program.inner, layout, proof_mode).map_err(runtime_error)?;
+        Ok(PyCairoRunner {
+            inner,
+            hint_processor: BuiltinHintProcessor::new_empty(),
+        })
+    }
+
+    /// Initializes the runner and returns the address at which the execution ends
+    fn initialize(&mut self, mut vm: PyRefMut<PyVirtualMachine>) -> PyResult<(isize, usize)> {
+        let end = self
+            .inner
+            .initialize(&mut vm.inner)
+            .map_err(runtime_error)?;
+        Ok((end.segment_index, end.offset))
+    }
+
+    fn run_until_pc(
+        &mut self,
+        address: (isize, usize),
+        mut vm: PyRefMut<PyVirtualMachine>,
+    ) -> PyResult<()> {
+        self.inner
+            .run_until_pc(
+                Relocatable::from(address),
+                &mut vm.inner,
+                &mut self.hint_processor,
+            )
+            .map_err(runtime_error)
+    }
+
+    #[pyo3(signature = (vm, disable_trace_padding = false, disable_finalize_all = false))]
+    fn end_run(
+        &mut self,
+        mut vm: PyRefMut<PyVirtualMachine>,
+        disable_trace_padding: bool,
+        disable_finalize_all: bool,
+    ) -> PyResult<()> {
+        self.inner
+            .end_run(
+                disable_trace_padding,
+                disable_finalize_all,
+                &mut vm.inner,
+                &mut self.hint_processor,
+            )
+            .map_err(runtime_error)
+    }
+
+    #[pyo3(signature = (vm, relocate_mem = true))]
+    fn relocate(&mut self, mut vm: PyRefMut<PyVirtualMachine>, relocate_mem: bool) -> PyResult<()> {
+        self.inner
+            .relocate(&mut vm.inner, relocate_mem)
+            .map_err(runtime_error)
+    }
+
+    /// Returns the relocated memory as a list of decimal strings, with `None` for holes
+    fn get_relocated_memory(&self) -> Vec<Option<String>> {
+        self.inner
+            .relocated_memory
+            .iter()
+            .map(|value| value.as_ref().map(|value| value.to_string()))
+            .collect()
+    }
+}
+
+/// Adds the `Program`, `VirtualMachine` and `CairoRunner` classes to `module`.
+pub fn register(module: &PyModule) -> PyResult<()> {
+    module.add_class::<PyProgram>()?;
+    module.add_class::<PyVirtualMachine>()?;
+    module.add_class::<PyCairoRunner>()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::*;
+
+    fn with_gil<R>(f: impl FnOnce(Python) -> R) -> R {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(f)
+    }
+
+    #[test]
+    fn run_program() {
+        // ret, followed by a negative felt and a relocatable value
+        let program = PyProgram {
+            inner: program!(
+                data = vec![
+                    mayberelocatable!(2345108766317314046),
+                    mayberelocatable!(-1),
+                    mayberelocatable!(0, 1),
+                ],
+                main = Some(0),
+            ),
+        };
+        with_gil(|py| {
+            let vm = Py::new(py, PyVirtualMachine::new(true)).unwrap();
+            let mut runner = PyCairoRunner::new(&program, "plain", false).unwrap();
+            let end = runner.initialize(vm.borrow_mut(py)).unwrap();
+            assert_eq!(end, (3, 0));
+            runner.run_until_pc(end, vm.borrow_mut(py)).unwrap();
+            runner.end_run(vm.borrow_mut(py), false, false).unwrap();
+            runner.relocate(vm.borrow_mut(py), true).unwrap();
+
+            assert_eq!(vm.borrow(py).current_step(), 1);
+            assert_eq!(vm.borrow(py).get_relocated_trace().unwrap()[0], (1, 6, 6));
+            // The program segment is relocated to address 1
+            assert_eq!(
+                runner.get_relocated_memory()[..4],
+                [
+                    None,
+                    Some(String::from("2345108766317314046")),
+                    Some(String::from(
+                        "3618502788666131213697322783095070105623107215331596699973092056135872020480"
+                    )),
+                    Some(String::from("2")),
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn errors_are_mapped_to_python_exceptions() {
+        with_gil(|py| {
+            let error = PyProgram::new(b"{}", None).err().unwrap();
+            assert!(error.is_instance_of::<PyValueError>(py));
+            assert_eq!(
+                error.value(py).to_string(),
+                Program::from_bytes(b"{}", None).unwrap_err().to_string()
+            );
+
+            let program = PyProgram { inner: program!() };
+            let error = PyCairoRunner::new(&program, "unknown", false)
+                .err()
+                .unwrap();
+            assert!(error.is_instance_of::<PyRuntimeError>(py));
+            assert_eq!(
+                error.value(py).to_string(),
+                CairoRunner::new(&program.inner, "unknown", false)
+                    .err()
+                    .unwrap()
+                    .to_string()
+            );
+        });
+    }
+}
@@ -1,12 +1,21 @@
-use crate::stdlib::{collections::HashMap, fmt, prelude::*, sync::Arc};
+use crate::stdlib::{
+    collections::{BTreeMap, HashMap},
+    convert::Infallible,
+    fmt,
+    prelude::*,
+    str::FromStr,
+    sync::Arc,
+};
 
 use crate::vm::runners::builtin_runner::SEGMENT_ARENA_BUILTIN_NAME;
 use crate::{
     serde::deserialize_utils,
     types::{
+        code_map::CodeMap,
+        constants::Constants,
         errors::program_errors::ProgramError,
         instruction::Register,
-        program::{Program, SharedProgramData},
+        program::{Program, ProgramData, ProgramLoadOptions, SharedProgramData},
         relocatable::MaybeRelocatable,
     },
     vm::runners::builtin_runner::{
@@ -18,11 +27,11 @@ use crate::{
 use felt::{Felt252, PRIME_STR};
 use num_traits::float::FloatCore;
 use num_traits::{Num, Pow};
-use serde::{de, de::MapAccess, de::SeqAccess, Deserialize, Deserializer, Serialize};
+use serde::{de, de::MapAccess, de::SeqAccess, Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Number;
 
 // This enum is used to deserialize program builtins into &str and catch non-valid names
-#[derive(Serialize, Deserialize, Debug, PartialEq, Copy, Clone, Eq, Hash)]
+#[derive(Debug, PartialEq, Clone, Eq, Hash)]
 #[allow(non_camel_case_types)]
 pub enum BuiltinName {
     output,
@@ -34,10 +43,16 @@ pub enum BuiltinName {
     ec_op,
     poseidon,
     segment_arena,
+    /// A builtin this version of the VM doesn't know about, holding its `_builtin` suffixed name.
+    /// Programs using one can still be loaded, unless they are loaded in strict mode.
+    Other(String),
 }
 
+const BUILTIN_SUFFIX: &str = "_builtin";
+
 impl BuiltinName {
-    pub fn name(&self) -> &'static str {
+    /// Returns the `_builtin` suffixed name of the builtin, as used by the builtin runners.
+    pub fn name(&self) -> &str {
         match self {
             BuiltinName::output => OUTPUT_BUILTIN_NAME,
             BuiltinName::range_check => RANGE_CHECK_BUILTIN_NAME,
@@ -48,10 +63,66 @@ impl BuiltinName {
             BuiltinName::ec_op => EC_OP_BUILTIN_NAME,
             BuiltinName::poseidon => POSEIDON_BUILTIN_NAME,
             BuiltinName::segment_arena => SEGMENT_ARENA_BUILTIN_NAME,
+            BuiltinName::Other(name) => name,
+        }
+    }
+
+    /// Same as [BuiltinName::from_str], failing on builtins the VM doesn't know about.
+    pub fn from_str_strict(name: &str) -> Result<Self, ProgramError> {
+        match BuiltinName::from(name) {
+            BuiltinName::Other(name) => Err(ProgramError::UnknownBuiltin(name)),
+            builtin => Ok(builtin),
         }
     }
 }
 
+/// Parses a builtin name, with or without its `_builtin` suffix.
+impl From<&str> for BuiltinName {
+    fn from(name: &str) -> Self {
+        match name.strip_suffix(BUILTIN_SUFFIX).unwrap_or(name) {
+            "output" => BuiltinName::output,
+            "range_check" => BuiltinName::range_check,
+            "pedersen" => BuiltinName::pedersen,
+            "ecdsa" => BuiltinName::ecdsa,
+            "keccak" => BuiltinName::keccak,
+            "bitwise" => BuiltinName::bitwise,
+            "ec_op" => BuiltinName::ec_op,
+            "poseidon" => BuiltinName::poseidon,
+            "segment_arena" => BuiltinName::segment_arena,
+            name => BuiltinName::Other(format!("{name}{BUILTIN_SUFFIX}")),
+        }
+    }
+}
+
+impl FromStr for BuiltinName {
+    type Err = Infallible;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Ok(BuiltinName::from(name))
+    }
+}
+
+/// Displays the name of the builtin without its `_builtin` suffix, as written in programs.
+impl fmt::Display for BuiltinName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = self.name();
+        write!(f, "{}", name.strip_suffix(BUILTIN_SUFFIX).unwrap_or(name))
+    }
+}
+
+impl Serialize for BuiltinName {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for BuiltinName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(BuiltinName::from(name.as_str()))
+    }
+}
+
 #[derive(Deserialize, Debug)]
 pub struct ProgramJson {
     pub prime: String,
@@ -63,6 +134,8 @@ pub struct ProgramJson {
     pub reference_manager: ReferenceManager,
     pub attributes: Vec<Attribute>,
     pub debug_info: Option<DebugInfo>,
+    #[serde(default)]
+    pub compiler_version: Option<String>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
@@ -75,7 +148,10 @@ pub struct HintParams {
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct FlowTrackingData {
     pub ap_tracking: ApTracking,
-    #[serde(deserialize_with = "deserialize_map_to_string_and_usize_hashmap")]
+    #[serde(
+        serialize_with = "serialize_map_sorted",
+        deserialize_with = "deserialize_map_to_string_and_usize_hashmap"
+    )]
     pub reference_ids: HashMap<String, usize>,
 }
 
@@ -110,6 +186,7 @@ pub struct Identifier {
     pub value: Option<Felt252>,
 
     pub full_name: Option<String>,
+    #[serde(serialize_with = "serialize_optional_map_sorted")]
     pub members: Option<HashMap<String, Member>>,
     pub cairo_type: Option<String>,
 }
@@ -148,6 +225,8 @@ pub struct DebugInfo {
 pub struct InstructionLocation {
     pub inst: Location,
     pub hints: Vec<HintLocation>,
+    #[serde(default)]
+    pub flow_tracking_data: Option<FlowTrackingData>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -361,6 +440,25 @@ pub fn deserialize_map_to_string_and_usize_hashmap<'de, D: Deserializer<'de>>(
     d.deserialize_map(ReferenceIdsVisitor)
 }
 
+/// Serializes `map` with its entries sorted by key, so the output doesn't depend on the
+/// iteration order of the [`HashMap`].
+pub fn serialize_map_sorted<S: Serializer, K: Serialize + Ord, V: Serialize>(
+    map: &HashMap<K, V>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(map.iter().collect::<BTreeMap<_, _>>())
+}
+
+fn serialize_optional_map_sorted<S: Serializer, K: Serialize + Ord, V: Serialize>(
+    map: &Option<HashMap<K, V>>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match map {
+        Some(map) => serializer.serialize_some(&map.iter().collect::<BTreeMap<_, _>>()),
+        None => serializer.serialize_none(),
+    }
+}
+
 pub fn deserialize_value_address<'de, D: Deserializer<'de>>(
     d: D,
 ) -> Result<ValueAddress, D::Error> {
@@ -379,6 +477,68 @@ pub fn deserialize_and_parse_program(
     parse_program_json(program_json, entrypoint)
 }
 
+pub fn deserialize_and_parse_program_with_options(
+    reader: &[u8],
+    options: &ProgramLoadOptions,
+) -> Result<Program, ProgramError> {
+    let program_json: ProgramJson = deserialize_program_json(reader)?;
+    check_compiler_version(program_json.compiler_version.as_deref(), options)?;
+    if options.strict {
+        for builtin in program_json.builtins.iter() {
+            BuiltinName::from_str_strict(builtin.name())?;
+        }
+    }
+    let program = parse_program_json(program_json, options.entrypoint)?;
+    if options.validate_bytecode {
+        program.validate_bytecode()?;
+    }
+    Ok(program)
+}
+
+// Parses a version such as `0.11.0.2` into its numeric components, ignoring trailing zeros
+// so that `0.11` and `0.11.0` compare as equal
+fn parse_compiler_version(version: &str) -> Result<Vec<u64>, ProgramError> {
+    let mut components = version
+        .trim()
+        .split('.')
+        .map(|component| component.parse::<u64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| ProgramError::InvalidCompilerVersion(version.to_string()))?;
+    while components.last() == Some(&0) {
+        components.pop();
+    }
+    Ok(components)
+}
+
+pub fn check_compiler_version(
+    compiler_version: Option<&str>,
+    options: &ProgramLoadOptions,
+) -> Result<(), ProgramError> {
+    let compiler_version = match compiler_version {
+        Some(compiler_version) => compiler_version,
+        None if options.strict => return Err(ProgramError::MissingCompilerVersion),
+        None => return Ok(()),
+    };
+    let version = parse_compiler_version(compiler_version)?;
+    if let Some(min_version) = options.min_compiler_version {
+        if version < parse_compiler_version(min_version)? {
+            return Err(ProgramError::CompilerVersionTooOld(Box::new((
+                compiler_version.to_string(),
+                min_version.to_string(),
+            ))));
+        }
+    }
+    if let Some(max_version) = options.max_compiler_version {
+        if version > parse_compiler_version(max_version)? {
+            return Err(ProgramError::CompilerVersionTooNew(Box::new((
+                compiler_version.to_string(),
+                max_version.to_string(),
+            ))));
+        }
+    }
+    Ok(())
+}
+
 pub fn parse_program_json(
     program_json: ProgramJson,
     entrypoint: Option<&str>,
@@ -407,7 +567,7 @@ pub fn parse_program_json(
         None => None,
     };
 
-    let mut constants = HashMap::new();
+    let mut constants = Constants::new();
     for (key, value) in program_json.identifiers.iter() {
         if value.type_.as_deref() == Some("const") {
             let value = value
@@ -418,22 +578,32 @@ pub fn parse_program_json(
         }
     }
 
+    let (error_message_attributes, custom_attributes) = program_json
+        .attributes
+        .into_iter()
+        .partition(|attr| attr.name == "error_message");
+    let data = ProgramData::try_from(program_json.data)?;
+    let code_map = CodeMap::new(
+        &data,
+        entrypoint_pc,
+        start,
+        program_json.identifiers.values(),
+    )?;
     let shared_program_data = SharedProgramData {
-        data: program_json.data,
+        data,
         hints: program_json.hints,
         main: entrypoint_pc,
         start,
         end,
-        error_message_attributes: program_json
-            .attributes
-            .into_iter()
-            .filter(|attr| attr.name == "error_message")
-            .collect(),
+        error_message_attributes,
+        custom_attributes,
         instruction_locations: program_json
             .debug_info
             .map(|debug_info| debug_info.instruction_locations),
-        identifiers: program_json.identifiers,
+        identifiers: program_json.identifiers.into_iter().collect(),
         reference_manager: Program::get_reference_list(&program_json.reference_manager),
+        compiler_version: program_json.compiler_version,
+        code_map,
     };
     Ok(Program {
         shared_program_data: Arc::new(shared_program_data),
@@ -445,6 +615,7 @@ pub fn parse_program_json(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::vm::errors::vm_errors::VirtualMachineError;
     use assert_matches::assert_matches;
     use felt::felt_str;
     use num_traits::One;
@@ -793,6 +964,125 @@ mod tests {
         );
     }
 
+    fn program_with_compiler_version(version: &str) -> Vec<u8> {
+        let reader =
+            include_bytes!("../../../cairo_programs/manually_compiled/valid_program_a.json");
+        let mut program_json: serde_json::Value = serde_json::from_slice(reader).unwrap();
+        program_json["compiler_version"] = version.into();
+        program_json.to_string().into_bytes()
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn deserialize_program_with_compiler_version() {
+        let reader = program_with_compiler_version("0.11.0.2");
+        let options = ProgramLoadOptions {
+            entrypoint: Some("main"),
+            min_compiler_version: Some("0.10.3"),
+            max_compiler_version: Some("0.11.0.2"),
+            strict: true,
+            validate_bytecode: true,
+        };
+
+        let program = deserialize_and_parse_program_with_options(&reader, &options).unwrap();
+        assert_eq!(program.compiler_version(), Some("0.11.0.2"));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn deserialize_program_with_invalid_bytecode() {
+        let reader =
+            include_bytes!("../../../cairo_programs/manually_compiled/valid_program_a.json");
+        let mut program_json: serde_json::Value = serde_json::from_slice(reader).unwrap();
+        // [ap] = [ap - 1] + [ap - 2], with an immediate at [pc]
+        program_json["data"][4] = "0x48247fff7ffe8000".into();
+        let reader = program_json.to_string().into_bytes();
+        let options = ProgramLoadOptions {
+            validate_bytecode: true,
+            ..Default::default()
+        };
+
+        assert!(deserialize_and_parse_program(&reader, None).is_ok());
+        assert_matches!(
+            deserialize_and_parse_program_with_options(&reader, &options),
+            Err(ProgramError::InvalidInstruction(bx))
+                if matches!(*bx, (4, VirtualMachineError::ImmShouldBe1))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn deserialize_program_main_in_immediate() {
+        let reader =
+            include_bytes!("../../../cairo_programs/manually_compiled/valid_program_a.json");
+        let mut program_json: serde_json::Value = serde_json::from_slice(reader).unwrap();
+        // The immediate of the first instruction, [ap] = 1000; ap++
+        program_json["identifiers"]["__main__.f"] =
+            program_json["identifiers"]["__main__.main"].clone();
+        program_json["identifiers"]["__main__.main"]["pc"] = 1.into();
+        let reader = program_json.to_string().into_bytes();
+
+        assert_matches!(
+            deserialize_and_parse_program(&reader, Some("main")),
+            Err(ProgramError::InstructionInImmediate(1))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn deserialize_program_compiler_version_too_old() {
+        let reader = program_with_compiler_version("0.10.3");
+        let options = ProgramLoadOptions {
+            min_compiler_version: Some("0.11"),
+            ..Default::default()
+        };
+
+        assert_matches!(
+            deserialize_and_parse_program_with_options(&reader, &options),
+            Err(ProgramError::CompilerVersionTooOld(bx)) if *bx == ("0.10.3".to_string(), "0.11".to_string())
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn check_compiler_version_bounds() {
+        let options = ProgramLoadOptions {
+            min_compiler_version: Some("0.10.0"),
+            max_compiler_version: Some("0.11"),
+            ..Default::default()
+        };
+
+        assert_matches!(check_compiler_version(Some("0.10"), &options), Ok(()));
+        assert_matches!(check_compiler_version(Some("0.11.0.0"), &options), Ok(()));
+        assert_matches!(
+            check_compiler_version(Some("0.11.0.2"), &options),
+            Err(ProgramError::CompilerVersionTooNew(bx)) if *bx == ("0.11.0.2".to_string(), "0.11".to_string())
+        );
+        assert_matches!(
+            check_compiler_version(Some("0.9a"), &options),
+            Err(ProgramError::InvalidCompilerVersion(version)) if version == "0.9a"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn check_missing_compiler_version() {
+        let lenient = ProgramLoadOptions {
+            min_compiler_version: Some("0.11"),
+            ..Default::default()
+        };
+        let strict = ProgramLoadOptions {
+            strict: true,
+            ..lenient
+        };
+
+        assert_matches!(check_compiler_version(None, &lenient), Ok(()));
+        assert_matches!(
+            check_compiler_version(None, &strict),
+            Err(ProgramError::MissingCompilerVersion)
+        );
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn deserialize_program_test() {
@@ -1146,6 +1436,63 @@ mod tests {
         assert_eq!(program_json.attributes, attributes);
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn deserialize_program_with_custom_attributes() {
+        let program_json = br#"
+            {
+                "prime": "0x800000000000011000000000000000000000000000000000000000000000001",
+                "attributes": [
+                    {
+                        "accessible_scopes": ["__main__", "__main__.main"],
+                        "end_pc": 4,
+                        "flow_tracking_data": null,
+                        "name": "build_tag",
+                        "start_pc": 0,
+                        "value": "release"
+                    },
+                    {
+                        "accessible_scopes": ["__main__", "__main__.main"],
+                        "end_pc": 3,
+                        "flow_tracking_data": null,
+                        "name": "error_message",
+                        "start_pc": 1,
+                        "value": "Invalid input"
+                    },
+                    {
+                        "accessible_scopes": ["__main__", "__main__.main"],
+                        "end_pc": 2,
+                        "flow_tracking_data": null,
+                        "name": "build_tag",
+                        "start_pc": 1,
+                        "value": "audited"
+                    }
+                ],
+                "debug_info": null,
+                "builtins": [],
+                "data": [],
+                "identifiers": {},
+                "hints": {},
+                "reference_manager": {
+                    "references": []
+                }
+            }"#;
+
+        let program = deserialize_and_parse_program(program_json, None).unwrap();
+        let values = |name| {
+            program
+                .attributes_named(name)
+                .map(|attribute| attribute.value.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(values("build_tag"), ["release", "audited"]);
+        assert_eq!(values("error_message"), ["Invalid input"]);
+        assert!(values("unknown").is_empty());
+        assert_eq!(program.attributes().count(), 3);
+        // Custom attributes don't show up as error messages
+        assert_eq!(program.error_attributes().len(), 1);
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn deserialize_instruction_locations_test_no_parent() {
@@ -1233,6 +1580,10 @@ mod tests {
                             start_col: 5,
                         },
                         hints: vec![],
+                        flow_tracking_data: Some(FlowTrackingData {
+                            ap_tracking: ApTracking { group: 0, offset: 0 },
+                            reference_ids: HashMap::new(),
+                        }),
                     },
                 ),
                 (
@@ -1247,6 +1598,10 @@ mod tests {
                             start_col: 5,
                         },
                         hints: vec![],
+                        flow_tracking_data: Some(FlowTrackingData {
+                            ap_tracking: ApTracking { group: 1, offset: 1 },
+                            reference_ids: HashMap::new(),
+                        }),
                     },
                 ),
             ]),
@@ -1349,6 +1704,7 @@ mod tests {
                         }), String::from( "While expanding the reference 'syscall_ptr' in:"))
                     ), start_line: 9, start_col: 18 },
                     hints: vec![],
+                    flow_tracking_data: None,
                 }),
             ]
         ) };
@@ -1464,4 +1820,102 @@ mod tests {
             .unwrap()
         );
     }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn builtin_name_parse_and_display() {
+        for name in ["range_check", "range_check_builtin"] {
+            assert_eq!(BuiltinName::from_str(name), Ok(BuiltinName::range_check));
+        }
+        assert_eq!(BuiltinName::range_check.to_string(), "range_check");
+        assert_eq!(BuiltinName::range_check.name(), RANGE_CHECK_BUILTIN_NAME);
+
+        let gas = BuiltinName::from("gas");
+        assert_eq!(gas, BuiltinName::Other(String::from("gas_builtin")));
+        assert_eq!(gas, BuiltinName::from("gas_builtin"));
+        assert_eq!(gas.to_string(), "gas");
+        assert_eq!(gas.name(), "gas_builtin");
+
+        assert_matches!(
+            BuiltinName::from_str_strict("segment_arena_builtin"),
+            Ok(BuiltinName::segment_arena)
+        );
+        assert_matches!(
+            BuiltinName::from_str_strict("gas"),
+            Err(ProgramError::UnknownBuiltin(name)) if name == "gas_builtin"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn builtin_name_serde() {
+        let builtins: Vec<BuiltinName> =
+            serde_json::from_str(r#"["output", "pedersen_builtin", "gas"]"#).unwrap();
+        assert_eq!(
+            builtins,
+            vec![
+                BuiltinName::output,
+                BuiltinName::pedersen,
+                BuiltinName::Other(String::from("gas_builtin"))
+            ]
+        );
+        assert_eq!(
+            serde_json::to_string(&builtins).unwrap(),
+            r#"["output","pedersen","gas"]"#
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn deserialize_program_with_unknown_builtin() {
+        let program_json = br#"
+            {
+                "prime": "0x800000000000011000000000000000000000000000000000000000000000001",
+                "attributes": [],
+                "debug_info": null,
+                "builtins": ["output", "gas"],
+                "data": [],
+                "identifiers": {},
+                "hints": {},
+                "reference_manager": {
+                    "references": []
+                },
+                "compiler_version": "0.11.0"
+            }"#;
+
+        let program =
+            deserialize_and_parse_program_with_options(program_json, &Default::default()).unwrap();
+        assert_eq!(
+            program.builtins,
+            vec![BuiltinName::output, BuiltinName::from("gas")]
+        );
+
+        let strict = ProgramLoadOptions {
+            strict: true,
+            ..Default::default()
+        };
+        assert_matches!(
+            deserialize_and_parse_program_with_options(program_json, &strict),
+            Err(ProgramError::UnknownBuiltin(name)) if name == "gas_builtin"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn serialize_flow_tracking_data_sorts_reference_ids() {
+        let flow_tracking_data = FlowTrackingData {
+            ap_tracking: ApTracking::new(),
+            reference_ids: HashMap::from([
+                ("c".to_string(), 2),
+                ("a".to_string(), 0),
+                ("d".to_string(), 3),
+                ("b".to_string(), 1),
+            ]),
+        };
+
+        assert_eq!(
+            serde_json::to_string(&flow_tracking_data).unwrap(),
+            r#"{"ap_tracking":{"group":0,"offset":0},"reference_ids":{"a":0,"b":1,"c":2,"d":3}}"#
+        );
+    }
 }
@@ -0,0 +1,690 @@
+//! A small JSON-RPC 2.0 execution service over HTTP.
+//!
+//! Requests are sent as `POST` bodies and answered with a single JSON-RPC response.
+//! The following methods are supported:
+//! - `load_program`: `{"program": <compiled program>}` -> `{"program_id": String}`
+//! - `run_entrypoint`: `{"program_id": String, "entrypoint"?: String, "layout"?: String, "proof_mode"?: bool}`
+//!   -> `{"run_id": String, "steps": usize}`
+//! - `get_trace`: `{"run_id": String}` -> `[{"pc": usize, "ap": usize, "fp": usize}]`
+//! - `get_memory`: `{"run_id": String}` -> `[[usize, String]]` (relocated address and decimal value)
+//!
+//! Loaded programs are kept in a cache, and parsed once per requested entrypoint. The programs
+//! and the results of the runs are evicted once more than
+//! [cache_capacity](Server::cache_capacity) of them are held, the least recently used first.
+//!
+//! Connections are handled one at a time. Each connection is given
+//! [timeout](Server::timeout) to send its whole request, and again to read the response.
+//! Requests are rejected with `400 Bad Request` if their `Content-Length` isn't a number,
+//! `414 URI Too Long` or `431 Request Header Fields Too Large` if a line of their head is longer
+//! than 8 KiB or they have more than 100 headers, and `413 Payload Too Large` if their body is
+//! larger than [max_body_size](Server::max_body_size). Runs are stopped after
+//! [max_steps](Server::max_steps) steps.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream, ToSocketAddrs},
+    time::{Duration, Instant},
+};
+
+use felt::Felt252;
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    cairo_run::{cairo_run_program, CairoRunConfig},
+    hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+    types::program::Program,
+    vm::{runners::cairo_runner::RunResources, trace::trace_entry::TraceEntry},
+};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const EXECUTION_ERROR: i64 = -32000;
+
+const DEFAULT_MAX_BODY_SIZE: usize = 128 * 1024 * 1024;
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+const DEFAULT_CACHE_CAPACITY: usize = 16;
+const DEFAULT_MAX_STEPS: usize = 10_000_000;
+
+const MAX_LINE_LENGTH: usize = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+
+#[derive(Debug, PartialEq, Eq)]
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl ToString) -> Self {
+        RpcError {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+struct CachedProgram {
+    content: Vec<u8>,
+    // Parsed programs, by entrypoint
+    programs: HashMap<String, Program>,
+}
+
+struct RunResult {
+    trace: Vec<TraceEntry>,
+    memory: Vec<Option<Felt252>>,
+}
+
+#[derive(Deserialize)]
+struct LoadProgramParams {
+    program: Value,
+}
+
+#[derive(Deserialize)]
+struct RunEntrypointParams {
+    program_id: String,
+    entrypoint: Option<String>,
+    layout: Option<String>,
+    proof_mode: Option<bool>,
+}
+
+#[derive(Deserialize)]
+struct RunParams {
+    run_id: String,
+}
+
+// A map holding up to `capacity` entries, evicting the least recently used one to make room
+struct LruCache<V> {
+    capacity: usize,
+    entries: HashMap<String, V>,
+    // Keys from the least to the most recently used
+    order: VecDeque<String>,
+}
+
+impl<V> LruCache<V> {
+    fn new(capacity: usize) -> Self {
+        LruCache {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn insert(&mut self, key: String, value: V) {
+        while self.entries.len() >= self.capacity.max(1) {
+            match self.order.pop_front() {
+                Some(oldest) => self.entries.remove(&oldest),
+                None => break,
+            };
+        }
+        self.order.push_back(key.clone());
+        self.entries.insert(key, value);
+    }
+
+    fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        if let Some(position) = self.order.iter().position(|used| used == key) {
+            let used = self.order.remove(position)?;
+            self.order.push_back(used);
+        }
+        self.entries.get_mut(key)
+    }
+}
+
+type ErrorHandler = Box<dyn FnMut(&io::Error) + Send>;
+
+// Reads from a stream until `deadline`, so that a client sending its request slowly can't hold
+// the connection longer than the timeout
+struct DeadlineReader {
+    stream: TcpStream,
+    deadline: Instant,
+}
+
+fn request_timed_out() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::TimedOut,
+        "The request wasn't received before the timeout",
+    )
+}
+
+impl Read for DeadlineReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(request_timed_out());
+        }
+        self.stream.set_read_timeout(Some(remaining))?;
+        // An expired read timeout is reported as WouldBlock on Unix and TimedOut on Windows
+        self.stream.read(buf).map_err(|error| match error.kind() {
+            io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut => request_timed_out(),
+            _ => error,
+        })
+    }
+}
+
+/// JSON-RPC execution service, holding the loaded programs and the results of their runs.
+pub struct Server {
+    programs: LruCache<CachedProgram>,
+    runs: LruCache<RunResult>,
+    next_id: usize,
+    max_body_size: usize,
+    timeout: Duration,
+    max_steps: usize,
+    error_handler: Option<ErrorHandler>,
+}
+
+impl Default for Server {
+    fn default() -> Self {
+        Server {
+            programs: LruCache::new(DEFAULT_CACHE_CAPACITY),
+            runs: LruCache::new(DEFAULT_CACHE_CAPACITY),
+            next_id: 0,
+            max_body_size: DEFAULT_MAX_BODY_SIZE,
+            timeout: DEFAULT_TIMEOUT,
+            max_steps: DEFAULT_MAX_STEPS,
+            error_handler: None,
+        }
+    }
+}
+
+impl Server {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the largest request body accepted, in bytes. 128 MiB by default.
+    pub fn max_body_size(mut self, max_body_size: usize) -> Self {
+        self.max_body_size = max_body_size;
+        self
+    }
+
+    /// Sets how long a connection may take to send its whole request, and to read the response.
+    /// 30 seconds by default.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets the number of steps after which a run is stopped with an error. 10 million by
+    /// default.
+    pub fn max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Sets a function called with the errors of accepting and handling connections, to log
+    /// them. They are ignored by default.
+    pub fn error_handler(mut self, error_handler: impl FnMut(&io::Error) + Send + 'static) -> Self {
+        self.error_handler = Some(Box::new(error_handler));
+        self
+    }
+
+    /// Sets how many programs, and how many run results, are kept. 16 of each by default.
+    pub fn cache_capacity(mut self, cache_capacity: usize) -> Self {
+        self.programs.capacity = cache_capacity;
+        self.runs.capacity = cache_capacity;
+        self
+    }
+
+    /// Listens for HTTP connections on `addr`, handling them one at a time.
+    pub fn listen(&mut self, addr: impl ToSocketAddrs) -> io::Result<()> {
+        let listener = TcpListener::bind(addr)?;
+        for stream in listener.incoming() {
+            // A misbehaving client shouldn't bring the whole service down
+            let result = stream.and_then(|stream| self.handle_connection(stream));
+            if let (Err(error), Some(error_handler)) = (result, &mut self.error_handler) {
+                error_handler(&error);
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_connection(&mut self, stream: TcpStream) -> io::Result<()> {
+        stream.set_write_timeout(Some(self.timeout))?;
+        let mut reader = BufReader::new(DeadlineReader {
+            stream: stream.try_clone()?,
+            deadline: Instant::now() + self.timeout,
+        });
+        let (status, body) = match read_request(&mut reader, self.max_body_size)? {
+            Ok(body) => ("200 OK", self.handle_request(&body)),
+            Err(status) => (status, String::new()),
+        };
+
+        let mut stream = stream;
+        write!(
+            stream,
+            "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )?;
+        stream.flush()
+    }
+
+    /// Handles a single JSON-RPC request, returning the serialized response.
+    pub fn handle_request(&mut self, request: &[u8]) -> String {
+        let (id, result) = match serde_json::from_slice::<Value>(request) {
+            Ok(request) => {
+                let id = request.get("id").cloned().unwrap_or(Value::Null);
+                (id, self.dispatch(&request))
+            }
+            Err(error) => (Value::Null, Err(RpcError::new(PARSE_ERROR, error))),
+        };
+
+        let response = match result {
+            Ok(result) => json!({"jsonrpc": "2.0", "id": id, "result": result}),
+            Err(error) => json!({
+                "jsonrpc": "2.0",
+                "id": id,
+                "error": {"code": error.code, "message": error.message},
+            }),
+        };
+        response.to_string()
+    }
+
+    fn dispatch(&mut self, request: &Value) -> Result<Value, RpcError> {
+        let method = request
+            .get("method")
+            .and_then(Value::as_str)
+            .ok_or_else(|| RpcError::new(INVALID_REQUEST, "Missing method"))?;
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+
+        match method {
+            "load_program" => self.load_program(parse_params(params)?),
+            "run_entrypoint" => self.run_entrypoint(parse_params(params)?),
+            "get_trace" => self.get_trace(parse_params(params)?),
+            "get_memory" => self.get_memory(parse_params(params)?),
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Unknown method {method}"),
+            )),
+        }
+    }
+
+    fn fresh_id(&mut self, prefix: &str) -> String {
+        self.next_id += 1;
+        format!("{prefix}-{}", self.next_id)
+    }
+
+    fn load_program(&mut self, params: LoadProgramParams) -> Result<Value, RpcError> {
+        // The program may be sent either as a JSON object or as a string containing it
+        let content = match params.program {
+            Value::String(program) => program.into_bytes(),
+            program => program.to_string().into_bytes(),
+        };
+        // Parse it eagerly so that invalid programs are rejected on load
+        let program = Program::from_bytes(&content, Some("main"))
+            .or_else(|_| Program::from_bytes(&content, None))
+            .map_err(|error| RpcError::new(INVALID_PARAMS, error))?;
+
+        let mut programs = HashMap::new();
+        if program.shared_program_data.main.is_some() {
+            programs.insert(String::from("main"), program);
+        }
+        let program_id = self.fresh_id("program");
+        self.programs
+            .insert(program_id.clone(), CachedProgram { content, programs });
+        Ok(json!({ "program_id": program_id }))
+    }
+
+    fn run_entrypoint(&mut self, params: RunEntrypointParams) -> Result<Value, RpcError> {
+        let entrypoint = params.entrypoint.as_deref().unwrap_or("main");
+        let cached = self.programs.get_mut(&params.program_id).ok_or_else(|| {
+            RpcError::new(
+                INVALID_PARAMS,
+                format!("Unknown program {}", params.program_id),
+            )
+        })?;
+        if !cached.programs.contains_key(entrypoint) {
+            let program = Program::from_bytes(&cached.content, Some(entrypoint))
+                .map_err(|error| RpcError::new(INVALID_PARAMS, error))?;
+            cached.programs.insert(entrypoint.to_string(), program);
+        }
+        let program = &cached.programs[entrypoint];
+
+        let cairo_run_config = CairoRunConfig {
+            entrypoint,
+            trace_enabled: true,
+            relocate_mem: true,
+            layout: params.layout.as_deref().unwrap_or("plain"),
+            proof_mode: params.proof_mode.unwrap_or_default(),
+            ..Default::default()
+        };
+        let mut hint_processor =
+            BuiltinHintProcessor::new(HashMap::new(), RunResources::new(self.max_steps));
+        let (cairo_runner, mut vm) =
+            cairo_run_program(program, &cairo_run_config, &mut hint_processor)
+                .map_err(|error| RpcError::new(EXECUTION_ERROR, error))?;
+
+        // The relocated trace replaces the VM's one, so it can be moved out
+        vm.get_relocated_trace()
+            .map_err(|error| RpcError::new(EXECUTION_ERROR, error))?;
+        let trace = vm.trace.take().unwrap_or_default();
+
+        let run_id = self.fresh_id("run");
+        self.runs.insert(
+            run_id.clone(),
+            RunResult {
+                trace,
+                memory: cairo_runner.relocated_memory,
+            },
+        );
+        Ok(json!({ "run_id": run_id, "steps": vm.current_step }))
+    }
+
+    fn get_run(&mut self, run_id: &str) -> Result<&RunResult, RpcError> {
+        self.runs
+            .get_mut(run_id)
+            .map(|run| &*run)
+            .ok_or_else(|| RpcError::new(INVALID_PARAMS, format!("Unknown run {run_id}")))
+    }
+
+    fn get_trace(&mut self, params: RunParams) -> Result<Value, RpcError> {
+        let run = self.get_run(&params.run_id)?;
+        serde_json::to_value(&run.trace).map_err(|error| RpcError::new(EXECUTION_ERROR, error))
+    }
+
+    fn get_memory(&mut self, params: RunParams) -> Result<Value, RpcError> {
+        let run = self.get_run(&params.run_id)?;
+        let memory = run
+            .memory
+            .iter()
+            .enumerate()
+            .filter_map(|(addr, value)| Some(json!([addr, value.as_ref()?.to_string()])))
+            .collect();
+        Ok(Value::Array(memory))
+    }
+}
+
+// Reads a request, returning its body, or the status of the response if it's rejected
+fn read_request(
+    reader: &mut impl BufRead,
+    max_body_size: usize,
+) -> io::Result<Result<Vec<u8>, &'static str>> {
+    let Some(request_line) = read_head_line(reader)? else {
+        return Ok(Err("414 URI Too Long"));
+    };
+
+    let mut content_length = 0;
+    for n_headers in 0.. {
+        let Some(header) = read_head_line(reader)? else {
+            return Ok(Err("431 Request Header Fields Too Large"));
+        };
+        if header.trim().is_empty() {
+            break;
+        }
+        if n_headers == MAX_HEADERS {
+            return Ok(Err("431 Request Header Fields Too Large"));
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("content-length") {
+                match value.trim().parse() {
+                    Ok(length) => content_length = length,
+                    Err(_) => return Ok(Err("400 Bad Request")),
+                }
+            }
+        }
+    }
+
+    if !request_line.starts_with("POST ") {
+        return Ok(Err("405 Method Not Allowed"));
+    }
+    if content_length > max_body_size {
+        return Ok(Err("413 Payload Too Large"));
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+    Ok(Ok(body))
+}
+
+// Reads a line of the head of a request, returning None if it's longer than MAX_LINE_LENGTH. The
+// head ends at the end of the stream.
+fn read_head_line(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = Vec::new();
+    reader
+        .take(MAX_LINE_LENGTH as u64 + 1)
+        .read_until(b'\n', &mut line)?;
+    if line.len() > MAX_LINE_LENGTH {
+        return Ok(None);
+    }
+    Ok(Some(String::from_utf8_lossy(&line).into_owned()))
+}
+
+fn parse_params<T: for<'de> Deserialize<'de>>(params: Value) -> Result<T, RpcError> {
+    serde_json::from_value(params).map_err(|error| RpcError::new(INVALID_PARAMS, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    fn call(server: &mut Server, method: &str, params: Value) -> Value {
+        let request = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+        serde_json::from_str(&server.handle_request(request.to_string().as_bytes())).unwrap()
+    }
+
+    #[test]
+    fn load_run_and_inspect_program() {
+        let mut server = Server::new();
+        let program: Value =
+            serde_json::from_slice(include_bytes!("../../cairo_programs/fibonacci.json")).unwrap();
+
+        let response = call(&mut server, "load_program", json!({ "program": program }));
+        let program_id = response["result"]["program_id"].as_str().unwrap();
+
+        let response = call(
+            &mut server,
+            "run_entrypoint",
+            json!({ "program_id": program_id, "layout": "all_cairo" }),
+        );
+        let run_id = response["result"]["run_id"].as_str().unwrap().to_string();
+        let steps = response["result"]["steps"].as_u64().unwrap();
+
+        let trace = call(&mut server, "get_trace", json!({ "run_id": run_id }));
+        assert_eq!(trace["result"].as_array().unwrap().len() as u64, steps);
+        assert!(trace["result"][0]["pc"].is_u64());
+
+        let memory = call(&mut server, "get_memory", json!({ "run_id": run_id }));
+        assert!(!memory["result"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn unknown_method() {
+        let mut server = Server::new();
+        let response = call(&mut server, "run_forever", Value::Null);
+        assert_eq!(response["id"], json!(1));
+        assert_eq!(response["error"]["code"], json!(METHOD_NOT_FOUND));
+    }
+
+    #[test]
+    fn unknown_run() {
+        let mut server = Server::new();
+        let response = call(&mut server, "get_trace", json!({ "run_id": "run-1" }));
+        assert_eq!(response["error"]["code"], json!(INVALID_PARAMS));
+    }
+
+    #[test]
+    fn invalid_params() {
+        let mut server = Server::new();
+        let response = call(
+            &mut server,
+            "run_entrypoint",
+            json!({ "entrypoint": "main" }),
+        );
+        assert_eq!(response["error"]["code"], json!(INVALID_PARAMS));
+    }
+
+    #[test]
+    fn evicts_least_recently_used_program() {
+        let mut server = Server::new().cache_capacity(2);
+        let program: Value =
+            serde_json::from_slice(include_bytes!("../../cairo_programs/fibonacci.json")).unwrap();
+        let load = |server: &mut Server| {
+            let response = call(server, "load_program", json!({ "program": program }));
+            response["result"]["program_id"]
+                .as_str()
+                .unwrap()
+                .to_string()
+        };
+        let run = |server: &mut Server, program_id: &str| {
+            let response = call(
+                server,
+                "run_entrypoint",
+                json!({ "program_id": program_id, "layout": "all_cairo" }),
+            );
+            response["result"]["run_id"].is_string()
+        };
+
+        let first = load(&mut server);
+        let second = load(&mut server);
+        // Running the first program makes the second one the least recently used
+        assert!(run(&mut server, &first));
+        let third = load(&mut server);
+
+        assert!(run(&mut server, &first));
+        assert!(!run(&mut server, &second));
+        assert!(run(&mut server, &third));
+    }
+
+    // Sends `request` to a connection handled by `server`, returning the response and the
+    // result of handling the connection
+    fn send(
+        server: &mut Server,
+        request: impl AsRef<[u8]> + Send + 'static,
+    ) -> (String, io::Result<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let client = std::thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            // The server may reject the request before reading all of it
+            let _ = stream.write_all(request.as_ref());
+            let mut response = String::new();
+            let _ = stream.read_to_string(&mut response);
+            response
+        });
+        let (stream, _) = listener.accept().unwrap();
+        let result = server.handle_connection(stream);
+        (client.join().unwrap(), result)
+    }
+
+    #[test]
+    fn rejects_large_body() {
+        let mut server = Server::new().max_body_size(16);
+        let (response, result) = send(
+            &mut server,
+            b"POST / HTTP/1.1\r\nContent-Length: 1000000000\r\n\r\n",
+        );
+        assert!(result.is_ok());
+        assert!(response.starts_with("HTTP/1.1 413 Payload Too Large"));
+    }
+
+    #[test]
+    fn times_out_on_incomplete_request() {
+        let mut server = Server::new().timeout(Duration::from_millis(50));
+        // The body is never sent
+        let (response, result) = send(&mut server, b"POST / HTTP/1.1\r\nContent-Length: 8\r\n\r\n");
+        assert!(result.is_err());
+        assert!(response.is_empty());
+    }
+
+    #[test]
+    fn times_out_on_slow_request() {
+        let mut server = Server::new().timeout(Duration::from_millis(200));
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        // Each header arrives well within the timeout, but the head never ends
+        let client = std::thread::spawn(move || {
+            let mut stream = TcpStream::connect(addr).unwrap();
+            for _ in 0..40 {
+                if stream.write_all(b"X-Header: 1\r\n").is_err() {
+                    break;
+                }
+                std::thread::sleep(Duration::from_millis(50));
+            }
+        });
+        let (stream, _) = listener.accept().unwrap();
+        let start = Instant::now();
+        let result = server.handle_connection(stream);
+        assert_matches!(result, Err(error) if error.kind() == io::ErrorKind::TimedOut);
+        assert!(start.elapsed() < Duration::from_secs(1));
+        client.join().unwrap();
+    }
+
+    #[test]
+    fn rejects_malformed_content_length() {
+        let mut server = Server::new();
+        let (response, result) = send(
+            &mut server,
+            b"POST / HTTP/1.1\r\nContent-Length: 12abc\r\n\r\n{}",
+        );
+        assert!(result.is_ok());
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request"));
+    }
+
+    #[test]
+    fn rejects_long_lines() {
+        let mut server = Server::new();
+        let request = format!("POST /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE_LENGTH));
+        let (response, _) = send(&mut server, request);
+        assert!(response.starts_with("HTTP/1.1 414 URI Too Long"));
+
+        let request = format!(
+            "POST / HTTP/1.1\r\nX-Header: {}\r\n\r\n",
+            "a".repeat(MAX_LINE_LENGTH)
+        );
+        let (response, _) = send(&mut server, request);
+        assert!(response.starts_with("HTTP/1.1 431 Request Header Fields Too Large"));
+    }
+
+    #[test]
+    fn rejects_too_many_headers() {
+        let mut server = Server::new();
+        let request = |n_headers| {
+            format!(
+                "POST / HTTP/1.1\r\n{}Content-Length: 2\r\n\r\n{{}}",
+                "X-Header: 1\r\n".repeat(n_headers - 1)
+            )
+        };
+        let (response, _) = send(&mut server, request(MAX_HEADERS));
+        assert!(response.starts_with("HTTP/1.1 200 OK"));
+        let (response, _) = send(&mut server, request(MAX_HEADERS + 1));
+        assert!(response.starts_with("HTTP/1.1 431 Request Header Fields Too Large"));
+    }
+
+    #[test]
+    fn stops_runs_after_max_steps() {
+        let mut program: Value = serde_json::from_slice(include_bytes!(
+            "../../cairo_programs/manually_compiled/valid_program_a.json"
+        ))
+        .unwrap();
+        // Without its hints, the main function of the program runs for 4 steps
+        program["hints"] = json!({});
+        let run = |max_steps| {
+            let mut server = Server::new().max_steps(max_steps);
+            let response = call(&mut server, "load_program", json!({ "program": program }));
+            let program_id = response["result"]["program_id"].clone();
+            call(
+                &mut server,
+                "run_entrypoint",
+                json!({ "program_id": program_id }),
+            )
+        };
+
+        assert_eq!(run(4)["result"]["steps"], json!(4));
+        let response = run(3);
+        assert_eq!(response["error"]["code"], json!(EXECUTION_ERROR));
+        assert!(response["error"]["message"]
+            .as_str()
+            .unwrap()
+            .contains("RunResources has no remaining steps"));
+    }
+
+    #[test]
+    fn parse_error() {
+        let mut server = Server::new();
+        let response: Value = serde_json::from_str(&server.handle_request(b"{")).unwrap();
+        assert_eq!(response["id"], Value::Null);
+        assert_eq!(response["error"]["code"], json!(PARSE_ERROR));
+    }
+}
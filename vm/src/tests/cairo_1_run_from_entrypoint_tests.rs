@@ -98,6 +98,18 @@ fn fibonacci_3() {
     );
 }
 
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn fibonacci_3_contract_runner() {
+    let program_data = include_bytes!("../../../cairo_programs/cairo-1-contracts/fib.casm");
+    run_cairo_1_contract_entrypoint(
+        program_data.as_slice(),
+        0,
+        &[3_usize.into(), 3_usize.into(), 3_usize.into()],
+        &[9_usize.into()],
+    );
+}
+
 #[test]
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
 fn factorial_50() {
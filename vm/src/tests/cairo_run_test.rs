@@ -1,4 +1,5 @@
 use crate::tests::*;
+use crate::vm::unknown_hints::UnknownHintPolicy;
 
 #[test]
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
@@ -619,6 +620,52 @@ fn error_msg_attr() {
     run_program_with_error(program_data.as_slice(), error_msg);
 }
 
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn unknown_hint() {
+    let program_data = include_bytes!("../../../cairo_programs/bad_programs/unknown_hint.json");
+    let error_msg = "Unknown Hint: print(\"Computing the result\")";
+    run_program_with_error(program_data.as_slice(), error_msg);
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn fibonacci_strict_memory_reads() {
+    let program_data = include_bytes!("../../../cairo_programs/fibonacci.json");
+    let cairo_run_config = CairoRunConfig {
+        strict_memory_reads: true,
+        ..Default::default()
+    };
+    let (_, vm) = cairo_run(
+        program_data,
+        &cairo_run_config,
+        &mut BuiltinHintProcessor::new_empty(),
+    )
+    .expect("Execution failed");
+    assert_eq!(vm.current_step, 80);
+}
+
+#[test]
+#[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+fn unknown_hint_skipped() {
+    let program_data = include_bytes!("../../../cairo_programs/bad_programs/unknown_hint.json");
+    let cairo_run_config = CairoRunConfig {
+        layout: "all_cairo",
+        unknown_hint_policy: UnknownHintPolicy::Skip,
+        ..Default::default()
+    };
+    let (_, vm) = cairo_run(
+        program_data,
+        &cairo_run_config,
+        &mut BuiltinHintProcessor::new_empty(),
+    )
+    .expect("Execution failed");
+    let skipped_hints = vm.get_skipped_hints();
+    assert_eq!(skipped_hints.len(), 1);
+    assert_eq!(skipped_hints[0].code, "print(\"Computing the result\")");
+    assert_eq!(skipped_hints[0].pc.segment_index, 0);
+}
+
 #[test]
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
 fn error_msg_attr_tempvar() {
@@ -8,7 +8,10 @@ use crate::{
     serde::deserialize_program::BuiltinName,
     types::relocatable::MaybeRelocatable,
     vm::{
-        runners::cairo_runner::{CairoArg, CairoRunner},
+        runners::{
+            cairo_runner::{CairoArg, CairoRunner},
+            contract_runner::ContractRunner,
+        },
         vm_core::VirtualMachine,
     },
 };
@@ -134,7 +137,7 @@ pub(self) fn run_cairo_1_entrypoint(
     // Implicit Args
     let syscall_segment = MaybeRelocatable::from(vm.add_memory_segment());
 
-    let builtins: Vec<&'static str> = runner
+    let builtins: Vec<&str> = runner
         .get_program_builtins()
         .iter()
         .map(|b| b.name())
@@ -236,7 +239,7 @@ pub(self) fn run_cairo_1_entrypoint_with_run_resources(
     // Implicit Args
     let syscall_segment = MaybeRelocatable::from(vm.add_memory_segment());
 
-    let builtins: Vec<&'static str> = runner
+    let builtins: Vec<&str> = runner
         .get_program_builtins()
         .iter()
         .map(|b| b.name())
@@ -324,22 +327,42 @@ fn get_casm_contract_builtins(
         .unwrap()
         .builtins
         .iter()
-        .map(|n| format!("{}_builtin", n))
-        .map(|s| match &*s {
-            crate::vm::runners::builtin_runner::OUTPUT_BUILTIN_NAME => BuiltinName::output,
-            crate::vm::runners::builtin_runner::RANGE_CHECK_BUILTIN_NAME => {
-                BuiltinName::range_check
-            }
-            crate::vm::runners::builtin_runner::HASH_BUILTIN_NAME => BuiltinName::pedersen,
-            crate::vm::runners::builtin_runner::SIGNATURE_BUILTIN_NAME => BuiltinName::ecdsa,
-            crate::vm::runners::builtin_runner::KECCAK_BUILTIN_NAME => BuiltinName::keccak,
-            crate::vm::runners::builtin_runner::BITWISE_BUILTIN_NAME => BuiltinName::bitwise,
-            crate::vm::runners::builtin_runner::EC_OP_BUILTIN_NAME => BuiltinName::ec_op,
-            crate::vm::runners::builtin_runner::POSEIDON_BUILTIN_NAME => BuiltinName::poseidon,
-            crate::vm::runners::builtin_runner::SEGMENT_ARENA_BUILTIN_NAME => {
-                BuiltinName::segment_arena
-            }
-            _ => panic!("Invalid builtin {}", s),
-        })
+        .map(|n| BuiltinName::from_str_strict(n).unwrap())
         .collect()
 }
+
+#[cfg(feature = "cairo-1-hints")]
+// Runs a contract entrypoint through a ContractRunner, selecting it by the selector of the
+// entrypoint at the given offset, and checks its return values
+pub(self) fn run_cairo_1_contract_entrypoint(
+    program_content: &[u8],
+    entrypoint_offset: usize,
+    calldata: &[Felt252],
+    expected_retdata: &[Felt252],
+) {
+    let contract_class: CasmContractClass = serde_json::from_slice(program_content).unwrap();
+    let mut hint_processor =
+        Cairo1HintProcessor::new(&contract_class.hints, RunResources::default());
+    let selector = get_casm_contract_selector(&contract_class, entrypoint_offset);
+    let output = ContractRunner::new(&contract_class)
+        .unwrap()
+        .run(&selector, calldata, usize::MAX as u128, &mut hint_processor)
+        .unwrap();
+    assert_eq!(expected_retdata, output.retdata.data());
+}
+
+#[cfg(feature = "cairo-1-hints")]
+fn get_casm_contract_selector(
+    contract_class: &CasmContractClass,
+    entrypoint_offset: usize,
+) -> Felt252 {
+    contract_class
+        .entry_points_by_type
+        .external
+        .iter()
+        .find(|e| e.offset == entrypoint_offset)
+        .unwrap()
+        .selector
+        .clone()
+        .into()
+}
@@ -0,0 +1,144 @@
+//! Decoding of serialized Cairo 1 `ByteArray`s.
+//!
+//! A `ByteArray` is serialized as the number of its full words, the full words themselves, holding
+//! 31 bytes each, a pending word holding the remaining bytes and the number of these bytes.
+//! Panicking with a `ByteArray` serializes it after [BYTE_ARRAY_MAGIC].
+
+use crate::stdlib::prelude::*;
+
+use felt::Felt252;
+use num_traits::ToPrimitive;
+
+/// Felt preceding the `ByteArray`s in panic data, in hexadecimal
+pub const BYTE_ARRAY_MAGIC: &str =
+    "46a6158a16a947e5916b2a2ca68501a45e93d7110e81aa2d6438b1c57c879a3";
+
+/// Number of bytes of a full word of a `ByteArray`
+pub const BYTES_IN_WORD: usize = 31;
+
+/// Returns whether `felt` is the [BYTE_ARRAY_MAGIC].
+pub fn is_byte_array_magic(felt: &Felt252) -> bool {
+    felt.to_str_radix(16) == BYTE_ARRAY_MAGIC
+}
+
+/// Decodes the `ByteArray` serialized at the start of `felts`, returning its bytes and the
+/// number of felts it takes. Returns None if `felts` doesn't start with a valid `ByteArray`.
+pub fn decode_byte_array(felts: &[Felt252]) -> Option<(Vec<u8>, usize)> {
+    let (n_full_words, felts) = felts.split_first()?;
+    let n_full_words = n_full_words.to_usize()?;
+    if felts.len() < n_full_words.checked_add(2)? {
+        return None;
+    }
+    let (full_words, felts) = felts.split_at(n_full_words);
+    let pending_word_len = felts[1].to_usize()?;
+    if pending_word_len >= BYTES_IN_WORD {
+        return None;
+    }
+
+    let mut bytes = Vec::with_capacity(n_full_words * BYTES_IN_WORD + pending_word_len);
+    for word in full_words {
+        bytes.extend(word_bytes(word, BYTES_IN_WORD)?);
+    }
+    bytes.extend(word_bytes(&felts[0], pending_word_len)?);
+    Some((bytes, n_full_words + 3))
+}
+
+/// Same as [decode_byte_array], decoding the bytes as UTF-8, with invalid sequences replaced by
+/// `U+FFFD`.
+pub fn decode_byte_array_string(felts: &[Felt252]) -> Option<(String, usize)> {
+    let (bytes, len) = decode_byte_array(felts)?;
+    Some((String::from_utf8_lossy(&bytes).into_owned(), len))
+}
+
+// Returns the last `len` bytes of `word`, None if it has more than `len` bytes
+fn word_bytes(word: &Felt252, len: usize) -> Option<Vec<u8>> {
+    let bytes = word.to_be_bytes();
+    let (high, low) = bytes.split_at(bytes.len() - len);
+    if high.iter().any(|byte| *byte != 0) {
+        return None;
+    }
+    Some(low.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::short_string::encode_short_string;
+    use num_traits::Num;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn decode_short_byte_array() {
+        let felts = [
+            Felt252::from(0),
+            encode_short_string("hello").unwrap(),
+            Felt252::from(5),
+            Felt252::from(42),
+        ];
+        assert_eq!(
+            decode_byte_array_string(&felts),
+            Some((String::from("hello"), 3))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn decode_long_byte_array() {
+        let text = "This ByteArray is longer than a word, caf\u{e9}";
+        let bytes = text.as_bytes();
+        let felts = [
+            Felt252::from(1),
+            Felt252::from_bytes_be(&bytes[..BYTES_IN_WORD]),
+            Felt252::from_bytes_be(&bytes[BYTES_IN_WORD..]),
+            Felt252::from(bytes.len() - BYTES_IN_WORD),
+        ];
+        assert_eq!(
+            decode_byte_array_string(&felts),
+            Some((String::from(text), 4))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn decode_pending_word_with_leading_zeros() {
+        let felts = [Felt252::from(0), Felt252::from(0x41), Felt252::from(2)];
+        assert_eq!(decode_byte_array(&felts), Some((vec![0, 0x41], 3)));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn decode_invalid_byte_array() {
+        // Missing pending word length
+        assert_eq!(
+            decode_byte_array(&[Felt252::from(0), Felt252::from(1)]),
+            None
+        );
+        // Pending word longer than its length
+        assert_eq!(
+            decode_byte_array(&[Felt252::from(0), Felt252::from(0x4142), Felt252::from(1)]),
+            None
+        );
+        // Pending word length of a full word
+        assert_eq!(
+            decode_byte_array(&[Felt252::from(0), Felt252::from(0), Felt252::from(31)]),
+            None
+        );
+        // More full words than felts
+        assert_eq!(
+            decode_byte_array(&[Felt252::from(2), Felt252::from(0)]),
+            None
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn byte_array_magic() {
+        assert!(is_byte_array_magic(
+            &Felt252::from_str_radix(BYTE_ARRAY_MAGIC, 16).unwrap()
+        ));
+        assert!(!is_byte_array_magic(&Felt252::from(0)));
+    }
+}
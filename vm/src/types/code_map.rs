@@ -0,0 +1,216 @@
+//! Classification of the cells of a program into instructions and immediates.
+//!
+//! The data of a program mixes instructions with the immediates following some of them, and a
+//! cell doesn't tell which of the two it holds. A [CodeMap] finds out by following the control
+//! flow from the entrypoints of the program: its main, its `__start__` label and each of its
+//! functions. Jumps to a computed address aren't followed, and the cells which aren't reached,
+//! such as the data embedded with `dw`, are left [Unreached](CellKind::Unreached).
+
+use crate::stdlib::prelude::*;
+
+use num_traits::ToPrimitive;
+
+use crate::{
+    serde::deserialize_program::Identifier,
+    types::{
+        errors::program_errors::ProgramError,
+        instruction::{Op1Addr, Opcode, PcUpdate, Res},
+        program::ProgramData,
+        relocatable::MaybeRelocatable,
+    },
+    vm::decoding::decoder::decode_instruction,
+};
+
+/// What a cell of the program holds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CellKind {
+    /// A cell reached as an instruction, which doesn't decode in malformed programs
+    Instruction,
+    /// The immediate operand of the instruction in the previous cell
+    Immediate,
+    Unreached,
+}
+
+/// The kind of each cell of a program, indexed by its offset.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CodeMap {
+    cells: Vec<CellKind>,
+}
+
+impl CodeMap {
+    /// Classifies the cells of `data` reached from `main`, `start` and the functions among the
+    /// `identifiers`. Fails if a cell is reached both as an instruction and as an immediate, as
+    /// when main points to the immediate of an instruction.
+    pub(crate) fn new<'a>(
+        data: &ProgramData,
+        main: Option<usize>,
+        start: Option<usize>,
+        identifiers: impl IntoIterator<Item = &'a Identifier>,
+    ) -> Result<CodeMap, ProgramError> {
+        let mut cells = vec![CellKind::Unreached; data.len()];
+        let mut pending: Vec<usize> = identifiers
+            .into_iter()
+            .filter(|identifier| identifier.type_.as_deref() == Some("function"))
+            .filter_map(|identifier| identifier.pc)
+            .chain(start)
+            .chain(main)
+            .collect();
+        while let Some(pc) = pending.pop() {
+            match cells.get(pc) {
+                Some(CellKind::Unreached) => (),
+                Some(CellKind::Immediate) => {
+                    return Err(ProgramError::InstructionInImmediate(pc));
+                }
+                Some(CellKind::Instruction) | None => continue,
+            }
+            cells[pc] = CellKind::Instruction;
+            let Some(instruction) = data
+                .get(pc)
+                .and_then(MaybeRelocatable::get_int_ref)
+                .and_then(|encoding| encoding.to_u64())
+                .and_then(|encoding| decode_instruction(encoding).ok())
+            else {
+                continue;
+            };
+
+            let mut immediate = None;
+            if instruction.size() == 2 {
+                match cells.get(pc + 1) {
+                    Some(CellKind::Instruction) => {
+                        return Err(ProgramError::InstructionInImmediate(pc + 1));
+                    }
+                    Some(_) => {
+                        cells[pc + 1] = CellKind::Immediate;
+                        immediate = data.get(pc + 1).and_then(MaybeRelocatable::get_int_ref);
+                    }
+                    None => (),
+                }
+            }
+
+            // Calls return to the next instruction, and jnz falls through to it
+            if instruction.opcode == Opcode::Call
+                || matches!(instruction.pc_update, PcUpdate::Regular | PcUpdate::Jnz)
+            {
+                pending.push(pc + instruction.size());
+            }
+            // Relative jumps to an immediate offset
+            let jumps_to_op1 = match instruction.pc_update {
+                PcUpdate::JumpRel => instruction.res == Res::Op1,
+                PcUpdate::Jnz => true,
+                _ => false,
+            };
+            if jumps_to_op1 && instruction.op1_addr == Op1Addr::Imm {
+                if let Some(target) = immediate
+                    .and_then(|offset| offset.to_signed_felt().to_isize())
+                    .and_then(|offset| pc.checked_add_signed(offset))
+                {
+                    pending.push(target);
+                }
+            }
+        }
+        Ok(CodeMap { cells })
+    }
+
+    /// Returns what the cell at `offset` holds, None if it's past the end of the program or if
+    /// the program wasn't classified.
+    pub fn kind(&self, offset: usize) -> Option<CellKind> {
+        self.cells.get(offset).copied()
+    }
+
+    /// Iterates over the offsets of the instructions of the program, in order.
+    pub fn instructions(&self) -> impl Iterator<Item = usize> + '_ {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, kind)| **kind == CellKind::Instruction)
+            .map(|(offset, _)| offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::*;
+    use assert_matches::assert_matches;
+    use felt::Felt252;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    // main:
+    //   jmp rel 5 if [ap - 1] != 0
+    //   [ap] = 1; ap++
+    //   ret
+    //   [ap] = 2; ap++
+    //   ret
+    //   dw 7
+    fn branching_data() -> ProgramData {
+        ProgramData::try_from(vec![
+            mayberelocatable!(0x020680017fff7fff_i64),
+            mayberelocatable!(5),
+            mayberelocatable!(0x480680017fff8000_i64),
+            mayberelocatable!(1),
+            mayberelocatable!(0x208b7fff7fff7ffe_i64),
+            mayberelocatable!(0x480680017fff8000_i64),
+            mayberelocatable!(2),
+            mayberelocatable!(0x208b7fff7fff7ffe_i64),
+            mayberelocatable!(7),
+        ])
+        .unwrap()
+    }
+
+    fn function(pc: usize) -> Identifier {
+        Identifier {
+            pc: Some(pc),
+            type_: Some(String::from("function")),
+            value: None,
+            full_name: None,
+            members: None,
+            cairo_type: None,
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn classify_branches() {
+        let code_map = CodeMap::new(&branching_data(), Some(0), None, []).unwrap();
+
+        assert_eq!(code_map.instructions().collect::<Vec<_>>(), [0, 2, 4, 5, 7]);
+        assert_eq!(code_map.kind(1), Some(CellKind::Immediate));
+        assert_eq!(code_map.kind(6), Some(CellKind::Immediate));
+        assert_eq!(code_map.kind(8), Some(CellKind::Unreached));
+        assert_eq!(code_map.kind(9), None);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn classify_from_functions() {
+        // The second branch is only reached as a function
+        let mut data: Vec<MaybeRelocatable> = branching_data().iter().cloned().collect();
+        data[0] = mayberelocatable!(0x208b7fff7fff7ffe_i64);
+        data[1] = MaybeRelocatable::from(Felt252::new(-1));
+        let functions = [function(5)];
+
+        let code_map = CodeMap::new(
+            &ProgramData::try_from(data).unwrap(),
+            Some(0),
+            None,
+            &functions,
+        )
+        .unwrap();
+
+        assert_eq!(code_map.instructions().collect::<Vec<_>>(), [0, 5, 7]);
+        assert_eq!(code_map.kind(1), Some(CellKind::Unreached));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn main_in_immediate() {
+        let functions = [function(0)];
+
+        assert_matches!(
+            CodeMap::new(&branching_data(), Some(3), None, &functions),
+            Err(ProgramError::InstructionInImmediate(3))
+        );
+    }
+}
@@ -0,0 +1,137 @@
+//! The constants of a program, as looked up by hints.
+
+use crate::stdlib::{collections::HashMap, ops::Deref, prelude::*};
+
+use felt::Felt252;
+
+/// The values of the `const` identifiers of a program, by full path.
+///
+/// Derefs to the map of full paths to values. Constants can also be looked up by their name,
+/// the last component of their path, through an index built as they are inserted, so hints
+/// referring to a constant without knowing the module declaring it don't scan the whole map.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Constants {
+    by_path: HashMap<String, Felt252>,
+    // Name of each constant to its full path. When several constants share a name, the smallest
+    // path wins, so lookups don't depend on the order in which they were inserted.
+    by_name: HashMap<String, String>,
+}
+
+impl Constants {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Inserts the constant at `path`, returning its previous value if it was already present.
+    pub fn insert(&mut self, path: String, value: Felt252) -> Option<Felt252> {
+        let name = path.rsplit('.').next().unwrap_or(&path);
+        match self.by_name.get_mut(name) {
+            Some(indexed_path) if *indexed_path <= path => {}
+            Some(indexed_path) => *indexed_path = path.clone(),
+            None => {
+                self.by_name.insert(name.to_string(), path.clone());
+            }
+        }
+        self.by_path.insert(path, value)
+    }
+
+    /// Returns the value of the constant named `name`, the last component of its path.
+    /// If several constants share that name, returns the one with the smallest path.
+    pub fn get_by_name(&self, name: &str) -> Option<&Felt252> {
+        self.by_name
+            .get(name)
+            .and_then(|path| self.by_path.get(path))
+    }
+}
+
+impl Deref for Constants {
+    type Target = HashMap<String, Felt252>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.by_path
+    }
+}
+
+impl FromIterator<(String, Felt252)> for Constants {
+    fn from_iter<I: IntoIterator<Item = (String, Felt252)>>(iter: I) -> Self {
+        let mut constants = Constants::new();
+        constants.extend(iter);
+        constants
+    }
+}
+
+impl Extend<(String, Felt252)> for Constants {
+    fn extend<I: IntoIterator<Item = (String, Felt252)>>(&mut self, iter: I) {
+        for (path, value) in iter {
+            self.insert(path, value);
+        }
+    }
+}
+
+impl<const N: usize> From<[(String, Felt252); N]> for Constants {
+    fn from(constants: [(String, Felt252); N]) -> Self {
+        constants.into_iter().collect()
+    }
+}
+
+impl From<HashMap<String, Felt252>> for Constants {
+    fn from(constants: HashMap<String, Felt252>) -> Self {
+        constants.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn get_by_path_and_name() {
+        let constants = Constants::from([
+            ("__main__.SHIFT".to_string(), Felt252::new(1)),
+            (
+                "starkware.cairo.common.math.UPPER_BOUND".to_string(),
+                Felt252::new(2),
+            ),
+        ]);
+        assert_eq!(constants.get("__main__.SHIFT"), Some(&Felt252::new(1)));
+        assert_eq!(constants.get("SHIFT"), None);
+        assert_eq!(constants.get_by_name("SHIFT"), Some(&Felt252::new(1)));
+        assert_eq!(constants.get_by_name("UPPER_BOUND"), Some(&Felt252::new(2)));
+        assert_eq!(constants.get_by_name("math.UPPER_BOUND"), None);
+        assert_eq!(constants.get_by_name("LOWER_BOUND"), None);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn get_by_name_shared_by_several_constants() {
+        let paths = ["b.SHIFT", "a.SHIFT", "c.SHIFT"];
+        let mut constants = Constants::new();
+        for (value, path) in paths.iter().enumerate() {
+            constants.insert(path.to_string(), Felt252::new(value));
+        }
+        assert_eq!(constants.len(), 3);
+        assert_eq!(constants.get_by_name("SHIFT"), Some(&Felt252::new(1)));
+
+        let reversed: Constants = paths
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(value, path)| (path.to_string(), Felt252::new(value)))
+            .collect();
+        assert_eq!(reversed, constants);
+        assert_eq!(reversed.get_by_name("SHIFT"), Some(&Felt252::new(1)));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn constant_without_module() {
+        let constants: Constants = [("SHIFT".to_string(), Felt252::new(3))]
+            .into_iter()
+            .collect();
+        assert_eq!(constants.get_by_name("SHIFT"), Some(&Felt252::new(3)));
+    }
+}
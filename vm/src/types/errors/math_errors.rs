@@ -33,6 +33,10 @@ pub enum MathError {
     // Relocatable Operations
     #[error("Cant convert felt: {0} to Relocatable")]
     Felt252ToRelocatable(Box<Felt252>),
+    #[error("Cant convert [{}, {}] to Relocatable, the offset cant be negative", (*.0)[0], (*.0)[1])]
+    IntsToRelocatable(Box<[i64; 2]>),
+    #[error("Cant convert relocatable: {0} to Felt252")]
+    RelocatableToFelt252(Box<Relocatable>),
     #[error("Operation failed: {} - {}, offsets cant be negative", (*.0).0, (*.0).1)]
     RelocatableSubFelt252NegOffset(Box<(Relocatable, Felt252)>),
     #[error("Operation failed: {} - {}, offsets cant be negative", (*.0).0, (*.0).1)]
@@ -60,6 +64,12 @@ pub enum MathError {
     Felt252ToUsizeConversion(Box<Felt252>),
     #[error("Conversion to u64 failed for Felt252 {0}")]
     Felt252ToU64Conversion(Box<Felt252>),
+    #[error("Conversion to u128 failed for Felt252 {0}")]
+    Felt252ToU128Conversion(Box<Felt252>),
+    #[error("Short strings can't be longer than 31 characters, got {0}")]
+    ShortStringTooLong(usize),
+    #[error("Short strings can only hold ASCII characters, got {0:?}")]
+    NonAsciiShortString(char),
 }
 
 #[cfg(test)]
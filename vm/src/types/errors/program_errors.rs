@@ -4,6 +4,8 @@ use thiserror_no_std::Error;
 
 use felt::PRIME_STR;
 
+use crate::vm::errors::vm_errors::VirtualMachineError;
+
 #[derive(Debug, Error)]
 pub enum ProgramError {
     #[cfg(feature = "std")]
@@ -17,6 +19,30 @@ pub enum ProgramError {
     ConstWithoutValue(String),
     #[error("Expected prime {PRIME_STR}, got {0}")]
     PrimeDiffers(String),
+    #[error("Identifier {0} has conflicting definitions in the linked programs")]
+    LinkConflict(String),
+    #[error("Unable to compute the size of cairo type {0}")]
+    UnknownCairoType(String),
+    #[error("Program has no compiler_version, recompile it with a newer cairo-lang version or disable strict version checks")]
+    MissingCompilerVersion,
+    #[error("Invalid compiler version {0}")]
+    InvalidCompilerVersion(String),
+    #[error("Program was compiled with cairo-lang {}, but at least version {} is required, recompile it with a newer version", (*.0).0, (*.0).1)]
+    CompilerVersionTooOld(Box<(String, String)>),
+    #[error("Program was compiled with cairo-lang {}, but at most version {} is supported, recompile it with an older version", (*.0).0, (*.0).1)]
+    CompilerVersionTooNew(Box<(String, String)>),
+    #[error("Unknown builtin {0}")]
+    UnknownBuiltin(String),
+    #[error("Invalid instruction at offset {} of the program: {}", (*.0).0, (*.0).1)]
+    InvalidInstruction(Box<(usize, VirtualMachineError)>),
+    #[error("Offset {0} of the program is reached both as an instruction and as the immediate of the previous one")]
+    InstructionInImmediate(usize),
+    #[error("The instruction at offset {0} of the program jumps to an absolute address, so its function can't be extracted")]
+    AbsoluteJumpInExtraction(usize),
+    #[error("Offset {0} of the program holds a relocatable value, so the program can't be hashed")]
+    RelocatableInData(usize),
+    #[error("Program data has {0} distinct values, more than its cells can index")]
+    TooManyDistinctValues(usize),
 }
 
 #[cfg(test)]
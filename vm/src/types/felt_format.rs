@@ -0,0 +1,172 @@
+//! How felts are displayed in memory dumps and program outputs.
+//!
+//! Felts are displayed in decimal by default. Starknet selectors and other hashes are easier to
+//! compare in hex, and felts holding text are easier to read as short strings, so
+//! [Memory::display_in_format](crate::vm::vm_memory::memory::Memory::display_in_format) and
+//! [VirtualMachine::write_output_in_format](crate::vm::vm_core::VirtualMachine::write_output_in_format)
+//! take the format to display felts in. [FeltFormat::display] and [FeltFormat::display_value]
+//! display a single felt or value.
+
+use crate::stdlib::{
+    fmt::{self, Display},
+    prelude::*,
+    str::FromStr,
+};
+
+use felt::Felt252;
+use num_traits::Zero;
+use serde::{Deserialize, Serialize};
+
+use crate::types::{relocatable::MaybeRelocatable, short_string::decode_short_string};
+
+/// The format felts are displayed in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeltFormat {
+    /// `1819043144`
+    #[default]
+    Decimal,
+    /// `0x6c6c6548`
+    Hex,
+    /// `'lleH'`, or in hex like [FeltFormat::Hex] if the felt isn't made of printable ASCII
+    /// characters
+    ShortString,
+}
+
+impl FeltFormat {
+    /// Returns a value displaying `felt` in this format.
+    pub fn display(self, felt: &Felt252) -> FeltDisplay<'_> {
+        FeltDisplay { felt, format: self }
+    }
+
+    /// Returns a value displaying `value` with its felt in this format.
+    pub fn display_value(self, value: &MaybeRelocatable) -> ValueDisplay<'_> {
+        ValueDisplay {
+            value,
+            format: self,
+        }
+    }
+}
+
+impl FromStr for FeltFormat {
+    type Err = String;
+
+    /// Parses `dec`, `hex` or `str`, as well as the full names `decimal` and `short_string`.
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "dec" | "decimal" => Ok(FeltFormat::Decimal),
+            "hex" => Ok(FeltFormat::Hex),
+            "str" | "short_string" => Ok(FeltFormat::ShortString),
+            _ => Err(format!(
+                "{format} is not a valid felt format, expected dec, hex or str"
+            )),
+        }
+    }
+}
+
+/// Displays a felt in a given format, see [FeltFormat::display].
+#[derive(Clone, Copy, Debug)]
+pub struct FeltDisplay<'a> {
+    felt: &'a Felt252,
+    format: FeltFormat,
+}
+
+impl<'a> Display for FeltDisplay<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.format {
+            FeltFormat::Decimal => write!(f, "{}", self.felt),
+            FeltFormat::Hex => write!(f, "0x{}", self.felt.to_str_radix(16)),
+            FeltFormat::ShortString => match decode_short_string(self.felt) {
+                Some(string) if !self.felt.is_zero() => write!(f, "'{string}'"),
+                _ => write!(f, "0x{}", self.felt.to_str_radix(16)),
+            },
+        }
+    }
+}
+
+/// Displays a value, with its felt in a given format, see [FeltFormat::display_value].
+#[derive(Clone, Copy, Debug)]
+pub struct ValueDisplay<'a> {
+    value: &'a MaybeRelocatable,
+    format: FeltFormat,
+}
+
+impl<'a> Display for ValueDisplay<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.value {
+            MaybeRelocatable::RelocatableValue(rel) => rel.fmt(f),
+            MaybeRelocatable::Int(num) => self.format.display(num).fmt(f),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{stdlib::collections::HashMap, utils::test_utils::*};
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn display_felt_in_each_format() {
+        let felt = Felt252::new(0x6c6c6548_u64);
+        assert_eq!(FeltFormat::Decimal.display(&felt).to_string(), "1819043144");
+        assert_eq!(FeltFormat::Hex.display(&felt).to_string(), "0x6c6c6548");
+        assert_eq!(FeltFormat::ShortString.display(&felt).to_string(), "'lleH'");
+        // Not printable, nor a string at all
+        assert_eq!(
+            FeltFormat::ShortString
+                .display(&Felt252::new(7))
+                .to_string(),
+            "0x7"
+        );
+        assert_eq!(
+            FeltFormat::ShortString
+                .display(&Felt252::zero())
+                .to_string(),
+            "0x0"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn parse_felt_format() {
+        assert_eq!("dec".parse(), Ok(FeltFormat::Decimal));
+        assert_eq!("hex".parse(), Ok(FeltFormat::Hex));
+        assert_eq!("short_string".parse(), Ok(FeltFormat::ShortString));
+        assert!("oct".parse::<FeltFormat>().is_err());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn display_value_in_format() {
+        assert_eq!(
+            FeltFormat::Hex
+                .display_value(&MaybeRelocatable::from(255))
+                .to_string(),
+            "0xff"
+        );
+        assert_eq!(
+            FeltFormat::Hex
+                .display_value(&mayberelocatable!(1, 0))
+                .to_string(),
+            "1:0"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn display_memory_dumps_in_format() {
+        let segments = segments![((0, 0), 255), ((0, 1), (1, 0))];
+        assert_eq!(
+            segments
+                .memory
+                .display_in_format(FeltFormat::Hex)
+                .to_string(),
+            "(0,0) : 0xff\n(0,1) : 1:0\n}\n"
+        );
+        assert_eq!(segments.memory.to_string(), "(0,0) : 255\n(0,1) : 1:0\n}\n");
+    }
+}
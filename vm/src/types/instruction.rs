@@ -72,6 +72,28 @@ pub enum Opcode {
     Ret,
 }
 
+/// The shape of an instruction, as far as its register updates are concerned.
+///
+/// Computed once when the instruction is decoded, so the VM can dispatch on it at each step
+/// instead of going through every update field of the instruction.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum InstructionKind {
+    /// `assert_eq` moving to the next instruction, with an optional `ap++`
+    AssertEq,
+    /// `call abs` and `call rel`
+    Call,
+    /// `ret`
+    Ret,
+    /// `jmp abs` and `jmp rel`, with an optional `ap++`
+    Jump,
+    /// `jmp rel if != 0`, with an optional `ap++`
+    Jnz,
+    /// `ap += ...`
+    AddAp,
+    /// Any other combination of flags
+    Generic,
+}
+
 impl Instruction {
     pub fn size(&self) -> usize {
         match self.op1_addr {
@@ -79,6 +101,29 @@ impl Instruction {
             _ => 1,
         }
     }
+
+    pub fn kind(&self) -> InstructionKind {
+        match (self.opcode, self.pc_update, self.ap_update, self.fp_update) {
+            (Opcode::AssertEq, PcUpdate::Regular, _, FpUpdate::Regular) => {
+                InstructionKind::AssertEq
+            }
+            (
+                Opcode::Call,
+                PcUpdate::Jump | PcUpdate::JumpRel,
+                ApUpdate::Add2,
+                FpUpdate::APPlus2,
+            ) => InstructionKind::Call,
+            (Opcode::Ret, PcUpdate::Jump, ApUpdate::Regular, FpUpdate::Dst) => InstructionKind::Ret,
+            (Opcode::NOp, PcUpdate::Jump | PcUpdate::JumpRel, _, FpUpdate::Regular) => {
+                InstructionKind::Jump
+            }
+            (Opcode::NOp, PcUpdate::Jnz, _, FpUpdate::Regular) => InstructionKind::Jnz,
+            (Opcode::NOp, PcUpdate::Regular, ApUpdate::Add, FpUpdate::Regular) => {
+                InstructionKind::AddAp
+            }
+            _ => InstructionKind::Generic,
+        }
+    }
 }
 
 // Returns True if the given instruction looks like a call instruction
@@ -119,6 +164,27 @@ mod tests {
         assert!(!is_call_instruction(&encoded_instruction));
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn instruction_kinds() {
+        let kind =
+            |encoded_instruction: u64| decode_instruction(encoded_instruction).unwrap().kind();
+        // [ap] = [fp + -3] + 1; ap++
+        assert_eq!(kind(0x482680017ffd8000), InstructionKind::AssertEq);
+        // call rel
+        assert_eq!(kind(0x1104800180018000), InstructionKind::Call);
+        // ret
+        assert_eq!(kind(0x208b7fff7fff7ffe), InstructionKind::Ret);
+        // jmp rel 0
+        assert_eq!(kind(0x10780017fff7fff), InstructionKind::Jump);
+        // jmp rel if [ap + -1] != 0
+        assert_eq!(kind(0x20680017fff7fff), InstructionKind::Jnz);
+        // ap += 2
+        assert_eq!(kind(0x40780017fff7fff), InstructionKind::AddAp);
+        // A bare ap++, which the compiler doesn't emit
+        assert_eq!(kind(0x80b7fff7fff7fff), InstructionKind::Generic);
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn is_call_instruction_invalid() {
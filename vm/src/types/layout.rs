@@ -18,7 +18,37 @@ pub(crate) struct CairoLayout {
     pub(crate) _cpu_instance_def: CpuInstanceDef,
 }
 
+/// Builds one of the standard layouts.
+pub(crate) type LayoutInstance = fn() -> CairoLayout;
+
+/// The standard layouts and their names, from the one allotting the fewest cells to builtins.
+pub(crate) const LAYOUTS: [(&str, LayoutInstance); 9] = [
+    ("plain", CairoLayout::plain_instance),
+    ("small", CairoLayout::small_instance),
+    ("dex", CairoLayout::dex_instance),
+    ("starknet", CairoLayout::starknet_instance),
+    (
+        "starknet_with_keccak",
+        CairoLayout::starknet_with_keccak_instance,
+    ),
+    (
+        "recursive_large_output",
+        CairoLayout::recursive_large_output_instance,
+    ),
+    ("all_cairo", CairoLayout::all_cairo_instance),
+    ("all_solidity", CairoLayout::all_solidity_instance),
+    ("dynamic", CairoLayout::dynamic_instance),
+];
+
 impl CairoLayout {
+    /// Returns the layout named `name`, if any.
+    pub(crate) fn from_name(name: &str) -> Option<CairoLayout> {
+        LAYOUTS
+            .iter()
+            .find(|(layout_name, _)| *layout_name == name)
+            .map(|(_, instance)| instance())
+    }
+
     pub(crate) fn plain_instance() -> CairoLayout {
         CairoLayout {
             _name: String::from("plain"),
@@ -169,6 +199,16 @@ mod tests {
     #[cfg(target_arch = "wasm32")]
     use wasm_bindgen_test::*;
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn layout_from_name() {
+        assert_eq!(
+            CairoLayout::from_name("all_cairo").unwrap().builtins,
+            BuiltinsInstanceDef::all_cairo()
+        );
+        assert!(CairoLayout::from_name("all_cairo_builtin").is_none());
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn get_plain_instance() {
@@ -1,7 +1,14 @@
+pub mod byte_array;
+pub mod code_map;
+pub mod constants;
 pub mod errors;
 pub mod exec_scope;
+pub mod felt_format;
 pub mod instance_definitions;
 pub mod instruction;
 pub mod layout;
 pub mod program;
 pub mod relocatable;
+pub mod scope_serde;
+pub mod scope_types;
+pub mod short_string;
@@ -1,20 +1,37 @@
-use crate::stdlib::{collections::HashMap, prelude::*, sync::Arc};
+use crate::stdlib::{
+    collections::{BTreeMap, HashMap},
+    ops::{Bound, RangeBounds},
+    prelude::*,
+    sync::Arc,
+};
 
 #[cfg(feature = "cairo-1-hints")]
-use crate::serde::deserialize_program::{ApTracking, FlowTrackingData};
+use crate::serde::deserialize_program::ApTracking;
 use crate::{
     hint_processor::hint_processor_definition::HintReference,
     serde::deserialize_program::{
-        deserialize_and_parse_program, Attribute, BuiltinName, HintParams, Identifier,
-        InstructionLocation, OffsetValue, ReferenceManager,
+        deserialize_and_parse_program, deserialize_and_parse_program_with_options, Attribute,
+        BuiltinName, FlowTrackingData, HintParams, Identifier, InstructionLocation, Location,
+        OffsetValue, ReferenceManager,
     },
     types::{
-        errors::program_errors::ProgramError, instruction::Register, relocatable::MaybeRelocatable,
+        code_map::CodeMap,
+        constants::Constants,
+        errors::program_errors::ProgramError,
+        instruction::{Op1Addr, PcUpdate, Register, Res},
+        relocatable::MaybeRelocatable,
+    },
+    vm::{
+        decoding::decoder::{decode_instruction, decode_strict},
+        errors::vm_errors::VirtualMachineError,
     },
 };
 #[cfg(feature = "cairo-1-hints")]
 use cairo_lang_starknet::casm_contract_class::CasmContractClass;
 use felt::{Felt252, PRIME_STR};
+use num_traits::{ToPrimitive, Zero};
+use sha2::{Digest, Sha256};
+use starknet_crypto::{pedersen_hash, FieldElement};
 
 #[cfg(feature = "std")]
 use std::path::Path;
@@ -42,22 +59,179 @@ use std::path::Path;
 // Fields in `Program` (other than `SharedProgramData` itself) are used by the main logic.
 #[derive(Clone, Default, Debug, PartialEq, Eq)]
 pub(crate) struct SharedProgramData {
-    pub(crate) data: Vec<MaybeRelocatable>,
+    pub(crate) data: ProgramData,
     pub(crate) hints: HashMap<usize, Vec<HintParams>>,
     pub(crate) main: Option<usize>,
     //start and end labels will only be used in proof-mode
     pub(crate) start: Option<usize>,
     pub(crate) end: Option<usize>,
     pub(crate) error_message_attributes: Vec<Attribute>,
+    // The attributes of the program other than `error_message`
+    pub(crate) custom_attributes: Vec<Attribute>,
     pub(crate) instruction_locations: Option<HashMap<usize, InstructionLocation>>,
-    pub(crate) identifiers: HashMap<String, Identifier>,
+    // Sorted by name, so that identifiers are listed and searched without sorting them each time
+    pub(crate) identifiers: BTreeMap<String, Identifier>,
     pub(crate) reference_manager: Vec<HintReference>,
+    pub(crate) compiler_version: Option<String>,
+    pub(crate) code_map: CodeMap,
+}
+
+/// The data of a program, each distinct value being stored once.
+///
+/// Programs repeat the same instruction encodings and immediates many times, so each cell only
+/// holds the index of its value in a pool of the distinct values of the program, kept in order of
+/// first appearance.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) struct ProgramData {
+    values: Vec<MaybeRelocatable>,
+    cells: Vec<u32>,
+    // SHA-256 digest of the values of the cells, computed as they are collected, so that caches
+    // can be keyed by the contents of a program without hashing all of its data on each lookup
+    digest: [u8; 32],
+}
+
+impl ProgramData {
+    pub(crate) fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub(crate) fn iter(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = &MaybeRelocatable> + ExactSizeIterator {
+        self.cells.iter().map(|cell| &self.values[*cell as usize])
+    }
+
+    pub(crate) fn get(&self, offset: usize) -> Option<&MaybeRelocatable> {
+        self.cells
+            .get(offset)
+            .map(|cell| &self.values[*cell as usize])
+    }
+
+    /// The SHA-256 digest of the data, equal for programs with the same data.
+    pub(crate) fn digest(&self) -> &[u8; 32] {
+        &self.digest
+    }
+}
+
+impl Default for ProgramData {
+    fn default() -> Self {
+        ProgramData {
+            values: Vec::new(),
+            cells: Vec::new(),
+            digest: Sha256::new().finalize().into(),
+        }
+    }
+}
+
+impl ProgramData {
+    /// Collects the values of a program, failing with [ProgramError::TooManyDistinctValues] if
+    /// there are more distinct values than the indices of the cells can hold.
+    pub(crate) fn try_from_iter(
+        iter: impl IntoIterator<Item = MaybeRelocatable>,
+    ) -> Result<Self, ProgramError> {
+        let mut data = ProgramData::default();
+        let mut indices = HashMap::new();
+        let mut hasher = Sha256::new();
+        for value in iter {
+            match &value {
+                MaybeRelocatable::Int(num) => {
+                    hasher.update([0_u8]);
+                    hasher.update(num.to_be_bytes());
+                }
+                MaybeRelocatable::RelocatableValue(rel) => {
+                    hasher.update([1_u8]);
+                    hasher.update((rel.segment_index as i64).to_le_bytes());
+                    hasher.update((rel.offset as u64).to_le_bytes());
+                }
+            }
+            let index = match indices.get(&value) {
+                Some(index) => *index,
+                None => {
+                    let index = value_index(data.values.len())?;
+                    indices.insert(value.clone(), index);
+                    data.values.push(value);
+                    index
+                }
+            };
+            data.cells.push(index);
+        }
+        data.digest = hasher.finalize().into();
+        Ok(data)
+    }
+}
+
+// The index of the next distinct value of a program with `n_values` distinct values
+fn value_index(n_values: usize) -> Result<u32, ProgramError> {
+    u32::try_from(n_values).map_err(|_| ProgramError::TooManyDistinctValues(n_values))
+}
+
+impl TryFrom<Vec<MaybeRelocatable>> for ProgramData {
+    type Error = ProgramError;
+
+    fn try_from(data: Vec<MaybeRelocatable>) -> Result<Self, ProgramError> {
+        ProgramData::try_from_iter(data)
+    }
+}
+
+impl PartialEq<Vec<MaybeRelocatable>> for ProgramData {
+    fn eq(&self, other: &Vec<MaybeRelocatable>) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+/// Options to customize how a [Program] is loaded.
+///
+/// Programs are checked against the `min_compiler_version` and `max_compiler_version` bounds
+/// (inclusive) when they record their `compiler_version`. In `strict` mode, programs that don't
+/// record it are rejected, otherwise the check is skipped for them. Programs using builtins
+/// unknown to the VM are also rejected in `strict` mode, and fail to run otherwise.
+/// With `validate_bytecode`, the instructions of the program are checked as by
+/// [Program::validate_bytecode] before it's returned.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ProgramLoadOptions<'a> {
+    pub entrypoint: Option<&'a str>,
+    pub min_compiler_version: Option<&'a str>,
+    pub max_compiler_version: Option<&'a str>,
+    pub strict: bool,
+    pub validate_bytecode: bool,
+}
+
+/// Layout of a single member of a function's `ImplicitArgs`, `Args` or `Return` type.
+/// `name` is empty for unnamed tuple elements.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemberLayout {
+    pub name: String,
+    pub cairo_type: String,
+    pub offset: usize,
+    pub size: usize,
+}
+
+/// Argument and return value layouts of a program function, sorted by offset
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FunctionSignature {
+    pub implicit_args: Vec<MemberLayout>,
+    pub args: Vec<MemberLayout>,
+    pub return_values: Vec<MemberLayout>,
+}
+
+impl FunctionSignature {
+    pub fn implicit_args_size(&self) -> usize {
+        self.implicit_args.iter().map(|member| member.size).sum()
+    }
+
+    pub fn args_size(&self) -> usize {
+        self.args.iter().map(|member| member.size).sum()
+    }
+
+    pub fn return_values_size(&self) -> usize {
+        self.return_values.iter().map(|member| member.size).sum()
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Program {
     pub(crate) shared_program_data: Arc<SharedProgramData>,
-    pub(crate) constants: HashMap<String, Felt252>,
+    pub(crate) constants: Constants,
     pub(crate) builtins: Vec<BuiltinName>,
 }
 
@@ -73,7 +247,7 @@ impl Program {
         error_message_attributes: Vec<Attribute>,
         instruction_locations: Option<HashMap<usize, InstructionLocation>>,
     ) -> Result<Program, ProgramError> {
-        let mut constants = HashMap::new();
+        let mut constants = Constants::new();
         for (key, value) in identifiers.iter() {
             if value.type_.as_deref() == Some("const") {
                 let value = value
@@ -83,6 +257,8 @@ impl Program {
                 constants.insert(key.clone(), value);
             }
         }
+        let data = ProgramData::try_from(data)?;
+        let code_map = CodeMap::new(&data, main, None, identifiers.values())?;
         let shared_program_data = SharedProgramData {
             data,
             hints,
@@ -90,9 +266,12 @@ impl Program {
             start: None,
             end: None,
             error_message_attributes,
+            custom_attributes: Vec::new(),
             instruction_locations,
-            identifiers,
+            identifiers: identifiers.into_iter().collect(),
             reference_manager: Self::get_reference_list(&reference_manager),
+            compiler_version: None,
+            code_map,
         };
         Ok(Self {
             shared_program_data: Arc::new(shared_program_data),
@@ -111,6 +290,18 @@ impl Program {
         deserialize_and_parse_program(bytes, entrypoint)
     }
 
+    pub fn from_bytes_with_options(
+        bytes: &[u8],
+        options: &ProgramLoadOptions,
+    ) -> Result<Program, ProgramError> {
+        deserialize_and_parse_program_with_options(bytes, options)
+    }
+
+    /// Returns the version of cairo-lang the program was compiled with, if it was recorded
+    pub fn compiler_version(&self) -> Option<&str> {
+        self.shared_program_data.compiler_version.as_deref()
+    }
+
     pub fn prime(&self) -> &str {
         _ = self;
         PRIME_STR
@@ -128,19 +319,718 @@ impl Program {
         self.shared_program_data.data.len()
     }
 
+    /// Decodes the instructions of the program with [decode_strict], as classified by its
+    /// [code map](Program::code_map), so malformed bytecode is caught before the program is run.
+    /// Fails on the first cell reached as an instruction which isn't a valid one.
+    pub fn validate_bytecode(&self) -> Result<(), ProgramError> {
+        let data = &self.shared_program_data.data;
+        for offset in self.shared_program_data.code_map.instructions() {
+            data.get(offset)
+                .and_then(MaybeRelocatable::get_int_ref)
+                .and_then(|encoding| encoding.to_u64())
+                .ok_or(VirtualMachineError::InvalidInstructionEncoding)
+                .and_then(decode_strict)
+                .map_err(|error| ProgramError::InvalidInstruction(Box::new((offset, error))))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the hash of the program, as computed by the bootloader with
+    /// `compute_program_hash_chain` of cairo-lang: the pedersen hash chain of the length of the
+    /// hashed data, followed by `bootloader_version`, the pc of main, the number of builtins,
+    /// the builtin names as short strings and the program data.
+    pub fn hash_chain(&self, bootloader_version: usize) -> Result<Felt252, ProgramError> {
+        let main = self
+            .shared_program_data
+            .main
+            .ok_or_else(|| ProgramError::EntrypointNotFound(String::from("main")))?;
+        // The hashed data starts with its own length, set once the data is complete
+        let mut data = vec![
+            Felt252::zero(),
+            Felt252::from(bootloader_version),
+            Felt252::from(main),
+            Felt252::from(self.builtins.len()),
+        ];
+        data.extend(
+            self.builtins
+                .iter()
+                .map(|builtin| Felt252::from_bytes_be(builtin.to_string().as_bytes())),
+        );
+        for (offset, value) in self.iter_data().enumerate() {
+            let value = value
+                .get_int_ref()
+                .ok_or(ProgramError::RelocatableInData(offset))?;
+            data.push(value.clone());
+        }
+
+        data[0] = Felt252::from(data.len() - 1);
+
+        let to_field_element = |value: &Felt252| {
+            FieldElement::from_bytes_be(&value.to_be_bytes()).unwrap_or(FieldElement::ZERO)
+        };
+        let mut values = data.iter().rev().map(to_field_element);
+        let last = values.next().unwrap_or(FieldElement::ZERO);
+        let hash = values.fold(last, |hash, value| pedersen_hash(&value, &hash));
+        Ok(Felt252::from_bytes_be(&hash.to_bytes_be()))
+    }
+
+    /// Returns which cells of the program are instructions and which are immediates.
+    pub fn code_map(&self) -> &CodeMap {
+        &self.shared_program_data.code_map
+    }
+
     pub fn builtins_len(&self) -> usize {
         self.builtins.len()
     }
 
+    /// Returns the hints at `pc`, in the order they are executed.
+    pub fn hints_at(&self, pc: usize) -> &[HintParams] {
+        self.shared_program_data
+            .hints
+            .get(&pc)
+            .map_or(&[], Vec::as_slice)
+    }
+
+    /// Iterates over the hints of the program grouped by pc, in increasing pc order. Pcs without
+    /// hints are skipped.
+    pub fn hints_by_pc(&self) -> impl Iterator<Item = (usize, &[HintParams])> {
+        let mut hints: Vec<(usize, &[HintParams])> = self
+            .shared_program_data
+            .hints
+            .iter()
+            .filter(|(_, hints)| !hints.is_empty())
+            .map(|(pc, hints)| (*pc, hints.as_slice()))
+            .collect();
+        hints.sort_unstable_by_key(|(pc, _)| *pc);
+        hints.into_iter()
+    }
+
+    /// Iterates over the references of the program, indexed by the `reference_ids` of the
+    /// flow tracking data of its hints.
+    pub fn iter_references(&self) -> impl Iterator<Item = &HintReference> {
+        self.shared_program_data.reference_manager.iter()
+    }
+
+    /// Returns the `error_message` attributes of the program, giving the message of the errors
+    /// raised within their pc range.
+    pub fn error_attributes(&self) -> &[Attribute] {
+        &self.shared_program_data.error_message_attributes
+    }
+
+    /// Iterates over the attributes of the program named `name`, such as the custom attributes
+    /// emitted by build tooling, in order of appearance.
+    pub fn attributes_named<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Attribute> {
+        self.attributes()
+            .filter(move |attribute| attribute.name == name)
+    }
+
+    /// Iterates over all the attributes of the program, the `error_message` ones first.
+    pub fn attributes(&self) -> impl Iterator<Item = &Attribute> {
+        self.shared_program_data
+            .error_message_attributes
+            .iter()
+            .chain(self.shared_program_data.custom_attributes.iter())
+    }
+
+    /// Returns the pc of the `__start__` label, only present in programs compiled for proof mode.
+    pub fn start(&self) -> Option<usize> {
+        self.shared_program_data.start
+    }
+
+    /// Returns the pc of the `__end__` label, only present in programs compiled for proof mode.
+    pub fn end(&self) -> Option<usize> {
+        self.shared_program_data.end
+    }
+
     pub fn get_identifier(&self, id: &str) -> Option<&Identifier> {
         self.shared_program_data.identifiers.get(id)
     }
 
+    /// Iterates over the program's identifiers, sorted by name.
     pub fn iter_identifiers(&self) -> impl Iterator<Item = (&str, &Identifier)> {
         self.shared_program_data
             .identifiers
             .iter()
-            .map(|(cairo_type, identifier)| (cairo_type.as_str(), identifier))
+            .map(|(name, identifier)| (name.as_str(), identifier))
+    }
+
+    /// Returns the location of the instruction at `pc`, if the program has debug info for it.
+    pub fn location_for_pc(&self, pc: usize) -> Option<ResolvedInstructionLocation<'_>> {
+        let instruction_location = self
+            .shared_program_data
+            .instruction_locations
+            .as_ref()?
+            .get(&pc)?;
+        Some(ResolvedInstructionLocation::new(pc, instruction_location))
+    }
+
+    /// Iterates over the locations of the instructions with a pc in `pcs`, sorted by pc.
+    /// Instructions without debug info are skipped.
+    pub fn locations_in_range(
+        &self,
+        pcs: impl RangeBounds<usize>,
+    ) -> impl Iterator<Item = ResolvedInstructionLocation<'_>> {
+        let mut locations: Vec<_> = self
+            .shared_program_data
+            .instruction_locations
+            .iter()
+            .flatten()
+            .filter(|(pc, _)| pcs.contains(*pc))
+            .map(|(pc, instruction_location)| {
+                ResolvedInstructionLocation::new(*pc, instruction_location)
+            })
+            .collect();
+        locations.sort_unstable_by_key(|location| location.pc);
+        locations.into_iter()
+    }
+
+    /// Iterates over the identifiers whose name matches `pattern`, sorted by name.
+    ///
+    /// Patterns containing `*` (any sequence of characters, dots included) or `?` (any single
+    /// character) are matched against the whole name, e.g. `__main__.*` or `*.SIZEOF_LOCALS`.
+    /// Other patterns match the names they are a suffix of, on a `.` boundary, so `SIZEOF_LOCALS`
+    /// and `main.SIZEOF_LOCALS` both match `__main__.main.SIZEOF_LOCALS`.
+    pub fn find_identifiers<'a>(
+        &'a self,
+        pattern: &'a str,
+    ) -> impl Iterator<Item = (&'a str, &'a Identifier)> + 'a {
+        // Names matching a glob pattern start with its literal prefix, so only that range of the
+        // sorted identifiers is scanned
+        let wildcard = pattern.find(['*', '?']);
+        let prefix = &pattern[..wildcard.unwrap_or(0)];
+        self.shared_program_data
+            .identifiers
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .map(|(name, identifier)| (name.as_str(), identifier))
+            .take_while(move |(name, _)| name.starts_with(prefix))
+            .filter(move |(name, _)| {
+                if wildcard.is_some() {
+                    glob_matches(pattern, name)
+                } else {
+                    name.strip_suffix(pattern)
+                        .is_some_and(|prefix| prefix.is_empty() || prefix.ends_with('.'))
+                }
+            })
+    }
+
+    /// Links `library` into this program, returning a new [`Program`] with the library's code
+    /// appended after this program's data.
+    ///
+    /// The library's pcs (hints, identifiers, error attributes and instruction locations) are
+    /// shifted by the length of this program's data, and its hint reference ids are shifted by the
+    /// length of this program's reference manager. The entrypoint (`main`, `start` and `end`) of
+    /// `self` is kept, and builtins are merged preserving the canonical builtin order.
+    ///
+    /// Identifiers defined in both programs must agree, with the exception of labels and
+    /// functions: in that case this program's definition is kept, as the library's copy of the code
+    /// remains reachable through the relative calls in its own body.
+    pub fn link(&self, library: &Program) -> Result<Program, ProgramError> {
+        let host = &self.shared_program_data;
+        let lib = &library.shared_program_data;
+        let pc_offset = host.data.len();
+        let reference_offset = host.reference_manager.len();
+
+        let offset_flow_tracking_data = |flow_tracking_data: &mut FlowTrackingData| {
+            flow_tracking_data
+                .reference_ids
+                .values_mut()
+                .for_each(|id| *id += reference_offset)
+        };
+
+        let data = ProgramData::try_from_iter(host.data.iter().chain(lib.data.iter()).cloned())?;
+
+        let mut hints = host.hints.clone();
+        for (pc, hint_params) in lib.hints.iter() {
+            let hint_params = hint_params
+                .iter()
+                .cloned()
+                .map(|mut hint| {
+                    offset_flow_tracking_data(&mut hint.flow_tracking_data);
+                    hint
+                })
+                .collect();
+            hints.insert(pc + pc_offset, hint_params);
+        }
+
+        let mut reference_manager = host.reference_manager.clone();
+        reference_manager.extend(lib.reference_manager.iter().cloned());
+
+        let offset_attribute = |attribute: &Attribute| {
+            let mut attribute = attribute.clone();
+            attribute.start_pc += pc_offset;
+            attribute.end_pc += pc_offset;
+            if let Some(flow_tracking_data) = attribute.flow_tracking_data.as_mut() {
+                offset_flow_tracking_data(flow_tracking_data);
+            }
+            attribute
+        };
+        let mut error_message_attributes = host.error_message_attributes.clone();
+        error_message_attributes.extend(lib.error_message_attributes.iter().map(offset_attribute));
+        let mut custom_attributes = host.custom_attributes.clone();
+        custom_attributes.extend(lib.custom_attributes.iter().map(offset_attribute));
+
+        let instruction_locations = match (&host.instruction_locations, &lib.instruction_locations)
+        {
+            (None, None) => None,
+            (host_locations, lib_locations) => {
+                let mut locations = host_locations.clone().unwrap_or_default();
+                locations.extend(
+                    lib_locations
+                        .iter()
+                        .flatten()
+                        .map(|(pc, location)| (pc + pc_offset, location.clone())),
+                );
+                Some(locations)
+            }
+        };
+
+        let mut identifiers = host.identifiers.clone();
+        for (name, identifier) in lib.identifiers.iter() {
+            let mut identifier = identifier.clone();
+            if let Some(pc) = identifier.pc.as_mut() {
+                *pc += pc_offset;
+            }
+            if let Some(existing) = identifiers.get(name) {
+                let is_label = existing.pc.is_some() && existing.type_ == identifier.type_;
+                if !is_label && *existing != identifier {
+                    return Err(ProgramError::LinkConflict(name.clone()));
+                }
+            } else {
+                identifiers.insert(name.clone(), identifier);
+            }
+        }
+
+        // Constants come from `const` identifiers, which were checked for conflicts above
+        let mut constants = self.constants.clone();
+        constants.extend(
+            library
+                .constants
+                .iter()
+                .map(|(name, value)| (name.clone(), value.clone())),
+        );
+
+        let builtin_ordered_list = [
+            BuiltinName::output,
+            BuiltinName::pedersen,
+            BuiltinName::range_check,
+            BuiltinName::ecdsa,
+            BuiltinName::bitwise,
+            BuiltinName::ec_op,
+            BuiltinName::keccak,
+            BuiltinName::poseidon,
+            BuiltinName::segment_arena,
+        ];
+        let mut builtins = self.builtins.clone();
+        for builtin in library.builtins.iter() {
+            if !builtins.contains(builtin) {
+                builtins.push(builtin.clone());
+            }
+        }
+        builtins.sort_by_key(|builtin| {
+            builtin_ordered_list
+                .iter()
+                .position(|ordered| ordered == builtin)
+        });
+
+        let code_map = CodeMap::new(&data, host.main, host.start, identifiers.values())?;
+        let shared_program_data = SharedProgramData {
+            data,
+            hints,
+            main: host.main,
+            start: host.start,
+            end: host.end,
+            error_message_attributes,
+            custom_attributes,
+            instruction_locations,
+            identifiers,
+            reference_manager,
+            compiler_version: host.compiler_version.clone(),
+            code_map,
+        };
+        Ok(Program {
+            shared_program_data: Arc::new(shared_program_data),
+            constants,
+            builtins,
+        })
+    }
+
+    /// Returns true if both programs run the same way, to check that two compilations produced the
+    /// same executable artifact.
+    ///
+    /// Compares the data, builtins, entrypoints (`main`, `start` and `end`), constants and hints,
+    /// each hint by its code, accessible scopes, ap tracking and references. Debug info is
+    /// ignored: identifiers, instruction locations, error message attributes, the compiler version
+    /// and the full paths of the references of hints, of which only the name used by the hint code
+    /// is compared.
+    ///
+    /// Constants are compared by their full path, as hints can look them up by path, so moving or
+    /// renaming the module declaring a constant makes the programs differ.
+    pub fn semantically_equal(&self, other: &Program) -> bool {
+        let (data, other_data) = (&self.shared_program_data, &other.shared_program_data);
+        data.data == other_data.data
+            && self.builtins == other.builtins
+            && (data.main, data.start, data.end)
+                == (other_data.main, other_data.start, other_data.end)
+            && *self.constants == *other.constants
+            && data.hints.len() == other_data.hints.len()
+            && data.hints.iter().all(|(pc, hints)| {
+                other_data.hints.get(pc).is_some_and(|other_hints| {
+                    hints.len() == other_hints.len()
+                        && hints.iter().zip(other_hints).all(|(hint, other_hint)| {
+                            hint.code == other_hint.code
+                                && hint.accessible_scopes == other_hint.accessible_scopes
+                                && hint.flow_tracking_data.ap_tracking
+                                    == other_hint.flow_tracking_data.ap_tracking
+                                && self.hint_references(hint) == other.hint_references(other_hint)
+                        })
+                })
+            })
+    }
+
+    /// The references of `hint`, by the name the hint code refers to them with, sorted by name.
+    fn hint_references<'a>(
+        &'a self,
+        hint: &'a HintParams,
+    ) -> Vec<(&'a str, Option<&'a HintReference>)> {
+        let mut references: Vec<_> = hint
+            .flow_tracking_data
+            .reference_ids
+            .iter()
+            .map(|(path, id)| {
+                (
+                    path.rsplit('.').next().unwrap_or(path),
+                    self.shared_program_data.reference_manager.get(*id),
+                )
+            })
+            .collect();
+        references.sort_unstable_by_key(|(name, _)| *name);
+        references
+    }
+
+    /// Reconstructs the layout of the implicit arguments, arguments and return values of the
+    /// function `name` from its `ImplicitArgs`, `Args` and `Return` identifiers.
+    /// `name` can be either a full identifier name or the name of a function in `__main__`.
+    pub fn function_signature(&self, name: &str) -> Result<FunctionSignature, ProgramError> {
+        let full_name = self.function_full_name(name)?;
+
+        Ok(FunctionSignature {
+            implicit_args: self.member_layouts(&format!("{full_name}.ImplicitArgs"))?,
+            args: self.member_layouts(&format!("{full_name}.Args"))?,
+            return_values: self.member_layouts(&format!("{full_name}.Return"))?,
+        })
+    }
+
+    // Resolves `name` to the full name of a function, trying it as is and then in `__main__`
+    fn function_full_name(&self, name: &str) -> Result<String, ProgramError> {
+        [name.to_string(), format!("__main__.{name}")]
+            .into_iter()
+            .find(|full_name| {
+                self.get_identifier(full_name)
+                    .and_then(|identifier| identifier.type_.as_deref())
+                    == Some("function")
+            })
+            .ok_or_else(|| ProgramError::EntrypointNotFound(name.to_string()))
+    }
+
+    /// Extracts the function `name` into a standalone program, to run it on its own instead of
+    /// through the main of the program, e.g. to unit test it from Rust.
+    ///
+    /// The extracted program holds the code of the function and of the functions it calls
+    /// (transitively), which is copied as a contiguous span of the data so the relative calls
+    /// and jumps keep their targets, after a prologue calling the function and returning:
+    /// ```text
+    /// call rel <function>
+    /// ret
+    /// ```
+    /// The prologue is the main of the extracted program, so running it from its main (or from
+    /// pc 0 with [CairoRunner::run_from_entrypoint](crate::vm::runners::cairo_runner::CairoRunner::run_from_entrypoint))
+    /// passes the initial stack to the function as its arguments, and leaves its return values at
+    /// the end of the stack. Its builtins are those of the program whose pointers are implicit
+    /// arguments of the function.
+    ///
+    /// The hints, identifiers, error attributes and instruction locations within the span are
+    /// kept, shifted to their new pcs. Fails if the code jumps to an immediate absolute address,
+    /// which can't be shifted.
+    pub fn extract_function(&self, name: &str) -> Result<Program, ProgramError> {
+        const PROLOGUE_SIZE: usize = 3;
+        let program_data = &self.shared_program_data;
+        let full_name = self.function_full_name(name)?;
+        let function_pc = self
+            .get_identifier(&full_name)
+            .and_then(|identifier| identifier.pc)
+            .ok_or_else(|| ProgramError::EntrypointNotFound(name.to_string()))?;
+
+        // Each function spans until the next one, or until the end of the data
+        let mut function_pcs: Vec<usize> = program_data
+            .identifiers
+            .values()
+            .filter(|identifier| identifier.type_.as_deref() == Some("function"))
+            .filter_map(|identifier| identifier.pc)
+            .collect();
+        function_pcs.sort_unstable();
+        function_pcs.dedup();
+        let function_range = |pc: usize| {
+            let index = function_pcs.partition_point(|function_pc| *function_pc <= pc);
+            let start = index.checked_sub(1).map_or(0, |index| function_pcs[index]);
+            let end = function_pcs
+                .get(index)
+                .copied()
+                .unwrap_or(program_data.data.len());
+            start..end
+        };
+
+        // Follows the calls and jumps to immediate targets out of each function reached
+        let mut span = function_range(function_pc);
+        let mut pending = vec![span.clone()];
+        let mut visited = vec![span.start];
+        while let Some(range) = pending.pop() {
+            for pc in program_data
+                .code_map
+                .instructions()
+                .filter(|pc| range.contains(pc))
+            {
+                let Some(instruction) = program_data
+                    .data
+                    .get(pc)
+                    .and_then(MaybeRelocatable::get_int_ref)
+                    .and_then(|encoding| encoding.to_u64())
+                    .and_then(|encoding| decode_instruction(encoding).ok())
+                else {
+                    continue;
+                };
+                if instruction.op1_addr != Op1Addr::Imm {
+                    continue;
+                }
+                let relative_target = || {
+                    program_data
+                        .data
+                        .get(pc + 1)
+                        .and_then(MaybeRelocatable::get_int_ref)
+                        .and_then(|offset| offset.to_signed_felt().to_isize())
+                        .and_then(|offset| pc.checked_add_signed(offset))
+                };
+                let target = match (instruction.pc_update, instruction.res) {
+                    (PcUpdate::Jump, Res::Op1) => {
+                        return Err(ProgramError::AbsoluteJumpInExtraction(pc))
+                    }
+                    (PcUpdate::JumpRel, Res::Op1) | (PcUpdate::Jnz, _) => relative_target(),
+                    _ => None,
+                };
+                let Some(target) = target.filter(|target| !range.contains(target)) else {
+                    continue;
+                };
+                let target_range = function_range(target);
+                if !visited.contains(&target_range.start) {
+                    visited.push(target_range.start);
+                    span = span.start.min(target_range.start)..span.end.max(target_range.end);
+                    pending.push(target_range);
+                }
+            }
+        }
+
+        let relocate = |pc: usize| span.contains(&pc).then(|| pc - span.start + PROLOGUE_SIZE);
+        let call_offset = relocate(function_pc).unwrap_or_default();
+        let data: Vec<MaybeRelocatable> = [
+            // call rel <function>
+            MaybeRelocatable::from(Felt252::new(0x1104800180018000_u64)),
+            MaybeRelocatable::from(call_offset),
+            // ret
+            MaybeRelocatable::from(Felt252::new(0x208b7fff7fff7ffe_u64)),
+        ]
+        .into_iter()
+        .chain(
+            program_data
+                .data
+                .iter()
+                .skip(span.start)
+                .take(span.len())
+                .cloned(),
+        )
+        .collect();
+
+        let hints = program_data
+            .hints
+            .iter()
+            .filter_map(|(pc, hints)| Some((relocate(*pc)?, hints.clone())))
+            .collect();
+        let identifiers: BTreeMap<String, Identifier> = program_data
+            .identifiers
+            .iter()
+            .filter_map(|(name, identifier)| {
+                let mut identifier = identifier.clone();
+                if let Some(pc) = identifier.pc {
+                    identifier.pc = Some(relocate(pc)?);
+                }
+                Some((name.clone(), identifier))
+            })
+            .collect();
+        let relocate_attributes = |attributes: &[Attribute]| {
+            attributes
+                .iter()
+                .filter_map(|attribute| {
+                    let mut attribute = attribute.clone();
+                    attribute.start_pc = relocate(attribute.start_pc)?;
+                    attribute.end_pc = attribute.end_pc.min(span.end) - span.start + PROLOGUE_SIZE;
+                    Some(attribute)
+                })
+                .collect::<Vec<_>>()
+        };
+        let error_message_attributes = relocate_attributes(&program_data.error_message_attributes);
+        let custom_attributes = relocate_attributes(&program_data.custom_attributes);
+        let instruction_locations = program_data
+            .instruction_locations
+            .as_ref()
+            .map(|locations| {
+                locations
+                    .iter()
+                    .filter_map(|(pc, location)| Some((relocate(*pc)?, location.clone())))
+                    .collect()
+            });
+
+        let implicit_args = self
+            .get_identifier(&format!("{full_name}.ImplicitArgs"))
+            .and_then(|identifier| identifier.members.as_ref());
+        let builtins = self
+            .builtins
+            .iter()
+            .filter(|builtin| {
+                implicit_args.is_some_and(|members| members.contains_key(&format!("{builtin}_ptr")))
+            })
+            .cloned()
+            .collect();
+
+        let data = ProgramData::try_from(data)?;
+        let code_map = CodeMap::new(&data, Some(0), None, identifiers.values())?;
+        let shared_program_data = SharedProgramData {
+            data,
+            hints,
+            main: Some(0),
+            start: None,
+            end: None,
+            error_message_attributes,
+            custom_attributes,
+            instruction_locations,
+            identifiers,
+            reference_manager: program_data.reference_manager.clone(),
+            compiler_version: program_data.compiler_version.clone(),
+            code_map,
+        };
+        Ok(Program {
+            shared_program_data: Arc::new(shared_program_data),
+            constants: self.constants.clone(),
+            builtins,
+        })
+    }
+
+    // Returns the members of a struct or tuple type identifier, or an empty list if it doesn't exist
+    fn member_layouts(&self, type_name: &str) -> Result<Vec<MemberLayout>, ProgramError> {
+        let Some(identifier) = self.get_identifier(type_name) else {
+            return Ok(Vec::new());
+        };
+        match identifier.type_.as_deref() {
+            Some("struct") => {
+                let mut members = identifier
+                    .members
+                    .iter()
+                    .flatten()
+                    .map(|(name, member)| {
+                        Ok(MemberLayout {
+                            name: name.clone(),
+                            cairo_type: member.cairo_type.clone(),
+                            offset: member.offset,
+                            size: self.cairo_type_size(&member.cairo_type)?,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, ProgramError>>()?;
+                members.sort_by_key(|member| member.offset);
+                Ok(members)
+            }
+            Some("type_definition") => match identifier.cairo_type.as_deref() {
+                Some(cairo_type) if cairo_type.starts_with('(') => self.tuple_layouts(cairo_type),
+                Some(cairo_type) => Ok(vec![MemberLayout {
+                    name: String::new(),
+                    cairo_type: cairo_type.to_string(),
+                    offset: 0,
+                    size: self.cairo_type_size(cairo_type)?,
+                }]),
+                None => Err(ProgramError::UnknownCairoType(type_name.to_string())),
+            },
+            _ => Err(ProgramError::UnknownCairoType(type_name.to_string())),
+        }
+    }
+
+    // Parses a (possibly named) tuple type such as `(res: felt, point: (felt, felt))`
+    fn tuple_layouts(&self, cairo_type: &str) -> Result<Vec<MemberLayout>, ProgramError> {
+        let unknown_type = || ProgramError::UnknownCairoType(cairo_type.to_string());
+        let inner = cairo_type
+            .trim()
+            .strip_prefix('(')
+            .and_then(|inner| inner.strip_suffix(')'))
+            .ok_or_else(unknown_type)?;
+
+        let mut elements = Vec::new();
+        let (mut depth, mut start) = (0_usize, 0);
+        for (index, c) in inner.char_indices() {
+            match c {
+                '(' => depth += 1,
+                ')' => depth = depth.checked_sub(1).ok_or_else(unknown_type)?,
+                ',' if depth == 0 => {
+                    elements.push(&inner[start..index]);
+                    start = index + 1;
+                }
+                _ => {}
+            }
+        }
+        elements.push(&inner[start..]);
+
+        let mut offset = 0;
+        let mut members = Vec::new();
+        for element in elements.into_iter().map(str::trim) {
+            if element.is_empty() {
+                continue;
+            }
+            let (name, element_type) = match element.split_once(':') {
+                Some((name, element_type)) if !element.starts_with('(') => {
+                    (name.trim(), element_type.trim())
+                }
+                _ => ("", element),
+            };
+            let size = self.cairo_type_size(element_type)?;
+            members.push(MemberLayout {
+                name: name.to_string(),
+                cairo_type: element_type.to_string(),
+                offset,
+                size,
+            });
+            offset += size;
+        }
+        Ok(members)
+    }
+
+    fn cairo_type_size(&self, cairo_type: &str) -> Result<usize, ProgramError> {
+        let cairo_type = cairo_type.trim();
+        if cairo_type.ends_with('*') || cairo_type == "felt" || cairo_type == "codeoffset" {
+            return Ok(1);
+        }
+        if cairo_type.starts_with('(') {
+            return Ok(self
+                .tuple_layouts(cairo_type)?
+                .iter()
+                .map(|member| member.size)
+                .sum());
+        }
+        match self
+            .get_identifier(cairo_type)
+            .and_then(|identifier| identifier.type_.as_deref())
+        {
+            Some("struct") | Some("type_definition") => Ok(self
+                .member_layouts(cairo_type)?
+                .iter()
+                .map(|member| member.offset + member.size)
+                .max()
+                .unwrap_or_default()),
+            _ => Err(ProgramError::UnknownCairoType(cairo_type.to_string())),
+        }
     }
 
     pub(crate) fn get_reference_list(reference_manager: &ReferenceManager) -> Vec<HintReference> {
@@ -167,11 +1057,75 @@ impl Program {
     }
 }
 
+/// The debug info of the instruction at a pc, with the chain of locations it was inlined from
+/// flattened, see [Program::location_for_pc].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolvedInstructionLocation<'a> {
+    pub pc: usize,
+    pub instruction_location: &'a InstructionLocation,
+    /// The locations the instruction was expanded from, from the innermost to the outermost,
+    /// each with the message describing the expansion
+    pub parent_locations: Vec<(&'a Location, &'a str)>,
+}
+
+impl<'a> ResolvedInstructionLocation<'a> {
+    fn new(pc: usize, instruction_location: &'a InstructionLocation) -> Self {
+        let mut parent_locations = Vec::new();
+        let mut parent = &instruction_location.inst.parent_location;
+        while let Some((location, message)) = parent {
+            parent_locations.push((location.as_ref(), message.as_str()));
+            parent = &location.parent_location;
+        }
+        ResolvedInstructionLocation {
+            pc,
+            instruction_location,
+            parent_locations,
+        }
+    }
+
+    /// Returns the location of the instruction itself.
+    pub fn location(&self) -> &'a Location {
+        &self.instruction_location.inst
+    }
+}
+
+/// Matches `text` against a glob `pattern` in which `*` matches any sequence of characters and
+/// `?` any single character.
+fn glob_matches(pattern: &str, text: &str) -> bool {
+    let (pattern, text): (Vec<char>, Vec<char>) =
+        (pattern.chars().collect(), text.chars().collect());
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` in the pattern, and of the text it was matched from, to backtrack to
+    let mut star: Option<(usize, usize)> = None;
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match star {
+                // Let the last `*` match one more character
+                Some((star_p, star_t)) => {
+                    star = Some((star_p, star_t + 1));
+                    p = star_p + 1;
+                    t = star_t + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
 impl Default for Program {
     fn default() -> Self {
         Self {
             shared_program_data: Arc::new(SharedProgramData::default()),
-            constants: HashMap::new(),
+            constants: Constants::new(),
             builtins: Vec::new(),
         }
     }
@@ -226,8 +1180,9 @@ impl TryFrom<CasmContractClass> for Program {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::serde::deserialize_program::{ApTracking, FlowTrackingData};
+    use crate::serde::deserialize_program::{ApTracking, InputFile, Member};
     use crate::utils::test_utils::*;
+    use assert_matches::assert_matches;
     use felt::felt_str;
     use num_traits::Zero;
 
@@ -266,7 +1221,7 @@ mod tests {
         assert_eq!(program.builtins, builtins);
         assert_eq!(program.shared_program_data.data, data);
         assert_eq!(program.shared_program_data.main, None);
-        assert_eq!(program.shared_program_data.identifiers, HashMap::new());
+        assert_eq!(program.shared_program_data.identifiers, BTreeMap::new());
     }
 
     #[test]
@@ -328,13 +1283,16 @@ mod tests {
         assert_eq!(program.builtins, builtins);
         assert_eq!(program.shared_program_data.data, data);
         assert_eq!(program.shared_program_data.main, None);
-        assert_eq!(program.shared_program_data.identifiers, identifiers);
+        assert_eq!(
+            program.shared_program_data.identifiers,
+            identifiers.into_iter().collect::<BTreeMap<_, _>>()
+        );
         assert_eq!(
             program.constants,
             [("__main__.main.SIZEOF_LOCALS", Felt252::zero())]
                 .into_iter()
                 .map(|(key, value)| (key.to_string(), value))
-                .collect::<HashMap<_, _>>(),
+                .collect::<Constants>(),
         );
     }
 
@@ -384,29 +1342,92 @@ mod tests {
 
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
-    fn iter_data() {
-        let reference_manager = ReferenceManager {
-            references: Vec::new(),
-        };
-
-        let builtins: Vec<BuiltinName> = Vec::new();
-        let data: Vec<MaybeRelocatable> = vec![
+    fn program_data_stores_distinct_values_once() {
+        let data = vec![
             mayberelocatable!(5189976364521848832),
             mayberelocatable!(1000),
             mayberelocatable!(5189976364521848832),
-            mayberelocatable!(2000),
-            mayberelocatable!(5201798304953696256),
-            mayberelocatable!(2345108766317314046),
+            mayberelocatable!(1, 2),
+            mayberelocatable!(1000),
         ];
+        let program_data = ProgramData::try_from(data.clone()).unwrap();
 
-        let program = Program::new(
-            builtins,
-            data.clone(),
-            None,
-            HashMap::new(),
-            reference_manager,
-            HashMap::new(),
-            Vec::new(),
+        assert_eq!(
+            program_data.values,
+            vec![
+                mayberelocatable!(5189976364521848832),
+                mayberelocatable!(1000),
+                mayberelocatable!(1, 2),
+            ]
+        );
+        assert_eq!(program_data.cells, vec![0, 1, 0, 2, 1]);
+        assert_eq!(program_data.len(), 5);
+        assert_eq!(program_data, data);
+        assert_eq!(
+            program_data.iter().rev().cloned().collect::<Vec<_>>(),
+            data.into_iter().rev().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn program_data_value_index_overflow() {
+        assert_eq!(value_index(3).unwrap(), 3);
+        assert_eq!(value_index(u32::MAX as usize).unwrap(), u32::MAX);
+        #[cfg(target_pointer_width = "64")]
+        assert_matches!(
+            value_index(u32::MAX as usize + 1),
+            Err(ProgramError::TooManyDistinctValues(n)) if n == u32::MAX as usize + 1
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn program_data_digest() {
+        let data = |values: Vec<MaybeRelocatable>| ProgramData::try_from(values).unwrap();
+        let digest = data(vec![mayberelocatable!(1), mayberelocatable!(1, 0)])
+            .digest()
+            .to_owned();
+        assert_eq!(
+            data(vec![mayberelocatable!(1), mayberelocatable!(1, 0)]).digest(),
+            &digest
+        );
+        assert_ne!(
+            data(vec![mayberelocatable!(1, 0), mayberelocatable!(1)]).digest(),
+            &digest
+        );
+        assert_ne!(
+            data(vec![mayberelocatable!(1), mayberelocatable!(1)]).digest(),
+            &digest
+        );
+        assert_eq!(ProgramData::default(), data(Vec::new()));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn iter_data() {
+        let reference_manager = ReferenceManager {
+            references: Vec::new(),
+        };
+
+        let builtins: Vec<BuiltinName> = Vec::new();
+        let data: Vec<MaybeRelocatable> = vec![
+            mayberelocatable!(5189976364521848832),
+            mayberelocatable!(1000),
+            mayberelocatable!(5189976364521848832),
+            mayberelocatable!(2000),
+            mayberelocatable!(5201798304953696256),
+            mayberelocatable!(2345108766317314046),
+        ];
+
+        let program = Program::new(
+            builtins,
+            data.clone(),
+            None,
+            HashMap::new(),
+            reference_manager,
+            HashMap::new(),
+            Vec::new(),
             None,
         )
         .unwrap();
@@ -578,6 +1599,208 @@ mod tests {
             .collect();
 
         assert_eq!(collected_identifiers, identifiers);
+
+        let names: Vec<&str> = program.iter_identifiers().map(|(name, _)| name).collect();
+        let mut sorted_names = names.clone();
+        sorted_names.sort_unstable();
+        assert_eq!(names, sorted_names);
+    }
+
+    fn program_with_identifiers(names: &[&str]) -> Program {
+        let identifiers = names
+            .iter()
+            .map(|name| {
+                (
+                    name.to_string(),
+                    Identifier {
+                        pc: None,
+                        type_: Some(String::from("const")),
+                        value: Some(Felt252::zero()),
+                        full_name: None,
+                        members: None,
+                        cairo_type: None,
+                    },
+                )
+            })
+            .collect();
+        Program::new(
+            Vec::new(),
+            Vec::new(),
+            None,
+            HashMap::new(),
+            ReferenceManager {
+                references: Vec::new(),
+            },
+            identifiers,
+            Vec::new(),
+            None,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn find_identifiers() {
+        let program = program_with_identifiers(&[
+            "__main__.main",
+            "__main__.main.SIZEOF_LOCALS",
+            "__main__.fib.SIZEOF_LOCALS",
+            "__main__.fib.Args",
+            "starkware.cairo.common.math.assert_nn.SIZEOF_LOCALS",
+            "starkware.cairo.common.math.MAIN",
+        ]);
+        let find = |pattern: &'static str| {
+            program
+                .find_identifiers(pattern)
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            find("*.SIZEOF_LOCALS"),
+            [
+                "__main__.fib.SIZEOF_LOCALS",
+                "__main__.main.SIZEOF_LOCALS",
+                "starkware.cairo.common.math.assert_nn.SIZEOF_LOCALS",
+            ]
+        );
+        assert_eq!(find("SIZEOF_LOCALS"), find("*.SIZEOF_LOCALS"));
+        assert_eq!(
+            find("__main__.*"),
+            [
+                "__main__.fib.Args",
+                "__main__.fib.SIZEOF_LOCALS",
+                "__main__.main",
+                "__main__.main.SIZEOF_LOCALS",
+            ]
+        );
+        assert_eq!(
+            find("__main__.???.*"),
+            ["__main__.fib.Args", "__main__.fib.SIZEOF_LOCALS"]
+        );
+        assert_eq!(
+            find("__main__.f*"),
+            ["__main__.fib.Args", "__main__.fib.SIZEOF_LOCALS"]
+        );
+        assert_eq!(
+            find("starkware.*.MAIN"),
+            ["starkware.cairo.common.math.MAIN"]
+        );
+        assert!(find("__main__.g*").is_empty());
+        assert_eq!(find("main.SIZEOF_LOCALS"), ["__main__.main.SIZEOF_LOCALS"]);
+        // Suffixes only match whole components
+        assert_eq!(find("main"), ["__main__.main"]);
+        assert_eq!(find("__main__.main"), ["__main__.main"]);
+        assert_eq!(find("*"), find("*.*"));
+        assert!(find("fib").is_empty());
+        assert!(find("*.fib").is_empty());
+    }
+
+    fn instruction_location(line: u32, parent: Option<(Location, &str)>) -> InstructionLocation {
+        InstructionLocation {
+            inst: Location {
+                end_line: line,
+                end_col: 10,
+                input_file: InputFile {
+                    filename: String::from("program.cairo"),
+                },
+                parent_location: parent
+                    .map(|(location, message)| (Box::new(location), message.to_string())),
+                start_line: line,
+                start_col: 1,
+            },
+            hints: Vec::new(),
+            flow_tracking_data: None,
+        }
+    }
+
+    fn program_with_locations() -> Program {
+        let inlined = instruction_location(
+            3,
+            Some((
+                instruction_location(
+                    7,
+                    Some((instruction_location(9, None).inst, "While expanding g")),
+                )
+                .inst,
+                "While expanding f",
+            )),
+        );
+        let instruction_locations = HashMap::from([
+            (0, instruction_location(1, None)),
+            (2, inlined),
+            (4, instruction_location(5, None)),
+        ]);
+        Program::new(
+            Vec::new(),
+            Vec::new(),
+            None,
+            HashMap::new(),
+            ReferenceManager {
+                references: Vec::new(),
+            },
+            HashMap::new(),
+            Vec::new(),
+            Some(instruction_locations),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn location_for_pc() {
+        let program = program_with_locations();
+
+        let location = program.location_for_pc(0).unwrap();
+        assert_eq!(location.pc, 0);
+        assert_eq!(location.location().start_line, 1);
+        assert!(location.parent_locations.is_empty());
+
+        let location = program.location_for_pc(2).unwrap();
+        assert_eq!(location.location().start_line, 3);
+        let parents: Vec<_> = location
+            .parent_locations
+            .iter()
+            .map(|(location, message)| (location.start_line, *message))
+            .collect();
+        assert_eq!(
+            parents,
+            [(7, "While expanding f"), (9, "While expanding g")]
+        );
+
+        assert_eq!(program.location_for_pc(1), None);
+        assert_eq!(Program::default().location_for_pc(0), None);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn locations_in_range() {
+        fn pcs<'a>(locations: impl Iterator<Item = ResolvedInstructionLocation<'a>>) -> Vec<usize> {
+            locations.map(|location| location.pc).collect()
+        }
+        let program = program_with_locations();
+
+        assert_eq!(pcs(program.locations_in_range(..)), [0, 2, 4]);
+        assert_eq!(pcs(program.locations_in_range(1..4)), [2]);
+        assert_eq!(pcs(program.locations_in_range(2..=4)), [2, 4]);
+        assert!(pcs(program.locations_in_range(5..)).is_empty());
+        assert_eq!(
+            program.locations_in_range(2..3).next(),
+            program.location_for_pc(2)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn glob_matches_patterns() {
+        assert!(glob_matches("", ""));
+        assert!(glob_matches("*", ""));
+        assert!(glob_matches("a*b*c", "aXbYbZc"));
+        assert!(glob_matches("a*c", "abcbc"));
+        assert!(glob_matches("a?c", "abc"));
+        assert!(!glob_matches("a?c", "ac"));
+        assert!(!glob_matches("a*b", "aXbY"));
+        assert!(!glob_matches("abc", "ab"));
     }
 
     #[test]
@@ -718,7 +1941,120 @@ mod tests {
         assert_eq!(program.builtins, builtins);
         assert_eq!(program.shared_program_data.data, data);
         assert_eq!(program.shared_program_data.main, Some(0));
-        assert_eq!(program.shared_program_data.identifiers, identifiers);
+        assert_eq!(
+            program.shared_program_data.identifiers,
+            identifiers.into_iter().collect::<BTreeMap<_, _>>()
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn program_getters() {
+        let program = Program::from_bytes(
+            include_bytes!("../../../cairo_programs/manually_compiled/valid_program_a.json"),
+            Some("main"),
+        )
+        .unwrap();
+
+        let codes = |pc| {
+            program
+                .hints_at(pc)
+                .iter()
+                .map(|hint| hint.code.as_str())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(codes(0), ["memory[ap] = segments.add()"]);
+        assert_eq!(codes(46), ["import math"]);
+        assert!(codes(1).is_empty());
+
+        assert_eq!(program.iter_references().count(), 0);
+        // Only the `error_message` attributes are kept
+        let error_attributes = program.error_attributes();
+        assert_eq!(error_attributes.len(), 1);
+        assert_eq!(error_attributes[0].value, "SafeUint256: addition overflow");
+        assert_eq!(
+            (error_attributes[0].start_pc, error_attributes[0].end_pc),
+            (379, 381)
+        );
+
+        assert_eq!((program.start(), program.end()), (None, None));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn hints_by_pc() {
+        let program = Program::from_bytes(
+            include_bytes!("../../../cairo_programs/manually_compiled/valid_program_a.json"),
+            Some("main"),
+        )
+        .unwrap();
+
+        let hints: Vec<(usize, Vec<&str>)> = program
+            .hints_by_pc()
+            .map(|(pc, hints)| (pc, hints.iter().map(|hint| hint.code.as_str()).collect()))
+            .collect();
+        assert_eq!(
+            hints,
+            [
+                (0, vec!["memory[ap] = segments.add()"]),
+                (46, vec!["import math"])
+            ]
+        );
+        for (pc, hints) in program.hints_by_pc() {
+            assert_eq!(hints, program.hints_at(pc));
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn hints_by_pc_skips_pcs_without_hints() {
+        let hint = HintParams {
+            code: "ids.a = 1".to_string(),
+            accessible_scopes: vec!["__main__".to_string()],
+            flow_tracking_data: FlowTrackingData {
+                ap_tracking: ApTracking::new(),
+                reference_ids: HashMap::new(),
+            },
+        };
+        let shared_program_data = SharedProgramData {
+            hints: HashMap::from([
+                (7, vec![hint.clone(), hint.clone()]),
+                (3, vec![]),
+                (2, vec![hint.clone()]),
+            ]),
+            ..Default::default()
+        };
+        let program = Program {
+            shared_program_data: Arc::new(shared_program_data),
+            ..Default::default()
+        };
+
+        let pcs: Vec<(usize, usize)> = program
+            .hints_by_pc()
+            .map(|(pc, hints)| (pc, hints.len()))
+            .collect();
+        assert_eq!(pcs, [(2, 1), (7, 2)]);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn program_getters_proof_mode_labels() {
+        let shared_program_data = SharedProgramData {
+            start: Some(0),
+            end: Some(4),
+            reference_manager: vec![HintReference::new_simple(-1)],
+            ..Default::default()
+        };
+        let program = Program {
+            shared_program_data: Arc::new(shared_program_data),
+            ..Default::default()
+        };
+
+        assert_eq!((program.start(), program.end()), (Some(0), Some(4)));
+        assert_eq!(
+            program.iter_references().collect::<Vec<_>>(),
+            [&HintReference::new_simple(-1)]
+        );
     }
 
     /// Deserialize a program without an entrypoint.
@@ -817,7 +2153,10 @@ mod tests {
         assert_eq!(program.builtins, builtins);
         assert_eq!(program.shared_program_data.data, data);
         assert_eq!(program.shared_program_data.main, None);
-        assert_eq!(program.shared_program_data.identifiers, identifiers);
+        assert_eq!(
+            program.shared_program_data.identifiers,
+            identifiers.into_iter().collect::<BTreeMap<_, _>>()
+        );
         assert_eq!(
             program.shared_program_data.error_message_attributes,
             error_message_attributes
@@ -860,7 +2199,7 @@ mod tests {
         ]
         .into_iter()
         .map(|(key, value)| (key.to_string(), value))
-        .collect::<HashMap<_, _>>();
+        .collect::<Constants>();
 
         assert_eq!(program.constants, constants);
     }
@@ -869,24 +2208,637 @@ mod tests {
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn default_program() {
         let shared_program_data = SharedProgramData {
-            data: Vec::new(),
+            data: ProgramData::default(),
             hints: HashMap::new(),
             main: None,
             start: None,
             end: None,
             error_message_attributes: Vec::new(),
+            custom_attributes: Vec::new(),
             instruction_locations: None,
-            identifiers: HashMap::new(),
+            identifiers: BTreeMap::new(),
             reference_manager: Program::get_reference_list(&ReferenceManager {
                 references: Vec::new(),
             }),
+            compiler_version: None,
+            code_map: CodeMap::default(),
         };
         let program = Program {
             shared_program_data: Arc::new(shared_program_data),
-            constants: HashMap::new(),
+            constants: Constants::new(),
             builtins: Vec::new(),
         };
 
         assert_eq!(program, Program::default());
     }
+
+    fn program_with_hint(shared_program_data: SharedProgramData) -> Program {
+        Program {
+            shared_program_data: Arc::new(SharedProgramData {
+                data: ProgramData::try_from(vec![mayberelocatable!(1), mayberelocatable!(2)])
+                    .unwrap(),
+                main: Some(0),
+                ..shared_program_data
+            }),
+            constants: Constants::new(),
+            builtins: vec![BuiltinName::output],
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn semantically_equal_ignores_debug_info() {
+        let program = program_with_hint(SharedProgramData {
+            hints: HashMap::from([(1, vec![hint_params("hint", &[("__main__.main.x", 0)])])]),
+            identifiers: BTreeMap::from([(
+                String::from("__main__.main"),
+                identifier(Some(0), "function"),
+            )]),
+            reference_manager: vec![HintReference::new_simple(-3)],
+            ..Default::default()
+        });
+        let recompiled = program_with_hint(SharedProgramData {
+            hints: HashMap::from([(1, vec![hint_params("hint", &[("__main__.renamed.x", 1)])])]),
+            identifiers: BTreeMap::from([(
+                String::from("__main__.renamed"),
+                identifier(Some(0), "function"),
+            )]),
+            reference_manager: vec![HintReference::new_simple(-1), HintReference::new_simple(-3)],
+            compiler_version: Some(String::from("0.11.0")),
+            ..Default::default()
+        });
+
+        assert_ne!(program, recompiled);
+        assert!(program.semantically_equal(&recompiled));
+        assert!(recompiled.semantically_equal(&program));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn semantically_equal_detects_changes() {
+        let program = program_with_hint(SharedProgramData {
+            hints: HashMap::from([(1, vec![hint_params("hint", &[("x", 0)])])]),
+            reference_manager: vec![HintReference::new_simple(-3)],
+            ..Default::default()
+        });
+        assert!(program.semantically_equal(&program.clone()));
+
+        let other_code = program_with_hint(SharedProgramData {
+            hints: HashMap::from([(1, vec![hint_params("other", &[("x", 0)])])]),
+            reference_manager: vec![HintReference::new_simple(-3)],
+            ..Default::default()
+        });
+        let other_pc = program_with_hint(SharedProgramData {
+            hints: HashMap::from([(0, vec![hint_params("hint", &[("x", 0)])])]),
+            reference_manager: vec![HintReference::new_simple(-3)],
+            ..Default::default()
+        });
+        let other_reference_name = program_with_hint(SharedProgramData {
+            hints: HashMap::from([(1, vec![hint_params("hint", &[("y", 0)])])]),
+            reference_manager: vec![HintReference::new_simple(-3)],
+            ..Default::default()
+        });
+        let other_reference = program_with_hint(SharedProgramData {
+            hints: HashMap::from([(1, vec![hint_params("hint", &[("x", 0)])])]),
+            reference_manager: vec![HintReference::new_simple(-2)],
+            ..Default::default()
+        });
+        let mut hint = hint_params("hint", &[("x", 0)]);
+        hint.accessible_scopes = vec![String::from("__main__.main")];
+        let other_scopes = program_with_hint(SharedProgramData {
+            hints: HashMap::from([(1, vec![hint])]),
+            reference_manager: vec![HintReference::new_simple(-3)],
+            ..Default::default()
+        });
+        let mut other_constants = program.clone();
+        other_constants
+            .constants
+            .insert(String::from("__main__.SIZE"), Felt252::new(3));
+        let mut other_builtins = program.clone();
+        other_builtins.builtins = vec![BuiltinName::range_check];
+        let mut other_entrypoint = program.clone();
+        Arc::make_mut(&mut other_entrypoint.shared_program_data).main = Some(1);
+        let mut other_data = program.clone();
+        Arc::make_mut(&mut other_data.shared_program_data).data =
+            ProgramData::try_from(vec![mayberelocatable!(1), mayberelocatable!(3)]).unwrap();
+
+        for other in [
+            other_code,
+            other_pc,
+            other_reference_name,
+            other_reference,
+            other_scopes,
+            other_constants,
+            other_builtins,
+            other_entrypoint,
+            other_data,
+        ] {
+            assert!(!program.semantically_equal(&other));
+        }
+    }
+
+    fn identifier(pc: Option<usize>, type_: &str) -> Identifier {
+        Identifier {
+            pc,
+            type_: Some(String::from(type_)),
+            value: None,
+            full_name: None,
+            members: None,
+            cairo_type: None,
+        }
+    }
+
+    fn hint_params(code: &str, reference_ids: &[(&str, usize)]) -> HintParams {
+        HintParams {
+            code: String::from(code),
+            accessible_scopes: Vec::new(),
+            flow_tracking_data: FlowTrackingData {
+                ap_tracking: ApTracking::default(),
+                reference_ids: reference_ids
+                    .iter()
+                    .map(|(name, id)| (String::from(*name), *id))
+                    .collect(),
+            },
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn link_programs() {
+        let host = Program {
+            shared_program_data: Arc::new(SharedProgramData {
+                data: ProgramData::try_from(vec![
+                    mayberelocatable!(1),
+                    mayberelocatable!(2),
+                    mayberelocatable!(3),
+                ])
+                .unwrap(),
+                hints: HashMap::from([(1, vec![hint_params("host", &[("x", 0)])])]),
+                main: Some(0),
+                identifiers: BTreeMap::from([
+                    (
+                        String::from("__main__.main"),
+                        identifier(Some(0), "function"),
+                    ),
+                    (
+                        String::from("common.alloc"),
+                        identifier(Some(2), "function"),
+                    ),
+                ]),
+                reference_manager: vec![HintReference::new_simple(-3)],
+                ..Default::default()
+            }),
+            constants: Constants::new(),
+            builtins: vec![BuiltinName::output, BuiltinName::bitwise],
+        };
+        let library = Program {
+            shared_program_data: Arc::new(SharedProgramData {
+                data: ProgramData::try_from(vec![mayberelocatable!(4), mayberelocatable!(5)])
+                    .unwrap(),
+                hints: HashMap::from([(0, vec![hint_params("library", &[("y", 0)])])]),
+                error_message_attributes: vec![Attribute {
+                    name: String::from("error_message"),
+                    start_pc: 0,
+                    end_pc: 2,
+                    value: String::from("Library failed"),
+                    flow_tracking_data: None,
+                }],
+                identifiers: BTreeMap::from([
+                    (String::from("lib.func"), identifier(Some(1), "function")),
+                    (
+                        String::from("common.alloc"),
+                        identifier(Some(0), "function"),
+                    ),
+                ]),
+                reference_manager: vec![HintReference::new_simple(-4)],
+                ..Default::default()
+            }),
+            constants: Constants::new(),
+            builtins: vec![BuiltinName::range_check],
+        };
+
+        let linked = host.link(&library).unwrap();
+        let linked_data = &linked.shared_program_data;
+
+        assert_eq!(
+            linked_data.data,
+            vec![
+                mayberelocatable!(1),
+                mayberelocatable!(2),
+                mayberelocatable!(3),
+                mayberelocatable!(4),
+                mayberelocatable!(5)
+            ]
+        );
+        assert_eq!(linked_data.main, Some(0));
+        assert_eq!(
+            linked_data.hints,
+            HashMap::from([
+                (1, vec![hint_params("host", &[("x", 0)])]),
+                (3, vec![hint_params("library", &[("y", 1)])]),
+            ])
+        );
+        assert_eq!(
+            linked_data.reference_manager,
+            vec![HintReference::new_simple(-3), HintReference::new_simple(-4)]
+        );
+        assert_eq!(linked_data.error_message_attributes[0].start_pc, 3);
+        assert_eq!(linked_data.error_message_attributes[0].end_pc, 5);
+        assert_eq!(
+            linked.get_identifier("lib.func"),
+            Some(&identifier(Some(4), "function"))
+        );
+        // Labels defined in both programs resolve to the host's definition
+        assert_eq!(
+            linked.get_identifier("common.alloc"),
+            Some(&identifier(Some(2), "function"))
+        );
+        assert_eq!(
+            linked.builtins,
+            vec![
+                BuiltinName::output,
+                BuiltinName::range_check,
+                BuiltinName::bitwise
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn link_programs_conflicting_constants() {
+        let mut constant = identifier(None, "const");
+        constant.value = Some(Felt252::new(1));
+        let host =
+            program!(identifiers = HashMap::from([(String::from("SIZE"), constant.clone())]),);
+        constant.value = Some(Felt252::new(2));
+        let library = program!(identifiers = HashMap::from([(String::from("SIZE"), constant)]),);
+
+        assert_matches!(
+            host.link(&library),
+            Err(ProgramError::LinkConflict(name)) if name == "SIZE"
+        );
+    }
+
+    fn struct_identifier(members: &[(&str, &str, usize)]) -> Identifier {
+        Identifier {
+            pc: None,
+            type_: Some(String::from("struct")),
+            value: None,
+            full_name: None,
+            members: Some(
+                members
+                    .iter()
+                    .map(|(name, cairo_type, offset)| {
+                        (
+                            String::from(*name),
+                            Member {
+                                cairo_type: String::from(*cairo_type),
+                                offset: *offset,
+                            },
+                        )
+                    })
+                    .collect(),
+            ),
+            cairo_type: None,
+        }
+    }
+
+    fn member_layout(name: &str, cairo_type: &str, offset: usize, size: usize) -> MemberLayout {
+        MemberLayout {
+            name: String::from(name),
+            cairo_type: String::from(cairo_type),
+            offset,
+            size,
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn function_signature() {
+        let mut return_type = identifier(None, "type_definition");
+        return_type.cairo_type = Some(String::from("(res: felt, point: (felt, felt))"));
+        let identifiers = HashMap::from([
+            (
+                String::from("__main__.main"),
+                identifier(Some(0), "function"),
+            ),
+            (
+                String::from("__main__.main.ImplicitArgs"),
+                struct_identifier(&[("output_ptr", "felt*", 0)]),
+            ),
+            (
+                String::from("__main__.main.Args"),
+                struct_identifier(&[
+                    ("value", "starkware.cairo.common.uint256.Uint256", 1),
+                    ("n", "felt", 0),
+                ]),
+            ),
+            (String::from("__main__.main.Return"), return_type),
+            (
+                String::from("starkware.cairo.common.uint256.Uint256"),
+                struct_identifier(&[("low", "felt", 0), ("high", "felt", 1)]),
+            ),
+        ]);
+        let program = program!(identifiers = identifiers,);
+
+        let signature = program.function_signature("main").unwrap();
+        assert_eq!(
+            signature,
+            FunctionSignature {
+                implicit_args: vec![member_layout("output_ptr", "felt*", 0, 1)],
+                args: vec![
+                    member_layout("n", "felt", 0, 1),
+                    member_layout("value", "starkware.cairo.common.uint256.Uint256", 1, 2)
+                ],
+                return_values: vec![
+                    member_layout("res", "felt", 0, 1),
+                    member_layout("point", "(felt, felt)", 1, 2)
+                ],
+            }
+        );
+        assert_eq!(signature.implicit_args_size(), 1);
+        assert_eq!(signature.args_size(), 3);
+        assert_eq!(signature.return_values_size(), 3);
+        assert_eq!(
+            program.function_signature("__main__.main").unwrap(),
+            signature
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn function_signature_unknown_type() {
+        let identifiers = HashMap::from([
+            (
+                String::from("__main__.main"),
+                identifier(Some(0), "function"),
+            ),
+            (
+                String::from("__main__.main.Args"),
+                struct_identifier(&[("value", "__main__.Missing", 0)]),
+            ),
+        ]);
+        let program = program!(identifiers = identifiers,);
+
+        assert_matches!(
+            program.function_signature("main"),
+            Err(ProgramError::UnknownCairoType(cairo_type)) if cairo_type == "__main__.Missing"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn function_signature_function_not_found() {
+        let program = program!();
+
+        assert_matches!(
+            program.function_signature("main"),
+            Err(ProgramError::EntrypointNotFound(name)) if name == "main"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn extract_function_with_callees() {
+        // main:
+        //   call rel 3
+        //   ret
+        // f:
+        //   call rel 3
+        //   ret
+        // g:
+        //   [ap] = 2; ap++
+        //   ret
+        // h:
+        //   ret
+        let call = mayberelocatable!(0x1104800180018000_i64);
+        let ret = mayberelocatable!(0x208b7fff7fff7ffe_i64);
+        let identifiers = BTreeMap::from([
+            (
+                String::from("__main__.main"),
+                identifier(Some(0), "function"),
+            ),
+            (String::from("__main__.f"), identifier(Some(3), "function")),
+            (String::from("__main__.g"), identifier(Some(6), "function")),
+            (String::from("__main__.h"), identifier(Some(9), "function")),
+            (
+                String::from("__main__.f.ImplicitArgs"),
+                struct_identifier(&[("range_check_ptr", "felt", 0)]),
+            ),
+        ]);
+        let data = ProgramData::try_from(vec![
+            call.clone(),
+            mayberelocatable!(3),
+            ret.clone(),
+            call.clone(),
+            mayberelocatable!(3),
+            ret.clone(),
+            mayberelocatable!(0x480680017fff8000_i64),
+            mayberelocatable!(2),
+            ret.clone(),
+            ret.clone(),
+        ])
+        .unwrap();
+        let code_map = CodeMap::new(&data, Some(0), None, identifiers.values()).unwrap();
+        let program = Program {
+            shared_program_data: Arc::new(SharedProgramData {
+                data,
+                hints: HashMap::from([
+                    (0, vec![hint_params("main", &[])]),
+                    (6, vec![hint_params("g", &[])]),
+                ]),
+                main: Some(0),
+                identifiers,
+                code_map,
+                ..Default::default()
+            }),
+            constants: Constants::new(),
+            builtins: vec![BuiltinName::output, BuiltinName::range_check],
+        };
+
+        let extracted = program.extract_function("f").unwrap();
+        let extracted_data = &extracted.shared_program_data;
+
+        assert_eq!(
+            extracted_data.data,
+            vec![
+                call.clone(),
+                mayberelocatable!(3),
+                ret.clone(),
+                call,
+                mayberelocatable!(3),
+                ret.clone(),
+                mayberelocatable!(0x480680017fff8000_i64),
+                mayberelocatable!(2),
+                ret,
+            ]
+        );
+        assert_eq!(extracted_data.main, Some(0));
+        assert_eq!(
+            extracted_data.hints,
+            HashMap::from([(6, vec![hint_params("g", &[])])])
+        );
+        assert_eq!(
+            extracted.get_identifier("__main__.g"),
+            Some(&identifier(Some(6), "function"))
+        );
+        assert_eq!(extracted.get_identifier("__main__.main"), None);
+        assert_eq!(extracted.get_identifier("__main__.h"), None);
+        assert_eq!(extracted.builtins, vec![BuiltinName::range_check]);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn extract_function_absolute_jump() {
+        // f:
+        //   jmp abs 0
+        let identifiers =
+            HashMap::from([(String::from("__main__.f"), identifier(Some(0), "function"))]);
+        let program = program!(
+            data = vec![
+                mayberelocatable!(0x008780017fff7fff_i64),
+                mayberelocatable!(0)
+            ],
+            identifiers = identifiers,
+        );
+
+        assert_matches!(
+            program.extract_function("f"),
+            Err(ProgramError::AbsoluteJumpInExtraction(0))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn validate_bytecode_skips_immediates_and_data() {
+        let program = program!(
+            data = vec![
+                // [ap] = -1; ap++
+                mayberelocatable!(0x480680017fff8000_i64),
+                MaybeRelocatable::from(Felt252::new(-1)),
+                // ret
+                mayberelocatable!(0x208b7fff7fff7ffe_i64),
+                // dw -1
+                MaybeRelocatable::from(Felt252::new(-1)),
+            ],
+            main = Some(0),
+        );
+
+        assert_eq!(
+            program.code_map().instructions().collect::<Vec<_>>(),
+            [0, 2]
+        );
+        assert_matches!(program.validate_bytecode(), Ok(()));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn validate_bytecode_invalid_instructions() {
+        let program = program!(
+            data = vec![
+                // [ap] = [ap - 1] + [ap - 2]; ap++
+                mayberelocatable!(0x48307ffe7fff8000_i64),
+                // ret, jumping to [fp - 2]
+                mayberelocatable!(0x208b7ffe7fff7ffe_i64),
+            ],
+            main = Some(0),
+        );
+        assert_matches!(
+            program.validate_bytecode(),
+            Err(ProgramError::InvalidInstruction(bx))
+                if matches!(*bx, (1, VirtualMachineError::NonCanonicalInstruction(_)))
+        );
+
+        let program = program!(data = vec![mayberelocatable!(0, 0)], main = Some(0),);
+        assert_matches!(
+            program.validate_bytecode(),
+            Err(ProgramError::InvalidInstruction(bx))
+                if matches!(*bx, (0, VirtualMachineError::InvalidInstructionEncoding))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn new_program_main_in_immediate() {
+        // [ap] = 1; ap++
+        let data = vec![
+            mayberelocatable!(0x480680017fff8000_i64),
+            mayberelocatable!(1),
+        ];
+        let identifiers = HashMap::from([(
+            String::from("__main__.f"),
+            Identifier {
+                pc: Some(0),
+                type_: Some(String::from("function")),
+                value: None,
+                full_name: None,
+                members: None,
+                cairo_type: None,
+            },
+        )]);
+
+        assert_matches!(
+            Program::new(
+                Vec::new(),
+                data,
+                Some(1),
+                HashMap::new(),
+                ReferenceManager {
+                    references: Vec::new()
+                },
+                identifiers,
+                Vec::new(),
+                None,
+            ),
+            Err(ProgramError::InstructionInImmediate(1))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn hash_chain() {
+        let program = program!(
+            builtins = vec![BuiltinName::output],
+            main = Some(1),
+            data = vec![mayberelocatable!(7), mayberelocatable!(8)],
+        );
+        let element = |value: u64| FieldElement::from_dec_str(&value.to_string()).unwrap();
+        // [len, bootloader_version, main, n_builtins, "output", data...]
+        let output =
+            FieldElement::from_bytes_be(&Felt252::from_bytes_be(b"output").to_be_bytes()).unwrap();
+        let expected = pedersen_hash(
+            &element(6),
+            &pedersen_hash(
+                &element(2),
+                &pedersen_hash(
+                    &element(1),
+                    &pedersen_hash(
+                        &element(1),
+                        &pedersen_hash(&output, &pedersen_hash(&element(7), &element(8))),
+                    ),
+                ),
+            ),
+        );
+        assert_matches!(
+            program.hash_chain(2),
+            Ok(hash) if hash == Felt252::from_bytes_be(&expected.to_bytes_be())
+        );
+        assert_ne!(
+            program.hash_chain(0).unwrap(),
+            program.hash_chain(2).unwrap()
+        );
+
+        let relocatable = program!(
+            main = Some(0),
+            data = vec![mayberelocatable!(7), mayberelocatable!(0, 1)],
+        );
+        assert_matches!(
+            relocatable.hash_chain(0),
+            Err(ProgramError::RelocatableInData(1))
+        );
+        assert_matches!(
+            program!().hash_chain(0),
+            Err(ProgramError::EntrypointNotFound(name)) if name == "main"
+        );
+    }
 }
@@ -1,4 +1,5 @@
 use crate::stdlib::{
+    collections::BTreeMap,
     fmt::{self, Display},
     ops::{Add, AddAssign, Sub},
     prelude::*,
@@ -38,6 +39,29 @@ impl From<(isize, usize)> for MaybeRelocatable {
     }
 }
 
+impl TryFrom<[i64; 2]> for Relocatable {
+    type Error = MathError;
+    fn try_from(index_offset: [i64; 2]) -> Result<Self, MathError> {
+        match (
+            isize::try_from(index_offset[0]),
+            usize::try_from(index_offset[1]),
+        ) {
+            (Ok(segment_index), Ok(offset)) => Ok(Relocatable {
+                segment_index,
+                offset,
+            }),
+            _ => Err(MathError::IntsToRelocatable(Box::new(index_offset))),
+        }
+    }
+}
+
+impl TryFrom<[i64; 2]> for MaybeRelocatable {
+    type Error = MathError;
+    fn try_from(index_offset: [i64; 2]) -> Result<Self, MathError> {
+        Relocatable::try_from(index_offset).map(MaybeRelocatable::RelocatableValue)
+    }
+}
+
 impl From<usize> for MaybeRelocatable {
     fn from(num: usize) -> Self {
         MaybeRelocatable::Int(Felt252::new(num))
@@ -89,6 +113,111 @@ impl Display for Relocatable {
     }
 }
 
+impl Relocatable {
+    /// Returns a value displaying `self` as `name:offset` if its segment is named in `names`,
+    /// e.g. `pedersen:12` instead of `4:12`. The names of the segments of a run are available
+    /// through [crate::vm::vm_memory::memory_segments::MemorySegmentManager::segment_names].
+    pub fn display_with<'a>(
+        &'a self,
+        names: &'a BTreeMap<isize, String>,
+    ) -> RelocatableDisplay<'a> {
+        RelocatableDisplay {
+            relocatable: self,
+            names,
+        }
+    }
+}
+
+/// Displays a [Relocatable] with the name of its segment, see [Relocatable::display_with].
+#[derive(Clone, Copy, Debug)]
+pub struct RelocatableDisplay<'a> {
+    relocatable: &'a Relocatable,
+    names: &'a BTreeMap<isize, String>,
+}
+
+impl Display for RelocatableDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.names.get(&self.relocatable.segment_index) {
+            Some(name) => write!(f, "{name}:{}", self.relocatable.offset),
+            None => self.relocatable.fmt(f),
+        }
+    }
+}
+
+/// Displays a [MaybeRelocatable] with the name of its segment, see
+/// [MaybeRelocatable::display_with].
+#[derive(Clone, Copy, Debug)]
+pub struct MaybeRelocatableDisplay<'a> {
+    value: &'a MaybeRelocatable,
+    names: &'a BTreeMap<isize, String>,
+}
+
+impl Display for MaybeRelocatableDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.value {
+            MaybeRelocatable::RelocatableValue(rel) => rel.display_with(self.names).fmt(f),
+            MaybeRelocatable::Int(num) => write!(f, "{num}"),
+        }
+    }
+}
+
+/// Returns a value displaying `value`, e.g. an error, with the relocatable values of the segments
+/// named in `names` written as `name:offset` instead of `segment_index:offset`.
+///
+/// The relocatable values are found in the text of `value`, as the words made of a segment index
+/// and a numeric offset, so a location such as `main.cairo:4:12` is left as is.
+pub fn display_with_segment_names<'a, T: Display + ?Sized>(
+    value: &'a T,
+    names: &'a BTreeMap<isize, String>,
+) -> SegmentNamesDisplay<'a, T> {
+    SegmentNamesDisplay { value, names }
+}
+
+/// Displays a value with the names of its segments, see [display_with_segment_names].
+#[derive(Clone, Copy, Debug)]
+pub struct SegmentNamesDisplay<'a, T: ?Sized> {
+    value: &'a T,
+    names: &'a BTreeMap<isize, String>,
+}
+
+impl<T: Display + ?Sized> Display for SegmentNamesDisplay<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.names.is_empty() {
+            return self.value.fmt(f);
+        }
+        let string = self.value.to_string();
+        let bytes = string.as_bytes();
+        let is_word = |byte: &u8| byte.is_ascii_alphanumeric() || b"_:.-".contains(byte);
+        // The text before `written` has been written, with the names replacing segment indexes
+        let (mut written, mut position) = (0, 0);
+        while position < bytes.len() {
+            let len = bytes[position..]
+                .iter()
+                .take_while(|byte| is_word(byte))
+                .count();
+            if len == 0 {
+                position += 1;
+                continue;
+            }
+            let word = string[position..position + len].trim_end_matches('.');
+            let name = word
+                .split_once(':')
+                .filter(|(_, offset)| {
+                    !offset.is_empty() && offset.bytes().all(|byte| byte.is_ascii_digit())
+                })
+                .and_then(|(segment_index, _)| segment_index.parse::<isize>().ok())
+                .and_then(|segment_index| self.names.get(&segment_index));
+            if let Some(name) = name {
+                f.write_str(&string[written..position])?;
+                f.write_str(name)?;
+                written = position + word.find(':').unwrap_or_default();
+            }
+            position += len;
+        }
+        f.write_str(&string[written..])
+    }
+}
+
 impl Add<usize> for Relocatable {
     type Output = Result<Relocatable, MathError>;
     fn add(self, other: usize) -> Result<Self, MathError> {
@@ -201,7 +330,43 @@ impl TryFrom<&MaybeRelocatable> for Relocatable {
     }
 }
 
+impl TryFrom<&MaybeRelocatable> for u64 {
+    type Error = MathError;
+    fn try_from(other: &MaybeRelocatable) -> Result<Self, MathError> {
+        let num = other.as_felt_ref()?;
+        num.to_u64()
+            .ok_or_else(|| MathError::Felt252ToU64Conversion(Box::new(num.clone())))
+    }
+}
+
+impl TryFrom<&MaybeRelocatable> for usize {
+    type Error = MathError;
+    fn try_from(other: &MaybeRelocatable) -> Result<Self, MathError> {
+        let num = other.as_felt_ref()?;
+        num.to_usize()
+            .ok_or_else(|| MathError::Felt252ToUsizeConversion(Box::new(num.clone())))
+    }
+}
+
+impl TryFrom<&MaybeRelocatable> for u128 {
+    type Error = MathError;
+    fn try_from(other: &MaybeRelocatable) -> Result<Self, MathError> {
+        let num = other.as_felt_ref()?;
+        num.to_u128()
+            .ok_or_else(|| MathError::Felt252ToU128Conversion(Box::new(num.clone())))
+    }
+}
+
 impl MaybeRelocatable {
+    /// Returns a value displaying `self` with the name of its segment, if it is a relocatable
+    /// value of a segment named in `names`, see [Relocatable::display_with].
+    pub fn display_with<'a>(
+        &'a self,
+        names: &'a BTreeMap<isize, String>,
+    ) -> MaybeRelocatableDisplay<'a> {
+        MaybeRelocatableDisplay { value: self, names }
+    }
+
     /// Adds a Felt252 to self
     pub fn add_int(&self, other: &Felt252) -> Result<MaybeRelocatable, MathError> {
         match *self {
@@ -307,6 +472,17 @@ impl MaybeRelocatable {
         }
     }
 
+    /// Returns a reference to the inner value if it is a Felt252, fails with
+    /// [MathError::RelocatableToFelt252] otherwise.
+    pub fn as_felt_ref(&self) -> Result<&Felt252, MathError> {
+        match self {
+            MaybeRelocatable::Int(num) => Ok(num),
+            MaybeRelocatable::RelocatableValue(rel) => {
+                Err(MathError::RelocatableToFelt252(Box::new(*rel)))
+            }
+        }
+    }
+
     /// Returns the inner value if it is a Relocatable, returns None otherwise.
     pub fn get_relocatable(&self) -> Option<Relocatable> {
         match self {
@@ -332,7 +508,7 @@ impl<'a> Add<usize> for &'a Relocatable {
 /// If the value is RelocatableValue, it will relocate it according to the relocation_table
 pub fn relocate_value(
     value: MaybeRelocatable,
-    relocation_table: &Vec<usize>,
+    relocation_table: &[usize],
 ) -> Result<Felt252, MemoryError> {
     match value {
         MaybeRelocatable::Int(num) => Ok(num),
@@ -346,7 +522,7 @@ pub fn relocate_value(
 // Relocates a Relocatable value according to the relocation_table
 pub fn relocate_address(
     relocatable: Relocatable,
-    relocation_table: &Vec<usize>,
+    relocation_table: &[usize],
 ) -> Result<usize, MemoryError> {
     let (segment_index, offset) = if relocatable.segment_index >= 0 {
         (relocatable.segment_index as usize, relocatable.offset)
@@ -356,17 +532,17 @@ pub fn relocate_address(
         ));
     };
 
-    if relocation_table.len() <= segment_index {
-        return Err(MemoryError::Relocation);
-    }
-
-    Ok(relocation_table[segment_index] + offset)
+    relocation_table
+        .get(segment_index)
+        .map(|base| base + offset)
+        .ok_or(MemoryError::Relocation)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{relocatable, utils::test_utils::mayberelocatable};
+    use assert_matches::assert_matches;
     use felt::felt_str;
     use num_traits::{One, Zero};
 
@@ -899,6 +1075,46 @@ mod tests {
         )
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn relocatable_display_with_segment_names() {
+        let names = BTreeMap::from([(4, String::from("pedersen"))]);
+        assert_eq!(
+            MaybeRelocatable::from((4, 12))
+                .display_with(&names)
+                .to_string(),
+            "pedersen:12"
+        );
+        assert_eq!(
+            MaybeRelocatable::from(12).display_with(&names).to_string(),
+            "12"
+        );
+        assert_eq!(
+            Relocatable::from((5, 0)).display_with(&names).to_string(),
+            "5:0"
+        );
+        assert_eq!(Relocatable::from((4, 12)).to_string(), "4:12");
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn error_display_with_segment_names() {
+        let names = BTreeMap::from([(-1, String::from("temp")), (4, String::from("pedersen"))]);
+        let error = MathError::RelocatableSubDiffIndex(Box::new((
+            Relocatable::from((4, 12)),
+            Relocatable::from((-1, 3)),
+        )));
+        assert_eq!(
+            display_with_segment_names(&error, &names).to_string(),
+            "Operation failed: pedersen:12 - temp:3, can't subtract two relocatable values with different segment indexes"
+        );
+        // Only whole words made of a segment index and an offset are renamed
+        assert_eq!(
+            display_with_segment_names("main.cairo:4:12 4:12. 4:1a 14:2 4:", &names).to_string(),
+            "main.cairo:4:12 pedersen:12. 4:1a 14:2 4:"
+        );
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn maybe_relocatable_relocatable_display() {
@@ -923,4 +1139,67 @@ mod tests {
         addr += 1;
         assert_eq!(addr, Relocatable::from((1, 1)))
     }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn maybe_relocatable_as_felt_ref() {
+        assert_eq!(mayberelocatable!(7).as_felt_ref(), Ok(&Felt252::new(7)));
+        assert_eq!(
+            mayberelocatable!(1, 2).as_felt_ref(),
+            Err(MathError::RelocatableToFelt252(Box::new(relocatable!(
+                1, 2
+            ))))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn try_from_maybe_relocatable_to_integers() {
+        let value = mayberelocatable!(7);
+        assert_eq!(u64::try_from(&value), Ok(7));
+        assert_eq!(usize::try_from(&value), Ok(7));
+        assert_eq!(u128::try_from(&value), Ok(7));
+
+        let value = MaybeRelocatable::from(Felt252::new(u128::MAX) + 1_u32);
+        assert_matches!(
+            u64::try_from(&value),
+            Err(MathError::Felt252ToU64Conversion(_))
+        );
+        assert_matches!(
+            usize::try_from(&value),
+            Err(MathError::Felt252ToUsizeConversion(_))
+        );
+        assert_matches!(
+            u128::try_from(&value),
+            Err(MathError::Felt252ToU128Conversion(bx)) if *bx == Felt252::new(u128::MAX) + 1_u32
+        );
+
+        let value = mayberelocatable!(1, 2);
+        assert_matches!(
+            u64::try_from(&value),
+            Err(MathError::RelocatableToFelt252(_))
+        );
+        assert_matches!(
+            usize::try_from(&value),
+            Err(MathError::RelocatableToFelt252(_))
+        );
+        assert_matches!(
+            u128::try_from(&value),
+            Err(MathError::RelocatableToFelt252(_))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn try_from_i64_pair_to_relocatable() {
+        assert_eq!(Relocatable::try_from([-2_i64, 5]), Ok(relocatable!(-2, 5)));
+        assert_eq!(
+            MaybeRelocatable::try_from([1_i64, 2]),
+            Ok(mayberelocatable!(1, 2))
+        );
+        assert_eq!(
+            Relocatable::try_from([1_i64, -2]),
+            Err(MathError::IntsToRelocatable(Box::new([1, -2])))
+        );
+    }
 }
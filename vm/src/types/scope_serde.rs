@@ -0,0 +1,416 @@
+//! (De)serialization of execution scopes, to checkpoint runs spanning several processes.
+//!
+//! Scope variables are stored as `Box<dyn Any>`, so only the variables whose concrete type was
+//! registered in a [ScopeSerdeRegistry] can be serialized. Each type is registered under a tag,
+//! stored along with the serialized value to pick the type it is deserialized as.
+
+use crate::stdlib::{
+    any::{Any, TypeId},
+    cell::RefCell,
+    collections::{BTreeMap, HashMap},
+    prelude::*,
+    rc::Rc,
+};
+
+use felt::Felt252;
+use num_bigint::{BigInt, BigUint};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    hint_processor::builtin_hint_processor::dict_manager::{DictManager, DictTracker, Dictionary},
+    types::{
+        exec_scope::ExecutionScopes,
+        relocatable::{MaybeRelocatable, Relocatable},
+    },
+    vm::errors::exec_scope_errors::ExecScopeError,
+};
+
+type SerializeFn = fn(&dyn Any) -> Result<Value, String>;
+type DeserializeFn = fn(Value) -> Result<Box<dyn Any>, String>;
+
+struct RegisteredType {
+    tag: String,
+    serialize: SerializeFn,
+    deserialize: DeserializeFn,
+}
+
+/// The types of scope variables that can be (de)serialized, each under a unique tag.
+#[derive(Default)]
+pub struct ScopeSerdeRegistry {
+    types: HashMap<TypeId, RegisteredType>,
+    tags: HashMap<String, TypeId>,
+}
+
+impl ScopeSerdeRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry with the types of the values commonly stored by hints: felts, big
+    /// integers, integers, booleans, strings, lists of felts and the dict manager of the dict
+    /// hints.
+    pub fn with_common_types() -> Self {
+        let mut registry = Self::new();
+        registry.register::<Felt252>("felt");
+        registry.register::<BigInt>("bigint");
+        registry.register::<BigUint>("biguint");
+        registry.register::<u64>("u64");
+        registry.register::<usize>("usize");
+        registry.register::<bool>("bool");
+        registry.register::<String>("string");
+        registry.register::<Vec<Felt252>>("felt_list");
+        registry.register_with::<Rc<RefCell<DictManager>>>(
+            "dict_manager",
+            serialize_dict_manager,
+            deserialize_dict_manager,
+        );
+        registry
+    }
+
+    /// Registers `T` under `tag`, replacing the type previously registered under it, if any.
+    pub fn register<T: Any + Serialize + DeserializeOwned>(&mut self, tag: &str) {
+        self.register_with::<T>(tag, serialize_value::<T>, deserialize_value::<T>)
+    }
+
+    /// Registers `T` under `tag`, (de)serialized with `serialize` and `deserialize`, for the types
+    /// that don't implement serde's traits.
+    fn register_with<T: Any>(
+        &mut self,
+        tag: &str,
+        serialize: SerializeFn,
+        deserialize: DeserializeFn,
+    ) {
+        if let Some(previous) = self.tags.insert(tag.to_string(), TypeId::of::<T>()) {
+            self.types.remove(&previous);
+        }
+        let registered = RegisteredType {
+            tag: tag.to_string(),
+            serialize,
+            deserialize,
+        };
+        if let Some(previous) = self.types.insert(TypeId::of::<T>(), registered) {
+            if previous.tag != tag {
+                self.tags.remove(&previous.tag);
+            }
+        }
+    }
+
+    pub fn is_registered<T: Any>(&self) -> bool {
+        self.types.contains_key(&TypeId::of::<T>())
+    }
+}
+
+fn serialize_value<T: Any + Serialize>(value: &dyn Any) -> Result<Value, String> {
+    let value = value
+        .downcast_ref::<T>()
+        .ok_or_else(|| String::from("Mismatched registered type"))?;
+    serde_json::to_value(value).map_err(|error| error.to_string())
+}
+
+fn deserialize_value<T: Any + DeserializeOwned>(value: Value) -> Result<Box<dyn Any>, String> {
+    let value: T = serde_json::from_value(value).map_err(|error| error.to_string())?;
+    Ok(Box::new(value))
+}
+
+/// A [DictTracker] as serialized, along with the index of the segment of its dictionary.
+#[derive(Serialize, Deserialize)]
+struct SerializedDictTracker {
+    segment_index: isize,
+    /// Entries of the dictionary, sorted by key
+    entries: Vec<(MaybeRelocatable, MaybeRelocatable)>,
+    default_value: Option<MaybeRelocatable>,
+    current_ptr: Relocatable,
+}
+
+// The keys of the dictionaries aren't strings, so they are serialized as lists of trackers and
+// entries rather than as JSON maps
+fn serialize_dict_manager(value: &dyn Any) -> Result<Value, String> {
+    let dict_manager = value
+        .downcast_ref::<Rc<RefCell<DictManager>>>()
+        .ok_or_else(|| String::from("Mismatched registered type"))?;
+    let mut trackers: Vec<_> = dict_manager
+        .borrow()
+        .trackers
+        .iter()
+        .map(|(segment_index, tracker)| {
+            let (dict, default_value) = match &tracker.data {
+                Dictionary::SimpleDictionary(dict) => (dict, None),
+                Dictionary::DefaultDictionary {
+                    dict,
+                    default_value,
+                } => (dict, Some(default_value.clone())),
+            };
+            let mut entries: Vec<_> = dict.iter().map(|(k, v)| (k.clone(), v.clone())).collect();
+            entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+            SerializedDictTracker {
+                segment_index: *segment_index,
+                entries,
+                default_value,
+                current_ptr: tracker.current_ptr,
+            }
+        })
+        .collect();
+    trackers.sort_by_key(|tracker| tracker.segment_index);
+    serde_json::to_value(trackers).map_err(|error| error.to_string())
+}
+
+fn deserialize_dict_manager(value: Value) -> Result<Box<dyn Any>, String> {
+    let trackers: Vec<SerializedDictTracker> =
+        serde_json::from_value(value).map_err(|error| error.to_string())?;
+    let trackers = trackers
+        .into_iter()
+        .map(|tracker| {
+            let dict = tracker.entries.into_iter().collect();
+            let data = match tracker.default_value {
+                Some(default_value) => Dictionary::DefaultDictionary {
+                    dict,
+                    default_value,
+                },
+                None => Dictionary::SimpleDictionary(dict),
+            };
+            (
+                tracker.segment_index,
+                DictTracker {
+                    data,
+                    current_ptr: tracker.current_ptr,
+                },
+            )
+        })
+        .collect();
+    Ok(Box::new(Rc::new(RefCell::new(DictManager { trackers }))))
+}
+
+/// A scope variable serialized along with the tag of its type.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SerializedScopeVariable {
+    pub type_tag: String,
+    pub value: Value,
+}
+
+/// The serialized [ExecutionScopes], from the main scope to the current one.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SerializedExecutionScopes {
+    pub scopes: Vec<BTreeMap<String, SerializedScopeVariable>>,
+}
+
+impl ExecutionScopes {
+    /// Serializes every scope variable, failing if the type of one of them isn't registered in
+    /// `registry`.
+    pub fn serialize_scopes(
+        &self,
+        registry: &ScopeSerdeRegistry,
+    ) -> Result<SerializedExecutionScopes, ExecScopeError> {
+        let scopes = self
+            .data
+            .iter()
+            .map(|scope| {
+                scope
+                    .iter()
+                    .map(|(name, variable)| -> Result<_, ExecScopeError> {
+                        // Deref the box, as the TypeId of the box itself isn't the one registered
+                        let registered =
+                            registry.types.get(&(**variable).type_id()).ok_or_else(|| {
+                                ExecScopeError::UnregisteredScopeType(name.as_str().into())
+                            })?;
+                        let value = (registered.serialize)(&**variable).map_err(|error| {
+                            ExecScopeError::ScopeVariableSerde(Box::new((name.clone(), error)))
+                        })?;
+                        Ok((
+                            name.clone(),
+                            SerializedScopeVariable {
+                                type_tag: registered.tag.clone(),
+                                value,
+                            },
+                        ))
+                    })
+                    .collect::<Result<BTreeMap<_, _>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(SerializedExecutionScopes { scopes })
+    }
+
+    /// Rebuilds the scopes serialized by [serialize_scopes](Self::serialize_scopes), with the
+    /// types registered under the tags of their variables in `registry`.
+    pub fn deserialize_scopes(
+        serialized: SerializedExecutionScopes,
+        registry: &ScopeSerdeRegistry,
+    ) -> Result<ExecutionScopes, ExecScopeError> {
+        if serialized.scopes.is_empty() {
+            return Err(ExecScopeError::NoScopeError);
+        }
+        let data = serialized
+            .scopes
+            .into_iter()
+            .map(|scope| {
+                scope
+                    .into_iter()
+                    .map(|(name, variable)| -> Result<_, ExecScopeError> {
+                        let registered = registry
+                            .tags
+                            .get(&variable.type_tag)
+                            .and_then(|type_id| registry.types.get(type_id))
+                            .ok_or_else(|| {
+                                ExecScopeError::UnknownScopeTypeTag(Box::new((
+                                    name.clone(),
+                                    variable.type_tag.clone(),
+                                )))
+                            })?;
+                        let value = (registered.deserialize)(variable.value).map_err(|error| {
+                            ExecScopeError::ScopeVariableSerde(Box::new((name.clone(), error)))
+                        })?;
+                        Ok((name, value))
+                    })
+                    .collect::<Result<HashMap<_, _>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ExecutionScopes { data })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+    struct Accumulator {
+        total: Felt252,
+        count: usize,
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn scopes_round_trip() {
+        let mut registry = ScopeSerdeRegistry::with_common_types();
+        registry.register::<Accumulator>("accumulator");
+
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value("n", 3_u64);
+        exec_scopes.insert_value("x", Felt252::new(17));
+        exec_scopes.enter_scope(HashMap::new());
+        exec_scopes.insert_value("values", vec![Felt252::new(1), Felt252::new(2)]);
+        exec_scopes.insert_value(
+            "accumulator",
+            Accumulator {
+                total: Felt252::new(10),
+                count: 2,
+            },
+        );
+
+        let serialized = exec_scopes.serialize_scopes(&registry).unwrap();
+        assert_eq!(serialized.scopes.len(), 2);
+        assert_eq!(serialized.scopes[0]["n"].type_tag, "u64");
+
+        // Through a checkpoint file
+        let json = serde_json::to_string(&serialized).unwrap();
+        let serialized: SerializedExecutionScopes = serde_json::from_str(&json).unwrap();
+        let mut exec_scopes = ExecutionScopes::deserialize_scopes(serialized, &registry).unwrap();
+
+        assert_eq!(
+            exec_scopes.get::<Vec<Felt252>>("values").unwrap(),
+            [Felt252::new(1), Felt252::new(2)]
+        );
+        assert_eq!(
+            exec_scopes.get_ref::<Accumulator>("accumulator").unwrap(),
+            &Accumulator {
+                total: Felt252::new(10),
+                count: 2,
+            }
+        );
+        exec_scopes.exit_scope().unwrap();
+        assert_matches!(exec_scopes.get::<u64>("n"), Ok(3));
+        assert_eq!(exec_scopes.get::<Felt252>("x").unwrap(), Felt252::new(17));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn dict_manager_round_trip() {
+        let mut dict_manager = DictManager::new();
+        dict_manager.trackers.insert(
+            2,
+            DictTracker::new_with_initial(
+                Relocatable::from((2, 4)),
+                HashMap::from([
+                    (MaybeRelocatable::from(5), MaybeRelocatable::from((1, 3))),
+                    (MaybeRelocatable::from(1), MaybeRelocatable::from(7)),
+                ]),
+            ),
+        );
+        dict_manager.trackers.insert(
+            3,
+            DictTracker::new_default_dict(
+                Relocatable::from((3, 0)),
+                &MaybeRelocatable::from(17),
+                None,
+            ),
+        );
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value("dict_manager", Rc::new(RefCell::new(dict_manager.clone())));
+
+        let registry = ScopeSerdeRegistry::with_common_types();
+        let serialized = exec_scopes.serialize_scopes(&registry).unwrap();
+        assert_eq!(
+            serialized.scopes[0]["dict_manager"].type_tag,
+            "dict_manager"
+        );
+        let exec_scopes = ExecutionScopes::deserialize_scopes(serialized, &registry).unwrap();
+        assert_eq!(
+            *exec_scopes.get_dict_manager().unwrap().borrow(),
+            dict_manager
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn serialize_unregistered_type() {
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value("n", 3_u64);
+        assert_eq!(
+            exec_scopes.serialize_scopes(&ScopeSerdeRegistry::new()),
+            Err(ExecScopeError::UnregisteredScopeType("n".into()))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn deserialize_unknown_tag_or_bad_value() {
+        let registry = ScopeSerdeRegistry::with_common_types();
+        let scopes_with = |type_tag: &str, value: Value| SerializedExecutionScopes {
+            scopes: vec![BTreeMap::from([(
+                String::from("n"),
+                SerializedScopeVariable {
+                    type_tag: type_tag.to_string(),
+                    value,
+                },
+            )])],
+        };
+        assert_matches!(
+            ExecutionScopes::deserialize_scopes(scopes_with("u32", Value::from(3)), &registry),
+            Err(ExecScopeError::UnknownScopeTypeTag(bx)) if *bx == (String::from("n"), String::from("u32"))
+        );
+        assert_matches!(
+            ExecutionScopes::deserialize_scopes(scopes_with("bool", Value::from(3)), &registry),
+            Err(ExecScopeError::ScopeVariableSerde(bx)) if bx.0 == "n"
+        );
+        assert_matches!(
+            ExecutionScopes::deserialize_scopes(SerializedExecutionScopes::default(), &registry),
+            Err(ExecScopeError::NoScopeError)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn register_replaces_tag() {
+        let mut registry = ScopeSerdeRegistry::new();
+        registry.register::<u64>("int");
+        registry.register::<usize>("int");
+        assert!(!registry.is_registered::<u64>());
+        assert!(registry.is_registered::<usize>());
+        registry.register::<usize>("size");
+        assert!(!registry.tags.contains_key("int"));
+    }
+}
@@ -0,0 +1,239 @@
+//! Optional registry of the types of scope variables.
+//!
+//! Scope variables are stored as `Box<dyn Any>`, so a hint reading a variable with a different
+//! type than the one it was created with only sees a missing variable. Once enabled with
+//! [ExecutionScopes::enable_scope_types], hints going through the `*_declared` accessors record
+//! the type of the variables they create, and reading one with another type fails with an error
+//! naming both hints.
+
+use crate::stdlib::{
+    any::{type_name, Any, TypeId},
+    collections::HashMap,
+    prelude::*,
+};
+
+use crate::{any_box, types::exec_scope::ExecutionScopes, vm::errors::hint_errors::HintError};
+
+/// Name of the main scope variable holding the [ScopeTypes] of a run
+pub const SCOPE_TYPES: &str = "__scope_types";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScopeVariableDeclaration {
+    pub type_name: &'static str,
+    type_id: TypeId,
+    /// Hint that created the variable
+    pub hint: String,
+}
+
+/// Types of the scope variables declared so far, by name.
+#[derive(Clone, Debug, Default)]
+pub struct ScopeTypes {
+    declarations: HashMap<String, ScopeVariableDeclaration>,
+}
+
+impl ScopeTypes {
+    pub fn get(&self, name: &str) -> Option<&ScopeVariableDeclaration> {
+        self.declarations.get(name)
+    }
+
+    /// Fails if `name` was declared with a type other than `T`, `hint` being the one using it.
+    fn check<T: Any>(&self, hint: &str, name: &str) -> Result<(), HintError> {
+        match self.declarations.get(name) {
+            Some(declaration) if declaration.type_id != TypeId::of::<T>() => {
+                Err(HintError::ScopeVariableTypeMismatch(Box::new((
+                    name.to_string(),
+                    format!("{} by hint {}", declaration.type_name, declaration.hint),
+                    format!("{} by hint {}", type_name::<T>(), hint),
+                ))))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn declare<T: Any>(&mut self, hint: &str, name: &str) -> Result<(), HintError> {
+        self.check::<T>(hint, name)?;
+        self.declarations
+            .entry(name.to_string())
+            .or_insert_with(|| ScopeVariableDeclaration {
+                type_name: type_name::<T>(),
+                type_id: TypeId::of::<T>(),
+                hint: hint.to_string(),
+            });
+        Ok(())
+    }
+}
+
+impl ExecutionScopes {
+    /// Starts recording the types of the variables created through
+    /// [insert_declared](Self::insert_declared).
+    pub fn enable_scope_types(&mut self) {
+        if let Some(main_scope) = self.data.first_mut() {
+            main_scope
+                .entry(SCOPE_TYPES.to_string())
+                .or_insert_with(|| any_box!(ScopeTypes::default()));
+        }
+    }
+
+    pub fn scope_types(&self) -> Option<&ScopeTypes> {
+        self.data.first()?.get(SCOPE_TYPES)?.downcast_ref()
+    }
+
+    fn scope_types_mut(&mut self) -> Option<&mut ScopeTypes> {
+        self.data.first_mut()?.get_mut(SCOPE_TYPES)?.downcast_mut()
+    }
+
+    /// Inserts `value` into the current scope, declaring `name` as a `T` created by `hint`.
+    /// Fails if `name` was already declared with another type.
+    pub fn insert_declared<T: Any>(
+        &mut self,
+        hint: &str,
+        name: &str,
+        value: T,
+    ) -> Result<(), HintError> {
+        if let Some(scope_types) = self.scope_types_mut() {
+            scope_types.declare::<T>(hint, name)?;
+        }
+        self.insert_value(name, value);
+        Ok(())
+    }
+
+    /// Same as [get](Self::get), failing with the hints involved if `name` was declared with
+    /// another type.
+    pub fn get_declared<T: Any + Clone>(&self, hint: &str, name: &str) -> Result<T, HintError> {
+        self.check_declared::<T>(hint, name)?;
+        self.get(name)
+    }
+
+    /// Same as [get_ref](Self::get_ref), failing with the hints involved if `name` was declared
+    /// with another type.
+    pub fn get_declared_ref<T: Any>(&self, hint: &str, name: &str) -> Result<&T, HintError> {
+        self.check_declared::<T>(hint, name)?;
+        self.get_ref(name)
+    }
+
+    /// Same as [get_mut_ref](Self::get_mut_ref), failing with the hints involved if `name` was
+    /// declared with another type.
+    pub fn get_declared_mut_ref<T: Any>(
+        &mut self,
+        hint: &str,
+        name: &str,
+    ) -> Result<&mut T, HintError> {
+        self.check_declared::<T>(hint, name)?;
+        self.get_mut_ref(name)
+    }
+
+    fn check_declared<T: Any>(&self, hint: &str, name: &str) -> Result<(), HintError> {
+        match self.scope_types() {
+            Some(scope_types) => scope_types.check::<T>(hint, name),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hint_processor::{
+            builtin_hint_processor::builtin_hint_processor_definition::{
+                BuiltinHintProcessor, HintFunc, HintProcessorData,
+            },
+            hint_processor_definition::HintProcessorLogic,
+        },
+        stdlib::rc::Rc,
+        types::constants::Constants,
+        utils::test_utils::*,
+        vm::vm_core::VirtualMachine,
+    };
+    use assert_matches::assert_matches;
+    use felt::Felt252;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn declared_variable_round_trip() {
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.enable_scope_types();
+        exec_scopes.insert_declared("create_n", "n", 3_u64).unwrap();
+        assert_matches!(exec_scopes.get_declared::<u64>("read_n", "n"), Ok(3));
+        *exec_scopes
+            .get_declared_mut_ref::<u64>("increment_n", "n")
+            .unwrap() += 1;
+        assert_matches!(exec_scopes.get_declared_ref::<u64>("read_n", "n"), Ok(4));
+        assert_eq!(
+            exec_scopes.scope_types().unwrap().get("n").unwrap().hint,
+            "create_n"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn type_mismatch_names_both_hints() {
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.enable_scope_types();
+        exec_scopes.insert_declared("create_n", "n", 3_u64).unwrap();
+
+        let error = exec_scopes
+            .get_declared::<Felt252>("read_n", "n")
+            .unwrap_err();
+        assert_matches!(error, HintError::ScopeVariableTypeMismatch(_));
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Scope variable n was declared as u64 by hint create_n, but used as {} by hint read_n",
+                type_name::<Felt252>()
+            )
+        );
+        assert_matches!(
+            exec_scopes.insert_declared("overwrite_n", "n", true),
+            Err(HintError::ScopeVariableTypeMismatch(_))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn without_scope_types_declarations_are_ignored() {
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_declared("create_n", "n", 3_u64).unwrap();
+        assert!(exec_scopes.scope_types().is_none());
+        assert_matches!(
+            exec_scopes.get_declared::<Felt252>("read_n", "n"),
+            Err(HintError::VariableNotInScopeError(_))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn mismatch_across_hints() {
+        let create_n = HintFunc(Box::new(|_, exec_scopes, _, _, _| {
+            exec_scopes.insert_declared("create_n", "n", 3_u64)
+        }));
+        let read_n = HintFunc(Box::new(|_, exec_scopes, _, _, _| {
+            exec_scopes
+                .get_declared::<Felt252>("read_n", "n")
+                .map(|_| ())
+        }));
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        hint_processor.add_hint(String::from("create_n"), Rc::new(create_n));
+        hint_processor.add_hint(String::from("read_n"), Rc::new(read_n));
+
+        let mut vm = vm!();
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.enable_scope_types();
+        for (hint, result_is_ok) in [("create_n", true), ("read_n", false)] {
+            let hint_data = any_box!(HintProcessorData::new_default(
+                String::from(hint),
+                HashMap::new()
+            ));
+            let result = hint_processor.execute_hint(
+                &mut vm,
+                &mut exec_scopes,
+                &hint_data,
+                &Constants::new(),
+            );
+            assert_eq!(result.is_ok(), result_is_ok);
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! Conversions between felts and Cairo short strings.
+//!
+//! A short string is an ASCII string of at most 31 characters stored in a single felt, its
+//! characters being the big-endian bytes of the felt, without leading zeros.
+
+use crate::stdlib::prelude::*;
+
+use felt::Felt252;
+
+use crate::types::errors::math_errors::MathError;
+
+/// Maximum number of characters of a short string
+pub const MAX_SHORT_STRING_LEN: usize = 31;
+
+/// Encodes `string` as a short string.
+pub fn encode_short_string(string: &str) -> Result<Felt252, MathError> {
+    if let Some(character) = string.chars().find(|character| !character.is_ascii()) {
+        return Err(MathError::NonAsciiShortString(character));
+    }
+    if string.len() > MAX_SHORT_STRING_LEN {
+        return Err(MathError::ShortStringTooLong(string.len()));
+    }
+    Ok(Felt252::from_bytes_be(string.as_bytes()))
+}
+
+/// Decodes `felt` as a short string made of printable ASCII characters, returns None if some
+/// of its bytes aren't.
+pub fn decode_short_string(felt: &Felt252) -> Option<String> {
+    let bytes = short_string_bytes(felt);
+    if !bytes
+        .iter()
+        .all(|byte| byte.is_ascii_graphic() || *byte == b' ')
+    {
+        return None;
+    }
+    Some(bytes.iter().map(|byte| *byte as char).collect())
+}
+
+/// Decodes `felt` as a short string, escaping the bytes that aren't printable ASCII characters
+/// as well as quotes and backslashes, the way `str::escape_default` does.
+pub fn escape_short_string(felt: &Felt252) -> String {
+    short_string_bytes(felt)
+        .iter()
+        .flat_map(|byte| core::ascii::escape_default(*byte))
+        .map(char::from)
+        .collect()
+}
+
+fn short_string_bytes(felt: &Felt252) -> Vec<u8> {
+    let bytes = felt.to_be_bytes();
+    let start = bytes
+        .iter()
+        .position(|byte| *byte != 0)
+        .unwrap_or(bytes.len());
+    bytes[start..].to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+    use num_traits::Zero;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn short_string_round_trip() {
+        // Values extracted from cairo book example
+        let felt = Felt252::new(5735816763073854913753904210465_u128);
+        assert_eq!(encode_short_string("Hello, Scarb!"), Ok(felt.clone()));
+        assert_eq!(decode_short_string(&felt).as_deref(), Some("Hello, Scarb!"));
+        assert_eq!(escape_short_string(&felt), "Hello, Scarb!");
+
+        let longest = "a".repeat(MAX_SHORT_STRING_LEN);
+        let felt = encode_short_string(&longest).unwrap();
+        assert_eq!(decode_short_string(&felt), Some(longest));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn empty_short_string() {
+        assert_eq!(encode_short_string(""), Ok(Felt252::zero()));
+        assert_eq!(decode_short_string(&Felt252::zero()).as_deref(), Some(""));
+        assert_eq!(escape_short_string(&Felt252::zero()), "");
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn encode_invalid_short_string() {
+        assert_matches!(
+            encode_short_string(&"a".repeat(MAX_SHORT_STRING_LEN + 1)),
+            Err(MathError::ShortStringTooLong(32))
+        );
+        assert_matches!(
+            encode_short_string("caf\u{e9}"),
+            Err(MathError::NonAsciiShortString('\u{e9}'))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn escape_non_printable_bytes() {
+        let felt = Felt252::from_bytes_be(b"a\"b\nc\x01");
+        assert_eq!(decode_short_string(&felt), None);
+        assert_eq!(escape_short_string(&felt), "a\\\"b\\nc\\x01");
+        // Felts that don't fit in 31 bytes are escaped as well
+        let felt = Felt252::from(-1);
+        assert_eq!(decode_short_string(&felt), None);
+        assert!(escape_short_string(&felt).starts_with("\\x08"));
+    }
+}
@@ -108,6 +108,9 @@ pub mod test_utils {
                     segment_sizes: HashMap::new(),
                     segment_used_sizes: None,
                     public_memory_offsets: HashMap::new(),
+                    segment_capacities: Vec::new(),
+                    segment_names: $crate::stdlib::collections::BTreeMap::new(),
+                    free_segments: $crate::stdlib::collections::BTreeSet::new(),
                 }
 
             }
@@ -257,21 +260,24 @@ pub mod test_utils {
         //Program with builtins
         ( $( $builtin_name: expr ),* ) => {{
             let shared_program_data = SharedProgramData {
-                data: crate::stdlib::vec::Vec::new(),
+                data: crate::types::program::ProgramData::default(),
                 hints: crate::stdlib::collections::HashMap::new(),
                 main: None,
                 start: None,
                 end: None,
                 error_message_attributes: crate::stdlib::vec::Vec::new(),
+                custom_attributes: crate::stdlib::vec::Vec::new(),
                 instruction_locations: None,
-                identifiers: crate::stdlib::collections::HashMap::new(),
+                identifiers: $crate::stdlib::collections::BTreeMap::new(),
                 reference_manager: Program::get_reference_list(&ReferenceManager {
                     references: crate::stdlib::vec::Vec::new(),
                 }),
+                compiler_version: None,
+                code_map: Default::default(),
             };
             Program {
                 shared_program_data: Arc::new(shared_program_data),
-                constants: crate::stdlib::collections::HashMap::new(),
+                constants: crate::types::constants::Constants::new(),
                 builtins: vec![$( $builtin_name ),*],
             }
         }};
@@ -312,12 +318,10 @@ pub mod test_utils {
             crate::stdlib::string::String,
             crate::serde::deserialize_program::Identifier,
         >,
-        pub(crate) constants: crate::stdlib::collections::HashMap<
-            crate::stdlib::string::String,
-            crate::utils::Felt252,
-        >,
+        pub(crate) constants: crate::types::constants::Constants,
         pub(crate) builtins: crate::utils::Vec<crate::serde::deserialize_program::BuiltinName>,
         pub(crate) reference_manager: crate::serde::deserialize_program::ReferenceManager,
+        pub(crate) compiler_version: Option<crate::stdlib::string::String>,
     }
 
     impl Default for ProgramFlat {
@@ -336,23 +340,35 @@ pub mod test_utils {
                 reference_manager: crate::serde::deserialize_program::ReferenceManager {
                     references: crate::utils::Vec::new(),
                 },
+                compiler_version: Default::default(),
             }
         }
     }
 
     impl From<ProgramFlat> for Program {
         fn from(val: ProgramFlat) -> Self {
+            let data = crate::types::program::ProgramData::try_from(val.data).unwrap();
+            let code_map = crate::types::code_map::CodeMap::new(
+                &data,
+                val.main,
+                val.start,
+                val.identifiers.values(),
+            )
+            .unwrap_or_default();
             Program {
                 shared_program_data: Arc::new(SharedProgramData {
-                    data: val.data,
+                    data,
                     hints: val.hints,
                     main: val.main,
                     start: val.start,
                     end: val.end,
                     error_message_attributes: val.error_message_attributes,
+                    custom_attributes: crate::stdlib::vec::Vec::new(),
                     instruction_locations: val.instruction_locations,
-                    identifiers: val.identifiers,
+                    identifiers: val.identifiers.into_iter().collect(),
                     reference_manager: Program::get_reference_list(&val.reference_manager),
+                    compiler_version: val.compiler_version,
+                    code_map,
                 }),
                 constants: val.constants,
                 builtins: val.builtins,
@@ -439,7 +455,7 @@ pub mod test_utils {
                 &mut $vm,
                 $exec_scopes,
                 &any_box!(hint_data),
-                &crate::stdlib::collections::HashMap::new(),
+                &crate::types::constants::Constants::new(),
             )
         }};
         ($vm:expr, $ids_data:expr, $hint_code:expr) => {{
@@ -452,7 +468,7 @@ pub mod test_utils {
                 &mut $vm,
                 exec_scopes_ref!(),
                 &any_box!(hint_data),
-                &crate::stdlib::collections::HashMap::new(),
+                &crate::types::constants::Constants::new(),
             )
         }};
     }
@@ -598,7 +614,13 @@ pub mod test_utils {
 #[cfg(test)]
 mod test {
     use crate::hint_processor::hint_processor_definition::HintProcessorLogic;
-    use crate::stdlib::{cell::RefCell, collections::HashMap, rc::Rc, string::String, vec::Vec};
+    use crate::stdlib::{
+        cell::RefCell,
+        collections::{BTreeMap, HashMap},
+        rc::Rc,
+        string::String,
+        vec::Vec,
+    };
     use crate::{
         hint_processor::{
             builtin_hint_processor::{
@@ -608,7 +630,12 @@ mod test {
             hint_processor_definition::HintReference,
         },
         serde::deserialize_program::{BuiltinName, ReferenceManager},
-        types::{exec_scope::ExecutionScopes, program::Program, relocatable::MaybeRelocatable},
+        types::{
+            constants::Constants,
+            exec_scope::ExecutionScopes,
+            program::{Program, ProgramData},
+            relocatable::MaybeRelocatable,
+        },
         utils::test_utils::*,
         vm::{trace::trace_entry::TraceEntry, vm_core::VirtualMachine, vm_memory::memory::Memory},
     };
@@ -919,21 +946,24 @@ mod test {
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn program_macro() {
         let shared_data = SharedProgramData {
-            data: Vec::new(),
+            data: ProgramData::default(),
             hints: HashMap::new(),
             main: None,
             start: None,
             end: None,
             error_message_attributes: Vec::new(),
+            custom_attributes: Vec::new(),
             instruction_locations: None,
-            identifiers: HashMap::new(),
+            identifiers: BTreeMap::new(),
             reference_manager: Program::get_reference_list(&ReferenceManager {
                 references: Vec::new(),
             }),
+            compiler_version: None,
+            code_map: Default::default(),
         };
         let program = Program {
             shared_program_data: Arc::new(shared_data),
-            constants: HashMap::new(),
+            constants: Constants::new(),
             builtins: Vec::new(),
         };
         assert_eq!(program, program!())
@@ -943,21 +973,24 @@ mod test {
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn program_macro_with_builtin() {
         let shared_data = SharedProgramData {
-            data: Vec::new(),
+            data: ProgramData::default(),
             hints: HashMap::new(),
             main: None,
             start: None,
             end: None,
             error_message_attributes: Vec::new(),
+            custom_attributes: Vec::new(),
             instruction_locations: None,
-            identifiers: HashMap::new(),
+            identifiers: BTreeMap::new(),
             reference_manager: Program::get_reference_list(&ReferenceManager {
                 references: Vec::new(),
             }),
+            compiler_version: None,
+            code_map: Default::default(),
         };
         let program = Program {
             shared_program_data: Arc::new(shared_data),
-            constants: HashMap::new(),
+            constants: Constants::new(),
             builtins: vec![BuiltinName::range_check],
         };
 
@@ -968,21 +1001,24 @@ mod test {
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn program_macro_custom_definition() {
         let shared_data = SharedProgramData {
-            data: Vec::new(),
+            data: ProgramData::default(),
             hints: HashMap::new(),
             main: Some(2),
             start: None,
             end: None,
             error_message_attributes: Vec::new(),
+            custom_attributes: Vec::new(),
             instruction_locations: None,
-            identifiers: HashMap::new(),
+            identifiers: BTreeMap::new(),
             reference_manager: Program::get_reference_list(&ReferenceManager {
                 references: Vec::new(),
             }),
+            compiler_version: None,
+            code_map: Default::default(),
         };
         let program = Program {
             shared_program_data: Arc::new(shared_data),
-            constants: HashMap::new(),
+            constants: Constants::new(),
             builtins: vec![BuiltinName::range_check],
         };
 
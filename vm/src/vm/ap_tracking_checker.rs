@@ -0,0 +1,335 @@
+//! Debug-mode check of the ap tracking data of a program against the actual ap of its run.
+//!
+//! The compiler tracks how far ap moved since the start of each ap tracking group, and
+//! references to ap-based values are resolved in hints by moving back by the difference between
+//! the offsets of the hint and of the reference. When this tracking doesn't match the actual ap
+//! movement, hints silently read and write the wrong cells. The [ApTrackingChecker] catches the
+//! first instruction where it doesn't, see [CairoRunner::set_check_ap_tracking].
+//!
+//! [CairoRunner::set_check_ap_tracking]: crate::vm::runners::cairo_runner::CairoRunner::set_check_ap_tracking
+
+use crate::stdlib::{collections::HashMap, fmt, prelude::*, sync::Arc};
+
+use num_traits::ToPrimitive;
+
+use crate::{
+    serde::deserialize_program::ApTracking,
+    types::{
+        program::{Program, SharedProgramData},
+        relocatable::Relocatable,
+    },
+    vm::{
+        decoding::decoder::decode_instruction, errors::vm_errors::VirtualMachineError,
+        vm_core::VirtualMachine,
+    },
+};
+
+/// An instruction whose ap tracking data doesn't match the ap of the run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ApTrackingMismatch {
+    pub pc: Relocatable,
+    pub ap_tracking: ApTracking,
+    pub ap: Relocatable,
+    /// The ap implied by the tracking data of `anchor_pc`, an earlier instruction of the same
+    /// group and frame
+    pub expected_ap: Relocatable,
+    pub anchor_pc: Relocatable,
+    /// The ap-based references of the hints at `pc` resolved through the group, which would
+    /// point to the wrong cells
+    pub references: Vec<String>,
+}
+
+impl fmt::Display for ApTrackingMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "ap tracking mismatch at pc {} (group {}, offset {}): ap is {}, but the tracking data of pc {} expects {}",
+            self.pc,
+            self.ap_tracking.group,
+            self.ap_tracking.offset,
+            self.ap,
+            self.anchor_pc,
+            self.expected_ap
+        )?;
+        if !self.references.is_empty() {
+            write!(f, ". Affected references: {}", self.references.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+// Start of an ap tracking group in a frame, as seen at `pc`
+struct Anchor {
+    group_start: Option<Relocatable>,
+    pc: Relocatable,
+}
+
+/// Checks, before each step, that the ap of the run moved as the ap tracking data of the
+/// program says since the last instruction of the same group and frame.
+///
+/// The tracking data is read from the hints and, when the program has debug info, from every
+/// instruction. Groups entered through a jump are anchored again, as the compiler can't track ap
+/// across the back edges of loops.
+pub struct ApTrackingChecker {
+    program_base: Relocatable,
+    shared_program_data: Arc<SharedProgramData>,
+    ap_trackings: HashMap<usize, ApTracking>,
+    // Anchors of each group, by frame
+    anchors: HashMap<Relocatable, HashMap<usize, Anchor>>,
+    // fp and expected next pc of the last checked step
+    last_step: Option<(Relocatable, Option<Relocatable>)>,
+}
+
+impl ApTrackingChecker {
+    pub fn new(program: &Program, program_base: Relocatable) -> Self {
+        let shared_program_data = program.shared_program_data.clone();
+        let mut ap_trackings = HashMap::new();
+        if let Some(instruction_locations) = &shared_program_data.instruction_locations {
+            for (pc, location) in instruction_locations {
+                if let Some(flow_tracking_data) = &location.flow_tracking_data {
+                    ap_trackings.insert(*pc, flow_tracking_data.ap_tracking.clone());
+                }
+            }
+        }
+        for (pc, hints) in shared_program_data.hints.iter() {
+            if let Some(hint) = hints.first() {
+                ap_trackings.insert(*pc, hint.flow_tracking_data.ap_tracking.clone());
+            }
+        }
+        ApTrackingChecker {
+            program_base,
+            shared_program_data,
+            ap_trackings,
+            anchors: HashMap::new(),
+            last_step: None,
+        }
+    }
+
+    /// Checks the ap tracking data of the next instruction to run.
+    pub fn check(&mut self, vm: &VirtualMachine) -> Result<(), VirtualMachineError> {
+        let (pc, ap, fp) = (vm.get_pc(), vm.get_ap(), vm.get_fp());
+        let mut jumped = false;
+        if let Some((last_fp, next_pc)) = self.last_step.take() {
+            if fp < last_fp {
+                // Returned from the frames after fp
+                self.anchors.retain(|frame, _| *frame <= fp);
+            } else if fp == last_fp {
+                jumped = next_pc != Some(pc);
+            }
+        }
+        self.last_step = Some((fp, self.next_pc(vm)));
+
+        if pc.segment_index != self.program_base.segment_index {
+            return Ok(());
+        }
+        let Some(ap_tracking) = pc
+            .offset
+            .checked_sub(self.program_base.offset)
+            .and_then(|offset| self.ap_trackings.get(&offset))
+        else {
+            return Ok(());
+        };
+        let group_start = (ap - ap_tracking.offset).ok();
+        let anchors = self.anchors.entry(fp).or_default();
+        if jumped || !anchors.contains_key(&ap_tracking.group) {
+            anchors.insert(ap_tracking.group, Anchor { group_start, pc });
+            return Ok(());
+        }
+        let anchor = &anchors[&ap_tracking.group];
+        if anchor.group_start == group_start {
+            return Ok(());
+        }
+        let anchor_pc = anchor.pc;
+        let expected_ap = match anchor.group_start {
+            Some(anchor_start) => (anchor_start + ap_tracking.offset)?,
+            None => ap,
+        };
+        Err(VirtualMachineError::ApTrackingMismatch(Box::new(
+            ApTrackingMismatch {
+                pc,
+                ap_tracking: ap_tracking.clone(),
+                ap,
+                expected_ap,
+                anchor_pc,
+                references: self.hint_references(pc, ap_tracking.group),
+            },
+        )))
+    }
+
+    // The pc following the instruction at pc, None if it can't be decoded
+    fn next_pc(&self, vm: &VirtualMachine) -> Option<Relocatable> {
+        let encoding = vm.segments.memory.get_integer(vm.get_pc()).ok()?.to_u64()?;
+        let instruction = decode_instruction(encoding).ok()?;
+        (vm.get_pc() + instruction.size()).ok()
+    }
+
+    // Names of the ap-based references of the hints at pc tracked in the given group
+    fn hint_references(&self, pc: Relocatable, group: usize) -> Vec<String> {
+        let Some(hints) = self
+            .shared_program_data
+            .hints
+            .get(&(pc.offset - self.program_base.offset))
+        else {
+            return Vec::new();
+        };
+        let mut references: Vec<String> = hints
+            .iter()
+            .flat_map(|hint| hint.flow_tracking_data.reference_ids.iter())
+            .filter_map(|(name, id)| {
+                let ap_tracking = self
+                    .shared_program_data
+                    .reference_manager
+                    .get(*id)?
+                    .ap_tracking_data
+                    .as_ref()?;
+                (ap_tracking.group == group)
+                    .then(|| format!("{name} (offset {})", ap_tracking.offset))
+            })
+            .collect();
+        references.sort();
+        references.dedup();
+        references
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hint_processor::{
+            builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+            hint_processor_definition::HintReference,
+        },
+        serde::deserialize_program::{
+            FlowTrackingData, HintParams, InputFile, InstructionLocation, Location, OffsetValue,
+        },
+        types::{instruction::Register, program::ProgramData, relocatable::MaybeRelocatable},
+        utils::test_utils::*,
+        vm::runners::cairo_runner::CairoRunner,
+    };
+    use assert_matches::assert_matches;
+    use felt::Felt252;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    fn flow_tracking_data(offset: usize) -> FlowTrackingData {
+        FlowTrackingData {
+            ap_tracking: ApTracking { group: 1, offset },
+            reference_ids: HashMap::new(),
+        }
+    }
+
+    fn instruction_location(offset: usize) -> InstructionLocation {
+        InstructionLocation {
+            inst: Location {
+                end_line: 0,
+                end_col: 0,
+                input_file: InputFile {
+                    filename: String::from("ap_tracking.cairo"),
+                },
+                parent_location: None,
+                start_line: 0,
+                start_col: 0,
+            },
+            hints: Vec::new(),
+            flow_tracking_data: Some(flow_tracking_data(offset)),
+        }
+    }
+
+    // [ap] = 5, ap++; [ap] = 5, ap++; ret, with a hint before the ret tracked at `ret_offset`
+    fn ap_tracking_program(ret_offset: usize) -> Program {
+        let mut program = program!(main = Some(0),);
+        let shared_program_data = Arc::get_mut(&mut program.shared_program_data).unwrap();
+        let set_ap = Felt252::new(0x480680017fff8000_u64);
+        shared_program_data.data = ProgramData::try_from(vec![
+            MaybeRelocatable::from(set_ap.clone()),
+            MaybeRelocatable::from(Felt252::new(5)),
+            MaybeRelocatable::from(set_ap),
+            MaybeRelocatable::from(Felt252::new(5)),
+            MaybeRelocatable::from(Felt252::new(0x208b7fff7fff7ffe_u64)),
+        ])
+        .unwrap();
+        shared_program_data.instruction_locations = Some(HashMap::from([
+            (0, instruction_location(0)),
+            (2, instruction_location(1)),
+        ]));
+        let mut hint_flow_tracking_data = flow_tracking_data(ret_offset);
+        hint_flow_tracking_data
+            .reference_ids
+            .insert(String::from("__main__.main.x"), 0);
+        shared_program_data.hints = HashMap::from([(
+            4,
+            vec![HintParams {
+                code: String::from("ids.x = 1"),
+                accessible_scopes: Vec::new(),
+                flow_tracking_data: hint_flow_tracking_data,
+            }],
+        )]);
+        shared_program_data.reference_manager = vec![HintReference {
+            offset1: OffsetValue::Reference(Register::AP, -1, false),
+            offset2: OffsetValue::Value(0),
+            dereference: true,
+            ap_tracking_data: Some(ApTracking {
+                group: 1,
+                offset: 1,
+            }),
+            cairo_type: Some(String::from("felt")),
+        }];
+        program
+    }
+
+    fn run_checked(program: &Program) -> Result<(), VirtualMachineError> {
+        let mut cairo_runner = cairo_runner!(program);
+        cairo_runner.set_check_ap_tracking(true);
+        let mut vm = vm!();
+        let end = cairo_runner.initialize(&mut vm).unwrap();
+        cairo_runner.run_until_pc(end, &mut vm, &mut BuiltinHintProcessor::new_empty())
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn ap_tracking_mismatch() {
+        let program = ap_tracking_program(3);
+        let error = run_checked(&program).unwrap_err();
+        assert_matches!(
+            &error,
+            VirtualMachineError::ApTrackingMismatch(mismatch) if **mismatch == ApTrackingMismatch {
+                pc: (0, 4).into(),
+                ap_tracking: ApTracking { group: 1, offset: 3 },
+                ap: (1, 4).into(),
+                expected_ap: (1, 5).into(),
+                anchor_pc: (0, 0).into(),
+                references: vec![String::from("__main__.main.x (offset 1)")],
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "ap tracking mismatch at pc 0:4 (group 1, offset 3): ap is 1:4, but the tracking data of pc 0:0 expects 1:5. Affected references: __main__.main.x (offset 1)"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn ap_tracking_match() {
+        // The hint runs once the tracking data is checked, and isn't supported
+        let program = ap_tracking_program(2);
+        assert_matches!(
+            run_checked(&program),
+            Err(VirtualMachineError::Hint(hint)) if hint.0 == 0
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn ap_tracking_not_checked_by_default() {
+        let program = ap_tracking_program(3);
+        let mut cairo_runner = cairo_runner!(program);
+        let mut vm = vm!();
+        let end = cairo_runner.initialize(&mut vm).unwrap();
+        assert_matches!(
+            cairo_runner.run_until_pc(end, &mut vm, &mut BuiltinHintProcessor::new_empty()),
+            Err(VirtualMachineError::Hint(_))
+        );
+    }
+}
@@ -0,0 +1,251 @@
+//! A snapshot of the state of a run in a single JSON document, to attach to bug reports or to
+//! show in a debugger.
+//!
+//! The snapshot holds the registers, the instruction at pc, the frames of the call stack walked
+//! from fp, the last cells written by the instructions run, and the names of the variables of each
+//! execution scope. The writes are taken from the memory journal, so they are only reported when
+//! it is enabled, see [VirtualMachine::enable_memory_journal].
+
+use crate::stdlib::prelude::*;
+
+use num_traits::ToPrimitive;
+use serde::Serialize;
+
+use crate::{
+    types::{exec_scope::ExecutionScopes, relocatable::Relocatable},
+    vm::{
+        decoding::decoder::decode_instruction, memory_journal::AccessKind, vm_core::VirtualMachine,
+    },
+};
+
+/// Frames walked from fp at most, as the fp chain of a corrupted stack may loop.
+const MAX_DEBUG_FRAMES: usize = 20;
+
+/// The state of a run, see [VirtualMachine::debug_state].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DebugState {
+    pub step: usize,
+    pub pc: Relocatable,
+    pub ap: Relocatable,
+    pub fp: Relocatable,
+    /// The instruction at pc, or None if pc doesn't hold a valid instruction
+    pub instruction: Option<DebugInstruction>,
+    /// The frames of the call stack, from the innermost one
+    pub frames: Vec<DebugFrame>,
+    /// The last cells written by the instructions run, from the oldest one, or None if the memory
+    /// journal is disabled
+    pub last_writes: Option<Vec<DebugWrite>>,
+    /// The names of the variables of each execution scope, sorted, from the outermost scope
+    pub scopes: Vec<Vec<String>>,
+}
+
+/// The instruction at pc, along with its decoded fields.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DebugInstruction {
+    pub encoding: String,
+    pub decoded: String,
+}
+
+/// A frame of the call stack: the cells before its fp hold the fp and pc to return to.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DebugFrame {
+    pub fp: Relocatable,
+    pub return_fp: Option<Relocatable>,
+    pub return_pc: Option<Relocatable>,
+}
+
+/// A cell written by an instruction, with the value it holds now.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DebugWrite {
+    pub step: usize,
+    pub address: Relocatable,
+    pub value: Option<String>,
+}
+
+impl VirtualMachine {
+    /// Takes a snapshot of the state of the run, reporting the last `last_writes` cells written
+    /// by its instructions if the memory journal is enabled.
+    pub fn debug_state(&self, exec_scopes: &ExecutionScopes, last_writes: usize) -> DebugState {
+        DebugState {
+            step: self.current_step,
+            pc: self.get_pc(),
+            ap: self.get_ap(),
+            fp: self.get_fp(),
+            instruction: self.debug_instruction(),
+            frames: self.debug_frames(),
+            last_writes: self.memory_journal.as_ref().map(|journal| {
+                let writes: Vec<_> = journal
+                    .iter()
+                    .filter(|access| access.kind == AccessKind::Write)
+                    .collect();
+                writes[writes.len().saturating_sub(last_writes)..]
+                    .iter()
+                    .map(|access| DebugWrite {
+                        step: access.step,
+                        address: access.address,
+                        value: self
+                            .segments
+                            .memory
+                            .get(&access.address)
+                            .map(|value| value.to_string()),
+                    })
+                    .collect()
+            }),
+            scopes: exec_scopes
+                .data
+                .iter()
+                .map(|scope| {
+                    let mut names: Vec<String> = scope.keys().cloned().collect();
+                    names.sort_unstable();
+                    names
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the snapshot taken by [VirtualMachine::debug_state] as a JSON object.
+    pub fn debug_state_json(&self, exec_scopes: &ExecutionScopes, last_writes: usize) -> String {
+        serde_json::to_string(&self.debug_state(exec_scopes, last_writes)).unwrap_or_default()
+    }
+
+    fn debug_instruction(&self) -> Option<DebugInstruction> {
+        let encoding = self
+            .segments
+            .memory
+            .get_integer(self.get_pc())
+            .ok()?
+            .to_u64()?;
+        let instruction = decode_instruction(encoding).ok()?;
+        Some(DebugInstruction {
+            encoding: format!("{encoding:#x}"),
+            decoded: format!("{instruction:?}"),
+        })
+    }
+
+    fn debug_frames(&self) -> Vec<DebugFrame> {
+        let mut frames = Vec::new();
+        let mut fp = Some(self.get_fp());
+        while let Some(frame_fp) = fp {
+            if frames.len() == MAX_DEBUG_FRAMES {
+                break;
+            }
+            let return_cell = |offset: usize| {
+                (frame_fp - offset)
+                    .ok()
+                    .and_then(|address| self.segments.memory.get_relocatable(address).ok())
+            };
+            let return_fp = return_cell(2);
+            frames.push(DebugFrame {
+                fp: frame_fp,
+                return_fp,
+                return_pc: return_cell(1),
+            });
+            // Stops at a frame returning to itself instead of looping on it
+            fp = return_fp.filter(|return_fp| *return_fp != frame_fp);
+        }
+        frames
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        any_box, relocatable, stdlib::collections::HashMap, types::relocatable::MaybeRelocatable,
+        utils::test_utils::*, vm::memory_journal::MemoryJournal,
+    };
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn debug_state_of_nested_call() {
+        let mut vm = vm!();
+        // [ap] = 5, ap++
+        vm.segments = segments![
+            ((0, 0), 0x480680017fff8000_i64),
+            ((0, 1), 5),
+            // main's frame, returning to the end of the program
+            ((1, 0), (1, 0)),
+            ((1, 1), (0, 20)),
+            // the frame of a function called by main
+            ((1, 3), (1, 2)),
+            ((1, 4), (0, 9)),
+            ((1, 5), 7)
+        ];
+        vm.set_pc(relocatable!(0, 0));
+        vm.set_ap(6);
+        vm.set_fp(5);
+        vm.current_step = 4;
+        let mut journal = MemoryJournal::new(3);
+        journal.record(
+            relocatable!(0, 4),
+            [relocatable!(1, 5), relocatable!(1, 4), relocatable!(0, 5)],
+            [true, false, false],
+        );
+        vm.memory_journal = Some(journal);
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.enter_scope(HashMap::from([
+            (String::from("n"), any_box!(1)),
+            (String::from("dict_manager"), any_box!(2)),
+        ]));
+
+        let state = vm.debug_state(&exec_scopes, 10);
+        assert_eq!(state.step, 4);
+        assert_eq!(state.ap, relocatable!(1, 6));
+        assert_eq!(
+            state.instruction.unwrap().encoding,
+            String::from("0x480680017fff8000")
+        );
+        assert_eq!(
+            state.frames,
+            [
+                DebugFrame {
+                    fp: relocatable!(1, 5),
+                    return_fp: Some(relocatable!(1, 2)),
+                    return_pc: Some(relocatable!(0, 9)),
+                },
+                DebugFrame {
+                    fp: relocatable!(1, 2),
+                    return_fp: Some(relocatable!(1, 0)),
+                    return_pc: Some(relocatable!(0, 20)),
+                },
+                DebugFrame {
+                    fp: relocatable!(1, 0),
+                    return_fp: None,
+                    return_pc: None,
+                },
+            ]
+        );
+        assert_eq!(
+            state.last_writes,
+            Some(vec![DebugWrite {
+                step: 3,
+                address: relocatable!(1, 5),
+                value: Some(MaybeRelocatable::from(7).to_string()),
+            }])
+        );
+        assert_eq!(
+            state.scopes,
+            [
+                vec![],
+                vec![String::from("dict_manager"), String::from("n")]
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn debug_state_json_without_journal() {
+        let mut vm = vm!();
+        vm.segments = segments![((1, 0), 3)];
+        let json: serde_json::Value =
+            serde_json::from_str(&vm.debug_state_json(&ExecutionScopes::new(), 10)).unwrap();
+        assert_eq!(json["step"], 0);
+        assert_eq!(json["pc"]["segment_index"], 0);
+        assert!(json["instruction"].is_null());
+        assert!(json["last_writes"].is_null());
+        assert_eq!(json["scopes"], serde_json::json!([[]]));
+    }
+}
@@ -1,4 +1,5 @@
 use crate::{
+    stdlib::prelude::*,
     types::instruction::{
         ApUpdate, FpUpdate, Instruction, Op1Addr, Opcode, PcUpdate, Register, Res,
     },
@@ -127,6 +128,61 @@ pub fn decode_instruction(encoded_instr: u64) -> Result<Instruction, VirtualMach
     })
 }
 
+/// Decodes an instruction, rejecting the ones [decode_instruction] accepts but which don't follow
+/// the encoding of the Cairo whitepaper, and so can't be emitted by the compiler nor proven:
+/// - jnz leaves res unconstrained, so its res_logic flags must be unset, and it can't be an assert_eq
+/// - an immediate is the cell following its instruction, so off2 must be 1
+/// - call writes fp to [ap] and the return pc to [ap + 1], and its ap_update flags must be unset
+/// - ret restores fp from [fp - 2] and jumps to [fp - 1], and doesn't update ap
+pub fn decode_strict(encoded_instr: u64) -> Result<Instruction, VirtualMachineError> {
+    let instruction = decode_instruction(encoded_instr)?;
+    if instruction.op1_addr == Op1Addr::Imm && instruction.off2 != 1 {
+        return Err(VirtualMachineError::ImmShouldBe1);
+    }
+    match non_canonical_reason(&instruction) {
+        None => Ok(instruction),
+        Some(reason) => Err(VirtualMachineError::NonCanonicalInstruction(Box::new((
+            encoded_instr,
+            reason,
+        )))),
+    }
+}
+
+// Returns which rule of the whitepaper encoding the instruction breaks, if any
+fn non_canonical_reason(instruction: &Instruction) -> Option<&'static str> {
+    if instruction.pc_update == PcUpdate::Jnz {
+        if instruction.res != Res::Unconstrained {
+            return Some("jnz must leave res unconstrained");
+        }
+        if instruction.opcode == Opcode::AssertEq {
+            return Some("assert_eq needs the res jnz leaves unconstrained");
+        }
+    }
+    match instruction.opcode {
+        Opcode::Call if instruction.ap_update != ApUpdate::Add2 => {
+            Some("call must leave the ap_update flags unset")
+        }
+        Opcode::Call if instruction.dst_register != Register::AP || instruction.off0 != 0 => {
+            Some("call must write fp to [ap]")
+        }
+        Opcode::Call if instruction.op0_register != Register::AP || instruction.off1 != 1 => {
+            Some("call must write the return pc to [ap + 1]")
+        }
+        Opcode::Ret
+            if instruction.dst_register != Register::FP
+                || instruction.off0 != -2
+                || instruction.op1_addr != Op1Addr::FP
+                || instruction.off2 != -1
+                || instruction.res != Res::Op1
+                || instruction.pc_update != PcUpdate::Jump
+                || instruction.ap_update != ApUpdate::Regular =>
+        {
+            Some("ret must restore fp from [fp - 2] and jump to [fp - 1] without updating ap")
+        }
+        _ => None,
+    }
+}
+
 fn decode_offset(offset: u64) -> isize {
     let vectorized_offset: [u8; 8] = offset.to_le_bytes();
     let offset_16b_encoded = u16::from_le_bytes([vectorized_offset[0], vectorized_offset[1]]);
@@ -292,6 +348,88 @@ mod decoder_test {
         assert_matches!(inst.fp_update, FpUpdate::Regular);
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn decode_strict_compiled_instructions() {
+        for encoded_instr in [
+            // call rel 1
+            0x1104800180018000,
+            // ret
+            0x208B7FFF7FFF7FFE,
+            // jmp rel 1 if [ap - 1] != 0
+            0x020680017FFF7FFF,
+            // [ap] = [ap - 1] + [ap - 2]; ap++
+            0x48307FFE7FFF8000,
+            // ap += 1
+            0x040780017FFF7FFF,
+        ] {
+            assert_eq!(
+                decode_strict(encoded_instr).unwrap(),
+                decode_instruction(encoded_instr).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn decode_strict_non_canonical_instructions() {
+        // Accepted by decode_instruction, see the decode_flags tests
+        assert_matches!(
+            decode_strict(0x14A7800080008000),
+            Err(VirtualMachineError::ImmShouldBe1)
+        );
+        assert_matches!(
+            decode_strict(0x2948800080008000),
+            Err(VirtualMachineError::NonCanonicalInstruction(bx))
+                if *bx == (0x2948800080008000, "ret must restore fp from [fp - 2] and jump to [fp - 1] without updating ap")
+        );
+        assert_matches!(
+            decode_strict(0x4A50800080008000),
+            Err(VirtualMachineError::NonCanonicalInstruction(bx))
+                if bx.1 == "jnz must leave res unconstrained"
+        );
+        assert_matches!(
+            decode_strict(0x4200800080008000),
+            Err(VirtualMachineError::NonCanonicalInstruction(bx))
+                if bx.1 == "assert_eq needs the res jnz leaves unconstrained"
+        );
+        // call rel 1, writing fp to [ap + 1]
+        assert_matches!(
+            decode_strict(0x1104800180018001),
+            Err(VirtualMachineError::NonCanonicalInstruction(bx))
+                if bx.1 == "call must write fp to [ap]"
+        );
+        // call rel 1; ap++
+        let error = decode_strict(0x1904800180018000).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Instruction 0x1904800180018000 doesn't follow the Cairo encoding: call must leave the ap_update flags unset"
+        );
+    }
+
+    // Decodes every combination of flags, with the offsets of a call, a ret and an immediate
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn decode_strict_all_flags() {
+        for flags in 0..1_u64 << 15 {
+            for offsets in [0x800180018000, 0x7FFF7FFF7FFE] {
+                let encoded_instr = (flags << 48) | offsets;
+                let Ok(instruction) = decode_strict(encoded_instr) else {
+                    continue;
+                };
+                assert_eq!(instruction, decode_instruction(encoded_instr).unwrap());
+                assert!(
+                    instruction.pc_update != PcUpdate::Jnz || instruction.res == Res::Unconstrained
+                );
+                assert!(instruction.op1_addr != Op1Addr::Imm || instruction.off2 == 1);
+                assert!(
+                    instruction.opcode != Opcode::Call || instruction.ap_update == ApUpdate::Add2
+                );
+                assert!(instruction.opcode != Opcode::Ret || instruction.off0 == -2);
+            }
+        }
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn decode_offset_negative() {
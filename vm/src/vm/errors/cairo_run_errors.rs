@@ -1,10 +1,13 @@
+use crate::stdlib::{collections::BTreeMap, prelude::*};
 use thiserror_no_std::Error;
 
 use super::memory_errors::MemoryError;
 use super::vm_exception::VmException;
 use crate::types::errors::program_errors::ProgramError;
+use crate::types::relocatable::{display_with_segment_names, SegmentNamesDisplay};
 use crate::vm::errors::{
-    runner_errors::RunnerError, trace_errors::TraceError, vm_errors::VirtualMachineError,
+    checkpoint_errors::CheckpointError, runner_errors::RunnerError, trace_errors::TraceError,
+    vm_errors::VirtualMachineError,
 };
 
 #[derive(Debug, Error)]
@@ -21,4 +24,17 @@ pub enum CairoRunError {
     MemoryError(#[from] MemoryError),
     #[error(transparent)]
     VmException(#[from] VmException),
+    #[error(transparent)]
+    Checkpoint(#[from] CheckpointError),
+}
+
+impl CairoRunError {
+    /// Returns a value displaying the error with the relocatable values of the segments named in
+    /// `names` written as `name:offset`, see [display_with_segment_names].
+    pub fn display_with<'a>(
+        &'a self,
+        names: &'a BTreeMap<isize, String>,
+    ) -> SegmentNamesDisplay<'a, Self> {
+        display_with_segment_names(self, names)
+    }
 }
@@ -0,0 +1,37 @@
+use crate::stdlib::prelude::*;
+
+use thiserror_no_std::Error;
+
+use crate::vm::errors::{
+    exec_scope_errors::ExecScopeError, memory_errors::MemoryError, runner_errors::RunnerError,
+};
+
+#[derive(Debug, PartialEq, Error)]
+pub enum CheckpointError {
+    #[error("Can't checkpoint a run that has ended")]
+    RunEnded,
+    #[error("The checkpoint wasn't taken from a run of this program")]
+    ProgramMismatch,
+    #[error("The checkpoint wasn't taken from a run initialized by CairoRunner::initialize")]
+    InitializationMismatch,
+    #[error(transparent)]
+    ExecScope(#[from] ExecScopeError),
+    #[error(transparent)]
+    Runner(#[from] RunnerError),
+    #[error(transparent)]
+    Memory(#[from] MemoryError),
+    #[error("Failed to (de)serialize the checkpoint: {0}")]
+    Serde(Box<str>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Test to catch possible enum size regressions
+    fn test_checkpoint_error_size() {
+        let size = crate::stdlib::mem::size_of::<CheckpointError>();
+        assert!(size <= 40, "{size}")
+    }
+}
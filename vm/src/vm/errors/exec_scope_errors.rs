@@ -1,3 +1,8 @@
+// The `(*.0).0` syntax of thiserror falsely triggers this clippy warning
+#![allow(clippy::explicit_auto_deref)]
+
+use crate::stdlib::prelude::*;
+
 use thiserror_no_std::Error;
 
 #[derive(Eq, Hash, PartialEq, Debug, Error)]
@@ -6,4 +11,10 @@ pub enum ExecScopeError {
     ExitMainScopeError,
     #[error("Every enter_scope() requires a corresponding exit_scope().")]
     NoScopeError,
+    #[error("Scope variable {0} can't be serialized, its type isn't registered")]
+    UnregisteredScopeType(Box<str>),
+    #[error("Scope variable {} has the type tag {}, which isn't registered", (*.0).0, (*.0).1)]
+    UnknownScopeTypeTag(Box<(String, String)>),
+    #[error("Failed to (de)serialize scope variable {}: {}", (*.0).0, (*.0).1)]
+    ScopeVariableSerde(Box<(String, String)>),
 }
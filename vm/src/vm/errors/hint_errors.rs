@@ -58,6 +58,8 @@ pub enum HintError {
     ScopeError,
     #[error("Variable {0} not present in current execution scope")]
     VariableNotInScopeError(Box<str>),
+    #[error("Scope variable {} was declared as {}, but used as {}", (*.0).0, (*.0).1, (*.0).2)]
+    ScopeVariableTypeMismatch(Box<(String, String, String)>),
     #[error("DictManagerError: Tried to create tracker for a dictionary on segment: {0} when there is already a tracker for a dictionary on this segment")]
     CantCreateDictionaryOnTakenSegment(isize),
     #[error("Dict Error: No dict tracker found for segment {0}")]
@@ -168,6 +170,16 @@ pub enum HintError {
     NonLeFelt252(Box<(Felt252, Felt252)>),
     #[error("Unknown Hint: {0}")]
     UnknownHint(Box<str>),
+    #[error("No host function registered as {0}")]
+    UnknownHostFunction(Box<str>),
+    #[error("Expected a host value of {} felts, got {}", (*.0).0, (*.0).1)]
+    WrongHostValueLength(Box<(usize, usize)>),
+    #[error("No oracle set for the run, needed to answer {0}")]
+    MissingOracle(Box<str>),
+    #[error("The oracle has no answer for {0}")]
+    OracleNoAnswer(Box<str>),
+    #[error("Invalid answer of the oracle for {}: {}", (*.0).0, (*.0).1)]
+    InvalidOracleAnswer(Box<(String, String)>),
     #[error("Signature hint must point to the signature builtin segment, not {0}.")]
     AddSignatureWrongEcdsaPtr(Box<Relocatable>),
     #[error("Signature hint must point to the public key cell, not {0}.")]
@@ -176,12 +188,16 @@ pub enum HintError {
     RandomEcPointNotOnCurve,
     #[error("Invalid value for len. Got: {0}.")]
     InvalidLenValue(Box<Felt252>),
+    #[error("chained_ec_op() can only be used with len<={}. Got: n_elms={}.", (*.0).0, (*.0).1)]
+    ChainedEcOpMaxLenExceeded(Box<(Felt252, Felt252)>),
     #[error("recover_y: {0} does not represent the x coordinate of a point on the curve.")]
     RecoverYPointNotOnCurve(Box<Felt252>),
     #[error("Invalid value for {}. Got: {}. Expected: {}", (*.0).0, (*.0).1, (*.0).2)]
     InvalidValue(Box<(&'static str, Felt252, Felt252)>),
     #[error("Attempt to subtract with overflow: ids.m - 1")]
     NPairBitsTooLowM,
+    #[error("Hint wrote to {}, outside of the segments it may write to. Hint code:\n{}", (*.0).1, (*.0).0)]
+    WriteOutsideSandbox(Box<(String, Relocatable)>),
 }
 
 #[cfg(test)]
@@ -42,6 +42,10 @@ pub enum MemoryError {
     NonZeroOffset(usize),
     #[error("Attempt to overwrite a relocation rule, segment: {0}")]
     DuplicatedRelocation(isize),
+    #[error(
+        "The relocation rules of the temporary segment {0} loop back to a segment they relocate"
+    )]
+    RelocationCycle(isize),
     #[error("Segment effective sizes haven't been calculated.")]
     MissingSegmentUsedSizes,
     #[error("Found a memory gap when calling get_continuous_range with base:{} and size: {}", (*.0).0, (*.0).1)]
@@ -96,6 +100,12 @@ pub enum MemoryError {
     InvalidUsedSizeSegmentArena,
     #[error("Vector capacity exceeded")]
     VecCapacityExceeded,
+    #[error("Segment {0} was already added, it can't be reserved")]
+    SegmentAlreadyAdded(usize),
+    #[error("Segment {0} doesn't exist")]
+    UnknownSegment(usize),
+    #[error("Can't write to {}, beyond the maximum segment offset {}", (*.0).0, (*.0).1)]
+    SegmentOffsetLimitExceeded(Box<(Relocatable, usize)>),
 }
 
 #[derive(Debug, PartialEq, Eq, Error)]
@@ -1,7 +1,9 @@
 pub mod cairo_run_errors;
+pub mod checkpoint_errors;
 pub mod exec_scope_errors;
 pub mod hint_errors;
 pub mod memory_errors;
+pub mod prover_input_errors;
 pub mod runner_errors;
 pub mod trace_errors;
 pub mod vm_errors;
@@ -0,0 +1,61 @@
+// The `(*.0).0` syntax of thiserror falsely triggers this clippy warning
+#![allow(clippy::explicit_auto_deref)]
+
+use crate::stdlib::prelude::*;
+
+use thiserror_no_std::Error;
+
+use crate::types::relocatable::Relocatable;
+use crate::vm::errors::{
+    memory_errors::MemoryError, runner_errors::RunnerError, trace_errors::TraceError,
+};
+
+#[derive(Debug, PartialEq, Error)]
+pub enum ProverInputError {
+    #[error("The run must be in proof mode to be proven")]
+    NotProofMode,
+    #[error("The segments must be finalized before packaging the run for a prover")]
+    SegmentsNotFinalized,
+    #[error("The memory must be relocated before packaging the run for a prover")]
+    MemoryNotRelocated,
+    #[error("The run doesn't use any range check unit, so it has no range check limits")]
+    NoRangeCheckLimits,
+    #[error("The public memory cell {0} has no value")]
+    MissingPublicMemoryValue(Box<Relocatable>),
+    #[error("The {} segment stops at {}, before it begins at {}", (*.0).0, (*.0).1, (*.0).2)]
+    SegmentStopsBeforeBegin(Box<(&'static str, usize, usize)>),
+    #[error("The {} and {} segments overlap", (*.0).0, (*.0).1)]
+    OverlappingSegments(Box<(&'static str, &'static str)>),
+    #[error("The {0} builtin isn't part of the layout")]
+    BuiltinNotInLayout(&'static str),
+    #[error("The {} segment has {} cells, which isn't a multiple of the {} cells of an instance", (*.0).0, (*.0).1, (*.0).2)]
+    MisalignedBuiltinSegment(Box<(&'static str, usize, u32)>),
+    #[error("The {} segment uses {} cells, more than the {} allocated to it", (*.0).0, (*.0).1, (*.0).2)]
+    BuiltinCellsNotAllocated(Box<(&'static str, usize, usize)>),
+    #[error("The range check limits {0} and {1} aren't ordered 16 bit values")]
+    InvalidRangeCheckLimits(isize, isize),
+    #[error("The number of steps {0} isn't a power of two")]
+    StepsNotPowerOfTwo(usize),
+    #[error("The public memory has the address {0} twice")]
+    DuplicatedPublicMemoryAddress(usize),
+    #[error(transparent)]
+    Trace(#[from] TraceError),
+    #[error(transparent)]
+    Runner(#[from] RunnerError),
+    #[error(transparent)]
+    Memory(#[from] MemoryError),
+    #[error("Failed to write the prover input: {0}")]
+    Write(Box<str>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    // Test to catch possible enum size regressions
+    fn test_prover_input_error_size() {
+        let size = crate::stdlib::mem::size_of::<ProverInputError>();
+        assert!(size <= 40, "{size}")
+    }
+}
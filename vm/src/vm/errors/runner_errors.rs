@@ -1,12 +1,13 @@
 // The `(*.0).0` syntax of thiserror falsely triggers this clippy warning
 #![allow(clippy::explicit_auto_deref)]
 
-use crate::stdlib::{collections::HashSet, prelude::*};
+use crate::stdlib::{collections::BTreeSet, prelude::*};
 
 use thiserror_no_std::Error;
 
 use super::memory_errors::MemoryError;
 use crate::types::{errors::math_errors::MathError, relocatable::Relocatable};
+use crate::vm::runners::{layout_diagnostic::LayoutDiagnostic, syscall_segment::SyscallViolation};
 use felt::Felt252;
 
 #[derive(Debug, PartialEq, Error)]
@@ -40,7 +41,7 @@ pub enum RunnerError {
     #[error("EcOpBuiltin: point {0:?} is not on the curve")]
     PointNotOnCurve(Box<(Felt252, Felt252)>),
     #[error("Builtin(s) {:?} not present in layout {}", (*.0).0, (*.0).1)]
-    NoBuiltinForInstance(Box<(HashSet<&'static str>, String)>),
+    NoBuiltinForInstance(Box<(BTreeSet<String>, String)>),
     #[error("Invalid layout {0}")]
     InvalidLayoutName(Box<str>),
     #[error("end_run called twice.")]
@@ -51,6 +52,8 @@ pub enum RunnerError {
     ReadReturnValuesNoEndRun,
     #[error("Error while finalizing segments: {0}")]
     FinalizeSegements(MemoryError),
+    #[error("Insufficient allocated cells: {0}")]
+    InsufficientLayout(Box<LayoutDiagnostic>),
     #[error("finalize_segments called but proof_mode is not enabled")]
     FinalizeSegmentsNoProofMode,
     #[error("Invalid stop pointer for {}: Stop pointer has value {} but builtin segment is {}", (*.0).0, (*.0).1, (*.0).2)]
@@ -79,12 +82,32 @@ pub enum RunnerError {
     Memory(#[from] MemoryError),
     #[error(transparent)]
     Math(#[from] MathError),
+    #[error("Expected the return values to end with a PanicResult<Array<felt252>>")]
+    InvalidPanicResult,
+    #[error("Expected the return values to end with the start and end of an array")]
+    InvalidRetdataSpan,
+    #[error("Invalid syscall segment: {0}")]
+    InvalidSyscallSegment(Box<SyscallViolation>),
+    #[error("The output page starting at {} is not in the output segment {}", (*.0).0, (*.0).1)]
+    OutputPageNotInSegment(Box<(Relocatable, usize)>),
+    #[error("The output page {0} was already assigned")]
+    OutputPageAlreadyAssigned(Box<usize>),
+    #[error("The output page {} ends at offset {}, after the {} cells of the output", (*.0).0, (*.0).1, (*.0).2)]
+    OutputPageOutOfBounds(Box<(usize, usize, usize)>),
     #[error("keccak_builtin: Failed to get first input address")]
     KeccakNoFirstInput,
     #[error("{}: Expected integer at address {}", (*.0).0, (*.0).1)]
     BuiltinExpectedInteger(Box<(&'static str, Relocatable)>),
     #[error("keccak_builtin: Failed to convert input cells to u64 values")]
     KeccakInputCellsNotU64,
+    #[error("The builtin {0} is not whitelisted")]
+    BuiltinNotWhitelisted(Box<str>),
+    #[error("The entrypoint at pc {0} is not whitelisted")]
+    EntrypointNotWhitelisted(usize),
+    #[error("fill_builtin_memory_holes called but proof_mode is not enabled")]
+    FillHolesNoProofMode,
+    #[error("end_run must be called before fill_builtin_memory_holes.")]
+    FillHolesNoEndRun,
 }
 
 #[cfg(test)]
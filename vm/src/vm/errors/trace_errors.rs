@@ -14,6 +14,8 @@ pub enum TraceError {
     MemoryError(#[from] MemoryError),
     #[error("Trace not relocated")]
     TraceNotRelocated,
+    #[error("Invalid compressed trace, entry {0} is malformed")]
+    InvalidCompressedTrace(usize),
 }
 
 #[cfg(test)]
@@ -1,18 +1,25 @@
 // The `(*.0).0` syntax of thiserror falsely triggers this clippy warning
 #![allow(clippy::explicit_auto_deref)]
 
-use crate::stdlib::prelude::*;
+use crate::stdlib::{collections::BTreeMap, prelude::*};
 
 use thiserror_no_std::Error;
 
 use crate::{
     types::{
         errors::math_errors::MathError,
-        relocatable::{MaybeRelocatable, Relocatable},
+        instruction::Instruction,
+        relocatable::{
+            display_with_segment_names, MaybeRelocatable, Relocatable, SegmentNamesDisplay,
+        },
     },
-    vm::errors::{
-        exec_scope_errors::ExecScopeError, hint_errors::HintError, memory_errors::MemoryError,
-        runner_errors::RunnerError, trace_errors::TraceError,
+    vm::{
+        ap_tracking_checker::ApTrackingMismatch,
+        errors::{
+            exec_scope_errors::ExecScopeError, hint_errors::HintError, memory_errors::MemoryError,
+            runner_errors::RunnerError, trace_errors::TraceError,
+        },
+        operands_audit::OperandsAudit,
     },
 };
 use felt::Felt252;
@@ -25,6 +32,10 @@ pub enum VirtualMachineError {
     Memory(#[from] MemoryError),
     #[error(transparent)]
     Math(#[from] MathError),
+    #[error("Failed to run the instruction at pc {}: {}. Instruction: {:?}", (*.0).0, (*.0).2, (*.0).1)]
+    InstructionMath(Box<(Relocatable, Instruction, MathError)>),
+    #[error("The instruction at pc {} reads the uninitialized memory cell {} as {}, which no builtin deduces. Instruction: {:?}", (*.0).0, (*.0).2, (*.0).1, (*.0).3)]
+    UninitializedMemoryRead(Box<(Relocatable, &'static str, Relocatable, Instruction)>),
     #[error(transparent)]
     TracerError(#[from] TraceError),
     #[error(transparent)]
@@ -39,6 +50,8 @@ pub enum VirtualMachineError {
     InvalidOp1Reg(u64),
     #[error("In immediate mode, off2 should be 1")]
     ImmShouldBe1,
+    #[error("Instruction {:#x} doesn't follow the Cairo encoding: {}", (*.0).0, (*.0).1)]
+    NonCanonicalInstruction(Box<(u64, &'static str)>),
     #[error("op0 must be known in double dereference")]
     UnknownOp0,
     #[error("Invalid ap_update value: {0}")]
@@ -61,8 +74,8 @@ pub enum VirtualMachineError {
     ComputeResRelocatableMul(Box<(MaybeRelocatable, MaybeRelocatable)>),
     #[error("Couldn't compute operand {}. Unknown value for memory cell {}", (*.0).0, (*.0).1)]
     FailedToComputeOperands(Box<(String, Relocatable)>),
-    #[error("An ASSERT_EQ instruction failed: {} != {}.", (*.0).0, (*.0).1)]
-    DiffAssertValues(Box<(MaybeRelocatable, MaybeRelocatable)>),
+    #[error("An ASSERT_EQ instruction failed: {} != {}.", (*.0).dst.value, (*.0).res)]
+    DiffAssertValues(Box<OperandsAudit>),
     #[error("Call failed to write return-pc (inconsistent op0): {} != {}. Did you forget to increment ap?", (*.0).0, (*.0).1)]
     CantWriteReturnPc(Box<(MaybeRelocatable, MaybeRelocatable)>),
     #[error("Call failed to write return-fp (inconsistent dst): {} != {}. Did you forget to increment ap?", (*.0).0, (*.0).1)]
@@ -83,6 +96,8 @@ pub enum VirtualMachineError {
     NoRangeCheckBuiltin,
     #[error("Expected ecdsa builtin to be present")]
     NoSignatureBuiltin,
+    #[error("Expected output builtin to be present")]
+    NoOutputBuiltin,
     #[error("Div out of range: 0 < {} <= {}", (*.0).0, (*.0).1)]
     OutOfValidRange(Box<(Felt252, Felt252)>),
     #[error("Failed to compare {} and {}, cant compare a relocatable to an integer value", (*.0).0, (*.0).1)]
@@ -103,6 +118,8 @@ pub enum VirtualMachineError {
     EndOfProgram(usize),
     #[error("Could not reach the end of the program. Executed steps: {0}.")]
     StepsLimit(u64),
+    #[error("The instruction at pc {} exceeded its limit of {} executions", (*.0).0, (*.0).1)]
+    PcExecutionLimit(Box<(Relocatable, usize)>),
     #[error("Could not reach the end of the program. RunResources has no remaining steps.")]
     UnfinishedExecution,
     #[error("Current run is not finished")]
@@ -119,12 +136,31 @@ pub enum VirtualMachineError {
     OutOfBoundsBuiltinSegmentAccess,
     #[error("Out of bounds access to program segment")]
     OutOfBoundsProgramSegmentAccess,
+    #[error("Out of bounds access to constant segment {0}")]
+    OutOfBoundsConstantSegmentAccess(usize),
     #[error("Security Error: Invalid Memory Value: temporary address not relocated: {0}")]
     InvalidMemoryValueTemporaryAddress(Box<Relocatable>),
     #[error("accessed_addresses is None.")]
     MissingAccessedAddresses,
     #[error("Failed to write the output builtin content")]
     FailedToWriteOutput,
+    #[error("{0}")]
+    ApTrackingMismatch(Box<ApTrackingMismatch>),
+    #[error("The instruction at {0} is not a conditional jump")]
+    NotAConditionalJump(Box<Relocatable>),
+    #[error("The condition of the nondeterministic jump at {} is already set to {}, which doesn't take the chosen branch", (*.0).0, (*.0).1)]
+    NondetJumpConflict(Box<(Relocatable, MaybeRelocatable)>),
+}
+
+impl VirtualMachineError {
+    /// Returns a value displaying the error with the relocatable values of the segments named in
+    /// `names` written as `name:offset`, see [display_with_segment_names].
+    pub fn display_with<'a>(
+        &'a self,
+        names: &'a BTreeMap<isize, String>,
+    ) -> SegmentNamesDisplay<'a, Self> {
+        display_with_segment_names(self, names)
+    }
 }
 
 #[cfg(test)]
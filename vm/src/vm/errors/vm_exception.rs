@@ -1,4 +1,5 @@
 use crate::stdlib::{
+    collections::{BTreeMap, BTreeSet},
     fmt::{self, Display},
     prelude::*,
     str,
@@ -7,9 +8,14 @@ use crate::stdlib::{
 use thiserror_no_std::Error;
 
 use crate::{
-    hint_processor::hint_processor_utils::get_maybe_relocatable_from_reference,
+    hint_processor::hint_processor_utils::{
+        compute_addr_from_reference, get_maybe_relocatable_from_reference,
+    },
     serde::deserialize_program::{ApTracking, Attribute, Location, OffsetValue},
-    types::{instruction::Register, relocatable::MaybeRelocatable},
+    types::{
+        instruction::Register,
+        relocatable::{display_with_segment_names, MaybeRelocatable},
+    },
     vm::{runners::cairo_runner::CairoRunner, vm_core::VirtualMachine},
 };
 
@@ -21,6 +27,43 @@ pub struct VmException {
     pub inner_exc: VirtualMachineError,
     pub error_attr_value: Option<String>,
     pub traceback: Option<String>,
+    pub traceback_frames: Vec<TracebackFrame>,
+    /// The `ids` variables referenced by the code of the hint that failed, sorted by name. Empty
+    /// if the error didn't come from a hint.
+    pub hint_ids: Vec<HintIdValue>,
+    /// The names of the segments of the run, which the relocatable values of the exception are
+    /// displayed with, e.g. `pedersen:12`, see [display_with_segment_names]
+    pub segment_names: BTreeMap<isize, String>,
+}
+
+/// An entry of the cairo traceback, ordered from the outermost call to the innermost one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TracebackFrame {
+    pub pc: usize,
+    pub location: Option<Location>,
+    pub error_attr_value: Option<String>,
+}
+
+/// An `ids` variable of a failed hint, resolved from memory when the hint failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HintIdValue {
+    pub name: String,
+    pub cairo_type: Option<String>,
+    /// The value of the variable for felts and pointers, its address for other types, or None if
+    /// it couldn't be resolved
+    pub value: Option<MaybeRelocatable>,
+}
+
+impl Display for HintIdValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (&self.value, &self.cairo_type) {
+            (None, _) => write!(f, "ids.{} = <unknown>", self.name),
+            (Some(value), Some(cairo_type)) if !is_value_type(cairo_type) => {
+                write!(f, "ids.{}: {cairo_type} at {value}", self.name)
+            }
+            (Some(value), _) => write!(f, "ids.{} = {value}", self.name),
+        }
+    }
 }
 
 impl VmException {
@@ -30,19 +73,79 @@ impl VmException {
         error: VirtualMachineError,
     ) -> Self {
         let pc = vm.run_context.pc.offset;
-        let error_attr_value = get_error_attr_value(pc, runner, vm);
+        // ap-based references can only be evaluated at the pc where the error fired, as the
+        // value of ap is unknown for the rest of the traceback
+        let error_attr_value =
+            error_attr_value_with_ap_tracking(pc, runner, vm, get_ap_tracking(pc, runner));
         let hint_index = if let VirtualMachineError::Hint(ref bx) = error {
             Some(bx.0)
         } else {
             None
         };
+        let traceback_frames = get_traceback_frames(vm, runner);
         VmException {
             pc,
             inst_location: get_location(pc, runner, hint_index),
             inner_exc: error,
             error_attr_value,
-            traceback: get_traceback(vm, runner),
+            traceback: traceback_from_frames(&traceback_frames),
+            traceback_frames,
+            hint_ids: hint_index
+                .map(|index| get_hint_ids(pc, index, runner, vm))
+                .unwrap_or_default(),
+            segment_names: vm.segments.segment_names().clone(),
+        }
+    }
+
+    /// Renders the exception in the style of rustc diagnostics: each location is followed by the
+    /// source line it points to (when its input file can be read), with carets under the
+    /// offending columns. Error attributes are added as notes of the frame they belong to.
+    pub fn to_rich_string(&self) -> String {
+        let names = &self.segment_names;
+        let mut string = format!("error: {}\n", self.inner_exc.display_with(names));
+        match self.inst_location {
+            Some(ref location) => {
+                string.push_str(&location.to_rich_string(Some(self.pc)));
+                let mut parent_location = &location.parent_location;
+                while let Some((location, message)) = parent_location {
+                    string.push_str(&format!("note: {message}\n"));
+                    string.push_str(&location.to_rich_string(None));
+                    parent_location = &location.parent_location;
+                }
+            }
+            None => string.push_str(&format!(" --> Unknown location (pc=0:{})\n", self.pc)),
+        }
+        push_error_attr_notes(&mut string, self.error_attr_value.as_deref());
+        for hint_id in self.hint_ids.iter() {
+            string.push_str(&format!(
+                "  = {}\n",
+                display_with_segment_names(hint_id, names)
+            ));
+        }
+        if let VirtualMachineError::DiffAssertValues(ref audit) = self.inner_exc {
+            for line in display_with_segment_names(audit, names).to_string().lines() {
+                string.push_str(&format!("  = {line}\n"));
+            }
         }
+        if !self.traceback_frames.is_empty() {
+            string.push_str("Cairo traceback (most recent call last):\n");
+            for frame in self.traceback_frames.iter() {
+                match frame.location {
+                    Some(ref location) => string.push_str(&location.to_rich_string(Some(frame.pc))),
+                    None => {
+                        string.push_str(&format!(" --> Unknown location (pc=0:{})\n", frame.pc))
+                    }
+                }
+                push_error_attr_notes(&mut string, frame.error_attr_value.as_deref());
+            }
+        }
+        string
+    }
+}
+
+fn push_error_attr_notes(string: &mut String, error_attr_value: Option<&str>) {
+    for line in error_attr_value.into_iter().flat_map(str::lines) {
+        string.push_str(&format!("  = {line}\n"));
     }
 }
 
@@ -50,13 +153,38 @@ pub fn get_error_attr_value(
     pc: usize,
     runner: &CairoRunner,
     vm: &VirtualMachine,
+) -> Option<String> {
+    error_attr_value_with_ap_tracking(pc, runner, vm, None)
+}
+
+// Returns the ap tracking data of the instruction at pc, if the program has debug info
+fn get_ap_tracking(pc: usize, runner: &CairoRunner) -> Option<&ApTracking> {
+    let instruction_location = runner
+        .program
+        .shared_program_data
+        .instruction_locations
+        .as_ref()?
+        .get(&pc)?;
+    Some(
+        &instruction_location
+            .flow_tracking_data
+            .as_ref()?
+            .ap_tracking,
+    )
+}
+
+fn error_attr_value_with_ap_tracking(
+    pc: usize,
+    runner: &CairoRunner,
+    vm: &VirtualMachine,
+    ap_tracking: Option<&ApTracking>,
 ) -> Option<String> {
     let mut errors = String::new();
     for attribute in &runner.program.shared_program_data.error_message_attributes {
         if attribute.start_pc <= pc && attribute.end_pc > pc {
             errors.push_str(&format!(
                 "Error message: {}\n",
-                substitute_error_message_references(attribute, runner, vm)
+                substitute_error_message_references(attribute, runner, vm, ap_tracking)
             ));
         }
     }
@@ -88,22 +216,95 @@ pub fn get_location(
     }
 }
 
+/// Resolves the `ids` variables referenced by the code of the hint `hint_index` at `pc`, sorted
+/// by name. Must be called before the vm leaves the hint's pc, as ap-based references are
+/// evaluated with the current value of ap.
+pub fn get_hint_ids(
+    pc: usize,
+    hint_index: usize,
+    runner: &CairoRunner,
+    vm: &VirtualMachine,
+) -> Vec<HintIdValue> {
+    let hint = match runner.program.hints_at(pc).get(hint_index) {
+        Some(hint) => hint,
+        None => return Vec::new(),
+    };
+    let flow_tracking_data = &hint.flow_tracking_data;
+    let references = &runner.program.shared_program_data.reference_manager;
+    referenced_ids(&hint.code)
+        .into_iter()
+        .filter_map(|name| {
+            // Get the reference of the variable from its path ie: __main__.main.x -> x
+            let ref_id = flow_tracking_data
+                .reference_ids
+                .iter()
+                .find(|(path, _)| path.rsplit('.').next() == Some(name))?
+                .1;
+            let reference = references.get(*ref_id)?;
+            let ap_tracking = &flow_tracking_data.ap_tracking;
+            let value = match reference.cairo_type {
+                Some(ref cairo_type) if !is_value_type(cairo_type) => {
+                    compute_addr_from_reference(reference, vm, ap_tracking)
+                        .map(MaybeRelocatable::from)
+                }
+                _ => get_maybe_relocatable_from_reference(vm, reference, ap_tracking),
+            };
+            Some(HintIdValue {
+                name: name.to_string(),
+                cairo_type: reference.cairo_type.clone(),
+                value,
+            })
+        })
+        .collect()
+}
+
+// Returns the names of the variables accessed as `ids.<name>` in the code of a hint
+fn referenced_ids(code: &str) -> BTreeSet<&str> {
+    let is_identifier_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    code.match_indices("ids.")
+        .filter(|(start, _)| !code[..*start].ends_with(is_identifier_char))
+        .filter_map(|(start, _)| {
+            let rest = &code[start + 4..];
+            let name = &rest[..rest.find(|c| !is_identifier_char(c)).unwrap_or(rest.len())];
+            (!name.is_empty()).then_some(name)
+        })
+        .collect()
+}
+
+// Felts and pointers are shown by value, structs by address
+fn is_value_type(cairo_type: &str) -> bool {
+    cairo_type == "felt" || cairo_type.ends_with('*')
+}
+
 // Returns the traceback at the current pc.
 pub fn get_traceback(vm: &VirtualMachine, runner: &CairoRunner) -> Option<String> {
+    traceback_from_frames(&get_traceback_frames(vm, runner))
+}
+
+// Returns the frames of the traceback at the current pc.
+pub fn get_traceback_frames(vm: &VirtualMachine, runner: &CairoRunner) -> Vec<TracebackFrame> {
+    vm.get_traceback_entries()
+        .into_iter()
+        .map(|(_fp, traceback_pc)| TracebackFrame {
+            pc: traceback_pc.offset,
+            location: get_location(traceback_pc.offset, runner, None),
+            error_attr_value: get_error_attr_value(traceback_pc.offset, runner, vm),
+        })
+        .collect()
+}
+
+fn traceback_from_frames(frames: &[TracebackFrame]) -> Option<String> {
     let mut traceback = String::new();
-    for (_fp, traceback_pc) in vm.get_traceback_entries() {
-        if let Some(ref attr) = get_error_attr_value(traceback_pc.offset, runner, vm) {
+    for frame in frames {
+        if let Some(ref attr) = frame.error_attr_value {
             traceback.push_str(attr)
         }
-        match get_location(traceback_pc.offset, runner, None) {
-            Some(location) => traceback.push_str(&format!(
+        match frame.location {
+            Some(ref location) => traceback.push_str(&format!(
                 "{}\n",
-                location.to_string_with_content(&format!("(pc=0:{})", traceback_pc.offset))
-            )),
-            None => traceback.push_str(&format!(
-                "Unknown location (pc=0:{})\n",
-                traceback_pc.offset
+                location.to_string_with_content(&format!("(pc=0:{})", frame.pc))
             )),
+            None => traceback.push_str(&format!("Unknown location (pc=0:{})\n", frame.pc)),
         }
     }
     (!traceback.is_empty())
@@ -111,11 +312,13 @@ pub fn get_traceback(vm: &VirtualMachine, runner: &CairoRunner) -> Option<String
 }
 
 // Substitutes references in the given error_message attribute with their actual value.
-// References are defined with '{}'. E.g., 'x must be positive. Got: {x}'.
+// References are defined with '{}', with an optional 'ids.' prefix. E.g., 'x must be positive. Got: {x}'.
+// ap-based references are only evaluated when the ap tracking data of the current pc is given.
 fn substitute_error_message_references(
     error_message_attr: &Attribute,
     runner: &CairoRunner,
     vm: &VirtualMachine,
+    ap_tracking: Option<&ApTracking>,
 ) -> String {
     let mut error_msg = error_message_attr.value.clone();
     if let Some(tracking_data) = &error_message_attr.flow_tracking_data {
@@ -128,21 +331,30 @@ fn substitute_error_message_references(
                 None => continue,
             };
             // Format the variable name to make it easier to search for and replace in the error message
-            // ie: x -> {x}
-            let formated_variable_name = format!("{{{cairo_variable_name}}}");
-            // Look for the formated name inside the error message
-            if error_msg.contains(&formated_variable_name) {
+            // ie: x -> {x} and {ids.x}
+            let formated_variable_names = [
+                format!("{{{cairo_variable_name}}}"),
+                format!("{{ids.{cairo_variable_name}}}"),
+            ];
+            // Look for the formated names inside the error message
+            if formated_variable_names
+                .iter()
+                .any(|name| error_msg.contains(name))
+            {
                 // Get the value of the cairo variable from its reference id
                 match get_value_from_simple_reference(
                     *ref_id,
-                    &tracking_data.ap_tracking,
+                    ap_tracking.unwrap_or(&tracking_data.ap_tracking),
+                    ap_tracking.is_some(),
                     runner,
                     vm,
                 ) {
                     Some(cairo_variable) => {
                         // Replace the value in the error message
-                        error_msg =
-                            error_msg.replace(&formated_variable_name, &format!("{cairo_variable}"))
+                        for formated_variable_name in formated_variable_names.iter() {
+                            error_msg = error_msg
+                                .replace(formated_variable_name, &format!("{cairo_variable}"))
+                        }
                     }
                     None => {
                         // If the reference is too complex or ap-based it might lead to a wrong value
@@ -165,9 +377,12 @@ fn substitute_error_message_references(
     error_msg
 }
 
+// ap-based references are rejected unless `ap_based` is set, in which case `ap_tracking` must be
+// the ap tracking data of the current pc
 fn get_value_from_simple_reference(
     ref_id: usize,
     ap_tracking: &ApTracking,
+    ap_based: bool,
     runner: &CairoRunner,
     vm: &VirtualMachine,
 ) -> Option<MaybeRelocatable> {
@@ -178,7 +393,7 @@ fn get_value_from_simple_reference(
         .get(ref_id)?;
     // Filter ap-based references
     match reference.offset1 {
-        OffsetValue::Reference(Register::AP, _, _) => None,
+        OffsetValue::Reference(Register::AP, _, _) if !ap_based => None,
         _ => {
             // Filer complex types (only felt/felt pointers)
             match reference.cairo_type {
@@ -193,8 +408,13 @@ fn get_value_from_simple_reference(
 
 impl Display for VmException {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = &self.segment_names;
         // Build initial message
-        let message = format!("Error at pc=0:{}:\n{}", self.pc, self.inner_exc);
+        let message = format!(
+            "Error at pc=0:{}:\n{}",
+            self.pc,
+            self.inner_exc.display_with(names)
+        );
         let mut error_msg = String::new();
         // Add error attribute value
         if let Some(ref string) = self.error_attr_value {
@@ -221,6 +441,23 @@ impl Display for VmException {
         } else {
             error_msg.push_str(&format!("{message}\n"));
         }
+        // Add the operands of the failed assertion
+        if let VirtualMachineError::DiffAssertValues(ref audit) = self.inner_exc {
+            error_msg.push_str("Operands:\n");
+            for line in display_with_segment_names(audit, names).to_string().lines() {
+                error_msg.push_str(&format!("    {line}\n"));
+            }
+        }
+        // Add the values of the ids of the failed hint
+        if !self.hint_ids.is_empty() {
+            error_msg.push_str("Hint ids:\n");
+            for hint_id in self.hint_ids.iter() {
+                error_msg.push_str(&format!(
+                    "    {}\n",
+                    display_with_segment_names(hint_id, names)
+                ));
+            }
+        }
         if let Some(ref string) = self.traceback {
             error_msg.push_str(string);
         }
@@ -247,6 +484,28 @@ impl Location {
     #[cfg(feature = "std")]
     pub fn to_string_with_content(&self, message: &str) -> String {
         let mut string = self.to_string(message);
+        if let Some(file_content) = self.read_input_file() {
+            string.push_str(&format!("\n{}", self.get_location_marks(&file_content)));
+        }
+        string
+    }
+
+    /// Prints the location as a `-->` line, followed by the marked source line when the input
+    /// file can be read.
+    pub fn to_rich_string(&self, pc: Option<usize>) -> String {
+        let margin = " ".repeat(self.start_line.to_string().len());
+        let pc = pc.map(|pc| format!(" (pc=0:{pc})")).unwrap_or_default();
+        let string = format!("{margin}--> {}{pc}\n", self.to_string(""));
+        #[cfg(feature = "std")]
+        let string = match self.read_input_file() {
+            Some(file_content) => string + &self.get_rich_location_marks(&file_content),
+            None => string,
+        };
+        string
+    }
+
+    #[cfg(feature = "std")]
+    fn read_input_file(&self) -> Option<Vec<u8>> {
         let input_file_path = std::path::Path::new(&self.input_file.filename);
         #[cfg(test)]
         let input_file_path = {
@@ -259,10 +518,7 @@ impl Location {
             parent_dir.push(input_file_path);
             parent_dir
         };
-        if let Ok(file_content) = std::fs::read(input_file_path) {
-            string.push_str(&format!("\n{}", self.get_location_marks(&file_content)));
-        }
-        string
+        std::fs::read(input_file_path).ok()
     }
 
     pub fn get_location_marks(&self, file_contents: &[u8]) -> String {
@@ -291,6 +547,32 @@ impl Location {
         }
         result
     }
+
+    /// Returns the source line of the location between line number gutters, with carets under
+    /// its columns, or an empty string if the line isn't in `file_contents`.
+    pub fn get_rich_location_marks(&self, file_contents: &[u8]) -> String {
+        let contents = str::from_utf8(file_contents).unwrap_or_default();
+        let line = match (self.start_line as usize)
+            .checked_sub(1)
+            .and_then(|index| contents.split('\n').nth(index))
+        {
+            Some(line) => line.trim_end_matches('\r'),
+            None => return String::new(),
+        };
+        let start_col = self.start_col as usize;
+        let end_col = if self.start_line == self.end_line {
+            self.end_col as usize
+        } else {
+            line.len() + 1
+        };
+        let margin = " ".repeat(self.start_line.to_string().len());
+        let left_margin = " ".repeat(start_col.saturating_sub(1));
+        let carets = "^".repeat(end_col.saturating_sub(start_col).max(1));
+        format!(
+            "{margin} |\n{} | {line}\n{margin} | {left_margin}{carets}\n",
+            self.start_line
+        )
+    }
 }
 #[cfg(test)]
 mod test {
@@ -300,6 +582,7 @@ mod test {
     use std::path::Path;
 
     use crate::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor;
+    use crate::hint_processor::builtin_hint_processor::hint_code;
     use crate::serde::deserialize_program::{
         Attribute, HintLocation, InputFile, InstructionLocation,
     };
@@ -328,6 +611,7 @@ mod test {
         let instruction_location = InstructionLocation {
             inst: location.clone(),
             hints: vec![],
+            flow_tracking_data: None,
         };
         let program =
             program!(instruction_locations = Some(HashMap::from([(pc, instruction_location)])),);
@@ -340,7 +624,14 @@ mod test {
                 inner_exc: VirtualMachineError::NoImm,
                 error_attr_value: None,
                 traceback: None,
-            } if x == pc && y == location
+                traceback_frames: frames,
+                hint_ids,
+                segment_names,
+            } if hint_ids.is_empty()
+                && x == pc
+                && y == location
+                && frames.is_empty()
+                && segment_names.is_empty()
         )
     }
 
@@ -384,6 +675,31 @@ mod test {
         )
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn vm_exception_display_with_segment_names() {
+        let vm_excep = VmException {
+            pc: 2,
+            inst_location: None,
+            inner_exc: VirtualMachineError::FailedToComputeOperands(Box::new((
+                "op0".to_string(),
+                Relocatable::from((1, 4)),
+            ))),
+            error_attr_value: None,
+            traceback: None,
+            traceback_frames: Vec::new(),
+            hint_ids: Vec::new(),
+            segment_names: BTreeMap::from([
+                (0, String::from("program")),
+                (1, String::from("execution")),
+            ]),
+        };
+        assert_eq!(
+            vm_excep.to_string(),
+            "Error at pc=0:2:\nCouldn't compute operand op0. Unknown value for memory cell execution:4\n"
+        )
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn vm_exception_display_instruction_no_location_no_attributes() {
@@ -396,6 +712,9 @@ mod test {
             ))),
             error_attr_value: None,
             traceback: None,
+            traceback_frames: Vec::new(),
+            hint_ids: Vec::new(),
+            segment_names: BTreeMap::new(),
         };
         assert_eq!(
             vm_excep.to_string(),
@@ -421,6 +740,9 @@ mod test {
             ))),
             error_attr_value: Some(String::from("Error message: Block may fail\n")),
             traceback: None,
+            traceback_frames: Vec::new(),
+            hint_ids: Vec::new(),
+            segment_names: BTreeMap::new(),
         };
         assert_eq!(
             vm_excep.to_string(),
@@ -456,6 +778,9 @@ mod test {
             ))),
             error_attr_value: None,
             traceback: None,
+            traceback_frames: Vec::new(),
+            hint_ids: Vec::new(),
+            segment_names: BTreeMap::new(),
         };
         assert_eq!(
             vm_excep.to_string(),
@@ -503,6 +828,9 @@ mod test {
             ))),
             error_attr_value: None,
             traceback: None,
+            traceback_frames: Vec::new(),
+            hint_ids: Vec::new(),
+            segment_names: BTreeMap::new(),
         };
         assert_eq!(
             vm_excep.to_string(),
@@ -564,6 +892,7 @@ mod test {
         let instruction_location = InstructionLocation {
             inst: location.clone(),
             hints: vec![],
+            flow_tracking_data: None,
         };
         let program =
             program!(instruction_locations = Some(HashMap::from([(2, instruction_location)])),);
@@ -587,6 +916,7 @@ mod test {
         let instruction_location = InstructionLocation {
             inst: location,
             hints: vec![],
+            flow_tracking_data: None,
         };
         let program =
             program!(instruction_locations = Some(HashMap::from([(2, instruction_location)])),);
@@ -624,6 +954,7 @@ mod test {
         let instruction_location = InstructionLocation {
             inst: location_a,
             hints: vec![hint_location],
+            flow_tracking_data: None,
         };
         let program =
             program!(instruction_locations = Some(HashMap::from([(2, instruction_location)])),);
@@ -665,6 +996,56 @@ mod test {
         assert_eq!(get_traceback(&vm, &cairo_runner), Some(expected_traceback));
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn get_hint_ids_bad_dict_update() {
+        let program = Program::from_bytes(
+            include_bytes!("../../../../cairo_programs/bad_programs/bad_dict_update.json"),
+            Some("main"),
+        )
+        .unwrap();
+
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let mut cairo_runner = cairo_runner!(program, "all_cairo", false);
+        let mut vm = vm!();
+
+        let end = cairo_runner.initialize(&mut vm).unwrap();
+        let error = cairo_runner
+            .run_until_pc(end, &mut vm, &mut hint_processor)
+            .unwrap_err();
+        let vm_exception = VmException::from_vm_error(&cairo_runner, &vm, error);
+        // ids.DictAccess is a type, not a variable
+        let names: Vec<&str> = vm_exception
+            .hint_ids
+            .iter()
+            .map(|hint_id| hint_id.name.as_str())
+            .collect();
+        assert_eq!(names, ["dict_ptr", "key", "new_value", "prev_value"]);
+        assert_matches!(
+            vm_exception.hint_ids[0].value,
+            Some(MaybeRelocatable::RelocatableValue(_))
+        );
+        for (hint_id, value) in vm_exception.hint_ids[1..].iter().zip([2, 4, 3]) {
+            assert_eq!(hint_id.value, Some(MaybeRelocatable::from(value)));
+        }
+        let error_string = vm_exception.to_string();
+        assert!(error_string.contains("Hint ids:\n    ids.dict_ptr = "));
+        assert!(error_string
+            .contains("    ids.key = 2\n    ids.new_value = 4\n    ids.prev_value = 3\n"));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn referenced_ids_of_hint_code() {
+        assert_eq!(
+            referenced_ids(hint_code::DICT_UPDATE)
+                .into_iter()
+                .collect::<Vec<_>>(),
+            ["DictAccess", "dict_ptr", "key", "new_value", "prev_value"]
+        );
+        assert!(referenced_ids("my_ids.x = 1\nids = 2").is_empty());
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn get_traceback_bad_usort() {
@@ -752,6 +1133,103 @@ cairo_programs/bad_programs/bad_usort.cairo:64:5: (pc=0:60)
         )
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn location_to_rich_string() {
+        let location = Location {
+            end_line: 5,
+            end_col: 29,
+            input_file: InputFile {
+                filename: String::from("cairo_programs/bad_programs/bad_usort.cairo"),
+            },
+            parent_location: None,
+            start_line: 5,
+            start_col: 6,
+        };
+
+        #[cfg(feature = "std")]
+        let expected_message = " --> cairo_programs/bad_programs/bad_usort.cairo:5:6 (pc=0:75)\n  |\n5 | func usort{range_check_ptr}(input_len: felt, input: felt*) -> (\n  |      ^^^^^^^^^^^^^^^^^^^^^^^\n";
+        #[cfg(not(feature = "std"))]
+        let expected_message = " --> cairo_programs/bad_programs/bad_usort.cairo:5:6 (pc=0:75)\n";
+
+        assert_eq!(location.to_rich_string(Some(75)), expected_message);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn location_get_rich_location_marks() {
+        let location = Location {
+            end_line: 12,
+            end_col: 3,
+            input_file: InputFile {
+                filename: String::from("file.cairo"),
+            },
+            parent_location: None,
+            start_line: 11,
+            start_col: 3,
+        };
+        let mut file_contents = "\n".repeat(10);
+        file_contents.push_str("  assert x = y;\n  return ();\n");
+
+        assert_eq!(
+            location.get_rich_location_marks(file_contents.as_bytes()),
+            "   |\n11 |   assert x = y;\n   |   ^^^^^^^^^^^^^\n"
+        );
+        assert_eq!(location.get_rich_location_marks(b"too short"), "");
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn vm_exception_to_rich_string() {
+        let location = Location {
+            end_line: 2,
+            end_col: 2,
+            input_file: InputFile {
+                filename: String::from("Folder/file.cairo"),
+            },
+            parent_location: Some((
+                Box::new(Location {
+                    end_line: 3,
+                    end_col: 3,
+                    input_file: InputFile {
+                        filename: String::from("Folder/file_b.cairo"),
+                    },
+                    parent_location: None,
+                    start_line: 2,
+                    start_col: 2,
+                }),
+                String::from("While expanding the reference:"),
+            )),
+            start_line: 1,
+            start_col: 1,
+        };
+        let vm_excep = VmException {
+            pc: 2,
+            inst_location: Some(location),
+            inner_exc: VirtualMachineError::NoImm,
+            error_attr_value: Some(String::from("Error message: Block may fail\n")),
+            traceback: None,
+            traceback_frames: vec![TracebackFrame {
+                pc: 7,
+                location: None,
+                error_attr_value: None,
+            }],
+            hint_ids: vec![HintIdValue {
+                name: String::from("x"),
+                cairo_type: Some(String::from("felt")),
+                value: Some(MaybeRelocatable::from(5)),
+            }],
+            segment_names: BTreeMap::new(),
+        };
+        assert_eq!(
+            vm_excep.to_rich_string(),
+            format!(
+                "error: {}\n --> Folder/file.cairo:1:1 (pc=0:2)\nnote: While expanding the reference:\n --> Folder/file_b.cairo:2:2\n  = Error message: Block may fail\n  = ids.x = 5\nCairo traceback (most recent call last):\n --> Unknown location (pc=0:7)\n",
+                VirtualMachineError::NoImm
+            )
+        )
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn location_to_string_with_contents_no_file() {
@@ -821,6 +1299,12 @@ cairo_programs/bad_programs/bad_range_check.cairo:5:9: Error at pc=0:0:
 An ASSERT_EQ instruction failed: 4 != 5.
         [range_check_ptr] = num;
         ^*********************^
+Operands:
+    instruction at pc program:0: Instruction { off0: -3, off1: -4, off2: 0, dst_register: FP, op0_register: FP, op1_addr: Op0, res: Op1, pc_update: Regular, ap_update: Regular, fp_update: Regular, opcode: AssertEq }
+    dst [fp - 3] at execution:26 = 4 (read)
+    op0 [fp - 4] at execution:25 = range_check:0 (read)
+    op1 [op0 + 0] at range_check:0 = 5 (read)
+    res (op1) = 5
 Cairo traceback (most recent call last):
 cairo_programs/bad_programs/bad_range_check.cairo:23:5: (pc=0:29)
     sub_by_1_check_range(6, 7);
@@ -839,6 +1323,12 @@ cairo_programs/bad_programs/bad_range_check.cairo:11:5: (pc=0:6)
         let expected_error_string = r#"Error message: Failed range-check
 cairo_programs/bad_programs/bad_range_check.cairo:5:9: Error at pc=0:0:
 An ASSERT_EQ instruction failed: 4 != 5.
+Operands:
+    instruction at pc 0:0: Instruction { off0: -3, off1: -4, off2: 0, dst_register: FP, op0_register: FP, op1_addr: Op0, res: Op1, pc_update: Regular, ap_update: Regular, fp_update: Regular, opcode: AssertEq }
+    dst [fp - 3] at 1:26 = 4 (read)
+    op0 [fp - 4] at 1:25 = 2:0 (read)
+    op1 [op0 + 0] at 2:0 = 5 (read)
+    res (op1) = 5
 Cairo traceback (most recent call last):
 cairo_programs/bad_programs/bad_range_check.cairo:23:5: (pc=0:29)
 cairo_programs/bad_programs/bad_range_check.cairo:19:12: (pc=0:21)
@@ -922,7 +1412,7 @@ cairo_programs/bad_programs/bad_usort.cairo:64:5: (pc=0:60)
         let vm = vm!();
         // Ref id 0 corresponds to __main__.main.x, our tempvar
         assert_eq!(
-            get_value_from_simple_reference(0, &ApTracking::default(), &runner, &vm),
+            get_value_from_simple_reference(0, &ApTracking::default(), false, &runner, &vm),
             None
         )
     }
@@ -941,7 +1431,7 @@ cairo_programs/bad_programs/bad_usort.cairo:64:5: (pc=0:60)
         let vm = vm!();
         let attribute = &program.shared_program_data.error_message_attributes[0];
         assert_eq!(
-            substitute_error_message_references(attribute, &runner, &vm),
+            substitute_error_message_references(attribute, &runner, &vm, None),
             format!(
                 "{} (Cannot evaluate ap-based or complex references: ['x'])",
                 attribute.value
@@ -949,6 +1439,30 @@ cairo_programs/bad_programs/bad_usort.cairo:64:5: (pc=0:60)
         );
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_error_msg_attr_ap_based_and_check_error_attr_value() {
+        let program = Program::from_bytes(
+            include_bytes!("../../../../cairo_programs/bad_programs/error_msg_attr_ap_based.json"),
+            Some("main"),
+        )
+        .unwrap();
+
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let mut cairo_runner = cairo_runner!(program);
+        let mut vm = vm!();
+
+        let end = cairo_runner.initialize(&mut vm).unwrap();
+        let error = cairo_runner
+            .run_until_pc(end, &mut vm, &mut hint_processor)
+            .unwrap_err();
+        let vm_exception = VmException::from_vm_error(&cairo_runner, &vm, error);
+        assert_eq!(
+            vm_exception.error_attr_value,
+            Some(String::from("Error message: x is 3 and y is 4\n"))
+        );
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn get_value_from_simple_reference_complex() {
@@ -963,7 +1477,7 @@ cairo_programs/bad_programs/bad_usort.cairo:64:5: (pc=0:60)
         let vm = vm!();
         // Ref id 0 corresponds to __main__.main.cat, our struct
         assert_eq!(
-            get_value_from_simple_reference(0, &ApTracking::default(), &runner, &vm),
+            get_value_from_simple_reference(0, &ApTracking::default(), false, &runner, &vm),
             None
         )
     }
@@ -982,7 +1496,7 @@ cairo_programs/bad_programs/bad_usort.cairo:64:5: (pc=0:60)
         let vm = vm!();
         let attribute = &program.shared_program_data.error_message_attributes[0];
         assert_eq!(
-            substitute_error_message_references(attribute, &runner, &vm),
+            substitute_error_message_references(attribute, &runner, &vm, None),
             format!(
                 "{} (Cannot evaluate ap-based or complex references: ['cat'])",
                 attribute.value
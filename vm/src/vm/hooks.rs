@@ -11,10 +11,9 @@
 
 use crate::stdlib::{any::Any, collections::HashMap, prelude::*, sync::Arc};
 
-use felt::Felt252;
-
 use crate::{
-    hint_processor::hint_processor_definition::HintProcessor, types::exec_scope::ExecutionScopes,
+    hint_processor::hint_processor_definition::HintProcessor,
+    types::{constants::Constants, exec_scope::ExecutionScopes},
 };
 
 use super::{
@@ -38,7 +37,7 @@ type StepHookFunc = Arc<
             &mut dyn HintProcessor,
             &mut ExecutionScopes,
             &HashMap<usize, Vec<Box<dyn Any>>>,
-            &HashMap<String, Felt252>,
+            &Constants,
         ) -> Result<(), VirtualMachineError>
         + Sync
         + Send,
@@ -86,7 +85,7 @@ impl VirtualMachine {
         hint_executor: &mut dyn HintProcessor,
         exec_scope: &mut ExecutionScopes,
         hint_data_dictionary: &HashMap<usize, Vec<Box<dyn Any>>>,
-        constants: &HashMap<String, Felt252>,
+        constants: &Constants,
     ) -> Result<(), VirtualMachineError> {
         if let Some(hook_func) = self.hooks.clone().pre_step_instruction {
             (hook_func)(
@@ -106,7 +105,7 @@ impl VirtualMachine {
         hint_executor: &mut dyn HintProcessor,
         exec_scope: &mut ExecutionScopes,
         hint_data_dictionary: &HashMap<usize, Vec<Box<dyn Any>>>,
-        constants: &HashMap<String, Felt252>,
+        constants: &Constants,
     ) -> Result<(), VirtualMachineError> {
         if let Some(hook_func) = self.hooks.clone().post_step_instruction {
             (hook_func)(
@@ -170,7 +169,7 @@ mod tests {
             _hint_processor: &mut dyn HintProcessor,
             _exec_scope: &mut ExecutionScopes,
             _hint_data: &HashMap<usize, Vec<Box<dyn Any>>>,
-            _constants: &HashMap<String, Felt252>,
+            _constants: &Constants,
         ) -> Result<(), VirtualMachineError> {
             Err(VirtualMachineError::Unexpected)
         }
@@ -180,7 +179,7 @@ mod tests {
             _hint_processor: &mut dyn HintProcessor,
             _exec_scope: &mut ExecutionScopes,
             _hint_data: &HashMap<usize, Vec<Box<dyn Any>>>,
-            _constants: &HashMap<String, Felt252>,
+            _constants: &Constants,
         ) -> Result<(), VirtualMachineError> {
             Err(VirtualMachineError::Unexpected)
         }
@@ -240,7 +239,7 @@ mod tests {
             _hint_processor: &mut dyn HintProcessor,
             _exec_scope: &mut ExecutionScopes,
             _hint_data: &HashMap<usize, Vec<Box<dyn Any>>>,
-            _constants: &HashMap<String, Felt252>,
+            _constants: &Constants,
         ) -> Result<(), VirtualMachineError> {
             Ok(())
         }
@@ -250,7 +249,7 @@ mod tests {
             _hint_processor: &mut dyn HintProcessor,
             _exec_scope: &mut ExecutionScopes,
             _hint_data: &HashMap<usize, Vec<Box<dyn Any>>>,
-            _constants: &HashMap<String, Felt252>,
+            _constants: &Constants,
         ) -> Result<(), VirtualMachineError> {
             Ok(())
         }
@@ -0,0 +1,230 @@
+//! A journal of the memory accesses of the instructions run, in order.
+//!
+//! Each step accesses four cells, as in the AIR of Cairo: the instruction at pc, then dst, op0 and
+//! op1. The journal keeps these addresses along with which operands the step wrote, deducing
+//! them, so the step of each access is implied by its position and isn't stored. The accesses
+//! made by hints aren't recorded. When the journal is disabled, the cost of a step is a single
+//! check of an `Option`.
+
+use crate::stdlib::prelude::*;
+
+use crate::{types::relocatable::Relocatable, vm::vm_core::VirtualMachine};
+
+/// Whether an access read a cell or wrote it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+/// An access to memory by an instruction.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MemoryAccess {
+    pub step: usize,
+    pub address: Relocatable,
+    pub kind: AccessKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct StepAccesses {
+    // The addresses of the instruction, dst, op0 and op1
+    addresses: [Relocatable; 4],
+    // Bit i is set if the operand at addresses[i + 1] was written
+    writes: u8,
+}
+
+/// The memory accesses of the steps run since the journal was enabled, see
+/// [VirtualMachine::enable_memory_journal].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MemoryJournal {
+    first_step: usize,
+    steps: Vec<StepAccesses>,
+}
+
+impl MemoryJournal {
+    /// Creates an empty journal, whose first access is made by `first_step`.
+    pub fn new(first_step: usize) -> Self {
+        MemoryJournal {
+            first_step,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Returns the step making the first access of the journal.
+    pub fn first_step(&self) -> usize {
+        self.first_step
+    }
+
+    /// Returns the number of steps recorded.
+    pub fn steps(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Returns the number of accesses recorded, four per step.
+    pub fn len(&self) -> usize {
+        self.steps.len() * 4
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Returns the four accesses of `step`, or `None` if it wasn't recorded.
+    pub fn accesses_at(&self, step: usize) -> Option<[MemoryAccess; 4]> {
+        let accesses = self.steps.get(step.checked_sub(self.first_step)?)?;
+        Some(core::array::from_fn(|index| {
+            Self::access(step, accesses, index)
+        }))
+    }
+
+    /// Iterates over the accesses recorded, in the order they were made.
+    pub fn iter(&self) -> impl Iterator<Item = MemoryAccess> + '_ {
+        self.steps
+            .iter()
+            .enumerate()
+            .flat_map(move |(index, accesses)| {
+                let step = self.first_step + index;
+                (0..4).map(move |index| Self::access(step, accesses, index))
+            })
+    }
+
+    fn access(step: usize, accesses: &StepAccesses, index: usize) -> MemoryAccess {
+        let written = index > 0 && accesses.writes & (1 << (index - 1)) != 0;
+        MemoryAccess {
+            step,
+            address: accesses.addresses[index],
+            kind: if written {
+                AccessKind::Write
+            } else {
+                AccessKind::Read
+            },
+        }
+    }
+
+    // Records the accesses of the next step, given the addresses of dst, op0 and op1 and whether
+    // each of them was written
+    pub(crate) fn record(
+        &mut self,
+        pc: Relocatable,
+        operands: [Relocatable; 3],
+        writes: [bool; 3],
+    ) {
+        self.steps.push(StepAccesses {
+            addresses: [pc, operands[0], operands[1], operands[2]],
+            writes: writes
+                .iter()
+                .enumerate()
+                .fold(0, |bits, (index, written)| bits | (*written as u8) << index),
+        });
+    }
+}
+
+impl VirtualMachine {
+    /// Records the memory accesses of the steps run from now on, replacing the journal recorded so
+    /// far.
+    pub fn enable_memory_journal(&mut self) {
+        self.memory_journal = Some(MemoryJournal::new(self.current_step));
+    }
+
+    /// Stops recording the memory accesses, returning the journal, if it was enabled.
+    pub fn disable_memory_journal(&mut self) -> Option<MemoryJournal> {
+        self.memory_journal.take()
+    }
+
+    /// Returns the journal recorded so far, if it is enabled.
+    pub fn get_memory_journal(&self) -> Option<&MemoryJournal> {
+        self.memory_journal.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cairo_run::{cairo_run, CairoRunConfig},
+        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+    };
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn journal_records_accesses_by_step() {
+        let mut journal = MemoryJournal::new(3);
+        journal.record(
+            Relocatable::from((0, 0)),
+            [
+                Relocatable::from((1, 5)),
+                Relocatable::from((1, 3)),
+                Relocatable::from((0, 1)),
+            ],
+            [true, false, false],
+        );
+        journal.record(
+            Relocatable::from((0, 2)),
+            [
+                Relocatable::from((1, 6)),
+                Relocatable::from((1, 4)),
+                Relocatable::from((2, 0)),
+            ],
+            [false, false, true],
+        );
+        assert_eq!(journal.steps(), 2);
+        assert_eq!(journal.len(), 8);
+
+        let accesses: Vec<MemoryAccess> = journal.iter().collect();
+        assert_eq!(accesses.len(), 8);
+        assert_eq!(
+            accesses[1],
+            MemoryAccess {
+                step: 3,
+                address: Relocatable::from((1, 5)),
+                kind: AccessKind::Write,
+            }
+        );
+        assert_eq!(
+            accesses[4],
+            MemoryAccess {
+                step: 4,
+                address: Relocatable::from((0, 2)),
+                kind: AccessKind::Read,
+            }
+        );
+        assert_eq!(journal.accesses_at(4).unwrap()[..], accesses[4..]);
+        assert_eq!(journal.accesses_at(4).unwrap()[3].kind, AccessKind::Write);
+        assert!(journal.accesses_at(2).is_none());
+        assert!(journal.accesses_at(5).is_none());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn journal_of_fibonacci_run() {
+        let cairo_run_config = CairoRunConfig {
+            memory_journal: true,
+            ..Default::default()
+        };
+        let (_, mut vm) = cairo_run(
+            include_bytes!("../../../cairo_programs/fibonacci.json"),
+            &cairo_run_config,
+            &mut BuiltinHintProcessor::new_empty(),
+        )
+        .unwrap();
+
+        // The run takes 80 steps
+        let journal = vm.get_memory_journal().unwrap();
+        assert_eq!(journal.first_step(), 0);
+        assert_eq!(journal.steps(), 80);
+        // Instructions are only read, from the program segment
+        assert!(journal
+            .iter()
+            .step_by(4)
+            .all(|access| access.kind == AccessKind::Read && access.address.segment_index == 0));
+        assert!(journal
+            .iter()
+            .any(|access| access.kind == AccessKind::Write && access.address.segment_index == 1));
+
+        assert!(vm.disable_memory_journal().is_some());
+        assert!(vm.get_memory_journal().is_none());
+    }
+}
@@ -1,12 +1,27 @@
+pub mod ap_tracking_checker;
 pub mod context;
+pub mod debug_state;
 pub mod decoding;
 pub mod errors;
+pub mod memory_journal;
+pub mod operands_audit;
+pub mod parallel_hints;
+pub mod pc_limits;
+#[cfg(feature = "std")]
+pub mod run_events;
 pub mod runners;
 pub mod security;
+pub mod step_sampling;
 pub mod trace;
+pub mod unknown_hints;
+pub mod validation_cache;
 pub mod vm_core;
 pub mod vm_memory;
 
 #[cfg(any(feature = "hooks"))]
 #[cfg_attr(docsrs, doc(cfg(feature = "hooks")))]
 pub mod hooks;
+
+#[cfg(feature = "opcode-extensions")]
+#[cfg_attr(docsrs, doc(cfg(feature = "opcode-extensions")))]
+pub mod opcode_extensions;
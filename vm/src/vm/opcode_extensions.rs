@@ -0,0 +1,242 @@
+//! Opcode extensions, an experimental mechanism to prototype new instructions
+//!
+//! An instruction is encoded in the 63 low bits of a felt, and the VM fails on the instructions
+//! setting a higher bit. With the `opcode-extensions` feature, the value of the bits from the
+//! 63rd on is the id of an extension, and an instruction with a non-zero id is run by the
+//! [OpcodeExtension] registered for it with
+//! [add_opcode_extension](VirtualMachine::add_opcode_extension), instead of by the VM. The low
+//! 63 bits encode a regular instruction, giving the operands of the extended one. This lets
+//! forks try out new opcodes, such as a blake opcode, without patching the execution loop.
+//!
+//! Instructions whose id has no registered extension fail as without the feature.
+
+use crate::stdlib::{collections::HashMap, num::NonZeroU64, sync::Arc};
+
+use felt::Felt252;
+
+use crate::{
+    types::instruction::Instruction,
+    vm::{
+        decoding::decoder::decode_instruction,
+        errors::vm_errors::VirtualMachineError,
+        vm_core::{VirtualMachine, VirtualMachineBuilder},
+    },
+};
+
+/// The instructions of an extension, run in place of the VM.
+pub trait OpcodeExtension: Send + Sync {
+    /// Runs `instruction`, the one at pc. It has to update the registers, pc included, and
+    /// to write the memory cells its semantics require. The VM adds the registers from before
+    /// the call to the trace and counts the step.
+    fn execute(
+        &self,
+        vm: &mut VirtualMachine,
+        instruction: &ExtendedInstruction,
+    ) -> Result<(), VirtualMachineError>;
+}
+
+/// An instruction setting the bits of the encoding above the 63rd.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ExtendedInstruction {
+    /// The value of the bits from the 63rd on
+    pub extension: NonZeroU64,
+    /// The regular instruction encoded by the 63 low bits
+    pub instruction: Instruction,
+}
+
+impl ExtendedInstruction {
+    /// Decodes `encoding` as an extended instruction, returns None if its extension id is 0 or
+    /// doesn't fit in 64 bits.
+    pub fn decode(encoding: &Felt252) -> Result<Option<Self>, VirtualMachineError> {
+        let digits = encoding.to_le_digits();
+        let Some(extension) = extension_id(&digits) else {
+            return Ok(None);
+        };
+        Ok(Some(ExtendedInstruction {
+            extension,
+            instruction: decode_instruction(digits[0] & (u64::MAX >> 1))?,
+        }))
+    }
+}
+
+// Returns the value of the bits from the 63rd on, if non-zero and fitting in 64 bits
+fn extension_id(digits: &[u64; 4]) -> Option<NonZeroU64> {
+    if digits[1] >> 63 != 0 || digits[2] != 0 || digits[3] != 0 {
+        return None;
+    }
+    NonZeroU64::new((digits[0] >> 63) | (digits[1] << 1))
+}
+
+// An extended instruction, along with the registered extension running it
+type DecodedExtension = (ExtendedInstruction, Arc<dyn OpcodeExtension>);
+
+/// The extensions registered on a VM, by id.
+#[derive(Default)]
+pub(crate) struct OpcodeExtensions(HashMap<NonZeroU64, Arc<dyn OpcodeExtension>>);
+
+impl VirtualMachine {
+    /// Runs the instructions whose extension id is `extension` with `opcode_extension`,
+    /// replacing the extension previously registered for it.
+    pub fn add_opcode_extension(
+        &mut self,
+        extension: NonZeroU64,
+        opcode_extension: Arc<dyn OpcodeExtension>,
+    ) {
+        self.opcode_extensions.0.insert(extension, opcode_extension);
+    }
+
+    /// Returns the instruction at pc and its extension if it is extended by a registered one.
+    pub(crate) fn decode_extended_instruction(
+        &self,
+    ) -> Result<Option<DecodedExtension>, VirtualMachineError> {
+        if self.opcode_extensions.0.is_empty() {
+            return Ok(None);
+        }
+        let encoding = self.segments.memory.get_integer(self.run_context.pc)?;
+        // The instructions of unregistered extensions fail to decode as regular ones
+        let Some(extension) = extension_id(&encoding.to_le_digits())
+            .and_then(|extension| self.opcode_extensions.0.get(&extension))
+        else {
+            return Ok(None);
+        };
+        let extension = extension.clone();
+        Ok(ExtendedInstruction::decode(&encoding)?.map(|extended| (extended, extension)))
+    }
+}
+
+impl VirtualMachineBuilder {
+    /// Registers an extension on the VM, see [VirtualMachine::add_opcode_extension].
+    pub fn opcode_extension(
+        mut self,
+        extension: NonZeroU64,
+        opcode_extension: Arc<dyn OpcodeExtension>,
+    ) -> VirtualMachineBuilder {
+        self.opcode_extensions.0.insert(extension, opcode_extension);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        types::relocatable::{MaybeRelocatable, Relocatable},
+        utils::test_utils::*,
+        vm::{trace::trace_entry::TraceEntry, vm_core::VirtualMachineBuilder},
+    };
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    // [ap] = [ap - 1] + [ap - 2]; ap++
+    const ADD_ENCODING: u64 = 0x48307FFE7FFF8000;
+    const SQUARE_EXTENSION: u64 = 3;
+
+    // Writes the square of the result of the instruction instead of the result
+    struct SquareOfResult;
+
+    impl OpcodeExtension for SquareOfResult {
+        fn execute(
+            &self,
+            vm: &mut VirtualMachine,
+            extended: &ExtendedInstruction,
+        ) -> Result<(), VirtualMachineError> {
+            let instruction = &extended.instruction;
+            let (operands, addresses, _) = vm.compute_operands(instruction)?;
+            let Some(MaybeRelocatable::Int(res)) = operands.res else {
+                return Err(VirtualMachineError::NoDst);
+            };
+            vm.insert_value(addresses.dst_addr, &res * &res)?;
+            vm.set_pc((vm.get_pc() + instruction.size())?);
+            vm.set_ap(vm.get_ap().offset + 1);
+            Ok(())
+        }
+    }
+
+    fn extended_encoding(extension: u64, encoding: u64) -> Felt252 {
+        (Felt252::from(extension) << 63_u32) + Felt252::from(encoding)
+    }
+
+    // Makes the same addition with a regular and with an extended instruction
+    fn run_additions(vm: &mut VirtualMachine, extension: u64) -> Result<(), VirtualMachineError> {
+        vm.segments = segments![((0, 0), 0x48307FFE7FFF8000_u64), ((1, 0), 2), ((1, 1), 3)];
+        vm.segments
+            .memory
+            .insert(
+                Relocatable::from((0, 1)),
+                extended_encoding(extension, ADD_ENCODING),
+            )
+            .unwrap();
+        vm.set_ap(2);
+        vm.step_instruction()?;
+        vm.step_instruction()
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn decode_extended_instruction() {
+        assert_eq!(
+            ExtendedInstruction::decode(&Felt252::from(ADD_ENCODING)).unwrap(),
+            None
+        );
+        assert_eq!(
+            ExtendedInstruction::decode(&extended_encoding(SQUARE_EXTENSION, ADD_ENCODING))
+                .unwrap(),
+            Some(ExtendedInstruction {
+                extension: NonZeroU64::new(SQUARE_EXTENSION).unwrap(),
+                instruction: decode_instruction(ADD_ENCODING).unwrap(),
+            })
+        );
+        // Ids of more than 64 bits aren't extensions
+        assert_eq!(
+            ExtendedInstruction::decode(&(Felt252::from(1) << 200_u32)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_extended_instruction() {
+        let mut vm = vm!(true);
+        vm.add_opcode_extension(
+            NonZeroU64::new(SQUARE_EXTENSION).unwrap(),
+            Arc::new(SquareOfResult),
+        );
+        run_additions(&mut vm, SQUARE_EXTENSION).unwrap();
+        // (3 + 5)^2
+        check_memory![vm.segments.memory, ((1, 2), 5), ((1, 3), 64)];
+        assert_eq!(vm.get_pc(), Relocatable::from((0, 2)));
+        assert_eq!(vm.get_ap(), Relocatable::from((1, 4)));
+        assert_eq!(vm.current_step, 2);
+        assert_matches!(
+            vm.trace.as_deref(),
+            Some([_, TraceEntry { pc: 1, ap: 3, .. }])
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_extended_instruction_from_builder() {
+        let mut vm = VirtualMachineBuilder::default()
+            .opcode_extension(
+                NonZeroU64::new(SQUARE_EXTENSION).unwrap(),
+                Arc::new(SquareOfResult),
+            )
+            .build();
+        run_additions(&mut vm, SQUARE_EXTENSION).unwrap();
+        check_memory![vm.segments.memory, ((1, 3), 64)];
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_unregistered_extension() {
+        let mut vm = vm!();
+        vm.add_opcode_extension(NonZeroU64::new(1).unwrap(), Arc::new(SquareOfResult));
+        assert_matches!(
+            run_additions(&mut vm, SQUARE_EXTENSION),
+            Err(VirtualMachineError::InvalidInstructionEncoding)
+        );
+        assert_eq!(vm.current_step, 1);
+    }
+}
@@ -0,0 +1,173 @@
+//! The operands of a failed `assert_eq` instruction, attached to
+//! [VirtualMachineError::DiffAssertValues](crate::vm::errors::vm_errors::VirtualMachineError::DiffAssertValues)
+//! so the error tells which operand was wrong.
+//!
+//! For each operand the audit holds the address it was computed from, its value, and whether it
+//! was read from memory or deduced by the VM, along with the decoded instruction. It is displayed
+//! below the location of the error by [VmException](crate::vm::errors::vm_exception::VmException).
+
+use crate::stdlib::{
+    fmt::{self, Display},
+    prelude::*,
+};
+
+use crate::types::{
+    instruction::{Instruction, Op1Addr, Register, Res},
+    relocatable::{MaybeRelocatable, Relocatable},
+};
+
+/// How the VM obtained the value of an operand.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OperandSource {
+    /// Read from memory
+    Read,
+    /// Deduced from the other operands or by a builtin, then written to memory
+    Deduced,
+}
+
+/// An operand of an instruction.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AuditedOperand {
+    pub address: Relocatable,
+    pub value: MaybeRelocatable,
+    pub source: OperandSource,
+}
+
+/// The operands of an `assert_eq` instruction whose `dst` and `res` differ.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OperandsAudit {
+    pub pc: Relocatable,
+    pub instruction: Instruction,
+    pub dst: AuditedOperand,
+    pub op0: AuditedOperand,
+    pub op1: AuditedOperand,
+    pub res: MaybeRelocatable,
+}
+
+impl Display for OperandsAudit {
+    /// Writes a line for the instruction, then one for each operand and the result, e.g.
+    /// `op1 [op0 + 2] at 2:0 = 5 (deduced)`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let instruction = &self.instruction;
+        writeln!(f, "instruction at pc {}: {:?}", self.pc, instruction)?;
+        let operands = [
+            (
+                "dst",
+                memory_expression(register_name(instruction.dst_register), instruction.off0),
+                &self.dst,
+            ),
+            (
+                "op0",
+                memory_expression(register_name(instruction.op0_register), instruction.off1),
+                &self.op0,
+            ),
+            (
+                "op1",
+                match instruction.op1_addr {
+                    Op1Addr::Imm => memory_expression("pc", 1),
+                    Op1Addr::AP => memory_expression("ap", instruction.off2),
+                    Op1Addr::FP => memory_expression("fp", instruction.off2),
+                    Op1Addr::Op0 => memory_expression("op0", instruction.off2),
+                },
+                &self.op1,
+            ),
+        ];
+        for (name, expression, operand) in operands {
+            let source = match operand.source {
+                OperandSource::Read => "read",
+                OperandSource::Deduced => "deduced",
+            };
+            writeln!(
+                f,
+                "{name} {expression} at {} = {} ({source})",
+                operand.address, operand.value
+            )?;
+        }
+        let res = match instruction.res {
+            Res::Op1 => "op1",
+            Res::Add => "op0 + op1",
+            Res::Mul => "op0 * op1",
+            Res::Unconstrained => "unconstrained",
+        };
+        write!(f, "res ({res}) = {}", self.res)
+    }
+}
+
+fn register_name(register: Register) -> &'static str {
+    match register {
+        Register::AP => "ap",
+        Register::FP => "fp",
+    }
+}
+
+// `[base + offset]`, or `[base - offset]` for negative offsets
+fn memory_expression(base: &str, offset: isize) -> String {
+    if offset < 0 {
+        format!("[{base} - {}]", offset.unsigned_abs())
+    } else {
+        format!("[{base} + {offset}]")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        relocatable,
+        types::instruction::{ApUpdate, FpUpdate, Opcode, PcUpdate},
+        utils::test_utils::mayberelocatable,
+    };
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn display_operands_audit() {
+        // [fp - 3] = [[fp - 4] + 2]
+        let audit = OperandsAudit {
+            pc: relocatable!(0, 4),
+            instruction: Instruction {
+                off0: -3,
+                off1: -4,
+                off2: 2,
+                dst_register: Register::FP,
+                op0_register: Register::FP,
+                op1_addr: Op1Addr::Op0,
+                res: Res::Op1,
+                pc_update: PcUpdate::Regular,
+                ap_update: ApUpdate::Regular,
+                fp_update: FpUpdate::Regular,
+                opcode: Opcode::AssertEq,
+            },
+            dst: AuditedOperand {
+                address: relocatable!(1, 7),
+                value: mayberelocatable!(4),
+                source: OperandSource::Read,
+            },
+            op0: AuditedOperand {
+                address: relocatable!(1, 6),
+                value: mayberelocatable!(2, 0),
+                source: OperandSource::Read,
+            },
+            op1: AuditedOperand {
+                address: relocatable!(2, 2),
+                value: mayberelocatable!(5),
+                source: OperandSource::Deduced,
+            },
+            res: mayberelocatable!(5),
+        };
+        let lines: Vec<String> = audit.to_string().lines().map(String::from).collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("instruction at pc 0:4: Instruction { off0: -3"));
+        assert_eq!(
+            lines[1..],
+            [
+                "dst [fp - 3] at 1:7 = 4 (read)",
+                "op0 [fp - 4] at 1:6 = 2:0 (read)",
+                "op1 [op0 + 2] at 2:2 = 5 (deduced)",
+                "res (op1) = 5",
+            ]
+        );
+    }
+}
@@ -0,0 +1,452 @@
+//! Parallel execution of the independent hints attached to the same pc.
+//!
+//! Hints implemented as a [ParallelHintFunc] don't touch the VM nor the execution scopes: they
+//! read the memory through a [HintMemoryView] and return the cells to write. When parallel hints
+//! are enabled with [VirtualMachine::set_parallel_hints] and every hint at a pc has such an
+//! implementation, hints whose ids refer to distinct cells run concurrently, on the rayon thread
+//! pool with the `parallel` feature, then their writes are committed in order.
+//!
+//! The results are those of running the hints one after the other: the cells read and written by
+//! each hint are recorded, and if a hint read a cell written by an earlier hint at the pc, or
+//! wrote the same cell, or if any of them failed, nothing is committed and the hints are run again
+//! one after the other.
+
+use crate::stdlib::{
+    any::Any,
+    borrow::Cow,
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    prelude::*,
+};
+
+use felt::Felt252;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+use crate::{
+    hint_processor::hint_processor_definition::HintProcessor,
+    types::relocatable::{MaybeRelocatable, Relocatable},
+    utils::from_relocatable_to_indexes,
+    vm::{
+        errors::{
+            hint_errors::HintError, memory_errors::MemoryError, vm_errors::VirtualMachineError,
+        },
+        vm_core::VirtualMachine,
+        vm_memory::memory::{relocate_temporary_address, MemoryCell},
+    },
+};
+
+/// The cells written by a hint, in order.
+pub type HintWrites = Vec<(Relocatable, MaybeRelocatable)>;
+
+/// A hint which only reads the memory and returns the cells to write, given the addresses of its
+/// ids, so it may run in parallel with the other hints at its pc.
+#[allow(clippy::type_complexity)]
+pub struct ParallelHintFunc(
+    pub  Box<
+        dyn Fn(&HintMemoryView, &HashMap<String, Relocatable>) -> Result<HintWrites, HintError>
+            + Send
+            + Sync,
+    >,
+);
+
+/// A read only view of the memory given to a [ParallelHintFunc], recording the cells it reads.
+pub struct HintMemoryView<'a> {
+    data: &'a [Vec<Option<MemoryCell>>],
+    temp_data: &'a [Vec<Option<MemoryCell>>],
+    relocation_rules: &'a HashMap<usize, Relocatable>,
+    reads: RefCell<Vec<Relocatable>>,
+}
+
+impl<'a> HintMemoryView<'a> {
+    fn new(
+        data: &'a [Vec<Option<MemoryCell>>],
+        temp_data: &'a [Vec<Option<MemoryCell>>],
+        relocation_rules: &'a HashMap<usize, Relocatable>,
+    ) -> Self {
+        HintMemoryView {
+            data,
+            temp_data,
+            relocation_rules,
+            reads: RefCell::new(Vec::new()),
+        }
+    }
+
+    // Returns the value stored at `addr`, before applying the relocation rules
+    fn get_stored(&self, addr: Relocatable) -> Option<&'a MaybeRelocatable> {
+        self.reads.borrow_mut().push(addr);
+        let (i, j) = from_relocatable_to_indexes(addr);
+        let data = if addr.segment_index < 0 {
+            self.temp_data
+        } else {
+            self.data
+        };
+        data.get(i)?.get(j)?.as_ref().map(MemoryCell::get_value)
+    }
+
+    /// Returns the value at `addr`, if any, with the relocation rules applied to it as done by
+    /// [Memory::get](crate::vm::vm_memory::memory::Memory).
+    pub fn get(&self, addr: Relocatable) -> Option<Cow<'a, MaybeRelocatable>> {
+        Some(match self.get_stored(addr)? {
+            value @ MaybeRelocatable::Int(_) => Cow::Borrowed(value),
+            MaybeRelocatable::RelocatableValue(value) => {
+                Cow::Owned(relocate_temporary_address(*value, self.relocation_rules).into())
+            }
+        })
+    }
+
+    /// Returns the integer at `addr`, failing if the cell is empty or holds a relocatable value.
+    pub fn get_integer(&self, addr: Relocatable) -> Result<&'a Felt252, MemoryError> {
+        match self.get_stored(addr) {
+            Some(MaybeRelocatable::Int(value)) => Ok(value),
+            Some(MaybeRelocatable::RelocatableValue(_)) => {
+                Err(MemoryError::ExpectedInteger(Box::new(addr)))
+            }
+            None => Err(MemoryError::UnknownMemoryCell(Box::new(addr))),
+        }
+    }
+
+    /// Returns the relocatable value at `addr`, with the relocation rules applied to it, failing
+    /// if the cell is empty or holds an integer.
+    pub fn get_relocatable(&self, addr: Relocatable) -> Result<Relocatable, MemoryError> {
+        match self.get_stored(addr) {
+            Some(MaybeRelocatable::RelocatableValue(value)) => {
+                Ok(relocate_temporary_address(*value, self.relocation_rules))
+            }
+            Some(MaybeRelocatable::Int(_)) => Err(MemoryError::ExpectedRelocatable(Box::new(addr))),
+            None => Err(MemoryError::UnknownMemoryCell(Box::new(addr))),
+        }
+    }
+}
+
+/// A hint which may run in parallel, as returned by
+/// [HintProcessorLogic::parallel_hint](crate::hint_processor::hint_processor_definition::HintProcessorLogic::parallel_hint).
+pub struct ParallelHint<'a> {
+    pub func: &'a ParallelHintFunc,
+    /// Addresses of the ids of the hint, by name
+    pub ids: HashMap<String, Relocatable>,
+}
+
+/// Returns true if no cell is referred to by the ids of two different hints, the conservative
+/// condition for running the hints in parallel. Whether they actually are independent is checked
+/// against the cells they read and write.
+pub fn ids_are_disjoint(hints: &[ParallelHint]) -> bool {
+    let mut cells = HashSet::new();
+    hints.iter().all(|hint| {
+        let hint_cells: HashSet<_> = hint.ids.values().copied().collect();
+        hint_cells.into_iter().all(|cell| cells.insert(cell))
+    })
+}
+
+/// Runs `func` on the memory of `vm` and commits its writes, as done for a hint running alone.
+pub fn execute_parallel_hint(
+    func: &ParallelHintFunc,
+    vm: &mut VirtualMachine,
+    ids: &HashMap<String, Relocatable>,
+) -> Result<(), HintError> {
+    let memory = &vm.segments.memory;
+    let view = HintMemoryView::new(&memory.data, &memory.temp_data, &memory.relocation_rules);
+    let writes = func.0(&view, ids)?;
+    for (addr, value) in writes {
+        vm.insert_value(addr, value)?;
+    }
+    Ok(())
+}
+
+impl VirtualMachine {
+    /// Runs the independent hints attached to the same pc in parallel when all of them have a
+    /// parallel implementation, see the [module documentation](self). The hints still run one
+    /// after the other while the [hint sandbox](VirtualMachine::set_hint_sandbox) is enabled.
+    pub fn set_parallel_hints(&mut self, parallel_hints: bool) {
+        self.parallel_hints = parallel_hints;
+    }
+
+    // Runs the hints at pc in parallel, returning None if they have to run one after the other
+    pub(crate) fn step_parallel_hints(
+        &mut self,
+        hint_executor: &dyn HintProcessor,
+        hint_list: &[Box<dyn Any>],
+    ) -> Option<Result<(), VirtualMachineError>> {
+        if !self.parallel_hints || self.hint_sandbox || hint_list.len() < 2 {
+            return None;
+        }
+        let hints = hint_list
+            .iter()
+            .map(|hint_data| hint_executor.parallel_hint(self, hint_data))
+            .collect::<Option<Vec<_>>>()?;
+        if !ids_are_disjoint(&hints) {
+            return None;
+        }
+
+        let memory = &self.segments.memory;
+        let (data, temp_data, relocation_rules) =
+            (&memory.data, &memory.temp_data, &memory.relocation_rules);
+        let run = |hint: &ParallelHint| {
+            let view = HintMemoryView::new(data, temp_data, relocation_rules);
+            let writes = hint.func.0(&view, &hint.ids);
+            (writes, view.reads.into_inner())
+        };
+        #[cfg(feature = "parallel")]
+        let results: Vec<_> = hints.par_iter().map(run).collect();
+        #[cfg(not(feature = "parallel"))]
+        let results: Vec<_> = hints.iter().map(run).collect();
+
+        let mut all_writes = Vec::with_capacity(results.len());
+        let mut written = HashSet::new();
+        for (writes, reads) in results {
+            let writes = writes.ok()?;
+            if reads.iter().any(|addr| written.contains(addr))
+                || !writes.iter().all(|(addr, _)| written.insert(*addr))
+            {
+                return None;
+            }
+            all_writes.push(writes);
+        }
+        Some(self.commit_hint_writes(all_writes))
+    }
+
+    // Commits the writes of each hint at pc, in order
+    fn commit_hint_writes(
+        &mut self,
+        all_writes: Vec<HintWrites>,
+    ) -> Result<(), VirtualMachineError> {
+        for (hint_index, writes) in all_writes.into_iter().enumerate() {
+            for (addr, value) in writes {
+                self.insert_value(addr, value).map_err(|error| {
+                    VirtualMachineError::Hint(Box::new((hint_index, error.into())))
+                })?;
+            }
+            #[cfg(feature = "std")]
+            self.emit_run_event(|| crate::vm::run_events::RunEvent::HintExecuted {
+                pc: self.run_context.pc,
+                hint_index,
+            });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        any_box,
+        hint_processor::{
+            builtin_hint_processor::builtin_hint_processor_definition::{
+                BuiltinHintProcessor, HintProcessorData,
+            },
+            hint_processor_definition::HintReference,
+        },
+        relocatable,
+        stdlib::sync::Arc,
+        types::{constants::Constants, exec_scope::ExecutionScopes},
+        utils::test_utils::*,
+    };
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    // Writes ids.x + 1 to ids.y
+    fn increment() -> ParallelHintFunc {
+        ParallelHintFunc(Box::new(|memory, ids| {
+            let x = memory.get_integer(ids["x"])?;
+            Ok(vec![(ids["y"], MaybeRelocatable::from(x.clone() + 1_u32))])
+        }))
+    }
+
+    // Writes ids.x + 1 to the cell after ids.x, which isn't an id, taking a missing ids.x as 0
+    fn increment_next() -> ParallelHintFunc {
+        ParallelHintFunc(Box::new(|memory, ids| {
+            let x = match memory.get(ids["x"]).as_deref() {
+                Some(MaybeRelocatable::Int(x)) => x.clone(),
+                _ => Felt252::from(0),
+            };
+            let next = (ids["x"] + 1_usize)?;
+            Ok(vec![(next, MaybeRelocatable::from(x + 1_u32))])
+        }))
+    }
+
+    // Writes the value at ids.x to ids.y
+    fn copy() -> ParallelHintFunc {
+        ParallelHintFunc(Box::new(|memory, ids| {
+            let x = memory
+                .get(ids["x"])
+                .ok_or_else(|| MemoryError::UnknownMemoryCell(Box::new(ids["x"])))?;
+            Ok(vec![(ids["y"], x.into_owned())])
+        }))
+    }
+
+    fn hint_processor() -> BuiltinHintProcessor {
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        hint_processor.add_parallel_hint(String::from("increment"), Arc::new(increment()));
+        hint_processor
+            .add_parallel_hint(String::from("increment_next"), Arc::new(increment_next()));
+        hint_processor.add_parallel_hint(String::from("copy"), Arc::new(copy()));
+        hint_processor
+    }
+
+    // The hints at pc 0, with their ids at the given offsets from fp
+    fn hints_at_pc(hints: &[(&str, &[(&str, i32)])]) -> HashMap<usize, Vec<Box<dyn Any>>> {
+        let hint_list = hints
+            .iter()
+            .map(|(code, ids)| {
+                any_box!(HintProcessorData::new_default(
+                    code.to_string(),
+                    ids.iter()
+                        .map(|(name, offset)| (
+                            name.to_string(),
+                            HintReference::new_simple(*offset)
+                        ))
+                        .collect(),
+                ))
+            })
+            .collect();
+        HashMap::from([(0, hint_list)])
+    }
+
+    fn step_hint(
+        vm: &mut VirtualMachine,
+        hint_data_dictionary: &HashMap<usize, Vec<Box<dyn Any>>>,
+    ) {
+        vm.step_hint(
+            &mut hint_processor(),
+            &mut ExecutionScopes::new(),
+            hint_data_dictionary,
+            &Constants::new(),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn independent_hints_run_in_parallel() {
+        let mut vm = vm!();
+        vm.set_parallel_hints(true);
+        vm.segments = segments![((1, 0), 1), ((1, 2), 10)];
+        vm.set_fp(4);
+        let hints = hints_at_pc(&[
+            ("increment", &[("x", -4), ("y", -3)]),
+            ("increment", &[("x", -2), ("y", -1)]),
+        ]);
+        assert_matches!(
+            vm.step_parallel_hints(&hint_processor(), &hints[&0]),
+            Some(Ok(()))
+        );
+        check_memory![vm.segments.memory, ((1, 1), 2), ((1, 3), 11)];
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn hints_sharing_ids_run_one_after_the_other() {
+        let mut vm = vm!();
+        vm.set_parallel_hints(true);
+        vm.segments = segments![((1, 0), 1)];
+        vm.set_fp(3);
+        // The second hint reads the cell written by the first one
+        let hints = hints_at_pc(&[
+            ("increment", &[("x", -3), ("y", -2)]),
+            ("increment", &[("x", -2), ("y", -1)]),
+        ]);
+        assert!(vm
+            .step_parallel_hints(&hint_processor(), &hints[&0])
+            .is_none());
+        step_hint(&mut vm, &hints);
+        check_memory![vm.segments.memory, ((1, 0), 1), ((1, 1), 2), ((1, 2), 3)];
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn reads_of_earlier_writes_run_one_after_the_other() {
+        let mut vm = vm!();
+        vm.set_parallel_hints(true);
+        vm.segments = segments![((1, 0), 1)];
+        vm.set_fp(2);
+        // The second hint reads the cell the first one writes, which isn't one of their ids
+        let hints = hints_at_pc(&[
+            ("increment_next", &[("x", -2)]),
+            ("increment_next", &[("x", -1)]),
+        ]);
+        assert!(vm
+            .step_parallel_hints(&hint_processor(), &hints[&0])
+            .is_none());
+        assert!(vm.segments.memory.get(&relocatable!(1, 1)).is_none());
+        step_hint(&mut vm, &hints);
+        check_memory![vm.segments.memory, ((1, 0), 1), ((1, 1), 2), ((1, 2), 3)];
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn reads_apply_relocation_rules() {
+        let mut vm = vm!();
+        vm.set_parallel_hints(true);
+        vm.segments = segments![((1, 0), (-1, 2)), ((1, 2), 10), ((-1, 0), 7)];
+        vm.segments
+            .memory
+            .add_relocation_rule(relocatable!(-1, 0), relocatable!(2, 5))
+            .unwrap();
+        vm.set_fp(4);
+        let hints = hints_at_pc(&[
+            ("copy", &[("x", -4), ("y", -3)]),
+            ("increment", &[("x", -2), ("y", -1)]),
+        ]);
+        assert_matches!(
+            vm.step_parallel_hints(&hint_processor(), &hints[&0]),
+            Some(Ok(()))
+        );
+        // The pointer to the temporary segment is read as relocated, as by Memory::get
+        check_memory![vm.segments.memory, ((1, 1), (2, 7)), ((1, 3), 11)];
+
+        let view = HintMemoryView::new(
+            &vm.segments.memory.data,
+            &vm.segments.memory.temp_data,
+            &vm.segments.memory.relocation_rules,
+        );
+        // Cells of temporary segments can still be read until the memory is relocated
+        assert_eq!(view.get_integer(relocatable!(-1, 0)), Ok(&Felt252::from(7)));
+        assert_eq!(
+            view.get_relocatable(relocatable!(1, 0)),
+            Ok(relocatable!(2, 7))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn parallel_hints_disabled() {
+        let mut vm = vm!();
+        vm.segments = segments![((1, 0), 1), ((1, 2), 10)];
+        vm.set_fp(4);
+        let hints = hints_at_pc(&[
+            ("increment", &[("x", -4), ("y", -3)]),
+            ("increment", &[("x", -2), ("y", -1)]),
+        ]);
+        assert!(vm
+            .step_parallel_hints(&hint_processor(), &hints[&0])
+            .is_none());
+        // Run one after the other, they give the same results
+        step_hint(&mut vm, &hints);
+        check_memory![vm.segments.memory, ((1, 1), 2), ((1, 3), 11)];
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn ids_are_disjoint_within_hints() {
+        let func = increment();
+        let hint = |ids: &[(&str, (isize, usize))]| ParallelHint {
+            func: &func,
+            ids: ids
+                .iter()
+                .map(|(name, addr)| (name.to_string(), Relocatable::from(*addr)))
+                .collect(),
+        };
+        // A hint may refer to the same cell twice
+        assert!(ids_are_disjoint(&[
+            hint(&[("x", (1, 0)), ("y", (1, 0))]),
+            hint(&[("x", (1, 1))]),
+        ]));
+        assert!(!ids_are_disjoint(&[
+            hint(&[("x", (1, 0))]),
+            hint(&[("y", (1, 0))]),
+        ]));
+    }
+}
@@ -0,0 +1,146 @@
+//! Limits on the number of times each instruction of a program runs.
+//!
+//! A run stuck in a loop keeps running the same few instructions, so capping the executions of
+//! each pc stops it after `max_executions` iterations of the loop, long before a global step limit
+//! sized for the whole program would. The counters are a vector indexed by the offset of the pc
+//! in the program segment, so counting costs an increment and a comparison per step.
+
+use crate::stdlib::{boxed::Box, prelude::*};
+
+use crate::{
+    types::relocatable::Relocatable,
+    vm::{errors::vm_errors::VirtualMachineError, vm_core::VirtualMachine},
+};
+
+/// Counts the executions of each pc of the program segment, failing once one of them exceeds
+/// `max_executions`, see [VirtualMachine::set_pc_execution_limit].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PcExecutionCounter {
+    max_executions: usize,
+    counts: Vec<usize>,
+}
+
+impl PcExecutionCounter {
+    pub fn new(max_executions: usize) -> Self {
+        PcExecutionCounter {
+            max_executions,
+            counts: Vec::new(),
+        }
+    }
+
+    pub fn max_executions(&self) -> usize {
+        self.max_executions
+    }
+
+    /// Returns the number of times the instruction at `pc` of the program segment ran.
+    pub fn count(&self, pc: usize) -> usize {
+        self.counts.get(pc).copied().unwrap_or_default()
+    }
+
+    /// Iterates over the pcs which ran at least once, along with the number of times they did.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.counts
+            .iter()
+            .enumerate()
+            .filter(|(_, count)| **count > 0)
+            .map(|(pc, count)| (pc, *count))
+    }
+
+    // Counts an execution of the instruction at `pc`, failing if it ran too many times
+    #[inline]
+    pub(crate) fn record(&mut self, pc: usize) -> Result<(), VirtualMachineError> {
+        if self.counts.len() <= pc {
+            self.counts.resize(pc + 1, 0);
+        }
+        let count = &mut self.counts[pc];
+        if *count >= self.max_executions {
+            return Err(VirtualMachineError::PcExecutionLimit(Box::new((
+                Relocatable::from((0, pc)),
+                self.max_executions,
+            ))));
+        }
+        *count += 1;
+        Ok(())
+    }
+}
+
+impl VirtualMachine {
+    /// Fails the run with [VirtualMachineError::PcExecutionLimit] when the instruction at a pc is
+    /// about to run more than `max_executions` times, to stop untrusted programs stuck in a loop.
+    /// None removes the limit. Executions are counted from the step the limit is set at.
+    pub fn set_pc_execution_limit(&mut self, max_executions: Option<usize>) {
+        self.pc_execution_counter = max_executions.map(PcExecutionCounter::new);
+    }
+
+    /// Returns the executions counted for each pc, or None if the executions aren't limited.
+    pub fn get_pc_execution_counter(&self) -> Option<&PcExecutionCounter> {
+        self.pc_execution_counter.as_ref()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cairo_run::{cairo_run, CairoRunConfig},
+        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+        vm::errors::cairo_run_errors::CairoRunError,
+    };
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn counter_fails_past_the_limit() {
+        let mut counter = PcExecutionCounter::new(2);
+        assert_matches!(counter.record(3), Ok(()));
+        assert_matches!(counter.record(3), Ok(()));
+        assert_matches!(counter.record(1), Ok(()));
+        assert_matches!(
+            counter.record(3),
+            Err(VirtualMachineError::PcExecutionLimit(bx)) if *bx == (Relocatable::from((0, 3)), 2)
+        );
+        assert_eq!(counter.count(3), 2);
+        assert_eq!(counter.count(7), 0);
+        assert_eq!(counter.iter().collect::<Vec<_>>(), [(1, 1), (3, 2)]);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn limit_stops_fibonacci_loop() {
+        let program_data = include_bytes!("../../../cairo_programs/fibonacci.json");
+        // fib(1, 1, 10) calls itself until n is 0, so its first instruction runs 11 times
+        let cairo_run_config = CairoRunConfig {
+            pc_execution_limit: Some(10),
+            ..Default::default()
+        };
+        let Err(error) = cairo_run(
+            program_data,
+            &cairo_run_config,
+            &mut BuiltinHintProcessor::new_empty(),
+        ) else {
+            panic!("the run should hit the pc execution limit");
+        };
+        assert_matches!(
+            error,
+            CairoRunError::VmException(exception)
+                if matches!(&exception.inner_exc, VirtualMachineError::PcExecutionLimit(bx) if bx.1 == 10)
+        );
+
+        let cairo_run_config = CairoRunConfig {
+            pc_execution_limit: Some(11),
+            ..Default::default()
+        };
+        let (_, vm) = cairo_run(
+            program_data,
+            &cairo_run_config,
+            &mut BuiltinHintProcessor::new_empty(),
+        )
+        .unwrap();
+        let counter = vm.get_pc_execution_counter().unwrap();
+        assert_eq!(counter.max_executions(), 11);
+        assert_eq!(counter.iter().map(|(_, count)| count).max(), Some(11));
+    }
+}
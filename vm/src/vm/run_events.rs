@@ -0,0 +1,185 @@
+//! Events emitted during a run, for UIs showing the progress of long executions.
+//!
+//! Events are sent over a bounded [std::sync::mpsc] channel set with
+//! [VirtualMachine::set_run_events]. Sending never blocks, so the run goes on at its own pace
+//! while the receiver consumes the events, usually on another thread. Events are discarded when
+//! the channel is full, counted by [RunEvents::dropped_events], and once the receiver is dropped.
+
+use crate::stdlib::{prelude::*, sync::Arc};
+use core::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender, TrySendError};
+
+use crate::{types::relocatable::Relocatable, vm::vm_core::VirtualMachine};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RunEvent {
+    /// `step` steps were run, `pc` being the next instruction to run
+    StepMilestone { step: usize, pc: Relocatable },
+    /// The hint with index `hint_index` among the hints at `pc` was executed
+    HintExecuted { pc: Relocatable, hint_index: usize },
+    /// A segment starting at `base` was added through [VirtualMachine::add_memory_segment]
+    SegmentAdded { base: Relocatable },
+    /// The stop pointer of the builtin was read back from the stack at the end of the run
+    BuiltinFinalized {
+        name: &'static str,
+        used_cells: Option<usize>,
+    },
+}
+
+/// Sending half of a channel of [RunEvent]s.
+#[derive(Clone, Debug)]
+pub struct RunEvents {
+    sender: SyncSender<RunEvent>,
+    milestone_interval: usize,
+    // Shared by the clones, as they send to the same channel
+    dropped_events: Arc<AtomicUsize>,
+}
+
+impl RunEvents {
+    /// Creates a channel holding up to `capacity` events not received yet, emitting a
+    /// [RunEvent::StepMilestone] every `milestone_interval` steps, or never if it is 0.
+    pub fn channel(milestone_interval: usize, capacity: usize) -> (RunEvents, Receiver<RunEvent>) {
+        let (sender, receiver) = sync_channel(capacity);
+        (
+            RunEvents {
+                sender,
+                milestone_interval,
+                dropped_events: Arc::new(AtomicUsize::new(0)),
+            },
+            receiver,
+        )
+    }
+
+    /// Returns the number of events discarded because the channel was full.
+    pub fn dropped_events(&self) -> usize {
+        self.dropped_events.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn emit(&self, event: RunEvent) {
+        // The receiver going away doesn't concern the run
+        if let Err(TrySendError::Full(_)) = self.sender.try_send(event) {
+            self.dropped_events.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn step(&self, step: usize, pc: Relocatable) {
+        if self.milestone_interval != 0 && step.is_multiple_of(self.milestone_interval) {
+            self.emit(RunEvent::StepMilestone { step, pc });
+        }
+    }
+}
+
+impl VirtualMachine {
+    /// Sends the events of the run through `run_events`, or stops sending them if None.
+    pub fn set_run_events(&mut self, run_events: Option<RunEvents>) {
+        self.run_events = run_events;
+    }
+
+    /// Returns the channel the events of the run are sent through, e.g. to read how many events
+    /// were dropped.
+    pub fn get_run_events(&self) -> Option<&RunEvents> {
+        self.run_events.as_ref()
+    }
+
+    pub(crate) fn emit_run_event(&self, event: impl FnOnce() -> RunEvent) {
+        if let Some(run_events) = &self.run_events {
+            run_events.emit(event());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+        types::program::Program,
+        vm::runners::{builtin_runner::OUTPUT_BUILTIN_NAME, cairo_runner::CairoRunner},
+    };
+    use std::thread;
+
+    #[test]
+    fn run_events_of_a_run() {
+        let program = Program::from_bytes(
+            include_bytes!("../../../cairo_programs/array_sum.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let (run_events, receiver) = RunEvents::channel(10, 1024);
+        // Events are received while the run goes on
+        let events = thread::spawn(move || receiver.iter().collect::<Vec<_>>());
+
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let mut cairo_runner = CairoRunner::new(&program, "all_cairo", false).unwrap();
+        let mut vm = VirtualMachine::new(false);
+        vm.set_run_events(Some(run_events));
+        let end = cairo_runner.initialize(&mut vm).unwrap();
+        cairo_runner
+            .run_until_pc(end, &mut vm, &mut hint_processor)
+            .unwrap();
+        cairo_runner
+            .end_run(false, false, &mut vm, &mut hint_processor)
+            .unwrap();
+        cairo_runner.read_return_values(&mut vm).unwrap();
+        let steps = vm.current_step;
+        assert_eq!(vm.get_run_events().unwrap().dropped_events(), 0);
+        // Dropping the VM closes the channel
+        drop(vm);
+
+        let events = events.join().unwrap();
+        let milestones: Vec<_> = events
+            .iter()
+            .filter_map(|event| match event {
+                RunEvent::StepMilestone { step, .. } => Some(*step),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(milestones, (10..=steps).step_by(10).collect::<Vec<_>>());
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, RunEvent::HintExecuted { hint_index: 0, .. })));
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, RunEvent::SegmentAdded { .. })));
+        assert!(events.contains(&RunEvent::BuiltinFinalized {
+            name: OUTPUT_BUILTIN_NAME,
+            used_cells: Some(1),
+        }));
+    }
+
+    #[test]
+    fn dropped_receiver_doesnt_stop_the_run() {
+        let (run_events, receiver) = RunEvents::channel(1, 1);
+        drop(receiver);
+        let mut vm = VirtualMachine::new(false);
+        vm.set_run_events(Some(run_events));
+        vm.add_memory_segment();
+        // The events sent after the receiver is gone aren't counted as dropped
+        assert_eq!(vm.get_run_events().unwrap().dropped_events(), 0);
+        vm.set_run_events(None);
+        assert!(vm.run_events.is_none());
+    }
+
+    #[test]
+    fn full_channel_drops_events() {
+        let (run_events, receiver) = RunEvents::channel(1, 2);
+        let mut vm = VirtualMachine::new(false);
+        vm.set_run_events(Some(run_events));
+        for _ in 0..5 {
+            vm.add_memory_segment();
+        }
+        assert_eq!(vm.get_run_events().unwrap().dropped_events(), 3);
+        vm.set_run_events(None);
+        assert_eq!(
+            receiver.iter().collect::<Vec<_>>(),
+            vec![
+                RunEvent::SegmentAdded {
+                    base: Relocatable::from((0, 0))
+                },
+                RunEvent::SegmentAdded {
+                    base: Relocatable::from((1, 0))
+                },
+            ]
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::stdlib::{cell::RefCell, prelude::*};
+use crate::stdlib::{cell::RefCell, collections::HashMap, prelude::*};
 use crate::types::instance_definitions::pedersen_instance_def::{
     CELLS_PER_HASH, INPUT_CELLS_PER_HASH,
 };
@@ -13,6 +13,9 @@ use starknet_crypto::{pedersen_hash, FieldElement};
 
 use super::HASH_BUILTIN_NAME;
 
+/// The two input cells of an instance and their hash
+type MemoizedHash = ((Felt252, Felt252), Felt252);
+
 #[derive(Debug, Clone)]
 pub struct HashBuiltinRunner {
     pub base: usize,
@@ -24,9 +27,9 @@ pub struct HashBuiltinRunner {
     pub(crate) instances_per_component: u32,
     // This act as a cache to optimize calls to deduce_memory_cell
     // Therefore need interior mutability
-    // 1 at position 'n' means offset 'n' relative to base pointer
-    // has been verified
-    pub(self) verified_addresses: RefCell<Vec<bool>>,
+    // Maps each instance whose hash has been deduced to the two input cells it was computed
+    // from and the hash, so cells read several times before being written aren't hashed again
+    pub(self) hashes: RefCell<HashMap<usize, MemoizedHash>>,
 }
 
 impl HashBuiltinRunner {
@@ -37,7 +40,7 @@ impl HashBuiltinRunner {
             cells_per_instance: CELLS_PER_HASH,
             n_input_cells: INPUT_CELLS_PER_HASH,
             stop_ptr: None,
-            verified_addresses: RefCell::new(Vec::new()),
+            hashes: RefCell::new(HashMap::new()),
             included,
             instances_per_component: 1,
         }
@@ -74,11 +77,6 @@ impl HashBuiltinRunner {
             .offset
             .mod_floor(&(self.cells_per_instance as usize))
             != 2
-            || *self
-                .verified_addresses
-                .borrow()
-                .get(address.offset)
-                .unwrap_or(&false)
         {
             return Ok(None);
         };
@@ -95,12 +93,12 @@ impl HashBuiltinRunner {
             num_a.as_ref().map(|x| x.as_ref()),
             num_b.as_ref().map(|x| x.as_ref()),
         ) {
-            if self.verified_addresses.borrow().len() <= address.offset {
-                self.verified_addresses
-                    .borrow_mut()
-                    .resize(address.offset + 1, false);
+            let instance = address.offset / self.cells_per_instance as usize;
+            if let Some(((a, b), hash)) = self.hashes.borrow().get(&instance) {
+                if (a, b) == (num_a, num_b) {
+                    return Ok(Some(MaybeRelocatable::from(hash.clone())));
+                }
             }
-            self.verified_addresses.borrow_mut()[address.offset] = true;
 
             //Convert MaybeRelocatable to FieldElement
             let a_string = num_a.to_str_radix(10);
@@ -117,6 +115,9 @@ impl HashBuiltinRunner {
             //Convert result from FieldElement to MaybeRelocatable
             let r_byte_slice = fe_result.to_bytes_be();
             let result = Felt252::from_bytes_be(&r_byte_slice);
+            self.hashes
+                .borrow_mut()
+                .insert(instance, ((num_a.clone(), num_b.clone()), result.clone()));
             return Ok(Some(MaybeRelocatable::from(result)));
         }
         Ok(None)
@@ -414,8 +415,16 @@ mod tests {
             ))))
         );
         assert_eq!(
-            builtin.verified_addresses.into_inner(),
-            vec![false, false, false, false, false, true],
+            builtin.hashes.into_inner(),
+            HashMap::from([(
+                1,
+                (
+                    (Felt252::new(72), Felt252::new(32)),
+                    felt_str!(
+                        "3270867057177188607814717243084834301278723532952411121381966378910183338911"
+                    )
+                )
+            )]),
         );
     }
 
@@ -442,9 +451,38 @@ mod tests {
     fn deduce_memory_cell_pedersen_for_preset_memory_already_computed() {
         let memory = memory![((0, 3), 32), ((0, 4), 72), ((0, 5), 0)];
         let mut builtin = HashBuiltinRunner::new(Some(8), true);
-        builtin.verified_addresses = RefCell::new(vec![false, false, false, false, false, true]);
+        // Not the actual hash, to check that the memoized one is returned
+        builtin.hashes = RefCell::new(HashMap::from([(
+            1,
+            ((Felt252::new(72), Felt252::new(32)), Felt252::new(7)),
+        )]));
         let result = builtin.deduce_memory_cell(Relocatable::from((0, 5)), &memory);
-        assert_eq!(result, Ok(None));
+        assert_eq!(result, Ok(Some(MaybeRelocatable::from(Felt252::new(7)))));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn deduce_memory_cell_pedersen_repeated_calls() {
+        let builtin = HashBuiltinRunner::new(Some(8), true);
+        let memory = memory![((0, 3), 32), ((0, 4), 72)];
+        let hash = builtin
+            .deduce_memory_cell(Relocatable::from((0, 5)), &memory)
+            .unwrap();
+        assert!(hash.is_some());
+        assert_eq!(
+            builtin.deduce_memory_cell(Relocatable::from((0, 5)), &memory),
+            Ok(hash.clone())
+        );
+        assert_eq!(builtin.hashes.borrow().len(), 1);
+
+        // The memoized hash is only reused for the same inputs
+        let memory = memory![((0, 3), 32), ((0, 4), 73)];
+        let other_hash = builtin
+            .deduce_memory_cell(Relocatable::from((0, 5)), &memory)
+            .unwrap();
+        assert!(other_hash.is_some());
+        assert_ne!(other_hash, hash);
+        assert_eq!(builtin.hashes.borrow().len(), 1);
     }
 
     #[test]
@@ -330,7 +330,7 @@ impl BuiltinRunner {
         }
     }
 
-    fn cells_per_instance(&self) -> u32 {
+    pub(crate) fn cells_per_instance(&self) -> u32 {
         match self {
             BuiltinRunner::Bitwise(builtin) => builtin.cells_per_instance,
             BuiltinRunner::EcOp(builtin) => builtin.cells_per_instance,
@@ -344,7 +344,7 @@ impl BuiltinRunner {
         }
     }
 
-    fn n_input_cells(&self) -> u32 {
+    pub(crate) fn n_input_cells(&self) -> u32 {
         match self {
             BuiltinRunner::Bitwise(builtin) => builtin.n_input_cells,
             BuiltinRunner::EcOp(builtin) => builtin.n_input_cells,
@@ -358,7 +358,7 @@ impl BuiltinRunner {
         }
     }
 
-    fn instances_per_component(&self) -> u32 {
+    pub(crate) fn instances_per_component(&self) -> u32 {
         match self {
             BuiltinRunner::Bitwise(builtin) => builtin.instances_per_component,
             BuiltinRunner::EcOp(builtin) => builtin.instances_per_component,
@@ -472,7 +472,65 @@ impl BuiltinRunner {
         }
     }
 
-    #[cfg(test)]
+    /// Fills the holes of the builtin segment up to `size` cells, returning the number of cells
+    /// filled. The missing cells of the used instances are deduced when the builtin can deduce
+    /// them, and the unused instances are filled with copies of the first instance, which is
+    /// valid by construction. Segments of unused builtins are left empty, as are those of the
+    /// output and segment arena builtins, which don't have instances.
+    pub(crate) fn fill_memory_holes(
+        &self,
+        segments: &mut MemorySegmentManager,
+        size: usize,
+    ) -> Result<usize, RunnerError> {
+        if matches!(
+            self,
+            BuiltinRunner::Output(_) | BuiltinRunner::SegmentArena(_)
+        ) {
+            return Ok(0);
+        }
+        let base = self.base() as isize;
+        let cells_per_instance = self.cells_per_instance() as usize;
+        let used_cells = self.get_used_instances(segments)? * cells_per_instance;
+        let mut filled = 0;
+
+        for offset in 0..used_cells {
+            let addr = Relocatable::from((base, offset));
+            if segments.memory.get(&addr).is_some() {
+                continue;
+            }
+            if let Some(value) = self.deduce_memory_cell(addr, &segments.memory)? {
+                segments.memory.insert(addr, value)?;
+                filled += 1;
+            }
+        }
+
+        let first_instance: Vec<Option<MaybeRelocatable>> = (0..cells_per_instance)
+            .map(|offset| {
+                segments
+                    .memory
+                    .get(&Relocatable::from((base, offset)))
+                    .map(|value| value.into_owned())
+            })
+            .collect();
+        if first_instance.iter().all(Option::is_none) {
+            return Ok(filled);
+        }
+        for instance_start in (used_cells..size).step_by(cells_per_instance) {
+            for (offset, value) in first_instance.iter().enumerate() {
+                if let Some(value) = value {
+                    segments
+                        .memory
+                        .insert(Relocatable::from((base, instance_start + offset)), value)?;
+                    filled += 1;
+                }
+            }
+            if let BuiltinRunner::Signature(signature) = self {
+                signature.copy_signature((base, 0).into(), (base, instance_start).into());
+            }
+        }
+        Ok(filled)
+    }
+
     pub(crate) fn set_stop_ptr(&mut self, stop_ptr: usize) {
         match self {
             BuiltinRunner::Bitwise(ref mut bitwise) => bitwise.stop_ptr = Some(stop_ptr),
@@ -550,6 +608,7 @@ mod tests {
     use crate::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor;
     use crate::relocatable;
     use crate::serde::deserialize_program::BuiltinName;
+    use crate::stdlib::collections::HashMap;
     use crate::types::instance_definitions::ecdsa_instance_def::EcdsaInstanceDef;
     use crate::types::instance_definitions::keccak_instance_def::KeccakInstanceDef;
     use crate::types::program::Program;
@@ -563,6 +622,7 @@ mod tests {
         vm::vm_core::VirtualMachine,
     };
     use assert_matches::assert_matches;
+    use felt::Felt252;
 
     #[cfg(target_arch = "wasm32")]
     use wasm_bindgen_test::*;
@@ -1674,4 +1734,41 @@ mod tests {
             assert_eq!(stop_ptr, Some(ptr));
         }
     }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn fill_memory_holes_deduces_and_copies_instances() {
+        let builtin: BuiltinRunner =
+            BitwiseBuiltinRunner::new(&BitwiseInstanceDef::default(), true).into();
+        let mut vm = vm!();
+        // Only the `and` of the first instance was read
+        vm.segments = segments![((0, 0), 12), ((0, 1), 10), ((0, 2), 8)];
+        vm.segments.segment_used_sizes = Some(vec![3]);
+
+        assert_eq!(builtin.fill_memory_holes(&mut vm.segments, 15), Ok(12));
+        let segment: Vec<_> = (0..15)
+            .map(|offset| {
+                vm.segments
+                    .memory
+                    .get_integer((0, offset).into())
+                    .unwrap()
+                    .into_owned()
+            })
+            .collect();
+        for instance in segment.chunks(5) {
+            assert_eq!(instance, [12, 10, 8, 6, 14].map(Felt252::new));
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn fill_memory_holes_unused_builtin() {
+        let builtin: BuiltinRunner = RangeCheckBuiltinRunner::new(Some(8), 8, true).into();
+        let mut vm = vm!();
+        vm.segments = segments![((1, 0), 1)];
+        vm.segments.segment_used_sizes = Some(vec![0, 1]);
+
+        assert_eq!(builtin.fill_memory_holes(&mut vm.segments, 8), Ok(0));
+        assert_eq!(vm.segments.memory.data[0].len(), 0);
+    }
 }
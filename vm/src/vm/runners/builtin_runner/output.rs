@@ -1,4 +1,4 @@
-use crate::stdlib::prelude::*;
+use crate::stdlib::{collections::HashMap, prelude::*};
 use crate::types::relocatable::{MaybeRelocatable, Relocatable};
 use crate::vm::errors::memory_errors::MemoryError;
 use crate::vm::errors::runner_errors::RunnerError;
@@ -8,9 +8,34 @@ use crate::vm::vm_memory::memory_segments::MemorySegmentManager;
 
 use super::OUTPUT_BUILTIN_NAME;
 
+/// A page of the output, made of `size` cells from offset `start` of the output segment. The
+/// cells of the output outside of every page belong to page 0.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PublicMemoryPage {
+    pub start: usize,
+    pub size: usize,
+}
+
+/// The pages of the output, by id.
+pub type Pages = HashMap<usize, PublicMemoryPage>;
+/// The attributes of the output, such as the fact topologies of the bootloader, by name.
+pub type Attributes = HashMap<String, Vec<usize>>;
+
+/// The state of an output builtin runner, saved with [get_state](OutputBuiltinRunner::get_state)
+/// before a bootloader runs a child task on an output segment of its own, and restored with
+/// [set_state](OutputBuiltinRunner::set_state) after it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OutputBuiltinState {
+    pub base: usize,
+    pub pages: Pages,
+    pub attributes: Attributes,
+}
+
 #[derive(Debug, Clone)]
 pub struct OutputBuiltinRunner {
     base: usize,
+    pub(crate) pages: Pages,
+    pub(crate) attributes: Attributes,
     pub(crate) stop_ptr: Option<usize>,
     pub(crate) included: bool,
 }
@@ -19,11 +44,138 @@ impl OutputBuiltinRunner {
     pub fn new(included: bool) -> OutputBuiltinRunner {
         OutputBuiltinRunner {
             base: 0,
+            pages: HashMap::new(),
+            attributes: HashMap::new(),
             stop_ptr: None,
             included,
         }
     }
 
+    /// Resets the runner to write to the segment `base`, without pages nor attributes.
+    pub fn new_state(&mut self, base: usize, included: bool) {
+        self.base = base;
+        self.pages = HashMap::new();
+        self.attributes = HashMap::new();
+        self.stop_ptr = None;
+        self.included = included;
+    }
+
+    pub fn get_state(&self) -> OutputBuiltinState {
+        OutputBuiltinState {
+            base: self.base,
+            pages: self.pages.clone(),
+            attributes: self.attributes.clone(),
+        }
+    }
+
+    pub fn set_state(&mut self, state: OutputBuiltinState) {
+        self.base = state.base;
+        self.pages = state.pages;
+        self.attributes = state.attributes;
+        self.stop_ptr = None;
+    }
+
+    /// Adds the page `page_id`, of `page_size` cells from `page_start`.
+    pub fn add_page(
+        &mut self,
+        page_id: usize,
+        page_start: Relocatable,
+        page_size: usize,
+    ) -> Result<(), RunnerError> {
+        if page_start.segment_index != self.base as isize {
+            return Err(RunnerError::OutputPageNotInSegment(Box::new((
+                page_start, self.base,
+            ))));
+        }
+        if self.pages.contains_key(&page_id) {
+            return Err(RunnerError::OutputPageAlreadyAssigned(Box::new(page_id)));
+        }
+        self.pages.insert(
+            page_id,
+            PublicMemoryPage {
+                start: page_start.offset,
+                size: page_size,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn add_attribute(&mut self, name: String, value: Vec<usize>) {
+        self.attributes.insert(name, value);
+    }
+
+    /// Copies the `size` cells written by a child task to the segment of `child`, the state of
+    /// the runner during its run, to the output of this runner from `output_ptr`. The pages of
+    /// the child are added with their ids, shifted to their new offsets, and the values of its
+    /// attributes are appended to the attributes of the same name. Returns the end of the copy.
+    pub fn merge_child_output(
+        &mut self,
+        memory: &mut Memory,
+        child: &OutputBuiltinState,
+        size: usize,
+        output_ptr: Relocatable,
+    ) -> Result<Relocatable, RunnerError> {
+        if output_ptr.segment_index != self.base as isize {
+            return Err(RunnerError::OutputPageNotInSegment(Box::new((
+                output_ptr, self.base,
+            ))));
+        }
+        if let Some(page_id) = child
+            .pages
+            .keys()
+            .find(|page_id| self.pages.contains_key(*page_id))
+        {
+            return Err(RunnerError::OutputPageAlreadyAssigned(Box::new(*page_id)));
+        }
+        let child_base = Relocatable::from((child.base as isize, 0));
+        for offset in 0..size {
+            let address = (child_base + offset)?;
+            let value = memory
+                .get(&address)
+                .ok_or_else(|| MemoryError::UnknownMemoryCell(Box::new(address)))?
+                .into_owned();
+            memory.insert((output_ptr + offset)?, value)?;
+        }
+        for (page_id, page) in child.pages.iter() {
+            self.pages.insert(
+                *page_id,
+                PublicMemoryPage {
+                    start: page.start + output_ptr.offset,
+                    size: page.size,
+                },
+            );
+        }
+        for (name, value) in child.attributes.iter() {
+            self.attributes
+                .entry(name.clone())
+                .or_default()
+                .extend(value);
+        }
+        Ok((output_ptr + size)?)
+    }
+
+    /// Returns the offsets of the cells of the output along with the ids of their pages, to be
+    /// added to the public memory. The used sizes of the segments must have been computed.
+    pub fn get_public_memory(
+        &self,
+        segments: &MemorySegmentManager,
+    ) -> Result<Vec<(usize, usize)>, RunnerError> {
+        let size = self.get_used_cells(segments)?;
+        let mut public_memory: Vec<(usize, usize)> = (0..size).map(|offset| (offset, 0)).collect();
+        for (page_id, page) in self.pages.iter() {
+            let page_end = page.start + page.size;
+            if page_end > size {
+                return Err(RunnerError::OutputPageOutOfBounds(Box::new((
+                    *page_id, page_end, size,
+                ))));
+            }
+            for entry in public_memory[page.start..page_end].iter_mut() {
+                entry.1 = *page_id;
+            }
+        }
+        Ok(public_memory)
+    }
+
     pub fn initialize_segments(&mut self, segments: &mut MemorySegmentManager) {
         self.base = segments.add().segment_index as usize // segments.add() always returns a positive index
     }
@@ -431,4 +583,130 @@ mod tests {
         vm.segments.segment_used_sizes = Some(vec![0]);
         builtin.add_validation_rule(&mut vm.segments.memory);
     }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn get_and_set_state() {
+        let mut builtin = OutputBuiltinRunner::new(true);
+        builtin.new_state(2, true);
+        builtin.add_page(1, (2, 3).into(), 2).unwrap();
+        builtin.add_attribute(String::from("gps_fact_topology"), vec![1, 2]);
+        let state = builtin.get_state();
+        assert_eq!(
+            state,
+            OutputBuiltinState {
+                base: 2,
+                pages: HashMap::from([(1, PublicMemoryPage { start: 3, size: 2 })]),
+                attributes: HashMap::from([(String::from("gps_fact_topology"), vec![1, 2])]),
+            }
+        );
+
+        // A child task runs on a segment of its own
+        builtin.new_state(5, true);
+        assert_eq!(builtin.base(), 5);
+        assert_eq!(
+            builtin.get_state(),
+            OutputBuiltinState {
+                base: 5,
+                ..Default::default()
+            }
+        );
+
+        builtin.set_state(state.clone());
+        assert_eq!(builtin.get_state(), state);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn add_page_errors() {
+        let mut builtin = OutputBuiltinRunner::new(true);
+        builtin.new_state(2, true);
+        assert_eq!(
+            builtin.add_page(1, (3, 0).into(), 2),
+            Err(RunnerError::OutputPageNotInSegment(Box::new((
+                Relocatable::from((3, 0)),
+                2
+            ))))
+        );
+        builtin.add_page(1, (2, 0).into(), 2).unwrap();
+        assert_eq!(
+            builtin.add_page(1, (2, 2).into(), 2),
+            Err(RunnerError::OutputPageAlreadyAssigned(Box::new(1)))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn get_public_memory_with_pages() {
+        let mut builtin = OutputBuiltinRunner::new(true);
+        let mut vm = vm!();
+        vm.segments.segment_used_sizes = Some(vec![5]);
+        builtin.add_page(1, (0, 1).into(), 2).unwrap();
+        builtin.add_page(2, (0, 4).into(), 1).unwrap();
+        assert_eq!(
+            builtin.get_public_memory(&vm.segments),
+            Ok(vec![(0, 0), (1, 1), (2, 1), (3, 0), (4, 2)])
+        );
+
+        builtin.add_page(3, (0, 5).into(), 1).unwrap();
+        assert_eq!(
+            builtin.get_public_memory(&vm.segments),
+            Err(RunnerError::OutputPageOutOfBounds(Box::new((3, 6, 5))))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn merge_child_output() {
+        let mut builtin = OutputBuiltinRunner::new(true);
+        builtin.new_state(0, true);
+        builtin.add_page(1, (0, 0).into(), 1).unwrap();
+        builtin.add_attribute(String::from("gps_fact_topology"), vec![1]);
+        let mut vm = vm!();
+        vm.segments = segments![((0, 0), 7), ((1, 0), 10), ((1, 1), 11), ((1, 2), 12)];
+
+        let child = OutputBuiltinState {
+            base: 1,
+            pages: HashMap::from([(2, PublicMemoryPage { start: 1, size: 2 })]),
+            attributes: HashMap::from([(String::from("gps_fact_topology"), vec![2, 3])]),
+        };
+        let end = builtin
+            .merge_child_output(&mut vm.segments.memory, &child, 3, (0, 1).into())
+            .unwrap();
+        assert_eq!(end, Relocatable::from((0, 4)));
+        check_memory![
+            vm.segments.memory,
+            ((0, 0), 7),
+            ((0, 1), 10),
+            ((0, 2), 11),
+            ((0, 3), 12)
+        ];
+        assert_eq!(
+            builtin.pages,
+            HashMap::from([
+                (1, PublicMemoryPage { start: 0, size: 1 }),
+                (2, PublicMemoryPage { start: 2, size: 2 })
+            ])
+        );
+        assert_eq!(
+            builtin.attributes,
+            HashMap::from([(String::from("gps_fact_topology"), vec![1, 2, 3])])
+        );
+
+        // The pages of the child are already assigned, and it has no fourth cell
+        assert_eq!(
+            builtin.merge_child_output(&mut vm.segments.memory, &child, 3, end),
+            Err(RunnerError::OutputPageAlreadyAssigned(Box::new(2)))
+        );
+        let child = OutputBuiltinState {
+            base: 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            builtin.merge_child_output(&mut vm.segments.memory, &child, 4, end),
+            Err(RunnerError::Memory(MemoryError::UnknownMemoryCell(
+                Box::new(Relocatable::from((1, 3)))
+            )))
+        );
+    }
 }
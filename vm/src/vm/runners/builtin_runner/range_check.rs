@@ -1,6 +1,6 @@
 use crate::stdlib::{
     cmp::{max, min},
-    ops::Shl,
+    ops::{Range, Shl},
     prelude::*,
 };
 
@@ -86,22 +86,19 @@ impl RangeCheckBuiltinRunner {
     }
 
     pub fn add_validation_rule(&self, memory: &mut Memory) {
-        let rule: ValidationRule = ValidationRule(Box::new(
-            |memory: &Memory, address: Relocatable| -> Result<Vec<Relocatable>, MemoryError> {
-                let num = memory
-                    .get_integer(address)
-                    .map_err(|_| MemoryError::RangeCheckFoundNonInt(Box::new(address)))?;
-                if num.bits() <= N_PARTS * INNER_RC_BOUND_SHIFT {
-                    Ok(vec![address.to_owned()])
-                } else {
-                    Err(MemoryError::RangeCheckNumOutOfBounds(Box::new((
-                        num.into_owned(),
-                        Felt252::one() << ((N_PARTS * INNER_RC_BOUND_SHIFT) as u32),
-                    ))))
-                }
-            },
-        ));
-        memory.add_validation_rule(self.base, rule);
+        memory.add_validation_rule(self.base, Box::new(RangeCheckValidationRule));
+    }
+
+    /// Validates all the cells of the builtin segment whose offsets fall within `range` in a
+    /// single pass, instead of running the validation rule on each address.
+    /// Gaps are skipped, as they are validated once (and if) they are written.
+    /// Returns the addresses of the validated cells.
+    pub fn validate_range(
+        &self,
+        memory: &Memory,
+        range: Range<usize>,
+    ) -> Result<Vec<Relocatable>, MemoryError> {
+        RangeCheckValidationRule.validate_range(memory, self.base, range)
     }
 
     pub fn deduce_memory_cell(
@@ -195,13 +192,72 @@ impl RangeCheckBuiltinRunner {
     }
 }
 
+/// Checks that the cells of the range check segment hold integers of at most
+/// `N_PARTS * INNER_RC_BOUND_SHIFT` bits.
+struct RangeCheckValidationRule;
+
+impl ValidationRule for RangeCheckValidationRule {
+    fn validate(
+        &self,
+        memory: &Memory,
+        address: Relocatable,
+    ) -> Result<Vec<Relocatable>, MemoryError> {
+        let num = memory
+            .get_integer(address)
+            .map_err(|_| MemoryError::RangeCheckFoundNonInt(Box::new(address)))?;
+        if num.bits() <= N_PARTS * INNER_RC_BOUND_SHIFT {
+            Ok(vec![address.to_owned()])
+        } else {
+            Err(MemoryError::RangeCheckNumOutOfBounds(Box::new((
+                num.into_owned(),
+                Felt252::one() << ((N_PARTS * INNER_RC_BOUND_SHIFT) as u32),
+            ))))
+        }
+    }
+
+    fn validate_range(
+        &self,
+        memory: &Memory,
+        segment_index: usize,
+        range: Range<usize>,
+    ) -> Result<Vec<Relocatable>, MemoryError> {
+        let segment = match memory.data.get(segment_index) {
+            Some(segment) => segment,
+            None => return Ok(Vec::new()),
+        };
+        let end = range.end.min(segment.len());
+        let start = range.start.min(end);
+        let cells = &segment[start..end];
+        let max_bits = N_PARTS * INNER_RC_BOUND_SHIFT;
+
+        let mut validated = Vec::with_capacity(cells.len());
+        for (offset, cell) in cells.iter().enumerate() {
+            let address = Relocatable::from((segment_index as isize, start + offset));
+            match cell.as_ref().map(|cell| cell.get_value()) {
+                Some(MaybeRelocatable::Int(num)) if num.bits() > max_bits => {
+                    return Err(MemoryError::RangeCheckNumOutOfBounds(Box::new((
+                        num.clone(),
+                        Felt252::one() << (max_bits as u32),
+                    ))))
+                }
+                Some(MaybeRelocatable::Int(_)) => validated.push(address),
+                Some(MaybeRelocatable::RelocatableValue(_)) => {
+                    return Err(MemoryError::RangeCheckFoundNonInt(Box::new(address)))
+                }
+                None => {}
+            }
+        }
+        Ok(validated)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::relocatable;
     use crate::serde::deserialize_program::BuiltinName;
     use crate::stdlib::collections::HashMap;
-    use crate::vm::vm_memory::memory::Memory;
+    use crate::vm::vm_memory::memory::{Memory, MemoryCell};
     use crate::{
         hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
         types::program::Program,
@@ -226,6 +282,77 @@ mod tests {
         assert_eq!(builtin.get_used_instances(&vm.segments), Ok(1));
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn validate_range_ok() {
+        let builtin = RangeCheckBuiltinRunner::new(Some(8), 8, true);
+        let memory = memory![((0, 0), 1), ((0, 1), 2), ((0, 3), 4)];
+
+        assert_eq!(
+            builtin.validate_range(&memory, 0..4),
+            Ok(vec![
+                relocatable!(0, 0),
+                relocatable!(0, 1),
+                relocatable!(0, 3)
+            ])
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn validate_range_clamps_to_segment_len() {
+        let builtin = RangeCheckBuiltinRunner::new(Some(8), 8, true);
+        let memory = memory![((0, 0), 1), ((0, 1), 2)];
+
+        assert_eq!(
+            builtin.validate_range(&memory, 1..10),
+            Ok(vec![relocatable!(0, 1)])
+        );
+        assert_eq!(builtin.validate_range(&memory, 5..10), Ok(vec![]));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn validate_range_missing_segment() {
+        let builtin = RangeCheckBuiltinRunner::new(Some(8), 8, true);
+        let memory = Memory::new();
+
+        assert_eq!(builtin.validate_range(&memory, 0..4), Ok(vec![]));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn validate_range_out_of_bounds() {
+        let builtin = RangeCheckBuiltinRunner::new(Some(8), 8, true);
+        let mut memory = memory![((0, 0), 1)];
+        memory.data[0].push(Some(MemoryCell::new(MaybeRelocatable::from(Felt252::new(
+            -1,
+        )))));
+
+        assert_eq!(
+            builtin.validate_range(&memory, 0..2),
+            Err(MemoryError::RangeCheckNumOutOfBounds(Box::new((
+                Felt252::new(-1),
+                Felt252::one() << 128_u32
+            ))))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn validate_range_non_int() {
+        let builtin = RangeCheckBuiltinRunner::new(Some(8), 8, true);
+        let mut memory = memory![((0, 0), 1)];
+        memory.data[0].push(Some(MemoryCell::new(MaybeRelocatable::from((1, 0)))));
+
+        assert_eq!(
+            builtin.validate_range(&memory, 0..2),
+            Err(MemoryError::RangeCheckFoundNonInt(Box::new(relocatable!(
+                0, 1
+            ))))
+        );
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn final_stack() {
@@ -7,10 +7,7 @@ use crate::{
     },
     vm::{
         errors::{memory_errors::MemoryError, runner_errors::RunnerError},
-        vm_memory::{
-            memory::{Memory, ValidationRule},
-            memory_segments::MemorySegmentManager,
-        },
+        vm_memory::{memory::Memory, memory_segments::MemorySegmentManager},
     },
 };
 use felt::Felt252;
@@ -75,6 +72,38 @@ impl SignatureBuiltinRunner {
 
         Ok(())
     }
+
+    /// Returns the signatures added so far with their instance addresses, sorted by address.
+    pub(crate) fn get_signatures(&self) -> Vec<(Relocatable, (Felt252, Felt252))> {
+        let mut signatures: Vec<_> = self
+            .signatures
+            .borrow()
+            .iter()
+            .map(|(address, signature)| {
+                (
+                    *address,
+                    (
+                        Felt252::from_bytes_be(&signature.r.to_bytes_be()),
+                        Felt252::from_bytes_be(&signature.s.to_bytes_be()),
+                    ),
+                )
+            })
+            .collect();
+        signatures.sort_by_key(|(address, _)| *address);
+        signatures
+    }
+
+    /// Registers the signature of the instance at `from`, if any, for the instance at `to`.
+    pub(crate) fn copy_signature(&self, from: Relocatable, to: Relocatable) {
+        let mut signatures = self.signatures.borrow_mut();
+        if let Some(signature) = signatures.get(&from) {
+            let signature = Signature {
+                r: signature.r,
+                s: signature.s,
+            };
+            signatures.entry(to).or_insert(signature);
+        }
+    }
 }
 
 impl SignatureBuiltinRunner {
@@ -96,7 +125,7 @@ impl SignatureBuiltinRunner {
     pub fn add_validation_rule(&self, memory: &mut Memory) {
         let cells_per_instance = self.cells_per_instance;
         let signatures = Rc::clone(&self.signatures);
-        let rule: ValidationRule = ValidationRule(Box::new(
+        let rule =
             move |memory: &Memory, addr: Relocatable| -> Result<Vec<Relocatable>, MemoryError> {
                 let cell_index = addr.offset % cells_per_instance as usize;
 
@@ -142,9 +171,8 @@ impl SignatureBuiltinRunner {
                         msg.into_owned(),
                     )))),
                 }
-            },
-        ));
-        memory.add_validation_rule(self.base, rule);
+            };
+        memory.add_validation_rule(self.base, Box::new(rule));
     }
 
     pub fn deduce_memory_cell(
@@ -0,0 +1,324 @@
+//! Return value conventions of Cairo 1 executables.
+//!
+//! The `main` function of a Cairo 1 executable returns a `PanicResult<Array<felt252>>`, which
+//! ends the stack of the run as its variant (0 when it returned, 1 when it panicked) followed by
+//! the start and end of the array. Contract entrypoints end their stack the same way, after the
+//! gas and syscall pointer. When the run panicked, the array holds the panic data, usually short
+//! strings such as `'Out of gas'`.
+//!
+//! Entrypoints which can't panic return their array alone, so their stack ends with its start and
+//! end, read as a [RetdataSpan]. [CairoRunOutput::from_stack] reads the output of a run following
+//! either [RetdataConvention].
+
+use crate::stdlib::{fmt, prelude::*};
+
+use felt::Felt252;
+use num_traits::{One, Zero};
+
+use crate::{
+    types::{
+        byte_array::{decode_byte_array_string, is_byte_array_magic},
+        relocatable::{MaybeRelocatable, Relocatable},
+        short_string::decode_short_string,
+    },
+    vm::{errors::runner_errors::RunnerError, vm_core::VirtualMachine},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CairoRunOutput {
+    /// The values returned by `main`
+    Return(Vec<Felt252>),
+    /// The panic data, along with its decoded message
+    Panic(Vec<Felt252>, String),
+}
+
+/// How the array returned by an entrypoint ends the stack of its run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RetdataConvention {
+    /// The array is preceded by the variant of a `PanicResult`
+    PanicResult,
+    /// The entrypoint can't panic, the stack ends with the array alone
+    Span,
+}
+
+/// The start and end of an array returned by an entrypoint.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetdataSpan {
+    pub start: Relocatable,
+    pub end: Relocatable,
+}
+
+impl RetdataSpan {
+    /// Reads the span from the last 2 values of the stack of a finished run.
+    pub fn from_return_values(vm: &VirtualMachine) -> Result<Self, RunnerError> {
+        let return_values = vm.get_return_values(2)?;
+        Self::from_values(&return_values[0], &return_values[1])
+            .ok_or(RunnerError::InvalidRetdataSpan)
+    }
+
+    /// Returns the span from its start and end, or None if they aren't both pointers.
+    pub fn from_values(start: &MaybeRelocatable, end: &MaybeRelocatable) -> Option<Self> {
+        match (start, end) {
+            (
+                MaybeRelocatable::RelocatableValue(start),
+                MaybeRelocatable::RelocatableValue(end),
+            ) => Some(RetdataSpan {
+                start: *start,
+                end: *end,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the number of values of the array. Fails if the end is before the start, or in
+    /// another segment.
+    pub fn len(&self) -> Result<usize, RunnerError> {
+        Ok((self.end - self.start)?)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+
+    /// Reads the values of the array, which must all be felts.
+    pub fn read(&self, vm: &VirtualMachine) -> Result<Vec<Felt252>, RunnerError> {
+        Ok(vm
+            .get_integer_range(self.start, self.len()?)?
+            .into_iter()
+            .map(|felt| felt.into_owned())
+            .collect())
+    }
+}
+
+impl CairoRunOutput {
+    /// Reads the output of a finished run from the last 3 values of its stack.
+    pub fn from_return_values(vm: &VirtualMachine) -> Result<Self, RunnerError> {
+        let return_values = vm.get_return_values(3)?;
+        let data = RetdataSpan::from_values(&return_values[1], &return_values[2])
+            .ok_or(RunnerError::InvalidPanicResult)?
+            .read(vm)?;
+
+        match &return_values[0] {
+            MaybeRelocatable::Int(variant) if variant.is_zero() => Ok(CairoRunOutput::Return(data)),
+            MaybeRelocatable::Int(variant) if variant.is_one() => {
+                let message = decode_panic_data(&data);
+                Ok(CairoRunOutput::Panic(data, message))
+            }
+            _ => Err(RunnerError::InvalidPanicResult),
+        }
+    }
+
+    /// Reads the output of a finished run from the end of its stack, as laid out by `convention`.
+    pub fn from_stack(
+        vm: &VirtualMachine,
+        convention: RetdataConvention,
+    ) -> Result<Self, RunnerError> {
+        match convention {
+            RetdataConvention::PanicResult => Self::from_return_values(vm),
+            RetdataConvention::Span => Ok(CairoRunOutput::Return(
+                RetdataSpan::from_return_values(vm)?.read(vm)?,
+            )),
+        }
+    }
+
+    pub fn is_panic(&self) -> bool {
+        matches!(self, CairoRunOutput::Panic(..))
+    }
+
+    /// Returns the returned values or the panic data.
+    pub fn data(&self) -> &[Felt252] {
+        match self {
+            CairoRunOutput::Return(data) | CairoRunOutput::Panic(data, _) => data,
+        }
+    }
+}
+
+impl fmt::Display for CairoRunOutput {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CairoRunOutput::Return(data) => {
+                let values: Vec<_> = data.iter().map(|felt| felt.to_string()).collect();
+                write!(f, "[{}]", values.join(", "))
+            }
+            CairoRunOutput::Panic(_, message) => write!(f, "Panicked with {message}"),
+        }
+    }
+}
+
+/// Decodes panic data into a message, showing the `ByteArray`s and short strings it holds as
+/// strings and the other felts as numbers, separated by commas.
+pub fn decode_panic_data(data: &[Felt252]) -> String {
+    let mut parts = Vec::new();
+    let mut data = data;
+    while let Some((felt, rest)) = data.split_first() {
+        data = rest;
+        if is_byte_array_magic(felt) {
+            if let Some((string, len)) = decode_byte_array_string(rest) {
+                parts.push(string);
+                data = &rest[len..];
+                continue;
+            }
+        }
+        parts.push(match decode_short_string(felt) {
+            Some(string) if !string.is_empty() => string,
+            _ => felt.to_string(),
+        });
+    }
+    parts.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{types::byte_array::BYTE_ARRAY_MAGIC, utils::test_utils::*};
+    use assert_matches::assert_matches;
+    use num_traits::Num;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    // Loads a stack ending with `variant`, if any, and the span of `data`
+    fn run_stack(variant: Option<i32>, data: &[Felt252]) -> VirtualMachine {
+        let mut vm = vm!();
+        vm.segments.add();
+        let stack_base = vm.segments.add();
+        let data_base = vm.segments.add();
+        let data: Vec<_> = data.iter().map(MaybeRelocatable::from).collect();
+        let data_end = vm.load_data(data_base, &data).unwrap();
+        let mut stack: Vec<MaybeRelocatable> = variant
+            .map(|variant| Felt252::from(variant).into())
+            .into_iter()
+            .collect();
+        stack.extend([data_base.into(), data_end.into()]);
+        let ap = vm.load_data(stack_base, &stack).unwrap();
+        vm.set_ap(ap.offset);
+        vm
+    }
+
+    fn run_output(variant: i32, data: &[Felt252]) -> Result<CairoRunOutput, RunnerError> {
+        CairoRunOutput::from_return_values(&run_stack(Some(variant), data))
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn read_returned_values() {
+        let output = run_output(0, &[Felt252::from(1), Felt252::from(2)]).unwrap();
+        assert_eq!(
+            output,
+            CairoRunOutput::Return(vec![Felt252::from(1), Felt252::from(2)])
+        );
+        assert!(!output.is_panic());
+        assert_eq!(output.to_string(), "[1, 2]");
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn read_panic_data() {
+        let data = vec![Felt252::from_bytes_be(b"Out of gas"), Felt252::from(7)];
+        let output = run_output(1, &data).unwrap();
+        assert_eq!(
+            output,
+            CairoRunOutput::Panic(data.clone(), String::from("Out of gas, 7"))
+        );
+        assert!(output.is_panic());
+        assert_eq!(output.data(), data.as_slice());
+        assert_eq!(output.to_string(), "Panicked with Out of gas, 7");
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn read_invalid_variant() {
+        assert_matches!(run_output(2, &[]), Err(RunnerError::InvalidPanicResult));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn read_retdata_span() {
+        let vm = run_stack(None, &[Felt252::from(5), Felt252::from(6)]);
+        let span = RetdataSpan::from_return_values(&vm).unwrap();
+        assert_eq!(
+            span,
+            RetdataSpan {
+                start: Relocatable::from((2, 0)),
+                end: Relocatable::from((2, 2)),
+            }
+        );
+        assert_eq!(span.len(), Ok(2));
+        assert!(!span.is_empty());
+        assert_eq!(
+            span.read(&vm).unwrap(),
+            [Felt252::from(5), Felt252::from(6)]
+        );
+        assert_eq!(
+            CairoRunOutput::from_stack(&vm, RetdataConvention::Span),
+            Ok(CairoRunOutput::Return(vec![
+                Felt252::from(5),
+                Felt252::from(6)
+            ]))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn read_panic_result_from_stack() {
+        let vm = run_stack(Some(1), &[Felt252::from_bytes_be(b"Out of gas")]);
+        assert_matches!(
+            CairoRunOutput::from_stack(&vm, RetdataConvention::PanicResult),
+            Ok(CairoRunOutput::Panic(_, message)) if message == "Out of gas"
+        );
+        // The span is read from the last 2 values, after the variant
+        let vm = run_stack(Some(0), &[]);
+        let span = RetdataSpan::from_return_values(&vm).unwrap();
+        assert!(span.is_empty());
+        assert_eq!(span.read(&vm), Ok(Vec::new()));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn read_invalid_retdata_span() {
+        let mut vm = vm!();
+        vm.segments.add();
+        let stack_base = vm.segments.add();
+        let stack = vec![MaybeRelocatable::from(3), MaybeRelocatable::from(4)];
+        let ap = vm.load_data(stack_base, &stack).unwrap();
+        vm.set_ap(ap.offset);
+        assert_eq!(
+            RetdataSpan::from_return_values(&vm),
+            Err(RunnerError::InvalidRetdataSpan)
+        );
+
+        let span = RetdataSpan {
+            start: Relocatable::from((2, 3)),
+            end: Relocatable::from((2, 1)),
+        };
+        assert_matches!(span.len(), Err(RunnerError::Math(_)));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn decode_byte_array_panic_data() {
+        let magic = Felt252::from_str_radix(BYTE_ARRAY_MAGIC, 16).unwrap();
+        let data = [
+            magic.clone(),
+            Felt252::from(0),
+            Felt252::from_bytes_be("d\u{e9}j\u{e0} vu".as_bytes()),
+            Felt252::from(9),
+            Felt252::from(3),
+            // Not followed by a valid ByteArray
+            magic.clone(),
+        ];
+        assert_eq!(
+            decode_panic_data(&data),
+            format!("d\u{e9}j\u{e0} vu, 3, {magic}")
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn decode_non_string_panic_data() {
+        assert_eq!(
+            decode_panic_data(&[Felt252::zero(), Felt252::from(10), Felt252::from(0x41)]),
+            "0, 10, A"
+        );
+    }
+}
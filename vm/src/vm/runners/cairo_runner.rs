@@ -1,7 +1,9 @@
 use crate::{
+    cairo_run::CairoRunConfig,
     stdlib::{
         any::Any,
-        collections::{HashMap, HashSet},
+        collections::{BTreeMap, HashMap, HashSet},
+        fmt,
         ops::{Add, AddAssign, Mul, MulAssign, Sub, SubAssign},
         prelude::*,
     },
@@ -14,6 +16,7 @@ use crate::{
     math_utils::safe_div_usize,
     serde::deserialize_program::BuiltinName,
     types::{
+        constants::Constants,
         errors::{math_errors::MathError, program_errors::ProgramError},
         exec_scope::ExecutionScopes,
         instance_definitions::{
@@ -22,10 +25,11 @@ use crate::{
         },
         layout::CairoLayout,
         program::Program,
-        relocatable::{relocate_address, relocate_value, MaybeRelocatable, Relocatable},
+        relocatable::{relocate_value, MaybeRelocatable, Relocatable},
     },
     utils::is_subsequence,
     vm::{
+        ap_tracking_checker::ApTrackingChecker,
         errors::{
             cairo_run_errors::CairoRunError,
             memory_errors::{InsufficientAllocatedCellsError, MemoryError},
@@ -35,6 +39,11 @@ use crate::{
             vm_exception::VmException,
         },
         security::verify_secure_runner,
+        trace::{
+            builtin_interaction::{BuiltinUsageByFunction, FunctionBuiltinUsage},
+            call_stack::{CallStack, FunctionIndex},
+        },
+        validation_cache::ValidationCache,
         {
             runners::builtin_runner::{
                 BitwiseBuiltinRunner, BuiltinRunner, EcOpBuiltinRunner, HashBuiltinRunner,
@@ -47,9 +56,14 @@ use crate::{
 use felt::Felt252;
 use num_integer::div_rem;
 use num_traits::Zero;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 use serde::Deserialize;
 
-use super::builtin_runner::{KeccakBuiltinRunner, PoseidonBuiltinRunner};
+use super::{
+    builtin_runner::{KeccakBuiltinRunner, PoseidonBuiltinRunner},
+    whitelist::RunnerWhitelist,
+};
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub enum CairoArg {
@@ -124,21 +138,52 @@ impl ResourceTracker for RunResources {
 #[derive(Debug)]
 pub struct CairoRunner {
     pub(crate) program: Program,
-    layout: CairoLayout,
+    pub(crate) layout: CairoLayout,
     final_pc: Option<Relocatable>,
     pub program_base: Option<Relocatable>,
-    execution_base: Option<Relocatable>,
+    pub(crate) execution_base: Option<Relocatable>,
     entrypoint: Option<usize>,
-    initial_ap: Option<Relocatable>,
-    initial_fp: Option<Relocatable>,
-    initial_pc: Option<Relocatable>,
-    run_ended: bool,
-    segments_finalized: bool,
-    execution_public_memory: Option<Vec<usize>>,
-    proof_mode: bool,
+    pub(crate) initial_ap: Option<Relocatable>,
+    pub(crate) initial_fp: Option<Relocatable>,
+    pub(crate) initial_pc: Option<Relocatable>,
+    pub(crate) run_ended: bool,
+    pub(crate) segments_finalized: bool,
+    pub(crate) execution_public_memory: Option<Vec<usize>>,
+    pub(crate) proof_mode: bool,
     pub original_steps: Option<usize>,
     pub relocated_memory: Vec<Option<Felt252>>,
+    // Relocated address of the first cell of each segment, computed once by the relocation
+    relocation_table: Option<Vec<usize>>,
     pub exec_scopes: ExecutionScopes,
+    // Index and size of the segments added with load_constant_segment
+    pub(crate) constant_segments: Vec<(usize, usize)>,
+    allow_unordered_builtins: bool,
+    check_ap_tracking: bool,
+    whitelist: Option<RunnerWhitelist>,
+    filled_builtin_holes: Option<HashMap<&'static str, usize>>,
+    tolerate_stop_pointer_mismatches: bool,
+    stop_pointer_warnings: Vec<StopPointerWarning>,
+}
+
+/// A builtin whose stop pointer doesn't match the cells used in its segment, tolerated by
+/// [CairoRunner::set_tolerate_stop_pointer_mismatches].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StopPointerWarning {
+    pub builtin: &'static str,
+    /// The end of the cells used in the segment of the builtin
+    pub expected: Relocatable,
+    /// The stop pointer returned by the program
+    pub found: Relocatable,
+}
+
+impl fmt::Display for StopPointerWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Invalid stop pointer for {}. Expected: {}, found: {}",
+            self.builtin, self.expected, self.found
+        )
+    }
 }
 
 impl CairoRunner {
@@ -147,22 +192,8 @@ impl CairoRunner {
         layout: &str,
         proof_mode: bool,
     ) -> Result<CairoRunner, RunnerError> {
-        let cairo_layout = match layout {
-            "plain" => CairoLayout::plain_instance(),
-            "small" => CairoLayout::small_instance(),
-            "dex" => CairoLayout::dex_instance(),
-            "starknet" => CairoLayout::starknet_instance(),
-            "starknet_with_keccak" => CairoLayout::starknet_with_keccak_instance(),
-            "recursive_large_output" => CairoLayout::recursive_large_output_instance(),
-            "all_cairo" => CairoLayout::all_cairo_instance(),
-            "all_solidity" => CairoLayout::all_solidity_instance(),
-            "dynamic" => CairoLayout::dynamic_instance(),
-            name => {
-                return Err(RunnerError::InvalidLayoutName(
-                    name.to_string().into_boxed_str(),
-                ))
-            }
-        };
+        let cairo_layout = CairoLayout::from_name(layout)
+            .ok_or_else(|| RunnerError::InvalidLayoutName(layout.to_string().into_boxed_str()))?;
         Ok(CairoRunner {
             program: program.clone(),
             layout: cairo_layout,
@@ -178,11 +209,149 @@ impl CairoRunner {
             proof_mode,
             original_steps: None,
             relocated_memory: Vec::new(),
+            relocation_table: None,
             exec_scopes: ExecutionScopes::new(),
             execution_public_memory: if proof_mode { Some(Vec::new()) } else { None },
+            constant_segments: Vec::new(),
+            allow_unordered_builtins: false,
+            check_ap_tracking: false,
+            whitelist: None,
+            filled_builtin_holes: None,
+            tolerate_stop_pointer_mismatches: false,
+            stop_pointer_warnings: Vec::new(),
         })
     }
 
+    /// Creates a runner with the layout, proof mode and runner options of `cairo_run_config`.
+    /// The options of the virtual machine are applied by [CairoRunConfig::new_vm], and those of
+    /// the steps around the run (`entrypoint`, `relocate_mem`, `secure_run` and
+    /// `fill_builtin_holes`) by [cairo_run_program](crate::cairo_run::cairo_run_program).
+    pub fn from_config(
+        program: &Program,
+        cairo_run_config: &CairoRunConfig,
+    ) -> Result<CairoRunner, RunnerError> {
+        let mut cairo_runner = CairoRunner::new(
+            program,
+            cairo_run_config.layout,
+            cairo_run_config.proof_mode,
+        )?;
+        cairo_runner.set_allow_unordered_builtins(cairo_run_config.allow_unordered_builtins);
+        cairo_runner.set_check_ap_tracking(cairo_run_config.check_ap_tracking);
+        cairo_runner.set_tolerate_stop_pointer_mismatches(
+            cairo_run_config.tolerate_stop_pointer_mismatches,
+        );
+        Ok(cairo_runner)
+    }
+
+    /// Allows running programs whose builtins aren't declared in the order of the layout.
+    /// Their builtin runners are still created in the layout order, while their pointers are
+    /// passed to and read back from the entrypoint in the order of the program.
+    pub fn set_allow_unordered_builtins(&mut self, allow_unordered_builtins: bool) {
+        self.allow_unordered_builtins = allow_unordered_builtins;
+    }
+
+    /// Checks before each step of [run_until_pc](Self::run_until_pc) that ap moved as the ap
+    /// tracking data of the program says, failing with
+    /// [VirtualMachineError::ApTrackingMismatch] otherwise. Meant for debugging, as it slows
+    /// down the run.
+    pub fn set_check_ap_tracking(&mut self, check_ap_tracking: bool) {
+        self.check_ap_tracking = check_ap_tracking;
+    }
+
+    /// Tolerates the stop pointers which don't match the cells used in the segment of their
+    /// builtin when reading the return values of a run out of proof mode, for exploratory runs of
+    /// programs which don't handle their builtins yet. Instead of failing with
+    /// [RunnerError::InvalidStopPointer], the stop pointer is kept and reported by
+    /// [get_stop_pointer_warnings](Self::get_stop_pointer_warnings). Proof mode runs still fail.
+    pub fn set_tolerate_stop_pointer_mismatches(&mut self, tolerate: bool) {
+        self.tolerate_stop_pointer_mismatches = tolerate;
+    }
+
+    /// Returns the stop pointer mismatches tolerated when reading the return values, in the
+    /// order the builtins were read.
+    pub fn get_stop_pointer_warnings(&self) -> &[StopPointerWarning] {
+        &self.stop_pointer_warnings
+    }
+
+    /// Restricts the builtins the program may request and the entrypoints
+    /// [run_from_entrypoint](Self::run_from_entrypoint) may target, for programs from untrusted
+    /// sources. Initializing the builtins of a program requesting others fails with
+    /// [RunnerError::BuiltinNotWhitelisted], and running other entrypoints with
+    /// [RunnerError::EntrypointNotWhitelisted]. `None`, the default, allows everything.
+    pub fn set_whitelist(&mut self, whitelist: Option<RunnerWhitelist>) {
+        self.whitelist = whitelist;
+    }
+
+    fn check_whitelisted_builtins(&self) -> Result<(), RunnerError> {
+        let whitelist = match &self.whitelist {
+            Some(whitelist) => whitelist,
+            None => return Ok(()),
+        };
+        match self
+            .program
+            .builtins
+            .iter()
+            .find(|builtin| !whitelist.allows_builtin(builtin))
+        {
+            Some(builtin) => Err(RunnerError::BuiltinNotWhitelisted(builtin.name().into())),
+            None => Ok(()),
+        }
+    }
+
+    /// Adds a segment holding `data` and returns its base, so that it can be passed as an argument
+    /// to the program (e.g. as a lookup table) instead of being written by hints.
+    ///
+    /// The segment is finalized with the size of `data`: as memory cells can only be written once,
+    /// its contents can't change, and the secure run checks reject runs that write past its end.
+    ///
+    /// Must be called after [initialize_segments](Self::initialize_segments), so the segment
+    /// doesn't take the place of the program or execution segments.
+    pub fn load_constant_segment(
+        &mut self,
+        vm: &mut VirtualMachine,
+        data: &[Felt252],
+    ) -> Result<Relocatable, RunnerError> {
+        if self.program_base.is_none() {
+            return Err(RunnerError::NoProgBase);
+        }
+        if self.execution_base.is_none() {
+            return Err(RunnerError::NoExecBase);
+        }
+        let base = vm.segments.add();
+        let data = data.iter().map(MaybeRelocatable::from).collect();
+        vm.segments.load_data(base, &data)?;
+        let segment_index = base.segment_index as usize;
+        vm.segments.finalize(Some(data.len()), segment_index, None);
+        self.constant_segments.push((segment_index, data.len()));
+        Ok(base)
+    }
+
+    /// Loads the instructions of the program decoded by previous runs from `cache`, so this run
+    /// doesn't decode them again. Must be called after the program is loaded into `vm`, as done by
+    /// [initialize](Self::initialize).
+    pub fn load_validation_cache(&self, vm: &mut VirtualMachine, cache: &ValidationCache) {
+        let program_base = match self.program_base {
+            Some(base) if base.segment_index == 0 => base,
+            _ => return,
+        };
+        if let Some(decoded) = cache.get(&self.program.shared_program_data.data) {
+            vm.load_decoded_instructions(program_base.offset, decoded);
+        }
+    }
+
+    /// Stores the instructions of the program decoded so far in `cache`, for the next runs of the
+    /// same program.
+    pub fn update_validation_cache(&self, vm: &VirtualMachine, cache: &mut ValidationCache) {
+        let program_base = match self.program_base {
+            Some(base) if base.segment_index == 0 => base,
+            _ => return,
+        };
+        let decoded = vm.decoded_instructions();
+        let start = program_base.offset.min(decoded.len());
+        let end = (start + self.program.shared_program_data.data.len()).min(decoded.len());
+        cache.insert(&self.program.shared_program_data.data, &decoded[start..end]);
+    }
+
     pub fn initialize(&mut self, vm: &mut VirtualMachine) -> Result<Relocatable, RunnerError> {
         self.initialize_builtins(vm)?;
         self.initialize_segments(vm, None);
@@ -192,6 +361,7 @@ impl CairoRunner {
     }
 
     pub fn initialize_builtins(&self, vm: &mut VirtualMachine) -> Result<(), RunnerError> {
+        self.check_whitelisted_builtins()?;
         let builtin_ordered_list = vec![
             BuiltinName::output,
             BuiltinName::pedersen,
@@ -202,7 +372,16 @@ impl CairoRunner {
             BuiltinName::keccak,
             BuiltinName::poseidon,
         ];
-        if !is_subsequence(&self.program.builtins, &builtin_ordered_list) {
+        // Builtins unknown to the VM are reported below, as no layout has them
+        let known_builtins: Vec<_> = self
+            .program
+            .builtins
+            .iter()
+            .filter(|builtin| !matches!(builtin, BuiltinName::Other(_)))
+            .cloned()
+            .collect();
+        if !self.allow_unordered_builtins && !is_subsequence(&known_builtins, &builtin_ordered_list)
+        {
             return Err(RunnerError::DisorderedBuiltins);
         };
         let mut program_builtins: HashSet<&BuiltinName> = self.program.builtins.iter().collect();
@@ -273,7 +452,10 @@ impl CairoRunner {
         }
         if !program_builtins.is_empty() {
             return Err(RunnerError::NoBuiltinForInstance(Box::new((
-                program_builtins.iter().map(|n| n.name()).collect(),
+                program_builtins
+                    .iter()
+                    .map(|n| n.name().to_string())
+                    .collect(),
                 self.layout._name.clone(),
             ))));
         }
@@ -289,6 +471,7 @@ impl CairoRunner {
         vm: &mut VirtualMachine,
         add_segment_arena_builtin: bool,
     ) -> Result<(), RunnerError> {
+        self.check_whitelisted_builtins()?;
         let starknet_preset_builtins = vec![
             BuiltinName::pedersen,
             BuiltinName::range_check,
@@ -301,10 +484,11 @@ impl CairoRunner {
         ];
 
         fn initialize_builtin(
-            name: BuiltinName,
+            name: &BuiltinName,
             vm: &mut VirtualMachine,
             add_segment_arena_builtin: bool,
-        ) {
+            layout_name: &str,
+        ) -> Result<(), RunnerError> {
             match name {
                 BuiltinName::pedersen => vm
                     .builtin_runners
@@ -337,15 +521,32 @@ impl CairoRunner {
                             .push(SegmentArenaBuiltinRunner::new(true).into())
                     }
                 }
+                BuiltinName::Other(name) => {
+                    return Err(RunnerError::NoBuiltinForInstance(Box::new((
+                        [name.clone()].into_iter().collect(),
+                        layout_name.to_string(),
+                    ))))
+                }
             }
+            Ok(())
         }
 
         for builtin_name in &self.program.builtins {
-            initialize_builtin(*builtin_name, vm, add_segment_arena_builtin);
+            initialize_builtin(
+                builtin_name,
+                vm,
+                add_segment_arena_builtin,
+                &self.layout._name,
+            )?;
         }
         for builtin_name in starknet_preset_builtins {
             if !self.program.builtins.contains(&builtin_name) {
-                initialize_builtin(builtin_name, vm, add_segment_arena_builtin)
+                initialize_builtin(
+                    &builtin_name,
+                    vm,
+                    add_segment_arena_builtin,
+                    &self.layout._name,
+                )?;
             }
         }
         Ok(())
@@ -365,6 +566,36 @@ impl CairoRunner {
         for builtin_runner in vm.builtin_runners.iter_mut() {
             builtin_runner.initialize_segments(&mut vm.segments);
         }
+        self.name_segments(vm);
+    }
+
+    /// Names the program, execution and builtin segments, so their values are displayed as
+    /// e.g. `pedersen:12`, see
+    /// [crate::vm::vm_memory::memory_segments::MemorySegmentManager::set_segment_name].
+    /// Keeps the names set by the user.
+    fn name_segments(&self, vm: &mut VirtualMachine) {
+        let builtin_segments = vm.builtin_runners.iter().map(|builtin_runner| {
+            let name = builtin_runner.name();
+            (
+                builtin_runner.base() as isize,
+                name.strip_suffix("_builtin").unwrap_or(name),
+            )
+        });
+        let segments = self
+            .program_base
+            .map(|base| (base.segment_index, "program"))
+            .into_iter()
+            .chain(
+                self.execution_base
+                    .map(|base| (base.segment_index, "execution")),
+            )
+            .chain(builtin_segments)
+            .collect::<Vec<_>>();
+        for (segment_index, name) in segments {
+            if vm.segments.get_segment_name(segment_index).is_none() {
+                vm.segments.set_segment_name(segment_index, name);
+            }
+        }
     }
 
     fn initialize_state(
@@ -379,9 +610,21 @@ impl CairoRunner {
                 offset: prog_base.offset + entrypoint,
             };
             self.initial_pc = Some(initial_pc);
-            vm.segments
-                .load_data(prog_base, &self.program.shared_program_data.data)
-                .map_err(RunnerError::MemoryInitializationError)?;
+            // Starting from the end ensures any necessary resize
+            // is performed once with enough room for everything
+            for (offset, value) in self
+                .program
+                .shared_program_data
+                .data
+                .iter()
+                .enumerate()
+                .rev()
+            {
+                vm.segments
+                    .memory
+                    .insert((prog_base + offset)?, value)
+                    .map_err(RunnerError::MemoryInitializationError)?;
+            }
 
             // Mark all addresses from the program segment as accessed
             let base = self
@@ -435,8 +678,8 @@ impl CairoRunner {
         vm: &mut VirtualMachine,
     ) -> Result<Relocatable, RunnerError> {
         let mut stack = Vec::new();
-        for builtin_runner in vm.builtin_runners.iter() {
-            stack.append(&mut builtin_runner.initial_stack());
+        for index in self.builtin_runners_stack_order(&vm.builtin_runners) {
+            stack.append(&mut vm.builtin_runners[index].initial_stack());
         }
         //Different process if proof_mode is enabled
         if self.proof_mode {
@@ -506,7 +749,12 @@ impl CairoRunner {
         self.initial_fp
     }
 
-    /// Gets the data used by the HintProcessor to execute each hint
+    /// Gets the data used by the HintProcessor to execute each hint, keyed by the offset of its pc
+    ///
+    /// The hints at each pc are compiled in the order the program declares them, then arranged
+    /// by [HintProcessorLogic::arrange_hints](crate::hint_processor::hint_processor_definition::HintProcessorLogic::arrange_hints),
+    /// which keeps that order by default.
+    /// [VirtualMachine::step_hint] runs them in the order of the returned lists.
     pub fn get_hint_data_dictionary(
         &self,
         references: &[HintReference],
@@ -514,25 +762,28 @@ impl CairoRunner {
     ) -> Result<HashMap<usize, Vec<Box<dyn Any>>>, VirtualMachineError> {
         let mut hint_data_dictionary = HashMap::<usize, Vec<Box<dyn Any>>>::new();
         for (hint_index, hints) in self.program.shared_program_data.hints.iter() {
+            let mut hints_data = Vec::with_capacity(hints.len());
             for hint in hints {
-                let hint_data = hint_executor.compile_hint(
+                let hint_data = hint_executor.compile_hint_with_scopes(
                     &hint.code,
+                    &hint.accessible_scopes,
                     &hint.flow_tracking_data.ap_tracking,
                     &hint.flow_tracking_data.reference_ids,
                     references,
                 );
-                hint_data_dictionary
-                    .entry(*hint_index)
-                    .or_default()
-                    .push(hint_data.map_err(|_| {
-                        VirtualMachineError::CompileHintFail(hint.code.clone().into_boxed_str())
-                    })?);
+                hints_data.push(hint_data.map_err(|_| {
+                    VirtualMachineError::CompileHintFail(hint.code.clone().into_boxed_str())
+                })?);
             }
+            hint_data_dictionary.insert(
+                *hint_index,
+                hint_executor.arrange_hints(*hint_index, hints_data),
+            );
         }
         Ok(hint_data_dictionary)
     }
 
-    pub fn get_constants(&self) -> &HashMap<String, Felt252> {
+    pub fn get_constants(&self) -> &Constants {
         &self.program.constants
     }
 
@@ -548,11 +799,20 @@ impl CairoRunner {
     ) -> Result<(), VirtualMachineError> {
         let references = &self.program.shared_program_data.reference_manager;
         let hint_data_dictionary = self.get_hint_data_dictionary(references, hint_processor)?;
+        let mut ap_tracking_checker = if self.check_ap_tracking {
+            let program_base = self.program_base.ok_or(RunnerError::NoProgBase)?;
+            Some(ApTrackingChecker::new(&self.program, program_base))
+        } else {
+            None
+        };
 
         #[cfg(feature = "hooks")]
         vm.execute_before_first_step(self, &hint_data_dictionary)?;
 
         while vm.run_context.pc != address && !hint_processor.consumed() {
+            if let Some(ap_tracking_checker) = &mut ap_tracking_checker {
+                ap_tracking_checker.check(vm)?;
+            }
             vm.step(
                 hint_processor,
                 &mut self.exec_scopes,
@@ -578,6 +838,7 @@ impl CairoRunner {
     ) -> Result<(), VirtualMachineError> {
         let references = &self.program.shared_program_data.reference_manager;
         let hint_data_dictionary = self.get_hint_data_dictionary(references, hint_processor)?;
+        vm.reserve_steps(steps);
 
         for remaining_steps in (1..=steps).rev() {
             if self.final_pc.as_ref() == Some(&vm.run_context.pc) {
@@ -712,6 +973,10 @@ impl CairoRunner {
 
         vm.segments.memory.relocate_memory()?;
         vm.end_run(&self.exec_scopes)?;
+        vm.segments
+            .memory
+            .validate_pending_memory()
+            .map_err(RunnerError::MemoryValidationError)?;
 
         if disable_finalize_all {
             return Ok(());
@@ -742,24 +1007,26 @@ impl CairoRunner {
     /// Relocates the VM's memory, turning bidimensional indexes into contiguous numbers, and values
     /// into Felt252s. Uses the relocation_table to asign each index a number according to the value
     /// on its segment number.
+    ///
+    /// With the `parallel` feature, the values of each segment are relocated on a separate thread.
+    /// The relocated memory is the same either way.
     fn relocate_memory(
         &mut self,
         vm: &mut VirtualMachine,
-        relocation_table: &Vec<usize>,
+        relocation_table: &[usize],
     ) -> Result<(), MemoryError> {
         if !(self.relocated_memory.is_empty()) {
             return Err(MemoryError::Relocation);
         }
         //Relocated addresses start at 1
         self.relocated_memory.push(None);
+        #[cfg(not(feature = "parallel"))]
         for (index, segment) in vm.segments.memory.data.iter().enumerate() {
+            let segment_base = *relocation_table.get(index).ok_or(MemoryError::Relocation)?;
             for (seg_offset, cell) in segment.iter().enumerate() {
                 match cell {
                     Some(cell) => {
-                        let relocated_addr = relocate_address(
-                            Relocatable::from((index as isize, seg_offset)),
-                            relocation_table,
-                        )?;
+                        let relocated_addr = segment_base + seg_offset;
                         let value = relocate_value(cell.get_value().clone(), relocation_table)?;
                         if self.relocated_memory.len() <= relocated_addr {
                             self.relocated_memory.resize(relocated_addr + 1, None);
@@ -770,18 +1037,94 @@ impl CairoRunner {
                 }
             }
         }
+        #[cfg(feature = "parallel")]
+        {
+            // The values are relocated first, one segment per thread, and then placed at their
+            // relocated addresses
+            let relocated_segments = vm
+                .segments
+                .memory
+                .data
+                .par_iter()
+                .map(|segment| {
+                    segment
+                        .iter()
+                        .map(|cell| {
+                            cell.as_ref()
+                                .map(|cell| {
+                                    relocate_value(cell.get_value().clone(), relocation_table)
+                                })
+                                .transpose()
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            for (index, segment) in relocated_segments.into_iter().enumerate() {
+                let segment_base = *relocation_table.get(index).ok_or(MemoryError::Relocation)?;
+                for (seg_offset, value) in segment.into_iter().enumerate() {
+                    match value {
+                        Some(value) => {
+                            let relocated_addr = segment_base + seg_offset;
+                            if self.relocated_memory.len() <= relocated_addr {
+                                self.relocated_memory.resize(relocated_addr + 1, None);
+                            }
+                            self.relocated_memory[relocated_addr] = Some(value);
+                        }
+                        None => self.relocated_memory.push(None),
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Relocates only the memory of the segments in `segment_indices` (such as the program and
+    /// output segments) into `relocated_memory`, leaving the rest of the addresses empty.
+    /// Addresses are the same ones a full relocation would produce. On error, neither the
+    /// relocated memory nor the relocation table are set, so the run can still be relocated.
+    pub fn relocate_segments(
+        &mut self,
+        vm: &mut VirtualMachine,
+        segment_indices: &[usize],
+    ) -> Result<(), MemoryError> {
+        if !(self.relocated_memory.is_empty()) {
+            return Err(MemoryError::Relocation);
+        }
+        vm.segments.compute_effective_sizes();
+        let relocation_table = vm.segments.relocate_segments()?;
+        let data = &vm.segments.memory.data;
+
+        //Relocated addresses start at 1
+        let mut relocated_memory = vec![None];
+        for index in segment_indices.iter().copied() {
+            let segment = data
+                .get(index)
+                .ok_or_else(|| MemoryError::UnallocatedSegment(Box::new((index, data.len()))))?;
+            for (seg_offset, cell) in segment.iter().enumerate() {
+                if let Some(cell) = cell {
+                    let relocated_addr = relocation_table[index] + seg_offset;
+                    let value = relocate_value(cell.get_value().clone(), &relocation_table)?;
+                    if relocated_memory.len() <= relocated_addr {
+                        relocated_memory.resize(relocated_addr + 1, None);
+                    }
+                    relocated_memory[relocated_addr] = Some(value);
+                }
+            }
+        }
+        self.relocated_memory = relocated_memory;
+        self.relocation_table = Some(relocation_table);
         Ok(())
     }
 
+    /// Relocates the memory if `relocate_mem` is set, and the trace if it is enabled.
+    /// The relocation table is computed once for both, and kept to be read with
+    /// [get_relocation_table](Self::get_relocation_table), even if neither is relocated.
     pub fn relocate(
         &mut self,
         vm: &mut VirtualMachine,
         relocate_mem: bool,
     ) -> Result<(), TraceError> {
         vm.segments.compute_effective_sizes();
-        if !relocate_mem && vm.trace.is_none() {
-            return Ok(());
-        }
         // relocate_segments can fail if compute_effective_sizes is not called before.
         // The expect should be unreachable.
         let relocation_table = vm
@@ -793,7 +1136,16 @@ impl CairoRunner {
                 return Err(TraceError::MemoryError(memory_error));
             }
         }
-        vm.relocate_trace(&relocation_table)
+        let result = vm.relocate_trace(&relocation_table);
+        self.relocation_table = Some(relocation_table);
+        result
+    }
+
+    /// Returns the relocated address of the first cell of each segment, indexed by segment,
+    /// once the run has been relocated with [relocate](Self::relocate) or
+    /// [relocate_segments](Self::relocate_segments).
+    pub fn get_relocation_table(&self) -> Option<&[usize]> {
+        self.relocation_table.as_deref()
     }
 
     // Returns a map from builtin base's segment index to stop_ptr offset
@@ -816,6 +1168,65 @@ impl CairoRunner {
         Ok(builtin_segment_info)
     }
 
+    /// Attributes the builtin instances used in the run to the function whose frame first
+    /// accessed each of them. The run must have the trace enabled, and not be relocated yet.
+    pub fn get_builtin_usage_by_function(
+        &self,
+        vm: &VirtualMachine,
+    ) -> Result<BuiltinUsageByFunction, VirtualMachineError> {
+        let interactions = vm.get_builtin_interactions()?;
+        let trace = vm.trace.as_ref().ok_or(TraceError::TraceNotEnabled)?;
+        let cells_per_instance: HashMap<&'static str, usize> = vm
+            .builtin_runners
+            .iter()
+            .map(|builtin| (builtin.name(), builtin.cells_per_instance() as usize))
+            .collect();
+        let program_offset = self
+            .program_base
+            .map(|base| base.offset)
+            .unwrap_or_default();
+
+        let functions = FunctionIndex::new(&self.program);
+        let mut call_stack = CallStack::default();
+        let mut interactions = interactions.iter().peekable();
+        let mut used_instances = HashSet::new();
+        let mut usage = HashMap::<(String, &'static str), usize>::new();
+        for (step, entry) in trace.iter().enumerate() {
+            call_stack.step(
+                entry.fp,
+                entry.pc.saturating_sub(program_offset),
+                &functions,
+            );
+            while let Some(interaction) =
+                interactions.next_if(|interaction| interaction.step == step)
+            {
+                // The output builtin has no cells per instance, each of its cells counts as one
+                let cells = cells_per_instance
+                    .get(interaction.builtin)
+                    .copied()
+                    .unwrap_or_default()
+                    .max(1);
+                if used_instances.insert((interaction.builtin, interaction.address.offset / cells))
+                {
+                    *usage
+                        .entry((call_stack.current().to_string(), interaction.builtin))
+                        .or_default() += 1;
+                }
+            }
+        }
+
+        let mut usage: Vec<_> = usage
+            .into_iter()
+            .map(|((function, builtin), instances)| FunctionBuiltinUsage {
+                function,
+                builtin,
+                instances,
+            })
+            .collect();
+        usage.sort_unstable_by(|a, b| (&a.function, a.builtin).cmp(&(&b.function, b.builtin)));
+        Ok(BuiltinUsageByFunction(usage))
+    }
+
     pub fn get_execution_resources(
         &self,
         vm: &VirtualMachine,
@@ -830,7 +1241,7 @@ impl CairoRunner {
         };
         let n_memory_holes = self.get_memory_holes(vm)?;
 
-        let mut builtin_instance_counter = HashMap::new();
+        let mut builtin_instance_counter = BTreeMap::new();
         for builtin_runner in &vm.builtin_runners {
             builtin_instance_counter.insert(
                 builtin_runner.name().to_string(),
@@ -845,6 +1256,40 @@ impl CairoRunner {
         })
     }
 
+    /// Fills the holes of the builtin segments, up to the number of cells the layout allocates to
+    /// each builtin for the steps run, as the prover expects them to be fully written: missing
+    /// cells of the used instances are deduced where possible and unused instances are filled
+    /// with copies of the first one. Must be called in proof mode, after
+    /// [read_return_values](Self::read_return_values) and before
+    /// [finalize_segments](Self::finalize_segments). The number of cells filled for each builtin
+    /// is then returned by [get_filled_builtin_holes](Self::get_filled_builtin_holes).
+    pub fn fill_builtin_memory_holes(
+        &mut self,
+        vm: &mut VirtualMachine,
+    ) -> Result<(), RunnerError> {
+        if !self.proof_mode {
+            return Err(RunnerError::FillHolesNoProofMode);
+        }
+        if !self.run_ended {
+            return Err(RunnerError::FillHolesNoEndRun);
+        }
+        let mut filled_holes = HashMap::new();
+        for builtin_runner in vm.builtin_runners.iter() {
+            let (_, size) = builtin_runner.get_used_cells_and_allocated_size(vm)?;
+            let filled = builtin_runner.fill_memory_holes(&mut vm.segments, size)?;
+            filled_holes.insert(builtin_runner.name(), filled);
+        }
+        self.filled_builtin_holes = Some(filled_holes);
+        Ok(())
+    }
+
+    /// Returns the number of cells filled in the segment of each builtin by
+    /// [fill_builtin_memory_holes](Self::fill_builtin_memory_holes), by builtin name, or None if
+    /// it wasn't called.
+    pub fn get_filled_builtin_holes(&self) -> Option<&HashMap<&'static str, usize>> {
+        self.filled_builtin_holes.as_ref()
+    }
+
     // Finalizes the segments.
     //     Note:
     //     1.  end_run() must precede a call to this method.
@@ -885,12 +1330,29 @@ impl CairoRunner {
         }
         vm.segments
             .finalize(None, exec_base.segment_index as usize, Some(&public_memory));
+        // Cells written with lazy validation after the run ended, e.g. when filling the holes of
+        // the builtin segments, are validated before the segments are finalized
+        vm.segments
+            .memory
+            .validate_pending_memory()
+            .map_err(RunnerError::FinalizeSegements)?;
         for builtin_runner in vm.builtin_runners.iter() {
             let (_, size) = builtin_runner
                 .get_used_cells_and_allocated_size(vm)
-                .map_err(RunnerError::FinalizeSegements)?;
+                .map_err(|err| match err {
+                    MemoryError::InsufficientAllocatedCells(_) => self
+                        .diagnose_layout(vm)
+                        .map_or(RunnerError::FinalizeSegements(err), |diagnostic| {
+                            RunnerError::InsufficientLayout(Box::new(diagnostic))
+                        }),
+                    err => RunnerError::FinalizeSegements(err),
+                })?;
+            let public_memory = match builtin_runner {
+                BuiltinRunner::Output(output) => Some(output.get_public_memory(&vm.segments)?),
+                _ => None,
+            };
             vm.segments
-                .finalize(Some(size), builtin_runner.base(), None)
+                .finalize(Some(size), builtin_runner.base(), public_memory.as_ref())
         }
         self.segments_finalized = true;
         Ok(())
@@ -909,6 +1371,11 @@ impl CairoRunner {
         vm: &mut VirtualMachine,
         hint_processor: &mut dyn HintProcessor,
     ) -> Result<(), CairoRunError> {
+        if let Some(whitelist) = &self.whitelist {
+            if !whitelist.allows_entrypoint(&self.program, entrypoint) {
+                return Err(RunnerError::EntrypointNotWhitelisted(entrypoint).into());
+            }
+        }
         let stack = args
             .iter()
             .map(|arg| vm.segments.gen_cairo_arg(arg))
@@ -1036,9 +1503,32 @@ impl CairoRunner {
             return Err(RunnerError::ReadReturnValuesNoEndRun);
         }
         let mut pointer = vm.get_ap();
-        for builtin_runner in vm.builtin_runners.iter_mut().rev() {
-            let new_pointer = builtin_runner.final_stack(&vm.segments, pointer)?;
-            pointer = new_pointer;
+        for index in self
+            .builtin_runners_stack_order(&vm.builtin_runners)
+            .into_iter()
+            .rev()
+        {
+            pointer = match vm.builtin_runners[index].final_stack(&vm.segments, pointer) {
+                Err(RunnerError::InvalidStopPointer(bx))
+                    if self.tolerate_stop_pointer_mismatches && !self.proof_mode =>
+                {
+                    let (builtin, expected, found) = *bx;
+                    vm.builtin_runners[index].set_stop_ptr(found.offset);
+                    self.stop_pointer_warnings.push(StopPointerWarning {
+                        builtin,
+                        expected,
+                        found,
+                    });
+                    // The stop pointer was read right before the pointer
+                    (pointer - 1)?
+                }
+                result => result?,
+            };
+            #[cfg(feature = "std")]
+            vm.emit_run_event(|| crate::vm::run_events::RunEvent::BuiltinFinalized {
+                name: vm.builtin_runners[index].name(),
+                used_cells: vm.builtin_runners[index].get_used_cells(&vm.segments).ok(),
+            });
         }
         if self.segments_finalized {
             return Err(RunnerError::FailedAddingReturnValues);
@@ -1083,21 +1573,45 @@ impl CairoRunner {
         stack_ptr: Relocatable,
     ) -> Result<Relocatable, RunnerError> {
         let mut stack_ptr = Relocatable::from(&stack_ptr);
-        for runner in vm
-            .builtin_runners
-            .iter_mut()
-            .rev()
-            .filter(|builtin_runner| {
+        let program_runners: Vec<usize> = self
+            .builtin_runners_stack_order(&vm.builtin_runners)
+            .into_iter()
+            .filter(|index| {
                 self.get_program_builtins()
                     .iter()
-                    .any(|bn| bn.name() == builtin_runner.name())
+                    .any(|bn| bn.name() == vm.builtin_runners[*index].name())
             })
-        {
-            stack_ptr = runner.final_stack(&vm.segments, stack_ptr)?
+            .collect();
+        for index in program_runners.into_iter().rev() {
+            stack_ptr = vm.builtin_runners[index].final_stack(&vm.segments, stack_ptr)?
         }
         Ok(stack_ptr)
     }
 
+    // Returns the indices of `builtin_runners` in the order their pointers are passed to the
+    // entrypoint. With unordered builtins allowed, these are the runners of the program builtins
+    // in the order of the program, followed by the other ones, which don't take stack space.
+    // Otherwise the runners keep their own order.
+    fn builtin_runners_stack_order(&self, builtin_runners: &[BuiltinRunner]) -> Vec<usize> {
+        if !self.allow_unordered_builtins {
+            return (0..builtin_runners.len()).collect();
+        }
+        let mut order = Vec::with_capacity(builtin_runners.len());
+        for builtin in self.program.builtins.iter() {
+            if let Some(index) = (0..builtin_runners.len()).find(|index| {
+                !order.contains(index) && builtin_runners[*index].name() == builtin.name()
+            }) {
+                order.push(index);
+            }
+        }
+        for index in 0..builtin_runners.len() {
+            if !order.contains(&index) {
+                order.push(index);
+            }
+        }
+        order
+    }
+
     /// Return CairoRunner.program
     pub fn get_program(&self) -> &Program {
         &self.program
@@ -1118,7 +1632,7 @@ pub struct SegmentInfo {
 pub struct ExecutionResources {
     pub n_steps: usize,
     pub n_memory_holes: usize,
-    pub builtin_instance_counter: HashMap<String, usize>,
+    pub builtin_instance_counter: BTreeMap<String, usize>,
 }
 
 /// Returns a copy of the execution resources where all the builtins with a usage counter
@@ -1205,7 +1719,7 @@ impl MulAssign<usize> for ExecutionResources {
 mod tests {
     use super::*;
     use crate::cairo_run::{cairo_run, CairoRunConfig};
-    use crate::stdlib::collections::{HashMap, HashSet};
+    use crate::stdlib::collections::{BTreeSet, HashMap};
     use crate::vm::runners::builtin_runner::{
         BITWISE_BUILTIN_NAME, EC_OP_BUILTIN_NAME, HASH_BUILTIN_NAME, KECCAK_BUILTIN_NAME,
         OUTPUT_BUILTIN_NAME, POSEIDON_BUILTIN_NAME, RANGE_CHECK_BUILTIN_NAME,
@@ -1214,11 +1728,23 @@ mod tests {
     use crate::vm::vm_memory::memory::MemoryCell;
 
     use crate::{
-        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+        any_box,
+        hint_processor::{
+            builtin_hint_processor::builtin_hint_processor_definition::{
+                BuiltinHintProcessor, HintFunc, HintProcessorData,
+            },
+            hint_processor_definition::HintProcessorLogic,
+        },
         relocatable,
-        serde::deserialize_program::{Identifier, ReferenceManager},
-        types::instance_definitions::bitwise_instance_def::BitwiseInstanceDef,
+        serde::deserialize_program::{
+            ApTracking, FlowTrackingData, HintParams, Identifier, ReferenceManager,
+        },
+        stdlib::rc::Rc,
+        types::{
+            instance_definitions::bitwise_instance_def::BitwiseInstanceDef, program::ProgramData,
+        },
         utils::test_utils::*,
+        vm::errors::hint_errors::HintError,
         vm::trace::trace_entry::TraceEntry,
     };
     use assert_matches::assert_matches;
@@ -1346,6 +1872,33 @@ mod tests {
         assert_eq!(vm.segments.num_segments(), 3);
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn initialize_segments_names_segments() {
+        let program = program![BuiltinName::output, BuiltinName::pedersen];
+        let mut cairo_runner = cairo_runner!(program);
+        let mut vm = vm!();
+        vm.segments.add();
+        vm.segments.set_segment_name(0, "custom");
+        cairo_runner.initialize_builtins(&mut vm).unwrap();
+        cairo_runner.initialize_segments(&mut vm, Some(relocatable!(0, 0)));
+        assert_eq!(
+            vm.segments.segment_names(),
+            &BTreeMap::from([
+                (0, String::from("custom")),
+                (1, String::from("execution")),
+                (2, String::from("output")),
+                (3, String::from("pedersen")),
+            ])
+        );
+        assert_eq!(
+            Relocatable::from((3, 12))
+                .display_with(vm.segments.segment_names())
+                .to_string(),
+            "pedersen:12"
+        );
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn initialize_state_empty_data_and_stack() {
@@ -2402,6 +2955,111 @@ mod tests {
         assert_eq!(cairo_runner.relocated_memory[9], Some(Felt252::new(5)));
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn relocate_selected_segments() {
+        let program = program!();
+        let mut cairo_runner = cairo_runner!(program);
+        let mut vm = vm!();
+        vm.segments = segments![
+            ((0, 0), 1),
+            ((0, 1), 2),
+            ((1, 0), (2, 0)),
+            ((1, 1), 3),
+            ((2, 0), (0, 1)),
+            ((2, 2), 4)
+        ];
+
+        assert_eq!(cairo_runner.relocate_segments(&mut vm, &[0, 2]), Ok(()));
+        assert_eq!(
+            cairo_runner.relocated_memory,
+            vec![
+                None,
+                Some(Felt252::new(1)),
+                Some(Felt252::new(2)),
+                None,
+                None,
+                Some(Felt252::new(2)),
+                None,
+                Some(Felt252::new(4))
+            ]
+        );
+        assert_eq!(cairo_runner.get_relocation_table(), Some(&[1, 3, 5][..]));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn relocate_keeps_relocation_table() {
+        let program = program!();
+        let mut cairo_runner = cairo_runner!(program);
+        let mut vm = vm!(true);
+        vm.segments = segments![((0, 0), 1), ((0, 1), 2), ((1, 0), (2, 1)), ((2, 1), 3)];
+        vm.trace = Some(vec![TraceEntry {
+            pc: 0,
+            ap: 1,
+            fp: 1,
+        }]);
+        assert_eq!(cairo_runner.get_relocation_table(), None);
+
+        assert_matches!(cairo_runner.relocate(&mut vm, true), Ok(()));
+        assert_eq!(cairo_runner.get_relocation_table(), Some(&[1, 3, 4][..]));
+        assert_eq!(
+            cairo_runner.relocated_memory,
+            vec![
+                None,
+                Some(Felt252::new(1)),
+                Some(Felt252::new(2)),
+                Some(Felt252::new(5)),
+                None,
+                Some(Felt252::new(3))
+            ]
+        );
+        assert_eq!(
+            vm.get_relocated_trace().unwrap(),
+            &[TraceEntry {
+                pc: 1,
+                ap: 4,
+                fp: 4
+            }]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn relocate_without_memory_nor_trace_keeps_relocation_table() {
+        let program = program!();
+        let mut cairo_runner = cairo_runner!(program);
+        let mut vm = vm!();
+        vm.segments = segments![((0, 0), 1), ((0, 1), 2), ((1, 0), (2, 1)), ((2, 1), 3)];
+
+        assert_matches!(cairo_runner.relocate(&mut vm, false), Ok(()));
+        assert_eq!(cairo_runner.get_relocation_table(), Some(&[1, 3, 4][..]));
+        assert!(cairo_runner.relocated_memory.is_empty());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn relocate_selected_segments_unallocated_segment() {
+        let program = program!();
+        let mut cairo_runner = cairo_runner!(program);
+        let mut vm = vm!();
+        vm.segments = segments![((0, 0), 1)];
+
+        assert_eq!(
+            cairo_runner.relocate_segments(&mut vm, &[0, 3]),
+            Err(MemoryError::UnallocatedSegment(Box::new((3, 1))))
+        );
+        assert!(cairo_runner.relocated_memory.is_empty());
+        assert_eq!(cairo_runner.get_relocation_table(), None);
+
+        // The failed call leaves the run to be relocated as usual
+        assert_eq!(cairo_runner.relocate(&mut vm, true), Ok(()));
+        assert_eq!(
+            cairo_runner.relocated_memory,
+            vec![None, Some(Felt252::new(1))]
+        );
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     /* Program used:
@@ -2748,6 +3406,27 @@ mod tests {
         assert_eq!(&output_buffer, "1\n2\n");
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn write_output_in_felt_format() {
+        let program = program![BuiltinName::output];
+        let mut cairo_runner = cairo_runner!(program);
+        let mut vm = vm!();
+        cairo_runner.initialize_builtins(&mut vm).unwrap();
+        cairo_runner.initialize_segments(&mut vm, None);
+
+        vm.segments = segments![((2, 0), 0x6f6b), ((2, 1), 255)];
+        vm.segments.segment_used_sizes = Some(vec![0, 0, 2]);
+
+        let mut output_buffer = String::new();
+        vm.write_output_in_format(
+            &mut output_buffer,
+            crate::types::felt_format::FeltFormat::ShortString,
+        )
+        .unwrap();
+        assert_eq!(&output_buffer, "'ok'\n0xff\n");
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     /*Program used:
@@ -3204,7 +3883,7 @@ mod tests {
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn get_constants() {
-        let program_constants = HashMap::from([
+        let program_constants = Constants::from([
             ("MAX".to_string(), Felt252::new(300)),
             ("MIN".to_string(), Felt252::new(20)),
         ]);
@@ -3468,7 +4147,7 @@ mod tests {
             Ok(ExecutionResources {
                 n_steps: 10,
                 n_memory_holes: 0,
-                builtin_instance_counter: HashMap::new(),
+                builtin_instance_counter: BTreeMap::new(),
             }),
         );
     }
@@ -3480,10 +4159,25 @@ mod tests {
         let cairo_run_config = CairoRunConfig {
             entrypoint: "main",
             trace_enabled: true,
+            compress_trace: false,
             relocate_mem: false,
             layout: "all_cairo",
             proof_mode: false,
             secure_run: Some(false),
+            allow_unordered_builtins: false,
+            check_ap_tracking: false,
+            lazy_validation: false,
+            size_hints: None,
+            max_segment_offset: None,
+            fill_builtin_holes: false,
+            step_sampling_interval: None,
+            pc_execution_limit: None,
+            tolerate_stop_pointer_mismatches: false,
+            memory_journal: false,
+            unknown_hint_policy: Default::default(),
+            strict_memory_reads: false,
+            #[cfg(feature = "std")]
+            run_events: None,
         };
         let mut hint_executor = BuiltinHintProcessor::new_empty();
         let (runner, vm) = cairo_run(program_data, &cairo_run_config, &mut hint_executor).unwrap();
@@ -3497,10 +4191,25 @@ mod tests {
         let cairo_run_config = CairoRunConfig {
             entrypoint: "main",
             trace_enabled: false,
+            compress_trace: false,
             relocate_mem: false,
             layout: "all_cairo",
             proof_mode: false,
             secure_run: Some(false),
+            allow_unordered_builtins: false,
+            check_ap_tracking: false,
+            lazy_validation: false,
+            size_hints: None,
+            max_segment_offset: None,
+            fill_builtin_holes: false,
+            step_sampling_interval: None,
+            pc_execution_limit: None,
+            tolerate_stop_pointer_mismatches: false,
+            memory_journal: false,
+            unknown_hint_policy: Default::default(),
+            strict_memory_reads: false,
+            #[cfg(feature = "std")]
+            run_events: None,
         };
         let mut hint_executor = BuiltinHintProcessor::new_empty();
         let (runner, vm) = cairo_run(program_data, &cairo_run_config, &mut hint_executor).unwrap();
@@ -3522,7 +4231,7 @@ mod tests {
             Ok(ExecutionResources {
                 n_steps: 10,
                 n_memory_holes: 0,
-                builtin_instance_counter: HashMap::new(),
+                builtin_instance_counter: BTreeMap::new(),
             }),
         );
     }
@@ -3548,7 +4257,7 @@ mod tests {
             Ok(ExecutionResources {
                 n_steps: 10,
                 n_memory_holes: 0,
-                builtin_instance_counter: HashMap::from([(
+                builtin_instance_counter: BTreeMap::from([(
                     BuiltinName::output.name().to_string(),
                     4
                 )]),
@@ -3568,6 +4277,19 @@ mod tests {
         )
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn fill_builtin_memory_holes_run_not_ended() {
+        let program = program!();
+        let mut cairo_runner = cairo_runner!(program, "all_cairo", true);
+        let mut vm = vm!();
+        assert_eq!(
+            cairo_runner.fill_builtin_memory_holes(&mut vm),
+            Err(RunnerError::FillHolesNoEndRun)
+        );
+        assert_eq!(cairo_runner.get_filled_builtin_holes(), None);
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn finalize_segments_run_ended_empty_no_prog_base() {
@@ -3631,7 +4353,7 @@ mod tests {
     fn finalize_segments_run_ended_not_emptyproof_mode_empty_execution_public_memory() {
         let mut program = program!();
         Arc::get_mut(&mut program.shared_program_data).unwrap().data =
-            vec_data![(1), (2), (3), (4), (5), (6), (7), (8)];
+            ProgramData::try_from(vec_data![(1), (2), (3), (4), (5), (6), (7), (8)]).unwrap();
         //Program data len = 8
         let mut cairo_runner = cairo_runner!(program, "plain", true);
         cairo_runner.program_base = Some(Relocatable::from((0, 0)));
@@ -3665,7 +4387,7 @@ mod tests {
     fn finalize_segments_run_ended_not_emptyproof_mode_with_execution_public_memory() {
         let mut program = program!();
         Arc::get_mut(&mut program.shared_program_data).unwrap().data =
-            vec_data![(1), (2), (3), (4)];
+            ProgramData::try_from(vec_data![(1), (2), (3), (4)]).unwrap();
         //Program data len = 4
         let mut cairo_runner = cairo_runner!(program, "plain", true);
         cairo_runner.program_base = Some(Relocatable::from((0, 0)));
@@ -4096,7 +4818,7 @@ mod tests {
         assert_eq!(
             cairo_runner.initialize_builtins(&mut vm),
             Err(RunnerError::NoBuiltinForInstance(Box::new((
-                HashSet::from([BuiltinName::output.name()]),
+                BTreeSet::from([BuiltinName::output.name().to_string()]),
                 String::from("plain")
             ))))
         );
@@ -4111,7 +4833,10 @@ mod tests {
         assert_eq!(
             cairo_runner.initialize_builtins(&mut vm),
             Err(RunnerError::NoBuiltinForInstance(Box::new((
-                HashSet::from([BuiltinName::output.name(), HASH_BUILTIN_NAME]),
+                BTreeSet::from([
+                    BuiltinName::output.name().to_string(),
+                    HASH_BUILTIN_NAME.to_string()
+                ]),
                 String::from("plain")
             ))))
         );
@@ -4126,11 +4851,30 @@ mod tests {
         assert_eq!(
             cairo_runner.initialize_builtins(&mut vm),
             Err(RunnerError::NoBuiltinForInstance(Box::new((
-                HashSet::from([BuiltinName::bitwise.name()]),
+                BTreeSet::from([BuiltinName::bitwise.name().to_string()]),
                 String::from("small")
             ))))
         );
     }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn initialize_builtins_unknown_builtin() {
+        let program = program![
+            BuiltinName::output,
+            BuiltinName::from("gas_builtin"),
+            BuiltinName::pedersen
+        ];
+        let mut vm = vm!();
+        let cairo_runner = cairo_runner!(program, "all_cairo");
+        assert_eq!(
+            cairo_runner.initialize_builtins(&mut vm),
+            Err(RunnerError::NoBuiltinForInstance(Box::new((
+                BTreeSet::from(["gas_builtin".to_string()]),
+                String::from("all_cairo")
+            ))))
+        );
+    }
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn initialize_main_entrypoint_proof_mode_empty_program() {
@@ -4289,7 +5033,7 @@ mod tests {
     fn read_return_values_test() {
         let mut program = program!();
         Arc::get_mut(&mut program.shared_program_data).unwrap().data =
-            vec_data![(1), (2), (3), (4), (5), (6), (7), (8)];
+            ProgramData::try_from(vec_data![(1), (2), (3), (4), (5), (6), (7), (8)]).unwrap();
         //Program data len = 8
         let mut cairo_runner = cairo_runner!(program, "plain", true);
         cairo_runner.program_base = Some(Relocatable::from((0, 0)));
@@ -4313,7 +5057,7 @@ mod tests {
     fn read_return_values_test_with_run_not_ended() {
         let mut program = program!();
         Arc::get_mut(&mut program.shared_program_data).unwrap().data =
-            vec_data![(1), (2), (3), (4), (5), (6), (7), (8)];
+            ProgramData::try_from(vec_data![(1), (2), (3), (4), (5), (6), (7), (8)]).unwrap();
         //Program data len = 8
         let mut cairo_runner = cairo_runner!(program, "plain", true);
         cairo_runner.program_base = Some(Relocatable::from((0, 0)));
@@ -4331,7 +5075,7 @@ mod tests {
     fn read_return_values_test_with_segments_finalized() {
         let mut program = program!();
         Arc::get_mut(&mut program.shared_program_data).unwrap().data =
-            vec_data![(1), (2), (3), (4), (5), (6), (7), (8)];
+            ProgramData::try_from(vec_data![(1), (2), (3), (4), (5), (6), (7), (8)]).unwrap();
         //Program data len = 8
         let mut cairo_runner = cairo_runner!(program, "plain", true);
         cairo_runner.program_base = Some(Relocatable::from((0, 0)));
@@ -4350,7 +5094,7 @@ mod tests {
     fn read_return_values_updates_builtin_stop_ptr_one_builtin_empty() {
         let mut program = program![BuiltinName::output];
         Arc::get_mut(&mut program.shared_program_data).unwrap().data =
-            vec_data![(1), (2), (3), (4), (5), (6), (7), (8)];
+            ProgramData::try_from(vec_data![(1), (2), (3), (4), (5), (6), (7), (8)]).unwrap();
         //Program data len = 8
         let mut cairo_runner = cairo_runner!(program, "all_cairo", true);
         cairo_runner.program_base = Some(Relocatable::from((0, 0)));
@@ -4381,7 +5125,7 @@ mod tests {
     fn read_return_values_updates_builtin_stop_ptr_one_builtin_one_element() {
         let mut program = program![BuiltinName::output];
         Arc::get_mut(&mut program.shared_program_data).unwrap().data =
-            vec_data![(1), (2), (3), (4), (5), (6), (7), (8)];
+            ProgramData::try_from(vec_data![(1), (2), (3), (4), (5), (6), (7), (8)]).unwrap();
         //Program data len = 8
         let mut cairo_runner = cairo_runner!(program, "all_cairo", true);
         cairo_runner.program_base = Some(Relocatable::from((0, 0)));
@@ -4407,12 +5151,60 @@ mod tests {
         assert_eq!(output_builtin.stop_ptr, Some(1))
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn read_return_values_tolerates_stop_pointer_mismatch() {
+        let program = program![BuiltinName::output];
+        let run_with_stop_pointer = |proof_mode, tolerate| {
+            let mut cairo_runner = cairo_runner!(program.clone(), "all_cairo", proof_mode);
+            cairo_runner.set_tolerate_stop_pointer_mismatches(tolerate);
+            cairo_runner.execution_base = Some(Relocatable::from((1, 0)));
+            cairo_runner.execution_public_memory = Some(Vec::new());
+            cairo_runner.run_ended = true;
+            let mut vm = vm!();
+            vm.builtin_runners
+                .push(OutputBuiltinRunner::new(true).into());
+            // The output segment has a single cell used, but the program returns 0:3
+            vm.segments.memory.data = vec![
+                vec![Some(MemoryCell::new(MaybeRelocatable::from((0, 0))))],
+                vec![Some(MemoryCell::new(MaybeRelocatable::from((0, 3))))],
+            ];
+            vm.set_ap(1);
+            vm.segments.segment_used_sizes = Some(vec![1, 1]);
+            let result = cairo_runner.read_return_values(&mut vm);
+            (result, cairo_runner, vm)
+        };
+
+        let (result, cairo_runner, vm) = run_with_stop_pointer(false, true);
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            cairo_runner.get_stop_pointer_warnings(),
+            [StopPointerWarning {
+                builtin: OUTPUT_BUILTIN_NAME,
+                expected: Relocatable::from((0, 1)),
+                found: Relocatable::from((0, 3)),
+            }]
+        );
+        assert_eq!(
+            cairo_runner.get_stop_pointer_warnings()[0].to_string(),
+            "Invalid stop pointer for output_builtin. Expected: 0:1, found: 0:3"
+        );
+        assert_matches!(&vm.builtin_runners[0], BuiltinRunner::Output(runner) if runner.stop_ptr == Some(3));
+
+        let (result, cairo_runner, _) = run_with_stop_pointer(false, false);
+        assert_matches!(result, Err(RunnerError::InvalidStopPointer(_)));
+        assert!(cairo_runner.get_stop_pointer_warnings().is_empty());
+
+        let (result, _, _) = run_with_stop_pointer(true, true);
+        assert_matches!(result, Err(RunnerError::InvalidStopPointer(_)));
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn read_return_values_updates_builtin_stop_ptr_two_builtins() {
         let mut program = program![BuiltinName::output, BuiltinName::bitwise];
         Arc::get_mut(&mut program.shared_program_data).unwrap().data =
-            vec_data![(1), (2), (3), (4), (5), (6), (7), (8)];
+            ProgramData::try_from(vec_data![(1), (2), (3), (4), (5), (6), (7), (8)]).unwrap();
         //Program data len = 8
         let mut cairo_runner = cairo_runner!(program, "all_cairo", true);
         cairo_runner.program_base = Some(Relocatable::from((0, 0)));
@@ -4451,6 +5243,78 @@ mod tests {
         assert_eq!(bitwise_builtin.stop_ptr, Some(5));
     }
 
+    fn unordered_builtins_program() -> Program {
+        // func main{range_check_ptr, output_ptr}() { return (); }
+        let mut program = program!(
+            builtins = vec![BuiltinName::range_check, BuiltinName::output],
+            main = Some(0),
+        );
+        Arc::get_mut(&mut program.shared_program_data).unwrap().data =
+            ProgramData::try_from(vec_data![
+                (0x480a7ffc7fff8000_i64),
+                (0x480a7ffd7fff8000_i64),
+                (0x208b7fff7fff7ffe_i64)
+            ])
+            .unwrap();
+        program
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn initialize_unordered_builtins_not_allowed() {
+        let program = unordered_builtins_program();
+        let cairo_runner = cairo_runner!(program, "all_cairo");
+        let mut vm = vm!();
+        assert_eq!(
+            cairo_runner.initialize_builtins(&mut vm),
+            Err(RunnerError::DisorderedBuiltins)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_unordered_builtins() {
+        let program = unordered_builtins_program();
+        let mut cairo_runner = cairo_runner!(program, "all_cairo");
+        cairo_runner.set_allow_unordered_builtins(true);
+        let mut vm = vm!();
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        let end = cairo_runner.initialize(&mut vm).unwrap();
+
+        // The runners follow the layout, the initial stack follows the program
+        assert_matches!(
+            vm.builtin_runners.as_slice(),
+            [BuiltinRunner::Output(_), BuiltinRunner::RangeCheck(_), ..]
+        );
+        let range_check_base = vm.builtin_runners[1].base() as isize;
+        let output_base = vm.builtin_runners[0].base() as isize;
+        assert_eq!(
+            vm.segments
+                .memory
+                .get_relocatable((cairo_runner.execution_base.unwrap().segment_index, 0).into()),
+            Ok((range_check_base, 0).into())
+        );
+        assert_eq!(
+            vm.segments
+                .memory
+                .get_relocatable((cairo_runner.execution_base.unwrap().segment_index, 1).into()),
+            Ok((output_base, 0).into())
+        );
+
+        cairo_runner
+            .run_until_pc(end, &mut vm, &mut hint_processor)
+            .unwrap();
+        cairo_runner
+            .end_run(false, false, &mut vm, &mut hint_processor)
+            .unwrap();
+        assert_eq!(cairo_runner.read_return_values(&mut vm), Ok(()));
+        let ap = vm.get_ap();
+        assert_eq!(
+            cairo_runner.get_builtins_final_stack(&mut vm, ap),
+            Ok((ap - 2).unwrap())
+        );
+    }
+
     /// Test that add_additional_hash_builtin() creates an additional builtin.
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
@@ -4546,6 +5410,115 @@ mod tests {
         );
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_from_entrypoint_whitelist() {
+        let program = Program::from_bytes(
+            include_bytes!("../../../../cairo_programs/example_program.json"),
+            None,
+        )
+        .unwrap();
+        let main_entrypoint = program.get_identifier("__main__.main").unwrap().pc.unwrap();
+        let fib_entrypoint = program
+            .get_identifier("__main__.evaluate_fib")
+            .unwrap()
+            .pc
+            .unwrap();
+        let mut cairo_runner = cairo_runner!(program);
+        cairo_runner.set_whitelist(Some(
+            RunnerWhitelist::new()
+                .allow_function("main")
+                .allow_builtin(BuiltinName::range_check),
+        ));
+        let mut vm = vm!(true);
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+
+        cairo_runner.initialize_builtins(&mut vm).unwrap();
+        cairo_runner.initialize_segments(&mut vm, None);
+        let args: &[&CairoArg] = &[
+            &mayberelocatable!(2).into(),
+            &MaybeRelocatable::from((2, 0)).into(),
+        ];
+        assert_matches!(
+            cairo_runner.run_from_entrypoint(
+                fib_entrypoint,
+                args,
+                true,
+                None,
+                &mut vm,
+                &mut hint_processor,
+            ),
+            Err(CairoRunError::Runner(RunnerError::EntrypointNotWhitelisted(pc))) if pc == fib_entrypoint
+        );
+        assert_matches!(
+            cairo_runner.run_from_entrypoint(
+                main_entrypoint,
+                args,
+                true,
+                None,
+                &mut vm,
+                &mut hint_processor,
+            ),
+            Ok(())
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_extracted_function() {
+        let program = Program::from_bytes(
+            include_bytes!("../../../../cairo_programs/example_program.json"),
+            None,
+        )
+        .unwrap()
+        .extract_function("fib")
+        .unwrap();
+        let mut cairo_runner = cairo_runner!(program);
+        let mut vm = vm!();
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+
+        cairo_runner.initialize_builtins(&mut vm).unwrap();
+        cairo_runner.initialize_segments(&mut vm, None);
+        assert_matches!(
+            cairo_runner.run_from_entrypoint(
+                0,
+                &[
+                    &mayberelocatable!(1).into(),
+                    &mayberelocatable!(1).into(),
+                    &mayberelocatable!(10).into()
+                ],
+                true,
+                None,
+                &mut vm,
+                &mut hint_processor,
+            ),
+            Ok(())
+        );
+        assert_eq!(
+            vm.get_return_values(1).unwrap(),
+            vec![mayberelocatable!(144)]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn initialize_builtins_not_whitelisted() {
+        let program = program![BuiltinName::output, BuiltinName::range_check];
+        let mut cairo_runner = cairo_runner!(program);
+        cairo_runner.set_whitelist(Some(
+            RunnerWhitelist::new().allow_builtin(BuiltinName::output),
+        ));
+        let mut vm = vm!();
+        assert_matches!(
+            cairo_runner.initialize_builtins(&mut vm),
+            Err(RunnerError::BuiltinNotWhitelisted(name)) if &*name == "range_check_builtin"
+        );
+        assert_matches!(
+            cairo_runner.initialize_function_runner(&mut vm),
+            Err(RunnerError::BuiltinNotWhitelisted(_))
+        );
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn run_from_entrypoint_bitwise_test_check_memory_holes() {
@@ -4603,7 +5576,7 @@ mod tests {
     }
 
     fn setup_execution_resources() -> (ExecutionResources, ExecutionResources) {
-        let mut builtin_instance_counter: HashMap<String, usize> = HashMap::new();
+        let mut builtin_instance_counter: BTreeMap<String, usize> = BTreeMap::new();
         builtin_instance_counter.insert(BuiltinName::output.name().to_string(), 8);
 
         let execution_resources_1 = ExecutionResources {
@@ -4807,7 +5780,7 @@ mod tests {
         let execution_resources_1 = ExecutionResources {
             n_steps: 800,
             n_memory_holes: 0,
-            builtin_instance_counter: HashMap::from([
+            builtin_instance_counter: BTreeMap::from([
                 ("pedersen_builtin".to_string(), 7),
                 ("range_check_builtin".to_string(), 16),
             ]),
@@ -4818,7 +5791,7 @@ mod tests {
             ExecutionResources {
                 n_steps: 1600,
                 n_memory_holes: 0,
-                builtin_instance_counter: HashMap::from([
+                builtin_instance_counter: BTreeMap::from([
                     ("pedersen_builtin".to_string(), 14),
                     ("range_check_builtin".to_string(), 32)
                 ])
@@ -4828,7 +5801,7 @@ mod tests {
         let execution_resources_2 = ExecutionResources {
             n_steps: 545,
             n_memory_holes: 0,
-            builtin_instance_counter: HashMap::from([("range_check_builtin".to_string(), 17)]),
+            builtin_instance_counter: BTreeMap::from([("range_check_builtin".to_string(), 17)]),
         };
 
         assert_eq!(
@@ -4836,14 +5809,17 @@ mod tests {
             ExecutionResources {
                 n_steps: 4360,
                 n_memory_holes: 0,
-                builtin_instance_counter: HashMap::from([("range_check_builtin".to_string(), 136)])
+                builtin_instance_counter: BTreeMap::from([(
+                    "range_check_builtin".to_string(),
+                    136
+                )])
             }
         );
 
         let execution_resources_3 = ExecutionResources {
             n_steps: 42,
             n_memory_holes: 0,
-            builtin_instance_counter: HashMap::new(),
+            builtin_instance_counter: BTreeMap::new(),
         };
 
         assert_eq!(
@@ -4851,7 +5827,7 @@ mod tests {
             ExecutionResources {
                 n_steps: 756,
                 n_memory_holes: 0,
-                builtin_instance_counter: HashMap::new()
+                builtin_instance_counter: BTreeMap::new()
             }
         );
     }
@@ -4947,4 +5923,216 @@ mod tests {
         );
         assert_eq!(hint_processor.run_resources(), &RunResources::new(0));
     }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn get_builtin_usage_by_function() {
+        let program = Program::from_bytes(
+            include_bytes!("../../../../cairo_programs/pedersen_test.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let mut cairo_runner = cairo_runner!(program);
+        let mut vm = vm!(true);
+        let end = cairo_runner.initialize(&mut vm).unwrap();
+        cairo_runner
+            .run_until_pc(end, &mut vm, &mut BuiltinHintProcessor::new_empty())
+            .unwrap();
+
+        let usage = cairo_runner.get_builtin_usage_by_function(&vm).unwrap();
+        assert_eq!(
+            usage.iter().cloned().collect::<Vec<_>>(),
+            vec![
+                FunctionBuiltinUsage {
+                    function: String::from("__main__.main"),
+                    builtin: OUTPUT_BUILTIN_NAME,
+                    instances: 1,
+                },
+                FunctionBuiltinUsage {
+                    function: String::from("starkware.cairo.common.hash.hash2"),
+                    builtin: HASH_BUILTIN_NAME,
+                    instances: 1,
+                },
+            ]
+        );
+        assert_eq!(
+            usage.to_string(),
+            concat!(
+                "function                           builtin           instances\n",
+                "__main__.main                      output_builtin    1\n",
+                "starkware.cairo.common.hash.hash2  pedersen_builtin  1\n",
+            )
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn get_builtin_usage_by_function_no_trace() {
+        let program = program!();
+        let cairo_runner = cairo_runner!(program);
+        let vm = vm!();
+        assert_matches!(
+            cairo_runner.get_builtin_usage_by_function(&vm),
+            Err(VirtualMachineError::TracerError(
+                TraceError::TraceNotEnabled
+            ))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn load_constant_segment() {
+        let program = program!();
+        let mut cairo_runner = cairo_runner!(program);
+        let mut vm = vm!();
+        cairo_runner.initialize_segments(&mut vm, None);
+
+        let data = [Felt252::new(3), Felt252::new(5), Felt252::new(7)];
+        let base = cairo_runner.load_constant_segment(&mut vm, &data).unwrap();
+
+        assert_eq!(base, Relocatable::from((2, 0)));
+        check_memory![vm.segments.memory, ((2, 0), 3), ((2, 1), 5), ((2, 2), 7)];
+        assert_eq!(vm.segments.get_segment_size(2), Some(3));
+        assert_eq!(cairo_runner.constant_segments, vec![(2, 3)]);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn load_constant_segment_before_initialize_segments() {
+        let program = program!();
+        let mut cairo_runner = cairo_runner!(program);
+        let mut vm = vm!();
+
+        assert_matches!(
+            cairo_runner.load_constant_segment(&mut vm, &[Felt252::new(3)]),
+            Err(RunnerError::NoProgBase)
+        );
+        assert_eq!(vm.segments.num_segments(), 0);
+        assert!(cairo_runner.constant_segments.is_empty());
+    }
+
+    // A processor running the hints of the program with their registered implementations, which
+    // runs them in reverse order and interleaves one of its own before each of them
+    struct ReversingHintProcessor(BuiltinHintProcessor);
+
+    impl HintProcessorLogic for ReversingHintProcessor {
+        fn execute_hint(
+            &mut self,
+            vm: &mut VirtualMachine,
+            exec_scopes: &mut ExecutionScopes,
+            hint_data: &Box<dyn Any>,
+            constants: &Constants,
+        ) -> Result<(), HintError> {
+            self.0.execute_hint(vm, exec_scopes, hint_data, constants)
+        }
+
+        fn arrange_hints(&self, _pc: usize, hints: Vec<Box<dyn Any>>) -> Vec<Box<dyn Any>> {
+            let mut arranged: Vec<Box<dyn Any>> = Vec::new();
+            for hint in hints.into_iter().rev() {
+                arranged.push(any_box!(HintProcessorData::new_default(
+                    String::from("custom"),
+                    HashMap::new()
+                )));
+                arranged.push(hint);
+            }
+            arranged
+        }
+    }
+
+    impl ResourceTracker for ReversingHintProcessor {
+        fn consumed(&self) -> bool {
+            self.0.consumed()
+        }
+
+        fn consume_step(&mut self) {
+            self.0.consume_step()
+        }
+
+        fn get_n_steps(&self) -> Option<usize> {
+            self.0.get_n_steps()
+        }
+
+        fn run_resources(&self) -> &RunResources {
+            self.0.run_resources()
+        }
+    }
+
+    // A program with three hints at pc 0, and a processor implementing them and a custom hint by
+    // recording their code in the "order" list of the scope
+    fn program_with_ordered_hints() -> (Program, BuiltinHintProcessor) {
+        let hint_params = |code: &str| HintParams {
+            code: code.to_string(),
+            accessible_scopes: Vec::new(),
+            flow_tracking_data: FlowTrackingData {
+                ap_tracking: ApTracking::default(),
+                reference_ids: HashMap::new(),
+            },
+        };
+        let program = program!(
+            hints = HashMap::from([(
+                0,
+                vec![
+                    hint_params("first"),
+                    hint_params("second"),
+                    hint_params("third")
+                ]
+            )]),
+        );
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        for code in ["first", "second", "third", "custom"] {
+            let record = HintFunc(Box::new(move |_, exec_scopes, _, _, _| {
+                exec_scopes
+                    .get_mut_list_ref::<String>("order")?
+                    .push(String::from(code));
+                Ok(())
+            }));
+            hint_processor.add_hint(String::from(code), Rc::new(record));
+        }
+        (program, hint_processor)
+    }
+
+    // Runs the hints at pc 0, returning the order they ran in
+    fn run_hints_at_pc_0(
+        cairo_runner: &CairoRunner,
+        hint_processor: &mut dyn HintProcessor,
+    ) -> Vec<String> {
+        let mut vm = vm!();
+        vm.set_pc(Relocatable::from((0, 0)));
+        let mut exec_scopes = ExecutionScopes::new();
+        exec_scopes.insert_value("order", Vec::<String>::new());
+        let hint_data_dictionary = cairo_runner
+            .get_hint_data_dictionary(&[], hint_processor)
+            .unwrap();
+        vm.step_hint(
+            hint_processor,
+            &mut exec_scopes,
+            &hint_data_dictionary,
+            &Constants::new(),
+        )
+        .unwrap();
+        exec_scopes.get_list::<String>("order").unwrap()
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn hints_at_same_pc_run_in_declared_order() {
+        let (program, mut hint_processor) = program_with_ordered_hints();
+        let cairo_runner = cairo_runner!(program);
+        assert_eq!(
+            run_hints_at_pc_0(&cairo_runner, &mut hint_processor),
+            ["first", "second", "third"]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn hint_processor_arranges_hints_at_same_pc() {
+        let (program, hint_processor) = program_with_ordered_hints();
+        let cairo_runner = cairo_runner!(program);
+        let mut hint_processor = ReversingHintProcessor(hint_processor);
+        assert_eq!(
+            run_hints_at_pc_0(&cairo_runner, &mut hint_processor),
+            ["custom", "third", "custom", "second", "custom", "first"]
+        );
+    }
 }
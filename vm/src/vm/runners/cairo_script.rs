@@ -0,0 +1,233 @@
+//! Small programs assembled at runtime from CASM instructions, with Rust closures as hints.
+//!
+//! A [CairoScript] is built by appending instructions, usually written with the `casm!` macro of
+//! `cairo-lang-casm`, and closures, each run as a hint before the instruction that follows it. It
+//! is run as a function taking the pointers of its builtins as arguments, so it has to end with
+//! `ret`. This is meant for testing the behavior of builtins and hints, and for running code
+//! generated at runtime without compiling it.
+
+use crate::stdlib::{collections::HashMap, prelude::*, rc::Rc};
+
+use cairo_lang_casm::instructions::Instruction;
+use felt::Felt252;
+
+use crate::{
+    hint_processor::builtin_hint_processor::builtin_hint_processor_definition::{
+        BuiltinHintProcessor, HintFunc,
+    },
+    serde::deserialize_program::{
+        ApTracking, BuiltinName, FlowTrackingData, HintParams, ReferenceManager,
+    },
+    types::{
+        errors::program_errors::ProgramError, exec_scope::ExecutionScopes, program::Program,
+        relocatable::MaybeRelocatable,
+    },
+    vm::{
+        errors::{
+            cairo_run_errors::CairoRunError, hint_errors::HintError, memory_errors::MemoryError,
+        },
+        runners::cairo_runner::{CairoArg, CairoRunner},
+        vm_core::VirtualMachine,
+    },
+};
+
+/// A program assembled from CASM instructions and Rust closures as hints.
+#[derive(Default)]
+pub struct CairoScript {
+    data: Vec<MaybeRelocatable>,
+    hints: HashMap<usize, Vec<HintParams>>,
+    hint_funcs: Vec<(String, Rc<HintFunc>)>,
+    builtins: Vec<BuiltinName>,
+}
+
+/// A finished run of a [CairoScript].
+pub struct ScriptRun {
+    pub runner: CairoRunner,
+    pub vm: VirtualMachine,
+}
+
+impl ScriptRun {
+    /// Returns the last `n` values returned by the script.
+    pub fn return_values(&self, n: usize) -> Result<Vec<MaybeRelocatable>, MemoryError> {
+        self.vm.get_return_values(n)
+    }
+}
+
+impl CairoScript {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends `instructions` to the script. The Cairo 1 hints of the instructions aren't run,
+    /// hints are added with [hint](Self::hint) instead.
+    pub fn instructions(mut self, instructions: impl IntoIterator<Item = Instruction>) -> Self {
+        for instruction in instructions {
+            self.data.extend(
+                instruction
+                    .assemble()
+                    .encode()
+                    .into_iter()
+                    .map(|value| MaybeRelocatable::from(Felt252::from(value))),
+            );
+        }
+        self
+    }
+
+    /// Adds `hint`, run before the next instruction appended to the script. The hints added
+    /// between two instructions are run in order.
+    pub fn hint<F>(mut self, hint: F) -> Self
+    where
+        F: Fn(&mut VirtualMachine, &mut ExecutionScopes) -> Result<(), HintError> + Sync + 'static,
+    {
+        let code = format!("__cairo_script_hint_{}", self.hint_funcs.len());
+        self.hints
+            .entry(self.data.len())
+            .or_default()
+            .push(HintParams {
+                code: code.clone(),
+                accessible_scopes: Vec::new(),
+                flow_tracking_data: FlowTrackingData {
+                    ap_tracking: ApTracking::default(),
+                    reference_ids: HashMap::new(),
+                },
+            });
+        let hint_func = HintFunc(Box::new(move |vm, exec_scopes, _, _, _| {
+            hint(vm, exec_scopes)
+        }));
+        self.hint_funcs.push((code, Rc::new(hint_func)));
+        self
+    }
+
+    /// Sets the builtins of the script, whose pointers are passed as its arguments in this order.
+    pub fn builtins(mut self, builtins: &[BuiltinName]) -> Self {
+        self.builtins = builtins.to_vec();
+        self
+    }
+
+    /// Returns the program of the script, starting at its first instruction.
+    pub fn program(&self) -> Result<Program, ProgramError> {
+        Program::new(
+            self.builtins.clone(),
+            self.data.clone(),
+            Some(0),
+            self.hints.clone(),
+            ReferenceManager {
+                references: Vec::new(),
+            },
+            HashMap::new(),
+            Vec::new(),
+            None,
+        )
+    }
+
+    /// Returns a hint processor running the hints of the script.
+    pub fn hint_processor(&self) -> BuiltinHintProcessor {
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        for (code, hint_func) in self.hint_funcs.iter() {
+            hint_processor.add_hint(code.clone(), Rc::clone(hint_func));
+        }
+        hint_processor
+    }
+
+    /// Runs the script on the `all_cairo` layout, checking the run is secure.
+    pub fn run(&self) -> Result<ScriptRun, CairoRunError> {
+        let program = self.program()?;
+        let mut runner = CairoRunner::new(&program, "all_cairo", false)?;
+        let mut vm = VirtualMachine::new(false);
+        runner.initialize_function_runner_cairo_1(&mut vm, &self.builtins)?;
+        // The builtins of the script are initialized first, in its order
+        let args: Vec<CairoArg> = vm
+            .get_builtin_runners()
+            .iter()
+            .take(self.builtins.len())
+            .flat_map(|builtin| builtin.initial_stack())
+            .map(CairoArg::from)
+            .collect();
+        runner.run_from_entrypoint(
+            0,
+            &args.iter().collect::<Vec<_>>(),
+            true,
+            Some(self.data.len()),
+            &mut vm,
+            &mut self.hint_processor(),
+        )?;
+        Ok(ScriptRun { runner, vm })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{types::relocatable::Relocatable, vm::errors::vm_errors::VirtualMachineError};
+    use assert_matches::assert_matches;
+    use cairo_lang_casm::casm;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_script_with_hint() {
+        let script = CairoScript::new()
+            .instructions(casm! { [ap + 0] = 3, ap++; }.instructions)
+            .hint(|vm, _| {
+                vm.insert_value(vm.get_ap(), Felt252::new(4))
+                    .map_err(HintError::Memory)
+            })
+            .instructions(
+                casm! {
+                    [ap + 1] = [ap + -1] * [ap + 0];
+                    ap += 2;
+                    ret;
+                }
+                .instructions,
+            );
+        // The first instruction takes two cells, with its immediate
+        assert_eq!(script.program().unwrap().hints_at(2).len(), 1);
+
+        let run = script.run().unwrap();
+        assert_eq!(
+            run.return_values(3).unwrap(),
+            [
+                MaybeRelocatable::from(3),
+                MaybeRelocatable::from(4),
+                MaybeRelocatable::from(12)
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_script_with_builtin() {
+        let run = CairoScript::new()
+            .builtins(&[BuiltinName::range_check])
+            .instructions(
+                casm! {
+                    [ap + 0] = [fp + -3], ap++;
+                    ret;
+                }
+                .instructions,
+            )
+            .run()
+            .unwrap();
+        let range_check_base = run.vm.get_range_check_builtin().unwrap().base();
+        assert_eq!(
+            run.return_values(1).unwrap(),
+            [MaybeRelocatable::from(Relocatable::from((
+                range_check_base as isize,
+                0
+            )))]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_script_failing_hint() {
+        let result = CairoScript::new()
+            .hint(|_, _| Err(HintError::CustomHint("the hint failed".into())))
+            .instructions(casm! { ret; }.instructions)
+            .run();
+        assert_matches!(result, Err(CairoRunError::VmException(exception))
+            if matches!(exception.inner_exc, VirtualMachineError::Hint(ref bx) if bx.0 == 0));
+    }
+}
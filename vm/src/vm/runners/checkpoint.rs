@@ -0,0 +1,425 @@
+//! Checkpoints of a run in progress, to resume it later in another process.
+//!
+//! A [RunCheckpoint] holds the state of the run that can't be rebuilt from the program: the
+//! memory, including the temporary segments and the accessed cells, the registers, the step count,
+//! the trace, the signatures added to the ecdsa builtin and the execution scopes. Restoring it
+//! initializes a new runner for the same program, layout and proof mode, and then replaces its
+//! state with the one of the checkpoint.
+//!
+//! Only runs initialized with [CairoRunner::initialize] can be restored. The state of the hint
+//! processor isn't part of the checkpoint, and scope variables are serialized through a
+//! [ScopeSerdeRegistry], so checkpointing fails if one of them has a type it doesn't know.
+
+use crate::stdlib::prelude::*;
+
+use felt::Felt252;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "std")]
+use crate::{
+    hint_processor::hint_processor_definition::HintProcessor,
+    vm::errors::cairo_run_errors::CairoRunError,
+};
+use crate::{
+    types::{
+        exec_scope::ExecutionScopes,
+        program::Program,
+        relocatable::{MaybeRelocatable, Relocatable},
+        scope_serde::{ScopeSerdeRegistry, SerializedExecutionScopes},
+    },
+    vm::{
+        errors::{checkpoint_errors::CheckpointError, runner_errors::RunnerError},
+        runners::{builtin_runner::BuiltinRunner, cairo_runner::CairoRunner},
+        trace::trace_entry::TraceEntry,
+        vm_core::VirtualMachine,
+        vm_memory::memory::MemoryCell,
+    },
+};
+
+/// The cells of a segment, with the value and whether it was accessed for each written cell.
+pub type SerializedSegment = Vec<Option<(MaybeRelocatable, bool)>>;
+
+/// The state of a run in progress, see [CairoRunner::get_checkpoint].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunCheckpoint {
+    pub layout: String,
+    pub proof_mode: bool,
+    pub initial_pc: Relocatable,
+    pub initial_ap: Relocatable,
+    pub pc: Relocatable,
+    pub ap: usize,
+    pub fp: usize,
+    pub current_step: usize,
+    pub trace: Option<Vec<TraceEntry>>,
+    pub rc_limits: Option<(isize, isize)>,
+    pub segments: Vec<SerializedSegment>,
+    pub temporary_segments: Vec<SerializedSegment>,
+    /// Relocation rules of the temporary segments, by temporary segment key, sorted by key
+    pub relocation_rules: Vec<(usize, Relocatable)>,
+    /// Sizes of the finalized segments, sorted by segment index
+    pub segment_sizes: Vec<(usize, usize)>,
+    /// Public memory offsets of the segments, sorted by segment index
+    pub public_memory_offsets: Vec<(usize, Vec<(usize, usize)>)>,
+    pub constant_segments: Vec<(usize, usize)>,
+    /// Signatures added to the ecdsa builtin, by instance address
+    pub signatures: Vec<(Relocatable, (Felt252, Felt252))>,
+    pub exec_scopes: SerializedExecutionScopes,
+}
+
+/// A run restored from a [RunCheckpoint], ready to continue.
+pub struct RestoredRun {
+    pub runner: CairoRunner,
+    pub vm: VirtualMachine,
+    /// The pc at which the run ends, as returned by [CairoRunner::initialize]
+    pub end: Relocatable,
+}
+
+fn serialize_segments(segments: &[Vec<Option<MemoryCell>>]) -> Vec<SerializedSegment> {
+    segments
+        .iter()
+        .map(|segment| {
+            segment
+                .iter()
+                .map(|cell| {
+                    cell.as_ref()
+                        .map(|cell| (cell.get_value().clone(), cell.is_accessed()))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn deserialize_segments(segments: Vec<SerializedSegment>) -> Vec<Vec<Option<MemoryCell>>> {
+    segments
+        .into_iter()
+        .map(|segment| {
+            segment
+                .into_iter()
+                .map(|cell| {
+                    cell.map(|(value, accessed)| {
+                        let mut cell = MemoryCell::new(value);
+                        if accessed {
+                            cell.mark_accessed();
+                        }
+                        cell
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn sorted<K: Ord, V>(iter: impl Iterator<Item = (K, V)>) -> Vec<(K, V)> {
+    let mut entries: Vec<_> = iter.collect();
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    entries
+}
+
+impl CairoRunner {
+    /// Captures the state of the run, which must have been initialized with
+    /// [initialize](Self::initialize) and not ended yet. Scope variables are serialized with the
+    /// types of `registry`, see [with_common_types](ScopeSerdeRegistry::with_common_types) for the
+    /// ones registered by default, the dict manager of the dict hints included.
+    ///
+    /// The variables kept in the main scope for the host, the [scope
+    /// types](crate::types::scope_types::SCOPE_TYPES), the [host
+    /// functions](crate::hint_processor::host_functions::HOST_FUNCTIONS) and the
+    /// [oracle](crate::hint_processor::builtin_hint_processor::oracle::ORACLE), can't be
+    /// serialized, so runs using them fail to checkpoint with
+    /// [UnregisteredScopeType](crate::vm::errors::exec_scope_errors::ExecScopeError::UnregisteredScopeType).
+    pub fn get_checkpoint(
+        &self,
+        vm: &VirtualMachine,
+        registry: &ScopeSerdeRegistry,
+    ) -> Result<RunCheckpoint, CheckpointError> {
+        if self.run_ended {
+            return Err(CheckpointError::RunEnded);
+        }
+        let memory = &vm.segments.memory;
+        let signatures = vm
+            .builtin_runners
+            .iter()
+            .find_map(|builtin| match builtin {
+                BuiltinRunner::Signature(signature) => Some(signature.get_signatures()),
+                _ => None,
+            })
+            .unwrap_or_default();
+        Ok(RunCheckpoint {
+            layout: self.layout._name.clone(),
+            proof_mode: self.proof_mode,
+            initial_pc: self.initial_pc.ok_or(RunnerError::NoPC)?,
+            initial_ap: self.initial_ap.ok_or(RunnerError::NoAP)?,
+            pc: vm.run_context.pc,
+            ap: vm.run_context.ap,
+            fp: vm.run_context.fp,
+            current_step: vm.current_step,
+            trace: vm.trace.clone(),
+            rc_limits: vm.rc_limits,
+            segments: serialize_segments(&memory.data),
+            temporary_segments: serialize_segments(&memory.temp_data),
+            relocation_rules: sorted(memory.relocation_rules.iter().map(|(k, v)| (*k, *v))),
+            segment_sizes: sorted(vm.segments.segment_sizes.iter().map(|(k, v)| (*k, *v))),
+            public_memory_offsets: sorted(
+                vm.segments
+                    .public_memory_offsets
+                    .iter()
+                    .map(|(k, v)| (*k, v.clone())),
+            ),
+            constant_segments: self.constant_segments.clone(),
+            signatures,
+            exec_scopes: self.exec_scopes.serialize_scopes(registry)?,
+        })
+    }
+
+    /// Initializes a runner for `program` as the run of `checkpoint` was, and restores the state
+    /// of the run from it. Scope variables are deserialized with the types of `registry`.
+    pub fn restore(
+        program: &Program,
+        checkpoint: RunCheckpoint,
+        registry: &ScopeSerdeRegistry,
+    ) -> Result<RestoredRun, CheckpointError> {
+        let mut runner = CairoRunner::new(program, &checkpoint.layout, checkpoint.proof_mode)?;
+        let mut vm = VirtualMachine::new(checkpoint.trace.is_some());
+        let end = runner.initialize(&mut vm)?;
+        if runner.initial_pc != Some(checkpoint.initial_pc)
+            || runner.initial_ap != Some(checkpoint.initial_ap)
+        {
+            return Err(CheckpointError::InitializationMismatch);
+        }
+        // The program segment of the checkpoint must hold the program
+        let program_base = runner.program_base.ok_or(RunnerError::NoProgBase)?;
+        let program_segment = checkpoint
+            .segments
+            .get(program_base.segment_index as usize)
+            .map(|segment| segment.get(program_base.offset..).unwrap_or_default())
+            .unwrap_or_default();
+        let program_data = &program.shared_program_data.data;
+        if program_segment.len() < program_data.len()
+            || program_data
+                .iter()
+                .zip(program_segment)
+                .any(|(value, cell)| cell.as_ref().map(|(v, _)| v) != Some(value))
+        {
+            return Err(CheckpointError::ProgramMismatch);
+        }
+
+        let memory = &mut vm.segments.memory;
+        memory.data = deserialize_segments(checkpoint.segments);
+        memory.temp_data = deserialize_segments(checkpoint.temporary_segments);
+        memory.relocation_rules = checkpoint.relocation_rules.into_iter().collect();
+        vm.segments.segment_sizes = checkpoint.segment_sizes.into_iter().collect();
+        vm.segments.public_memory_offsets = checkpoint.public_memory_offsets.into_iter().collect();
+        if !checkpoint.signatures.is_empty() {
+            let signature_builtin = vm
+                .get_signature_builtin()
+                .map_err(|_| CheckpointError::InitializationMismatch)?;
+            for (address, signature) in checkpoint.signatures.iter() {
+                signature_builtin.add_signature(*address, signature)?;
+            }
+        }
+        // The cells of the builtin segments are validated again with the restored signatures
+        vm.segments
+            .memory
+            .validate_existing_memory()
+            .map_err(RunnerError::MemoryValidationError)?;
+
+        vm.run_context.pc = checkpoint.pc;
+        vm.run_context.ap = checkpoint.ap;
+        vm.run_context.fp = checkpoint.fp;
+        vm.current_step = checkpoint.current_step;
+        vm.trace = checkpoint.trace;
+        vm.rc_limits = checkpoint.rc_limits;
+        runner.constant_segments = checkpoint.constant_segments;
+        runner.exec_scopes = ExecutionScopes::deserialize_scopes(checkpoint.exec_scopes, registry)?;
+        Ok(RestoredRun { runner, vm, end })
+    }
+
+    /// Writes a [checkpoint](Self::get_checkpoint) of the run as JSON to `writer`.
+    #[cfg(feature = "std")]
+    pub fn checkpoint(
+        &self,
+        vm: &VirtualMachine,
+        registry: &ScopeSerdeRegistry,
+        writer: impl std::io::Write,
+    ) -> Result<(), CheckpointError> {
+        let checkpoint = self.get_checkpoint(vm, registry)?;
+        serde_json::to_writer(writer, &checkpoint)
+            .map_err(|error| CheckpointError::Serde(error.to_string().into_boxed_str()))
+    }
+
+    /// Restores the run of the checkpoint written by [checkpoint](Self::checkpoint) to `reader`,
+    /// and runs it until its end with `hint_processor`. The returned run can then be ended and
+    /// relocated like any other.
+    #[cfg(feature = "std")]
+    pub fn resume(
+        program: &Program,
+        reader: impl std::io::Read,
+        registry: &ScopeSerdeRegistry,
+        hint_processor: &mut dyn HintProcessor,
+    ) -> Result<RestoredRun, Box<CairoRunError>> {
+        let checkpoint: RunCheckpoint = serde_json::from_reader(reader)
+            .map_err(|error| CheckpointError::Serde(error.to_string().into_boxed_str()))
+            .map_err(CairoRunError::from)?;
+        let mut run = Self::restore(program, checkpoint, registry).map_err(CairoRunError::from)?;
+        run.runner
+            .run_until_pc(run.end, &mut run.vm, hint_processor)
+            .map_err(CairoRunError::from)?;
+        Ok(run)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+        stdlib::any::Any,
+    };
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    fn fibonacci() -> Program {
+        Program::from_bytes(
+            include_bytes!("../../../../cairo_programs/fibonacci.json"),
+            Some("main"),
+        )
+        .unwrap()
+    }
+
+    fn start_run(program: &Program, steps: usize) -> (CairoRunner, VirtualMachine, Relocatable) {
+        let mut runner = CairoRunner::new(program, "all_cairo", false).unwrap();
+        let mut vm = VirtualMachine::new(true);
+        let end = runner.initialize(&mut vm).unwrap();
+        runner
+            .run_for_steps(steps, &mut vm, &mut BuiltinHintProcessor::new_empty())
+            .unwrap();
+        (runner, vm, end)
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn restore_and_finish_run() {
+        let program = fibonacci();
+        let registry = ScopeSerdeRegistry::with_common_types();
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+
+        let (mut runner, mut vm, end) = start_run(&program, 0);
+        runner
+            .run_until_pc(end, &mut vm, &mut hint_processor)
+            .unwrap();
+
+        let (mut interrupted_runner, interrupted_vm, _) = start_run(&program, 30);
+        interrupted_runner.exec_scopes.insert_value("n", 3_u64);
+        let checkpoint = interrupted_runner
+            .get_checkpoint(&interrupted_vm, &registry)
+            .unwrap();
+        assert_eq!(checkpoint.current_step, 30);
+
+        let mut restored = CairoRunner::restore(&program, checkpoint, &registry).unwrap();
+        assert_eq!(restored.end, end);
+        assert_matches!(restored.runner.exec_scopes.get::<u64>("n"), Ok(3));
+        restored
+            .runner
+            .run_until_pc(restored.end, &mut restored.vm, &mut hint_processor)
+            .unwrap();
+
+        assert_eq!(restored.vm.current_step, vm.current_step);
+        assert_eq!(restored.vm.trace, vm.trace);
+        assert_eq!(restored.vm.segments.memory.data, vm.segments.memory.data);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn checkpoint_through_writer() {
+        let program = fibonacci();
+        let registry = ScopeSerdeRegistry::new();
+        let (runner, vm, _) = start_run(&program, 20);
+        let mut bytes = Vec::new();
+        runner.checkpoint(&vm, &registry, &mut bytes).unwrap();
+
+        let run = CairoRunner::resume(
+            &program,
+            bytes.as_slice(),
+            &registry,
+            &mut BuiltinHintProcessor::new_empty(),
+        )
+        .unwrap();
+        assert_eq!(run.vm.run_context.pc, run.end);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn restore_run_using_dicts() {
+        let program = Program::from_bytes(
+            include_bytes!("../../../../cairo_programs/dict.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let registry = ScopeSerdeRegistry::with_common_types();
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+
+        let (mut runner, mut vm, end) = start_run(&program, 0);
+        runner
+            .run_until_pc(end, &mut vm, &mut hint_processor)
+            .unwrap();
+
+        // Interrupt the run once the dictionary was created
+        let (mut interrupted_runner, mut interrupted_vm, _) = start_run(&program, 0);
+        while interrupted_runner.exec_scopes.get_dict_manager().is_err() {
+            interrupted_runner
+                .run_for_steps(1, &mut interrupted_vm, &mut hint_processor)
+                .unwrap();
+        }
+        let checkpoint = interrupted_runner
+            .get_checkpoint(&interrupted_vm, &registry)
+            .unwrap();
+
+        let mut restored = CairoRunner::restore(&program, checkpoint, &registry).unwrap();
+        assert_eq!(
+            restored.runner.exec_scopes.get_dict_manager().unwrap(),
+            interrupted_runner.exec_scopes.get_dict_manager().unwrap()
+        );
+        restored
+            .runner
+            .run_until_pc(restored.end, &mut restored.vm, &mut hint_processor)
+            .unwrap();
+        assert_eq!(restored.vm.trace, vm.trace);
+        assert_eq!(restored.vm.segments.memory.data, vm.segments.memory.data);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn checkpoint_errors() {
+        let program = fibonacci();
+        let (mut runner, vm, _) = start_run(&program, 10);
+        runner
+            .exec_scopes
+            .insert_box("unserializable", Box::new(vec![0_u8]) as Box<dyn Any>);
+        assert_matches!(
+            runner.get_checkpoint(&vm, &ScopeSerdeRegistry::new()),
+            Err(CheckpointError::ExecScope(_))
+        );
+
+        runner.exec_scopes = ExecutionScopes::new();
+        let checkpoint = runner
+            .get_checkpoint(&vm, &ScopeSerdeRegistry::new())
+            .unwrap();
+        let other_program = Program::from_bytes(
+            include_bytes!("../../../../cairo_programs/bitwise_builtin_test.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let Err(error) =
+            CairoRunner::restore(&other_program, checkpoint, &ScopeSerdeRegistry::new())
+        else {
+            panic!("the checkpoint shouldn't restore with another program");
+        };
+        assert_matches!(
+            error,
+            CheckpointError::InitializationMismatch | CheckpointError::ProgramMismatch
+        );
+    }
+}
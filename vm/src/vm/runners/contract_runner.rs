@@ -0,0 +1,259 @@
+//! Execution of Starknet contract entrypoints compiled to CASM.
+//!
+//! Running an entrypoint of a [CasmContractClass] takes more than running a function of its
+//! program: its implicit arguments are the builtins it lists, in its order, followed by the gas
+//! and the syscall pointer, and its code expects a pointer to the builtin costs to be found right
+//! after the end of the program. [ContractRunner] performs this setup, runs the entrypoint and
+//! reads its retdata and remaining gas, and validates the syscall segment left by the run.
+
+use crate::stdlib::prelude::*;
+
+use cairo_lang_starknet::casm_contract_class::{CasmContractClass, CasmContractEntryPoint};
+use felt::Felt252;
+
+use crate::{
+    hint_processor::hint_processor_definition::HintProcessor,
+    serde::deserialize_program::BuiltinName,
+    types::{
+        errors::program_errors::ProgramError, program::Program, relocatable::MaybeRelocatable,
+    },
+    vm::{
+        errors::{cairo_run_errors::CairoRunError, runner_errors::RunnerError},
+        runners::{
+            cairo_1_output::CairoRunOutput,
+            cairo_runner::{CairoArg, CairoRunner},
+            syscall_segment::{SyscallSegmentReport, SyscallSegmentValidator},
+        },
+        vm_core::VirtualMachine,
+    },
+};
+
+/// Number of builtins whose costs are loaded for the gas computations of contracts: pedersen,
+/// bitwise, ec_op, poseidon and segment_arena. Their costs are all 0, gas is only spent on steps.
+const BUILTIN_COSTS_LEN: usize = 5;
+
+/// `ret`, ending the call made by contracts to fetch the builtin costs pointer stored after it.
+const RET_INSTRUCTION: u64 = 0x208B7FFF7FFF7FFE;
+
+/// The result of running a contract entrypoint.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContractRunOutput {
+    /// The retdata of the entrypoint, or its panic data if it failed
+    pub retdata: CairoRunOutput,
+    pub remaining_gas: u128,
+    /// The syscalls made by the entrypoint, and the violations of the structure of their segment
+    pub syscalls: SyscallSegmentReport,
+}
+
+/// Runs the entrypoints of a contract class, each call on a fresh runner and vm.
+pub struct ContractRunner<'a> {
+    contract_class: &'a CasmContractClass,
+    program: Program,
+    syscall_validator: SyscallSegmentValidator,
+}
+
+impl<'a> ContractRunner<'a> {
+    pub fn new(contract_class: &'a CasmContractClass) -> Result<Self, ProgramError> {
+        Ok(ContractRunner {
+            contract_class,
+            program: contract_class.clone().try_into()?,
+            syscall_validator: SyscallSegmentValidator::new(),
+        })
+    }
+
+    /// Sets the validator of the syscall segment, to know the layouts of syscalls other than
+    /// those of Starknet.
+    pub fn with_syscall_validator(mut self, syscall_validator: SyscallSegmentValidator) -> Self {
+        self.syscall_validator = syscall_validator;
+        self
+    }
+
+    /// Runs the entrypoint with the given `selector`, whether external, l1 handler or
+    /// constructor, with `calldata` and `gas`.
+    ///
+    /// The hints of the contract are executed by `hint_processor`, usually a
+    /// [Cairo1HintProcessor](crate::hint_processor::cairo_1_hint_processor::hint_processor::Cairo1HintProcessor)
+    /// built from the hints of the contract class, or a hint processor wrapping one to handle
+    /// syscalls.
+    pub fn run(
+        &self,
+        selector: &Felt252,
+        calldata: &[Felt252],
+        gas: u128,
+        hint_processor: &mut dyn HintProcessor,
+    ) -> Result<ContractRunOutput, CairoRunError> {
+        let entry_point = self.entry_point(selector)?;
+        let builtins = entry_point
+            .builtins
+            .iter()
+            .map(|name| BuiltinName::from_str_strict(name))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut runner = CairoRunner::new(&self.program, "all_cairo", false)?;
+        let mut vm = VirtualMachine::new(false);
+        runner.initialize_function_runner_cairo_1(&mut vm, &builtins)?;
+
+        // Implicit args: the builtins of the entrypoint, in its order, the gas and the syscall
+        // pointer. All builtins are initialized, those of the entrypoint first.
+        let mut args: Vec<CairoArg> = vm
+            .get_builtin_runners()
+            .iter()
+            .take(builtins.len())
+            .flat_map(|builtin| builtin.initial_stack())
+            .map(CairoArg::from)
+            .collect();
+        args.push(MaybeRelocatable::from(Felt252::from(gas)).into());
+        let syscall_ptr = vm.add_memory_segment();
+        args.push(MaybeRelocatable::from(syscall_ptr).into());
+
+        let builtin_costs_ptr = vm.add_memory_segment();
+        vm.load_data(
+            builtin_costs_ptr,
+            &vec![MaybeRelocatable::from(0_usize); BUILTIN_COSTS_LEN],
+        )?;
+        let program_end = (runner.program_base.ok_or(RunnerError::NoProgBase)?
+            + self.program.shared_program_data.data.len())
+        .map_err(RunnerError::from)?;
+        let program_extra_data = vec![
+            MaybeRelocatable::from(Felt252::from(RET_INSTRUCTION)),
+            MaybeRelocatable::from(builtin_costs_ptr),
+        ];
+        vm.load_data(program_end, &program_extra_data)?;
+
+        let calldata: Vec<MaybeRelocatable> = calldata.iter().map(MaybeRelocatable::from).collect();
+        let calldata_start = vm.add_memory_segment();
+        let calldata_end = vm.load_data(calldata_start, &calldata)?;
+        args.push(MaybeRelocatable::from(calldata_start).into());
+        args.push(MaybeRelocatable::from(calldata_end).into());
+
+        runner.run_from_entrypoint(
+            entry_point.offset,
+            &args.iter().collect::<Vec<_>>(),
+            true,
+            Some(self.program.shared_program_data.data.len() + program_extra_data.len()),
+            &mut vm,
+            hint_processor,
+        )?;
+
+        // The stack ends with the gas, the syscall pointer and the retdata
+        let return_values = vm.get_return_values(5)?;
+        let remaining_gas = u128::try_from(&return_values[0]).map_err(RunnerError::from)?;
+        Ok(ContractRunOutput {
+            retdata: CairoRunOutput::from_return_values(&vm)?,
+            remaining_gas,
+            syscalls: self
+                .syscall_validator
+                .validate(&vm, syscall_ptr, &return_values[1]),
+        })
+    }
+
+    fn entry_point(&self, selector: &Felt252) -> Result<&CasmContractEntryPoint, ProgramError> {
+        let entry_points = &self.contract_class.entry_points_by_type;
+        let selector = selector.to_biguint();
+        entry_points
+            .external
+            .iter()
+            .chain(&entry_points.l1_handler)
+            .chain(&entry_points.constructor)
+            .find(|entry_point| entry_point.selector == selector)
+            .ok_or_else(|| ProgramError::EntrypointNotFound(format!("{selector:#x}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hint_processor::cairo_1_hint_processor::hint_processor::Cairo1HintProcessor,
+        vm::runners::cairo_runner::RunResources,
+    };
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    fn fib_contract_class() -> CasmContractClass {
+        serde_json::from_slice(include_bytes!(
+            "../../../../cairo_programs/cairo-1-contracts/fib.casm"
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_by_selector() {
+        let contract_class = fib_contract_class();
+        let selector = Felt252::from(&contract_class.entry_points_by_type.external[0].selector);
+        let mut hint_processor =
+            Cairo1HintProcessor::new(&contract_class.hints, RunResources::default());
+        let runner = ContractRunner::new(&contract_class).unwrap();
+
+        let gas = 1_000_000;
+        let output = runner
+            .run(
+                &selector,
+                &[Felt252::new(3), Felt252::new(3), Felt252::new(3)],
+                gas,
+                &mut hint_processor,
+            )
+            .unwrap();
+        assert_eq!(
+            output.retdata,
+            CairoRunOutput::Return(vec![Felt252::new(9)])
+        );
+        assert!(output.remaining_gas < gas);
+        // The fib contract makes no syscalls
+        assert_eq!(output.syscalls, SyscallSegmentReport::default());
+
+        // Each run starts from a fresh vm
+        let output = runner
+            .run(
+                &selector,
+                &[Felt252::new(1), Felt252::new(1), Felt252::new(1)],
+                gas,
+                &mut hint_processor,
+            )
+            .unwrap();
+        assert_eq!(
+            output.retdata,
+            CairoRunOutput::Return(vec![Felt252::new(1)])
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_out_of_gas() {
+        let contract_class = fib_contract_class();
+        let selector = Felt252::from(&contract_class.entry_points_by_type.external[0].selector);
+        let mut hint_processor =
+            Cairo1HintProcessor::new(&contract_class.hints, RunResources::default());
+        let output = ContractRunner::new(&contract_class)
+            .unwrap()
+            .run(
+                &selector,
+                &[Felt252::new(1), Felt252::new(1), Felt252::new(20)],
+                0,
+                &mut hint_processor,
+            )
+            .unwrap();
+        assert_matches!(output.retdata, CairoRunOutput::Panic(_, message) if message == "Out of gas");
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_unknown_selector() {
+        let contract_class = fib_contract_class();
+        let mut hint_processor =
+            Cairo1HintProcessor::new(&contract_class.hints, RunResources::default());
+        assert_matches!(
+            ContractRunner::new(&contract_class).unwrap().run(
+                &Felt252::new(0x1234),
+                &[],
+                1_000_000,
+                &mut hint_processor,
+            ),
+            Err(CairoRunError::Program(ProgramError::EntrypointNotFound(selector)))
+                if selector == "0x1234"
+        );
+    }
+}
@@ -0,0 +1,230 @@
+//! The dictionaries allocated through the segment arena builtin, to debug the `Felt252Dict`s of
+//! Cairo 1 programs after a run.
+//!
+//! The segment arena builtin holds, for each allocation or finalization, a pointer to the infos
+//! of the segments allocated so far, the number of segments and the number of them finalized.
+//! Each info is made of the start of the segment, its end once finalized and the index it was
+//! finalized at. A dictionary is a segment of `(key, previous value, new value)` accesses, which
+//! is finalized when the dictionary is squashed. [CairoRunner::dicts_report] reads all of it back
+//! from memory, replaying the accesses of each dictionary to get its final values.
+
+use crate::stdlib::{collections::BTreeMap, fmt, prelude::*};
+
+use felt::Felt252;
+use num_traits::ToPrimitive;
+
+use crate::{
+    types::{errors::math_errors::MathError, relocatable::Relocatable},
+    vm::{
+        errors::memory_errors::MemoryError,
+        runners::{builtin_runner::BuiltinRunner, cairo_runner::CairoRunner},
+        vm_core::VirtualMachine,
+    },
+};
+
+// The cells of an instance of the segment arena builtin, and of the info of a segment
+const ARENA_INSTANCE_SIZE: usize = 3;
+const SEGMENT_INFO_SIZE: usize = 3;
+// The cells of an access to a dictionary
+const DICT_ACCESS_SIZE: usize = 3;
+
+/// A dictionary allocated through the segment arena builtin.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DictReport {
+    /// Index of the dictionary among the segments of the arena
+    pub index: usize,
+    pub start: Relocatable,
+    /// The end of the dictionary, set when it was squashed
+    pub end: Option<Relocatable>,
+    /// The order in which the dictionary was squashed among the segments of the arena
+    pub finalization_index: Option<Felt252>,
+    /// Number of accesses to the dictionary
+    pub accesses: usize,
+    /// The value of each key after the last access to it
+    pub entries: BTreeMap<Felt252, Felt252>,
+}
+
+impl DictReport {
+    /// Returns true if the dictionary was squashed, finalizing its segment.
+    pub fn is_squashed(&self) -> bool {
+        self.end.is_some()
+    }
+}
+
+impl fmt::Display for DictReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "dict {} at {}", self.index, self.start)?;
+        match self.end {
+            Some(end) => write!(f, " (squashed, ends at {end})")?,
+            None => write!(f, " (not squashed)")?,
+        }
+        writeln!(f, ": {} accesses", self.accesses)?;
+        for (key, value) in self.entries.iter() {
+            writeln!(f, "    {key}: {value}")?;
+        }
+        Ok(())
+    }
+}
+
+impl CairoRunner {
+    /// Returns the dictionaries allocated through the segment arena builtin, in the order they
+    /// were allocated, or none if the program doesn't use the builtin. Meant to be called once
+    /// the run is over, as the arena is read from its last instance.
+    pub fn dicts_report(&self, vm: &VirtualMachine) -> Result<Vec<DictReport>, MemoryError> {
+        let Some(arena_segment) = vm.builtin_runners.iter().find_map(|builtin| match builtin {
+            BuiltinRunner::SegmentArena(segment_arena) => Some(segment_arena.base()),
+            _ => None,
+        }) else {
+            return Ok(Vec::new());
+        };
+        // The arena always holds the instance written when its segment was initialized
+        let arena_size = vm
+            .segments
+            .memory
+            .data
+            .get(arena_segment)
+            .map_or(0, |segment| segment.len());
+        let last_instance = Relocatable::from((
+            arena_segment as isize,
+            arena_size.saturating_sub(ARENA_INSTANCE_SIZE),
+        ));
+        let infos = vm.get_relocatable(last_instance)?;
+        let n_segments = vm.get_integer((last_instance + 1_usize)?)?;
+        let n_segments = n_segments.to_usize().ok_or_else(|| {
+            MathError::Felt252ToUsizeConversion(Box::new(n_segments.into_owned()))
+        })?;
+
+        (0..n_segments)
+            .map(|index| -> Result<DictReport, MemoryError> {
+                let info = (infos + index * SEGMENT_INFO_SIZE)?;
+                let start = vm.get_relocatable(info)?;
+                let end = vm.get_relocatable((info + 1_usize)?).ok();
+                let finalization_index = vm
+                    .get_integer((info + 2_usize)?)
+                    .ok()
+                    .map(|index| index.into_owned());
+                let (accesses, entries) = replay_accesses(vm, start, end)?;
+                Ok(DictReport {
+                    index,
+                    start,
+                    end,
+                    finalization_index,
+                    accesses,
+                    entries,
+                })
+            })
+            .collect()
+    }
+}
+
+// Replays the accesses to the dictionary starting at `start`, up to `end` or the last access
+// written if it wasn't squashed, returning their number and the final value of each key
+fn replay_accesses(
+    vm: &VirtualMachine,
+    start: Relocatable,
+    end: Option<Relocatable>,
+) -> Result<(usize, BTreeMap<Felt252, Felt252>), MemoryError> {
+    let n_accesses = match end {
+        Some(end) => (end - start)? / DICT_ACCESS_SIZE,
+        None => {
+            let written = vm
+                .segments
+                .memory
+                .data
+                .get(start.segment_index as usize)
+                .map_or(0, |segment| segment.len());
+            written.saturating_sub(start.offset) / DICT_ACCESS_SIZE
+        }
+    };
+    let mut entries = BTreeMap::new();
+    for access in 0..n_accesses {
+        let address = (start + access * DICT_ACCESS_SIZE)?;
+        let key = vm.get_integer(address)?.into_owned();
+        let new_value = vm.get_integer((address + 2_usize)?)?.into_owned();
+        entries.insert(key, new_value);
+    }
+    Ok((n_accesses, entries))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        relocatable, stdlib::collections::HashMap, types::program::Program, utils::test_utils::*,
+        vm::runners::builtin_runner::SegmentArenaBuiltinRunner,
+    };
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn dicts_report_of_squashed_and_live_dicts() {
+        let program = program!();
+        let cairo_runner = cairo_runner!(program);
+        let mut vm = vm!();
+        vm.builtin_runners
+            .push(SegmentArenaBuiltinRunner::new(true).into());
+        vm.segments = segments![
+            // The arena, with two dicts allocated and the first one squashed
+            ((0, 0), (1, 0)),
+            ((0, 1), 0),
+            ((0, 2), 0),
+            ((0, 3), (1, 0)),
+            ((0, 4), 2),
+            ((0, 5), 1),
+            // The infos of the dicts
+            ((1, 0), (2, 0)),
+            ((1, 1), (2, 6)),
+            ((1, 2), 0),
+            ((1, 3), (3, 0)),
+            // dict[1] = 5, then dict[1] = 7
+            ((2, 0), 1),
+            ((2, 1), 0),
+            ((2, 2), 5),
+            ((2, 3), 1),
+            ((2, 4), 5),
+            ((2, 5), 7),
+            // dict[4] = 9
+            ((3, 0), 4),
+            ((3, 1), 0),
+            ((3, 2), 9)
+        ];
+
+        let report = cairo_runner.dicts_report(&vm).unwrap();
+        assert_eq!(
+            report,
+            [
+                DictReport {
+                    index: 0,
+                    start: relocatable!(2, 0),
+                    end: Some(relocatable!(2, 6)),
+                    finalization_index: Some(Felt252::new(0)),
+                    accesses: 2,
+                    entries: BTreeMap::from([(Felt252::new(1), Felt252::new(7))]),
+                },
+                DictReport {
+                    index: 1,
+                    start: relocatable!(3, 0),
+                    end: None,
+                    finalization_index: None,
+                    accesses: 1,
+                    entries: BTreeMap::from([(Felt252::new(4), Felt252::new(9))]),
+                },
+            ]
+        );
+        assert!(report[0].is_squashed());
+        assert_eq!(
+            report[1].to_string(),
+            "dict 1 at 3:0 (not squashed): 1 accesses\n    4: 9\n"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn dicts_report_without_segment_arena() {
+        let program = program!();
+        let cairo_runner = cairo_runner!(program);
+        assert_eq!(cairo_runner.dicts_report(&vm!()).unwrap(), []);
+    }
+}
@@ -0,0 +1,443 @@
+//! Lockstep runs of two versions of a program, to find the first observable divergence between
+//! them, e.g. to check that a compiler upgrade didn't change the behavior of a program.
+//!
+//! Both programs are run from their main, each with its own hint processor, and their executions
+//! are compared at the [Boundary] chosen: the cells written to the output segment, or the calls
+//! and returns of functions, named after the identifiers of each program. The pcs and step
+//! counts of the events are reported but not compared, as they are expected to change between
+//! versions of the compiler.
+
+use crate::stdlib::{
+    any::Any,
+    collections::{HashMap, VecDeque},
+    fmt,
+    prelude::*,
+};
+
+use crate::{
+    hint_processor::hint_processor_definition::HintProcessor,
+    types::{
+        instruction::Opcode,
+        program::Program,
+        relocatable::{MaybeRelocatable, Relocatable},
+    },
+    vm::{
+        decoding::decoder::decode_instruction,
+        errors::{cairo_run_errors::CairoRunError, vm_errors::VirtualMachineError},
+        runners::{builtin_runner::BuiltinRunner, cairo_runner::CairoRunner},
+        trace::call_stack::FunctionIndex,
+        vm_core::VirtualMachine,
+    },
+};
+use num_traits::ToPrimitive;
+
+/// The events compared between the two runs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Boundary {
+    /// The cells written to the output segment, in the order of their offsets
+    OutputWrites,
+    /// The calls and returns of functions
+    FunctionCalls,
+}
+
+/// An observable event of a run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Observation {
+    Output {
+        offset: usize,
+        value: MaybeRelocatable,
+    },
+    Call {
+        function: String,
+    },
+    Return {
+        function: String,
+    },
+}
+
+impl fmt::Display for Observation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Observation::Output { offset, value } => write!(f, "output[{offset}] = {value}"),
+            Observation::Call { function } => write!(f, "call {function}"),
+            Observation::Return { function } => write!(f, "return from {function}"),
+        }
+    }
+}
+
+/// An event of a run, along with where it happened.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ObservedEvent {
+    /// The number of steps run when the event was observed
+    pub step: usize,
+    /// The pc of the instruction causing the event
+    pub pc: Relocatable,
+    pub observation: Observation,
+}
+
+/// The first event on which two runs differ. A missing event means that its run ended before.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Divergence {
+    /// The number of matching events before the divergence
+    pub index: usize,
+    pub left: Option<ObservedEvent>,
+    pub right: Option<ObservedEvent>,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "The runs diverge after {} matching events:", self.index)?;
+        for (side, event) in [("left", &self.left), ("right", &self.right)] {
+            match event {
+                Some(event) => writeln!(
+                    f,
+                    "  {side}: {} (step {}, pc {})",
+                    event.observation, event.step, event.pc
+                )?,
+                None => writeln!(f, "  {side}: end of the run")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Runs two programs in lockstep and reports the first event on which they differ.
+#[derive(Clone, Debug)]
+pub struct DivergenceBisector {
+    layout: String,
+    boundary: Boundary,
+    max_steps: Option<usize>,
+}
+
+impl DivergenceBisector {
+    pub fn new(layout: &str, boundary: Boundary) -> Self {
+        DivergenceBisector {
+            layout: layout.to_string(),
+            boundary,
+            max_steps: None,
+        }
+    }
+
+    /// Limits the number of steps run by each program, failing with
+    /// [VirtualMachineError::StepsLimit] once one of them exceeds it.
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = Some(max_steps);
+        self
+    }
+
+    /// Runs both programs until the first event on which they differ, or until they both end.
+    /// Returns None if every event of the runs matched.
+    pub fn first_divergence(
+        &self,
+        left: &Program,
+        right: &Program,
+        left_hint_processor: &mut dyn HintProcessor,
+        right_hint_processor: &mut dyn HintProcessor,
+    ) -> Result<Option<Divergence>, Box<CairoRunError>> {
+        let mut left = LockstepRun::new(self, left, left_hint_processor)?;
+        let mut right = LockstepRun::new(self, right, right_hint_processor)?;
+        let mut index = 0;
+        loop {
+            let left_event = left.next_event().map_err(CairoRunError::from)?;
+            let right_event = right.next_event().map_err(CairoRunError::from)?;
+            match (&left_event, &right_event) {
+                (None, None) => return Ok(None),
+                (Some(left_event), Some(right_event))
+                    if left_event.observation == right_event.observation => {}
+                _ => {
+                    return Ok(Some(Divergence {
+                        index,
+                        left: left_event,
+                        right: right_event,
+                    }))
+                }
+            }
+            index += 1;
+        }
+    }
+}
+
+/// One of the runs of a [DivergenceBisector], yielding its events one at a time.
+struct LockstepRun<'a> {
+    runner: CairoRunner,
+    vm: VirtualMachine,
+    hint_processor: &'a mut dyn HintProcessor,
+    hint_data_dictionary: HashMap<usize, Vec<Box<dyn Any>>>,
+    functions: FunctionIndex<'a>,
+    boundary: Boundary,
+    max_steps: Option<usize>,
+    end: Relocatable,
+    // Only set when comparing the output writes
+    output_base: Option<isize>,
+    next_output_offset: usize,
+    pending: VecDeque<ObservedEvent>,
+}
+
+impl<'a> LockstepRun<'a> {
+    fn new(
+        bisector: &DivergenceBisector,
+        program: &'a Program,
+        hint_processor: &'a mut dyn HintProcessor,
+    ) -> Result<Self, Box<CairoRunError>> {
+        let mut runner =
+            CairoRunner::new(program, &bisector.layout, false).map_err(CairoRunError::from)?;
+        let mut vm = VirtualMachine::new(false);
+        let end = runner.initialize(&mut vm).map_err(CairoRunError::from)?;
+        let hint_data_dictionary = runner
+            .get_hint_data_dictionary(
+                &program.shared_program_data.reference_manager,
+                hint_processor,
+            )
+            .map_err(CairoRunError::from)?;
+        let output_base = match bisector.boundary {
+            Boundary::OutputWrites => vm
+                .builtin_runners
+                .iter()
+                .find(|builtin| matches!(builtin, BuiltinRunner::Output(_)))
+                .map(|builtin| builtin.base() as isize),
+            Boundary::FunctionCalls => None,
+        };
+        Ok(LockstepRun {
+            runner,
+            vm,
+            hint_processor,
+            hint_data_dictionary,
+            functions: FunctionIndex::new(program),
+            boundary: bisector.boundary,
+            max_steps: bisector.max_steps,
+            end,
+            output_base,
+            next_output_offset: 0,
+            pending: VecDeque::new(),
+        })
+    }
+
+    /// Runs until the next event, returning None once the run ends.
+    fn next_event(&mut self) -> Result<Option<ObservedEvent>, VirtualMachineError> {
+        while self.pending.is_empty() && self.vm.run_context.pc != self.end {
+            self.step()?;
+        }
+        Ok(self.pending.pop_front())
+    }
+
+    fn step(&mut self) -> Result<(), VirtualMachineError> {
+        if matches!(self.max_steps, Some(max_steps) if self.vm.current_step >= max_steps) {
+            return Err(VirtualMachineError::StepsLimit(self.vm.current_step as u64));
+        }
+        let pc = self.vm.run_context.pc;
+        let opcode = match self.boundary {
+            Boundary::FunctionCalls => Some(self.opcode_at(pc)?),
+            Boundary::OutputWrites => None,
+        };
+        self.vm.step(
+            self.hint_processor,
+            &mut self.runner.exec_scopes,
+            &self.hint_data_dictionary,
+            &self.runner.program.constants,
+        )?;
+        let step = self.vm.current_step;
+        match opcode {
+            Some(Opcode::Call) => {
+                let function = self.function_at(self.vm.run_context.pc);
+                self.pending.push_back(ObservedEvent {
+                    step,
+                    pc,
+                    observation: Observation::Call { function },
+                });
+            }
+            Some(Opcode::Ret) => {
+                let function = self.function_at(pc);
+                self.pending.push_back(ObservedEvent {
+                    step,
+                    pc,
+                    observation: Observation::Return { function },
+                });
+            }
+            _ => {}
+        }
+        if let Some(output_base) = self.output_base {
+            // Hints may write several cells at once, so every new cell is reported
+            while let Some(value) = self
+                .vm
+                .get_maybe(&Relocatable::from((output_base, self.next_output_offset)))
+            {
+                self.pending.push_back(ObservedEvent {
+                    step,
+                    pc,
+                    observation: Observation::Output {
+                        offset: self.next_output_offset,
+                        value,
+                    },
+                });
+                self.next_output_offset += 1;
+            }
+        }
+        Ok(())
+    }
+
+    fn opcode_at(&self, pc: Relocatable) -> Result<Opcode, VirtualMachineError> {
+        let encoding = self
+            .vm
+            .segments
+            .memory
+            .get_integer(pc)?
+            .to_u64()
+            .ok_or(VirtualMachineError::InvalidInstructionEncoding)?;
+        Ok(decode_instruction(encoding)?.opcode)
+    }
+
+    fn function_at(&self, pc: Relocatable) -> String {
+        let program_offset = self
+            .runner
+            .program_base
+            .map(|base| base.offset)
+            .unwrap_or_default();
+        self.functions
+            .function_at(pc.offset.saturating_sub(program_offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor;
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    fn bitwise_output() -> Program {
+        Program::from_bytes(
+            include_bytes!("../../../../cairo_programs/bitwise_output.json"),
+            Some("main"),
+        )
+        .unwrap()
+    }
+
+    fn function_return_if_print() -> Program {
+        Program::from_bytes(
+            include_bytes!("../../../../cairo_programs/function_return_if_print.json"),
+            Some("main"),
+        )
+        .unwrap()
+    }
+
+    fn first_divergence(
+        boundary: Boundary,
+        left: &Program,
+        right: &Program,
+    ) -> Result<Option<Divergence>, Box<CairoRunError>> {
+        DivergenceBisector::new("all_cairo", boundary).first_divergence(
+            left,
+            right,
+            &mut BuiltinHintProcessor::new_empty(),
+            &mut BuiltinHintProcessor::new_empty(),
+        )
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn same_program_doesnt_diverge() {
+        let program = bitwise_output();
+        assert_matches!(
+            first_divergence(Boundary::OutputWrites, &program, &program),
+            Ok(None)
+        );
+        assert_matches!(
+            first_divergence(Boundary::FunctionCalls, &program, &program),
+            Ok(None)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn output_writes_divergence() {
+        let divergence = first_divergence(
+            Boundary::OutputWrites,
+            &bitwise_output(),
+            &function_return_if_print(),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(divergence.index, 0);
+        assert_eq!(
+            divergence.left.unwrap().observation,
+            Observation::Output {
+                offset: 0,
+                value: MaybeRelocatable::from(0)
+            }
+        );
+        assert_eq!(
+            divergence.right.unwrap().observation,
+            Observation::Output {
+                offset: 0,
+                value: MaybeRelocatable::from(5)
+            }
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn function_calls_divergence() {
+        let divergence = first_divergence(
+            Boundary::FunctionCalls,
+            &bitwise_output(),
+            &function_return_if_print(),
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(divergence.index, 0);
+        assert_eq!(
+            divergence.left.unwrap().observation,
+            Observation::Call {
+                function: String::from("starkware.cairo.common.bitwise.bitwise_and")
+            }
+        );
+        assert_eq!(
+            divergence.right.unwrap().observation,
+            Observation::Call {
+                function: String::from("__main__.a")
+            }
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn max_steps_exceeded() {
+        let program = bitwise_output();
+        assert_matches!(
+            DivergenceBisector::new("all_cairo", Boundary::OutputWrites)
+                .with_max_steps(2)
+                .first_divergence(
+                    &program,
+                    &program,
+                    &mut BuiltinHintProcessor::new_empty(),
+                    &mut BuiltinHintProcessor::new_empty(),
+                )
+                .map_err(|error| *error),
+            Err(CairoRunError::VirtualMachine(
+                VirtualMachineError::StepsLimit(2)
+            ))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn display_divergence() {
+        let divergence = Divergence {
+            index: 3,
+            left: Some(ObservedEvent {
+                step: 10,
+                pc: Relocatable::from((0, 4)),
+                observation: Observation::Return {
+                    function: String::from("__main__.main"),
+                },
+            }),
+            right: None,
+        };
+        assert_eq!(
+            divergence.to_string(),
+            "The runs diverge after 3 matching events:\n  left: return from __main__.main (step 10, pc 0:4)\n  right: end of the run\n"
+        );
+    }
+}
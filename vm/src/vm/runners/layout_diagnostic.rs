@@ -0,0 +1,209 @@
+//! Diagnostics of the builtins that don't fit in the cells allotted to them by the layout.
+//!
+//! A layout allots one instance of each of its builtins every `ratio` steps, so a run using more
+//! instances than `steps / ratio` can't be proven with it. When this happens while finalizing the
+//! segments, the runner reports a [LayoutDiagnostic] naming each builtin over its allowance, the
+//! parameters of the layout, and the cheapest layout that would have fit the run, as selected by
+//! [LayoutSelector].
+
+use crate::stdlib::{fmt, prelude::*};
+
+use crate::vm::{
+    errors::memory_errors::MemoryError,
+    runners::{
+        cairo_runner::CairoRunner,
+        layout_selector::{LayoutSelector, RunStats},
+    },
+    vm_core::VirtualMachine,
+};
+
+/// The usage of a builtin whose instances are allotted by the layout.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BuiltinUsage {
+    pub name: &'static str,
+    pub used_instances: usize,
+    /// The number of instances allotted for the steps run, one every `ratio` steps
+    pub allowed_instances: usize,
+    pub ratio: u32,
+    /// The number of steps needed for the layout to allot a first component of the builtin
+    pub min_steps: usize,
+}
+
+impl BuiltinUsage {
+    /// Returns true if the builtin fits in the cells allotted to it.
+    pub fn fits(&self, steps: usize) -> bool {
+        steps >= self.min_steps && self.used_instances <= self.allowed_instances
+    }
+}
+
+/// The usage of the builtins of a run, relative to the cells allotted by its layout.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LayoutDiagnostic {
+    pub layout: String,
+    pub steps: usize,
+    /// The builtins with a ratio in the layout, in the order of the builtin runners
+    pub builtins: Vec<BuiltinUsage>,
+    /// The first layout fitting every builtin of the run, if any
+    pub minimal_layout: Option<&'static str>,
+}
+
+impl LayoutDiagnostic {
+    /// Iterates over the builtins that don't fit in the cells allotted to them.
+    pub fn exceeded(&self) -> impl Iterator<Item = &BuiltinUsage> {
+        self.builtins
+            .iter()
+            .filter(move |usage| !usage.fits(self.steps))
+    }
+}
+
+impl fmt::Display for LayoutDiagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for usage in self.exceeded() {
+            if self.steps < usage.min_steps {
+                write!(
+                    f,
+                    "The {} builtin needs at least {} steps with the {} layout (ratio {}), but only {} were run. ",
+                    usage.name, usage.min_steps, self.layout, usage.ratio, self.steps
+                )?;
+            } else {
+                write!(
+                    f,
+                    "The {} builtin used {} instances, but the {} layout allows {} in {} steps (ratio {}). ",
+                    usage.name,
+                    usage.used_instances,
+                    self.layout,
+                    usage.allowed_instances,
+                    self.steps,
+                    usage.ratio
+                )?;
+            }
+        }
+        match self.minimal_layout {
+            Some(layout) => write!(f, "The smallest layout fitting the run is {layout}."),
+            None => write!(
+                f,
+                "No layout fits the run, the number of steps should be increased."
+            ),
+        }
+    }
+}
+
+impl CairoRunner {
+    /// Returns the usage of the builtins of the run relative to the cells allotted by the layout,
+    /// along with the first layout that would have fit the run. The used sizes of the segments
+    /// must have been computed.
+    pub fn diagnose_layout(&self, vm: &VirtualMachine) -> Result<LayoutDiagnostic, MemoryError> {
+        let stats = RunStats::from_run(vm)?;
+        let builtins = vm
+            .builtin_runners
+            .iter()
+            .zip(stats.builtins.iter())
+            .filter_map(|(builtin_runner, builtin)| {
+                Some(builtin.usage(builtin_runner.ratio()?, stats.steps))
+            })
+            .collect();
+        Ok(LayoutDiagnostic {
+            layout: self.layout._name.clone(),
+            steps: stats.steps,
+            builtins,
+            minimal_layout: LayoutSelector::minimal_for(&stats),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        types::{
+            instance_definitions::bitwise_instance_def::BitwiseInstanceDef,
+            relocatable::Relocatable,
+        },
+        utils::test_utils::*,
+        vm::{
+            errors::runner_errors::RunnerError,
+            runners::builtin_runner::{BitwiseBuiltinRunner, BITWISE_BUILTIN_NAME},
+        },
+    };
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    // A run of 128 steps using 3 bitwise instances, on a layout allowing one every 64 steps
+    fn bitwise_run(cairo_runner: &CairoRunner) -> VirtualMachine {
+        let mut vm = vm!();
+        vm.current_step = 128;
+        vm.builtin_runners = vec![BitwiseBuiltinRunner::new(
+            &BitwiseInstanceDef::new(cairo_runner.layout.builtins.bitwise.as_ref().unwrap().ratio),
+            true,
+        )
+        .into()];
+        vm.segments.segment_used_sizes = Some(vec![15]);
+        vm
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn diagnose_exceeded_builtin() {
+        let cairo_runner = cairo_runner!(program!(), "starknet");
+        let vm = bitwise_run(&cairo_runner);
+        let diagnostic = cairo_runner.diagnose_layout(&vm).unwrap();
+        let usage = BuiltinUsage {
+            name: BITWISE_BUILTIN_NAME,
+            used_instances: 3,
+            allowed_instances: 2,
+            ratio: 64,
+            min_steps: 64,
+        };
+        assert_eq!(
+            diagnostic,
+            LayoutDiagnostic {
+                layout: String::from("starknet"),
+                steps: 128,
+                builtins: vec![usage.clone()],
+                minimal_layout: Some("recursive_large_output"),
+            }
+        );
+        assert_eq!(diagnostic.exceeded().collect::<Vec<_>>(), [&usage]);
+        assert_eq!(
+            diagnostic.to_string(),
+            "The bitwise_builtin builtin used 3 instances, but the starknet layout allows 2 in 128 steps (ratio 64). \
+             The smallest layout fitting the run is recursive_large_output."
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn diagnose_too_few_steps() {
+        let cairo_runner = cairo_runner!(program!(), "all_solidity");
+        let mut vm = bitwise_run(&cairo_runner);
+        vm.current_step = 16;
+        vm.segments.segment_used_sizes = Some(vec![5]);
+        let diagnostic = cairo_runner.diagnose_layout(&vm).unwrap();
+        assert_eq!(diagnostic.builtins[0].min_steps, 256);
+        // The layouts allotting a bitwise instance every 8 steps fit the run
+        assert_eq!(diagnostic.minimal_layout, Some("recursive_large_output"));
+        assert!(diagnostic
+            .to_string()
+            .starts_with("The bitwise_builtin builtin needs at least 256 steps"));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn finalize_segments_reports_diagnostic() {
+        let mut cairo_runner = cairo_runner!(program!(), "starknet", true);
+        cairo_runner.program_base = Some(Relocatable::from((1, 0)));
+        cairo_runner.execution_base = Some(Relocatable::from((2, 0)));
+        cairo_runner.execution_public_memory = Some(Vec::new());
+        cairo_runner.run_ended = true;
+        let mut vm = bitwise_run(&cairo_runner);
+        vm.segments.segment_used_sizes = Some(vec![15, 0, 0]);
+        assert_matches!(
+            cairo_runner.finalize_segments(&mut vm),
+            Err(RunnerError::InsufficientLayout(diagnostic))
+                if diagnostic.exceeded().count() == 1
+                    && diagnostic.minimal_layout == Some("recursive_large_output")
+        );
+    }
+}
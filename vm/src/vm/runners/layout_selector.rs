@@ -0,0 +1,264 @@
+//! Selection of the cheapest standard layout fitting a program.
+//!
+//! The layouts are tried from the one allotting the fewest cells to builtins. A layout fits a
+//! program if it has all of its builtins, and fits a run if it also allots enough instances of
+//! each builtin for the steps run, see [RunStats]. Only the latter matters to prove the run, so
+//! in proof mode the layout should be selected from the stats of a [dry run](LayoutSelector::dry_run).
+
+use crate::stdlib::prelude::*;
+
+use crate::{
+    cairo_run::{cairo_run_program, CairoRunConfig},
+    hint_processor::hint_processor_definition::HintProcessor,
+    serde::deserialize_program::BuiltinName,
+    types::{
+        layout::{CairoLayout, LAYOUTS},
+        program::Program,
+    },
+    vm::{
+        errors::{cairo_run_errors::CairoRunError, memory_errors::MemoryError},
+        runners::{
+            builtin_runner::{
+                BITWISE_BUILTIN_NAME, EC_OP_BUILTIN_NAME, HASH_BUILTIN_NAME, KECCAK_BUILTIN_NAME,
+                OUTPUT_BUILTIN_NAME, POSEIDON_BUILTIN_NAME, RANGE_CHECK_BUILTIN_NAME,
+                SEGMENT_ARENA_BUILTIN_NAME, SIGNATURE_BUILTIN_NAME,
+            },
+            layout_diagnostic::BuiltinUsage,
+        },
+        vm_core::VirtualMachine,
+    },
+};
+
+// The layout of the dry runs, which has every builtin known to the VM
+const DRY_RUN_LAYOUT: &str = "all_cairo";
+
+/// The instances of a builtin used by a run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BuiltinStats {
+    pub name: &'static str,
+    pub used_instances: usize,
+    pub instances_per_component: u32,
+}
+
+impl BuiltinStats {
+    /// Returns the usage of the builtin on a layout allotting an instance every `ratio` steps.
+    pub fn usage(&self, ratio: u32, steps: usize) -> BuiltinUsage {
+        BuiltinUsage {
+            name: self.name,
+            used_instances: self.used_instances,
+            allowed_instances: steps / ratio as usize,
+            ratio,
+            min_steps: (ratio * self.instances_per_component) as usize,
+        }
+    }
+}
+
+/// The number of steps of a run, and the instances used by each of its builtins.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RunStats {
+    pub steps: usize,
+    /// The builtins of the run, in the order of the builtin runners
+    pub builtins: Vec<BuiltinStats>,
+}
+
+impl RunStats {
+    /// Returns the stats of the run of `vm`. The used sizes of the segments must have been
+    /// computed, as they are when the run ends.
+    pub fn from_run(vm: &VirtualMachine) -> Result<RunStats, MemoryError> {
+        let builtins = vm
+            .builtin_runners
+            .iter()
+            .map(|builtin_runner| {
+                Ok(BuiltinStats {
+                    name: builtin_runner.name(),
+                    used_instances: builtin_runner.get_used_instances(&vm.segments)?,
+                    instances_per_component: builtin_runner.instances_per_component(),
+                })
+            })
+            .collect::<Result<Vec<BuiltinStats>, MemoryError>>()?;
+        Ok(RunStats {
+            steps: vm.current_step,
+            builtins,
+        })
+    }
+}
+
+/// What a layout must satisfy to be selected.
+#[derive(Clone, Copy, Debug)]
+pub enum LayoutRequirements<'a> {
+    /// Having each of the builtins
+    Builtins(&'a [BuiltinName]),
+    /// Allotting enough instances of each builtin for the steps of the run
+    Run(&'a RunStats),
+}
+
+impl<'a> From<&'a Program> for LayoutRequirements<'a> {
+    fn from(program: &'a Program) -> Self {
+        LayoutRequirements::Builtins(&program.builtins)
+    }
+}
+
+impl<'a> From<&'a RunStats> for LayoutRequirements<'a> {
+    fn from(stats: &'a RunStats) -> Self {
+        LayoutRequirements::Run(stats)
+    }
+}
+
+impl LayoutRequirements<'_> {
+    fn fit(&self, layout: &CairoLayout) -> bool {
+        match self {
+            LayoutRequirements::Builtins(builtins) => builtins
+                .iter()
+                .all(|builtin| layout_ratio(layout, builtin.name()).is_some()),
+            LayoutRequirements::Run(stats) => {
+                stats
+                    .builtins
+                    .iter()
+                    .all(|builtin| match layout_ratio(layout, builtin.name) {
+                        None => false,
+                        Some(None) => true,
+                        Some(Some(ratio)) => builtin.usage(ratio, stats.steps).fits(stats.steps),
+                    })
+            }
+        }
+    }
+}
+
+/// Selects the cheapest standard layout satisfying some [LayoutRequirements].
+pub struct LayoutSelector;
+
+impl LayoutSelector {
+    /// Returns the first layout, from the one allotting the fewest cells to builtins, satisfying
+    /// the requirements of a program or of the stats of a run. Returns `None` if no layout does,
+    /// as when the program uses a builtin unknown to the VM.
+    pub fn minimal_for<'a>(
+        requirements: impl Into<LayoutRequirements<'a>>,
+    ) -> Option<&'static str> {
+        let requirements = requirements.into();
+        LAYOUTS
+            .iter()
+            .find(|(_, instance)| requirements.fit(&instance()))
+            .map(|(name, _)| *name)
+    }
+
+    /// Runs `program` from its entrypoint, out of proof mode, on a layout having every builtin,
+    /// and returns the stats of the run. The steps of a run in proof mode are at least as many,
+    /// so a layout fitting the dry run fits the proof mode run too.
+    pub fn dry_run(
+        program: &Program,
+        hint_processor: &mut dyn HintProcessor,
+    ) -> Result<RunStats, Box<CairoRunError>> {
+        let cairo_run_config = CairoRunConfig {
+            layout: DRY_RUN_LAYOUT,
+            ..Default::default()
+        };
+        let (_, vm) = cairo_run_program(program, &cairo_run_config, hint_processor)?;
+        Ok(RunStats::from_run(&vm).map_err(CairoRunError::from)?)
+    }
+}
+
+// Returns None if the layout doesn't have the builtin, and Some(None) if it allots as many
+// instances as the run uses
+fn layout_ratio(layout: &CairoLayout, name: &str) -> Option<Option<u32>> {
+    let builtins = &layout.builtins;
+    match name {
+        OUTPUT_BUILTIN_NAME => builtins.output.then_some(None),
+        HASH_BUILTIN_NAME => builtins.pedersen.as_ref().map(|def| def.ratio),
+        RANGE_CHECK_BUILTIN_NAME => builtins.range_check.as_ref().map(|def| def.ratio),
+        SIGNATURE_BUILTIN_NAME => builtins.ecdsa.as_ref().map(|def| def.ratio),
+        BITWISE_BUILTIN_NAME => builtins.bitwise.as_ref().map(|def| def.ratio),
+        EC_OP_BUILTIN_NAME => builtins.ec_op.as_ref().map(|def| def.ratio),
+        KECCAK_BUILTIN_NAME => builtins.keccak.as_ref().map(|def| def.ratio),
+        POSEIDON_BUILTIN_NAME => builtins.poseidon.as_ref().map(|def| def.ratio),
+        // The segment arena isn't part of the layouts, every layout can run it
+        SEGMENT_ARENA_BUILTIN_NAME => Some(None),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+        serde::deserialize_program::ReferenceManager, utils::test_utils::*,
+    };
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    fn bitwise_stats(steps: usize, used_instances: usize) -> RunStats {
+        RunStats {
+            steps,
+            builtins: vec![BuiltinStats {
+                name: BITWISE_BUILTIN_NAME,
+                used_instances,
+                instances_per_component: 1,
+            }],
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn minimal_for_program_builtins() {
+        assert_eq!(LayoutSelector::minimal_for(&program!()), Some("plain"));
+        assert_eq!(
+            LayoutSelector::minimal_for(&program![BuiltinName::output, BuiltinName::pedersen]),
+            Some("small")
+        );
+        assert_eq!(
+            LayoutSelector::minimal_for(&program![BuiltinName::bitwise, BuiltinName::poseidon]),
+            Some("starknet")
+        );
+        assert_eq!(
+            LayoutSelector::minimal_for(&program![BuiltinName::keccak]),
+            Some("starknet_with_keccak")
+        );
+        assert_eq!(
+            LayoutSelector::minimal_for(&program![BuiltinName::Other(String::from(
+                "unknown_builtin"
+            ))]),
+            None
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn minimal_for_run_stats() {
+        // starknet allots a bitwise instance every 64 steps
+        assert_eq!(
+            LayoutSelector::minimal_for(&bitwise_stats(128, 2)),
+            Some("starknet")
+        );
+        // recursive_large_output allots one every 8 steps
+        assert_eq!(
+            LayoutSelector::minimal_for(&bitwise_stats(128, 3)),
+            Some("recursive_large_output")
+        );
+        assert_eq!(
+            LayoutSelector::minimal_for(&bitwise_stats(32, 2)),
+            Some("recursive_large_output")
+        );
+        // Only the dynamic layout allots as many instances as used
+        assert_eq!(
+            LayoutSelector::minimal_for(&bitwise_stats(128, 20)),
+            Some("dynamic")
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn dry_run_of_bitwise_program() {
+        let program = Program::from_bytes(
+            include_bytes!("../../../../cairo_programs/bitwise_builtin_test.json"),
+            Some("main"),
+        )
+        .unwrap();
+        assert_eq!(LayoutSelector::minimal_for(&program), Some("starknet"));
+        let stats =
+            LayoutSelector::dry_run(&program, &mut BuiltinHintProcessor::new_empty()).unwrap();
+        assert_matches!(stats.builtins.as_slice(), [bitwise] if bitwise.name == BITWISE_BUILTIN_NAME && bitwise.used_instances > 0);
+        assert!(LayoutSelector::minimal_for(&stats).is_some());
+    }
+}
@@ -1,2 +1,18 @@
 pub mod builtin_runner;
+pub mod cairo_1_output;
 pub mod cairo_runner;
+#[cfg(feature = "cairo-1-hints")]
+pub mod cairo_script;
+pub mod checkpoint;
+#[cfg(feature = "cairo-1-hints")]
+pub mod contract_runner;
+pub mod dicts_report;
+pub mod divergence;
+pub mod layout_diagnostic;
+pub mod layout_selector;
+pub mod prover_input;
+#[cfg(feature = "std")]
+pub mod run_bundle;
+pub mod run_outcome;
+pub mod syscall_segment;
+pub mod whitelist;
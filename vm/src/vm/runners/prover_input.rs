@@ -0,0 +1,1021 @@
+//! Packaging of a proof mode run into the inputs of a prover.
+//!
+//! A type implementing [ProverInput] is built from a run in one call, with
+//! [CairoRunner::get_prover_input]. The run must be in proof mode, with its trace enabled, its
+//! segments finalized and its memory relocated, as [cairo_run](crate::cairo_run::cairo_run) leaves
+//! it with `proof_mode`, `trace_enabled` and `relocate_mem` set. Two adapters are provided:
+//! * [StoneProverInput], the files read by the Stone prover: the binary trace and memory, and the
+//!   public and private inputs of the AIR as JSON
+//! * [StwoProverInput], the structures taken by the Stwo prover, kept in memory
+
+use crate::stdlib::{
+    collections::{BTreeMap, HashSet},
+    prelude::*,
+};
+
+use felt::Felt252;
+use num_bigint::BigUint;
+use serde::{Serialize, Serializer};
+
+use crate::{
+    types::{
+        instance_definitions::{
+            bitwise_instance_def::CELLS_PER_BITWISE, builtins_instance_def::BuiltinsInstanceDef,
+            ec_op_instance_def::CELLS_PER_EC_OP, ecdsa_instance_def::CELLS_PER_SIGNATURE,
+            pedersen_instance_def::CELLS_PER_HASH, poseidon_instance_def::CELLS_PER_POSEIDON,
+            range_check_instance_def::CELLS_PER_RANGE_CHECK,
+        },
+        layout::CairoLayout,
+        relocatable::Relocatable,
+    },
+    vm::{
+        errors::{prover_input_errors::ProverInputError, runner_errors::RunnerError},
+        runners::{builtin_runner::BuiltinRunner, cairo_runner::CairoRunner},
+        trace::trace_entry::TraceEntry,
+        vm_core::VirtualMachine,
+    },
+};
+
+// The order of the curve of the ecdsa builtin
+const EC_ORDER: &str = "800000000000010ffffffffffffffffb781126dcae7b2321e66a241adc64d2f";
+
+/// The inputs of a prover, packaged from a proof mode run.
+pub trait ProverInput: Sized {
+    /// Packages the run of `vm`, which must be in proof mode, with its trace enabled, its
+    /// segments finalized and its memory relocated.
+    fn from_run(runner: &CairoRunner, vm: &VirtualMachine) -> Result<Self, ProverInputError>;
+}
+
+impl CairoRunner {
+    /// Packages the run into the inputs of a prover, see [ProverInput].
+    pub fn get_prover_input<P: ProverInput>(
+        &self,
+        vm: &VirtualMachine,
+    ) -> Result<P, ProverInputError> {
+        P::from_run(self, vm)
+    }
+}
+
+fn serialize_felt_hex<S: Serializer>(value: &Felt252, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("0x{}", value.to_str_radix(16)))
+}
+
+/// The relocated addresses of the first cell of a segment and of the cell following its last.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct MemorySegmentAddresses {
+    pub begin_addr: usize,
+    pub stop_ptr: usize,
+}
+
+/// A cell of the public memory.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PublicMemoryEntry {
+    pub address: usize,
+    #[serde(serialize_with = "serialize_felt_hex")]
+    pub value: Felt252,
+    pub page: usize,
+}
+
+/// The public input of the AIR of the run, as in the `air_public_input.json` file of Stone.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct PublicInput {
+    pub layout: String,
+    pub rc_min: isize,
+    pub rc_max: isize,
+    pub n_steps: usize,
+    /// The segments of the program, the execution and the builtins, the builtins named without
+    /// their `_builtin` suffix
+    pub memory_segments: BTreeMap<&'static str, MemorySegmentAddresses>,
+    pub public_memory: Vec<PublicMemoryEntry>,
+    pub dynamic_params: Option<()>,
+}
+
+impl PublicInput {
+    /// Returns the public input of the run, see [ProverInput::from_run].
+    pub fn from_run(runner: &CairoRunner, vm: &VirtualMachine) -> Result<Self, ProverInputError> {
+        if !runner.proof_mode {
+            return Err(ProverInputError::NotProofMode);
+        }
+        if !runner.segments_finalized {
+            return Err(ProverInputError::SegmentsNotFinalized);
+        }
+        let relocation_table = runner
+            .get_relocation_table()
+            .filter(|_| !runner.relocated_memory.is_empty())
+            .ok_or(ProverInputError::MemoryNotRelocated)?;
+        let relocate = |address: Relocatable| {
+            relocation_table
+                .get(address.segment_index as usize)
+                .map(|base| base + address.offset)
+                .ok_or(ProverInputError::MemoryNotRelocated)
+        };
+
+        let (rc_min, rc_max) = runner
+            .get_perm_range_check_limits(vm)
+            .ok_or(ProverInputError::NoRangeCheckLimits)?;
+
+        // The program and execution segments stop at the final pc and ap
+        let mut memory_segments = BTreeMap::new();
+        let segments = [
+            ("program", runner.program_base, vm.get_pc()),
+            ("execution", runner.execution_base, vm.get_ap()),
+        ];
+        for (name, base, stop) in segments {
+            let Some(base) = base else {
+                return Err(ProverInputError::SegmentsNotFinalized);
+            };
+            memory_segments.insert(
+                name,
+                MemorySegmentAddresses {
+                    begin_addr: relocate(base)?,
+                    stop_ptr: relocate(stop)?,
+                },
+            );
+        }
+        for (builtin_runner, (index, stop_ptr)) in vm
+            .builtin_runners
+            .iter()
+            .zip(runner.get_builtin_segments_info(vm)?)
+        {
+            let base = Relocatable::from((index as isize, 0));
+            let name = builtin_runner.name();
+            memory_segments.insert(
+                name.strip_suffix("_builtin").unwrap_or(name),
+                MemorySegmentAddresses {
+                    begin_addr: relocate(base)?,
+                    stop_ptr: relocate(Relocatable::from((index as isize, stop_ptr)))?,
+                },
+            );
+        }
+
+        let mut public_memory = Vec::new();
+        let mut public_memory_offsets: Vec<_> = vm.segments.public_memory_offsets.iter().collect();
+        public_memory_offsets.sort_by_key(|(segment_index, _)| **segment_index);
+        for (segment_index, offsets) in public_memory_offsets {
+            for (offset, page) in offsets {
+                let cell = Relocatable::from((*segment_index as isize, *offset));
+                let address = relocate(cell)?;
+                let value = runner
+                    .relocated_memory
+                    .get(address)
+                    .cloned()
+                    .flatten()
+                    .ok_or_else(|| ProverInputError::MissingPublicMemoryValue(Box::new(cell)))?;
+                public_memory.push(PublicMemoryEntry {
+                    address,
+                    value,
+                    page: *page,
+                });
+            }
+        }
+
+        let public_input = PublicInput {
+            layout: runner.layout._name.clone(),
+            rc_min,
+            rc_max,
+            n_steps: vm.get_relocated_trace()?.len(),
+            memory_segments,
+            public_memory,
+            dynamic_params: None,
+        };
+        public_input.validate()?;
+        Ok(public_input)
+    }
+
+    /// Checks that the public input is consistent, so that a prover pipeline fails with a precise
+    /// error rather than with a rejected proof:
+    /// * `n_steps` is a power of two, and the range check limits are ordered 16 bit values
+    /// * each segment stops at or after its beginning, and no two segments overlap
+    /// * the segment of each builtin holds whole instances, and no more cells than the layout
+    ///   allocates to the builtin for `n_steps` steps. The segment arena, which isn't part of any
+    ///   layout, isn't checked.
+    /// * no address appears twice in the public memory
+    ///
+    /// The public input returned by [PublicInput::from_run] is validated.
+    pub fn validate(&self) -> Result<(), ProverInputError> {
+        let layout = CairoLayout::from_name(&self.layout)
+            .ok_or_else(|| RunnerError::InvalidLayoutName(self.layout.clone().into_boxed_str()))?;
+        if !self.n_steps.is_power_of_two() {
+            return Err(ProverInputError::StepsNotPowerOfTwo(self.n_steps));
+        }
+        if self.rc_min < 0 || self.rc_min > self.rc_max || self.rc_max >= 1 << 16 {
+            return Err(ProverInputError::InvalidRangeCheckLimits(
+                self.rc_min,
+                self.rc_max,
+            ));
+        }
+
+        let mut segments: Vec<_> = self.memory_segments.iter().collect();
+        for (name, segment) in segments.iter() {
+            if segment.stop_ptr < segment.begin_addr {
+                return Err(ProverInputError::SegmentStopsBeforeBegin(Box::new((
+                    name,
+                    segment.stop_ptr,
+                    segment.begin_addr,
+                ))));
+            }
+        }
+        // Empty segments may begin where the next one does
+        segments.sort_by_key(|(_, segment)| (segment.begin_addr, segment.stop_ptr));
+        for pair in segments.windows(2) {
+            let ((name, segment), (next_name, next_segment)) = (pair[0], pair[1]);
+            if segment.stop_ptr > next_segment.begin_addr {
+                return Err(ProverInputError::OverlappingSegments(Box::new((
+                    name, next_name,
+                ))));
+            }
+        }
+
+        for (name, segment) in self.memory_segments.iter() {
+            if matches!(*name, "program" | "execution" | "segment_arena") {
+                continue;
+            }
+            let (ratio, cells_per_instance) = builtin_instance_sizes(&layout.builtins, name)
+                .ok_or(ProverInputError::BuiltinNotInLayout(name))?;
+            let used_cells = segment.stop_ptr - segment.begin_addr;
+            if used_cells % cells_per_instance as usize != 0 {
+                return Err(ProverInputError::MisalignedBuiltinSegment(Box::new((
+                    name,
+                    used_cells,
+                    cells_per_instance,
+                ))));
+            }
+            // The output builtin and the builtins of the dynamic layout have no ratio, their
+            // segments are as large as needed
+            if let Some(ratio) = ratio {
+                let allocated_cells = self.n_steps / ratio as usize * cells_per_instance as usize;
+                if used_cells > allocated_cells {
+                    return Err(ProverInputError::BuiltinCellsNotAllocated(Box::new((
+                        name,
+                        used_cells,
+                        allocated_cells,
+                    ))));
+                }
+            }
+        }
+
+        let mut addresses = HashSet::new();
+        for entry in self.public_memory.iter() {
+            if !addresses.insert(entry.address) {
+                return Err(ProverInputError::DuplicatedPublicMemoryAddress(
+                    entry.address,
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Returns the ratio and the number of cells per instance of the builtin named `name`, without its
+// `_builtin` suffix, or None if the builtins of the layout don't include it
+fn builtin_instance_sizes(
+    builtins: &BuiltinsInstanceDef,
+    name: &str,
+) -> Option<(Option<u32>, u32)> {
+    match name {
+        "output" => builtins.output.then_some((None, 1)),
+        "pedersen" => builtins
+            .pedersen
+            .as_ref()
+            .map(|def| (def.ratio, CELLS_PER_HASH)),
+        "range_check" => builtins
+            .range_check
+            .as_ref()
+            .map(|def| (def.ratio, CELLS_PER_RANGE_CHECK)),
+        "ecdsa" => builtins
+            .ecdsa
+            .as_ref()
+            .map(|def| (def.ratio, CELLS_PER_SIGNATURE)),
+        "bitwise" => builtins
+            .bitwise
+            .as_ref()
+            .map(|def| (def.ratio, CELLS_PER_BITWISE)),
+        "ec_op" => builtins
+            .ec_op
+            .as_ref()
+            .map(|def| (def.ratio, CELLS_PER_EC_OP)),
+        "keccak" => builtins
+            .keccak
+            .as_ref()
+            .map(|def| (def.ratio, def.cells_per_builtin())),
+        "poseidon" => builtins
+            .poseidon
+            .as_ref()
+            .map(|def| (def.ratio, CELLS_PER_POSEIDON)),
+        _ => None,
+    }
+}
+
+/// The `r` and `w = s^-1` values of an ecdsa signature.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SignatureInput {
+    #[serde(serialize_with = "serialize_felt_hex")]
+    pub r: Felt252,
+    #[serde(serialize_with = "serialize_felt_hex")]
+    pub w: Felt252,
+}
+
+/// The inputs of a builtin instance, named as in the `air_private_input.json` file of Stone.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(untagged)]
+pub enum BuiltinPrivateInput {
+    RangeCheck {
+        index: usize,
+        #[serde(serialize_with = "serialize_felt_hex")]
+        value: Felt252,
+    },
+    /// The inputs of the pedersen and bitwise builtins
+    Pair {
+        index: usize,
+        #[serde(serialize_with = "serialize_felt_hex")]
+        x: Felt252,
+        #[serde(serialize_with = "serialize_felt_hex")]
+        y: Felt252,
+    },
+    Signature {
+        index: usize,
+        #[serde(serialize_with = "serialize_felt_hex")]
+        pubkey: Felt252,
+        #[serde(serialize_with = "serialize_felt_hex")]
+        msg: Felt252,
+        signature_input: SignatureInput,
+    },
+    EcOp {
+        index: usize,
+        #[serde(serialize_with = "serialize_felt_hex")]
+        p_x: Felt252,
+        #[serde(serialize_with = "serialize_felt_hex")]
+        p_y: Felt252,
+        #[serde(serialize_with = "serialize_felt_hex")]
+        q_x: Felt252,
+        #[serde(serialize_with = "serialize_felt_hex")]
+        q_y: Felt252,
+        #[serde(serialize_with = "serialize_felt_hex")]
+        m: Felt252,
+    },
+    Keccak {
+        index: usize,
+        #[serde(serialize_with = "serialize_felt_hex")]
+        input_s0: Felt252,
+        #[serde(serialize_with = "serialize_felt_hex")]
+        input_s1: Felt252,
+        #[serde(serialize_with = "serialize_felt_hex")]
+        input_s2: Felt252,
+        #[serde(serialize_with = "serialize_felt_hex")]
+        input_s3: Felt252,
+        #[serde(serialize_with = "serialize_felt_hex")]
+        input_s4: Felt252,
+        #[serde(serialize_with = "serialize_felt_hex")]
+        input_s5: Felt252,
+        #[serde(serialize_with = "serialize_felt_hex")]
+        input_s6: Felt252,
+        #[serde(serialize_with = "serialize_felt_hex")]
+        input_s7: Felt252,
+    },
+    Poseidon {
+        index: usize,
+        #[serde(serialize_with = "serialize_felt_hex")]
+        input_s0: Felt252,
+        #[serde(serialize_with = "serialize_felt_hex")]
+        input_s1: Felt252,
+        #[serde(serialize_with = "serialize_felt_hex")]
+        input_s2: Felt252,
+    },
+}
+
+// Returns w = s^-1 modulo the order of the curve
+fn signature_w(s: &Felt252) -> Felt252 {
+    let order = BigUint::parse_bytes(EC_ORDER.as_bytes(), 16).expect("The order is valid hex");
+    let w = s.to_biguint().modpow(&(&order - 2u32), &order);
+    Felt252::from_bytes_be(&w.to_bytes_be())
+}
+
+// Returns the inputs of the instances of the builtin whose input cells are all written
+fn builtin_private_input(
+    builtin_runner: &BuiltinRunner,
+    vm: &VirtualMachine,
+) -> Result<Vec<BuiltinPrivateInput>, ProverInputError> {
+    let base = builtin_runner.base() as isize;
+    let cells_per_instance = builtin_runner.cells_per_instance() as usize;
+    let n_input_cells = builtin_runner.n_input_cells() as usize;
+    let signatures: BTreeMap<Relocatable, (Felt252, Felt252)> = match builtin_runner {
+        BuiltinRunner::Signature(signature) => signature.get_signatures().into_iter().collect(),
+        _ => BTreeMap::new(),
+    };
+
+    let mut private_input = Vec::new();
+    for index in 0..builtin_runner.get_used_instances(&vm.segments)? {
+        let first_cell = Relocatable::from((base, index * cells_per_instance));
+        let Some(mut inputs) = (0..n_input_cells)
+            .map(|cell| {
+                let value = vm.get_integer((first_cell + cell).ok()?).ok()?;
+                Some(value.into_owned())
+            })
+            .collect::<Option<Vec<Felt252>>>()
+        else {
+            continue;
+        };
+        let mut input = || inputs.remove(0);
+        let input = match builtin_runner {
+            BuiltinRunner::RangeCheck(_) => BuiltinPrivateInput::RangeCheck {
+                index,
+                value: input(),
+            },
+            BuiltinRunner::Hash(_) | BuiltinRunner::Bitwise(_) => BuiltinPrivateInput::Pair {
+                index,
+                x: input(),
+                y: input(),
+            },
+            BuiltinRunner::Signature(_) => {
+                let Some((r, s)) = signatures.get(&first_cell) else {
+                    continue;
+                };
+                BuiltinPrivateInput::Signature {
+                    index,
+                    pubkey: input(),
+                    msg: input(),
+                    signature_input: SignatureInput {
+                        r: r.clone(),
+                        w: signature_w(s),
+                    },
+                }
+            }
+            BuiltinRunner::EcOp(_) => BuiltinPrivateInput::EcOp {
+                index,
+                p_x: input(),
+                p_y: input(),
+                q_x: input(),
+                q_y: input(),
+                m: input(),
+            },
+            BuiltinRunner::Keccak(_) => BuiltinPrivateInput::Keccak {
+                index,
+                input_s0: input(),
+                input_s1: input(),
+                input_s2: input(),
+                input_s3: input(),
+                input_s4: input(),
+                input_s5: input(),
+                input_s6: input(),
+                input_s7: input(),
+            },
+            BuiltinRunner::Poseidon(_) => BuiltinPrivateInput::Poseidon {
+                index,
+                input_s0: input(),
+                input_s1: input(),
+                input_s2: input(),
+            },
+            BuiltinRunner::Output(_) | BuiltinRunner::SegmentArena(_) => break,
+        };
+        private_input.push(input);
+    }
+    Ok(private_input)
+}
+
+/// The files read by the Stone prover: the binary trace and memory, as written by
+/// [write_encoded_trace](crate::cairo_run::write_encoded_trace) and
+/// [write_encoded_memory](crate::cairo_run::write_encoded_memory), and the public and private
+/// inputs of the AIR.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StoneProverInput {
+    pub public_input: PublicInput,
+    /// The inputs of the instances of each builtin having some, the builtins named without their
+    /// `_builtin` suffix
+    pub private_input: BTreeMap<&'static str, Vec<BuiltinPrivateInput>>,
+    pub trace: Vec<TraceEntry>,
+    pub memory: Vec<Option<Felt252>>,
+}
+
+impl ProverInput for StoneProverInput {
+    fn from_run(runner: &CairoRunner, vm: &VirtualMachine) -> Result<Self, ProverInputError> {
+        let public_input = PublicInput::from_run(runner, vm)?;
+        let mut private_input = BTreeMap::new();
+        for builtin_runner in vm.builtin_runners.iter() {
+            if matches!(
+                builtin_runner,
+                BuiltinRunner::Output(_) | BuiltinRunner::SegmentArena(_)
+            ) {
+                continue;
+            }
+            let name = builtin_runner.name();
+            private_input.insert(
+                name.strip_suffix("_builtin").unwrap_or(name),
+                builtin_private_input(builtin_runner, vm)?,
+            );
+        }
+        Ok(StoneProverInput {
+            public_input,
+            private_input,
+            trace: vm.get_relocated_trace()?.clone(),
+            memory: runner.relocated_memory.clone(),
+        })
+    }
+}
+
+/// The paths of the files written by [StoneProverInput::write_files].
+#[cfg(feature = "std")]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StoneFiles {
+    pub trace: std::path::PathBuf,
+    pub memory: std::path::PathBuf,
+    pub public_input: std::path::PathBuf,
+    pub private_input: std::path::PathBuf,
+}
+
+// Adapts a writer to the bincode writers taken by the encoding functions of the trace and memory
+#[cfg(feature = "std")]
+pub(crate) struct IoWriter<W: std::io::Write>(pub(crate) W);
+
+#[cfg(feature = "std")]
+impl<W: std::io::Write> bincode::enc::write::Writer for IoWriter<W> {
+    fn write(&mut self, bytes: &[u8]) -> Result<(), bincode::error::EncodeError> {
+        self.0
+            .write_all(bytes)
+            .map_err(|inner| bincode::error::EncodeError::Io { inner, index: 0 })
+    }
+}
+
+#[cfg(feature = "std")]
+impl StoneProverInput {
+    /// Writes the files read by the Stone prover to `dir`, which must exist: `trace.bin`,
+    /// `memory.bin`, `air_public_input.json`, and `air_private_input.json` pointing to the first
+    /// two. Returns their paths, to pass to the prover.
+    pub fn write_files(&self, dir: &std::path::Path) -> Result<StoneFiles, ProverInputError> {
+        use crate::cairo_run::{write_encoded_memory, write_encoded_trace};
+        use std::{
+            fs::File,
+            io::{BufWriter, Write},
+        };
+
+        let write_error = |error: &dyn std::fmt::Display| {
+            ProverInputError::Write(error.to_string().into_boxed_str())
+        };
+        let create = |name: &str| {
+            File::create(dir.join(name))
+                .map(BufWriter::new)
+                .map_err(|error| write_error(&error))
+        };
+        let files = StoneFiles {
+            trace: dir.join("trace.bin"),
+            memory: dir.join("memory.bin"),
+            public_input: dir.join("air_public_input.json"),
+            private_input: dir.join("air_private_input.json"),
+        };
+
+        let mut trace_writer = IoWriter(create("trace.bin")?);
+        write_encoded_trace(&self.trace, &mut trace_writer).map_err(|error| write_error(&error))?;
+        trace_writer
+            .0
+            .flush()
+            .map_err(|error| write_error(&error))?;
+        let mut memory_writer = IoWriter(create("memory.bin")?);
+        write_encoded_memory(&self.memory, &mut memory_writer)
+            .map_err(|error| write_error(&error))?;
+        memory_writer
+            .0
+            .flush()
+            .map_err(|error| write_error(&error))?;
+        let mut public_input_writer = create("air_public_input.json")?;
+        serde_json::to_writer(&mut public_input_writer, &self.public_input)
+            .map_err(|error| write_error(&error))?;
+        public_input_writer
+            .flush()
+            .map_err(|error| write_error(&error))?;
+
+        let mut private_input = serde_json::Map::new();
+        private_input.insert(
+            "trace_path".to_string(),
+            files.trace.to_string_lossy().into(),
+        );
+        private_input.insert(
+            "memory_path".to_string(),
+            files.memory.to_string_lossy().into(),
+        );
+        for (name, inputs) in self.private_input.iter() {
+            private_input.insert(
+                name.to_string(),
+                serde_json::to_value(inputs).map_err(|error| write_error(&error))?,
+            );
+        }
+        let mut private_input_writer = create("air_private_input.json")?;
+        serde_json::to_writer(&mut private_input_writer, &private_input)
+            .map_err(|error| write_error(&error))?;
+        private_input_writer
+            .flush()
+            .map_err(|error| write_error(&error))?;
+        Ok(files)
+    }
+}
+
+/// A cell of the memory, with its value split in little endian 32 bit limbs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct StwoMemoryEntry {
+    pub address: u64,
+    pub value: [u32; 8],
+}
+
+impl From<(usize, &Felt252)> for StwoMemoryEntry {
+    fn from((address, value): (usize, &Felt252)) -> Self {
+        let bytes = value.to_le_bytes();
+        StwoMemoryEntry {
+            address: address as u64,
+            value: core::array::from_fn(|limb| {
+                u32::from_le_bytes([
+                    bytes[4 * limb],
+                    bytes[4 * limb + 1],
+                    bytes[4 * limb + 2],
+                    bytes[4 * limb + 3],
+                ])
+            }),
+        }
+    }
+}
+
+/// The structures taken by the Stwo prover: the relocated trace, the written cells of the memory
+/// as limbs, and the public input, from which it reads the public memory and the segments.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StwoProverInput {
+    pub public_input: PublicInput,
+    pub trace: Vec<TraceEntry>,
+    /// The written cells, by increasing address
+    pub memory: Vec<StwoMemoryEntry>,
+}
+
+impl StwoProverInput {
+    /// Returns the addresses of the public memory, in order.
+    pub fn public_memory_addresses(&self) -> Vec<u32> {
+        self.public_input
+            .public_memory
+            .iter()
+            .map(|entry| entry.address as u32)
+            .collect()
+    }
+}
+
+impl ProverInput for StwoProverInput {
+    fn from_run(runner: &CairoRunner, vm: &VirtualMachine) -> Result<Self, ProverInputError> {
+        Ok(StwoProverInput {
+            public_input: PublicInput::from_run(runner, vm)?,
+            trace: vm.get_relocated_trace()?.clone(),
+            memory: runner
+                .relocated_memory
+                .iter()
+                .enumerate()
+                .filter_map(|(address, value)| {
+                    Some(StwoMemoryEntry::from((address, value.as_ref()?)))
+                })
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cairo_run::{cairo_run, CairoRunConfig},
+        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+    };
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    fn proof_mode_run(program: &[u8], layout: &str) -> (CairoRunner, VirtualMachine) {
+        let cairo_run_config = CairoRunConfig {
+            layout,
+            proof_mode: true,
+            trace_enabled: true,
+            relocate_mem: true,
+            ..Default::default()
+        };
+        cairo_run(
+            program,
+            &cairo_run_config,
+            &mut BuiltinHintProcessor::new_empty(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn stone_input_of_fibonacci() {
+        let (runner, vm) = proof_mode_run(
+            include_bytes!("../../../../cairo_programs/proof_programs/fibonacci.json"),
+            "plain",
+        );
+        let input: StoneProverInput = runner.get_prover_input(&vm).unwrap();
+        let public_input = &input.public_input;
+        assert_eq!(public_input.layout, "plain");
+        assert_eq!(public_input.n_steps, input.trace.len());
+        assert!(public_input.n_steps.is_power_of_two());
+        assert!(public_input.rc_min <= public_input.rc_max);
+        assert_eq!(
+            public_input.memory_segments.keys().collect::<Vec<_>>(),
+            [&"execution", &"program"]
+        );
+        // The program is public
+        let program_segment = public_input.memory_segments["program"];
+        assert_eq!(program_segment.begin_addr, 1);
+        assert!(public_input.public_memory.len() >= runner.get_program().data_len());
+        assert!(public_input
+            .public_memory
+            .iter()
+            .all(|entry| input.memory[entry.address].as_ref() == Some(&entry.value)));
+        assert!(input.private_input.is_empty());
+
+        let json = serde_json::to_value(public_input).unwrap();
+        assert_eq!(json["layout"], "plain");
+        assert!(json["public_memory"][0]["value"]
+            .as_str()
+            .unwrap()
+            .starts_with("0x"));
+        assert!(json["dynamic_params"].is_null());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn stone_private_input_of_bitwise_program() {
+        let (runner, vm) = proof_mode_run(
+            include_bytes!("../../../../cairo_programs/proof_programs/bitwise_builtin_test.json"),
+            "all_cairo",
+        );
+        let input: StoneProverInput = runner.get_prover_input(&vm).unwrap();
+        assert!(input.public_input.memory_segments.contains_key("bitwise"));
+        assert!(input.public_input.memory_segments.contains_key("output"));
+        let bitwise = &input.private_input["bitwise"];
+        assert!(!bitwise.is_empty());
+        assert_matches!(bitwise[0], BuiltinPrivateInput::Pair { index: 0, .. });
+        let json = serde_json::to_value(&bitwise[0]).unwrap();
+        assert!(json["x"].as_str().unwrap().starts_with("0x"));
+        assert_eq!(json["index"], 0);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn write_stone_files() {
+        let (runner, vm) = proof_mode_run(
+            include_bytes!("../../../../cairo_programs/proof_programs/fibonacci.json"),
+            "plain",
+        );
+        let input: StoneProverInput = runner.get_prover_input(&vm).unwrap();
+        let dir = std::env::temp_dir().join("cairo_vm_stone_prover_input");
+        std::fs::create_dir_all(&dir).unwrap();
+        let files = input.write_files(&dir).unwrap();
+        assert_eq!(
+            std::fs::metadata(&files.trace).unwrap().len() as usize,
+            input.trace.len() * 24
+        );
+        let private_input: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&files.private_input).unwrap()).unwrap();
+        assert_eq!(
+            private_input["memory_path"],
+            files.memory.to_string_lossy().as_ref()
+        );
+        let public_input: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&files.public_input).unwrap()).unwrap();
+        assert_eq!(public_input["n_steps"], input.trace.len());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn stwo_input_of_fibonacci() {
+        let (runner, vm) = proof_mode_run(
+            include_bytes!("../../../../cairo_programs/proof_programs/fibonacci.json"),
+            "plain",
+        );
+        let input: StwoProverInput = runner.get_prover_input(&vm).unwrap();
+        assert_eq!(
+            input.memory.len(),
+            runner.relocated_memory.iter().flatten().count()
+        );
+        assert!(input
+            .memory
+            .windows(2)
+            .all(|entries| entries[0].address < entries[1].address));
+        let entry = input.memory[0];
+        let value = runner.relocated_memory[entry.address as usize]
+            .as_ref()
+            .unwrap();
+        assert_eq!(
+            StwoMemoryEntry::from((entry.address as usize, value)).value,
+            entry.value
+        );
+        assert_eq!(
+            input.public_memory_addresses().len(),
+            input.public_input.public_memory.len()
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn prover_input_requires_proof_mode() {
+        let cairo_run_config = CairoRunConfig {
+            trace_enabled: true,
+            relocate_mem: true,
+            ..Default::default()
+        };
+        let (runner, vm) = cairo_run(
+            include_bytes!("../../../../cairo_programs/fibonacci.json"),
+            &cairo_run_config,
+            &mut BuiltinHintProcessor::new_empty(),
+        )
+        .unwrap();
+        assert_matches!(
+            runner.get_prover_input::<StwoProverInput>(&vm),
+            Err(ProverInputError::NotProofMode)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn signature_w_is_inverse_of_s() {
+        let s = Felt252::new(7);
+        let w = signature_w(&s);
+        let order = BigUint::parse_bytes(EC_ORDER.as_bytes(), 16).unwrap();
+        assert_eq!(
+            (s.to_biguint() * w.to_biguint()) % order,
+            BigUint::from(1u32)
+        );
+    }
+
+    // A consistent public input of 16 steps in the small layout, where the pedersen and range
+    // check builtins are allocated 6 and 2 cells
+    fn small_public_input() -> PublicInput {
+        let segment = |begin_addr, stop_ptr| MemorySegmentAddresses {
+            begin_addr,
+            stop_ptr,
+        };
+        PublicInput {
+            layout: String::from("small"),
+            rc_min: 32760,
+            rc_max: 32770,
+            n_steps: 16,
+            memory_segments: BTreeMap::from([
+                ("program", segment(1, 5)),
+                ("execution", segment(10, 20)),
+                ("output", segment(30, 31)),
+                ("pedersen", segment(40, 46)),
+                ("range_check", segment(50, 52)),
+                ("ecdsa", segment(60, 60)),
+            ]),
+            public_memory: (1..5)
+                .map(|address| PublicMemoryEntry {
+                    address,
+                    value: Felt252::new(address),
+                    page: 0,
+                })
+                .collect(),
+            dynamic_params: None,
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn validate_consistent_public_input() {
+        assert_eq!(small_public_input().validate(), Ok(()));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn validate_layout_and_steps() {
+        let mut public_input = small_public_input();
+        public_input.layout = String::from("large");
+        assert_matches!(
+            public_input.validate(),
+            Err(ProverInputError::Runner(RunnerError::InvalidLayoutName(name))) if &*name == "large"
+        );
+
+        let mut public_input = small_public_input();
+        public_input.n_steps = 24;
+        assert_eq!(
+            public_input.validate(),
+            Err(ProverInputError::StepsNotPowerOfTwo(24))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn validate_range_check_limits() {
+        for (rc_min, rc_max) in [(32770, 32760), (-1, 5), (0, 1 << 16)] {
+            let mut public_input = small_public_input();
+            (public_input.rc_min, public_input.rc_max) = (rc_min, rc_max);
+            assert_eq!(
+                public_input.validate(),
+                Err(ProverInputError::InvalidRangeCheckLimits(rc_min, rc_max))
+            );
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn validate_segment_bounds() {
+        let mut public_input = small_public_input();
+        public_input
+            .memory_segments
+            .get_mut("output")
+            .unwrap()
+            .stop_ptr = 29;
+        assert_eq!(
+            public_input.validate(),
+            Err(ProverInputError::SegmentStopsBeforeBegin(Box::new((
+                "output", 29, 30
+            ))))
+        );
+
+        let mut public_input = small_public_input();
+        public_input
+            .memory_segments
+            .get_mut("execution")
+            .unwrap()
+            .stop_ptr = 31;
+        assert_eq!(
+            public_input.validate(),
+            Err(ProverInputError::OverlappingSegments(Box::new((
+                "execution",
+                "output"
+            ))))
+        );
+
+        // An empty segment may begin where another one does
+        let mut public_input = small_public_input();
+        public_input
+            .memory_segments
+            .get_mut("ecdsa")
+            .unwrap()
+            .begin_addr = 50;
+        public_input
+            .memory_segments
+            .get_mut("ecdsa")
+            .unwrap()
+            .stop_ptr = 50;
+        assert_eq!(public_input.validate(), Ok(()));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn validate_builtin_segments() {
+        let mut public_input = small_public_input();
+        public_input
+            .memory_segments
+            .get_mut("pedersen")
+            .unwrap()
+            .stop_ptr = 44;
+        assert_eq!(
+            public_input.validate(),
+            Err(ProverInputError::MisalignedBuiltinSegment(Box::new((
+                "pedersen", 4, 3
+            ))))
+        );
+
+        let mut public_input = small_public_input();
+        public_input
+            .memory_segments
+            .get_mut("range_check")
+            .unwrap()
+            .stop_ptr = 53;
+        assert_eq!(
+            public_input.validate(),
+            Err(ProverInputError::BuiltinCellsNotAllocated(Box::new((
+                "range_check",
+                3,
+                2
+            ))))
+        );
+
+        // The output builtin isn't limited
+        let mut public_input = small_public_input();
+        public_input
+            .memory_segments
+            .get_mut("output")
+            .unwrap()
+            .stop_ptr = 40;
+        assert_eq!(public_input.validate(), Ok(()));
+
+        let mut public_input = small_public_input();
+        public_input.memory_segments.insert(
+            "bitwise",
+            MemorySegmentAddresses {
+                begin_addr: 70,
+                stop_ptr: 70,
+            },
+        );
+        assert_eq!(
+            public_input.validate(),
+            Err(ProverInputError::BuiltinNotInLayout("bitwise"))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn validate_public_memory_addresses() {
+        let mut public_input = small_public_input();
+        let mut entry = public_input.public_memory[0].clone();
+        entry.page = 1;
+        public_input.public_memory.push(entry);
+        assert_eq!(
+            public_input.validate(),
+            Err(ProverInputError::DuplicatedPublicMemoryAddress(1))
+        );
+    }
+}
@@ -0,0 +1,350 @@
+//! A single file holding the artifacts of a run, to ship them from the machine executing the
+//! program to the machine proving it.
+//!
+//! The bundle is a zip archive, readable with any zip tool, holding:
+//! * `trace.bin` and `memory.bin`, the binary trace and memory as written by
+//!   [write_encoded_trace](crate::cairo_run::write_encoded_trace) and
+//!   [write_encoded_memory](crate::cairo_run::write_encoded_memory). The trace is only present
+//!   when the run had it enabled
+//! * `air_public_input.json`, the public input of the AIR, only present for proof mode runs
+//! * `report.json`, the [RunReport] of the run
+//! * `program_hash`, the [hash](crate::types::program::Program::hash_chain) of the program in
+//!   hexadecimal
+//!
+//! The entries are stored without compression, as the trace and memory hardly compress, and use
+//! the zip64 extensions when they exceed the 4 GiB of the original format.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::{self, Read, Seek, Write},
+    path::Path,
+};
+
+use felt::Felt252;
+use num_traits::Num;
+use serde::{Deserialize, Serialize};
+use zip::{
+    result::ZipError, write::FileOptions, CompressionMethod, DateTime, ZipArchive, ZipWriter,
+};
+
+use crate::{
+    cairo_run::{write_encoded_memory, write_encoded_trace},
+    vm::{
+        runners::{prover_input::IoWriter, run_outcome::RunOutcome},
+        trace::trace_entry::TraceEntry,
+    },
+};
+
+const TRACE_ENTRY: &str = "trace.bin";
+const MEMORY_ENTRY: &str = "memory.bin";
+const PUBLIC_INPUT_ENTRY: &str = "air_public_input.json";
+const REPORT_ENTRY: &str = "report.json";
+const PROGRAM_HASH_ENTRY: &str = "program_hash";
+
+/// The resources and results of a run, as stored in the `report.json` entry of its bundle.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunReport {
+    pub n_steps: usize,
+    pub n_memory_holes: usize,
+    pub builtin_instance_counter: BTreeMap<String, usize>,
+    /// Relocated address of the first instruction of the program
+    pub program_base: usize,
+    /// Size of each memory segment, by segment index
+    pub segment_sizes: Vec<usize>,
+    /// Values of the output builtin segment in decimal, None for the cells never written
+    pub output: Vec<Option<String>>,
+    /// Number of unknown hints skipped during the run
+    pub skipped_hints: usize,
+}
+
+impl RunReport {
+    pub fn new(run_outcome: &RunOutcome) -> Self {
+        let resources = &run_outcome.execution_resources;
+        RunReport {
+            n_steps: resources.n_steps,
+            n_memory_holes: resources.n_memory_holes,
+            builtin_instance_counter: resources.builtin_instance_counter.clone(),
+            program_base: run_outcome.program_base,
+            segment_sizes: run_outcome.segment_sizes.clone(),
+            output: run_outcome
+                .output
+                .iter()
+                .map(|value| value.as_ref().map(Felt252::to_string))
+                .collect(),
+            skipped_hints: run_outcome.skipped_hints.len(),
+        }
+    }
+}
+
+/// The artifacts of a run, as read back from its bundle by [RunBundle::read].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RunBundle {
+    /// Relocated trace, if the run had it enabled
+    pub trace: Option<Vec<TraceEntry>>,
+    /// Relocated memory, None for the cells never written
+    pub memory: Vec<Option<Felt252>>,
+    /// Public input of the AIR, if the run was in proof mode
+    pub public_input: Option<serde_json::Value>,
+    pub report: RunReport,
+    pub program_hash: Felt252,
+}
+
+impl RunOutcome {
+    /// Writes the artifacts of the run to `path` as a single zip file, see the
+    /// [module documentation](self). Fails with [io::ErrorKind::InvalidInput] if the program
+    /// can't be [hashed](crate::types::program::Program::hash_chain).
+    pub fn write_bundle(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let program_hash = self
+            .program
+            .hash_chain(0)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))?;
+
+        let mut zip = ZipWriter::new(io::BufWriter::new(fs::File::create(path)?));
+        let mut add = |name: &str, data: &[u8]| -> io::Result<()> {
+            let options = FileOptions::default()
+                .compression_method(CompressionMethod::Stored)
+                // 1980-01-01, the earliest date of the format, so bundles of the same run are
+                // identical
+                .last_modified_time(DateTime::default())
+                .large_file(data.len() as u64 > u32::MAX as u64);
+            zip.start_file(name, options)?;
+            zip.write_all(data)
+        };
+        if let Some(trace) = &self.trace {
+            let mut writer = IoWriter(Vec::new());
+            write_encoded_trace(trace, &mut writer).map_err(invalid_input)?;
+            add(TRACE_ENTRY, &writer.0)?;
+        }
+        let mut writer = IoWriter(Vec::new());
+        write_encoded_memory(&self.relocated_memory, &mut writer).map_err(invalid_input)?;
+        add(MEMORY_ENTRY, &writer.0)?;
+        if let Some(public_input) = &self.public_input {
+            add(PUBLIC_INPUT_ENTRY, &serde_json::to_vec(public_input)?)?;
+        }
+        add(
+            REPORT_ENTRY,
+            &serde_json::to_vec_pretty(&RunReport::new(self))?,
+        )?;
+        add(
+            PROGRAM_HASH_ENTRY,
+            format!("0x{}", program_hash.to_str_radix(16)).as_bytes(),
+        )?;
+        zip.finish()?.flush()
+    }
+}
+
+impl RunBundle {
+    /// Reads the bundle written by [RunOutcome::write_bundle] to `path`. Fails with
+    /// [io::ErrorKind::InvalidData] if the file isn't a valid bundle.
+    pub fn read(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut archive =
+            ZipArchive::new(io::BufReader::new(fs::File::open(path)?)).map_err(zip_error)?;
+        let mut required = |name: &str| {
+            read_entry(&mut archive, name)?
+                .ok_or_else(|| invalid_data(format!("the bundle has no {name} entry")))
+        };
+
+        let report: RunReport = serde_json::from_slice(&required(REPORT_ENTRY)?)?;
+        let memory = decode_memory(&required(MEMORY_ENTRY)?, &report)?;
+        let program_hash = core::str::from_utf8(&required(PROGRAM_HASH_ENTRY)?)
+            .ok()
+            .and_then(|hash| hash.strip_prefix("0x"))
+            .and_then(|hash| Felt252::from_str_radix(hash, 16).ok())
+            .ok_or_else(|| invalid_data("invalid program hash"))?;
+        let trace = read_entry(&mut archive, TRACE_ENTRY)?
+            .as_deref()
+            .map(decode_trace)
+            .transpose()?;
+        let public_input = read_entry(&mut archive, PUBLIC_INPUT_ENTRY)?
+            .as_deref()
+            .map(serde_json::from_slice::<serde_json::Value>)
+            .transpose()?;
+        Ok(RunBundle {
+            trace,
+            memory,
+            public_input,
+            report,
+            program_hash,
+        })
+    }
+}
+
+fn invalid_input(error: impl ToString) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, error.to_string())
+}
+
+fn invalid_data(error: impl ToString) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error.to_string())
+}
+
+// Reads an entry of the bundle, None if there's no entry by that name
+fn read_entry<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    name: &str,
+) -> io::Result<Option<Vec<u8>>> {
+    let mut entry = match archive.by_name(name) {
+        Ok(entry) => entry,
+        Err(ZipError::FileNotFound) => return Ok(None),
+        Err(error) => return Err(zip_error(error)),
+    };
+    let mut data = Vec::new();
+    // Fails with InvalidData if the checksum of the entry doesn't match
+    entry.read_to_end(&mut data)?;
+    Ok(Some(data))
+}
+
+fn zip_error(error: ZipError) -> io::Error {
+    match error {
+        ZipError::Io(error) => error,
+        error => invalid_data(error),
+    }
+}
+
+fn decode_trace(bytes: &[u8]) -> io::Result<Vec<TraceEntry>> {
+    if !bytes.len().is_multiple_of(24) {
+        return Err(invalid_data("truncated trace"));
+    }
+    Ok(bytes
+        .chunks_exact(24)
+        .map(|entry| TraceEntry {
+            ap: u64_le(&entry[0..8]) as usize,
+            fp: u64_le(&entry[8..16]) as usize,
+            pc: u64_le(&entry[16..24]) as usize,
+        })
+        .collect())
+}
+
+// The addresses are bounded by the size of the relocated memory in the report, so a corrupted
+// address can't make us allocate an arbitrarily large memory
+fn decode_memory(bytes: &[u8], report: &RunReport) -> io::Result<Vec<Option<Felt252>>> {
+    if !bytes.len().is_multiple_of(40) {
+        return Err(invalid_data("truncated memory"));
+    }
+    // The relocated memory starts at address 1 and holds the segments one after the other
+    let memory_size = report
+        .segment_sizes
+        .iter()
+        .try_fold(1_usize, |size, segment_size| {
+            size.checked_add(*segment_size)
+        })
+        .ok_or_else(|| invalid_data("invalid segment sizes"))?;
+    let mut memory = Vec::new();
+    for cell in bytes.chunks_exact(40) {
+        let address = usize::try_from(u64_le(&cell[0..8]))
+            .ok()
+            .filter(|address| *address < memory_size)
+            .ok_or_else(|| invalid_data("memory address beyond the segments of the run"))?;
+        let mut value = [0; 32];
+        value.copy_from_slice(&cell[8..40]);
+        value.reverse();
+        if memory.len() <= address {
+            memory.resize(address + 1, None);
+        }
+        memory[address] = Some(Felt252::from_bytes_be(&value));
+    }
+    Ok(memory)
+}
+
+fn u64_le(bytes: &[u8]) -> u64 {
+    let mut le_bytes = [0; 8];
+    le_bytes.copy_from_slice(bytes);
+    u64::from_le_bytes(le_bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cairo_run::{cairo_run, CairoRunConfig},
+        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+    };
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn decode_memory_beyond_segments() {
+        let report = RunReport {
+            n_steps: 0,
+            n_memory_holes: 0,
+            builtin_instance_counter: BTreeMap::new(),
+            program_base: 1,
+            segment_sizes: vec![2],
+            output: Vec::new(),
+            skipped_hints: 0,
+        };
+        let cell = |address: u64| {
+            let mut cell = address.to_le_bytes().to_vec();
+            // The value 7, in little endian
+            cell.push(7);
+            cell.extend([0; 31]);
+            cell
+        };
+        assert_eq!(
+            decode_memory(&cell(2), &report).unwrap(),
+            vec![None, None, Some(Felt252::new(7))]
+        );
+        assert_matches!(
+            decode_memory(&cell(3), &report),
+            Err(error) if error.kind() == io::ErrorKind::InvalidData
+        );
+        assert_matches!(
+            decode_memory(&cell(u64::MAX), &report),
+            Err(error) if error.kind() == io::ErrorKind::InvalidData
+        );
+    }
+
+    #[test]
+    fn read_invalid_bundle() {
+        let path = std::env::temp_dir().join("cairo_vm_invalid_run_bundle.zip");
+        std::fs::write(&path, b"not a bundle").unwrap();
+        let result = RunBundle::read(&path);
+        std::fs::remove_file(path).unwrap();
+        assert_matches!(result, Err(error) if error.kind() == io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn write_and_read_bundle() {
+        let cairo_run_config = CairoRunConfig::prove();
+        let (cairo_runner, vm) = cairo_run(
+            include_bytes!("../../../../cairo_programs/proof_programs/fibonacci.json"),
+            &cairo_run_config,
+            &mut BuiltinHintProcessor::new_empty(),
+        )
+        .unwrap();
+        let run_outcome = RunOutcome::new(&cairo_runner, &vm).unwrap();
+        assert!(run_outcome.public_input.is_some());
+
+        let path = std::env::temp_dir().join("cairo_vm_run_bundle.zip");
+        run_outcome.write_bundle(&path).unwrap();
+        let bundle = RunBundle::read(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(bundle.trace, run_outcome.trace);
+        // Only the written cells are stored
+        let written_memory_len = run_outcome
+            .relocated_memory
+            .iter()
+            .rposition(Option::is_some)
+            .map_or(0, |address| address + 1);
+        assert_eq!(
+            bundle.memory,
+            run_outcome.relocated_memory[..written_memory_len]
+        );
+        assert_eq!(
+            bundle.public_input,
+            run_outcome
+                .public_input
+                .as_ref()
+                .map(|public_input| serde_json::to_value(public_input).unwrap())
+        );
+        assert_eq!(bundle.report, RunReport::new(&run_outcome));
+        assert_eq!(
+            bundle.program_hash,
+            run_outcome.program.hash_chain(0).unwrap()
+        );
+    }
+}
@@ -0,0 +1,828 @@
+//! The results of a finished run, detached from the runner and the VM that produced them.
+
+use crate::stdlib::{
+    borrow::Cow,
+    collections::{BTreeMap, HashMap},
+    fmt,
+    prelude::*,
+};
+
+use felt::Felt252;
+
+use crate::{
+    types::program::Program,
+    vm::{
+        errors::{
+            cairo_run_errors::CairoRunError, runner_errors::RunnerError, trace_errors::TraceError,
+        },
+        runners::{
+            builtin_runner::OUTPUT_BUILTIN_NAME,
+            cairo_runner::{CairoRunner, ExecutionResources},
+            prover_input::PublicInput,
+        },
+        trace::{
+            call_stack::{CallStack, FunctionIndex},
+            trace_entry::TraceEntry,
+        },
+        unknown_hints::SkippedHint,
+        vm_core::VirtualMachine,
+    },
+};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RunOutcome {
+    pub program: Program,
+    pub execution_resources: ExecutionResources,
+    /// Relocated address of the first instruction of the program
+    pub program_base: usize,
+    /// Relocated trace, if the run had it enabled
+    pub trace: Option<Vec<TraceEntry>>,
+    pub relocated_memory: Vec<Option<Felt252>>,
+    /// Size of each memory segment, by segment index
+    pub segment_sizes: Vec<usize>,
+    /// Relocated values of the output builtin segment, empty if the program doesn't use it
+    pub output: Vec<Option<Felt252>>,
+    /// Unknown hints skipped during the run, see [VirtualMachine::set_unknown_hint_policy]
+    pub skipped_hints: Vec<SkippedHint>,
+    /// Public input of the AIR, if the run was in proof mode with its segments finalized
+    pub public_input: Option<PublicInput>,
+}
+
+impl RunOutcome {
+    /// Collects the results of a run. The run must have been relocated, as done by
+    /// [cairo_run](crate::cairo_run::cairo_run).
+    pub fn new(runner: &CairoRunner, vm: &VirtualMachine) -> Result<Self, Box<CairoRunError>> {
+        let trace = match vm.get_relocated_trace() {
+            Ok(trace) => Some(trace.clone()),
+            Err(TraceError::TraceNotEnabled) => None,
+            Err(error) => return Err(Box::new(error.into())),
+        };
+        let relocation_table = match runner.get_relocation_table() {
+            Some(relocation_table) => Cow::Borrowed(relocation_table),
+            None => Cow::Owned(
+                vm.segments
+                    .relocate_segments()
+                    .map_err(CairoRunError::from)?,
+            ),
+        };
+        let program_base = runner
+            .program_base
+            .ok_or(CairoRunError::Runner(RunnerError::NoProgBase))?;
+        let program_base = relocation_table
+            .get(program_base.segment_index as usize)
+            .ok_or(CairoRunError::Trace(TraceError::NoRelocationFound))?
+            + program_base.offset;
+        let segment_sizes = (0..relocation_table.len())
+            .filter_map(|index| vm.segments.get_segment_size(index))
+            .collect();
+        let output = match vm
+            .get_builtin_runners()
+            .iter()
+            .find(|builtin| builtin.name() == OUTPUT_BUILTIN_NAME)
+        {
+            Some(builtin) => {
+                let base = *relocation_table
+                    .get(builtin.base())
+                    .ok_or(CairoRunError::Trace(TraceError::NoRelocationFound))?;
+                let size = vm
+                    .segments
+                    .get_segment_used_size(builtin.base())
+                    .unwrap_or(0);
+                runner
+                    .relocated_memory
+                    .get(base..base + size)
+                    .map(<[_]>::to_vec)
+                    .unwrap_or_default()
+            }
+            None => Vec::new(),
+        };
+
+        Ok(RunOutcome {
+            program: runner.get_program().clone(),
+            execution_resources: runner
+                .get_execution_resources(vm)
+                .map_err(CairoRunError::from)?,
+            program_base,
+            trace,
+            relocated_memory: runner.relocated_memory.clone(),
+            segment_sizes,
+            output,
+            skipped_hints: vm.get_skipped_hints().to_vec(),
+            public_input: PublicInput::from_run(runner, vm).ok(),
+        })
+    }
+
+    /// Returns the steps and segment sizes of this run, to pre-size the trace and segments of
+    /// another run of the same workload.
+    pub fn size_hints(&self) -> RunSizeHints {
+        RunSizeHints {
+            n_steps: self.execution_resources.n_steps,
+            segment_sizes: self.segment_sizes.clone(),
+        }
+    }
+
+    /// Summarizes the changes in steps, memory holes, builtin usage, segment sizes and output from
+    /// this run to `other`, e.g. a run of a newer version of the same program.
+    pub fn diff(&self, other: &RunOutcome) -> RunOutcomeDiff {
+        let (before, after) = (&self.execution_resources, &other.execution_resources);
+        let mut builtin_names: Vec<&String> = before
+            .builtin_instance_counter
+            .keys()
+            .chain(after.builtin_instance_counter.keys())
+            .collect();
+        builtin_names.sort();
+        builtin_names.dedup();
+        let builtin_instances = builtin_names
+            .into_iter()
+            .filter_map(|name| {
+                let change = Change::new(
+                    before
+                        .builtin_instance_counter
+                        .get(name)
+                        .copied()
+                        .unwrap_or(0),
+                    after
+                        .builtin_instance_counter
+                        .get(name)
+                        .copied()
+                        .unwrap_or(0),
+                )?;
+                Some((name.clone(), change))
+            })
+            .collect();
+        let segment_sizes = (0..self.segment_sizes.len().max(other.segment_sizes.len()))
+            .filter_map(|index| {
+                let change = Change::new(
+                    self.segment_sizes.get(index).copied().unwrap_or(0),
+                    other.segment_sizes.get(index).copied().unwrap_or(0),
+                )?;
+                Some((index, change))
+            })
+            .collect();
+        let first_output_difference = (self.output != other.output).then(|| {
+            self.output
+                .iter()
+                .zip(&other.output)
+                .position(|(before, after)| before != after)
+                .unwrap_or_else(|| self.output.len().min(other.output.len()))
+        });
+
+        RunOutcomeDiff {
+            n_steps: Change::new(before.n_steps, after.n_steps),
+            n_memory_holes: Change::new(before.n_memory_holes, after.n_memory_holes),
+            builtin_instances,
+            segment_sizes,
+            first_output_difference,
+        }
+    }
+
+    /// Returns the steps spent in each call stack as `(stack, steps)` pairs sorted by stack, where
+    /// the stack is the `;`-separated list of the functions called, starting from the outermost one.
+    ///
+    /// Calls are detected through the fp of each trace entry: a new fp starts a frame for the
+    /// function containing its pc, while returning to the fp of an outer frame drops the inner ones.
+    pub fn folded_stacks(&self) -> Result<Vec<(String, usize)>, TraceError> {
+        let trace = self.trace.as_ref().ok_or(TraceError::TraceNotEnabled)?;
+
+        let functions = FunctionIndex::new(&self.program);
+        let mut call_stack = CallStack::default();
+        let mut stack = String::new();
+        let mut steps = HashMap::<String, usize>::new();
+        for entry in trace.iter() {
+            let pc = entry.pc.saturating_sub(self.program_base);
+            if call_stack.step(entry.fp, pc, &functions) {
+                stack = call_stack.folded();
+            }
+            *steps.entry(stack.clone()).or_default() += 1;
+        }
+
+        let mut folded_stacks: Vec<_> = steps.into_iter().collect();
+        folded_stacks.sort_unstable();
+        Ok(folded_stacks)
+    }
+
+    /// Writes the [folded stacks](Self::folded_stacks) of the run to `path`, one `stack steps`
+    /// line each, in the format consumed by `inferno-flamegraph` and `flamegraph.pl`.
+    #[cfg(feature = "std")]
+    pub fn write_flamegraph(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        use std::io::Write;
+
+        let folded_stacks = self.folded_stacks().map_err(std::io::Error::other)?;
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+        for (stack, steps) in folded_stacks {
+            writeln!(writer, "{stack} {steps}")?;
+        }
+        writer.flush()
+    }
+
+    /// Returns the line coverage of the Cairo source files of the program, sorted by filename.
+    ///
+    /// Each instruction with debug info is attributed to the line it starts at. A line counts as
+    /// many hits as steps were executed on the instructions starting there, lines whose
+    /// instructions were never executed have 0 hits. Programs compiled without debug info have no
+    /// coverage.
+    pub fn line_coverage(&self) -> Result<Vec<FileCoverage>, TraceError> {
+        let trace = self.trace.as_ref().ok_or(TraceError::TraceNotEnabled)?;
+
+        let mut executions = HashMap::<usize, usize>::new();
+        for entry in trace.iter() {
+            *executions
+                .entry(entry.pc.saturating_sub(self.program_base))
+                .or_default() += 1;
+        }
+
+        let mut files = BTreeMap::<&str, BTreeMap<u32, usize>>::new();
+        for location in self.program.locations_in_range(..) {
+            let inst = location.location();
+            *files
+                .entry(inst.input_file.filename.as_str())
+                .or_default()
+                .entry(inst.start_line)
+                .or_default() += executions.get(&location.pc).copied().unwrap_or_default();
+        }
+
+        Ok(files
+            .into_iter()
+            .map(|(filename, lines)| FileCoverage {
+                filename: filename.to_string(),
+                lines: lines.into_iter().collect(),
+            })
+            .collect())
+    }
+
+    /// Writes the [line coverage](Self::line_coverage) of the run to `path` as an lcov tracefile,
+    /// as consumed by `genhtml` and most coverage services.
+    #[cfg(feature = "std")]
+    pub fn write_lcov(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        use std::io::Write;
+
+        let coverage = self.line_coverage().map_err(std::io::Error::other)?;
+        let mut writer = std::io::BufWriter::new(std::fs::File::create(path)?);
+        writeln!(writer, "TN:")?;
+        for file in coverage {
+            writeln!(writer, "SF:{}", file.filename)?;
+            for (line, hits) in &file.lines {
+                writeln!(writer, "DA:{line},{hits}")?;
+            }
+            writeln!(writer, "LF:{}", file.lines.len())?;
+            writeln!(writer, "LH:{}", file.lines_hit())?;
+            writeln!(writer, "end_of_record")?;
+        }
+        writer.flush()
+    }
+}
+
+/// The line coverage of a source file, as returned by [RunOutcome::line_coverage].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileCoverage {
+    pub filename: String,
+    /// Hits of each line holding instructions, as `(line, hits)` pairs sorted by line
+    pub lines: Vec<(u32, usize)>,
+}
+
+impl FileCoverage {
+    /// Returns the number of lines executed at least once
+    pub fn lines_hit(&self) -> usize {
+        self.lines.iter().filter(|(_, hits)| *hits > 0).count()
+    }
+}
+
+/// Expected sizes of a run, used to allocate its trace and memory segments upfront instead of
+/// growing them as the run goes, see [RunOutcome::size_hints].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RunSizeHints {
+    /// Steps of the run, reserved in the trace if it is enabled
+    pub n_steps: usize,
+    /// Size of each memory segment, by segment index
+    pub segment_sizes: Vec<usize>,
+}
+
+impl RunSizeHints {
+    /// Reserves room for the steps and segments of the hinted run in `vm`.
+    /// Must be called before the segments are added, i.e. before initializing the runner.
+    pub fn apply(&self, vm: &mut VirtualMachine) {
+        vm.reserve_steps(self.n_steps);
+        vm.segments
+            .set_segment_capacities(self.segment_sizes.clone());
+    }
+}
+
+/// A resource count that differs between two runs
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Change {
+    pub before: usize,
+    pub after: usize,
+}
+
+impl Change {
+    // None if the count didn't change
+    fn new(before: usize, after: usize) -> Option<Self> {
+        (before != after).then_some(Change { before, after })
+    }
+
+    pub fn is_increase(&self) -> bool {
+        self.after > self.before
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (sign, delta) = if self.is_increase() {
+            ('+', self.after - self.before)
+        } else {
+            ('-', self.before - self.after)
+        };
+        write!(f, "{} -> {} ({sign}{delta})", self.before, self.after)
+    }
+}
+
+/// The differences between two runs, as returned by [RunOutcome::diff]. Only the resources that
+/// changed are listed.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RunOutcomeDiff {
+    pub n_steps: Option<Change>,
+    pub n_memory_holes: Option<Change>,
+    /// Changes in builtin instances, sorted by builtin name
+    pub builtin_instances: Vec<(String, Change)>,
+    /// Changes in segment sizes, by segment index
+    pub segment_sizes: Vec<(usize, Change)>,
+    /// Index of the first output cell that differs, if the outputs differ
+    pub first_output_difference: Option<usize>,
+}
+
+impl RunOutcomeDiff {
+    /// Returns true if both runs used the same resources and had the same output
+    pub fn is_empty(&self) -> bool {
+        self == &RunOutcomeDiff::default()
+    }
+
+    /// Returns true if the second run spent more steps, memory holes, builtin instances or
+    /// segment cells than the first one, to fail CI checks on resource regressions.
+    pub fn has_resource_regression(&self) -> bool {
+        self.n_steps
+            .iter()
+            .chain(&self.n_memory_holes)
+            .chain(self.builtin_instances.iter().map(|(_, change)| change))
+            .chain(self.segment_sizes.iter().map(|(_, change)| change))
+            .any(Change::is_increase)
+    }
+}
+
+impl fmt::Display for RunOutcomeDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_empty() {
+            return writeln!(f, "no changes");
+        }
+        if let Some(change) = &self.n_steps {
+            writeln!(f, "steps: {change}")?;
+        }
+        if let Some(change) = &self.n_memory_holes {
+            writeln!(f, "memory holes: {change}")?;
+        }
+        for (name, change) in &self.builtin_instances {
+            writeln!(f, "{name}: {change}")?;
+        }
+        for (index, change) in &self.segment_sizes {
+            writeln!(f, "segment {index}: {change}")?;
+        }
+        if let Some(index) = self.first_output_difference {
+            writeln!(f, "output differs from cell {index}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cairo_run::{cairo_run, CairoRunConfig},
+        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+        serde::deserialize_program::{Identifier, InputFile, InstructionLocation, Location},
+        utils::test_utils::*,
+    };
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    fn function(pc: usize) -> Identifier {
+        Identifier {
+            pc: Some(pc),
+            type_: Some(String::from("function")),
+            value: None,
+            full_name: None,
+            members: None,
+            cairo_type: None,
+        }
+    }
+
+    fn run_outcome(trace: Option<Vec<(usize, usize, usize)>>) -> RunOutcome {
+        RunOutcome {
+            program: program!(
+                identifiers = HashMap::from([
+                    (String::from("__main__.main"), function(0)),
+                    (String::from("__main__.f"), function(4)),
+                ]),
+            ),
+            execution_resources: ExecutionResources::default(),
+            program_base: 1,
+            trace: trace.map(|trace| {
+                trace
+                    .into_iter()
+                    .map(|(pc, ap, fp)| TraceEntry { pc, ap, fp })
+                    .collect()
+            }),
+            relocated_memory: Vec::new(),
+            segment_sizes: Vec::new(),
+            output: Vec::new(),
+            skipped_hints: Vec::new(),
+            public_input: None,
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn folded_stacks_follow_calls_and_returns() {
+        // main calls f, which calls itself once, then main calls f again
+        let run_outcome = run_outcome(Some(vec![
+            (1, 20, 20),
+            (2, 21, 20),
+            (5, 22, 22),
+            (6, 23, 22),
+            (5, 24, 24),
+            (7, 25, 24),
+            (7, 25, 22),
+            (3, 26, 20),
+            (5, 28, 28),
+            (4, 29, 20),
+        ]));
+        assert_eq!(
+            run_outcome.folded_stacks(),
+            Ok(vec![
+                (String::from("__main__.main"), 4),
+                (String::from("__main__.main;__main__.f"), 4),
+                (String::from("__main__.main;__main__.f;__main__.f"), 2),
+            ])
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn folded_stacks_without_trace() {
+        assert_eq!(
+            run_outcome(None).folded_stacks(),
+            Err(TraceError::TraceNotEnabled)
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_outcome_fibonacci_folded_stacks() {
+        let cairo_run_config = CairoRunConfig {
+            trace_enabled: true,
+            relocate_mem: true,
+            ..Default::default()
+        };
+        let (cairo_runner, vm) = cairo_run(
+            include_bytes!("../../../../cairo_programs/fibonacci.json"),
+            &cairo_run_config,
+            &mut BuiltinHintProcessor::new_empty(),
+        )
+        .unwrap();
+        let run_outcome = RunOutcome::new(&cairo_runner, &vm).unwrap();
+
+        assert_eq!(run_outcome.program_base, 1);
+        let folded_stacks = run_outcome.folded_stacks().unwrap();
+        assert_eq!(
+            folded_stacks.iter().map(|(_, steps)| steps).sum::<usize>(),
+            run_outcome.execution_resources.n_steps
+        );
+        // fib recurses 10 times below the call from main
+        let deepest_stack = folded_stacks
+            .iter()
+            .map(|(stack, _)| stack.split(';').collect::<Vec<_>>())
+            .max_by_key(|stack| stack.len())
+            .unwrap();
+        assert_eq!(deepest_stack.len(), 12);
+        assert_eq!(deepest_stack[0], "__main__.main");
+        assert!(deepest_stack[1..]
+            .iter()
+            .all(|name| *name == "__main__.fib"));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_outcome_requires_relocation() {
+        let program = program!();
+        let cairo_runner = cairo_runner!(program);
+        let vm = vm!(true);
+        assert_matches!(
+            RunOutcome::new(&cairo_runner, &vm).map_err(|error| *error),
+            Err(CairoRunError::Trace(TraceError::TraceNotRelocated))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn diff_identical_runs() {
+        let run_outcome = run_outcome(None);
+        let diff = run_outcome.diff(&run_outcome);
+        assert!(diff.is_empty());
+        assert!(!diff.has_resource_regression());
+        assert_eq!(diff.to_string(), "no changes\n");
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn diff_changed_resources() {
+        let mut before = run_outcome(None);
+        before.execution_resources = ExecutionResources {
+            n_steps: 100,
+            n_memory_holes: 3,
+            builtin_instance_counter: BTreeMap::from([
+                (String::from("range_check_builtin"), 10),
+                (String::from("output_builtin"), 2),
+            ]),
+        };
+        before.segment_sizes = vec![20, 120, 2, 10];
+        before.output = vec![Some(Felt252::new(1)), Some(Felt252::new(2))];
+        let mut after = before.clone();
+        after.execution_resources.n_steps = 90;
+        after
+            .execution_resources
+            .builtin_instance_counter
+            .insert(String::from("range_check_builtin"), 12);
+        after
+            .execution_resources
+            .builtin_instance_counter
+            .insert(String::from("bitwise_builtin"), 1);
+        after.segment_sizes = vec![20, 110, 2, 12, 5];
+        after.output = vec![Some(Felt252::new(1)), Some(Felt252::new(3))];
+
+        let diff = before.diff(&after);
+        assert_eq!(
+            diff,
+            RunOutcomeDiff {
+                n_steps: Some(Change {
+                    before: 100,
+                    after: 90
+                }),
+                n_memory_holes: None,
+                builtin_instances: vec![
+                    (
+                        String::from("bitwise_builtin"),
+                        Change {
+                            before: 0,
+                            after: 1
+                        }
+                    ),
+                    (
+                        String::from("range_check_builtin"),
+                        Change {
+                            before: 10,
+                            after: 12
+                        }
+                    ),
+                ],
+                segment_sizes: vec![
+                    (
+                        1,
+                        Change {
+                            before: 120,
+                            after: 110
+                        }
+                    ),
+                    (
+                        3,
+                        Change {
+                            before: 10,
+                            after: 12
+                        }
+                    ),
+                    (
+                        4,
+                        Change {
+                            before: 0,
+                            after: 5
+                        }
+                    ),
+                ],
+                first_output_difference: Some(1),
+            }
+        );
+        assert!(diff.has_resource_regression());
+        assert_eq!(
+            diff.to_string(),
+            "steps: 100 -> 90 (-10)\n\
+             bitwise_builtin: 0 -> 1 (+1)\n\
+             range_check_builtin: 10 -> 12 (+2)\n\
+             segment 1: 120 -> 110 (-10)\n\
+             segment 3: 10 -> 12 (+2)\n\
+             segment 4: 0 -> 5 (+5)\n\
+             output differs from cell 1\n"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn diff_shorter_output_without_regression() {
+        let mut before = run_outcome(None);
+        before.execution_resources.n_steps = 100;
+        before.output = vec![Some(Felt252::new(1)), Some(Felt252::new(2))];
+        let mut after = before.clone();
+        after.execution_resources.n_steps = 80;
+        after.output.pop();
+
+        let diff = before.diff(&after);
+        assert_eq!(diff.first_output_difference, Some(1));
+        assert!(!diff.has_resource_regression());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_outcome_output_and_segment_sizes() {
+        let cairo_run_config = CairoRunConfig {
+            relocate_mem: true,
+            layout: "all_cairo",
+            ..Default::default()
+        };
+        let (cairo_runner, vm) = cairo_run(
+            include_bytes!("../../../../cairo_programs/bitwise_output.json"),
+            &cairo_run_config,
+            &mut BuiltinHintProcessor::new_empty(),
+        )
+        .unwrap();
+        let run_outcome = RunOutcome::new(&cairo_runner, &vm).unwrap();
+
+        assert_eq!(run_outcome.output, vec![Some(Felt252::new(0))]);
+        assert_eq!(
+            run_outcome.segment_sizes.len(),
+            vm.segments.segment_used_sizes.as_ref().unwrap().len()
+        );
+        assert!(run_outcome.diff(&run_outcome).is_empty());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn rerun_with_size_hints() {
+        let program = include_bytes!("../../../../cairo_programs/fibonacci.json");
+        let cairo_run_config = CairoRunConfig {
+            trace_enabled: true,
+            relocate_mem: true,
+            ..Default::default()
+        };
+        let (cairo_runner, vm) = cairo_run(
+            program,
+            &cairo_run_config,
+            &mut BuiltinHintProcessor::new_empty(),
+        )
+        .unwrap();
+        let first_run = RunOutcome::new(&cairo_runner, &vm).unwrap();
+        let size_hints = first_run.size_hints();
+        assert_eq!(size_hints.n_steps, first_run.execution_resources.n_steps);
+
+        let cairo_run_config = CairoRunConfig {
+            size_hints: Some(&size_hints),
+            ..cairo_run_config
+        };
+        let (cairo_runner, vm) = cairo_run(
+            program,
+            &cairo_run_config,
+            &mut BuiltinHintProcessor::new_empty(),
+        )
+        .unwrap();
+        for (segment, size) in vm
+            .segments
+            .memory
+            .data
+            .iter()
+            .zip(&size_hints.segment_sizes)
+        {
+            assert!(segment.capacity() >= *size);
+        }
+        assert!(vm.trace.as_ref().unwrap().capacity() >= size_hints.n_steps);
+        assert!(RunOutcome::new(&cairo_runner, &vm)
+            .unwrap()
+            .diff(&first_run)
+            .is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn write_flamegraph() {
+        let run_outcome = run_outcome(Some(vec![(1, 20, 20), (5, 22, 22)]));
+        let path = std::env::temp_dir().join("cairo_vm_run_outcome_flamegraph.folded");
+        run_outcome.write_flamegraph(&path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "__main__.main 1\n__main__.main;__main__.f 1\n"
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+
+    fn instruction_location(filename: &str, line: u32) -> InstructionLocation {
+        InstructionLocation {
+            inst: Location {
+                end_line: line,
+                end_col: 10,
+                input_file: InputFile {
+                    filename: String::from(filename),
+                },
+                parent_location: None,
+                start_line: line,
+                start_col: 1,
+            },
+            hints: Vec::new(),
+            flow_tracking_data: None,
+        }
+    }
+
+    fn run_outcome_with_locations(trace: Vec<usize>) -> RunOutcome {
+        let mut run_outcome =
+            run_outcome(Some(trace.into_iter().map(|pc| (pc + 1, 20, 20)).collect()));
+        run_outcome.program = program!(
+            instruction_locations = Some(HashMap::from([
+                (0, instruction_location("b.cairo", 1)),
+                (1, instruction_location("b.cairo", 2)),
+                (3, instruction_location("b.cairo", 2)),
+                (5, instruction_location("a.cairo", 4)),
+            ])),
+        );
+        run_outcome
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn line_coverage_counts_steps_per_line() {
+        // pc 6 has no debug info, pc 5 is never executed
+        let run_outcome = run_outcome_with_locations(vec![0, 1, 3, 1, 3, 6]);
+        let coverage = run_outcome.line_coverage().unwrap();
+        assert_eq!(
+            coverage,
+            [
+                FileCoverage {
+                    filename: String::from("a.cairo"),
+                    lines: vec![(4, 0)],
+                },
+                FileCoverage {
+                    filename: String::from("b.cairo"),
+                    lines: vec![(1, 1), (2, 4)],
+                },
+            ]
+        );
+        assert_eq!(coverage[0].lines_hit(), 0);
+        assert_eq!(coverage[1].lines_hit(), 2);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn line_coverage_without_trace_or_debug_info() {
+        assert_eq!(
+            run_outcome(None).line_coverage(),
+            Err(TraceError::TraceNotEnabled)
+        );
+        assert_eq!(
+            run_outcome(Some(vec![(1, 20, 20)])).line_coverage(),
+            Ok(Vec::new())
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn run_outcome_fibonacci_line_coverage() {
+        let cairo_run_config = CairoRunConfig {
+            trace_enabled: true,
+            relocate_mem: true,
+            ..Default::default()
+        };
+        let (cairo_runner, vm) = cairo_run(
+            include_bytes!("../../../../cairo_programs/fibonacci.json"),
+            &cairo_run_config,
+            &mut BuiltinHintProcessor::new_empty(),
+        )
+        .unwrap();
+        let run_outcome = RunOutcome::new(&cairo_runner, &vm).unwrap();
+
+        let coverage = run_outcome.line_coverage().unwrap();
+        assert_eq!(coverage.len(), 1);
+        assert!(coverage[0].filename.ends_with("fibonacci.cairo"));
+        // Every instruction of the program is executed
+        assert_eq!(coverage[0].lines_hit(), coverage[0].lines.len());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn write_lcov() {
+        let run_outcome = run_outcome_with_locations(vec![0, 1, 3]);
+        let path = std::env::temp_dir().join("cairo_vm_run_outcome_coverage.lcov");
+        run_outcome.write_lcov(&path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "TN:\n\
+             SF:a.cairo\nDA:4,0\nLF:1\nLH:0\nend_of_record\n\
+             SF:b.cairo\nDA:1,1\nDA:2,2\nLF:2\nLH:2\nend_of_record\n"
+        );
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,551 @@
+//! Validation of the syscall segment of Starknet contract runs.
+//!
+//! A contract makes a syscall by writing a request at its syscall pointer, made of the selector
+//! of the syscall, a short string such as `'StorageRead'`, the gas and the arguments of the
+//! syscall. The hint processor handling the syscall writes the response right after it: the gas
+//! left, a failure flag and, on success, the values returned by the syscall, or on failure the
+//! start and end of the revert reason. The contract then moves its syscall pointer past the
+//! response, and returns the final one.
+//!
+//! A hint processor writing responses of the wrong size, or at the wrong place, leaves the
+//! segment malformed without failing the run, so the error only surfaces when proving it.
+//! [SyscallSegmentValidator] walks the segment after the run, and reports the syscalls found and
+//! the [SyscallViolation]s of the structure above.
+
+use crate::stdlib::{fmt, prelude::*};
+
+use felt::Felt252;
+use num_traits::{One, Zero};
+
+use crate::{
+    types::{
+        errors::math_errors::MathError,
+        relocatable::{MaybeRelocatable, Relocatable},
+        short_string::{encode_short_string, escape_short_string},
+    },
+    vm::{errors::runner_errors::RunnerError, vm_core::VirtualMachine},
+};
+
+/// The syscalls of Starknet, with the cells of their requests after the selector and gas, and
+/// of their successful responses after the gas and failure flag.
+pub const STARKNET_SYSCALLS: [(&str, usize, usize); 11] = [
+    ("CallContract", 4, 2),
+    ("Deploy", 5, 3),
+    ("EmitEvent", 4, 0),
+    ("GetBlockHash", 1, 1),
+    ("GetExecutionInfo", 0, 1),
+    ("Keccak", 2, 2),
+    ("LibraryCall", 4, 2),
+    ("ReplaceClass", 1, 0),
+    ("SendMessageToL1", 3, 0),
+    ("StorageRead", 2, 1),
+    ("StorageWrite", 3, 0),
+];
+
+// The selector and gas of a request
+const REQUEST_HEADER_LEN: usize = 2;
+// The gas and failure flag of a response
+const RESPONSE_HEADER_LEN: usize = 2;
+// The header of a failed response, followed by the start and end of the revert reason
+const FAILED_RESPONSE_LEN: usize = RESPONSE_HEADER_LEN + 2;
+
+/// The number of cells of the request and response of a syscall.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyscallLayout {
+    pub selector: Felt252,
+    /// The cells of the request after the selector and gas
+    pub request_len: usize,
+    /// The cells of a successful response after the gas and failure flag
+    pub response_len: usize,
+}
+
+impl SyscallLayout {
+    /// Returns the layout of the syscall whose selector is the short string `name`.
+    pub fn new(name: &str, request_len: usize, response_len: usize) -> Result<Self, MathError> {
+        Ok(SyscallLayout {
+            selector: encode_short_string(name)?,
+            request_len,
+            response_len,
+        })
+    }
+}
+
+/// A syscall found in the syscall segment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SyscallRecord {
+    pub selector: Felt252,
+    pub request: Relocatable,
+    pub response: Relocatable,
+    pub failed: bool,
+}
+
+/// A break of the structure of the syscall segment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SyscallViolation {
+    /// The final syscall pointer isn't a pointer to the syscall segment at or after its start
+    NonMonotonicPointer {
+        start: Relocatable,
+        end: MaybeRelocatable,
+    },
+    /// A cell of a request or response was never written
+    MissingCell(Relocatable),
+    /// A request starts with a selector of no known syscall
+    UnknownSelector {
+        address: Relocatable,
+        selector: MaybeRelocatable,
+    },
+    /// The failure flag of a response is neither 0 nor 1
+    InvalidFailureFlag {
+        address: Relocatable,
+        value: MaybeRelocatable,
+    },
+    /// The syscall starting at `address` ends after the final syscall pointer
+    Truncated {
+        address: Relocatable,
+        end: Relocatable,
+    },
+    /// Cells were written after the final syscall pointer, outside of any syscall
+    TrailingCells { address: Relocatable, count: usize },
+}
+
+impl fmt::Display for SyscallViolation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SyscallViolation::NonMonotonicPointer { start, end } => write!(
+                f,
+                "The final syscall pointer {end} is not in the syscall segment starting at {start}, or is before its start"
+            ),
+            SyscallViolation::MissingCell(address) => {
+                write!(f, "The syscall cell {address} was never written")
+            }
+            SyscallViolation::UnknownSelector {
+                address,
+                selector: MaybeRelocatable::Int(selector),
+            } => write!(
+                f,
+                "Unknown syscall '{}' requested at {address}",
+                escape_short_string(selector)
+            ),
+            SyscallViolation::UnknownSelector { address, selector } => {
+                write!(f, "Unknown syscall {selector} requested at {address}")
+            }
+            SyscallViolation::InvalidFailureFlag { address, value } => write!(
+                f,
+                "The failure flag of the syscall response at {address} is {value}, expected 0 or 1"
+            ),
+            SyscallViolation::Truncated { address, end } => write!(
+                f,
+                "The syscall requested at {address} ends after the final syscall pointer {end}"
+            ),
+            SyscallViolation::TrailingCells { address, count } => write!(
+                f,
+                "{count} cells were written from {address}, after the final syscall pointer"
+            ),
+        }
+    }
+}
+
+/// The syscalls found in the syscall segment of a run, and the violations of its structure.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SyscallSegmentReport {
+    pub syscalls: Vec<SyscallRecord>,
+    pub violations: Vec<SyscallViolation>,
+}
+
+impl SyscallSegmentReport {
+    pub fn is_valid(&self) -> bool {
+        self.violations.is_empty()
+    }
+
+    /// Fails with the first violation, if any.
+    pub fn check(&self) -> Result<(), RunnerError> {
+        match self.violations.first() {
+            Some(violation) => Err(RunnerError::InvalidSyscallSegment(Box::new(
+                violation.clone(),
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Walks the syscall segment of a finished run, knowing the layouts of the syscalls it may hold.
+#[derive(Clone, Debug)]
+pub struct SyscallSegmentValidator {
+    layouts: Vec<SyscallLayout>,
+}
+
+impl Default for SyscallSegmentValidator {
+    fn default() -> Self {
+        let layouts = STARKNET_SYSCALLS
+            .iter()
+            .map(|(name, request_len, response_len)| {
+                SyscallLayout::new(name, *request_len, *response_len)
+                    .expect("The names of the syscalls are short strings")
+            })
+            .collect();
+        SyscallSegmentValidator { layouts }
+    }
+}
+
+impl SyscallSegmentValidator {
+    /// Returns a validator knowing the [STARKNET_SYSCALLS].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the layout of a syscall, replacing the one with the same selector if any.
+    pub fn with_syscall(mut self, layout: SyscallLayout) -> Self {
+        self.layouts
+            .retain(|known| known.selector != layout.selector);
+        self.layouts.push(layout);
+        self
+    }
+
+    /// Walks the syscalls written from `start`, the syscall pointer given to the run, up to
+    /// `end`, the one it returned. The walk stops at the first syscall it can't parse.
+    pub fn validate(
+        &self,
+        vm: &VirtualMachine,
+        start: Relocatable,
+        end: &MaybeRelocatable,
+    ) -> SyscallSegmentReport {
+        let mut report = SyscallSegmentReport::default();
+        let end = match end {
+            MaybeRelocatable::RelocatableValue(end)
+                if end.segment_index == start.segment_index && end.offset >= start.offset =>
+            {
+                *end
+            }
+            _ => {
+                report
+                    .violations
+                    .push(SyscallViolation::NonMonotonicPointer {
+                        start,
+                        end: end.clone(),
+                    });
+                return report;
+            }
+        };
+
+        let mut request = start;
+        while request < end {
+            match self.read_syscall(vm, request, end) {
+                Ok((syscall, syscall_end)) => {
+                    report.violations.extend(
+                        missing_cells(vm, request, syscall_end).map(SyscallViolation::MissingCell),
+                    );
+                    report.syscalls.push(syscall);
+                    request = syscall_end;
+                }
+                Err(violation) => {
+                    report.violations.push(violation);
+                    break;
+                }
+            }
+        }
+
+        let trailing: Vec<Relocatable> = written_cells_from(vm, end).collect();
+        if let Some(address) = trailing.first() {
+            report.violations.push(SyscallViolation::TrailingCells {
+                address: *address,
+                count: trailing.len(),
+            });
+        }
+        report
+    }
+
+    // Returns the syscall requested at `request`, and the end of its response
+    fn read_syscall(
+        &self,
+        vm: &VirtualMachine,
+        request: Relocatable,
+        end: Relocatable,
+    ) -> Result<(SyscallRecord, Relocatable), SyscallViolation> {
+        let selector = vm
+            .get_maybe(&request)
+            .ok_or(SyscallViolation::MissingCell(request))?;
+        let layout = match &selector {
+            MaybeRelocatable::Int(felt) => {
+                self.layouts.iter().find(|layout| &layout.selector == felt)
+            }
+            MaybeRelocatable::RelocatableValue(_) => None,
+        }
+        .ok_or_else(|| SyscallViolation::UnknownSelector {
+            address: request,
+            selector: selector.clone(),
+        })?;
+        let truncated = SyscallViolation::Truncated {
+            address: request,
+            end,
+        };
+
+        let response =
+            (request + (REQUEST_HEADER_LEN + layout.request_len)).map_err(|_| truncated.clone())?;
+        let failure_flag_address = (response + 1_usize).map_err(|_| truncated.clone())?;
+        if failure_flag_address >= end {
+            return Err(truncated);
+        }
+        let failed = match vm.get_maybe(&failure_flag_address) {
+            Some(MaybeRelocatable::Int(flag)) if flag.is_zero() => false,
+            Some(MaybeRelocatable::Int(flag)) if flag.is_one() => true,
+            Some(value) => {
+                return Err(SyscallViolation::InvalidFailureFlag {
+                    address: response,
+                    value,
+                })
+            }
+            None => return Err(SyscallViolation::MissingCell(failure_flag_address)),
+        };
+        let response_len = if failed {
+            FAILED_RESPONSE_LEN
+        } else {
+            RESPONSE_HEADER_LEN + layout.response_len
+        };
+        match response + response_len {
+            Ok(syscall_end) if syscall_end <= end => Ok((
+                SyscallRecord {
+                    selector: layout.selector.clone(),
+                    request,
+                    response,
+                    failed,
+                },
+                syscall_end,
+            )),
+            _ => Err(truncated),
+        }
+    }
+}
+
+// The addresses from `start` up to `end`, of the same segment, holding no value
+fn missing_cells(
+    vm: &VirtualMachine,
+    start: Relocatable,
+    end: Relocatable,
+) -> impl Iterator<Item = Relocatable> + '_ {
+    (start.offset..end.offset)
+        .map(move |offset| Relocatable::from((start.segment_index, offset)))
+        .filter(|address| vm.get_maybe(address).is_none())
+}
+
+// The addresses of the segment of `start` holding a value, from `start` on
+fn written_cells_from(
+    vm: &VirtualMachine,
+    start: Relocatable,
+) -> impl Iterator<Item = Relocatable> + '_ {
+    let segment_len = usize::try_from(start.segment_index)
+        .ok()
+        .and_then(|index| vm.segments.memory.data.get(index))
+        .map_or(0, |segment| segment.len());
+    (start.offset..segment_len)
+        .map(move |offset| Relocatable::from((start.segment_index, offset)))
+        .filter(|address| vm.get_maybe(address).is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::*;
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    fn short_string(name: &str) -> MaybeRelocatable {
+        encode_short_string(name).unwrap().into()
+    }
+
+    // Returns a vm whose segment 1 holds `cells`, None leaving a cell unwritten
+    fn syscall_segment(cells: &[Option<MaybeRelocatable>]) -> VirtualMachine {
+        let mut vm = vm!();
+        vm.segments.add();
+        let base = vm.segments.add();
+        for (offset, cell) in cells.iter().enumerate() {
+            if let Some(value) = cell {
+                vm.insert_value((base + offset).unwrap(), value.clone())
+                    .unwrap();
+            }
+        }
+        vm
+    }
+
+    // A successful StorageRead of address 7 returning 9, followed by a failed StorageWrite
+    fn storage_syscalls() -> Vec<Option<MaybeRelocatable>> {
+        vec![
+            Some(short_string("StorageRead")),
+            Some(100.into()),
+            Some(0.into()),
+            Some(7.into()),
+            Some(90.into()),
+            Some(0.into()),
+            Some(9.into()),
+            Some(short_string("StorageWrite")),
+            Some(90.into()),
+            Some(0.into()),
+            Some(7.into()),
+            Some(10.into()),
+            Some(80.into()),
+            Some(1.into()),
+            Some(Relocatable::from((0, 0)).into()),
+            Some(Relocatable::from((0, 1)).into()),
+        ]
+    }
+
+    fn end_at(offset: usize) -> MaybeRelocatable {
+        Relocatable::from((1, offset)).into()
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn validate_well_formed_segment() {
+        let vm = syscall_segment(&storage_syscalls());
+        let report = SyscallSegmentValidator::new().validate(&vm, (1, 0).into(), &end_at(16));
+        assert_eq!(
+            report,
+            SyscallSegmentReport {
+                syscalls: vec![
+                    SyscallRecord {
+                        selector: encode_short_string("StorageRead").unwrap(),
+                        request: (1, 0).into(),
+                        response: (1, 4).into(),
+                        failed: false,
+                    },
+                    SyscallRecord {
+                        selector: encode_short_string("StorageWrite").unwrap(),
+                        request: (1, 7).into(),
+                        response: (1, 12).into(),
+                        failed: true,
+                    },
+                ],
+                violations: Vec::new(),
+            }
+        );
+        assert!(report.is_valid());
+        assert_eq!(report.check(), Ok(()));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn validate_empty_segment() {
+        let vm = syscall_segment(&[]);
+        let report = SyscallSegmentValidator::new().validate(&vm, (1, 0).into(), &end_at(0));
+        assert_eq!(report, SyscallSegmentReport::default());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn validate_non_monotonic_pointer() {
+        let vm = syscall_segment(&storage_syscalls());
+        for end in [
+            MaybeRelocatable::from(Relocatable::from((1, 2))),
+            MaybeRelocatable::from(Relocatable::from((2, 16))),
+            MaybeRelocatable::from(16),
+        ] {
+            let report = SyscallSegmentValidator::new().validate(&vm, (1, 4).into(), &end);
+            assert_eq!(
+                report.violations,
+                [SyscallViolation::NonMonotonicPointer {
+                    start: (1, 4).into(),
+                    end
+                }]
+            );
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn validate_response_written_past_the_pointer() {
+        // The handler wrote a response one cell longer than the contract expects
+        let mut cells = storage_syscalls();
+        cells.insert(7, Some(10.into()));
+        let vm = syscall_segment(&cells);
+        let report = SyscallSegmentValidator::new().validate(&vm, (1, 0).into(), &end_at(16));
+        assert_eq!(report.syscalls.len(), 1);
+        assert_eq!(
+            report.violations,
+            [
+                SyscallViolation::UnknownSelector {
+                    address: (1, 7).into(),
+                    selector: 10.into(),
+                },
+                SyscallViolation::TrailingCells {
+                    address: (1, 16).into(),
+                    count: 1,
+                },
+            ]
+        );
+        assert_matches!(
+            report.check(),
+            Err(RunnerError::InvalidSyscallSegment(violation))
+                if violation.to_string() == "Unknown syscall '\\n' requested at 1:7"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn validate_missing_and_invalid_cells() {
+        let mut cells = storage_syscalls();
+        cells[3] = None;
+        let vm = syscall_segment(&cells);
+        let report = SyscallSegmentValidator::new().validate(&vm, (1, 0).into(), &end_at(16));
+        assert_eq!(
+            report.violations,
+            [SyscallViolation::MissingCell((1, 3).into())]
+        );
+        assert_eq!(report.syscalls.len(), 2);
+
+        let mut cells = storage_syscalls();
+        cells[5] = Some(2.into());
+        let vm = syscall_segment(&cells);
+        let report = SyscallSegmentValidator::new().validate(&vm, (1, 0).into(), &end_at(16));
+        assert_eq!(
+            report.violations,
+            [SyscallViolation::InvalidFailureFlag {
+                address: (1, 4).into(),
+                value: 2.into(),
+            }]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn validate_truncated_syscall() {
+        let vm = syscall_segment(&storage_syscalls());
+        let report = SyscallSegmentValidator::new().validate(&vm, (1, 0).into(), &end_at(14));
+        assert_eq!(
+            report.violations,
+            [
+                SyscallViolation::Truncated {
+                    address: (1, 7).into(),
+                    end: (1, 14).into(),
+                },
+                SyscallViolation::TrailingCells {
+                    address: (1, 14).into(),
+                    count: 2,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn validate_custom_syscall() {
+        let vm = syscall_segment(&[
+            Some(short_string("GetRandom")),
+            Some(100.into()),
+            Some(90.into()),
+            Some(0.into()),
+            Some(4.into()),
+        ]);
+        let report = SyscallSegmentValidator::new().validate(&vm, (1, 0).into(), &end_at(5));
+        assert_matches!(
+            report.violations.as_slice(),
+            [SyscallViolation::UnknownSelector { .. }]
+        );
+
+        let validator = SyscallSegmentValidator::new()
+            .with_syscall(SyscallLayout::new("GetRandom", 0, 1).unwrap());
+        let report = validator.validate(&vm, (1, 0).into(), &end_at(5));
+        assert!(report.is_valid());
+        assert_eq!(report.syscalls.len(), 1);
+    }
+}
@@ -0,0 +1,114 @@
+//! Entrypoints and builtins a runner accepts, to run programs from untrusted sources.
+//!
+//! Shared infrastructure executing user-supplied programs, such as contract classes, only means
+//! for some of their functions to be called and some builtins to be used. Once a [RunnerWhitelist]
+//! is set with
+//! [CairoRunner::set_whitelist](crate::vm::runners::cairo_runner::CairoRunner::set_whitelist),
+//! the runner rejects programs requesting other builtins and calls to
+//! [run_from_entrypoint](crate::vm::runners::cairo_runner::CairoRunner::run_from_entrypoint)
+//! targeting other pcs.
+
+use crate::stdlib::{collections::HashSet, prelude::*};
+
+use crate::{serde::deserialize_program::BuiltinName, types::program::Program};
+
+/// The entrypoints and builtins a runner accepts.
+///
+/// Entrypoints are allowed by pc or by function name. Names are resolved against the program being
+/// run, so the same whitelist can be shared by every program exposing the same functions, and can
+/// be either full identifier names or names of functions in `__main__`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RunnerWhitelist {
+    entrypoint_pcs: HashSet<usize>,
+    functions: HashSet<String>,
+    builtins: HashSet<BuiltinName>,
+}
+
+impl RunnerWhitelist {
+    /// Returns an empty whitelist, rejecting every entrypoint and builtin.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn allow_entrypoint(mut self, pc: usize) -> Self {
+        self.entrypoint_pcs.insert(pc);
+        self
+    }
+
+    pub fn allow_function(mut self, name: &str) -> Self {
+        self.functions.insert(name.to_string());
+        self
+    }
+
+    pub fn allow_builtin(mut self, builtin: BuiltinName) -> Self {
+        self.builtins.insert(builtin);
+        self
+    }
+
+    /// Returns true if `pc` is whitelisted, or is the pc of a whitelisted function of `program`.
+    pub fn allows_entrypoint(&self, program: &Program, pc: usize) -> bool {
+        self.entrypoint_pcs.contains(&pc)
+            || self
+                .functions
+                .iter()
+                .any(|name| function_pc(program, name) == Some(pc))
+    }
+
+    pub fn allows_builtin(&self, builtin: &BuiltinName) -> bool {
+        self.builtins.contains(builtin)
+    }
+}
+
+fn function_pc(program: &Program, name: &str) -> Option<usize> {
+    [name.to_string(), format!("__main__.{name}")]
+        .iter()
+        .filter_map(|full_name| program.get_identifier(full_name))
+        .find(|identifier| identifier.type_.as_deref() == Some("function"))
+        .and_then(|identifier| identifier.pc)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn allows_entrypoint_by_pc_and_function_name() {
+        let program = Program::from_bytes(
+            include_bytes!("../../../../cairo_programs/example_program.json"),
+            None,
+        )
+        .unwrap();
+        let main_pc = program.get_identifier("__main__.main").unwrap().pc.unwrap();
+        let fib_pc = program
+            .get_identifier("__main__.evaluate_fib")
+            .unwrap()
+            .pc
+            .unwrap();
+
+        let whitelist = RunnerWhitelist::new();
+        assert!(!whitelist.allows_entrypoint(&program, main_pc));
+
+        let whitelist = RunnerWhitelist::new().allow_entrypoint(main_pc);
+        assert!(whitelist.allows_entrypoint(&program, main_pc));
+        assert!(!whitelist.allows_entrypoint(&program, fib_pc));
+
+        let whitelist = RunnerWhitelist::new().allow_function("evaluate_fib");
+        assert!(!whitelist.allows_entrypoint(&program, main_pc));
+        assert!(whitelist.allows_entrypoint(&program, fib_pc));
+
+        let whitelist = RunnerWhitelist::new().allow_function("__main__.main");
+        assert!(whitelist.allows_entrypoint(&program, main_pc));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn allows_builtin() {
+        let whitelist = RunnerWhitelist::new().allow_builtin(BuiltinName::range_check);
+        assert!(whitelist.allows_builtin(&BuiltinName::range_check));
+        assert!(!whitelist.allows_builtin(&BuiltinName::output));
+    }
+}
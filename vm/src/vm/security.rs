@@ -17,6 +17,7 @@ use crate::types::relocatable::MaybeRelocatable;
 ///     the builtins themselves.
 ///   - There must not be accesses to the program segment outside the program
 ///     data range. This check will use the `program_segment_size` instead of the program data length if available.
+///   - There must not be accesses to the constant segments outside their data range.
 ///   - All addresses in memory must be real (not temporary)
 ///
 /// Note: Each builtin is responsible for checking its own segments' data.
@@ -60,6 +61,20 @@ pub fn verify_secure_runner(
     if program_length >= Some(program_segment_size + 1) {
         return Err(VirtualMachineError::OutOfBoundsProgramSegmentAccess);
     }
+    // Check that constant segments weren't written past their end
+    for (index, size) in runner.constant_segments.iter() {
+        let current_size = vm
+            .segments
+            .memory
+            .data
+            .get(*index)
+            .map(|segment| segment.len());
+        if current_size > Some(*size) {
+            return Err(VirtualMachineError::OutOfBoundsConstantSegmentAccess(
+                *index,
+            ));
+        }
+    }
     // Check that the addresses in memory are valid
     // This means that every temporary address has been properly relocated to a real address
     // Asumption: If temporary memory is empty, this means no temporary memory addresses were generated and all addresses in memory are real
@@ -291,4 +306,28 @@ mod test {
             )) if *bx == relocatable!(-3, 2)
         );
     }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn verify_secure_runner_constant_segment_access_out_of_bounds() {
+        let program = program!(main = Some(0),);
+        let mut runner = cairo_runner!(program);
+        let mut vm = vm!();
+        runner.initialize(&mut vm).unwrap();
+        let base = runner
+            .load_constant_segment(&mut vm, &[Felt252::new(1), Felt252::new(2)])
+            .unwrap();
+        vm.segments.compute_effective_sizes();
+
+        assert_matches!(verify_secure_runner(&runner, true, None, &mut vm), Ok(()));
+
+        vm.segments
+            .memory
+            .insert((base + 2usize).unwrap(), &MaybeRelocatable::from(3))
+            .unwrap();
+        assert_matches!(
+            verify_secure_runner(&runner, true, None, &mut vm),
+            Err(VirtualMachineError::OutOfBoundsConstantSegmentAccess(index)) if index as isize == base.segment_index
+        );
+    }
 }
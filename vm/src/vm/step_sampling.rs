@@ -0,0 +1,155 @@
+//! Periodic samples of the state of a run, taken every given number of steps.
+//!
+//! Sampling records much less than the trace, only the pc and the size of each segment every
+//! `interval` steps, so it can stay enabled on long runs to estimate their progress or find the
+//! code they spend their steps in. Checking whether a sample is due is a single comparison per
+//! step.
+
+use crate::stdlib::prelude::*;
+
+use crate::{types::relocatable::Relocatable, vm::vm_core::VirtualMachine};
+
+/// The state of the run after `step` steps.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StepSample {
+    pub step: usize,
+    /// The next instruction to run
+    pub pc: Relocatable,
+    /// Number of cells of each segment, by segment index, temporary segments excluded
+    pub segment_sizes: Vec<usize>,
+}
+
+/// Takes a [StepSample] every `interval` steps, see [VirtualMachine::enable_step_sampling].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StepSampler {
+    interval: usize,
+    next_sample_step: usize,
+    samples: Vec<StepSample>,
+}
+
+impl StepSampler {
+    /// Creates a sampler taking a sample every `interval` steps, counted from the step it is set
+    /// at. An `interval` of 0 is taken as 1.
+    pub fn new(interval: usize, current_step: usize) -> Self {
+        let interval = interval.max(1);
+        StepSampler {
+            interval,
+            next_sample_step: current_step + interval,
+            samples: Vec::new(),
+        }
+    }
+
+    pub fn interval(&self) -> usize {
+        self.interval
+    }
+
+    pub fn samples(&self) -> &[StepSample] {
+        &self.samples
+    }
+
+    #[inline]
+    pub(crate) fn is_due(&self, step: usize) -> bool {
+        step >= self.next_sample_step
+    }
+
+    pub(crate) fn sample(&mut self, step: usize, pc: Relocatable, segment_sizes: Vec<usize>) {
+        self.samples.push(StepSample {
+            step,
+            pc,
+            segment_sizes,
+        });
+        self.next_sample_step = step + self.interval;
+    }
+}
+
+impl VirtualMachine {
+    /// Takes a [StepSample] every `interval` steps from now on, replacing the samples taken so far.
+    pub fn enable_step_sampling(&mut self, interval: usize) {
+        self.step_sampler = Some(StepSampler::new(interval, self.current_step));
+    }
+
+    /// Stops sampling, returning the samples taken, in step order.
+    pub fn disable_step_sampling(&mut self) -> Vec<StepSample> {
+        self.step_sampler
+            .take()
+            .map(|sampler| sampler.samples)
+            .unwrap_or_default()
+    }
+
+    /// Returns the samples taken so far, in step order, or an empty slice if sampling is disabled.
+    pub fn get_step_samples(&self) -> &[StepSample] {
+        self.step_sampler
+            .as_ref()
+            .map_or(&[], |sampler| sampler.samples())
+    }
+
+    // Called after each step, takes a sample if one is due
+    #[inline]
+    pub(crate) fn sample_step(&mut self) {
+        if let Some(sampler) = &mut self.step_sampler {
+            if sampler.is_due(self.current_step) {
+                let segment_sizes = self.segments.memory.data.iter().map(Vec::len).collect();
+                sampler.sample(self.current_step, self.run_context.pc, segment_sizes);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cairo_run::{cairo_run, CairoRunConfig},
+        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+    };
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn sampler_takes_a_sample_every_interval() {
+        let mut sampler = StepSampler::new(3, 2);
+        for step in 3..=11 {
+            if sampler.is_due(step) {
+                sampler.sample(step, Relocatable::from((0, step)), vec![step]);
+            }
+        }
+        let steps: Vec<usize> = sampler.samples().iter().map(|sample| sample.step).collect();
+        assert_eq!(steps, [5, 8, 11]);
+        assert_eq!(sampler.samples()[0].pc, Relocatable::from((0, 5)));
+
+        assert_eq!(StepSampler::new(0, 0).interval(), 1);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn sample_fibonacci_run() {
+        let cairo_run_config = CairoRunConfig {
+            step_sampling_interval: Some(10),
+            ..Default::default()
+        };
+        let (_, mut vm) = cairo_run(
+            include_bytes!("../../../cairo_programs/fibonacci.json"),
+            &cairo_run_config,
+            &mut BuiltinHintProcessor::new_empty(),
+        )
+        .unwrap();
+
+        // The run takes 80 steps
+        let samples = vm.get_step_samples();
+        assert_eq!(samples.len(), 8);
+        assert!(samples
+            .iter()
+            .enumerate()
+            .all(|(index, sample)| sample.step == (index + 1) * 10));
+        // The execution segment only grows
+        assert!(samples
+            .windows(2)
+            .all(|window| window[0].segment_sizes[1] <= window[1].segment_sizes[1]));
+        assert_eq!(samples[0].pc.segment_index, 0);
+
+        assert_eq!(vm.disable_step_sampling().len(), 8);
+        assert!(vm.get_step_samples().is_empty());
+    }
+}
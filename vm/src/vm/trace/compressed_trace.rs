@@ -0,0 +1,291 @@
+//! A compact representation of a trace.
+//!
+//! Each entry is stored as the difference of its registers with the previous entry, as zigzag
+//! encoded LEB128 varints in pc, ap, fp order. Consecutive entries usually differ by a few
+//! units, so most entries take 3 bytes instead of the 24 of a [TraceEntry].
+
+use crate::stdlib::prelude::*;
+
+use crate::vm::{errors::trace_errors::TraceError, trace::trace_entry::TraceEntry};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CompressedTrace {
+    bytes: Vec<u8>,
+    len: usize,
+    last: (usize, usize, usize),
+}
+
+impl CompressedTrace {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, entry: &TraceEntry) {
+        let (pc, ap, fp) = self.last;
+        write_delta(&mut self.bytes, pc, entry.pc);
+        write_delta(&mut self.bytes, ap, entry.ap);
+        write_delta(&mut self.bytes, fp, entry.fp);
+        self.last = (entry.pc, entry.ap, entry.fp);
+        self.len += 1;
+    }
+
+    /// Number of entries in the trace
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the entries of the trace, decompressing them on the fly.
+    pub fn iter(&self) -> CompressedTraceIter<'_> {
+        CompressedTraceIter {
+            bytes: &self.bytes,
+            remaining: self.len,
+            last: (0, 0, 0),
+        }
+    }
+
+    /// Adds `pc`, `ap` and `fp` to the registers of every entry, as relocating the trace does.
+    /// The entries are stored relative to the previous one, so only the first one is re-encoded.
+    pub fn shift(&mut self, pc: usize, ap: usize, fp: usize) {
+        let mut position = 0;
+        let Some((first_pc, first_ap, first_fp)) =
+            read_entry(&self.bytes, &mut position, (0, 0, 0))
+        else {
+            return;
+        };
+        let mut first = Vec::new();
+        write_delta(&mut first, 0, first_pc.wrapping_add(pc));
+        write_delta(&mut first, 0, first_ap.wrapping_add(ap));
+        write_delta(&mut first, 0, first_fp.wrapping_add(fp));
+        self.bytes.splice(..position, first);
+        let (last_pc, last_ap, last_fp) = self.last;
+        self.last = (
+            last_pc.wrapping_add(pc),
+            last_ap.wrapping_add(ap),
+            last_fp.wrapping_add(fp),
+        );
+    }
+
+    /// Returns the compressed entries, to be stored and read back with [CompressedTrace::from_bytes].
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Reads a trace written from [CompressedTrace::as_bytes].
+    /// Fails with the position of the first malformed entry if `bytes` isn't a whole trace.
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, TraceError> {
+        let mut position = 0;
+        let mut last = (0, 0, 0);
+        let mut len = 0;
+        while position < bytes.len() {
+            last = read_entry(&bytes, &mut position, last)
+                .ok_or(TraceError::InvalidCompressedTrace(len))?;
+            len += 1;
+        }
+        Ok(CompressedTrace { bytes, len, last })
+    }
+}
+
+impl<'a> Extend<&'a TraceEntry> for CompressedTrace {
+    fn extend<I: IntoIterator<Item = &'a TraceEntry>>(&mut self, iter: I) {
+        for entry in iter {
+            self.push(entry);
+        }
+    }
+}
+
+impl<'a> FromIterator<&'a TraceEntry> for CompressedTrace {
+    fn from_iter<I: IntoIterator<Item = &'a TraceEntry>>(iter: I) -> Self {
+        let mut trace = CompressedTrace::new();
+        trace.extend(iter);
+        trace
+    }
+}
+
+impl From<&[TraceEntry]> for CompressedTrace {
+    fn from(trace: &[TraceEntry]) -> Self {
+        trace.iter().collect()
+    }
+}
+
+impl<'a> IntoIterator for &'a CompressedTrace {
+    type Item = TraceEntry;
+    type IntoIter = CompressedTraceIter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct CompressedTraceIter<'a> {
+    bytes: &'a [u8],
+    remaining: usize,
+    last: (usize, usize, usize),
+}
+
+impl Iterator for CompressedTraceIter<'_> {
+    type Item = TraceEntry;
+
+    fn next(&mut self) -> Option<TraceEntry> {
+        let mut position = 0;
+        // Only well formed traces can be built, so reading fails only at the end
+        self.last = read_entry(self.bytes, &mut position, self.last)?;
+        self.bytes = &self.bytes[position..];
+        self.remaining -= 1;
+        let (pc, ap, fp) = self.last;
+        Some(TraceEntry { pc, ap, fp })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for CompressedTraceIter<'_> {}
+
+fn write_delta(bytes: &mut Vec<u8>, last: usize, value: usize) {
+    // Through isize, so the difference keeps its sign on 32 bit targets
+    let delta = value.wrapping_sub(last) as isize as i64;
+    // Zigzag encoding, so small negative deltas also take few bytes
+    let mut delta = ((delta << 1) ^ (delta >> 63)) as u64;
+    while delta >= 0x80 {
+        bytes.push(delta as u8 | 0x80);
+        delta >>= 7;
+    }
+    bytes.push(delta as u8);
+}
+
+fn read_delta(bytes: &[u8], position: &mut usize, last: usize) -> Option<usize> {
+    let mut delta = 0_u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *bytes.get(*position)?;
+        *position += 1;
+        delta |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            let delta = ((delta >> 1) as i64) ^ -((delta & 1) as i64);
+            return Some(last.wrapping_add(delta as usize));
+        }
+    }
+    None
+}
+
+fn read_entry(
+    bytes: &[u8],
+    position: &mut usize,
+    (pc, ap, fp): (usize, usize, usize),
+) -> Option<(usize, usize, usize)> {
+    Some((
+        read_delta(bytes, position, pc)?,
+        read_delta(bytes, position, ap)?,
+        read_delta(bytes, position, fp)?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    fn trace() -> Vec<TraceEntry> {
+        [
+            (1, 40, 40),
+            (2, 41, 40),
+            (9, 43, 43),
+            (10, 44, 43),
+            (3, 44, 40),
+            (0, 0, 0),
+        ]
+        .into_iter()
+        .chain([(usize::MAX, 1 << 20, usize::MAX >> 1)])
+        .map(|(pc, ap, fp)| TraceEntry { pc, ap, fp })
+        .collect()
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn compressed_trace_round_trip() {
+        let trace = trace();
+        let compressed_trace = CompressedTrace::from(trace.as_slice());
+        assert_eq!(compressed_trace.len(), trace.len());
+        assert_eq!(compressed_trace.iter().len(), trace.len());
+        assert_eq!(compressed_trace.iter().collect::<Vec<_>>(), trace);
+
+        let read_trace = CompressedTrace::from_bytes(compressed_trace.as_bytes().to_vec()).unwrap();
+        assert_eq!(read_trace, compressed_trace);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn compressed_trace_small_deltas_take_a_byte() {
+        let trace = trace();
+        let trace = &trace[..5];
+        let compressed_trace = CompressedTrace::from(trace);
+        // The first entry starts from zero, the ap and fp of the next ones change by less than 64
+        assert_eq!(compressed_trace.as_bytes().len(), 3 * trace.len());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn push_after_reading() {
+        let trace = trace();
+        let mut compressed_trace = CompressedTrace::from(&trace[..3]);
+        compressed_trace =
+            CompressedTrace::from_bytes(compressed_trace.as_bytes().to_vec()).unwrap();
+        for entry in trace[3..].iter() {
+            compressed_trace.push(entry);
+        }
+        assert_eq!(compressed_trace.iter().collect::<Vec<_>>(), trace);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn shift_compressed_trace() {
+        let trace = trace();
+        let mut compressed_trace = CompressedTrace::from(trace.as_slice());
+        compressed_trace.shift(1, 100, 100);
+        let shifted_trace: Vec<_> = trace
+            .iter()
+            .map(|entry| TraceEntry {
+                pc: entry.pc.wrapping_add(1),
+                ap: entry.ap.wrapping_add(100),
+                fp: entry.fp.wrapping_add(100),
+            })
+            .collect();
+        assert_eq!(compressed_trace.iter().collect::<Vec<_>>(), shifted_trace);
+        assert_eq!(
+            compressed_trace,
+            CompressedTrace::from(shifted_trace.as_slice())
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn empty_compressed_trace() {
+        let compressed_trace = CompressedTrace::from_bytes(Vec::new()).unwrap();
+        assert!(compressed_trace.is_empty());
+        assert_eq!(compressed_trace.iter().next(), None);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn truncated_compressed_trace() {
+        let compressed_trace = CompressedTrace::from(trace().as_slice());
+        let mut bytes = compressed_trace.as_bytes().to_vec();
+        bytes.pop();
+        assert_matches!(
+            CompressedTrace::from_bytes(bytes),
+            Err(TraceError::InvalidCompressedTrace(6))
+        );
+        // The continuation bit of the last byte is set
+        assert_matches!(
+            CompressedTrace::from_bytes(vec![0, 0, 0x80]),
+            Err(TraceError::InvalidCompressedTrace(0))
+        );
+    }
+}
@@ -1,3 +1,5 @@
+pub mod compressed_trace;
+
 pub mod trace_entry {
     use serde::{Deserialize, Serialize};
 
@@ -7,10 +9,169 @@ pub mod trace_entry {
     ///     Register values are represented as their offsets, as their indexes will always be 0,1,1 respectively
     ///     The index of the last pc will not be equal to 0, but it is not appended to the trace
     /// After relocation the value of each register will be a single integer
-    #[derive(Debug, PartialEq, Eq, Deserialize, Serialize)]
+    #[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
     pub struct TraceEntry {
         pub pc: usize,
         pub ap: usize,
         pub fp: usize,
     }
 }
+
+pub mod builtin_interaction {
+    use crate::stdlib::{fmt, prelude::*};
+    use crate::{serde::deserialize_program::BuiltinName, types::relocatable::Relocatable};
+
+    /// The operand through which an instruction accessed a builtin segment.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Operand {
+        Dst,
+        Op0,
+        Op1,
+    }
+
+    /// A trace step whose operand lives in the segment of a builtin.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct BuiltinInteraction {
+        pub step: usize,
+        pub pc: usize,
+        pub builtin: &'static str,
+        pub operand: Operand,
+        pub address: Relocatable,
+    }
+
+    /// Builtin interactions of a run, in trace order.
+    #[derive(Clone, Debug, Default, PartialEq, Eq)]
+    pub struct BuiltinInteractions(pub(crate) Vec<BuiltinInteraction>);
+
+    impl BuiltinInteractions {
+        pub fn iter(&self) -> impl Iterator<Item = &BuiltinInteraction> {
+            self.0.iter()
+        }
+
+        pub fn iter_builtin_interactions(
+            &self,
+            builtin: BuiltinName,
+        ) -> impl Iterator<Item = &BuiltinInteraction> {
+            self.0
+                .iter()
+                .filter(move |interaction| interaction.builtin == builtin.name())
+        }
+    }
+
+    /// Builtin instances used by a function, counted in the frame that first accessed them.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct FunctionBuiltinUsage {
+        pub function: String,
+        pub builtin: &'static str,
+        pub instances: usize,
+    }
+
+    /// Builtin usage of a run by function, sorted by function and builtin.
+    #[derive(Clone, Debug, Default, PartialEq, Eq)]
+    pub struct BuiltinUsageByFunction(pub(crate) Vec<FunctionBuiltinUsage>);
+
+    impl BuiltinUsageByFunction {
+        pub fn iter(&self) -> impl Iterator<Item = &FunctionBuiltinUsage> {
+            self.0.iter()
+        }
+    }
+
+    impl fmt::Display for BuiltinUsageByFunction {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let (function_width, builtin_width) = self.0.iter().fold(
+                ("function".len(), "builtin".len()),
+                |(function_width, builtin_width), usage| {
+                    (
+                        function_width.max(usage.function.len()),
+                        builtin_width.max(usage.builtin.len()),
+                    )
+                },
+            );
+            writeln!(
+                f,
+                "{:function_width$}  {:builtin_width$}  instances",
+                "function", "builtin"
+            )?;
+            for usage in self.0.iter() {
+                writeln!(
+                    f,
+                    "{:function_width$}  {:builtin_width$}  {}",
+                    usage.function, usage.builtin, usage.instances
+                )?;
+            }
+            Ok(())
+        }
+    }
+}
+
+pub(crate) mod call_stack {
+    use crate::stdlib::prelude::*;
+    use crate::types::program::Program;
+
+    /// Functions of a program sorted by pc, to find the one an instruction belongs to.
+    pub(crate) struct FunctionIndex<'a>(Vec<(usize, &'a str)>);
+
+    impl<'a> FunctionIndex<'a> {
+        pub(crate) fn new(program: &'a Program) -> Self {
+            let mut functions: Vec<(usize, &str)> = program
+                .iter_identifiers()
+                .filter(|(_, identifier)| identifier.type_.as_deref() == Some("function"))
+                .filter_map(|(name, identifier)| Some((identifier.pc?, name)))
+                .collect();
+            functions.sort_unstable();
+            FunctionIndex(functions)
+        }
+
+        /// Returns the name of the function containing the instruction at `pc`, relative to
+        /// the start of the program.
+        pub(crate) fn function_at(&self, pc: usize) -> String {
+            let index = self
+                .0
+                .partition_point(|(function_pc, _)| *function_pc <= pc);
+            match index.checked_sub(1) {
+                Some(index) => self.0[index].1.to_string(),
+                None => format!("pc=0:{pc}"),
+            }
+        }
+    }
+
+    /// Call stack of a run, rebuilt from the fp of its trace entries: a new fp starts a frame
+    /// for the function containing its pc, while returning to the fp of an outer frame drops
+    /// the inner ones.
+    #[derive(Default)]
+    pub(crate) struct CallStack {
+        // (fp, function name), from the outermost frame
+        frames: Vec<(usize, String)>,
+    }
+
+    impl CallStack {
+        /// Moves to the frame of the next trace entry, returning true if the stack changed.
+        pub(crate) fn step(&mut self, fp: usize, pc: usize, functions: &FunctionIndex) -> bool {
+            if self.frames.last().map(|(frame_fp, _)| *frame_fp) == Some(fp) {
+                return false;
+            }
+            match self.frames.iter().position(|(frame_fp, _)| *frame_fp == fp) {
+                Some(index) => self.frames.truncate(index + 1),
+                None => self.frames.push((fp, functions.function_at(pc))),
+            }
+            true
+        }
+
+        /// Name of the function of the innermost frame
+        pub(crate) fn current(&self) -> &str {
+            self.frames
+                .last()
+                .map(|(_, name)| name.as_str())
+                .unwrap_or_default()
+        }
+
+        /// The `;`-separated names of the functions of every frame
+        pub(crate) fn folded(&self) -> String {
+            self.frames
+                .iter()
+                .map(|(_, name)| name.as_str())
+                .collect::<Vec<_>>()
+                .join(";")
+        }
+    }
+}
@@ -0,0 +1,185 @@
+//! What a run does with the hints its hint processor doesn't know.
+//!
+//! By default an unknown hint fails the run. When exploring a program whose hints are irrelevant
+//! to the path executed, the VM can instead skip them, recording each skipped hint so the run can
+//! be reported as incomplete. Reporting them, e.g. printing a warning, is left to the caller.
+
+use crate::stdlib::prelude::*;
+
+use crate::{types::relocatable::Relocatable, vm::vm_core::VirtualMachine};
+
+/// What the VM does when the hint processor fails a hint with
+/// [HintError::UnknownHint](crate::vm::errors::hint_errors::HintError::UnknownHint).
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum UnknownHintPolicy {
+    /// Fails the run
+    #[default]
+    Error,
+    /// Skips the hint, recording it as a [SkippedHint]
+    Skip,
+}
+
+/// An unknown hint skipped during the run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SkippedHint {
+    pub pc: Relocatable,
+    /// Index of the hint among the hints at `pc`
+    pub hint_index: usize,
+    pub code: String,
+    /// Number of times the hint was reached
+    pub count: usize,
+}
+
+impl VirtualMachine {
+    /// Sets what the VM does with the hints its hint processor doesn't know.
+    pub fn set_unknown_hint_policy(&mut self, policy: UnknownHintPolicy) {
+        self.unknown_hint_policy = policy;
+    }
+
+    /// Returns the unknown hints skipped so far, in the order they were first reached.
+    /// A hint reached several times is listed once, with the number of times it was reached.
+    pub fn get_skipped_hints(&self) -> &[SkippedHint] {
+        &self.skipped_hints
+    }
+
+    // Records an unknown hint, returning false if the policy requires failing the run instead
+    pub(crate) fn skip_unknown_hint(&mut self, hint_index: usize, code: &str) -> bool {
+        if self.unknown_hint_policy == UnknownHintPolicy::Error {
+            return false;
+        }
+        let pc = self.run_context.pc;
+        match self
+            .skipped_hints
+            .iter_mut()
+            .find(|skipped_hint| skipped_hint.pc == pc && skipped_hint.hint_index == hint_index)
+        {
+            Some(skipped_hint) => skipped_hint.count += 1,
+            None => self.skipped_hints.push(SkippedHint {
+                pc,
+                hint_index,
+                code: code.to_string(),
+                count: 1,
+            }),
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        any_box,
+        hint_processor::{
+            builtin_hint_processor::{
+                builtin_hint_processor_definition::{BuiltinHintProcessor, HintProcessorData},
+                hint_code,
+            },
+            hint_processor_definition::HintReference,
+        },
+        stdlib::{any::Any, collections::HashMap},
+        types::{constants::Constants, exec_scope::ExecutionScopes},
+        utils::test_utils::*,
+        vm::errors::{hint_errors::HintError, vm_errors::VirtualMachineError},
+    };
+    use assert_matches::assert_matches;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    fn step_hints(vm: &mut VirtualMachine, codes: &[&str]) -> Result<(), VirtualMachineError> {
+        let hints: Vec<Box<dyn Any>> = codes
+            .iter()
+            .map(|code| {
+                any_box!(HintProcessorData::new_default(
+                    code.to_string(),
+                    HashMap::<String, HintReference>::new(),
+                ))
+            })
+            .collect();
+        vm.step_hint(
+            &mut BuiltinHintProcessor::new_empty(),
+            &mut ExecutionScopes::new(),
+            &HashMap::from([(0, hints)]),
+            &Constants::new(),
+        )
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn unknown_hint_fails_by_default() {
+        let mut vm = vm!();
+        assert_matches!(
+            step_hints(&mut vm, &["unknown"]),
+            Err(VirtualMachineError::Hint(bx)) if bx.0 == 0
+                && matches!(&bx.1, HintError::UnknownHint(code) if code.as_ref() == "unknown")
+        );
+        assert!(vm.get_skipped_hints().is_empty());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn unknown_hints_skipped() {
+        let mut vm = vm!();
+        vm.set_unknown_hint_policy(UnknownHintPolicy::Skip);
+        add_segments!(vm, 2);
+        // The known hint between the unknown ones still runs
+        assert_matches!(
+            step_hints(
+                &mut vm,
+                &["unknown", "memory[ap] = segments.add()", "other unknown"]
+            ),
+            Ok(())
+        );
+        assert_eq!(vm.segments.num_segments(), 3);
+        assert_eq!(
+            vm.get_skipped_hints(),
+            [
+                SkippedHint {
+                    pc: Relocatable::from((0, 0)),
+                    hint_index: 0,
+                    code: String::from("unknown"),
+                    count: 1,
+                },
+                SkippedHint {
+                    pc: Relocatable::from((0, 0)),
+                    hint_index: 2,
+                    code: String::from("other unknown"),
+                    count: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn unknown_hint_reached_again_is_counted() {
+        let mut vm = vm!();
+        vm.set_unknown_hint_policy(UnknownHintPolicy::Skip);
+        for _ in 0..3 {
+            assert_matches!(step_hints(&mut vm, &["unknown"]), Ok(()));
+        }
+        assert_eq!(
+            vm.get_skipped_hints(),
+            [SkippedHint {
+                pc: Relocatable::from((0, 0)),
+                hint_index: 0,
+                code: String::from("unknown"),
+                count: 3,
+            }]
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn known_hint_errors_are_not_skipped() {
+        let mut vm = vm!();
+        vm.set_unknown_hint_policy(UnknownHintPolicy::Skip);
+        // Fails as there is no ids.a
+        assert_matches!(
+            step_hints(&mut vm, &[hint_code::ASSERT_NN]),
+            Err(VirtualMachineError::Hint(_))
+        );
+        assert!(vm.get_skipped_hints().is_empty());
+    }
+}
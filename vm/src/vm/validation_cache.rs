@@ -0,0 +1,141 @@
+//! Instructions decoded by previous runs, to be shared by runners executing the same program.
+//!
+//! Every run decodes, and so validates, the instructions of its program segment the first time
+//! it executes them. Runners loading the same program over and over can skip that work by
+//! storing what they decoded in a [ValidationCache] with
+//! [CairoRunner::update_validation_cache](crate::vm::runners::cairo_runner::CairoRunner::update_validation_cache)
+//! and loading it back with
+//! [CairoRunner::load_validation_cache](crate::vm::runners::cairo_runner::CairoRunner::load_validation_cache).
+
+use crate::stdlib::{collections::HashMap, prelude::*};
+
+use crate::types::{
+    instruction::{Instruction, InstructionKind},
+    program::ProgramData,
+};
+
+pub(crate) type DecodedInstruction = Option<(Instruction, InstructionKind)>;
+
+/// Decoded instructions of program segments, keyed by the digest of their contents.
+#[derive(Clone, Debug, Default)]
+pub struct ValidationCache {
+    programs: HashMap<[u8; 32], Vec<DecodedInstruction>>,
+}
+
+impl ValidationCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of distinct program segments in the cache
+    pub fn len(&self) -> usize {
+        self.programs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.programs.is_empty()
+    }
+
+    /// Returns the instructions decoded for `program_data`, indexed by their offset in it.
+    pub(crate) fn get(&self, program_data: &ProgramData) -> Option<&[DecodedInstruction]> {
+        self.programs.get(program_data.digest()).map(Vec::as_slice)
+    }
+
+    /// Adds the instructions decoded for `program_data` to the ones already in the cache.
+    pub(crate) fn insert(&mut self, program_data: &ProgramData, decoded: &[DecodedInstruction]) {
+        match self.programs.get_mut(program_data.digest()) {
+            Some(cached) => {
+                for (cached, decoded) in cached.iter_mut().zip(decoded) {
+                    if cached.is_none() {
+                        *cached = *decoded;
+                    }
+                }
+            }
+            None => {
+                let mut decoded = decoded.to_vec();
+                decoded.resize(program_data.len(), None);
+                self.programs.insert(*program_data.digest(), decoded);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+        types::program::Program,
+        vm::{runners::cairo_runner::CairoRunner, vm_core::VirtualMachine},
+    };
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    fn run(program: &Program, cache: &mut ValidationCache) -> VirtualMachine {
+        let mut cairo_runner = CairoRunner::new(program, "all_cairo", false).unwrap();
+        let mut vm = VirtualMachine::new(false);
+        let end = cairo_runner.initialize(&mut vm).unwrap();
+        cairo_runner.load_validation_cache(&mut vm, cache);
+        cairo_runner
+            .run_until_pc(end, &mut vm, &mut BuiltinHintProcessor::new_empty())
+            .unwrap();
+        cairo_runner.update_validation_cache(&vm, cache);
+        vm
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn runs_share_decoded_instructions() {
+        let program = Program::from_bytes(
+            include_bytes!("../../../cairo_programs/fibonacci.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let mut cache = ValidationCache::new();
+        assert!(cache.is_empty());
+
+        let first_vm = run(&program, &mut cache);
+        assert_eq!(cache.len(), 1);
+        let decoded = cache.get(&program.shared_program_data.data).unwrap();
+        assert!(decoded.iter().any(Option::is_some));
+
+        // A new runner starts with the instructions decoded by the first one
+        let mut cairo_runner = CairoRunner::new(&program, "all_cairo", false).unwrap();
+        let mut vm = VirtualMachine::new(false);
+        cairo_runner.initialize(&mut vm).unwrap();
+        cairo_runner.load_validation_cache(&mut vm, &cache);
+        assert_eq!(vm.decoded_instructions(), decoded);
+
+        let second_vm = run(&program, &mut cache);
+        assert_eq!(cache.len(), 1);
+        assert_eq!(second_vm.get_ap(), first_vm.get_ap());
+        assert_eq!(second_vm.current_step, first_vm.current_step);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn different_programs_dont_share_decoded_instructions() {
+        let fibonacci = Program::from_bytes(
+            include_bytes!("../../../cairo_programs/fibonacci.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let factorial = Program::from_bytes(
+            include_bytes!("../../../cairo_programs/factorial.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let mut cache = ValidationCache::new();
+        run(&fibonacci, &mut cache);
+
+        let mut cairo_runner = CairoRunner::new(&factorial, "all_cairo", false).unwrap();
+        let mut vm = VirtualMachine::new(false);
+        cairo_runner.initialize(&mut vm).unwrap();
+        cairo_runner.load_validation_cache(&mut vm, &cache);
+        assert!(vm.decoded_instructions().iter().all(Option::is_none));
+
+        run(&factorial, &mut cache);
+        assert_eq!(cache.len(), 2);
+    }
+}
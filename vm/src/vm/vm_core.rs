@@ -1,12 +1,15 @@
-use crate::stdlib::{any::Any, borrow::Cow, collections::HashMap, prelude::*};
+use crate::stdlib::{any::Any, borrow::Cow, collections::HashMap, ops::Range, prelude::*};
 
 use crate::{
     hint_processor::hint_processor_definition::HintProcessor,
     types::{
+        constants::Constants,
         errors::math_errors::MathError,
         exec_scope::ExecutionScopes,
+        felt_format::FeltFormat,
         instruction::{
-            is_call_instruction, ApUpdate, FpUpdate, Instruction, Opcode, PcUpdate, Res,
+            is_call_instruction, ApUpdate, FpUpdate, Instruction, InstructionKind, Opcode,
+            PcUpdate, Res,
         },
         relocatable::{MaybeRelocatable, Relocatable},
     },
@@ -14,18 +17,28 @@ use crate::{
         context::run_context::RunContext,
         decoding::decoder::decode_instruction,
         errors::{
-            exec_scope_errors::ExecScopeError, memory_errors::MemoryError,
+            exec_scope_errors::ExecScopeError, hint_errors::HintError, memory_errors::MemoryError,
             vm_errors::VirtualMachineError,
         },
-        runners::builtin_runner::{BuiltinRunner, RangeCheckBuiltinRunner, SignatureBuiltinRunner},
-        trace::trace_entry::TraceEntry,
+        operands_audit::{AuditedOperand, OperandSource, OperandsAudit},
+        runners::builtin_runner::{
+            BuiltinRunner, OutputBuiltinRunner, RangeCheckBuiltinRunner, SignatureBuiltinRunner,
+        },
+        trace::{
+            builtin_interaction::{BuiltinInteraction, BuiltinInteractions, Operand},
+            compressed_trace::CompressedTrace,
+            trace_entry::TraceEntry,
+        },
+        validation_cache::DecodedInstruction,
         vm_memory::memory_segments::MemorySegmentManager,
     },
 };
 
 use core::cmp::Ordering;
 use felt::Felt252;
-use num_traits::{ToPrimitive, Zero};
+use num_traits::{One, ToPrimitive, Zero};
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
 use super::errors::trace_errors::TraceError;
 use super::runners::builtin_runner::OUTPUT_BUILTIN_NAME;
@@ -34,17 +47,17 @@ const MAX_TRACEBACK_ENTRIES: u32 = 20;
 
 #[derive(PartialEq, Eq, Debug)]
 pub struct Operands {
-    dst: MaybeRelocatable,
-    res: Option<MaybeRelocatable>,
-    op0: MaybeRelocatable,
-    op1: MaybeRelocatable,
+    pub dst: MaybeRelocatable,
+    pub res: Option<MaybeRelocatable>,
+    pub op0: MaybeRelocatable,
+    pub op1: MaybeRelocatable,
 }
 
 #[derive(PartialEq, Eq, Debug)]
 pub struct OperandsAddresses {
-    dst_addr: Relocatable,
-    op0_addr: Relocatable,
-    op1_addr: Relocatable,
+    pub dst_addr: Relocatable,
+    pub op0_addr: Relocatable,
+    pub op1_addr: Relocatable,
 }
 
 #[derive(Default, Debug, Clone, Copy)]
@@ -77,14 +90,27 @@ pub struct VirtualMachine {
     pub(crate) builtin_runners: Vec<BuiltinRunner>,
     pub(crate) segments: MemorySegmentManager,
     pub(crate) trace: Option<Vec<TraceEntry>>,
+    pub(crate) compressed_trace: Option<CompressedTrace>,
     pub(crate) current_step: usize,
     pub(crate) rc_limits: Option<(isize, isize)>,
     trace_relocated: bool,
     skip_instruction_execution: bool,
     run_finished: bool,
-    instruction_cache: Vec<Option<Instruction>>,
+    instruction_cache: Vec<DecodedInstruction>,
+    pub(crate) hint_sandbox: bool,
+    pub(crate) parallel_hints: bool,
+    strict_memory_reads: bool,
+    pub(crate) step_sampler: Option<crate::vm::step_sampling::StepSampler>,
+    pub(crate) pc_execution_counter: Option<crate::vm::pc_limits::PcExecutionCounter>,
+    pub(crate) memory_journal: Option<crate::vm::memory_journal::MemoryJournal>,
+    pub(crate) unknown_hint_policy: crate::vm::unknown_hints::UnknownHintPolicy,
+    pub(crate) skipped_hints: Vec<crate::vm::unknown_hints::SkippedHint>,
     #[cfg(feature = "hooks")]
     pub(crate) hooks: crate::vm::hooks::Hooks,
+    #[cfg(feature = "opcode-extensions")]
+    pub(crate) opcode_extensions: crate::vm::opcode_extensions::OpcodeExtensions,
+    #[cfg(feature = "std")]
+    pub(crate) run_events: Option<crate::vm::run_events::RunEvents>,
 }
 
 impl VirtualMachine {
@@ -105,6 +131,7 @@ impl VirtualMachine {
             run_context,
             builtin_runners: Vec::new(),
             trace,
+            compressed_trace: None,
             current_step: 0,
             skip_instruction_execution: false,
             segments: MemorySegmentManager::new(),
@@ -112,8 +139,69 @@ impl VirtualMachine {
             run_finished: false,
             trace_relocated: false,
             instruction_cache: Vec::new(),
+            hint_sandbox: false,
+            parallel_hints: false,
+            strict_memory_reads: false,
+            step_sampler: None,
+            pc_execution_counter: None,
+            memory_journal: None,
+            unknown_hint_policy: Default::default(),
+            skipped_hints: Vec::new(),
             #[cfg(feature = "hooks")]
             hooks: Default::default(),
+            #[cfg(feature = "opcode-extensions")]
+            opcode_extensions: Default::default(),
+            #[cfg(feature = "std")]
+            run_events: None,
+        }
+    }
+
+    /// Enables the hint sandbox, for programs from untrusted sources: each hint may then only write
+    /// to the segments it adds and to the segments of its write scope, which for the hints of the
+    /// [BuiltinHintProcessor](crate::hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor)
+    /// are those holding its ids and those its ids point to, see
+    /// [HintProcessorLogic::hint_write_scope](crate::hint_processor::hint_processor_definition::HintProcessorLogic::hint_write_scope).
+    /// Other writes fail the hint with [HintError::WriteOutsideSandbox], reporting its code and the
+    /// address written.
+    pub fn set_hint_sandbox(&mut self, hint_sandbox: bool) {
+        self.hint_sandbox = hint_sandbox;
+    }
+
+    /// Enables the strict mode for memory reads, to catch reads past the end of an array or of
+    /// the arguments of a function at the instruction making them. An operand missing from memory
+    /// is then only deduced when a builtin deduces it, or when the instruction writes it: the dst
+    /// of an `assert_eq`, the op1 of an `assert_eq` copying it to its dst, and the return pc and
+    /// fp written by a `call`. Otherwise, the instruction fails with
+    /// [VirtualMachineError::UninitializedMemoryRead], reporting the operand and its address.
+    ///
+    /// Programs assigning a cell through an arithmetic `assert_eq`, such as `assert a = b + 1`
+    /// where `b` has no value yet, fail in this mode.
+    pub fn set_strict_memory_reads(&mut self, strict_memory_reads: bool) {
+        self.strict_memory_reads = strict_memory_reads;
+    }
+
+    /// Records the trace compressed in memory from now on, see [CompressedTrace]. The entries
+    /// recorded so far are compressed, and [get_relocated_trace](Self::get_relocated_trace) is
+    /// replaced by [get_relocated_compressed_trace](Self::get_relocated_compressed_trace).
+    pub fn compress_trace(&mut self) {
+        let trace = self.trace.take().unwrap_or_default();
+        self.compressed_trace
+            .get_or_insert_with(CompressedTrace::new)
+            .extend(trace.iter());
+    }
+
+    fn push_trace_entry(&mut self, entry: TraceEntry) {
+        if let Some(trace) = &mut self.trace {
+            trace.push(entry);
+        } else if let Some(compressed_trace) = &mut self.compressed_trace {
+            compressed_trace.push(&entry);
+        }
+    }
+
+    /// Reserves room in the trace for `steps` more entries, so running them doesn't reallocate it.
+    pub fn reserve_steps(&mut self, steps: usize) {
+        if let Some(trace) = &mut self.trace {
+            trace.reserve(steps);
         }
     }
 
@@ -196,6 +284,36 @@ impl VirtualMachine {
         Ok(())
     }
 
+    /// Same as [update_registers](Self::update_registers), skipping the updates that `kind` rules out.
+    fn update_registers_by_kind(
+        &mut self,
+        instruction: &Instruction,
+        kind: InstructionKind,
+        operands: Operands,
+    ) -> Result<(), VirtualMachineError> {
+        match kind {
+            InstructionKind::AssertEq | InstructionKind::AddAp => {
+                self.update_ap(instruction, &operands)?;
+                self.run_context.pc = (self.run_context.pc + instruction.size())?;
+            }
+            InstructionKind::Call => {
+                self.run_context.fp = self.run_context.ap + 2;
+                self.run_context.ap += 2;
+                self.update_pc(instruction, &operands)?;
+            }
+            InstructionKind::Ret => {
+                self.update_fp(instruction, &operands)?;
+                self.update_pc(instruction, &operands)?;
+            }
+            InstructionKind::Jump | InstructionKind::Jnz => {
+                self.update_ap(instruction, &operands)?;
+                self.update_pc(instruction, &operands)?;
+            }
+            InstructionKind::Generic => self.update_registers(instruction, operands)?,
+        }
+        Ok(())
+    }
+
     /// Returns true if the value is zero
     /// Used for JNZ instructions
     fn is_zero(addr: &MaybeRelocatable) -> bool {
@@ -245,14 +363,14 @@ impl VirtualMachine {
         &self,
         instruction: &Instruction,
         dst: Option<&MaybeRelocatable>,
-        op0: Option<MaybeRelocatable>,
+        op0: Option<&MaybeRelocatable>,
     ) -> Result<(Option<MaybeRelocatable>, Option<MaybeRelocatable>), VirtualMachineError> {
         if let Opcode::AssertEq = instruction.opcode {
             match instruction.res {
                 Res::Op1 => return Ok((dst.cloned(), dst.cloned())),
                 Res::Add => {
                     return Ok((
-                        dst.zip(op0).and_then(|(dst, op0)| dst.sub(&op0).ok()),
+                        dst.zip(op0).and_then(|(dst, op0)| dst.sub(op0).ok()),
                         dst.cloned(),
                     ))
                 }
@@ -326,13 +444,46 @@ impl VirtualMachine {
         &self,
         instruction: &Instruction,
         operands: &Operands,
+        operands_addresses: &OperandsAddresses,
+        deduced_operands: DeducedOperands,
     ) -> Result<(), VirtualMachineError> {
         match instruction.opcode {
             Opcode::AssertEq => match &operands.res {
                 None => Err(VirtualMachineError::UnconstrainedResAssertEq),
-                Some(res) if res != &operands.dst => Err(VirtualMachineError::DiffAssertValues(
-                    Box::new((operands.dst.clone(), res.clone())),
-                )),
+                Some(res) if res != &operands.dst => {
+                    let audited_operand =
+                        |address, value: &MaybeRelocatable, deduced| AuditedOperand {
+                            address,
+                            value: value.clone(),
+                            source: if deduced {
+                                OperandSource::Deduced
+                            } else {
+                                OperandSource::Read
+                            },
+                        };
+                    Err(VirtualMachineError::DiffAssertValues(Box::new(
+                        OperandsAudit {
+                            pc: self.run_context.pc,
+                            instruction: *instruction,
+                            dst: audited_operand(
+                                operands_addresses.dst_addr,
+                                &operands.dst,
+                                deduced_operands.was_dest_deducted(),
+                            ),
+                            op0: audited_operand(
+                                operands_addresses.op0_addr,
+                                &operands.op0,
+                                deduced_operands.was_op0_deducted(),
+                            ),
+                            op1: audited_operand(
+                                operands_addresses.op1_addr,
+                                &operands.op1,
+                                deduced_operands.was_op1_deducted(),
+                            ),
+                            res: res.clone(),
+                        },
+                    )))
+                }
                 _ => Ok(()),
             },
             Opcode::Call => {
@@ -384,21 +535,25 @@ impl VirtualMachine {
         Ok(())
     }
 
-    fn run_instruction(&mut self, instruction: &Instruction) -> Result<(), VirtualMachineError> {
-        let (operands, operands_addresses, deduced_operands) =
-            self.compute_operands(instruction)?;
-        self.insert_deduced_operands(deduced_operands, &operands, &operands_addresses)?;
-        self.opcode_assertions(instruction, &operands)?;
-
-        if let Some(ref mut trace) = &mut self.trace {
-            trace.push(TraceEntry {
-                pc: self.run_context.pc.offset,
-                ap: self.run_context.ap,
-                fp: self.run_context.fp,
-            });
-        }
+    /// Runs `instruction`, attaching the pc and the instruction to the arithmetic errors it raises,
+    /// whose operands alone don't tell which instruction failed.
+    fn run_instruction(
+        &mut self,
+        instruction: &Instruction,
+        kind: InstructionKind,
+    ) -> Result<(), VirtualMachineError> {
+        let pc = self.run_context.pc;
+        self.execute_instruction(instruction, kind)
+            .map_err(|err| match err {
+                VirtualMachineError::Math(err) => {
+                    VirtualMachineError::InstructionMath(Box::new((pc, *instruction, err)))
+                }
+                err => err,
+            })
+    }
 
-        // Update range check limits
+    // Updates the range check limits with the offsets of the instruction
+    pub(crate) fn update_rc_limits(&mut self, instruction: &Instruction) {
         const OFFSET_BITS: u32 = 16;
         let (off0, off1, off2) = (
             instruction.off0 + (1_isize << (OFFSET_BITS - 1)),
@@ -415,6 +570,30 @@ impl VirtualMachine {
                 .max()
                 .unwrap(),
         ));
+    }
+
+    fn execute_instruction(
+        &mut self,
+        instruction: &Instruction,
+        kind: InstructionKind,
+    ) -> Result<(), VirtualMachineError> {
+        let (operands, operands_addresses, deduced_operands) =
+            self.compute_operands(instruction)?;
+        self.insert_deduced_operands(deduced_operands, &operands, &operands_addresses)?;
+        self.opcode_assertions(
+            instruction,
+            &operands,
+            &operands_addresses,
+            deduced_operands,
+        )?;
+
+        self.push_trace_entry(TraceEntry {
+            pc: self.run_context.pc.offset,
+            ap: self.run_context.ap,
+            fp: self.run_context.fp,
+        });
+
+        self.update_rc_limits(instruction);
 
         self.segments
             .memory
@@ -426,8 +605,29 @@ impl VirtualMachine {
             .memory
             .mark_as_accessed(operands_addresses.op1_addr);
 
-        self.update_registers(instruction, operands)?;
+        if let Some(memory_journal) = &mut self.memory_journal {
+            memory_journal.record(
+                self.run_context.pc,
+                [
+                    operands_addresses.dst_addr,
+                    operands_addresses.op0_addr,
+                    operands_addresses.op1_addr,
+                ],
+                [
+                    deduced_operands.was_dest_deducted(),
+                    deduced_operands.was_op0_deducted(),
+                    deduced_operands.was_op1_deducted(),
+                ],
+            );
+        }
+
+        self.update_registers_by_kind(instruction, kind, operands)?;
         self.current_step += 1;
+        self.sample_step();
+        #[cfg(feature = "std")]
+        if let Some(run_events) = &self.run_events {
+            run_events.step(self.current_step, self.run_context.pc);
+        }
 
         Ok(())
     }
@@ -442,40 +642,111 @@ impl VirtualMachine {
         decode_instruction(instruction)
     }
 
+    /// Runs the hints at the current pc, one after the other in the order of their list in
+    /// `hint_data_dictionary`, each one seeing the memory and scopes left by the previous ones.
+    ///
+    /// The lists built by
+    /// [CairoRunner::get_hint_data_dictionary](crate::vm::runners::cairo_runner::CairoRunner::get_hint_data_dictionary)
+    /// follow the order the program declares the hints in, unless the hint processor rearranges
+    /// them, see
+    /// [HintProcessorLogic::arrange_hints](crate::hint_processor::hint_processor_definition::HintProcessorLogic::arrange_hints).
+    /// Hints run in parallel commit their writes in that same order, see
+    /// [VirtualMachine::set_parallel_hints].
     pub fn step_hint(
         &mut self,
         hint_executor: &mut dyn HintProcessor,
         exec_scopes: &mut ExecutionScopes,
         hint_data_dictionary: &HashMap<usize, Vec<Box<dyn Any>>>,
-        constants: &HashMap<String, Felt252>,
+        constants: &Constants,
     ) -> Result<(), VirtualMachineError> {
         if let Some(hint_list) = hint_data_dictionary.get(&self.run_context.pc.offset) {
+            if let Some(result) = self.step_parallel_hints(hint_executor, hint_list) {
+                return result;
+            }
             for (hint_index, hint_data) in hint_list.iter().enumerate() {
-                hint_executor
-                    .execute_hint(self, exec_scopes, hint_data, constants)
-                    .map_err(|err| VirtualMachineError::Hint(Box::new((hint_index, err))))?
+                let result = if self.hint_sandbox {
+                    self.execute_sandboxed_hint(hint_executor, exec_scopes, hint_data, constants)
+                } else {
+                    hint_executor.execute_hint(self, exec_scopes, hint_data, constants)
+                };
+                if let Err(HintError::UnknownHint(code)) = &result {
+                    if self.skip_unknown_hint(hint_index, code) {
+                        continue;
+                    }
+                }
+                result.map_err(|err| VirtualMachineError::Hint(Box::new((hint_index, err))))?;
+                #[cfg(feature = "std")]
+                self.emit_run_event(|| crate::vm::run_events::RunEvent::HintExecuted {
+                    pc: self.run_context.pc,
+                    hint_index,
+                });
             }
         }
         Ok(())
     }
 
+    /// Executes a hint, failing if it wrote outside of the segments it added and those of its
+    /// write scope.
+    fn execute_sandboxed_hint(
+        &mut self,
+        hint_executor: &mut dyn HintProcessor,
+        exec_scopes: &mut ExecutionScopes,
+        hint_data: &Box<dyn Any>,
+        constants: &Constants,
+    ) -> Result<(), HintError> {
+        let write_scope = hint_executor.hint_write_scope(self, hint_data);
+        // Segments added by the hint are those past the current ones, on both ends
+        let num_segments = self.segments.num_segments() as isize;
+        let num_temp_segments = self.segments.num_temp_segments() as isize;
+        self.segments.memory.start_recording_writes();
+        let result = hint_executor.execute_hint(self, exec_scopes, hint_data, constants);
+        let writes = self.segments.memory.stop_recording_writes();
+        result?;
+        let outside_scope = writes.into_iter().find(|addr| {
+            addr.segment_index < num_segments
+                && addr.segment_index >= -num_temp_segments
+                && !write_scope.segments.contains(&addr.segment_index)
+        });
+        match outside_scope {
+            Some(addr) => Err(HintError::WriteOutsideSandbox(Box::new((
+                write_scope.code,
+                addr,
+            )))),
+            None => Ok(()),
+        }
+    }
+
     pub fn step_instruction(&mut self) -> Result<(), VirtualMachineError> {
         let pc = self.run_context.pc.offset;
 
         if self.segments.memory.data[0].len() <= pc {
             return Err(MemoryError::UnknownMemoryCell(Box::new((0, pc).into())))?;
         }
+        if let Some(counter) = &mut self.pc_execution_counter {
+            counter.record(pc)?;
+        }
 
+        self.invalidate_program_writes();
         let mut inst_cache = core::mem::take(&mut self.instruction_cache);
-        inst_cache.resize((pc + 1).max(inst_cache.len()), None);
+        if inst_cache.len() <= pc {
+            // Size the cache for the whole program segment at once, so it doesn't grow step by step
+            inst_cache.resize(self.segments.memory.data[0].len(), None);
+        }
 
-        let instruction = inst_cache.get_mut(pc).unwrap();
-        if instruction.is_none() {
-            *instruction = Some(self.decode_current_instruction()?);
+        let cached_instruction = inst_cache.get_mut(pc).unwrap();
+        if cached_instruction.is_none() {
+            // Extended instructions aren't cached, their extension may be replaced between steps
+            #[cfg(feature = "opcode-extensions")]
+            if let Some((extended, extension)) = self.decode_extended_instruction()? {
+                self.instruction_cache = inst_cache;
+                return self.run_extended_instruction(&extended, extension.as_ref());
+            }
+            let instruction = self.decode_current_instruction()?;
+            *cached_instruction = Some((instruction, instruction.kind()));
         }
-        let instruction = instruction.as_ref().unwrap();
+        let (instruction, kind) = cached_instruction.as_ref().unwrap();
         if !self.skip_instruction_execution {
-            self.run_instruction(instruction)?;
+            self.run_instruction(instruction, *kind)?;
         } else {
             self.run_context.pc += instruction.size();
             self.skip_instruction_execution = false;
@@ -484,12 +755,40 @@ impl VirtualMachine {
         Ok(())
     }
 
+    #[cfg(feature = "opcode-extensions")]
+    fn run_extended_instruction(
+        &mut self,
+        extended: &crate::vm::opcode_extensions::ExtendedInstruction,
+        extension: &dyn crate::vm::opcode_extensions::OpcodeExtension,
+    ) -> Result<(), VirtualMachineError> {
+        if self.skip_instruction_execution {
+            self.run_context.pc += extended.instruction.size();
+            self.skip_instruction_execution = false;
+            return Ok(());
+        }
+        let entry = TraceEntry {
+            pc: self.run_context.pc.offset,
+            ap: self.run_context.ap,
+            fp: self.run_context.fp,
+        };
+        extension.execute(self, extended)?;
+        self.push_trace_entry(entry);
+        self.update_rc_limits(&extended.instruction);
+        self.current_step += 1;
+        self.sample_step();
+        #[cfg(feature = "std")]
+        if let Some(run_events) = &self.run_events {
+            run_events.step(self.current_step, self.run_context.pc);
+        }
+        Ok(())
+    }
+
     pub fn step(
         &mut self,
         hint_executor: &mut dyn HintProcessor,
         exec_scopes: &mut ExecutionScopes,
         hint_data_dictionary: &HashMap<usize, Vec<Box<dyn Any>>>,
-        constants: &HashMap<String, Felt252>,
+        constants: &Constants,
     ) -> Result<(), VirtualMachineError> {
         self.step_hint(hint_executor, exec_scopes, hint_data_dictionary, constants)?;
 
@@ -522,6 +821,9 @@ impl VirtualMachine {
     ) -> Result<MaybeRelocatable, VirtualMachineError> {
         let op0_op = match self.deduce_memory_cell(op0_addr)? {
             None => {
+                if instruction.opcode != Opcode::Call {
+                    self.check_strict_read("op0", op0_addr, instruction)?;
+                }
                 let op0;
                 (op0, *res) = self.deduce_op0(instruction, dst_op.as_ref(), op1_op.as_ref())?;
                 op0
@@ -544,8 +846,11 @@ impl VirtualMachine {
     ) -> Result<MaybeRelocatable, VirtualMachineError> {
         let op1_op = match self.deduce_memory_cell(op1_addr)? {
             None => {
+                if !(instruction.opcode == Opcode::AssertEq && instruction.res == Res::Op1) {
+                    self.check_strict_read("op1", op1_addr, instruction)?;
+                }
                 let (op1, deduced_res) =
-                    self.deduce_op1(instruction, dst_op.as_ref(), Some(op0.clone()))?;
+                    self.deduce_op1(instruction, dst_op.as_ref(), Some(op0))?;
                 if res.is_none() {
                     *res = deduced_res
                 }
@@ -561,9 +866,13 @@ impl VirtualMachine {
 
     fn compute_dst_deductions(
         &self,
+        dst_addr: Relocatable,
         instruction: &Instruction,
         res: &Option<MaybeRelocatable>,
     ) -> Result<MaybeRelocatable, VirtualMachineError> {
+        if !matches!(instruction.opcode, Opcode::AssertEq | Opcode::Call) {
+            self.check_strict_read("dst", dst_addr, instruction)?;
+        }
         let dst_op = match instruction.opcode {
             Opcode::AssertEq if res.is_some() => Option::clone(res),
             Opcode::Call => Some(MaybeRelocatable::from(self.run_context.get_fp())),
@@ -573,6 +882,29 @@ impl VirtualMachine {
         Ok(dst)
     }
 
+    // In strict mode, fails the read of an operand missing from memory outside the builtin segments
+    fn check_strict_read(
+        &self,
+        operand: &'static str,
+        addr: Relocatable,
+        instruction: &Instruction,
+    ) -> Result<(), VirtualMachineError> {
+        if self.strict_memory_reads
+            && !self
+                .builtin_runners
+                .iter()
+                .any(|builtin| builtin.base() as isize == addr.segment_index)
+        {
+            return Err(VirtualMachineError::UninitializedMemoryRead(Box::new((
+                self.run_context.pc,
+                operand,
+                addr,
+                *instruction,
+            ))));
+        }
+        Ok(())
+    }
+
     /// Compute operands and result, trying to deduce them if normal memory access returns a None
     /// value.
     pub fn compute_operands(
@@ -623,7 +955,7 @@ impl VirtualMachine {
             Some(dst) => dst,
             None => {
                 deduced_operands.set_dst(true);
-                self.compute_dst_deductions(instruction, &res)?
+                self.compute_dst_deductions(dst_addr, instruction, &res)?
             }
         };
         let accessed_addresses = OperandsAddresses {
@@ -770,7 +1102,10 @@ impl VirtualMachine {
 
     ///Adds a new segment and to the memory and returns its starting location as a Relocatable value.
     pub fn add_memory_segment(&mut self) -> Relocatable {
-        self.segments.add()
+        let base = self.segments.add();
+        #[cfg(feature = "std")]
+        self.emit_run_event(|| crate::vm::run_events::RunEvent::SegmentAdded { base });
+        base
     }
 
     pub fn get_ap(&self) -> Relocatable {
@@ -822,15 +1157,105 @@ impl VirtualMachine {
         self.segments.memory.insert_value(key, val)
     }
 
+    /// Writes `value` at ap, as the value of a `nondet %{ ... %}` expression read by the
+    /// instructions following the hint, and returns its address.
+    ///
+    /// Fails if the cell at ap was already written with another value.
+    pub fn insert_nondet<T: Into<MaybeRelocatable>>(
+        &mut self,
+        value: T,
+    ) -> Result<Relocatable, MemoryError> {
+        let ap = self.get_ap();
+        self.insert_value(ap, value)?;
+        Ok(ap)
+    }
+
+    /// Chooses the branch of the nondeterministic jump at pc, a `jmp label if [reg + off] != 0`
+    /// whose condition is left for a hint to write: the jump is taken if `taken`, and the next
+    /// instruction runs otherwise.
+    ///
+    /// Writes 1 or 0 to the condition. If the condition was already written, checks that it takes
+    /// the chosen branch instead, as the prover can't change it, failing with
+    /// [VirtualMachineError::NondetJumpConflict] otherwise. Fails with
+    /// [VirtualMachineError::NotAConditionalJump] if the instruction at pc isn't a conditional
+    /// jump.
+    pub fn insert_nondet_jump(&mut self, taken: bool) -> Result<(), VirtualMachineError> {
+        let instruction = self.decode_current_instruction()?;
+        if instruction.pc_update != PcUpdate::Jnz {
+            return Err(VirtualMachineError::NotAConditionalJump(Box::new(
+                self.run_context.pc,
+            )));
+        }
+        let condition_addr = self.run_context.compute_dst_addr(&instruction)?;
+        match self.get_maybe(&condition_addr) {
+            Some(condition) if VirtualMachine::is_zero(&condition) == taken => Err(
+                VirtualMachineError::NondetJumpConflict(Box::new((self.run_context.pc, condition))),
+            ),
+            Some(_) => Ok(()),
+            None => {
+                let condition = if taken {
+                    Felt252::one()
+                } else {
+                    Felt252::zero()
+                };
+                Ok(self.insert_value(condition_addr, condition)?)
+            }
+        }
+    }
+
+    /// Instructions decoded so far, indexed by their offset in the program segment
+    pub(crate) fn decoded_instructions(&self) -> &[DecodedInstruction] {
+        &self.instruction_cache
+    }
+
+    /// Adds `decoded` instructions, starting at offset `base` of the program segment, to the
+    /// ones decoded so far.
+    pub(crate) fn load_decoded_instructions(
+        &mut self,
+        base: usize,
+        decoded: &[DecodedInstruction],
+    ) {
+        // The writes loading the program must not drop the instructions loaded for it
+        self.invalidate_program_writes();
+        let end = base + decoded.len();
+        if self.instruction_cache.len() < end {
+            self.instruction_cache.resize(end, None);
+        }
+        for (cached, decoded) in self.instruction_cache[base..end].iter_mut().zip(decoded) {
+            if cached.is_none() {
+                *cached = *decoded;
+            }
+        }
+    }
+
+    /// Drops the instructions decoded at the offsets of the program segment in `range`, so they are
+    /// decoded again from memory when the pc reaches them.
+    ///
+    /// Every write to a cell of the program segment, whether through the VM, `segments` or
+    /// `segments.memory`, drops the instruction decoded there before the next step. This is only
+    /// needed if the cells change without being written, e.g. when the cells of the memory are
+    /// replaced directly.
+    pub fn invalidate_decoded_range(&mut self, range: Range<usize>) {
+        let end = range.end.min(self.instruction_cache.len());
+        let start = range.start.min(end);
+        self.instruction_cache[start..end].fill(None);
+    }
+
+    // Drops the instructions decoded at the cells of the program segment written since the last
+    // call
+    fn invalidate_program_writes(&mut self) {
+        let program_writes = self.segments.memory.take_program_writes();
+        if !program_writes.is_empty() {
+            self.invalidate_decoded_range(program_writes);
+        }
+    }
+
     ///Writes data into the memory from address ptr and returns the first address after the data.
     pub fn load_data(
         &mut self,
         ptr: Relocatable,
         data: &Vec<MaybeRelocatable>,
     ) -> Result<Relocatable, MemoryError> {
-        if ptr.segment_index == 0 {
-            self.instruction_cache.resize(data.len(), None);
-        }
         self.segments.load_data(ptr, data)
     }
 
@@ -901,8 +1326,23 @@ impl VirtualMachine {
 
         Err(VirtualMachineError::NoSignatureBuiltin)
     }
+
+    /// Returns the output builtin runner, to save and restore its state around the child tasks
+    /// of a bootloader.
+    pub fn get_output_builtin_mut(
+        &mut self,
+    ) -> Result<&mut OutputBuiltinRunner, VirtualMachineError> {
+        for builtin in self.get_builtin_runners_as_mut() {
+            if let BuiltinRunner::Output(output_builtin) = builtin {
+                return Ok(output_builtin);
+            };
+        }
+
+        Err(VirtualMachineError::NoOutputBuiltin)
+    }
     pub fn disable_trace(&mut self) {
-        self.trace = None
+        self.trace = None;
+        self.compressed_trace = None;
     }
 
     #[doc(hidden)]
@@ -960,6 +1400,16 @@ impl VirtualMachine {
     pub fn write_output(
         &mut self,
         writer: &mut impl core::fmt::Write,
+    ) -> Result<(), VirtualMachineError> {
+        self.write_output_in_format(writer, FeltFormat::Decimal)
+    }
+
+    /// Writes the output like [write_output](Self::write_output), with the felts in `format`.
+    /// Felts are written as signed decimals in the decimal format.
+    pub fn write_output_in_format(
+        &mut self,
+        writer: &mut impl core::fmt::Write,
+        format: FeltFormat,
     ) -> Result<(), VirtualMachineError> {
         let builtin = match self
             .builtin_runners
@@ -980,7 +1430,10 @@ impl VirtualMachine {
                 .get(&Relocatable::from((segment_index as isize, i)))
             {
                 Some(val) => match val.as_ref() {
-                    MaybeRelocatable::Int(num) => format!("{}", num.to_signed_felt()),
+                    MaybeRelocatable::Int(num) => match format {
+                        FeltFormat::Decimal => format!("{}", num.to_signed_felt()),
+                        format => format!("{}", format.display(num)),
+                    },
                     MaybeRelocatable::RelocatableValue(rel) => format!("{}", rel),
                 },
                 _ => "<missing>".to_string(),
@@ -992,26 +1445,88 @@ impl VirtualMachine {
         Ok(())
     }
 
-    ///Relocates the VM's trace, turning relocatable registers to numbered ones
+    ///Relocates the VM's trace, turning relocatable registers to numbered ones.
+    ///With the `parallel` feature, the entries are relocated by several threads.
     pub fn relocate_trace(&mut self, relocation_table: &[usize]) -> Result<(), TraceError> {
-        if let Some(ref mut trace) = self.trace {
-            if self.trace_relocated {
-                return Err(TraceError::AlreadyRelocated);
-            }
-            let segment_1_base = relocation_table
-                .get(1)
-                .ok_or(TraceError::NoRelocationFound)?;
+        if self.trace.is_none() && self.compressed_trace.is_none() {
+            return Ok(());
+        }
+        if self.trace_relocated {
+            return Err(TraceError::AlreadyRelocated);
+        }
+        let segment_1_base = relocation_table
+            .get(1)
+            .ok_or(TraceError::NoRelocationFound)?;
 
-            trace.iter_mut().for_each(|entry| {
+        if let Some(ref mut trace) = self.trace {
+            #[cfg(feature = "parallel")]
+            let entries = trace.par_iter_mut();
+            #[cfg(not(feature = "parallel"))]
+            let entries = trace.iter_mut();
+            entries.for_each(|entry| {
                 entry.pc += 1;
                 entry.ap += segment_1_base;
                 entry.fp += segment_1_base;
             });
-            self.trace_relocated = true;
         }
+        if let Some(ref mut compressed_trace) = self.compressed_trace {
+            compressed_trace.shift(1, *segment_1_base, *segment_1_base);
+        }
+        self.trace_relocated = true;
         Ok(())
     }
 
+    /// Annotates the trace with the steps whose operands live in the segment of a builtin.
+    /// Must be called before the trace is relocated.
+    pub fn get_builtin_interactions(&self) -> Result<BuiltinInteractions, VirtualMachineError> {
+        if self.trace_relocated {
+            return Err(TraceError::AlreadyRelocated.into());
+        }
+        let trace = self.trace.as_ref().ok_or(TraceError::TraceNotEnabled)?;
+        let builtin_segments: HashMap<isize, &'static str> = self
+            .builtin_runners
+            .iter()
+            .map(|builtin| (builtin.base() as isize, builtin.name()))
+            .collect();
+
+        let mut interactions = Vec::new();
+        for (step, entry) in trace.iter().enumerate() {
+            let run_context = RunContext {
+                pc: Relocatable::from((0, entry.pc)),
+                ap: entry.ap,
+                fp: entry.fp,
+            };
+            let instruction = decode_instruction(
+                self.segments
+                    .memory
+                    .get_integer(run_context.pc)?
+                    .to_u64()
+                    .ok_or(VirtualMachineError::InvalidInstructionEncoding)?,
+            )?;
+            let dst_addr = run_context.compute_dst_addr(&instruction)?;
+            let op0_addr = run_context.compute_op0_addr(&instruction)?;
+            let op0 = self.segments.memory.get(&op0_addr);
+            let op1_addr = run_context.compute_op1_addr(&instruction, op0.as_deref())?;
+
+            for (operand, address) in [
+                (Operand::Dst, dst_addr),
+                (Operand::Op0, op0_addr),
+                (Operand::Op1, op1_addr),
+            ] {
+                if let Some(builtin) = builtin_segments.get(&address.segment_index) {
+                    interactions.push(BuiltinInteraction {
+                        step,
+                        pc: entry.pc,
+                        builtin,
+                        operand,
+                        address,
+                    });
+                }
+            }
+        }
+        Ok(BuiltinInteractions(interactions))
+    }
+
     pub fn get_relocated_trace(&self) -> Result<&Vec<TraceEntry>, TraceError> {
         if self.trace_relocated {
             self.trace.as_ref().ok_or(TraceError::TraceNotEnabled)
@@ -1019,6 +1534,17 @@ impl VirtualMachine {
             Err(TraceError::TraceNotRelocated)
         }
     }
+
+    /// Returns the relocated trace of a run recorded with [compress_trace](Self::compress_trace).
+    pub fn get_relocated_compressed_trace(&self) -> Result<&CompressedTrace, TraceError> {
+        if self.trace_relocated {
+            self.compressed_trace
+                .as_ref()
+                .ok_or(TraceError::TraceNotEnabled)
+        } else {
+            Err(TraceError::TraceNotRelocated)
+        }
+    }
 }
 
 pub struct VirtualMachineBuilder {
@@ -1031,6 +1557,8 @@ pub struct VirtualMachineBuilder {
     run_finished: bool,
     #[cfg(feature = "hooks")]
     pub(crate) hooks: crate::vm::hooks::Hooks,
+    #[cfg(feature = "opcode-extensions")]
+    pub(crate) opcode_extensions: crate::vm::opcode_extensions::OpcodeExtensions,
 }
 
 impl Default for VirtualMachineBuilder {
@@ -1051,6 +1579,8 @@ impl Default for VirtualMachineBuilder {
             run_finished: false,
             #[cfg(feature = "hooks")]
             hooks: Default::default(),
+            #[cfg(feature = "opcode-extensions")]
+            opcode_extensions: Default::default(),
         }
     }
 }
@@ -1105,6 +1635,7 @@ impl VirtualMachineBuilder {
             run_context: self.run_context,
             builtin_runners: self.builtin_runners,
             trace: self.trace,
+            compressed_trace: None,
             current_step: self.current_step,
             skip_instruction_execution: self.skip_instruction_execution,
             segments: self.segments,
@@ -1112,8 +1643,20 @@ impl VirtualMachineBuilder {
             run_finished: self.run_finished,
             trace_relocated: false,
             instruction_cache: Vec::new(),
+            hint_sandbox: false,
+            parallel_hints: false,
+            strict_memory_reads: false,
+            step_sampler: None,
+            pc_execution_counter: None,
+            memory_journal: None,
+            unknown_hint_policy: Default::default(),
+            skipped_hints: Vec::new(),
             #[cfg(feature = "hooks")]
             hooks: self.hooks,
+            #[cfg(feature = "opcode-extensions")]
+            opcode_extensions: self.opcode_extensions,
+            #[cfg(feature = "std")]
+            run_events: None,
         }
     }
 }
@@ -1121,18 +1664,24 @@ impl VirtualMachineBuilder {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::serde::deserialize_program::BuiltinName;
     use crate::stdlib::collections::HashMap;
     use crate::types::program::Program;
     use crate::vm::runners::builtin_runner::{
-        BITWISE_BUILTIN_NAME, EC_OP_BUILTIN_NAME, HASH_BUILTIN_NAME,
+        BITWISE_BUILTIN_NAME, EC_OP_BUILTIN_NAME, HASH_BUILTIN_NAME, OUTPUT_BUILTIN_NAME,
     };
-    use crate::vm::vm_memory::memory::Memory;
+    use crate::vm::vm_memory::memory::{Memory, MemoryCell};
     use crate::{
         any_box,
         hint_processor::builtin_hint_processor::builtin_hint_processor_definition::{
-            BuiltinHintProcessor, HintProcessorData,
+            BuiltinHintProcessor, HintFunc, HintProcessorData,
         },
+        hint_processor::builtin_hint_processor::hint_utils::{
+            get_ptr_from_var_name, insert_value_from_var_name,
+        },
+        hint_processor::hint_processor_definition::HintReference,
         relocatable,
+        stdlib::rc::Rc,
         types::{
             instance_definitions::{
                 bitwise_instance_def::BitwiseInstanceDef, ec_op_instance_def::EcOpInstanceDef,
@@ -1839,6 +2388,51 @@ mod tests {
         assert_eq!(vm.run_context.fp, 11);
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn update_registers_by_kind_matches_update_registers() {
+        for encoded_instruction in [
+            0x482680017ffd8000,
+            0x1104800180018000,
+            0x208b7fff7fff7ffe,
+            0x10780017fff7fff,
+            0x20680017fff7fff,
+            0x40780017fff7fff,
+            0x80b7fff7fff7fff,
+        ] {
+            let instruction = decode_instruction(encoded_instruction).unwrap();
+            let kind = instruction.kind();
+            let operands = || Operands {
+                dst: MaybeRelocatable::Int(Felt252::new(3)),
+                res: Some(match kind {
+                    InstructionKind::Ret => MaybeRelocatable::from((0, 2)),
+                    _ => MaybeRelocatable::Int(Felt252::new(4)),
+                }),
+                op0: MaybeRelocatable::Int(Felt252::new(5)),
+                op1: MaybeRelocatable::Int(Felt252::new(6)),
+            };
+
+            let mut vm = vm!();
+            run_context!(vm, 4, 5, 6);
+            let mut vm_by_kind = vm!();
+            run_context!(vm_by_kind, 4, 5, 6);
+
+            vm.update_registers(&instruction, operands()).unwrap();
+            vm_by_kind
+                .update_registers_by_kind(&instruction, kind, operands())
+                .unwrap();
+            assert_eq!(
+                (vm.run_context.pc, vm.run_context.ap, vm.run_context.fp),
+                (
+                    vm_by_kind.run_context.pc,
+                    vm_by_kind.run_context.ap,
+                    vm_by_kind.run_context.fp
+                ),
+                "{kind:?}"
+            );
+        }
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn is_zero_int_value() {
@@ -2109,7 +2703,7 @@ mod tests {
         let dst = MaybeRelocatable::Int(Felt252::new(3));
         let op0 = MaybeRelocatable::Int(Felt252::new(2));
         assert_matches!(
-            vm.deduce_op1(&instruction, Some(&dst), Some(op0)),
+            vm.deduce_op1(&instruction, Some(&dst), Some(&op0)),
             Ok::<(Option<MaybeRelocatable>, Option<MaybeRelocatable>), VirtualMachineError>((
                 x,
                 y
@@ -2166,7 +2760,7 @@ mod tests {
         let dst = MaybeRelocatable::Int(Felt252::new(4));
         let op0 = MaybeRelocatable::Int(Felt252::new(2));
         assert_matches!(
-            vm.deduce_op1(&instruction, Some(&dst), Some(op0)),
+            vm.deduce_op1(&instruction, Some(&dst), Some(&op0)),
             Ok::<(Option<MaybeRelocatable>, Option<MaybeRelocatable>), VirtualMachineError>((
                 x,
                 y
@@ -2197,7 +2791,7 @@ mod tests {
         let dst = MaybeRelocatable::Int(Felt252::new(4));
         let op0 = MaybeRelocatable::Int(Felt252::new(0));
         assert_matches!(
-            vm.deduce_op1(&instruction, Some(&dst), Some(op0)),
+            vm.deduce_op1(&instruction, Some(&dst), Some(&op0)),
             Ok::<(Option<MaybeRelocatable>, Option<MaybeRelocatable>), VirtualMachineError>((
                 None, None
             ))
@@ -2225,7 +2819,7 @@ mod tests {
 
         let op0 = MaybeRelocatable::Int(Felt252::new(0));
         assert_matches!(
-            vm.deduce_op1(&instruction, None, Some(op0)),
+            vm.deduce_op1(&instruction, None, Some(&op0)),
             Ok::<(Option<MaybeRelocatable>, Option<MaybeRelocatable>), VirtualMachineError>((
                 None, None
             ))
@@ -2663,7 +3257,7 @@ mod tests {
                 &mut hint_processor,
                 exec_scopes_ref!(),
                 &HashMap::new(),
-                &HashMap::new(),
+                &Constants::new(),
             ),
             Ok(())
         );
@@ -2695,6 +3289,113 @@ mod tests {
         assert_matches!(error, VirtualMachineError::NoDst);
     }
 
+    fn assert_eq_instruction(res: Res, op1_addr: Op1Addr) -> Instruction {
+        Instruction {
+            off0: 0,
+            off1: 1,
+            off2: 2,
+            dst_register: Register::AP,
+            op0_register: Register::AP,
+            op1_addr,
+            res,
+            pc_update: PcUpdate::Regular,
+            ap_update: ApUpdate::Regular,
+            fp_update: FpUpdate::Regular,
+            opcode: Opcode::AssertEq,
+        }
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn compute_operands_strict_memory_reads_op0() {
+        // [ap] = [ap + 1] + [ap + 2], with [ap + 1] missing
+        let instruction = assert_eq_instruction(Res::Add, Op1Addr::AP);
+        let mut vm = vm!();
+        vm.segments = segments![((1, 0), 5), ((1, 2), 3)];
+
+        let (operands, _, _) = vm.compute_operands(&instruction).unwrap();
+        assert_eq!(operands.op0, mayberelocatable!(2));
+
+        vm.set_strict_memory_reads(true);
+        assert_matches!(
+            vm.compute_operands(&instruction),
+            Err(VirtualMachineError::UninitializedMemoryRead(bx))
+                if bx.0 == relocatable!(0, 0) && bx.1 == "op0" && bx.2 == relocatable!(1, 1)
+                    && bx.3 == instruction
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn compute_operands_strict_memory_reads_copy() {
+        // [ap] = [ap + 2] writes [ap + 2]
+        let instruction = assert_eq_instruction(Res::Op1, Op1Addr::AP);
+        let mut vm = vm!();
+        vm.set_strict_memory_reads(true);
+        vm.segments = segments![((1, 0), 5), ((1, 1), 0)];
+
+        let (operands, _, deduced_operands) = vm.compute_operands(&instruction).unwrap();
+        assert_eq!(operands.op1, mayberelocatable!(5));
+        assert!(deduced_operands.was_op1_deducted());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn compute_operands_strict_memory_reads_builtin() {
+        // [ap] = [ap + 1] + [[ap + 1] + 2], with the output of the bitwise builtin as op1
+        let instruction = assert_eq_instruction(Res::Add, Op1Addr::Op0);
+        let mut builtin = BitwiseBuiltinRunner::new(&BitwiseInstanceDef::default(), true);
+        builtin.base = 2;
+        let mut vm = vm!();
+        vm.builtin_runners.push(builtin.into());
+        vm.set_strict_memory_reads(true);
+        vm.segments = segments![((2, 0), 12), ((2, 1), 10), ((1, 1), (2, 0))];
+
+        let (operands, _, _) = vm.compute_operands(&instruction).unwrap();
+        assert_eq!(operands.op1, mayberelocatable!(8));
+        assert_eq!(operands.dst, mayberelocatable!(2, 8));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn compute_operands_strict_memory_reads_jnz_dst() {
+        // jmp rel 4 if [ap] != 0, with [ap] missing
+        let instruction = Instruction {
+            off0: 0,
+            off1: 1,
+            off2: 1,
+            dst_register: Register::AP,
+            op0_register: Register::AP,
+            op1_addr: Op1Addr::Imm,
+            res: Res::Unconstrained,
+            pc_update: PcUpdate::Jnz,
+            ap_update: ApUpdate::Regular,
+            fp_update: FpUpdate::Regular,
+            opcode: Opcode::NOp,
+        };
+        let mut vm = vm!();
+        vm.segments = segments![((0, 1), 4), ((1, 1), 4)];
+        assert_matches!(
+            vm.compute_operands(&instruction),
+            Err(VirtualMachineError::NoDst)
+        );
+
+        vm.set_strict_memory_reads(true);
+        assert_matches!(
+            vm.compute_operands(&instruction),
+            Err(VirtualMachineError::UninitializedMemoryRead(bx))
+                if bx.1 == "dst" && bx.2 == relocatable!(1, 0)
+        );
+    }
+
+    fn operands_addresses() -> OperandsAddresses {
+        OperandsAddresses {
+            dst_addr: relocatable!(1, 0),
+            op0_addr: relocatable!(1, 1),
+            op1_addr: relocatable!(1, 2),
+        }
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn opcode_assertions_res_unconstrained() {
@@ -2721,7 +3422,12 @@ mod tests {
 
         let vm = vm!();
 
-        let error = vm.opcode_assertions(&instruction, &operands);
+        let error = vm.opcode_assertions(
+            &instruction,
+            &operands,
+            &operands_addresses(),
+            DeducedOperands::default(),
+        );
         assert_matches!(error, Err(VirtualMachineError::UnconstrainedResAssertEq));
     }
 
@@ -2749,13 +3455,34 @@ mod tests {
             op1: MaybeRelocatable::Int(Felt252::new(10_i32)),
         };
 
-        let vm = vm!();
+        let mut vm = vm!();
+        vm.run_context.pc = relocatable!(0, 4);
+        let mut deduced_operands = DeducedOperands::default();
+        deduced_operands.set_op1(true);
 
         assert_matches!(
-            vm.opcode_assertions(&instruction, &operands),
+            vm.opcode_assertions(&instruction, &operands, &operands_addresses(), deduced_operands),
             Err(VirtualMachineError::DiffAssertValues(bx))
-            if *bx == (MaybeRelocatable::Int(Felt252::new(9_i32)),
-                 MaybeRelocatable::Int(Felt252::new(8_i32)))
+            if *bx == OperandsAudit {
+                pc: relocatable!(0, 4),
+                instruction,
+                dst: AuditedOperand {
+                    address: relocatable!(1, 0),
+                    value: mayberelocatable!(9),
+                    source: OperandSource::Read,
+                },
+                op0: AuditedOperand {
+                    address: relocatable!(1, 1),
+                    value: mayberelocatable!(9),
+                    source: OperandSource::Read,
+                },
+                op1: AuditedOperand {
+                    address: relocatable!(1, 2),
+                    value: mayberelocatable!(10),
+                    source: OperandSource::Deduced,
+                },
+                res: mayberelocatable!(8),
+            }
         );
     }
 
@@ -2786,8 +3513,9 @@ mod tests {
         let vm = vm!();
 
         assert_matches!(
-            vm.opcode_assertions(&instruction, &operands),
-            Err(VirtualMachineError::DiffAssertValues(bx)) if *bx == (MaybeRelocatable::from((1, 1)), MaybeRelocatable::from((1, 2)))
+            vm.opcode_assertions(&instruction, &operands, &operands_addresses(), DeducedOperands::default()),
+            Err(VirtualMachineError::DiffAssertValues(bx))
+            if bx.dst.value == MaybeRelocatable::from((1, 1)) && bx.res == MaybeRelocatable::from((1, 2))
         );
     }
 
@@ -2819,7 +3547,7 @@ mod tests {
         vm.run_context.pc = relocatable!(0, 4);
 
         assert_matches!(
-            vm.opcode_assertions(&instruction, &operands),
+            vm.opcode_assertions(&instruction, &operands, &operands_addresses(), DeducedOperands::default()),
             Err(VirtualMachineError::CantWriteReturnPc(bx)) if *bx == (mayberelocatable!(9), mayberelocatable!(0, 5))
         );
     }
@@ -2851,7 +3579,7 @@ mod tests {
         vm.run_context.fp = 6;
 
         assert_matches!(
-            vm.opcode_assertions(&instruction, &operands),
+            vm.opcode_assertions(&instruction, &operands, &operands_addresses(), DeducedOperands::default()),
             Err(VirtualMachineError::CantWriteReturnFp(bx)) if *bx == (mayberelocatable!(8), mayberelocatable!(1, 6))
         );
     }
@@ -2892,7 +3620,7 @@ mod tests {
                 &mut hint_processor,
                 exec_scopes_ref!(),
                 &HashMap::new(),
-                &HashMap::new(),
+                &Constants::new(),
             ),
             Ok(())
         );
@@ -2974,7 +3702,7 @@ mod tests {
                     &mut hint_processor,
                     exec_scopes_ref!(),
                     &HashMap::new(),
-                    &HashMap::new(),
+                    &Constants::new(),
                 ),
                 Ok(())
             );
@@ -3070,7 +3798,7 @@ mod tests {
                 &mut hint_processor,
                 exec_scopes_ref!(),
                 &HashMap::new(),
-                &HashMap::new(),
+                &Constants::new(),
             ),
             Ok(())
         );
@@ -3091,7 +3819,7 @@ mod tests {
                 &mut hint_processor,
                 exec_scopes_ref!(),
                 &HashMap::new(),
-                &HashMap::new(),
+                &Constants::new(),
             ),
             Ok(())
         );
@@ -3113,7 +3841,7 @@ mod tests {
                 &mut hint_processor,
                 exec_scopes_ref!(),
                 &HashMap::new(),
-                &HashMap::new(),
+                &Constants::new(),
             ),
             Ok(())
         );
@@ -3678,7 +4406,7 @@ mod tests {
                     &mut hint_processor,
                     exec_scopes_ref!(),
                     &hint_data_dictionary,
-                    &HashMap::new(),
+                    &Constants::new(),
                 ),
                 Ok(())
             );
@@ -3955,6 +4683,109 @@ mod tests {
         );
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn insert_nondet() {
+        let mut vm = vm!();
+        vm.segments = segments![((1, 0), 1)];
+        vm.set_ap(1);
+        assert_eq!(vm.insert_nondet(5), Ok(relocatable!(1, 1)));
+        assert_eq!(
+            vm.get_maybe(&relocatable!(1, 1)),
+            Some(mayberelocatable!(5))
+        );
+        vm.set_ap(0);
+        assert_matches!(vm.insert_nondet(5), Err(MemoryError::InconsistentMemory(_)));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn step_instruction_relocatable_add_error_has_context() {
+        let mut vm = vm!();
+        // [ap] = [ap + 1] + [ap + 2]
+        vm.segments = segments![
+            ((0, 0), 0x4030800280018000_i64),
+            ((1, 1), (2, 0)),
+            ((1, 2), (2, 1))
+        ];
+        let err = vm.step_instruction().unwrap_err();
+        assert_matches!(
+            &err,
+            VirtualMachineError::InstructionMath(bx)
+                if bx.0 == relocatable!(0, 0)
+                    && bx.1.res == Res::Add
+                    && bx.2 == MathError::RelocatableAdd(Box::new((
+                        relocatable!(2, 0),
+                        relocatable!(2, 1)
+                    )))
+        );
+        assert!(err
+            .to_string()
+            .starts_with("Failed to run the instruction at pc 0:0: Operation failed: 2:0 + 2:1"));
+    }
+
+    // jmp rel 3 if [ap] != 0
+    fn nondet_jump_vm() -> VirtualMachine {
+        let mut vm = vm!();
+        vm.segments = segments![((0, 0), 0x20680017fff8000_i64), ((0, 1), 3)];
+        vm.segments.add();
+        // op0 is read from [fp - 1], so fp can't be at the start of the execution segment
+        run_context!(vm, 0, 0, 1);
+        vm
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn insert_nondet_jump_taken() {
+        let mut vm = nondet_jump_vm();
+        assert_matches!(vm.insert_nondet_jump(true), Ok(()));
+        assert_eq!(
+            vm.get_maybe(&relocatable!(1, 0)),
+            Some(mayberelocatable!(1))
+        );
+        vm.step_instruction().unwrap();
+        assert_eq!(vm.get_pc(), relocatable!(0, 3));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn insert_nondet_jump_not_taken() {
+        let mut vm = nondet_jump_vm();
+        assert_matches!(vm.insert_nondet_jump(false), Ok(()));
+        assert_eq!(
+            vm.get_maybe(&relocatable!(1, 0)),
+            Some(mayberelocatable!(0))
+        );
+        vm.step_instruction().unwrap();
+        assert_eq!(vm.get_pc(), relocatable!(0, 2));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn insert_nondet_jump_condition_already_set() {
+        let mut vm = nondet_jump_vm();
+        vm.insert_value(relocatable!(1, 0), relocatable!(2, 0))
+            .unwrap();
+        assert_matches!(vm.insert_nondet_jump(true), Ok(()));
+        assert_matches!(
+            vm.insert_nondet_jump(false),
+            Err(VirtualMachineError::NondetJumpConflict(bx))
+                if *bx == (relocatable!(0, 0), mayberelocatable!(2, 0))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn insert_nondet_jump_not_a_jump() {
+        let mut vm = vm!();
+        vm.segments = segments![((0, 0), 0x480680017fff8000_i64), ((0, 1), 5)];
+        vm.segments.add();
+        assert_matches!(
+            vm.insert_nondet_jump(true),
+            Err(VirtualMachineError::NotAConditionalJump(bx)) if *bx == relocatable!(0, 0)
+        );
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn add_relocation_rule_test() {
@@ -4205,6 +5036,9 @@ mod tests {
                 segment_used_sizes: Some(vec![1]),
                 public_memory_offsets: HashMap::new(),
                 memory: Memory::new(),
+                segment_capacities: Vec::new(),
+                segment_names: crate::stdlib::collections::BTreeMap::new(),
+                free_segments: crate::stdlib::collections::BTreeSet::new(),
             })
             .skip_instruction_execution(true)
             .trace(Some(vec![TraceEntry {
@@ -4273,4 +5107,232 @@ mod tests {
                 .is_err());
         }
     }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn get_builtin_interactions() {
+        let program = Program::from_bytes(
+            include_bytes!("../../../cairo_programs/pedersen_test.json"),
+            Some("main"),
+        )
+        .unwrap();
+        let mut cairo_runner = cairo_runner!(program);
+        let mut vm = vm!(true);
+        let end = cairo_runner.initialize(&mut vm).unwrap();
+        cairo_runner
+            .run_until_pc(end, &mut vm, &mut BuiltinHintProcessor::new_empty())
+            .unwrap();
+
+        let builtin_base = |name| {
+            vm.builtin_runners
+                .iter()
+                .find(|builtin| builtin.name() == name)
+                .unwrap()
+                .base() as isize
+        };
+        let (pedersen_base, output_base) = (
+            builtin_base(HASH_BUILTIN_NAME),
+            builtin_base(OUTPUT_BUILTIN_NAME),
+        );
+        let interactions = vm.get_builtin_interactions().unwrap();
+
+        // hash2 writes x and y, and reads the result
+        let mut pedersen_addresses: Vec<_> = interactions
+            .iter_builtin_interactions(BuiltinName::pedersen)
+            .map(|interaction| interaction.address)
+            .collect();
+        pedersen_addresses.sort();
+        pedersen_addresses.dedup();
+        assert_eq!(
+            pedersen_addresses,
+            vec![
+                Relocatable::from((pedersen_base, 0)),
+                Relocatable::from((pedersen_base, 1)),
+                Relocatable::from((pedersen_base, 2))
+            ]
+        );
+        // The hash is written to the output
+        assert!(interactions
+            .iter_builtin_interactions(BuiltinName::output)
+            .all(|interaction| interaction.address == Relocatable::from((output_base, 0))));
+        assert_eq!(
+            interactions
+                .iter_builtin_interactions(BuiltinName::range_check)
+                .count(),
+            0
+        );
+        assert!(interactions
+            .iter()
+            .all(|interaction| interaction.step < vm.current_step));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn get_builtin_interactions_trace_not_enabled() {
+        let vm = vm!();
+        assert_matches!(
+            vm.get_builtin_interactions(),
+            Err(VirtualMachineError::TracerError(
+                TraceError::TraceNotEnabled
+            ))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn get_builtin_interactions_trace_relocated() {
+        let mut vm = vm!(true);
+        vm.trace_relocated = true;
+        assert_matches!(
+            vm.get_builtin_interactions(),
+            Err(VirtualMachineError::TracerError(
+                TraceError::AlreadyRelocated
+            ))
+        );
+    }
+
+    fn step_sandboxed_hint(code: &str, hint_sandbox: bool) -> Result<(), VirtualMachineError> {
+        let mut vm = vm!();
+        // ids.ptr, at fp, points to segment 2, while segment 3 isn't reachable from the ids
+        vm.segments = segments![((1, 0), (2, 0))];
+        vm.add_memory_segment();
+        vm.add_memory_segment();
+        vm.set_hint_sandbox(hint_sandbox);
+
+        let mut hint_processor = BuiltinHintProcessor::new_empty();
+        hint_processor.add_hint(
+            "write_through_ids".to_string(),
+            Rc::new(HintFunc(Box::new(|vm, _, ids_data, ap_tracking, _| {
+                let ptr = get_ptr_from_var_name("ptr", vm, ids_data, ap_tracking)?;
+                insert_value_from_var_name("value", ptr, vm, ids_data, ap_tracking)?;
+                Ok(vm.insert_value(ptr, 1)?)
+            }))),
+        );
+        hint_processor.add_hint(
+            "write_added_segment".to_string(),
+            Rc::new(HintFunc(Box::new(|vm, _, _, _, _| {
+                let segment = vm.add_memory_segment();
+                Ok(vm.insert_value(segment, 1)?)
+            }))),
+        );
+        hint_processor.add_hint(
+            "write_unreachable_segment".to_string(),
+            Rc::new(HintFunc(Box::new(|vm, _, _, _, _| {
+                Ok(vm.insert_value((3, 0).into(), 1)?)
+            }))),
+        );
+
+        let hint_data = HintProcessorData::new_default(
+            code.to_string(),
+            non_continuous_ids_data![("ptr", 0), ("value", 1)],
+        );
+        vm.step_hint(
+            &mut hint_processor,
+            exec_scopes_ref!(),
+            &HashMap::from([(0, vec![any_box!(hint_data)])]),
+            &Constants::new(),
+        )
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn step_sandboxed_hint_write_scope() {
+        assert_matches!(step_sandboxed_hint("write_through_ids", true), Ok(()));
+        assert_matches!(step_sandboxed_hint("write_added_segment", true), Ok(()));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn step_sandboxed_hint_write_outside_sandbox() {
+        assert_matches!(
+            step_sandboxed_hint("write_unreachable_segment", true),
+            Err(VirtualMachineError::Hint(bx)) if matches!(
+                &*bx,
+                (0, HintError::WriteOutsideSandbox(write))
+                    if **write == ("write_unreachable_segment".to_string(), (3, 0).into())
+            )
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn step_hint_write_outside_sandbox_disabled() {
+        assert_matches!(
+            step_sandboxed_hint("write_unreachable_segment", false),
+            Ok(())
+        );
+    }
+
+    // A vm which ran `ap += 1` at pc 0:0, decoding it, with its memory then replaced by one
+    // holding `[ap] = 5; ap++` from 0:0 but for the instruction itself
+    fn vm_with_replaced_program_segment() -> VirtualMachine {
+        let mut vm = vm!();
+        vm.segments = segments![
+            ((0, 0), 0x40780017fff7fff_i64),
+            ((0, 1), 1),
+            ((1, 0), (2, 0)),
+            ((1, 1), (3, 0))
+        ];
+        run_context!(vm, 0, 2, 2);
+        vm.step_instruction().unwrap();
+        assert_eq!(vm.run_context.ap, 3);
+        assert!(vm.decoded_instructions()[0].is_some());
+
+        vm.segments = segments![((0, 1), 5), ((1, 0), (2, 0)), ((1, 1), (3, 0))];
+        vm.set_pc(relocatable!(0, 0));
+        vm
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn write_to_program_segment_invalidates_decoded_instruction() {
+        let mut vm = vm_with_replaced_program_segment();
+        vm.insert_value(relocatable!(0, 0), Felt252::from(0x480680017fff8000_i64))
+            .unwrap();
+
+        vm.step_instruction().unwrap();
+        assert_eq!(vm.run_context.ap, 4);
+        assert_eq!(
+            vm.get_maybe(&relocatable!(1, 3)),
+            Some(mayberelocatable!(5))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn segments_write_to_program_segment_invalidates_decoded_instruction() {
+        let mut vm = vm_with_replaced_program_segment();
+        vm.segments
+            .load_data(
+                relocatable!(0, 0),
+                &vec![mayberelocatable!(0x480680017fff8000_i64)],
+            )
+            .unwrap();
+
+        vm.step_instruction().unwrap();
+        assert_eq!(vm.run_context.ap, 4);
+        assert_eq!(
+            vm.get_maybe(&relocatable!(1, 3)),
+            Some(mayberelocatable!(5))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn invalidate_decoded_range() {
+        let mut vm = vm_with_replaced_program_segment();
+        vm.segments.memory.data[0][0] =
+            Some(MemoryCell::new(mayberelocatable!(0x480680017fff8000_i64)));
+        vm.invalidate_decoded_range(0..1);
+        // Ranges past the decoded instructions are ignored
+        vm.invalidate_decoded_range(5..10);
+        assert!(vm.decoded_instructions().iter().all(Option::is_none));
+
+        vm.step_instruction().unwrap();
+        assert_eq!(vm.run_context.ap, 4);
+        assert_eq!(
+            vm.get_maybe(&relocatable!(1, 3)),
+            Some(mayberelocatable!(5))
+        );
+    }
 }
@@ -1,7 +1,10 @@
-use crate::stdlib::{borrow::Cow, collections::HashMap, fmt, prelude::*};
+use crate::stdlib::{borrow::Cow, collections::HashMap, fmt, ops::Range, prelude::*};
 
 use crate::{
-    types::relocatable::{MaybeRelocatable, Relocatable},
+    types::{
+        felt_format::FeltFormat,
+        relocatable::{MaybeRelocatable, Relocatable},
+    },
     utils::from_relocatable_to_indexes,
     vm::errors::memory_errors::MemoryError,
 };
@@ -9,11 +12,63 @@ use bitvec::prelude as bv;
 use core::cmp::Ordering;
 use felt::Felt252;
 use num_traits::ToPrimitive;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
+
+/// A check run on the cells written to a segment, such as the bounds of the range check builtin.
+///
+/// Rules are stored as trait objects, so custom builtins can register their own. Closures with the
+/// signature of [ValidationRule::validate] are rules too.
+pub trait ValidationRule {
+    /// Validates the cell at `address`, returning the addresses found valid by this check.
+    fn validate(
+        &self,
+        memory: &Memory,
+        address: Relocatable,
+    ) -> Result<Vec<Relocatable>, MemoryError>;
+
+    /// Validates the cells of the segment `segment_index` whose offsets fall within `range`,
+    /// returning the addresses found valid.
+    /// Gaps are skipped, as they are validated once (and if) they are written.
+    ///
+    /// Runs [ValidationRule::validate] on each cell that wasn't validated yet by default; rules
+    /// which can check a chunk of cells at once should override it.
+    fn validate_range(
+        &self,
+        memory: &Memory,
+        segment_index: usize,
+        range: Range<usize>,
+    ) -> Result<Vec<Relocatable>, MemoryError> {
+        let segment_len = memory.data.get(segment_index).map_or(0, Vec::len);
+        let mut validated = Vec::new();
+        let mut validated_set = AddressSet::new();
+        for offset in range.start..range.end.min(segment_len) {
+            let address = Relocatable::from((segment_index as isize, offset));
+            if memory.data[segment_index][offset].is_some()
+                && !memory.validated_addresses.contains(&address)
+                && !validated_set.contains(&address)
+            {
+                let addresses = self.validate(memory, address)?;
+                validated_set.extend(&addresses);
+                validated.extend(addresses);
+            }
+        }
+        Ok(validated)
+    }
+}
 
-pub struct ValidationRule(
-    #[allow(clippy::type_complexity)]
-    pub  Box<dyn Fn(&Memory, Relocatable) -> Result<Vec<Relocatable>, MemoryError>>,
-);
+impl<F> ValidationRule for F
+where
+    F: Fn(&Memory, Relocatable) -> Result<Vec<Relocatable>, MemoryError>,
+{
+    fn validate(
+        &self,
+        memory: &Memory,
+        address: Relocatable,
+    ) -> Result<Vec<Relocatable>, MemoryError> {
+        self(memory, address)
+    }
+}
 
 #[derive(Clone, Eq, Ord, PartialEq, PartialOrd, Debug)]
 pub(crate) struct MemoryCell(MaybeRelocatable, bool);
@@ -91,6 +146,120 @@ impl AddressSet {
     }
 }
 
+/// A cell present in both memories of a [MemoryDiff] holding different values
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChangedCell {
+    pub offset: usize,
+    pub old: MaybeRelocatable,
+    pub new: MaybeRelocatable,
+}
+
+/// Differences between two memories in a single segment, sorted by offset
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SegmentDiff {
+    pub segment_index: isize,
+    pub added: Vec<(usize, MaybeRelocatable)>,
+    pub removed: Vec<(usize, MaybeRelocatable)>,
+    pub changed: Vec<ChangedCell>,
+}
+
+impl SegmentDiff {
+    fn new(segment_index: isize) -> Self {
+        SegmentDiff {
+            segment_index,
+            added: Vec::new(),
+            removed: Vec::new(),
+            changed: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Result of [Memory::diff], only holding the segments that differ.
+/// Cells are `added` if they are only present in the other memory, and `removed` if they are
+/// only present in this one.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MemoryDiff {
+    pub segments: Vec<SegmentDiff>,
+}
+
+impl MemoryDiff {
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    fn push_segments(
+        &mut self,
+        segments: &[Vec<Option<MemoryCell>>],
+        other_segments: &[Vec<Option<MemoryCell>>],
+        segment_index: impl Fn(usize) -> isize,
+    ) {
+        for index in 0..segments.len().max(other_segments.len()) {
+            let lhs = segments.get(index).map(Vec::as_slice).unwrap_or_default();
+            let rhs = other_segments
+                .get(index)
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            let mut segment_diff = SegmentDiff::new(segment_index(index));
+            for offset in 0..lhs.len().max(rhs.len()) {
+                let old = lhs.get(offset).and_then(Option::as_ref);
+                let new = rhs.get(offset).and_then(Option::as_ref);
+                match (old, new) {
+                    (Some(old), Some(new)) if old.get_value() != new.get_value() => {
+                        segment_diff.changed.push(ChangedCell {
+                            offset,
+                            old: old.get_value().clone(),
+                            new: new.get_value().clone(),
+                        })
+                    }
+                    (Some(old), None) => {
+                        segment_diff.removed.push((offset, old.get_value().clone()))
+                    }
+                    (None, Some(new)) => segment_diff.added.push((offset, new.get_value().clone())),
+                    _ => {}
+                }
+            }
+            if !segment_diff.is_empty() {
+                self.segments.push(segment_diff);
+            }
+        }
+    }
+}
+
+impl fmt::Display for MemoryDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for segment in self.segments.iter() {
+            let index = segment.segment_index;
+            for (offset, value) in segment.removed.iter() {
+                writeln!(f, "- ({index},{offset}) : {value}")?;
+            }
+            for (offset, value) in segment.added.iter() {
+                writeln!(f, "+ ({index},{offset}) : {value}")?;
+            }
+            for cell in segment.changed.iter() {
+                writeln!(
+                    f,
+                    "~ ({index},{}) : {} -> {}",
+                    cell.offset, cell.old, cell.new
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Memory of a run, stored as one vector of cells per segment.
+///
+/// Gaps are stored as `None`, which doesn't take extra room: the accessed flag of [MemoryCell]
+/// leaves a niche for it. A cell takes 32 bytes on 64-bit targets, 24 of them for its value, so
+/// storing the values densely along with presence and accessed bitmaps would save a quarter of
+/// the cells, less once the heap of their felts is counted. On 4M cells, such a layout sped up
+/// pushes and sequential reads by 15 to 30%, a fraction of the time [Memory::insert] and the
+/// reads of the memory take around them, while every reader of the segments relies on them
+/// being slices of cells.
 pub struct Memory {
     pub(crate) data: Vec<Vec<Option<MemoryCell>>>,
     pub(crate) temp_data: Vec<Vec<Option<MemoryCell>>>,
@@ -98,7 +267,16 @@ pub struct Memory {
     // zero; that is, segment_index = -1 maps to key 0, -2 to key 1...
     pub(crate) relocation_rules: HashMap<usize, Relocatable>,
     pub validated_addresses: AddressSet,
-    validation_rules: Vec<Option<ValidationRule>>,
+    validation_rules: Vec<Option<Box<dyn ValidationRule>>>,
+    lazy_validation: bool,
+    // Cells written with lazy validation to segments with a validation rule, not validated yet
+    pending_validation: Vec<Relocatable>,
+    max_segment_offset: Option<usize>,
+    // Addresses of the cells written since `start_recording_writes`, for the hint sandbox
+    recorded_writes: Option<Vec<Relocatable>>,
+    // Offsets of the program segment written since the last `take_program_writes`, for the VM to
+    // drop the instructions it decoded there
+    program_writes: Range<usize>,
 }
 
 impl Memory {
@@ -109,6 +287,45 @@ impl Memory {
             relocation_rules: HashMap::new(),
             validated_addresses: AddressSet::new(),
             validation_rules: Vec::with_capacity(7),
+            lazy_validation: false,
+            pending_validation: Vec::new(),
+            max_segment_offset: None,
+            recorded_writes: None,
+            program_writes: 0..0,
+        }
+    }
+
+    /// Defers the validation of the cells written to segments with a [ValidationRule] until
+    /// [Memory::validate_pending_memory] is called, which the runner does when ending the run and
+    /// finalizing the segments.
+    /// Writes then skip the rule, but invalid values are only reported once the run ends instead
+    /// of at the step writing them.
+    pub fn set_lazy_validation(&mut self, lazy_validation: bool) {
+        self.lazy_validation = lazy_validation;
+    }
+
+    pub fn is_lazy_validation(&self) -> bool {
+        self.lazy_validation
+    }
+
+    /// Fails the writes growing a segment beyond `max_offset` with
+    /// [MemoryError::SegmentOffsetLimitExceeded], to catch runaway pointer arithmetic, e.g. from a
+    /// buggy hint, at the step or hint writing through the pointer instead of by running out of
+    /// memory. `None` disables the limit.
+    pub fn set_max_segment_offset(&mut self, max_offset: Option<usize>) {
+        self.max_segment_offset = max_offset;
+    }
+
+    pub fn max_segment_offset(&self) -> Option<usize> {
+        self.max_segment_offset
+    }
+
+    /// Returns a value displaying the memory like its [Display](fmt::Display) implementation,
+    /// with the felts in `format`.
+    pub fn display_in_format(&self, format: FeltFormat) -> MemoryDisplay<'_> {
+        MemoryDisplay {
+            memory: self,
+            format,
         }
     }
 
@@ -138,6 +355,11 @@ impl Memory {
         //Forgoing this check would allow data to be inserted in a different index
         let (len, capacity) = (segment.len(), segment.capacity());
         if len <= value_offset {
+            if let Some(max_offset) = self.max_segment_offset.filter(|max| value_offset > *max) {
+                return Err(MemoryError::SegmentOffsetLimitExceeded(Box::new((
+                    key, max_offset,
+                ))));
+            }
             let new_len = value_offset
                 .checked_add(1)
                 .ok_or(MemoryError::VecCapacityExceeded)?;
@@ -149,7 +371,26 @@ impl Memory {
         // At this point there's *something* in there
 
         match segment[value_offset] {
-            None => segment[value_offset] = Some(MemoryCell::new(val)),
+            None => {
+                segment[value_offset] = Some(MemoryCell::new(val));
+                if let Some(recorded_writes) = &mut self.recorded_writes {
+                    recorded_writes.push(key);
+                }
+                if key.segment_index == 0 {
+                    self.program_writes = if self.program_writes.is_empty() {
+                        value_offset..value_offset + 1
+                    } else {
+                        self.program_writes.start.min(value_offset)
+                            ..self.program_writes.end.max(value_offset + 1)
+                    };
+                }
+                if self.lazy_validation
+                    && !key.segment_index.is_negative()
+                    && matches!(self.validation_rules.get(value_index), Some(Some(_)))
+                {
+                    self.pending_validation.push(key);
+                }
+            }
             Some(ref current_cell) => {
                 if current_cell.get_value() != &val {
                     //Existing memory cannot be changed
@@ -161,9 +402,30 @@ impl Memory {
                 }
             }
         };
+        if self.lazy_validation {
+            return Ok(());
+        }
         self.validate_memory_cell(key)
     }
 
+    /// Records the addresses of the cells written from now on, until
+    /// [stop_recording_writes](Self::stop_recording_writes) is called.
+    pub(crate) fn start_recording_writes(&mut self) {
+        self.recorded_writes = Some(Vec::new());
+    }
+
+    /// Returns the addresses of the cells written since
+    /// [start_recording_writes](Self::start_recording_writes), in order of writing.
+    pub(crate) fn stop_recording_writes(&mut self) -> Vec<Relocatable> {
+        self.recorded_writes.take().unwrap_or_default()
+    }
+
+    /// Returns the range of offsets of the program segment covering the cells written since the
+    /// last call, and clears it.
+    pub(crate) fn take_program_writes(&mut self) -> Range<usize> {
+        core::mem::replace(&mut self.program_writes, 0..0)
+    }
+
     /// Retrieve a value from memory (either normal or temporary) and apply relocation rules
     pub(crate) fn get<'a, 'b: 'a, K: 'a>(&'b self, key: &'a K) -> Option<Cow<MaybeRelocatable>>
     where
@@ -197,26 +459,65 @@ impl Memory {
         }
     }
 
+    // Resolves the chains of relocation rules, where a temporary segment is relocated into
+    // another temporary segment which has a rule of its own, into rules relocating each segment
+    // to the end of its chain. Fails if a chain loops back to a segment it went through.
+    fn resolve_relocation_rules(&self) -> Result<HashMap<usize, Relocatable>, MemoryError> {
+        let mut resolved = HashMap::with_capacity(self.relocation_rules.len());
+        for (segment_index, dst_ptr) in self.relocation_rules.iter() {
+            let mut dst_ptr = *dst_ptr;
+            let mut hops = 0;
+            while dst_ptr.segment_index < 0 {
+                let Some(next) = self
+                    .relocation_rules
+                    .get(&(-(dst_ptr.segment_index + 1) as usize))
+                else {
+                    break;
+                };
+                // A chain without cycles goes through each rule at most once
+                hops += 1;
+                if hops > self.relocation_rules.len() {
+                    return Err(MemoryError::RelocationCycle(-(*segment_index as isize) - 1));
+                }
+                dst_ptr = (*next + dst_ptr.offset)?;
+            }
+            resolved.insert(*segment_index, dst_ptr);
+        }
+        Ok(resolved)
+    }
+
     /// Relocates the memory according to the relocation rules and clears `self.relocaction_rules`.
+    /// Rules relocating a temporary segment into another temporary segment are followed until
+    /// a real segment, failing with [MemoryError::RelocationCycle] if they loop.
     pub fn relocate_memory(&mut self) -> Result<(), MemoryError> {
         if self.relocation_rules.is_empty() || self.temp_data.is_empty() {
             return Ok(());
         }
-        // Relocate temporary addresses in memory
-        for segment in self.data.iter_mut().chain(self.temp_data.iter_mut()) {
+        let relocation_rules = self.resolve_relocation_rules()?;
+        // Relocate temporary addresses in memory, one segment per thread with the `parallel`
+        // feature
+        #[cfg(feature = "parallel")]
+        let segments = self
+            .data
+            .par_iter_mut()
+            .chain(self.temp_data.par_iter_mut());
+        #[cfg(not(feature = "parallel"))]
+        let segments = self.data.iter_mut().chain(self.temp_data.iter_mut());
+        let relocation_rules = &relocation_rules;
+        segments.for_each(|segment| {
             for cell in segment.iter_mut().flatten() {
                 let value = cell.get_value_mut();
                 match value {
                     MaybeRelocatable::RelocatableValue(addr) if addr.segment_index < 0 => {
-                        *value = Memory::relocate_address(*addr, &self.relocation_rules);
+                        *value = Memory::relocate_address(*addr, relocation_rules);
                     }
                     _ => {}
                 }
             }
-        }
+        });
         // Move relocated temporary memory into the real memory
         for index in (0..self.temp_data.len()).rev() {
-            if let Some(base_addr) = self.relocation_rules.get(&index) {
+            if let Some(base_addr) = relocation_rules.get(&index) {
                 let data_segment = self.temp_data.remove(index);
                 // Insert the to-be relocated segment into the real memory
                 let mut addr = *base_addr;
@@ -242,6 +543,9 @@ impl Memory {
     ///   - Source address's segment must be negative (temporary).
     ///   - Source address's offset must be zero.
     ///   - There shouldn't already be relocation at the source segment.
+    ///
+    /// The destination may be in another temporary segment, relocated by a rule of its own
+    /// added before or after this one.
     pub(crate) fn add_relocation_rule(
         &mut self,
         src_ptr: Relocatable,
@@ -303,13 +607,13 @@ impl Memory {
         self.insert(key, &val.into())
     }
 
-    pub fn add_validation_rule(&mut self, segment_index: usize, rule: ValidationRule) {
+    pub fn add_validation_rule(&mut self, segment_index: usize, rule: Box<dyn ValidationRule>) {
         if segment_index >= self.validation_rules.len() {
             // Fill gaps
             self.validation_rules
                 .resize_with(segment_index + 1, || None);
         }
-        self.validation_rules.insert(segment_index, Some(rule));
+        self.validation_rules[segment_index] = Some(rule);
     }
 
     fn validate_memory_cell(&mut self, addr: Relocatable) -> Result<(), MemoryError> {
@@ -321,7 +625,7 @@ impl Memory {
             if !self.validated_addresses.contains(&addr) {
                 {
                     self.validated_addresses
-                        .extend(rule.0(self, addr)?.as_slice());
+                        .extend(rule.validate(self, addr)?.as_slice());
                 }
             }
         }
@@ -330,17 +634,58 @@ impl Memory {
 
     ///Applies validation_rules to the current memory
     pub fn validate_existing_memory(&mut self) -> Result<(), MemoryError> {
-        for (index, rule) in self.validation_rules.iter().enumerate() {
-            if let Some(rule) = rule {
-                if index < self.data.len() {
-                    for offset in 0..self.data[index].len() {
-                        let addr = Relocatable::from((index as isize, offset));
-                        if !self.validated_addresses.contains(&addr) {
-                            self.validated_addresses
-                                .extend(rule.0(self, addr)?.as_slice());
-                        }
-                    }
+        for index in 0..self.validation_rules.len() {
+            self.validate_segment(index)?;
+        }
+        Ok(())
+    }
+
+    /// Validates the cells written with lazy validation since the last call, running the rule of
+    /// each segment once over the range of offsets written to it.
+    pub fn validate_pending_memory(&mut self) -> Result<(), MemoryError> {
+        let mut pending = core::mem::take(&mut self.pending_validation);
+        pending.sort_unstable();
+        let mut start = 0;
+        while start < pending.len() {
+            let segment_index = pending[start].segment_index;
+            let end = start
+                + pending[start..]
+                    .iter()
+                    .take_while(|address| address.segment_index == segment_index)
+                    .count();
+            let range = pending[start].offset..pending[end - 1].offset + 1;
+            if let Some(Some(rule)) = self.validation_rules.get(segment_index as usize) {
+                let validated = rule.validate_range(self, segment_index as usize, range)?;
+                self.validated_addresses.extend(&validated);
+            }
+            start = end;
+        }
+        Ok(())
+    }
+
+    /// Applies the validation rule of the segment `segment_index`, if it has one, to all of its
+    /// cells, a run of cells between gaps at a time.
+    /// The gaps are passed to [ValidationRule::validate] too, in order, so rules can reject them.
+    pub fn validate_segment(&mut self, segment_index: usize) -> Result<(), MemoryError> {
+        let segment = match self.data.get(segment_index) {
+            Some(segment) => segment,
+            None => return Ok(()),
+        };
+        if let Some(Some(rule)) = self.validation_rules.get(segment_index) {
+            let mut start = 0;
+            while start < segment.len() {
+                let end = segment[start..]
+                    .iter()
+                    .position(Option::is_none)
+                    .map_or(segment.len(), |gap| start + gap);
+                let validated = rule.validate_range(self, segment_index, start..end)?;
+                self.validated_addresses.extend(&validated);
+                if end < segment.len() {
+                    let gap = Relocatable::from((segment_index as isize, end));
+                    let validated = rule.validate(self, gap)?;
+                    self.validated_addresses.extend(&validated);
                 }
+                start = end + 1;
             }
         }
         Ok(())
@@ -481,6 +826,54 @@ impl Memory {
         Ok(values)
     }
 
+    /// Exports the values of the segment at `index` as 32-byte little-endian integers, one per
+    /// cell, so callers across an FFI boundary (e.g. from wasm) read a whole segment in a single
+    /// call. Cells without a value are exported as zero.
+    /// Fails if the segment doesn't exist, or if it holds a relocatable value, which has no
+    /// integer representation.
+    pub fn export_segment_u256_le(&self, index: usize) -> Result<Vec<[u8; 32]>, MemoryError> {
+        let segment = self
+            .data
+            .get(index)
+            .ok_or(MemoryError::UnknownSegment(index))?;
+        segment
+            .iter()
+            .enumerate()
+            .map(
+                |(offset, cell)| match cell.as_ref().map(MemoryCell::get_value) {
+                    None => Ok([0; 32]),
+                    Some(MaybeRelocatable::Int(value)) => Ok(value.to_le_bytes()),
+                    Some(MaybeRelocatable::RelocatableValue(_)) => Err(
+                        MemoryError::ExpectedInteger(Box::new((index as isize, offset).into())),
+                    ),
+                },
+            )
+            .collect()
+    }
+
+    /// Exports the values of the segment at `index` as a JSON array with one element per cell:
+    /// felts as `0x` prefixed hex strings, relocatable values as `"segment_index:offset"` strings
+    /// and cells without a value as `null`. Fails if the segment doesn't exist.
+    pub fn export_segment_hex_json(&self, index: usize) -> Result<String, MemoryError> {
+        let segment = self
+            .data
+            .get(index)
+            .ok_or(MemoryError::UnknownSegment(index))?;
+        let values: Vec<Option<String>> = segment
+            .iter()
+            .map(|cell| {
+                cell.as_ref().map(|cell| match cell.get_value() {
+                    MaybeRelocatable::Int(value) => format!("0x{}", value.to_str_radix(16)),
+                    MaybeRelocatable::RelocatableValue(value) => {
+                        format!("{}:{}", value.segment_index, value.offset)
+                    }
+                })
+            })
+            .collect();
+        // A list of strings always serializes
+        Ok(serde_json::to_string(&values).unwrap_or_default())
+    }
+
     pub fn mark_as_accessed(&mut self, addr: Relocatable) {
         let (i, j) = from_relocatable_to_indexes(addr);
         let data = if addr.segment_index < 0 {
@@ -512,23 +905,52 @@ impl Memory {
                 .count(),
         )
     }
+
+    /// Compares this memory against `other`, reporting the added, removed and changed cells
+    /// of every segment, including temporary ones.
+    pub fn diff(&self, other: &Memory) -> MemoryDiff {
+        let mut diff = self.diff_ignoring_temporary_segments(other);
+        diff.push_segments(&self.temp_data, &other.temp_data, |index| {
+            -(index as isize) - 1
+        });
+        diff
+    }
+
+    /// Same as [Memory::diff], but skipping temporary segments.
+    pub fn diff_ignoring_temporary_segments(&self, other: &Memory) -> MemoryDiff {
+        let mut diff = MemoryDiff::default();
+        diff.push_segments(&self.data, &other.data, |index| index as isize);
+        diff
+    }
 }
 
 impl fmt::Display for Memory {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        for (i, segment) in self.temp_data.iter().enumerate() {
+        self.display_in_format(FeltFormat::Decimal).fmt(f)
+    }
+}
+
+/// Displays a memory dump with its felts in a given format, see [Memory::display_in_format].
+pub struct MemoryDisplay<'a> {
+    memory: &'a Memory,
+    format: FeltFormat,
+}
+
+impl fmt::Display for MemoryDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, segment) in self.memory.temp_data.iter().enumerate() {
             for (j, cell) in segment.iter().enumerate() {
                 if let Some(cell) = cell {
                     let temp_segment = i + 1;
-                    let elem = cell.get_value();
+                    let elem = self.format.display_value(cell.get_value());
                     writeln!(f, "(-{temp_segment},{j}) : {elem}")?;
                 }
             }
         }
-        for (i, segment) in self.data.iter().enumerate() {
+        for (i, segment) in self.memory.data.iter().enumerate() {
             for (j, cell) in segment.iter().enumerate() {
                 if let Some(cell) = cell {
-                    let elem = cell.get_value();
+                    let elem = self.format.display_value(cell.get_value());
                     writeln!(f, "({i},{j}) : {elem}")?;
                 }
             }
@@ -544,18 +966,29 @@ pub(crate) trait RelocateValue<'a, Input: 'a, Output: 'a> {
 
 impl RelocateValue<'_, Relocatable, Relocatable> for Memory {
     fn relocate_value(&self, addr: Relocatable) -> Relocatable {
-        let segment_idx = addr.segment_index;
-        if segment_idx >= 0 {
-            return addr;
-        }
+        relocate_temporary_address(addr, &self.relocation_rules)
+    }
+}
 
+/// Applies `relocation_rules` to `addr`, as done by [Memory::get] for the values it returns
+pub(crate) fn relocate_temporary_address(
+    mut addr: Relocatable,
+    relocation_rules: &HashMap<usize, Relocatable>,
+) -> Relocatable {
+    // Follows the rules relocating into another temporary segment, going through each rule
+    // at most once so rules looping back don't hang
+    for _ in 0..relocation_rules.len() {
+        if addr.segment_index >= 0 {
+            break;
+        }
         // Adjust the segment index to begin at zero, as per the struct field's
         // comment.
-        match self.relocation_rules.get(&(-(segment_idx + 1) as usize)) {
-            Some(x) => x + addr.offset,
-            None => addr,
+        match relocation_rules.get(&(-(addr.segment_index + 1) as usize)) {
+            Some(x) => addr = x + addr.offset,
+            None => break,
         }
     }
+    addr
 }
 
 impl<'a> RelocateValue<'a, &'a Felt252, &'a Felt252> for Memory {
@@ -597,7 +1030,7 @@ mod memory_tests {
     };
     use assert_matches::assert_matches;
     use felt::felt_str;
-    use num_traits::One;
+    use num_traits::{One, Zero};
 
     use crate::vm::errors::memory_errors::MemoryError;
 
@@ -607,6 +1040,16 @@ mod memory_tests {
     #[cfg(target_arch = "wasm32")]
     use wasm_bindgen_test::*;
 
+    #[test]
+    #[cfg(all(target_pointer_width = "64", not(feature = "lambdaworks-felt")))]
+    // The sizes the layout of the memory was chosen for, see the docs of Memory
+    fn memory_cell_size() {
+        let size = crate::stdlib::mem::size_of::<Option<MemoryCell>>();
+        assert_eq!(size, 32, "{size}");
+        let size = crate::stdlib::mem::size_of::<MaybeRelocatable>();
+        assert_eq!(size, 24, "{size}");
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn insert_and_get_succesful() {
@@ -621,6 +1064,27 @@ mod memory_tests {
         );
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn insert_beyond_max_segment_offset() {
+        let mut memory = Memory::new();
+        memory.data.push(Vec::new());
+        memory.set_max_segment_offset(Some(10));
+        memory
+            .insert(relocatable!(0, 10), &mayberelocatable!(1))
+            .unwrap();
+        assert_matches!(
+            memory.insert(relocatable!(0, 11), &mayberelocatable!(1)),
+            Err(MemoryError::SegmentOffsetLimitExceeded(bx)) if *bx == (relocatable!(0, 11), 10)
+        );
+        assert_eq!(memory.data[0].len(), 11);
+
+        memory.set_max_segment_offset(None);
+        memory
+            .insert(relocatable!(0, 11), &mayberelocatable!(1))
+            .unwrap();
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn get_valuef_from_temp_segment() {
@@ -809,6 +1273,135 @@ mod memory_tests {
         );
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn lazy_validation_defers_range_check_until_validate_existing_memory() {
+        let mut builtin = RangeCheckBuiltinRunner::new(Some(8), 8, true);
+        let mut segments = MemorySegmentManager::new();
+        segments.add();
+        builtin.initialize_segments(&mut segments);
+        builtin.add_validation_rule(&mut segments.memory);
+        segments.memory.set_lazy_validation(true);
+        segments
+            .memory
+            .insert(Relocatable::from((1, 0)), &MaybeRelocatable::from(5))
+            .unwrap();
+        segments
+            .memory
+            .insert(
+                Relocatable::from((1, 1)),
+                &MaybeRelocatable::from(Felt252::new(-10)),
+            )
+            .unwrap();
+        assert!(!segments
+            .memory
+            .validated_addresses
+            .contains(&Relocatable::from((1, 0))));
+        assert_eq!(
+            segments.memory.validate_existing_memory(),
+            Err(MemoryError::RangeCheckNumOutOfBounds(Box::new((
+                Felt252::new(-10),
+                Felt252::one().shl(128_u32)
+            ))))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn validate_pending_memory_only_checks_new_cells() {
+        let mut builtin = RangeCheckBuiltinRunner::new(Some(8), 8, true);
+        let mut segments = MemorySegmentManager::new();
+        segments.add();
+        builtin.initialize_segments(&mut segments);
+        builtin.add_validation_rule(&mut segments.memory);
+        segments.memory.set_lazy_validation(true);
+        segments
+            .memory
+            .insert(
+                Relocatable::from((0, 0)),
+                &MaybeRelocatable::from(Felt252::new(-1)),
+            )
+            .unwrap();
+        segments
+            .memory
+            .insert(Relocatable::from((1, 0)), &MaybeRelocatable::from(5))
+            .unwrap();
+        // Cells of segments without a validation rule aren't pending
+        assert_eq!(
+            segments.memory.pending_validation,
+            [Relocatable::from((1, 0))]
+        );
+        assert_eq!(segments.memory.validate_pending_memory(), Ok(()));
+        assert!(segments.memory.pending_validation.is_empty());
+        assert!(segments
+            .memory
+            .validated_addresses
+            .contains(&Relocatable::from((1, 0))));
+
+        segments
+            .memory
+            .insert(
+                Relocatable::from((1, 2)),
+                &MaybeRelocatable::from(Felt252::new(-10)),
+            )
+            .unwrap();
+        assert_eq!(
+            segments.memory.pending_validation,
+            [Relocatable::from((1, 2))]
+        );
+        assert_eq!(
+            segments.memory.validate_pending_memory(),
+            Err(MemoryError::RangeCheckNumOutOfBounds(Box::new((
+                Felt252::new(-10),
+                Felt252::one().shl(128_u32)
+            ))))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn validate_segment_with_custom_rule() {
+        struct NonZeroRule;
+
+        impl ValidationRule for NonZeroRule {
+            fn validate(
+                &self,
+                memory: &Memory,
+                address: Relocatable,
+            ) -> Result<Vec<Relocatable>, MemoryError> {
+                // Gaps are left to be validated once written
+                if memory.get(&address).is_none() {
+                    return Ok(Vec::new());
+                }
+                if memory.get_integer(address)?.is_zero() {
+                    Err(MemoryError::ExpectedInteger(Box::new(address)))
+                } else {
+                    Ok(vec![address])
+                }
+            }
+        }
+
+        let mut memory = memory![((0, 0), 2), ((0, 2), 4)];
+        memory.add_validation_rule(0, Box::new(NonZeroRule));
+        assert_eq!(memory.validate_segment(0), Ok(()));
+        assert!(memory
+            .validated_addresses
+            .contains(&Relocatable::from((0, 0))));
+        assert!(!memory
+            .validated_addresses
+            .contains(&Relocatable::from((0, 1))));
+        assert!(memory
+            .validated_addresses
+            .contains(&Relocatable::from((0, 2))));
+
+        assert_eq!(
+            memory.insert(Relocatable::from((0, 1)), &MaybeRelocatable::from(0)),
+            Err(MemoryError::ExpectedInteger(Box::new(Relocatable::from((
+                0, 1
+            )))))
+        );
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn validate_existing_memory_for_invalid_signature() {
@@ -1142,6 +1735,44 @@ mod memory_tests {
         );
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn export_segment_u256_le() {
+        let memory = memory![((1, 0), 2), ((1, 2), 258)];
+
+        let mut expected = vec![[0; 32]; 3];
+        expected[0][0] = 2;
+        expected[2][0] = 2;
+        expected[2][1] = 1;
+        assert_eq!(memory.export_segment_u256_le(1), Ok(expected));
+        assert_eq!(
+            memory.export_segment_u256_le(2),
+            Err(MemoryError::UnknownSegment(2))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn export_segment_u256_le_relocatable() {
+        let memory = memory![((1, 0), 2), ((1, 1), (2, 3))];
+
+        assert_eq!(
+            memory.export_segment_u256_le(1),
+            Err(MemoryError::ExpectedInteger(Box::new((1, 1).into())))
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn export_segment_hex_json() {
+        let memory = memory![((1, 0), 255), ((1, 1), (2, 3)), ((1, 3), 16)];
+
+        assert_eq!(
+            memory.export_segment_hex_json(1),
+            Ok(String::from(r#"["0xff","2:3",null,"0x10"]"#))
+        );
+    }
+
     /// Test that relocate_memory() works when there are no relocation rules.
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
@@ -1223,6 +1854,65 @@ mod memory_tests {
         assert!(memory.temp_data.is_empty());
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn relocate_memory_chained_temporary_segments() {
+        let mut memory = memory![
+            ((0, 0), (-2, 1)),
+            ((0, 1), (-1, 0)),
+            ((-1, 0), 7),
+            ((-1, 1), (-2, 0)),
+            ((-2, 0), 8),
+            ((-2, 1), 9)
+        ];
+        // -2 is relocated after the first cell of -1, which is relocated to 1:0
+        memory
+            .add_relocation_rule((-2, 0).into(), (-1, 2).into())
+            .unwrap();
+        memory
+            .add_relocation_rule((-1, 0).into(), (1, 0).into())
+            .unwrap();
+        memory.data.push(vec![]);
+        assert_eq!(
+            memory.relocate_value(relocatable!(-2, 1)),
+            relocatable!(1, 3)
+        );
+
+        assert_eq!(memory.relocate_memory(), Ok(()));
+        check_memory!(
+            memory,
+            ((0, 0), (1, 3)),
+            ((0, 1), (1, 0)),
+            ((1, 0), 7),
+            ((1, 1), (1, 2)),
+            ((1, 2), 8),
+            ((1, 3), 9)
+        );
+        assert!(memory.temp_data.is_empty());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn relocate_memory_relocation_cycle() {
+        let mut memory = memory![((0, 0), (-1, 0)), ((-1, 0), 7), ((-2, 0), 8)];
+        memory
+            .add_relocation_rule((-1, 0).into(), (-2, 0).into())
+            .unwrap();
+        memory
+            .add_relocation_rule((-2, 0).into(), (-1, 1).into())
+            .unwrap();
+        // Reads go through each rule once instead of looping
+        assert_eq!(
+            memory.relocate_value(relocatable!(-1, 0)),
+            relocatable!(-1, 1)
+        );
+
+        assert_matches!(
+            memory.relocate_memory(),
+            Err(MemoryError::RelocationCycle(-1 | -2))
+        );
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn relocate_memory_new_segment_unallocated() {
@@ -1612,4 +2302,70 @@ mod memory_tests {
         check_memcmp((-2, 0), (-3, 5), 8, Greater, 0);
         check_memcmp((-3, 5), (-2, 0), 8, Less, 0);
     }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn diff_equal_memories() {
+        let memory = memory![((0, 0), 1), ((1, 0), (0, 0)), ((-1, 0), 2)];
+        let other = memory![((0, 0), 1), ((1, 0), (0, 0)), ((-1, 0), 2)];
+        assert!(memory.diff(&other).is_empty());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn diff_memories() {
+        let memory = memory![((0, 0), 1), ((0, 1), 2), ((1, 1), 3), ((-1, 0), 4)];
+        let other = memory![((0, 0), 1), ((0, 1), 5), ((1, 0), 6), ((2, 0), 7)];
+
+        let diff = memory.diff(&other);
+        assert_eq!(
+            diff,
+            MemoryDiff {
+                segments: vec![
+                    SegmentDiff {
+                        segment_index: 0,
+                        added: vec![],
+                        removed: vec![],
+                        changed: vec![ChangedCell {
+                            offset: 1,
+                            old: mayberelocatable!(2),
+                            new: mayberelocatable!(5),
+                        }],
+                    },
+                    SegmentDiff {
+                        segment_index: 1,
+                        added: vec![(0, mayberelocatable!(6))],
+                        removed: vec![(1, mayberelocatable!(3))],
+                        changed: vec![],
+                    },
+                    SegmentDiff {
+                        segment_index: 2,
+                        added: vec![(0, mayberelocatable!(7))],
+                        removed: vec![],
+                        changed: vec![],
+                    },
+                    SegmentDiff {
+                        segment_index: -1,
+                        added: vec![],
+                        removed: vec![(0, mayberelocatable!(4))],
+                        changed: vec![],
+                    },
+                ]
+            }
+        );
+        assert_eq!(
+            diff.to_string(),
+            "~ (0,1) : 2 -> 5\n- (1,1) : 3\n+ (1,0) : 6\n+ (2,0) : 7\n- (-1,0) : 4\n"
+        );
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn diff_ignoring_temporary_segments() {
+        let memory = memory![((0, 0), 1), ((-1, 0), 4)];
+        let other = memory![((0, 0), 1), ((-1, 0), 5)];
+
+        assert!(memory.diff_ignoring_temporary_segments(&other).is_empty());
+        assert_eq!(memory.diff(&other).segments.len(), 1);
+    }
 }
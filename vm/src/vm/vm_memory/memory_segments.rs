@@ -1,5 +1,8 @@
 use crate::stdlib::prelude::*;
-use crate::stdlib::{any::Any, collections::HashMap};
+use crate::stdlib::{
+    any::Any,
+    collections::{BTreeMap, BTreeSet, HashMap},
+};
 use crate::vm::runners::cairo_runner::CairoArg;
 
 use crate::{
@@ -17,6 +20,12 @@ pub struct MemorySegmentManager {
     // A map from segment index to a list of pairs (offset, page_id) that constitute the
     // public memory. Note that the offset is absolute (not based on the page_id).
     pub public_memory_offsets: HashMap<usize, Vec<(usize, usize)>>,
+    // Capacity reserved for each segment added with `add`, by segment index
+    pub(crate) segment_capacities: Vec<usize>,
+    // Names the values of each segment are displayed with, by segment index
+    pub(crate) segment_names: BTreeMap<isize, String>,
+    // Indices skipped by `add_reserved`, which `add` uses before adding new ones
+    pub(crate) free_segments: BTreeSet<usize>,
 }
 
 impl MemorySegmentManager {
@@ -31,14 +40,72 @@ impl MemorySegmentManager {
     }
 
     ///Adds a new segment and returns its starting location as a Relocatable value. Its segment index will always be positive.
+    ///If a capacity was set for its index with [MemorySegmentManager::set_segment_capacities], room for that many cells is reserved.
+    ///Indices skipped by [MemorySegmentManager::add_reserved] are used before new ones.
     pub fn add(&mut self) -> Relocatable {
-        self.memory.data.push(Vec::new());
+        let index = self
+            .free_segments
+            .first()
+            .copied()
+            .unwrap_or(self.memory.data.len());
+        let capacity = self
+            .segment_capacities
+            .get(index)
+            .copied()
+            .unwrap_or_default();
+        self.add_with_capacity(capacity)
+    }
+
+    /// Adds a new segment with room for `capacity` cells, so writing them doesn't reallocate it,
+    /// and returns its starting location.
+    pub fn add_with_capacity(&mut self, capacity: usize) -> Relocatable {
+        if let Some(index) = self.free_segments.pop_first() {
+            self.memory.data[index].reserve(capacity);
+            return Relocatable {
+                segment_index: index as isize,
+                offset: 0,
+            };
+        }
+        self.memory.data.push(Vec::with_capacity(capacity));
         Relocatable {
             segment_index: (self.memory.data.len() - 1) as isize,
             offset: 0,
         }
     }
 
+    /// Adds the segment at `index`, so known segments always get the same index (e.g. the output
+    /// at segment 2) whatever was added before them, and returns its starting location.
+    /// The indices skipped to reach `index` are used by the next calls to
+    /// [MemorySegmentManager::add], and are left as empty segments otherwise.
+    /// Fails if the segment at `index` was already added.
+    pub fn add_reserved(&mut self, index: usize) -> Result<Relocatable, MemoryError> {
+        if index < self.memory.data.len() && !self.free_segments.remove(&index) {
+            return Err(MemoryError::SegmentAlreadyAdded(index));
+        }
+        let capacity = self
+            .segment_capacities
+            .get(index)
+            .copied()
+            .unwrap_or_default();
+        while self.memory.data.len() <= index {
+            self.free_segments.insert(self.memory.data.len());
+            self.memory.data.push(Vec::new());
+        }
+        self.free_segments.remove(&index);
+        self.memory.data[index].reserve(capacity);
+        Ok(Relocatable {
+            segment_index: index as isize,
+            offset: 0,
+        })
+    }
+
+    /// Sets the capacity reserved by [MemorySegmentManager::add] for each segment, by segment
+    /// index, such as the segment sizes of a previous run of the same workload.
+    /// Only affects the segments added afterwards.
+    pub fn set_segment_capacities(&mut self, capacities: Vec<usize>) {
+        self.segment_capacities = capacities;
+    }
+
     /// Adds a new temporary segment and returns its starting location as a Relocatable value. Its segment index will always be negative.
     pub fn add_temporary_segment(&mut self) -> Relocatable {
         self.memory.temp_data.push(Vec::new());
@@ -69,9 +136,29 @@ impl MemorySegmentManager {
             segment_used_sizes: None,
             public_memory_offsets: HashMap::new(),
             memory: Memory::new(),
+            segment_capacities: Vec::new(),
+            segment_names: BTreeMap::new(),
+            free_segments: BTreeSet::new(),
         }
     }
 
+    /// Names the segment at `segment_index` (which is negative for temporary segments), so its
+    /// values are displayed as `name:offset` by [Relocatable::display_with].
+    /// The runner names the program, execution and builtin segments, e.g. `pedersen`.
+    pub fn set_segment_name(&mut self, segment_index: isize, name: impl Into<String>) {
+        self.segment_names.insert(segment_index, name.into());
+    }
+
+    /// Returns the name of the segment at `segment_index`, if it has one.
+    pub fn get_segment_name(&self, segment_index: isize) -> Option<&str> {
+        self.segment_names.get(&segment_index).map(String::as_str)
+    }
+
+    /// Returns the names of the named segments, by segment index.
+    pub fn segment_names(&self) -> &BTreeMap<isize, String> {
+        &self.segment_names
+    }
+
     /// Calculates the size of each memory segment.
     pub fn compute_effective_sizes(&mut self) -> &Vec<usize> {
         self.segment_used_sizes
@@ -259,6 +346,59 @@ mod tests {
         assert_eq!(segments.num_segments(), 1);
     }
 
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn add_segment_with_capacity() {
+        let mut segments = MemorySegmentManager::new();
+        let base = segments.add_with_capacity(100);
+        assert_eq!(base, relocatable!(0, 0));
+        assert!(segments.memory.data[0].is_empty());
+        assert!(segments.memory.data[0].capacity() >= 100);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn add_segments_with_capacities_set() {
+        let mut segments = MemorySegmentManager::new();
+        segments.set_segment_capacities(vec![10, 0, 30]);
+        for _ in 0..4 {
+            segments.add();
+        }
+        assert!(segments.memory.data[0].capacity() >= 10);
+        assert_eq!(segments.memory.data[1].capacity(), 0);
+        assert!(segments.memory.data[2].capacity() >= 30);
+        assert_eq!(segments.memory.data[3].capacity(), 0);
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn add_reserved_segment() {
+        let mut segments = MemorySegmentManager::new();
+        segments.add();
+        assert_eq!(segments.add_reserved(3), Ok(relocatable!(3, 0)));
+        assert_eq!(segments.num_segments(), 4);
+        // The skipped indices are used first
+        assert_eq!(segments.add(), relocatable!(1, 0));
+        assert_eq!(segments.add_reserved(2), Ok(relocatable!(2, 0)));
+        assert_eq!(segments.add(), relocatable!(4, 0));
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn add_reserved_segment_already_added() {
+        let mut segments = MemorySegmentManager::new();
+        segments.add();
+        segments.add_reserved(2).unwrap();
+        assert_eq!(
+            segments.add_reserved(0),
+            Err(MemoryError::SegmentAlreadyAdded(0))
+        );
+        assert_eq!(
+            segments.add_reserved(2),
+            Err(MemoryError::SegmentAlreadyAdded(2))
+        );
+    }
+
     #[test]
     #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
     fn add_segment_no_size_test_two_segments() {
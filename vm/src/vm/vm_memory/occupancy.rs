@@ -0,0 +1,173 @@
+//! Occupancy maps of the memory segments, for a UI to render the memory layout as heatmaps.
+//!
+//! Each segment is downsampled into a matrix of pixels, each pixel covering the same number of
+//! consecutive cells and holding the share of them which were written, from 0 to 255, so the rows
+//! can be written as the lines of a grayscale image as they are. The maps are built on demand from
+//! the cells of the memory, so collecting them costs nothing while the program runs.
+
+use crate::stdlib::prelude::*;
+
+use num_integer::Integer;
+use serde::Serialize;
+
+use crate::vm::vm_memory::{memory::MemoryCell, memory_segments::MemorySegmentManager};
+
+/// The occupancy map of a segment, see [MemorySegmentManager::occupancy_maps].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct SegmentOccupancy {
+    /// Index of the segment, negative for temporary segments
+    pub segment_index: isize,
+    pub name: Option<String>,
+    /// Number of cells of the segment, up to the last one written
+    pub size: usize,
+    /// Number of cells written
+    pub written: usize,
+    /// Number of consecutive cells covered by each pixel
+    pub cells_per_pixel: usize,
+    /// Rows of pixels, holding the share of the cells they cover which were written, from 0 for
+    /// none to 255 for all of them. Every row has the requested width, the pixels past the end of
+    /// the segment being 0.
+    pub rows: Vec<Vec<u8>>,
+}
+
+impl SegmentOccupancy {
+    fn new(
+        segment_index: isize,
+        name: Option<String>,
+        cells: &[Option<MemoryCell>],
+        width: usize,
+        max_rows: usize,
+    ) -> Self {
+        let width = width.max(1);
+        let max_pixels = width.saturating_mul(max_rows.max(1));
+        let cells_per_pixel = Integer::div_ceil(&cells.len(), &max_pixels).max(1);
+        let mut pixels: Vec<u8> = cells
+            .chunks(cells_per_pixel)
+            .map(|chunk| {
+                let written = chunk.iter().filter(|cell| cell.is_some()).count();
+                // Rounded up, so a pixel covering any written cell isn't blank
+                Integer::div_ceil(&(written * 255), &chunk.len()) as u8
+            })
+            .collect();
+        // Pads the last row
+        pixels.resize(Integer::next_multiple_of(&pixels.len(), &width), 0);
+        SegmentOccupancy {
+            segment_index,
+            name,
+            size: cells.len(),
+            written: cells.iter().filter(|cell| cell.is_some()).count(),
+            cells_per_pixel,
+            rows: pixels.chunks(width).map(<[u8]>::to_vec).collect(),
+        }
+    }
+}
+
+impl MemorySegmentManager {
+    /// Returns the occupancy map of each segment, the real segments first, then the temporary
+    /// ones. Each segment is downsampled to at most `max_rows` rows of `width` pixels.
+    pub fn occupancy_maps(&self, width: usize, max_rows: usize) -> Vec<SegmentOccupancy> {
+        let real_segments = self
+            .memory
+            .data
+            .iter()
+            .enumerate()
+            .map(|(index, cells)| (index as isize, cells));
+        let temp_segments = self
+            .memory
+            .temp_data
+            .iter()
+            .enumerate()
+            .map(|(index, cells)| (-(index as isize) - 1, cells));
+        real_segments
+            .chain(temp_segments)
+            .map(|(segment_index, cells)| {
+                SegmentOccupancy::new(
+                    segment_index,
+                    self.get_segment_name(segment_index).map(String::from),
+                    cells,
+                    width,
+                    max_rows,
+                )
+            })
+            .collect()
+    }
+
+    /// Returns the [occupancy maps](MemorySegmentManager::occupancy_maps) of the segments as a
+    /// JSON array.
+    pub fn occupancy_maps_json(&self, width: usize, max_rows: usize) -> String {
+        serde_json::to_string(&self.occupancy_maps(width, max_rows)).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{stdlib::collections::HashMap, utils::test_utils::*};
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::*;
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn occupancy_maps_downsample_segments() {
+        let mut segments = segments![
+            ((0, 0), 1),
+            ((0, 1), 2),
+            ((0, 2), 3),
+            ((1, 0), 1),
+            ((1, 3), 1),
+            ((1, 4), 1),
+            ((1, 9), 1)
+        ];
+        segments.add_temporary_segment();
+        segments.set_segment_name(1, "execution");
+
+        let maps = segments.occupancy_maps(2, 2);
+        assert_eq!(maps.len(), 3);
+        // 3 cells fit in the 4 pixels, one cell each
+        assert_eq!(
+            maps[0],
+            SegmentOccupancy {
+                segment_index: 0,
+                name: None,
+                size: 3,
+                written: 3,
+                cells_per_pixel: 1,
+                rows: vec![vec![255, 255], vec![255, 0]],
+            }
+        );
+        // 10 cells take 3 cells per pixel: [1, 0, 0], [1, 1, 0], [0, 0, 0], and the last one [1]
+        assert_eq!(
+            maps[1],
+            SegmentOccupancy {
+                segment_index: 1,
+                name: Some(String::from("execution")),
+                size: 10,
+                written: 4,
+                cells_per_pixel: 3,
+                rows: vec![vec![85, 170], vec![0, 255]],
+            }
+        );
+        assert_eq!(maps[2].segment_index, -1);
+        assert_eq!(maps[2].rows, Vec::<Vec<u8>>::new());
+    }
+
+    #[test]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn occupancy_maps_json() {
+        let segments = segments![((0, 1), 1)];
+        let json: serde_json::Value =
+            serde_json::from_str(&segments.occupancy_maps_json(4, 1)).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!([{
+                "segment_index": 0,
+                "name": null,
+                "size": 2,
+                "written": 1,
+                "cells_per_pixel": 1,
+                "rows": [[0, 255, 0, 0]],
+            }])
+        );
+    }
+}
@@ -0,0 +1,102 @@
+//! Checks that the step loop itself doesn't allocate, by running `jmp rel 0`, which handles no
+//! nonzero felt. Other instructions may allocate: `Felt252` is backed by a `BigUint`, so cloning
+//! or computing nonzero felts does, and so do hints and builtins.
+//!
+//! This needs its own global allocator, which the library tests can't define as they forbid
+//! `unsafe` code.
+
+#![cfg(feature = "std")]
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    collections::HashMap,
+};
+
+use cairo_vm::{
+    hint_processor::builtin_hint_processor::builtin_hint_processor_definition::BuiltinHintProcessor,
+    serde::deserialize_program::ReferenceManager,
+    types::{
+        constants::Constants, exec_scope::ExecutionScopes, program::Program,
+        relocatable::MaybeRelocatable,
+    },
+    vm::{runners::cairo_runner::CairoRunner, vm_core::VirtualMachine},
+};
+use felt::Felt252;
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|allocations| allocations.set(allocations.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.with(|allocations| allocations.set(allocations.get() + 1));
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+#[test]
+fn jmp_rel_0_steps_dont_allocate() {
+    // main: jmp rel 0
+    let program = Program::new(
+        Vec::new(),
+        vec![
+            MaybeRelocatable::from(Felt252::new(0x10780017fff7fff_u64)),
+            MaybeRelocatable::from(Felt252::new(0)),
+        ],
+        Some(0),
+        HashMap::new(),
+        ReferenceManager {
+            references: Vec::new(),
+        },
+        HashMap::new(),
+        Vec::new(),
+        None,
+    )
+    .unwrap();
+    let mut cairo_runner = CairoRunner::new(&program, "plain", false).unwrap();
+    let mut vm = VirtualMachine::new(true);
+    cairo_runner.initialize(&mut vm).unwrap();
+
+    let mut hint_processor = BuiltinHintProcessor::new_empty();
+    let mut exec_scopes = ExecutionScopes::new();
+    let hint_data_dictionary = HashMap::new();
+    let constants = Constants::new();
+    let mut step = |vm: &mut VirtualMachine| {
+        vm.step(
+            &mut hint_processor,
+            &mut exec_scopes,
+            &hint_data_dictionary,
+            &constants,
+        )
+        .unwrap()
+    };
+
+    // The first step decodes the instruction into the cache
+    step(&mut vm);
+    vm.reserve_steps(1000);
+
+    let before = allocations();
+    for _ in 0..1000 {
+        step(&mut vm);
+    }
+    assert_eq!(allocations() - before, 0);
+}